@@ -0,0 +1,75 @@
+//! Compose Plugin - Demonstrates plugin-to-plugin invocation via effects
+//!
+//! This plugin builds its output from two other plugins instead of doing
+//! the work itself. It declares both in its `invoke` capability; the host
+//! runs them (with their own permission checks) and resumes this plugin
+//! with their output.
+//!
+//! # Flow
+//!
+//! ```text
+//! Plugin: execute(["Rust"])
+//!       → Effect::InvokePlugin { id: 1, command: "greet", args: ["Rust"] }
+//!
+//! Host:  Runs greet(["Rust"])
+//!       → resume(1, EffectResult::PluginOutput("Hello, Rust!"))
+//!
+//! Plugin: → Effect::InvokePlugin { id: 2, command: "upper", args: ["Hello, Rust!"] }
+//!
+//! Host:  Runs upper(["Hello, Rust!"])
+//!       → resume(2, EffectResult::PluginOutput("HELLO, RUST!"))
+//!
+//! Plugin: → Success("Hello, Rust! | HELLO, RUST!")
+//! ```
+
+use sen_plugin_sdk::prelude::*;
+use std::sync::Mutex;
+
+/// Effect ID for the greet call
+const GREET: u32 = 1;
+
+/// Effect ID for the upper call
+const UPPER: u32 = 2;
+
+/// Greeting returned by the first call, kept until the second one finishes
+static GREETING: Mutex<String> = Mutex::new(String::new());
+
+struct ComposePlugin;
+
+impl Plugin for ComposePlugin {
+    fn manifest() -> PluginManifest {
+        PluginManifest::with_capabilities(
+            CommandSpec::new("compose", "Greet someone, then shout it (plugin invocation demo)")
+                .version("1.0.0")
+                .arg(ArgSpec::positional("name").help("Name to greet").default("World")),
+            Capabilities::default().with_invoke(vec!["greet".into(), "upper".into()]),
+        )
+    }
+
+    fn execute(args: Vec<String>) -> ExecuteResult {
+        let name = args.first().cloned().unwrap_or_else(|| "World".to_string());
+        ExecuteResult::invoke_plugin(GREET, "greet", vec![name])
+    }
+
+    fn resume(effect_id: u32, result: EffectResult) -> ExecuteResult {
+        let output = match result {
+            EffectResult::PluginOutput(output) => output,
+            EffectResult::Error(e) => {
+                return ExecuteResult::user_error(format!("Invocation failed: {}", e))
+            }
+            _ => return ExecuteResult::system_error("Unexpected effect result"),
+        };
+
+        let mut greeting = GREETING.lock().unwrap();
+        match effect_id {
+            GREET => {
+                *greeting = output.clone();
+                ExecuteResult::invoke_plugin(UPPER, "upper", vec![output])
+            }
+            UPPER => ExecuteResult::success(format!("{} | {}", greeting, output)),
+            _ => ExecuteResult::system_error(format!("Unknown effect id: {}", effect_id)),
+        }
+    }
+}
+
+export_plugin!(ComposePlugin);
@@ -340,7 +340,7 @@ fn buildManifest(plugin: Plugin) []const u8 {
     msgpack.writeMapHeader(&Static.buf, map_size);
 
     msgpack.writeStr(&Static.buf, "api_version");
-    msgpack.writeUint(&Static.buf, 2); // API v2 for capabilities support
+    msgpack.writeUint(&Static.buf, 3); // API v3 (capabilities, streaming effects)
 
     msgpack.writeStr(&Static.buf, "command");
     msgpack.writeMapHeader(&Static.buf, 6);
@@ -0,0 +1,40 @@
+//! Greet Plugin - Using #[derive(Plugin)]
+//!
+//! The same command as `greet-plugin`, with the manifest and argument
+//! parsing generated from the struct.
+
+use sen_plugin_sdk::prelude::*;
+
+/// Greets a person with a custom message
+#[derive(Plugin)]
+#[plugin(command = "greet", version = "1.0.0")]
+struct Greet {
+    /// Name to greet
+    #[arg(positional, default = "World")]
+    name: String,
+
+    /// Custom greeting
+    #[arg(short = 'g', default = "Hello")]
+    greeting: String,
+
+    /// Number of times to greet
+    #[arg(short = 'n', default = "1")]
+    count: u32,
+
+    /// Greet in upper case
+    shout: bool,
+}
+
+impl Greet {
+    fn run(self) -> ExecuteResult {
+        if self.count == 0 || self.count > 100 {
+            return ExecuteResult::user_error("Count must be between 1 and 100");
+        }
+
+        let line = format!("{}, {}!", self.greeting, self.name);
+        let line = if self.shout { line.to_uppercase() } else { line };
+        ExecuteResult::success(vec![line; self.count as usize].join("\n"))
+    }
+}
+
+// #[derive(Plugin)] also generates the export_plugin! exports
@@ -1,20 +1,22 @@
-//! HTTP Fetcher Plugin - Demonstrates Effect system for async I/O
+//! HTTP Fetcher Plugin - Demonstrates the WASI host-proxy HTTP import
 //!
-//! This plugin fetches data from URLs using the Effect system.
-//! Instead of making HTTP requests directly (impossible in WASM sandbox),
-//! it requests the host to perform the fetch and continues with the result.
+//! This plugin fetches data from URLs through the host. Plugins cannot open
+//! sockets (WASM sandbox), so the SDK's `http` module calls the host's
+//! `host_http_request` import, which checks the URL against the plugin's
+//! declared `net` capabilities before performing the request.
+//!
+//! Load with `WasiPluginLoader` (requires the host's `wasi` feature).
 //!
 //! # Flow
 //!
 //! ```text
 //! Plugin: execute(["https://api.example.com/data"])
-//!       → Effect::HttpGet { id: 1, url: "..." }
+//!       → http::request(HttpRequest { method: "GET", url: "..." })
 //!
-//! Host:  Performs HTTP GET asynchronously
-//!       → EffectResult::Http { status: 200, body: "..." }
+//! Host:  Validates URL against `net` patterns, performs HTTP GET
+//!       → Ok(HttpResponse { status: 200, body: "..." })
 //!
-//! Plugin: resume(1, result)
-//!       → Success("Fetched: ...")
+//! Plugin: → Success("=== HTTP 200 === ...")
 //! ```
 
 use sen_plugin_sdk::prelude::*;
@@ -24,7 +26,7 @@ struct HttpPlugin;
 impl Plugin for HttpPlugin {
     fn manifest() -> PluginManifest {
         PluginManifest::with_capabilities(
-            CommandSpec::new("http-fetch", "Fetch data from a URL (Effect demo)")
+            CommandSpec::new("http-fetch", "Fetch data from a URL (host HTTP proxy demo)")
                 .version("1.0.0")
                 .arg(ArgSpec::positional("url").help("URL to fetch").required())
                 .arg(
@@ -50,49 +52,35 @@ impl Plugin for HttpPlugin {
 
         let method = args.get(1).map(|s| s.as_str()).unwrap_or("GET");
 
-        // Validate URL (basic check)
-        if !url.starts_with("http://") && !url.starts_with("https://") {
-            return ExecuteResult::user_error("URL must start with http:// or https://");
-        }
-
-        // Request the host to perform HTTP fetch
-        // The host will call plugin_resume with the result
-        match method.to_uppercase().as_str() {
-            "GET" => ExecuteResult::http_get(1, url),
-            "POST" => {
-                // For POST, we'd need a body - using empty for demo
-                ExecuteResult::http_post(1, url, "")
-            }
-            _ => ExecuteResult::user_error(format!("Unsupported method: {}", method)),
-        }
-    }
-
-    fn resume(_effect_id: u32, result: EffectResult) -> ExecuteResult {
-        match result {
-            EffectResult::Http(response) => {
-                if response.is_success() {
-                    // Truncate long responses for display
-                    let body = if response.body.len() > 500 {
-                        format!(
-                            "{}... ({} bytes total)",
-                            &response.body[..500],
-                            response.body.len()
-                        )
-                    } else {
-                        response.body
-                    };
+        let request = match method.to_uppercase().as_str() {
+            "GET" => HttpRequest::get(url),
+            // For POST, we'd need a body - using empty for demo
+            "POST" => HttpRequest::post(url, ""),
+            _ => return ExecuteResult::user_error(format!("Unsupported method: {}", method)),
+        };
 
-                    ExecuteResult::success(format!("=== HTTP {} ===\n{}", response.status, body))
+        match http::request(&request) {
+            Ok(response) if response.is_success() => {
+                // Truncate long responses for display
+                let body = if response.body.len() > 500 {
+                    format!(
+                        "{}... ({} bytes total)",
+                        &response.body[..500],
+                        response.body.len()
+                    )
                 } else {
-                    ExecuteResult::user_error(format!(
-                        "HTTP error {}: {}",
-                        response.status,
-                        response.body.chars().take(200).collect::<String>()
-                    ))
-                }
+                    response.body
+                };
+
+                ExecuteResult::success(format!("=== HTTP {} ===\n{}", response.status, body))
             }
-            EffectResult::Error(e) => ExecuteResult::user_error(format!("Request failed: {}", e)),
-            _ => ExecuteResult::system_error("Unexpected effect result"),
+            Ok(response) => ExecuteResult::user_error(format!(
+                "HTTP error {}: {}",
+                response.status,
+                response.body.chars().take(200).collect::<String>()
+            )),
+            Err(e) if e.is_denied() => ExecuteResult::user_error(format!("Not allowed: {}", e)),
+            Err(e) => ExecuteResult::user_error(format!("Request failed: {}", e)),
         }
     }
 }
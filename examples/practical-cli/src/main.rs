@@ -44,7 +44,10 @@ pub struct GlobalOpts {
 impl FromGlobalArgs for GlobalOpts {
     fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), sen::CliError> {
         let mut verbose = false;
-        let mut config_path = "~/.myctl/config.yaml".to_string();
+        let mut config_path = sen::paths::config_dir("myctl")
+            .join("config.yaml")
+            .display()
+            .to_string();
         let mut remaining_args = Vec::new();
 
         let mut skip_next = false;
@@ -529,7 +532,6 @@ mod handlers {
     pub mod config {
         use super::*;
         use std::fs;
-        use std::path::PathBuf;
 
         pub async fn show(state: State<AppState>) -> CliResult<String> {
             let app = state.read().await;
@@ -563,7 +565,7 @@ mod handlers {
 
         pub async fn init(state: State<AppState>) -> CliResult<String> {
             let app = state.read().await;
-            let config_path = PathBuf::from(&app.global.config_path);
+            let config_path = sen::paths::tilde_expand(&app.global.config_path);
 
             // Create parent directory if it doesn't exist
             if let Some(parent) = config_path.parent() {
@@ -599,7 +601,7 @@ mod handlers {
 
         pub async fn validate(state: State<AppState>) -> CliResult<String> {
             let app = state.read().await;
-            let config_path = PathBuf::from(&app.global.config_path);
+            let config_path = sen::paths::tilde_expand(&app.global.config_path);
 
             if !config_path.exists() {
                 return Err(sen::CliError::user(format!(
@@ -623,28 +625,28 @@ mod handlers {
 
         pub async fn edit(state: State<AppState>) -> CliResult<String> {
             let app = state.read().await;
-            let config_path = &app.global.config_path;
+            let config_path = sen::paths::tilde_expand(&app.global.config_path);
 
-            if !PathBuf::from(config_path).exists() {
+            if !config_path.exists() {
                 return Err(sen::CliError::user(format!(
                     "Configuration file not found: {}\nRun 'myctl config init' to create one",
-                    config_path
+                    config_path.display()
                 )));
             }
 
             let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
 
             if app.global.verbose {
-                println!("[DEBUG] Opening {} with {}", config_path, editor);
+                println!("[DEBUG] Opening {} with {}", config_path.display(), editor);
             }
 
             let status = std::process::Command::new(&editor)
-                .arg(config_path)
+                .arg(&config_path)
                 .status()
                 .map_err(|e| sen::CliError::system(format!("Failed to launch editor: {}", e)))?;
 
             if status.success() {
-                Ok(format!("✓ Edited configuration: {}", config_path))
+                Ok(format!("✓ Edited configuration: {}", config_path.display()))
             } else {
                 Err(sen::CliError::user("Editor exited with error"))
             }
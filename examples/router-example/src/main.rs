@@ -140,36 +140,14 @@ async fn main() {
     // 0. Initialize tracing (controlled by RUST_LOG environment variable)
     init_subscriber();
 
-    // 1. Load application state
-    let app_state = match AppState::load() {
-        Ok(state) => state,
-        Err(e) => {
-            eprintln!("{}", format_error(&e));
-            std::process::exit(e.exit_code());
-        }
-    };
-
-    // 2. Build Router (Axum-style)
-    let router = Router::new()
+    // 1. Build Router (Axum-style)
+    // 2. Load application state (a load error is rendered like any other error)
+    // 3. Execute the command from env::args, print the response and exit
+    Router::new()
         .route("status", handlers::status)
         .route("build", handlers::build)
         .route("test", handlers::test)
-        .with_state(app_state);
-
-    // 3. Execute command via Router (reads from env::args automatically)
-    let response = router.execute().await;
-
-    // 5. Handle response
-    if !response.output.is_empty() {
-        println!("{}", response.output);
-    }
-
-    std::process::exit(response.exit_code);
-}
-
-fn format_error(e: &sen::CliError) -> String {
-    match e {
-        sen::CliError::User(user_err) => format!("{}", user_err),
-        sen::CliError::System(sys_err) => format!("{}", sys_err),
-    }
+        .try_run(async { AppState::load() })
+        .await
+        .exit();
 }
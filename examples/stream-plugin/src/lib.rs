@@ -0,0 +1,71 @@
+//! Stream Plugin - Demonstrates streaming output via effects
+//!
+//! This plugin emits its output in chunks instead of a single result.
+//! Each chunk is printed by the host as soon as it arrives, and progress
+//! updates drive the host's progress display.
+//!
+//! # Flow
+//!
+//! ```text
+//! Plugin: execute([])
+//!       → Effect::Emit("chunk 1/3")
+//!
+//! Host:  Prints "chunk 1/3"
+//!       → resume(0, EffectResult::Emitted)
+//!
+//! Plugin: → Effect::Progress { done: 1, total: 3 }
+//!  ...    (repeated for chunks 2 and 3)
+//!
+//! Plugin: → Success("Streamed 3 chunks")
+//! ```
+
+use sen_plugin_sdk::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of chunks to emit
+const TOTAL: u64 = 3;
+
+/// Streaming steps taken so far (each chunk is an emit plus a progress update)
+static STEP: AtomicU64 = AtomicU64::new(0);
+
+struct StreamPlugin;
+
+impl StreamPlugin {
+    /// Produce the next step of the stream
+    fn next() -> ExecuteResult {
+        let step = STEP.fetch_add(1, Ordering::SeqCst);
+        let chunk = step / 2 + 1;
+
+        if chunk > TOTAL {
+            ExecuteResult::success(format!("Streamed {} chunks", TOTAL))
+        } else if step % 2 == 0 {
+            ExecuteResult::emit(format!("chunk {}/{}", chunk, TOTAL))
+        } else {
+            ExecuteResult::progress(chunk, TOTAL)
+        }
+    }
+}
+
+impl Plugin for StreamPlugin {
+    fn manifest() -> PluginManifest {
+        PluginManifest::new(
+            CommandSpec::new("stream", "Emit output in chunks (streaming effect demo)")
+                .version("1.0.0"),
+        )
+    }
+
+    fn execute(_args: Vec<String>) -> ExecuteResult {
+        STEP.store(0, Ordering::SeqCst);
+        Self::next()
+    }
+
+    fn resume(_effect_id: u32, result: EffectResult) -> ExecuteResult {
+        match result {
+            EffectResult::Emitted => Self::next(),
+            EffectResult::Error(e) => ExecuteResult::user_error(format!("Stream failed: {}", e)),
+            _ => ExecuteResult::system_error("Unexpected effect result"),
+        }
+    }
+}
+
+export_plugin!(StreamPlugin);
@@ -0,0 +1,22 @@
+//! Upper Plugin - Pipeline stage example
+//!
+//! Upper-cases its input. Chain it after another plugin with
+//! `PluginRegistry::execute_pipeline`, which passes the previous stage's
+//! output as the first argument.
+
+use sen_plugin_sdk::prelude::*;
+
+/// Converts text to upper case
+#[derive(Plugin)]
+#[plugin(command = "upper", version = "1.0.0")]
+struct Upper {
+    /// Text to convert
+    #[arg(positional)]
+    text: String,
+}
+
+impl Upper {
+    fn run(self) -> ExecuteResult {
+        ExecuteResult::success(self.text.to_uppercase())
+    }
+}
@@ -26,7 +26,7 @@
 //! ```
 
 use sen_plugin_api::{ExecuteResult, PluginManifest};
-use sen_plugin_host::{HotReloadWatcher, PluginRegistry, PluginScanner, WatcherConfig};
+use sen_plugin_host::{HotReloadWatcher, PluginLoader, PluginRegistry, WatcherConfig};
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
@@ -283,6 +283,10 @@ async fn execute_plugin(registry: &PluginRegistry, cmd: &str, args: &[String]) -
                 eprintln!("Error ({}): {}", err.code, err.message);
                 ExitCode::from(err.code)
             }
+            ExecuteResult::Effect(effect) => {
+                eprintln!("Unsupported plugin effect: {:?}", effect);
+                ExitCode::FAILURE
+            }
         },
         Err(e) => {
             eprintln!("Execution failed: {}", e);
@@ -425,10 +429,10 @@ async fn run_doctor() {
         return;
     }
 
-    let scanner = match PluginScanner::new() {
-        Ok(s) => s,
+    let loader = match PluginLoader::new() {
+        Ok(l) => l,
         Err(e) => {
-            eprintln!("  Failed to create scanner: {}", e);
+            eprintln!("  Failed to create loader: {}", e);
             return;
         }
     };
@@ -440,20 +444,6 @@ async fn run_doctor() {
         let path = entry.path();
         let filename = path.file_name().unwrap_or_default().to_string_lossy();
 
-        // Check file size
-        let metadata = match std::fs::metadata(&path) {
-            Ok(m) => m,
-            Err(e) => {
-                println!("\u{2717} {}", filename);
-                println!("    Error: Cannot read file: {}", e);
-                println!();
-                fail_count += 1;
-                continue;
-            }
-        };
-        let size_kb = metadata.len() / 1024;
-
-        // Check WASM magic bytes
         let bytes = match std::fs::read(&path) {
             Ok(b) => b,
             Err(e) => {
@@ -464,93 +454,63 @@ async fn run_doctor() {
                 continue;
             }
         };
+        let size_kb = bytes.len() / 1024;
 
-        if bytes.len() < 8 || &bytes[0..4] != b"\0asm" {
-            println!("\u{2717} {}", filename);
-            println!("    Error: Not a valid WASM file (bad magic bytes)");
-            println!("    Suggestion: Rebuild with correct WASM target");
-            println!();
+        let report = loader.validate(&bytes);
+        if report.is_valid() {
+            println!("\u{2713} {} ({}KB)", filename, size_kb);
+            ok_count += 1;
+        } else {
+            println!("\u{2717} {} ({}KB)", filename, size_kb);
             fail_count += 1;
-            continue;
         }
 
-        // Try to load as plugin
-        let result = scanner.scan_directory(&plugin_dir);
-        let plugin_result = match &result {
-            Ok(r) => r
-                .plugins
-                .iter()
-                .find(|p| {
-                    path.file_stem()
-                        .map(|s| s.to_string_lossy().contains(&p.manifest.command.name))
-                        .unwrap_or(false)
-                })
-                .map(|p| Ok(p.manifest.clone()))
-                .or_else(|| {
-                    r.failures
-                        .iter()
-                        .find(|(p, _)| p == &path)
-                        .map(|(_, e)| Err(e.to_string()))
-                }),
-            Err(e) => Some(Err(e.to_string())),
-        };
-
-        match plugin_result {
-            Some(Ok(manifest)) => {
-                println!("\u{2713} {} ({}KB)", filename, size_kb);
-                println!("    Command: {}", manifest.command.name);
-                println!(
-                    "    API: v{} {}",
-                    manifest.api_version,
-                    if manifest.api_version == API_VERSION {
-                        "(compatible)"
-                    } else {
-                        "(INCOMPATIBLE!)"
-                    }
-                );
-                if let Some(version) = &manifest.command.version {
-                    println!("    Version: {}", version);
-                }
-                if !manifest.command.args.is_empty() {
-                    let args: Vec<_> = manifest.command.args.iter().map(|a| &a.name).collect();
-                    println!(
-                        "    Args: [{}]",
-                        args.into_iter()
-                            .map(|s| s.as_str())
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    );
+        if let Some(manifest) = &report.manifest {
+            println!("    Command: {}", manifest.command.name);
+            println!(
+                "    API: v{} {}",
+                manifest.api_version,
+                if manifest.api_version == API_VERSION {
+                    "(compatible)"
+                } else {
+                    "(INCOMPATIBLE!)"
                 }
-                println!();
-                ok_count += 1;
+            );
+            if let Some(version) = &manifest.command.version {
+                println!("    Version: {}", version);
             }
-            Some(Err(e)) => {
-                println!("\u{2717} {} ({}KB)", filename, size_kb);
-                println!("    Error: {}", e);
-
-                // Provide suggestions based on error
-                if e.contains("not found") {
-                    println!(
-                        "    Suggestion: Ensure plugin exports plugin_manifest and plugin_execute"
-                    );
-                } else if e.contains("version") {
-                    println!(
-                        "    Suggestion: Rebuild with sen-plugin-sdk {}.x",
-                        API_VERSION
-                    );
-                } else if e.contains("Deserialization") {
-                    println!("    Suggestion: Check manifest format matches sen-plugin-api");
-                }
-                println!();
-                fail_count += 1;
+            if !manifest.command.args.is_empty() {
+                let args: Vec<_> = manifest
+                    .command
+                    .args
+                    .iter()
+                    .map(|a| a.name.as_str())
+                    .collect();
+                println!("    Args: [{}]", args.join(", "));
             }
-            None => {
-                // File exists but wasn't processed - try direct load
-                println!("? {} ({}KB)", filename, size_kb);
-                println!("    Status: Unknown (scan did not process this file)");
-                println!();
+        }
+
+        for issue in report.errors() {
+            println!("    Error [{}]: {}", issue.code, issue.message);
+            match issue.code {
+                "invalid_module" => println!("    Suggestion: Rebuild with correct WASM target"),
+                "missing_export" | "export_type" => println!(
+                    "    Suggestion: Export the plugin with sen_plugin_sdk::export_plugin!"
+                ),
+                "api_version" => println!(
+                    "    Suggestion: Rebuild with sen-plugin-sdk {}.x",
+                    API_VERSION
+                ),
+                "manifest_decode" => {
+                    println!("    Suggestion: Check manifest format matches sen-plugin-api")
+                }
+                _ => {}
             }
         }
+        for issue in report.warnings() {
+            println!("    Warning [{}]: {}", issue.code, issue.message);
+        }
+        println!();
     }
 
     println!("Summary: {} OK, {} failed", ok_count, fail_count);
@@ -4,12 +4,21 @@
 //! Communication uses MessagePack serialization.
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 
 /// API version for compatibility checking
 /// - v1: Initial version (command + args only)
 /// - v2: Added capabilities support
-pub const API_VERSION: u32 = 2;
+/// - v3: Added streaming effects (`Effect::Emit`, `Effect::Progress`),
+///   plugin-to-plugin calls (`Effect::InvokePlugin` with
+///   `Capabilities::invoke`), and scratch space (`Capabilities::tmp`)
+pub const API_VERSION: u32 = 3;
+
+/// Oldest API version hosts still load
+///
+/// v3 only added effects and capabilities that older plugins never use, so
+/// v2 plugins run unchanged on a v3 host. A plugin that yields any v3 effect
+/// or requests `invoke` or `tmp` needs a v3 host.
+pub const MIN_SUPPORTED_API_VERSION: u32 = 2;
 
 // ============================================================================
 // Capabilities Types
@@ -53,6 +62,16 @@ pub struct Capabilities {
     /// Standard I/O access
     #[serde(default, skip_serializing_if = "StdioCapability::is_none")]
     pub stdio: StdioCapability,
+
+    /// Commands of other plugins this plugin may call via
+    /// [`Effect::InvokePlugin`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invoke: Vec<String>,
+
+    /// Private scratch directory, preopened read-write at `/tmp` in the
+    /// guest and removed after each execution
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tmp: bool,
 }
 
 impl Capabilities {
@@ -68,6 +87,8 @@ impl Capabilities {
             && self.env_read.is_empty()
             && self.net.is_empty()
             && self.stdio.is_none()
+            && self.invoke.is_empty()
+            && !self.tmp
     }
 
     /// Add filesystem read paths
@@ -100,6 +121,35 @@ impl Capabilities {
         self
     }
 
+    /// Add plugin commands this plugin may invoke
+    pub fn with_invoke(mut self, commands: Vec<String>) -> Self {
+        self.invoke = commands;
+        self
+    }
+
+    /// Request a private temp directory at `/tmp`
+    pub fn with_tmp(mut self, tmp: bool) -> Self {
+        self.tmp = tmp;
+        self
+    }
+
+    /// Combine two capability sets, e.g. a base preset and extra grants
+    ///
+    /// Each list keeps `self`'s entries in order, followed by those of
+    /// `other` not already present; the stdio and tmp flags are ORed.
+    pub fn union(mut self, other: Capabilities) -> Capabilities {
+        union_into(&mut self.fs_read, other.fs_read);
+        union_into(&mut self.fs_write, other.fs_write);
+        union_into(&mut self.env_read, other.env_read);
+        union_into(&mut self.net, other.net);
+        self.stdio.stdin |= other.stdio.stdin;
+        self.stdio.stdout |= other.stdio.stdout;
+        self.stdio.stderr |= other.stdio.stderr;
+        union_into(&mut self.invoke, other.invoke);
+        self.tmp |= other.tmp;
+        self
+    }
+
     /// Check if `self` is a subset of `other` (all requested capabilities are granted)
     pub fn is_subset_of(&self, other: &Capabilities) -> bool {
         // Check fs_read
@@ -141,6 +191,18 @@ impl Capabilities {
             return false;
         }
 
+        // Check invoke
+        for command in &self.invoke {
+            if !other.invoke.contains(command) {
+                return false;
+            }
+        }
+
+        // Check tmp
+        if self.tmp && !other.tmp {
+            return false;
+        }
+
         true
     }
 
@@ -164,10 +226,95 @@ impl Capabilities {
     }
 }
 
+/// Split a path into segments, dropping `.` and empty segments and
+/// resolving `..` against the segment before it
+///
+/// A leading `/` is kept as its own segment so absolute and relative paths
+/// never match each other. A `..` with nothing left to resolve stays in the
+/// result; wildcards never match it, so `./data/**` does not cover
+/// `./data/../../etc`.
+fn path_segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    if path.starts_with('/') {
+        segments.push("/");
+    }
+    for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+        match (segment, segments.last()) {
+            // `/..` is `/`
+            ("..", Some(&"/")) => {}
+            ("..", Some(&last)) if last != ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments
+}
+
+/// Match path segments against glob segments (`**` spans directories)
+///
+/// Wildcards never match a `..` segment.
+fn glob_match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => {
+            glob_match_segments(rest, path)
+                || (!path.is_empty() && path[0] != ".." && glob_match_segments(pattern, &path[1..]))
+        }
+        Some((segment, rest)) => {
+            !path.is_empty()
+                && (path[0] != ".." || *segment == "..")
+                && wildcard_match(segment, path[0])
+                && glob_match_segments(rest, &path[1..])
+        }
+    }
+}
+
+/// Match `text` against `pattern`, with `*` matching any run of characters
+///
+/// Used for names (environment variables, commands, plugins) and for a
+/// single path segment; whole paths go through [`PathPattern::matches`],
+/// where `*` never crosses `/`.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (p, t) = (pattern.as_bytes(), text.as_bytes());
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == t[ti] {
+            pi += 1;
+            ti += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == b'*')
+}
+
+/// Append the items of `from` that `into` does not contain yet
+fn union_into<T: PartialEq>(into: &mut Vec<T>, from: Vec<T>) {
+    for item in from {
+        if !into.contains(&item) {
+            into.push(item);
+        }
+    }
+}
+
 /// Filesystem path pattern
+///
+/// A pattern containing `*` is a glob, matched one path segment at a time:
+/// `*` matches within a segment and a `**` segment matches any number of
+/// directories (`./data/**/*.csv`). Other patterns name a directory.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PathPattern {
-    /// Path pattern (e.g., "./data", "/tmp", "~/.config/app")
+    /// Path pattern (e.g., "./data", "/tmp", "~/.config/app", "./data/*.json")
     pub pattern: String,
 
     /// Allow recursive access to subdirectories
@@ -190,6 +337,39 @@ impl PathPattern {
         self
     }
 
+    /// Whether the pattern contains wildcards
+    pub fn is_glob(&self) -> bool {
+        self.pattern.contains('*')
+    }
+
+    /// Directory before the first wildcard (`./data` for `./data/**/*.csv`)
+    ///
+    /// The pattern itself if it is not a glob.
+    pub fn base_dir(&self) -> &str {
+        let Some(star) = self.pattern.find('*') else {
+            return &self.pattern;
+        };
+        match self.pattern[..star].rfind('/') {
+            Some(0) => "/",
+            Some(slash) => &self.pattern[..slash],
+            None => ".",
+        }
+    }
+
+    /// Check if a concrete `path` is covered by this pattern
+    ///
+    /// Globs match the whole path; a plain pattern matches itself and, if
+    /// recursive, anything below it.
+    pub fn matches(&self, path: &str) -> bool {
+        let pattern = path_segments(&self.pattern);
+        let path = path_segments(path);
+        if self.is_glob() {
+            glob_match_segments(&pattern, &path)
+        } else {
+            path == pattern || (self.recursive && path.starts_with(&pattern))
+        }
+    }
+
     /// Check if this pattern contains/covers another pattern
     pub fn contains(&self, other: &PathPattern) -> bool {
         if self.pattern == other.pattern {
@@ -197,24 +377,45 @@ impl PathPattern {
             return self.recursive || !other.recursive;
         }
 
-        // If self is recursive, check if other is under self's path
-        if self.recursive {
-            let self_path = PathBuf::from(&self.pattern);
-            let other_path = PathBuf::from(&other.pattern);
-
-            // Normalize for comparison (handle ./foo vs foo)
-            let self_normalized = self_path.components().collect::<Vec<_>>();
-            let other_normalized = other_path.components().collect::<Vec<_>>();
-
-            if other_normalized.len() >= self_normalized.len() {
-                return other_normalized
-                    .iter()
-                    .take(self_normalized.len())
-                    .eq(self_normalized.iter());
+        if self.is_glob() {
+            // `<dir>/**` covers everything below `<dir>`, globs included
+            let own = path_segments(&self.pattern);
+            if let Some((&"**", base)) = own.split_last() {
+                if !base.iter().any(|s| s.contains('*')) {
+                    let theirs = path_segments(other.base_dir());
+                    return theirs.starts_with(base) && !theirs[base.len()..].contains(&"..");
+                }
+            }
+            // Otherwise a glob covers the paths it matches; a whole tree only
+            // if the glob ends in `**`
+            return !other.is_glob()
+                && (!other.recursive || self.pattern.ends_with("/**"))
+                && self.matches(&other.pattern);
+        }
+
+        if other.is_glob() {
+            let base = path_segments(other.base_dir());
+            let own = path_segments(&self.pattern);
+            if self.recursive {
+                return base.starts_with(&own);
             }
+            // Without recursion only the directory's own entries are covered
+            let wildcards = &path_segments(&other.pattern)[base.len()..];
+            return base == own && wildcards.len() == 1 && wildcards[0] != "**";
         }
 
-        false
+        // If self is recursive, check if other is under self's path
+        self.recursive && path_segments(&other.pattern).starts_with(&path_segments(&self.pattern))
+    }
+
+    /// Check if some path could be covered by both patterns
+    ///
+    /// Conservative: true whenever the directory of one pattern (before any
+    /// wildcard) is inside the other's, even if their wildcards never agree.
+    pub fn overlaps(&self, other: &PathPattern) -> bool {
+        let own = path_segments(self.base_dir());
+        let theirs = path_segments(other.base_dir());
+        own.starts_with(&theirs) || theirs.starts_with(&own)
     }
 }
 
@@ -252,6 +453,24 @@ impl NetPattern {
         }
     }
 
+    /// Create HTTP (plaintext) pattern
+    pub fn http(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            protocol: NetProtocol::Http,
+        }
+    }
+
+    /// Create HTTP (plaintext) pattern with specific port
+    pub fn http_port(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port: Some(port),
+            protocol: NetProtocol::Http,
+        }
+    }
+
     /// Create TCP pattern
     pub fn tcp(host: impl Into<String>, port: u16) -> Self {
         Self {
@@ -268,8 +487,10 @@ impl NetPattern {
             return false;
         }
 
-        // Host matching (simple wildcard support)
-        let host_matches = if self.host.starts_with("*.") {
+        // Host matching (simple wildcard support, "*" = any host)
+        let host_matches = if self.host == "*" {
+            true
+        } else if self.host.starts_with("*.") {
             let suffix = &self.host[1..]; // ".github.com"
             other.host.ends_with(suffix) || other.host == self.host[2..]
         } else {
@@ -463,6 +684,18 @@ pub struct ExecuteError {
 /// Host:   plugin_resume(1, EffectResult::Http { ... })
 /// Plugin: resume(1, result) -> Success("done")
 /// ```
+///
+/// # Streaming Flow (v3+)
+///
+/// ```text
+/// Plugin: execute(args)              -> Effect::Emit("chunk 1")
+/// Host:   prints "chunk 1" immediately
+/// Host:   plugin_resume(0, EffectResult::Emitted)
+/// Plugin: resume(0, Emitted)         -> Effect::Progress { done: 1, total: 2 }
+/// Host:   updates progress display
+/// Host:   plugin_resume(0, EffectResult::Emitted)
+/// Plugin: resume(0, Emitted)         -> Success("done")
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Effect {
     /// HTTP GET request
@@ -499,6 +732,40 @@ pub enum Effect {
         /// Duration in milliseconds
         duration_ms: u64,
     },
+
+    /// Incremental output chunk (v3+)
+    ///
+    /// The host prints the chunk immediately and resumes the plugin with
+    /// `EffectResult::Emitted`. Streaming effects carry no ID; the host
+    /// resumes them with effect ID `0`.
+    Emit(String),
+
+    /// Progress report (v3+)
+    ///
+    /// The host forwards the update to its progress display and resumes the
+    /// plugin with `EffectResult::Emitted`.
+    Progress {
+        /// Number of items completed
+        done: u64,
+        /// Total number of items
+        total: u64,
+    },
+
+    /// Run another loaded plugin's command
+    ///
+    /// The host executes `command` with `args` (driving its own effects) and
+    /// resumes the caller with `EffectResult::PluginOutput`, or
+    /// `EffectResult::Error` if it failed. The caller must list `command` in
+    /// its `invoke` capability.
+    InvokePlugin {
+        /// Unique ID for this effect
+        id: u32,
+        /// Command name of the plugin to run
+        command: String,
+        /// Arguments passed to that plugin
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
 }
 
 impl Effect {
@@ -508,9 +775,19 @@ impl Effect {
             Effect::HttpGet { id, .. } => *id,
             Effect::HttpPost { id, .. } => *id,
             Effect::Sleep { id, .. } => *id,
+            Effect::InvokePlugin { id, .. } => *id,
+            Effect::Emit(_) | Effect::Progress { .. } => 0,
         }
     }
 
+    /// Check if this is a streaming effect (`Emit` or `Progress`)
+    ///
+    /// Streaming effects are handled by the host's effect loop itself rather
+    /// than being dispatched to an effect handler.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Effect::Emit(_) | Effect::Progress { .. })
+    }
+
     /// Create an HTTP GET effect
     pub fn http_get(id: u32, url: impl Into<String>) -> Self {
         Effect::HttpGet {
@@ -535,6 +812,25 @@ impl Effect {
     pub fn sleep(id: u32, duration_ms: u64) -> Self {
         Effect::Sleep { id, duration_ms }
     }
+
+    /// Create an effect that runs another plugin's command
+    pub fn invoke_plugin(id: u32, command: impl Into<String>, args: Vec<String>) -> Self {
+        Effect::InvokePlugin {
+            id,
+            command: command.into(),
+            args,
+        }
+    }
+
+    /// Create an emit effect
+    pub fn emit(chunk: impl Into<String>) -> Self {
+        Effect::Emit(chunk.into())
+    }
+
+    /// Create a progress effect
+    pub fn progress(done: u64, total: u64) -> Self {
+        Effect::Progress { done, total }
+    }
 }
 
 /// Result of an effect, passed back to plugin via resume
@@ -546,6 +842,12 @@ pub enum EffectResult {
     /// Sleep completed
     SleepComplete,
 
+    /// Streaming effect (`Emit` or `Progress`) was delivered
+    Emitted,
+
+    /// Output of a plugin run via `Effect::InvokePlugin`
+    PluginOutput(String),
+
     /// Effect failed
     Error(String),
 }
@@ -586,8 +888,142 @@ impl ExecuteResult {
     pub fn sleep(id: u32, duration_ms: u64) -> Self {
         ExecuteResult::Effect(Effect::sleep(id, duration_ms))
     }
+
+    /// Create an effect that runs another plugin's command
+    pub fn invoke_plugin(id: u32, command: impl Into<String>, args: Vec<String>) -> Self {
+        ExecuteResult::Effect(Effect::invoke_plugin(id, command, args))
+    }
+
+    /// Create an emit effect
+    pub fn emit(chunk: impl Into<String>) -> Self {
+        ExecuteResult::Effect(Effect::emit(chunk))
+    }
+
+    /// Create a progress effect
+    pub fn progress(done: u64, total: u64) -> Self {
+        ExecuteResult::Effect(Effect::progress(done, total))
+    }
+}
+
+// =============================================================================
+// Host-Proxy HTTP (WASI plugins)
+// =============================================================================
+
+/// Import module name for host functions provided to WASI plugins
+pub const HOST_IMPORT_MODULE: &str = "sen";
+
+/// HTTP request passed to the `host_http_request` host import
+///
+/// Unlike [`Effect::HttpGet`], this is a synchronous call: the plugin blocks
+/// until the host returns an [`HttpResult`]. The host only performs requests
+/// whose URL is covered by the plugin's declared `net` capabilities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    /// HTTP method (e.g., "GET", "POST")
+    pub method: String,
+
+    /// Absolute `http://` or `https://` URL
+    pub url: String,
+
+    /// Optional request body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// Request headers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    /// Create a request with the given method and URL
+    pub fn new(method: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            method: method.into(),
+            url: url.into(),
+            body: None,
+            headers: vec![],
+        }
+    }
+
+    /// Create a GET request
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    /// Create a POST request with a body
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new("POST", url).body(body)
+    }
+
+    /// Set the request body
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Add a request header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Why a host-proxied HTTP request failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpErrorKind {
+    /// URL is not covered by the plugin's `net` capabilities
+    Denied,
+    /// Malformed URL, method, or request encoding
+    InvalidRequest,
+    /// Request exceeded the host's timeout
+    Timeout,
+    /// Connection or protocol failure
+    Transport,
+}
+
+/// Structured error returned to the plugin by `host_http_request`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpError {
+    /// Error category
+    pub kind: HttpErrorKind,
+
+    /// Human-readable message
+    pub message: String,
+}
+
+impl HttpError {
+    /// Create a new HTTP error
+    pub fn new(kind: HttpErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Check if the request was denied by the capability check
+    pub fn is_denied(&self) -> bool {
+        self.kind == HttpErrorKind::Denied
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            HttpErrorKind::Denied => "denied",
+            HttpErrorKind::InvalidRequest => "invalid request",
+            HttpErrorKind::Timeout => "timeout",
+            HttpErrorKind::Transport => "transport error",
+        };
+        write!(f, "{}: {}", kind, self.message)
+    }
 }
 
+impl std::error::Error for HttpError {}
+
+/// Result of a host-proxied HTTP request
+pub type HttpResult = Result<HttpResponse, HttpError>;
+
 /// Plugin manifest with API version
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginManifest {
@@ -806,6 +1242,23 @@ mod tests {
         assert!(!decoded.stdio.stdin);
     }
 
+    #[test]
+    fn test_capabilities_tmp() {
+        let caps = Capabilities::default().with_tmp(true);
+        assert!(!caps.is_empty());
+
+        let bytes = rmp_serde::to_vec_named(&caps).unwrap();
+        let decoded: Capabilities = rmp_serde::from_slice(&bytes).unwrap();
+        assert!(decoded.tmp);
+
+        // Omitted when not requested, so existing capability hashes are unchanged
+        let bytes = rmp_serde::to_vec_named(&Capabilities::default()).unwrap();
+        assert!(!bytes.windows(3).any(|w| w == b"tmp"));
+
+        assert!(!caps.is_subset_of(&Capabilities::default()));
+        assert!(caps.is_subset_of(&Capabilities::default().union(caps.clone())));
+    }
+
     #[test]
     fn test_capabilities_subset() {
         let requested = Capabilities::default()
@@ -826,6 +1279,49 @@ mod tests {
         assert!(!over_requested.is_subset_of(&granted));
     }
 
+    #[test]
+    fn test_capabilities_union_of_disjoint_sets() {
+        let base = Capabilities::default()
+            .with_fs_read(vec![PathPattern::new("./data")])
+            .with_stdio(StdioCapability::stdout_only());
+        let extra = Capabilities::default()
+            .with_fs_write(vec![PathPattern::new("./output")])
+            .with_env_read(vec!["HOME".to_string()])
+            .with_stdio(StdioCapability {
+                stderr: true,
+                ..StdioCapability::none()
+            });
+
+        let merged = base.clone().union(extra.clone());
+
+        assert!(base.is_subset_of(&merged));
+        assert!(extra.is_subset_of(&merged));
+        assert_eq!(merged.stdio, StdioCapability::stdout_stderr());
+    }
+
+    #[test]
+    fn test_capabilities_union_dedupes() {
+        let a = Capabilities::default().with_fs_read(vec![
+            PathPattern::new("./data"),
+            PathPattern::new("./config"),
+        ]);
+        let b = Capabilities::default().with_fs_read(vec![
+            PathPattern::new("./config"),
+            PathPattern::new("./data").recursive(),
+        ]);
+
+        let merged = a.union(b);
+
+        assert_eq!(
+            merged.fs_read,
+            vec![
+                PathPattern::new("./data"),
+                PathPattern::new("./config"),
+                PathPattern::new("./data").recursive(),
+            ]
+        );
+    }
+
     #[test]
     fn test_path_pattern_contains() {
         let parent = PathPattern::new("./data").recursive();
@@ -839,6 +1335,79 @@ mod tests {
         assert!(!same.contains(&parent)); // non-recursive doesn't cover recursive request
     }
 
+    #[test]
+    fn test_path_pattern_glob_matches_nested_files() {
+        let csv = PathPattern::new("./data/**/*.csv");
+        assert!(csv.is_glob());
+        assert_eq!(csv.base_dir(), "./data");
+
+        assert!(csv.matches("./data/report.csv"));
+        assert!(csv.matches("data/2024/q1/report.csv"));
+        assert!(!csv.matches("./data/notes.txt"));
+        assert!(!csv.matches("./other/report.csv"));
+
+        assert!(csv.contains(&PathPattern::new("./data/2024/report.csv")));
+        assert!(!csv.contains(&PathPattern::new("./data/2024/report.txt")));
+        assert!(!csv.contains(&PathPattern::new("./data/2024").recursive()));
+    }
+
+    #[test]
+    fn test_path_pattern_plain_covers_glob_under_it() {
+        let json = PathPattern::new("./data/*.json");
+        assert!(json.matches("./data/a.json"));
+        assert!(!json.matches("./data/sub/a.json"));
+
+        assert!(PathPattern::new("./data").recursive().contains(&json));
+        assert!(PathPattern::new("./data").contains(&json));
+        assert!(!PathPattern::new("./data").contains(&PathPattern::new("./data/**/*.json")));
+        assert!(!PathPattern::new("./other").recursive().contains(&json));
+
+        // Plain patterns keep prefix semantics
+        assert!(!PathPattern::new("./data").is_glob());
+        assert!(PathPattern::new("./data").recursive().matches("./data/x/y"));
+        assert!(!PathPattern::new("./data").matches("./data/x"));
+    }
+
+    #[test]
+    fn test_path_pattern_parent_segments_do_not_escape() {
+        let csv = PathPattern::new("./data/**/*.csv");
+        assert!(!csv.matches("./data/../../etc/x.csv"));
+        assert!(!csv.matches("./data/../secret.csv"));
+        assert!(csv.matches("./data/a/../b/x.csv"));
+        assert!(!csv.contains(&PathPattern::new("./data/../../etc/x.csv")));
+        assert!(!PathPattern::new("./**/*.csv").matches("../x.csv"));
+
+        let data = PathPattern::new("./data").recursive();
+        assert!(!data.matches("./data/../secret/key"));
+        assert!(!data.contains(&PathPattern::new("./data/../secret/*")));
+        assert!(!data.contains(&PathPattern::new("./data/../secret").recursive()));
+        assert!(data.contains(&PathPattern::new("./data/x/../y")));
+
+        // Explicit parents in the pattern still match literally
+        assert!(PathPattern::new("../shared/*.txt").matches("../shared/a.txt"));
+        assert!(PathPattern::new("/..").matches("/"));
+    }
+
+    #[test]
+    fn test_path_pattern_double_star_covers_globs_below() {
+        let data = PathPattern::new("./data/**");
+        assert!(data.contains(&PathPattern::new("./data/*.csv")));
+        assert!(data.contains(&PathPattern::new("./data/2024/**/*.csv")));
+        assert!(data.contains(&PathPattern::new("./data/2024").recursive()));
+        assert!(!data.contains(&PathPattern::new("./other/*.csv")));
+        assert!(!PathPattern::new("**").contains(&PathPattern::new("../*.csv")));
+    }
+
+    #[test]
+    fn test_path_pattern_overlaps() {
+        let secret = PathPattern::new("./secret").recursive();
+        assert!(secret.overlaps(&PathPattern::new("./secret/*.key")));
+        assert!(secret.overlaps(&PathPattern::new("./**")));
+        assert!(PathPattern::new("./secret/a").overlaps(&secret));
+        assert!(!secret.overlaps(&PathPattern::new("./data/*.key")));
+        assert!(!secret.overlaps(&PathPattern::new("./secret/../data")));
+    }
+
     #[test]
     fn test_net_pattern_contains() {
         let wildcard = NetPattern::https("*.github.com");
@@ -854,6 +1423,13 @@ mod tests {
         assert!(!with_port.contains(&any_port));
     }
 
+    #[test]
+    fn test_net_pattern_any_host() {
+        let any = NetPattern::https("*");
+        assert!(any.contains(&NetPattern::https_port("api.example.com", 443)));
+        assert!(!any.contains(&NetPattern::http_port("api.example.com", 80)));
+    }
+
     #[test]
     fn test_manifest_with_capabilities() {
         let caps = Capabilities::default().with_fs_read(vec![PathPattern::new("./data")]);
@@ -880,4 +1456,64 @@ mod tests {
         assert_eq!(caps1.compute_hash(), caps2.compute_hash());
         assert_ne!(caps1.compute_hash(), caps3.compute_hash());
     }
+
+    #[test]
+    fn test_stream_effects_roundtrip() {
+        let emit = ExecuteResult::emit("chunk 1");
+        let bytes = rmp_serde::to_vec(&emit).unwrap();
+        match rmp_serde::from_slice::<ExecuteResult>(&bytes).unwrap() {
+            ExecuteResult::Effect(Effect::Emit(chunk)) => assert_eq!(chunk, "chunk 1"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let progress = Effect::progress(2, 3);
+        assert!(progress.is_stream());
+        assert_eq!(progress.id(), 0);
+        assert!(!Effect::sleep(1, 10).is_stream());
+
+        let bytes = rmp_serde::to_vec(&EffectResult::Emitted).unwrap();
+        assert!(matches!(
+            rmp_serde::from_slice::<EffectResult>(&bytes).unwrap(),
+            EffectResult::Emitted
+        ));
+    }
+
+    #[test]
+    fn test_invoke_plugin_roundtrip() {
+        let invoke = ExecuteResult::invoke_plugin(3, "upper", vec!["hi".into()]);
+        let bytes = rmp_serde::to_vec_named(&invoke).unwrap();
+        match rmp_serde::from_slice::<ExecuteResult>(&bytes).unwrap() {
+            ExecuteResult::Effect(effect @ Effect::InvokePlugin { .. }) => {
+                assert_eq!(effect.id(), 3);
+                assert!(!effect.is_stream());
+                assert!(matches!(
+                    effect,
+                    Effect::InvokePlugin { ref command, ref args, .. }
+                        if command == "upper" && args == &["hi"]
+                ));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let caller = Capabilities::default().with_invoke(vec!["upper".into()]);
+        assert!(!caller.is_empty());
+        assert!(caller.is_subset_of(&caller.clone()));
+        assert!(!caller.is_subset_of(&Capabilities::default()));
+    }
+
+    #[test]
+    fn test_http_result_roundtrip() {
+        let request = HttpRequest::post("https://api.example.com/items", "{}")
+            .header("Content-Type", "application/json");
+        let bytes = rmp_serde::to_vec_named(&request).unwrap();
+        assert_eq!(
+            rmp_serde::from_slice::<HttpRequest>(&bytes).unwrap(),
+            request
+        );
+
+        let denied: HttpResult = Err(HttpError::new(HttpErrorKind::Denied, "not allowed"));
+        let bytes = rmp_serde::to_vec_named(&denied).unwrap();
+        let decoded: HttpResult = rmp_serde::from_slice(&bytes).unwrap();
+        assert!(decoded.unwrap_err().is_denied());
+    }
 }
@@ -5,7 +5,7 @@
 
 use chrono::{DateTime, Utc};
 use sen_plugin_api::Capabilities;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt;
 use std::fs::{File, OpenOptions};
@@ -24,7 +24,7 @@ fn now_iso8601() -> Timestamp {
 }
 
 /// Audit event representing a permission-related action
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
     /// Timestamp of the event
     pub timestamp: Timestamp,
@@ -33,7 +33,7 @@ pub struct AuditEvent {
     /// Plugin name
     pub plugin: String,
     /// Command path (if applicable)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub command: Option<String>,
     /// Additional details
     pub details: AuditDetails,
@@ -63,7 +63,7 @@ impl AuditEvent {
 }
 
 /// Type of audit event
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AuditEventType {
     /// Permission was requested
@@ -83,16 +83,16 @@ pub enum AuditEventType {
 }
 
 /// Details about the audit event
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum AuditDetails {
     /// Permission request/grant/deny details
     Permission {
         /// Trust level if granted
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         trust_level: Option<TrustLevel>,
         /// Reason for denial
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         reason: Option<String>,
         /// Capabilities involved
         capabilities_hash: String,
@@ -109,15 +109,15 @@ pub enum AuditDetails {
     Escalation { old_hash: String, new_hash: String },
     /// Plugin lifecycle
     Lifecycle {
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         path: Option<PathBuf>,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         version: Option<String>,
     },
 }
 
 /// Trust level for permission grants
-#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TrustLevel {
     /// Trust for this execution only
@@ -129,7 +129,7 @@ pub enum TrustLevel {
 }
 
 /// File access mode
-#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AccessMode {
     Read,
@@ -138,7 +138,7 @@ pub enum AccessMode {
 }
 
 /// Standard I/O stream
-#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum StdioStream {
     Stdin,
@@ -329,6 +329,108 @@ impl MemoryAuditSink {
             .cloned()
             .collect()
     }
+
+    /// Find events matching every criterion of `filter`
+    pub fn query(&self, filter: &AuditFilter) -> Vec<AuditEvent> {
+        self.events
+            .read()
+            .expect("MemoryAuditSink RwLock poisoned")
+            .iter()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect()
+    }
+
+    /// Export events in the JSON Lines format written by [`FileAuditSink`]
+    ///
+    /// Events that cannot be serialized (a non-UTF-8 path) are skipped, as
+    /// `FileAuditSink` would reject them too.
+    pub fn to_jsonl(&self) -> String {
+        self.events
+            .read()
+            .expect("MemoryAuditSink RwLock poisoned")
+            .iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .map(|json| json + "\n")
+            .collect()
+    }
+}
+
+/// Criteria for [`MemoryAuditSink::query`]
+///
+/// Unset criteria match every event.
+///
+/// ```rust,ignore
+/// let denied = sink.query(
+///     &AuditFilter::new()
+///         .plugin("fetch")
+///         .event_type(AuditEventType::PermissionDenied),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    plugin: Option<String>,
+    event_type: Option<AuditEventType>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    /// Create a filter that matches every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only events of this plugin
+    pub fn plugin(mut self, plugin: impl Into<String>) -> Self {
+        self.plugin = Some(plugin.into());
+        self
+    }
+
+    /// Only events of this type (e.g. the `PermissionGranted` /
+    /// `PermissionDenied` decision)
+    pub fn event_type(mut self, event_type: AuditEventType) -> Self {
+        self.event_type = Some(event_type);
+        self
+    }
+
+    /// Only events at or after `since`
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only events before `until`
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Whether `event` meets every criterion
+    ///
+    /// With a time range set, events whose timestamp does not parse never match.
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        if self.plugin.as_ref().is_some_and(|p| *p != event.plugin) {
+            return false;
+        }
+        if self
+            .event_type
+            .as_ref()
+            .is_some_and(|t| *t != event.event_type)
+        {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+
+        let Ok(timestamp) = DateTime::parse_from_rfc3339(&event.timestamp) else {
+            return false;
+        };
+        let timestamp = timestamp.with_timezone(&Utc);
+        self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp < until)
+    }
 }
 
 impl Default for MemoryAuditSink {
@@ -570,6 +672,58 @@ mod tests {
         assert!(json.contains("permanent"));
     }
 
+    #[test]
+    fn test_memory_sink_jsonl_round_trip() {
+        let sink = MemoryAuditSink::new();
+        let caps = Capabilities::default().with_fs_read(vec![PathPattern::new("./data")]);
+        sink.record(permission_granted("fetch", &caps, TrustLevel::Session).with_command("get"))
+            .unwrap();
+        sink.record(permission_denied("fetch", &caps, "user declined"))
+            .unwrap();
+
+        let jsonl = sink.to_jsonl();
+        let parsed: Vec<AuditEvent> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].event_type, AuditEventType::PermissionGranted);
+        assert_eq!(parsed[0].command.as_deref(), Some("get"));
+        assert!(matches!(
+            parsed[1].details,
+            AuditDetails::Permission { ref reason, .. } if reason.as_deref() == Some("user declined")
+        ));
+    }
+
+    #[test]
+    fn test_memory_sink_query() {
+        let sink = MemoryAuditSink::new();
+        let caps = Capabilities::none();
+        sink.record(permission_granted("fetch", &caps, TrustLevel::Once))
+            .unwrap();
+        sink.record(permission_denied("fetch", &caps, "no"))
+            .unwrap();
+        sink.record(permission_denied("shell", &caps, "no"))
+            .unwrap();
+
+        let denied = AuditFilter::new().event_type(AuditEventType::PermissionDenied);
+        assert_eq!(sink.query(&denied).len(), 2);
+
+        let events = sink.query(&denied.clone().plugin("shell"));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].plugin, "shell");
+
+        let hour = chrono::Duration::hours(1);
+        assert_eq!(
+            sink.query(&AuditFilter::new().since(Utc::now() - hour))
+                .len(),
+            3
+        );
+        assert!(sink
+            .query(&AuditFilter::new().until(Utc::now() - hour))
+            .is_empty());
+    }
+
     #[test]
     fn test_file_sink() {
         let dir = tempfile::tempdir().unwrap();
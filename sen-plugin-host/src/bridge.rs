@@ -2,9 +2,13 @@
 //!
 //! Provides integration to register plugin commands as native routes.
 
-use crate::{LoadedPlugin, PluginInstance};
-use sen::{Handler, HandlerMetadata, Response, State};
-use sen_plugin_api::{CommandSpec, ExecuteResult};
+use crate::{EffectHandler, LoadedPlugin, PluginInstance, PluginRegistry, RegistryError};
+use sen::{
+    CliError, CliResult, DryRun, Emitter, Handler, HandlerMetadata, IntoResponse, Progress,
+    Response, State, UserError,
+};
+use sen_plugin_api::{ArgSpec, CommandSpec, Effect, EffectResult, ExecuteResult, PluginManifest};
+use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -24,11 +28,13 @@ use tokio::sync::Mutex;
 /// load/unload plugins, consider using a different integration approach.
 #[derive(Clone)]
 pub struct WasmHandler {
-    instance: Arc<Mutex<PluginInstance>>,
+    backend: Backend,
     command_name: String,
     /// Leaked at construction time to satisfy HandlerMetadata's 'static requirement.
     /// See struct-level documentation for trade-offs.
     command_about: &'static str,
+    /// Declared arguments, checked before each execution
+    args: Arc<[ArgSpec]>,
 }
 
 impl WasmHandler {
@@ -40,22 +46,60 @@ impl WasmHandler {
         instance: PluginInstance,
         command_name: impl Into<String>,
         command_about: impl Into<String>,
+    ) -> Self {
+        let backend = Backend::Instance(Arc::new(Mutex::new(instance)));
+        Self::with_backend(backend, command_name, command_about)
+    }
+
+    fn with_backend(
+        backend: Backend,
+        command_name: impl Into<String>,
+        command_about: impl Into<String>,
     ) -> Self {
         // Leak the description once at construction time instead of on every metadata() call
         let about: String = command_about.into();
         let leaked_about: &'static str = Box::leak(about.into_boxed_str());
         Self {
-            instance: Arc::new(Mutex::new(instance)),
+            backend,
             command_name: command_name.into(),
             command_about: leaked_about,
+            args: Arc::from([]),
         }
     }
 
     /// Create from a loaded plugin
+    ///
+    /// The manifest's argument specs are checked before each execution
+    /// (see [`WasmHandler::with_arg_specs`]).
     pub fn from_loaded(plugin: LoadedPlugin) -> Self {
         let command_name = plugin.manifest.command.name.clone();
         let command_about = plugin.manifest.command.about.clone();
-        Self::new(plugin.instance, command_name, command_about)
+        let args = plugin.manifest.command.args.clone();
+        Self::new(plugin.instance, command_name, command_about).with_arg_specs(args)
+    }
+
+    /// Create a handler running `manifest`'s command from `registry`
+    ///
+    /// Each call goes through
+    /// [`PluginRegistry::execute_with_effects`], so the route follows
+    /// reloads, permission checks apply, and the plugin can call other
+    /// plugins of the registry with `Effect::InvokePlugin`. Effects other
+    /// than streaming and invocations resume the plugin with an error.
+    pub fn from_registry(registry: &PluginRegistry, manifest: &PluginManifest) -> Self {
+        let command = &manifest.command;
+        let backend = Backend::Registry(registry.clone());
+        Self::with_backend(backend, &command.name, &command.about)
+            .with_arg_specs(command.args.clone())
+    }
+
+    /// Check arguments against `args` before calling the plugin
+    ///
+    /// Missing required positionals and options, and more positionals than
+    /// declared, are rejected with `UserError::ValidationFailed` without
+    /// running the plugin. Without specs nothing is checked.
+    pub fn with_arg_specs(mut self, args: Vec<ArgSpec>) -> Self {
+        self.args = args.into();
+        self
     }
 
     /// Get the command name
@@ -75,28 +119,256 @@ where
 {
     type Future = Pin<Box<dyn Future<Output = Response> + Send + 'static>>;
 
-    fn call(self, _state: State<S>, args: Vec<String>) -> Self::Future {
+    fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
+        Box::pin(async move {
+            let result: CliResult<Response> = self.try_call(state, args).await;
+            result.into_response()
+        })
+    }
+
+    fn try_call(
+        self,
+        _state: State<S>,
+        mut args: Vec<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, CliError>> + Send + 'static>> {
         Box::pin(async move {
-            let mut instance = self.instance.lock().await;
+            check_args(&self.args, &args)?;
 
-            match instance.execute(&args) {
-                Ok(ExecuteResult::Success(output)) => Response::text(output),
-                Ok(ExecuteResult::Error(err)) => Response::error(err.code as i32, err.message),
-                Err(e) => Response::error(101, format!("Plugin execution error: {}", e)),
+            // Plugins can't use the DryRun extractor, so the router's dry-run
+            // flag is handed back to those declaring it as an argument
+            if DryRun::current().0 {
+                args.push(sen::dry_run::DRY_RUN_FLAG.to_string());
             }
+
+            // Streamed chunks go to the router's writer as they arrive; progress
+            // goes to the router's progress display
+            let effects = RouterEffects {
+                emitter: Emitter::current(),
+                progress: Progress::current(),
+            };
+
+            // Plugin error codes share sen's exit-code scheme (1 = user, 101 = system),
+            // so they are passed through unchanged
+            Ok(match &self.backend {
+                Backend::Instance(instance) => {
+                    let mut instance = instance.lock().await;
+                    match instance.execute_streaming(&args, |effect| effects.stream(effect)) {
+                        Ok(result) => result_response(result),
+                        Err(e) => Response::error(101, format!("Plugin execution error: {}", e)),
+                    }
+                }
+                Backend::Registry(registry) => {
+                    match registry
+                        .execute_with_effects(&self.command_name, &args, &effects)
+                        .await
+                    {
+                        Ok(result) => result_response(result),
+                        Err(e @ RegistryError::PermissionDenied { .. }) => {
+                            Response::error(1, e.to_string())
+                        }
+                        Err(e) => Response::error(101, format!("Plugin execution error: {}", e)),
+                    }
+                }
+            })
         })
     }
 
     fn metadata(&self) -> Option<HandlerMetadata> {
         // command_about is already leaked at construction time
-        Some(HandlerMetadata {
-            desc: Some(self.command_about),
-            tier: None,
-            tags: None,
-        })
+        let mut metadata = HandlerMetadata::default();
+        metadata.desc = Some(self.command_about);
+        Some(metadata)
+    }
+
+    fn handles_dry_run(&self) -> bool {
+        self.args
+            .iter()
+            .any(|spec| spec.long.as_deref() == Some("dry-run"))
     }
 }
 
+/// Where a [`WasmHandler`] runs its plugin
+#[derive(Clone)]
+enum Backend {
+    /// An instance owned by the handler
+    Instance(Arc<Mutex<PluginInstance>>),
+    /// A command looked up in a registry on each call
+    Registry(PluginRegistry),
+}
+
+/// Turn a plugin's terminal result into a response
+fn result_response(result: ExecuteResult) -> Response {
+    match result {
+        ExecuteResult::Success(output) => Response::text(output),
+        ExecuteResult::Error(err) => Response::error(err.code as i32, err.message),
+        ExecuteResult::Effect(Effect::InvokePlugin { command, .. }) => Response::error(
+            101,
+            format!(
+                "Plugin tried to invoke '{}', which needs a route created with \
+                 WasmHandler::from_registry",
+                command
+            ),
+        ),
+        ExecuteResult::Effect(effect) => Response::error(
+            101,
+            format!(
+                "Plugin requested effect {} which is not supported by the router bridge",
+                effect.id()
+            ),
+        ),
+    }
+}
+
+/// Effect handler forwarding streamed output to the running command
+struct RouterEffects {
+    emitter: Emitter,
+    progress: Progress,
+}
+
+#[async_trait::async_trait]
+impl EffectHandler for RouterEffects {
+    async fn handle(&self, effect: Effect) -> EffectResult {
+        EffectResult::Error(format!(
+            "Effect {} is not supported by the router bridge",
+            effect.id()
+        ))
+    }
+
+    fn stream(&self, effect: &Effect) {
+        match effect {
+            Effect::Emit(chunk) => self.emitter.emit(chunk),
+            Effect::Progress { done, total } => {
+                let fraction = if *total == 0 {
+                    0.0
+                } else {
+                    *done as f64 / *total as f64
+                };
+                self.progress.set(fraction, format!("{}/{}", done, total));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Check `args` against the declared `specs` before running a plugin
+///
+/// Options that are required or declare a default, value name or possible
+/// values take a value. Other options may be flags, so checks involving them
+/// only fail when no reading of `args` would pass: a required positional
+/// counts as given if the token after such an option could be it, and extra
+/// positionals are reported only if every such option could take a value.
+/// Help requests are left to the plugin.
+fn check_args(specs: &[ArgSpec], args: &[String]) -> Result<(), CliError> {
+    if specs.is_empty() {
+        return Ok(());
+    }
+
+    let mut details = Vec::new();
+    let mut given = HashSet::new();
+    // Positionals if every possible flag is a flag / takes a value
+    let (mut most, mut fewest) = (0, 0);
+    // The option whose value must be the next token
+    let mut awaiting_value: Option<&str> = None;
+    let mut after_possible_flag = false;
+    let mut options_done = false;
+    for arg in args {
+        if !options_done && arg == "--" {
+            options_done = true;
+            after_possible_flag = false;
+            continue;
+        }
+        if !options_done && (arg == "--help" || arg == "-h") {
+            return Ok(());
+        }
+        if awaiting_value.take().is_some() {
+            continue;
+        }
+
+        let option = if options_done {
+            None
+        } else {
+            parse_option(specs, arg)
+        };
+        match option {
+            Some((spec, inline)) => {
+                if let Some(spec) = spec {
+                    given.insert(spec.name.as_str());
+                }
+                let definite = !inline && spec.is_some_and(takes_value);
+                after_possible_flag = !inline && !definite;
+                if definite {
+                    awaiting_value = Some(arg);
+                }
+            }
+            None => {
+                most += 1;
+                if !std::mem::take(&mut after_possible_flag) {
+                    fewest += 1;
+                }
+            }
+        }
+    }
+    if let Some(option) = awaiting_value {
+        details.push(format!("Option '{}' requires a value", option));
+    }
+
+    let positionals: Vec<&ArgSpec> = specs.iter().filter(|a| a.long.is_none()).collect();
+    for (index, spec) in positionals.iter().enumerate() {
+        if spec.required && spec.default_value.is_none() && index >= most {
+            details.push(format!("Missing required argument: {}", spec.name));
+        }
+    }
+    for spec in specs.iter().filter(|a| a.long.is_some()) {
+        if spec.required && spec.default_value.is_none() && !given.contains(spec.name.as_str()) {
+            let long = spec.long.as_deref().unwrap_or_default();
+            details.push(format!("Missing required option: --{}", long));
+        }
+    }
+    if fewest > positionals.len() {
+        details.push(format!(
+            "Expected at most {} positional argument{}, got {}",
+            positionals.len(),
+            if positionals.len() == 1 { "" } else { "s" },
+            fewest
+        ));
+    }
+
+    if details.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::User(UserError::ValidationFailed { details }))
+    }
+}
+
+/// Whether the option `spec` certainly takes a value (flags declare none of these)
+fn takes_value(spec: &ArgSpec) -> bool {
+    spec.required
+        || spec.default_value.is_some()
+        || spec.value_name.is_some()
+        || spec.possible_values.is_some()
+}
+
+/// The spec matching `arg` if it is an option (`None` for an undeclared
+/// one), and whether its value is inline (`--name=value`)
+fn parse_option<'a>(specs: &'a [ArgSpec], arg: &str) -> Option<(Option<&'a ArgSpec>, bool)> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, inline) = match long.split_once('=') {
+            Some((name, _)) => (name, true),
+            None => (long, false),
+        };
+        let spec = specs.iter().find(|a| a.long.as_deref() == Some(name));
+        return Some((spec, inline));
+    }
+
+    let mut chars = arg.strip_prefix('-')?.chars();
+    let (Some(short), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    let spec = specs.iter().find(|a| a.short == Some(short));
+    // An undeclared "-5" is a negative number, not an option
+    (spec.is_some() || !short.is_ascii_digit()).then_some((spec, false))
+}
+
 /// Extension trait for Router to integrate plugins
 pub trait RouterPluginExt<S>
 where
@@ -144,6 +416,24 @@ where
     router
 }
 
+/// Register every command of `registry`, as of now, on `router`
+///
+/// Routes use [`WasmHandler::from_registry`], so the plugins can invoke each
+/// other. Commands loaded into the registry later are not added.
+pub async fn register_registry_plugins<S>(
+    mut router: sen::Router<S>,
+    registry: &PluginRegistry,
+) -> sen::Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    for manifest in registry.get_all_manifests().await {
+        let handler = WasmHandler::from_registry(registry, &manifest);
+        router = router.route(manifest.command.name.clone(), handler);
+    }
+    router
+}
+
 /// Generate help text for a plugin command
 pub fn generate_plugin_help(spec: &CommandSpec) -> String {
     let mut help = format!("{}\n\n", spec.about);
@@ -193,4 +483,46 @@ mod tests {
         assert!(help.contains("Name to greet"));
         assert!(help.contains("[default: World]"));
     }
+
+    #[test]
+    fn test_check_args_allows_flags_and_values() {
+        let specs = vec![
+            ArgSpec::positional("file").required(),
+            ArgSpec::option("verbose", "verbose").short('v'),
+            ArgSpec::option("count", "count").default("1"),
+        ];
+        let check = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            check_args(&specs, &args)
+        };
+
+        assert!(check(&["a.txt"]).is_ok());
+        // `--verbose` may be a flag, so `a.txt` may be the file
+        assert!(check(&["--verbose", "a.txt"]).is_ok());
+        assert!(check(&["-v", "a.txt", "--count", "3"]).is_ok());
+        assert!(check(&["--", "-5"]).is_ok());
+        assert!(check(&["-5"]).is_ok());
+        assert!(check(&["--help"]).is_ok());
+
+        // `--count` always takes a value, so `a.txt` is not the file
+        let missing = |result: Result<(), CliError>| match result {
+            Err(CliError::User(UserError::ValidationFailed { details })) => details,
+            other => panic!("Expected a validation error, got {:?}", other),
+        };
+        assert_eq!(
+            missing(check(&["--count", "a.txt"])),
+            ["Missing required argument: file"]
+        );
+        assert_eq!(
+            missing(check(&["a.txt", "--count"])),
+            ["Option '--count' requires a value"]
+        );
+        assert_eq!(
+            missing(check(&["a.txt", "b.txt"])),
+            ["Expected at most 1 positional argument, got 2"]
+        );
+
+        // Without declared args nothing is checked
+        assert!(check_args(&[], &["anything".to_string()]).is_ok());
+    }
 }
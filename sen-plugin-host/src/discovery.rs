@@ -2,6 +2,7 @@
 //!
 //! Automatically discovers and loads plugins from filesystem directories.
 
+use crate::loader::is_plugin_file;
 use crate::{LoadedPlugin, LoaderError, PluginLoader};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -62,7 +63,9 @@ impl PluginScanner {
         Self { loader }
     }
 
-    /// Scan a directory for .wasm plugin files
+    /// Scan a directory for plugin files
+    ///
+    /// Picks up `*.wasm`, and `*.wasm.gz` with the `compression` feature.
     pub fn scan_directory(&self, dir: impl AsRef<Path>) -> Result<DiscoveryResult, DiscoveryError> {
         let dir = dir.as_ref();
 
@@ -90,8 +93,8 @@ impl PluginScanner {
 
             let path = entry.path();
 
-            // Only process .wasm files
-            if path.extension().map(|e| e == "wasm").unwrap_or(false) {
+            // Only process plugin files
+            if is_plugin_file(&path) {
                 match self.load_plugin(&path) {
                     Ok(plugin) => plugins.push(plugin),
                     Err(e) => failures.push((path, e)),
@@ -133,13 +136,8 @@ impl PluginScanner {
 
     /// Load a single plugin file
     fn load_plugin(&self, path: &Path) -> Result<LoadedPlugin, DiscoveryError> {
-        let wasm_bytes = std::fs::read(path).map_err(|e| DiscoveryError::LoadPlugin {
-            path: path.to_path_buf(),
-            source: LoaderError::MemoryAccess(format!("Failed to read file: {}", e)),
-        })?;
-
         self.loader
-            .load(&wasm_bytes)
+            .load_path(path)
             .map_err(|e| DiscoveryError::LoadPlugin {
                 path: path.to_path_buf(),
                 source: e,
@@ -210,6 +208,69 @@ mod tests {
         assert_eq!(result.plugins[0].manifest.command.name, "hello");
     }
 
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_gzipped_plugin_loads_and_executes() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let temp = TempDir::new().unwrap();
+        let wasm_bytes = include_bytes!(
+            "../../examples/hello-plugin/target/wasm32-unknown-unknown/release/hello_plugin.wasm"
+        );
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(wasm_bytes).unwrap();
+        let plugin_path = temp.path().join("hello.wasm.gz");
+        fs::write(&plugin_path, encoder.finish().unwrap()).unwrap();
+
+        let mut plugin = PluginLoader::new()
+            .unwrap()
+            .load_path(&plugin_path)
+            .unwrap();
+        let result = plugin.instance.execute(&["World".to_string()]).unwrap();
+        assert!(
+            matches!(result, sen_plugin_api::ExecuteResult::Success(ref s) if s.contains("World")),
+            "unexpected result: {:?}",
+            result
+        );
+
+        let result = PluginScanner::new()
+            .unwrap()
+            .scan_directory(temp.path())
+            .unwrap();
+        assert_eq!(result.plugins.len(), 1);
+        assert_eq!(result.plugins[0].manifest.command.name, "hello");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_gzip_bomb_is_rejected() {
+        use crate::LoaderConfig;
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let temp = TempDir::new().unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0u8; 1024 * 1024]).unwrap();
+        let plugin_path = temp.path().join("bomb.wasm.gz");
+        fs::write(&plugin_path, encoder.finish().unwrap()).unwrap();
+
+        let result = PluginLoader::with_config(LoaderConfig {
+            max_decompressed_bytes: 64 * 1024,
+            ..Default::default()
+        })
+        .unwrap()
+        .load_path(&plugin_path);
+        assert!(
+            matches!(
+                result,
+                Err(LoaderError::DecompressedTooLarge { limit, .. }) if limit == 64 * 1024
+            ),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
     #[test]
     fn test_scan_ignores_non_wasm_files() {
         let temp = TempDir::new().unwrap();
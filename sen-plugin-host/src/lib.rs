@@ -36,7 +36,10 @@
 //! - **Plugin Discovery**: Scan directories for plugin files
 //! - **CPU Limits**: Prevent infinite loops with fuel-based execution limits
 //! - **Stack Limits**: Prevent stack overflow attacks (1MB limit)
+//! - **Validation**: `PluginLoader::validate` reports missing exports, API
+//!   mismatches and malformed manifests without running the plugin
 //! - `sen-integration`: Enable integration with sen-rs Router (adds `bridge` module)
+//! - `testing`: WAT plugin fixtures for tests (adds `testing` module)
 //!
 //! # Security
 //!
@@ -44,7 +47,7 @@
 //!
 //! | Protection | Description |
 //! |------------|-------------|
-//! | CPU Limit | 10M instructions per execution (fuel) |
+//! | CPU Limit | 10M instructions per execution (fuel, configurable via `LoaderConfig`) |
 //! | Stack Limit | 1MB maximum WASM stack |
 //! | Memory Isolation | Each plugin has isolated linear memory |
 //! | No System Access | No filesystem, network, or OS access |
@@ -199,6 +202,7 @@ pub mod discovery;
 pub mod loader;
 pub mod permission;
 pub mod registry;
+pub mod validate;
 pub mod watcher;
 
 #[cfg(feature = "wasi")]
@@ -207,17 +211,28 @@ pub mod wasi;
 #[cfg(feature = "sen-integration")]
 pub mod bridge;
 
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 pub use discovery::{default_plugin_dirs, DiscoveryError, DiscoveryResult, PluginScanner};
-pub use loader::{EffectHandler, LoadedPlugin, LoaderError, PluginInstance, PluginLoader};
-pub use registry::{PluginRegistry, RegistryError};
+pub use loader::{
+    is_plugin_file, EffectHandler, FuelExhaustedHook, LoadedPlugin, LoaderConfig, LoaderError,
+    PluginInstance, PluginLoader, PluginStats, WasmFeatures, DEFAULT_MAX_DECOMPRESSED_BYTES,
+    DEFAULT_MAX_PAYLOAD_BYTES,
+};
+pub use registry::{PipelineInput, PluginRegistry, RegistryError, RegistryEvent, MAX_INVOKE_DEPTH};
 pub use sen_plugin_api::{
     ArgSpec, Capabilities, CommandSpec, Effect, EffectResult, ExecuteError, ExecuteResult,
     HttpResponse, NetPattern, PathPattern, PluginManifest, StdioCapability,
 };
-pub use watcher::{HotReloadWatcher, WatcherConfig, WatcherError};
+pub use validate::{Severity, ValidationIssue, ValidationReport};
+pub use watcher::{HotReloadWatcher, WatchSource, WatcherConfig, WatcherError};
 
 // Permission system re-exports
 pub use permission::{PermissionConfig, PermissionConfigBuilder, PermissionPresets};
 
 #[cfg(feature = "sen-integration")]
-pub use bridge::{generate_plugin_help, register_plugins_from_spec, RouterPluginExt, WasmHandler};
+pub use bridge::{
+    generate_plugin_help, register_plugins_from_spec, register_registry_plugins, RouterPluginExt,
+    WasmHandler,
+};
@@ -2,7 +2,13 @@
 //!
 //! Loads Wasm plugins and provides safe execution with sandboxing.
 
-use sen_plugin_api::{Effect, EffectResult, ExecuteResult, PluginManifest, API_VERSION};
+use sen_plugin_api::{
+    Effect, EffectResult, ExecuteResult, PluginManifest, API_VERSION, MIN_SUPPORTED_API_VERSION,
+};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
 use thiserror::Error;
 use wasmtime::*;
 
@@ -15,6 +21,14 @@ pub enum LoaderError {
     #[error("Module compilation failed: {0}")]
     ModuleCompilation(#[source] anyhow::Error),
 
+    #[error("Plugin uses the Wasm {feature} feature, which is disabled on this host")]
+    UnsupportedFeature {
+        /// "simd", "bulk_memory", "reference_types", or "threads"
+        feature: &'static str,
+        #[source]
+        source: anyhow::Error,
+    },
+
     #[error("Instantiation failed: {0}")]
     Instantiation(#[source] anyhow::Error),
 
@@ -28,7 +42,9 @@ pub enum LoaderError {
         source: anyhow::Error,
     },
 
-    #[error("API version mismatch: expected {expected}, got {actual}")]
+    #[error(
+        "API version mismatch: expected {MIN_SUPPORTED_API_VERSION}..={expected}, got {actual}"
+    )]
     ApiVersionMismatch { expected: u32, actual: u32 },
 
     #[error("Deserialization failed: {0}")]
@@ -42,11 +58,249 @@ pub enum LoaderError {
 
     #[error("Store configuration failed: {0}")]
     StoreConfig(String),
+
+    #[error("Failed to read plugin file {path}: {source}")]
+    ReadFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Plugin {kind} is {len} bytes, over the {limit} byte limit")]
+    PayloadTooLarge {
+        /// What was being passed: "manifest", "result", "arguments", ...
+        kind: &'static str,
+        len: usize,
+        limit: usize,
+    },
+
+    #[error("Plugin file {path} decompresses to more than {limit} bytes")]
+    DecompressedTooLarge { path: PathBuf, limit: usize },
+}
+
+/// Default for [`LoaderConfig::max_payload_bytes`]: 16 MiB
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+/// Default for [`LoaderConfig::max_decompressed_bytes`]: 64 MiB
+pub const DEFAULT_MAX_DECOMPRESSED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Reject a host/guest payload of `len` bytes if it is over `limit`
+pub(crate) fn check_payload(
+    kind: &'static str,
+    len: usize,
+    limit: usize,
+) -> Result<(), LoaderError> {
+    if len > limit {
+        return Err(LoaderError::PayloadTooLarge { kind, len, limit });
+    }
+    Ok(())
+}
+
+/// Whether `path` names a plugin file: `*.wasm`, or `*.wasm.gz` with the
+/// `compression` feature
+pub fn is_plugin_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.ends_with(".wasm") || (cfg!(feature = "compression") && name.ends_with(".wasm.gz"))
+}
+
+/// Plugin bytes read from `path`, gunzipped if it ends in `.gz` (with the
+/// `compression` feature)
+///
+/// Decompression stops after `limit` bytes, so a gzip bomb fails with
+/// [`LoaderError::DecompressedTooLarge`] instead of exhausting memory.
+pub(crate) fn decode_plugin_file(
+    path: &Path,
+    bytes: Vec<u8>,
+    limit: usize,
+) -> Result<Vec<u8>, LoaderError> {
+    #[cfg(feature = "compression")]
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        use std::io::Read;
+
+        let mut wasm = Vec::new();
+        flate2::read::GzDecoder::new(bytes.as_slice())
+            .take(limit as u64 + 1)
+            .read_to_end(&mut wasm)
+            .map_err(|source| LoaderError::ReadFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        if wasm.len() > limit {
+            return Err(LoaderError::DecompressedTooLarge {
+                path: path.to_path_buf(),
+                limit,
+            });
+        }
+        return Ok(wasm);
+    }
+
+    #[cfg(not(feature = "compression"))]
+    let _ = (path, limit);
+    Ok(bytes)
+}
+
+/// Hook consulted when a plugin call runs out of fuel
+///
+/// Returns `true` to grant another `fuel_limit` worth of fuel.
+pub type FuelExhaustedHook = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Wasm proposals plugins may use
+///
+/// All are enabled by default, leaving wasmtime's own defaults in place.
+/// Disable one to run on hosts that don't support it; plugins using a
+/// disabled feature fail to load with [`LoaderError::UnsupportedFeature`].
+/// `reference_types` requires `bulk_memory`, so disable both together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmFeatures {
+    /// 128-bit SIMD (and relaxed SIMD)
+    pub simd: bool,
+
+    /// Bulk memory operations (`memory.copy`, `memory.fill`, ...)
+    pub bulk_memory: bool,
+
+    /// Reference types (`externref`, multiple tables)
+    pub reference_types: bool,
+
+    /// Threads and atomics (shared memories)
+    pub threads: bool,
+}
+
+impl Default for WasmFeatures {
+    fn default() -> Self {
+        Self {
+            simd: true,
+            bulk_memory: true,
+            reference_types: true,
+            threads: true,
+        }
+    }
+}
+
+impl WasmFeatures {
+    /// Turn off the disabled features in an engine config
+    ///
+    /// Enabled features are left untouched, so the default changes nothing.
+    pub(crate) fn apply(&self, config: &mut Config) {
+        if !self.simd {
+            config.wasm_simd(false);
+            config.wasm_relaxed_simd(false);
+        }
+        if !self.bulk_memory {
+            config.wasm_bulk_memory(false);
+        }
+        if !self.reference_types {
+            config.wasm_reference_types(false);
+        }
+        if !self.threads {
+            config.wasm_threads(false);
+        }
+    }
+
+    /// Map a compilation error caused by a disabled feature to
+    /// [`LoaderError::UnsupportedFeature`]
+    pub(crate) fn compile_error(&self, error: anyhow::Error) -> LoaderError {
+        let message = format!("{:#}", error);
+        let disabled = [
+            (self.simd, "simd", "SIMD support is not enabled"),
+            (self.bulk_memory, "bulk_memory", "bulk memory"),
+            (
+                self.reference_types,
+                "reference_types",
+                "reference types support is not enabled",
+            ),
+            (self.threads, "threads", "threads"),
+        ];
+        for (enabled, feature, marker) in disabled {
+            if !enabled && message.contains(marker) {
+                return LoaderError::UnsupportedFeature {
+                    feature,
+                    source: error,
+                };
+            }
+        }
+        LoaderError::ModuleCompilation(error)
+    }
+}
+
+/// Configuration for the plugin loader
+#[derive(Clone)]
+pub struct LoaderConfig {
+    /// Fuel limit per execution (CPU limit)
+    pub fuel_limit: u64,
+
+    /// Maximum WASM stack size in bytes
+    pub max_stack_size: usize,
+
+    /// Largest manifest, result, or argument payload passed between host
+    /// and plugin, in bytes
+    ///
+    /// Payloads over the limit fail with [`LoaderError::PayloadTooLarge`]
+    /// before the host copies or decodes them.
+    pub max_payload_bytes: usize,
+
+    /// Largest plugin a `.wasm.gz` file may decompress to, in bytes
+    ///
+    /// Larger files fail with [`LoaderError::DecompressedTooLarge`].
+    pub max_decompressed_bytes: usize,
+
+    /// Called when `execute`/`resume` runs out of fuel
+    ///
+    /// The call is paused, not aborted: if the hook returns `true`, it gets
+    /// another `fuel_limit` and continues where it stopped. If the hook is
+    /// unset or returns `false`, the call is cancelled and
+    /// [`LoaderError::FuelExhausted`] is returned.
+    ///
+    /// Typical use is an interactive "continue?" prompt:
+    ///
+    /// ```rust,ignore
+    /// let config = LoaderConfig {
+    ///     on_fuel_exhausted: Some(Arc::new(|| {
+    ///         eprint!("Plugin is still running. Continue? [y/N] ");
+    ///         let mut answer = String::new();
+    ///         std::io::stdin().read_line(&mut answer).is_ok()
+    ///             && answer.trim().eq_ignore_ascii_case("y")
+    ///     })),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    pub on_fuel_exhausted: Option<FuelExhaustedHook>,
+
+    /// Wasm proposals plugins may use (all enabled by default)
+    pub wasm_features: WasmFeatures,
+}
+
+impl Default for LoaderConfig {
+    fn default() -> Self {
+        Self {
+            fuel_limit: 10_000_000,
+            max_stack_size: 1024 * 1024, // 1MB
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            max_decompressed_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+            on_fuel_exhausted: None,
+            wasm_features: WasmFeatures::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for LoaderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoaderConfig")
+            .field("fuel_limit", &self.fuel_limit)
+            .field("max_stack_size", &self.max_stack_size)
+            .field("max_payload_bytes", &self.max_payload_bytes)
+            .field("max_decompressed_bytes", &self.max_decompressed_bytes)
+            .field("on_fuel_exhausted", &self.on_fuel_exhausted.is_some())
+            .field("wasm_features", &self.wasm_features)
+            .finish()
+    }
 }
 
 /// Plugin loader with wasmtime engine
 pub struct PluginLoader {
     engine: Engine,
+    config: LoaderConfig,
 }
 
 /// A loaded plugin ready for execution
@@ -59,12 +313,97 @@ pub struct LoadedPlugin {
 }
 
 /// Plugin instance that can execute commands
+///
+/// Dropping the instance frees its wasmtime store without running guest
+/// code; call [`PluginInstance::shutdown`] first to run the plugin's
+/// optional `plugin_shutdown` export.
 pub struct PluginInstance {
     store: Store<()>,
     instance: Instance,
     memory: Memory,
     alloc_fn: TypedFunc<i32, i32>,
     dealloc_fn: TypedFunc<(i32, i32), ()>,
+    /// `plugin_shutdown`, until it has been called
+    shutdown_fn: Option<TypedFunc<(), ()>>,
+    config: LoaderConfig,
+    fuel_consumed: u64,
+}
+
+/// Resource usage of a plugin call
+///
+/// Use it to tune [`LoaderConfig::fuel_limit`] and memory limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginStats {
+    /// Fuel burned by `plugin_execute` and any `plugin_resume` calls since,
+    /// including fuel granted by `on_fuel_exhausted`
+    pub fuel_consumed: u64,
+
+    /// Size of the plugin's linear memory after the call
+    ///
+    /// Wasm memory only grows, so this is the peak over the instance's
+    /// lifetime so far.
+    pub memory_peak_bytes: usize,
+
+    /// Captured stdout exceeded its limit and was truncated
+    ///
+    /// Only WASI plugins have stdio; see
+    /// [`WasiLoaderConfig::max_stdout_bytes`](crate::wasi::WasiLoaderConfig::max_stdout_bytes).
+    pub stdout_truncated: bool,
+
+    /// Captured stderr exceeded its limit and was truncated
+    pub stderr_truncated: bool,
+}
+
+/// Fiber stack beyond the Wasm stack, for host frames
+const ASYNC_STACK_HEADROOM: usize = 1024 * 1024;
+
+/// Fuel set for a metered call; only its consumption is measured
+const METERED_FUEL_RESERVE: u64 = u64::MAX / 2;
+
+/// Poll a wasmtime call on the current thread until it finishes
+///
+/// The loader's engine is asynchronous only so that fuel exhaustion pauses a
+/// call: no host function awaits, so the call is pending exactly when it has
+/// used up another fuel interval. `on_fuel_exhausted` decides whether it
+/// continues; if it returns `false` the call is dropped (which cancels it)
+/// and an out-of-fuel trap is returned.
+fn drive<R>(
+    future: impl Future<Output = wasmtime::Result<R>>,
+    mut on_fuel_exhausted: impl FnMut() -> bool,
+) -> wasmtime::Result<R> {
+    let mut future = std::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(result) => return result,
+            Poll::Pending if on_fuel_exhausted() => {}
+            Poll::Pending => return Err(Trap::OutOfFuel.into()),
+        }
+    }
+}
+
+/// [`drive`] without granting extra fuel
+fn run_to_completion<R>(future: impl Future<Output = wasmtime::Result<R>>) -> wasmtime::Result<R> {
+    drive(future, || false)
+}
+
+/// Call a guest function on whatever fuel the store has left
+fn call_unmetered<Params, Results>(
+    func: &TypedFunc<Params, Results>,
+    store: &mut Store<()>,
+    params: Params,
+) -> wasmtime::Result<Results>
+where
+    Params: WasmParams,
+    Results: WasmResults,
+{
+    run_to_completion(func.call_async(store, params))
+}
+
+fn is_out_of_fuel(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<Trap>()
+        .is_some_and(|t| *t == Trap::OutOfFuel)
 }
 
 /// Unpack ptr and len from a packed i64
@@ -83,40 +422,81 @@ impl PluginLoader {
     /// - Stack limits - 1MB maximum WASM stack
     /// - Memory64 disabled for wasm32 compatibility
     pub fn new() -> Result<Self, LoaderError> {
+        Self::with_config(LoaderConfig::default())
+    }
+
+    /// Create a new plugin loader with custom limits
+    pub fn with_config(loader_config: LoaderConfig) -> Result<Self, LoaderError> {
         let mut config = Config::new();
 
         // Security: Enable fuel for CPU limiting
         config.consume_fuel(true);
 
-        // Security: Limit WASM stack size (1MB) to prevent stack overflow
-        config.max_wasm_stack(1024 * 1024);
+        // Calls run on a fiber so that running out of fuel pauses them (see
+        // `call_metered`); nothing else is asynchronous
+        config.async_support(true);
+        config.async_stack_size(loader_config.max_stack_size + ASYNC_STACK_HEADROOM);
+
+        // Security: Limit WASM stack size to prevent stack overflow
+        config.max_wasm_stack(loader_config.max_stack_size);
 
         // Disable memory64 for wasm32 compatibility
         config.wasm_memory64(false);
 
+        loader_config.wasm_features.apply(&mut config);
+
         let engine = Engine::new(&config).map_err(LoaderError::EngineCreation)?;
 
-        Ok(Self { engine })
+        Ok(Self {
+            engine,
+            config: loader_config,
+        })
+    }
+
+    /// Get the loader configuration
+    pub fn config(&self) -> &LoaderConfig {
+        &self.config
+    }
+
+    pub(crate) fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Load a plugin file
+    ///
+    /// With the `compression` feature, files ending in `.gz` are
+    /// decompressed first, so `hello.wasm.gz` loads like `hello.wasm`.
+    pub fn load_path(&self, path: impl AsRef<Path>) -> Result<LoadedPlugin, LoaderError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| LoaderError::ReadFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.load(&decode_plugin_file(
+            path,
+            bytes,
+            self.config.max_decompressed_bytes,
+        )?)
     }
 
     /// Load a plugin from Wasm bytes
     pub fn load(&self, wasm_bytes: &[u8]) -> Result<LoadedPlugin, LoaderError> {
         // 1. Compile module
-        let module =
-            Module::new(&self.engine, wasm_bytes).map_err(LoaderError::ModuleCompilation)?;
+        let module = Module::new(&self.engine, wasm_bytes)
+            .map_err(|e| self.config.wasm_features.compile_error(e))?;
 
         // 2. Create store with fuel limit (no WASI for MVP)
         let mut store = Store::new(&self.engine, ());
         store
-            .set_fuel(10_000_000)
+            .set_fuel(self.config.fuel_limit)
+            .and_then(|()| store.fuel_async_yield_interval(Some(self.config.fuel_limit)))
             .map_err(|e| LoaderError::StoreConfig(format!("Failed to set fuel: {}", e)))?;
 
         // 3. Create linker (empty for now, no WASI imports)
         let linker = Linker::new(&self.engine);
 
         // 4. Instantiate
-        let instance = linker
-            .instantiate(&mut store, &module)
+        let instance = run_to_completion(linker.instantiate_async(&mut store, &module))
             .map_err(LoaderError::Instantiation)?;
 
         // 5. Get memory
@@ -138,10 +518,8 @@ impl PluginLoader {
             .get_typed_func::<(), i64>(&mut store, "plugin_manifest")
             .map_err(|_| LoaderError::FunctionNotFound("plugin_manifest".to_string()))?;
 
-        let packed = manifest_fn.call(&mut store, ()).map_err(|e| {
-            if e.downcast_ref::<Trap>()
-                .is_some_and(|t| *t == Trap::OutOfFuel)
-            {
+        let packed = call_unmetered(&manifest_fn, &mut store, ()).map_err(|e| {
+            if is_out_of_fuel(&e) {
                 LoaderError::FuelExhausted
             } else {
                 LoaderError::FunctionCall {
@@ -162,12 +540,13 @@ impl PluginLoader {
         }
 
         // 8. Read manifest from memory
+        check_payload("manifest", len as usize, self.config.max_payload_bytes)?;
         let manifest_bytes = Self::read_memory(&store, &memory, ptr as usize, len as usize)?;
         let manifest: PluginManifest =
             rmp_serde::from_slice(&manifest_bytes).map_err(LoaderError::Deserialization)?;
 
         // 9. Validate API version
-        if manifest.api_version != API_VERSION {
+        if !(MIN_SUPPORTED_API_VERSION..=API_VERSION).contains(&manifest.api_version) {
             return Err(LoaderError::ApiVersionMismatch {
                 expected: API_VERSION,
                 actual: manifest.api_version,
@@ -175,12 +554,17 @@ impl PluginLoader {
         }
 
         // 10. Deallocate manifest memory
-        dealloc_fn
-            .call(&mut store, (ptr, len))
-            .map_err(|e| LoaderError::FunctionCall {
+        call_unmetered(&dealloc_fn, &mut store, (ptr, len)).map_err(|e| {
+            LoaderError::FunctionCall {
                 function: "plugin_dealloc",
                 source: e,
-            })?;
+            }
+        })?;
+
+        // 11. Optional teardown hook
+        let shutdown_fn = instance
+            .get_typed_func::<(), ()>(&mut store, "plugin_shutdown")
+            .ok();
 
         Ok(LoadedPlugin {
             manifest,
@@ -190,6 +574,9 @@ impl PluginLoader {
                 memory,
                 alloc_fn,
                 dealloc_fn,
+                shutdown_fn,
+                config: self.config.clone(),
+                fuel_consumed: 0,
             },
         })
     }
@@ -219,9 +606,13 @@ impl PluginLoader {
 impl PluginInstance {
     /// Execute the plugin with given arguments
     pub fn execute(&mut self, args: &[String]) -> Result<ExecuteResult, LoaderError> {
+        // A new call starts a new stats window
+        self.fuel_consumed = 0;
+
         // 1. Serialize arguments
         let args_bytes = rmp_serde::to_vec(args)
             .map_err(|e| LoaderError::MemoryAccess(format!("Failed to serialize args: {}", e)))?;
+        check_payload("arguments", args_bytes.len(), self.config.max_payload_bytes)?;
 
         // 2. Allocate memory in guest
         let args_len: i32 = args_bytes.len().try_into().map_err(|_| {
@@ -230,7 +621,7 @@ impl PluginInstance {
                 args_bytes.len()
             ))
         })?;
-        let args_ptr = self.alloc_fn.call(&mut self.store, args_len).map_err(|e| {
+        let args_ptr = call_unmetered(&self.alloc_fn, &mut self.store, args_len).map_err(|e| {
             LoaderError::FunctionCall {
                 function: "plugin_alloc",
                 source: e,
@@ -248,25 +639,7 @@ impl PluginInstance {
             .get_typed_func::<(i32, i32), i64>(&mut self.store, "plugin_execute")
             .map_err(|_| LoaderError::FunctionNotFound("plugin_execute".to_string()))?;
 
-        // Reset fuel for execution
-        self.store
-            .set_fuel(10_000_000)
-            .map_err(|e| LoaderError::StoreConfig(format!("Failed to reset fuel: {}", e)))?;
-
-        let packed = execute_fn
-            .call(&mut self.store, (args_ptr, args_len))
-            .map_err(|e| {
-                if e.downcast_ref::<Trap>()
-                    .is_some_and(|t| *t == Trap::OutOfFuel)
-                {
-                    LoaderError::FuelExhausted
-                } else {
-                    LoaderError::FunctionCall {
-                        function: "plugin_execute",
-                        source: e,
-                    }
-                }
-            })?;
+        let packed = self.call_metered(&execute_fn, (args_ptr, args_len), "plugin_execute")?;
 
         let (result_ptr, result_len) = unpack_ptr_len(packed);
 
@@ -278,27 +651,16 @@ impl PluginInstance {
             )));
         }
 
-        // 5. Read result from memory
-        let result_bytes = PluginLoader::read_memory(
-            &self.store,
-            &self.memory,
-            result_ptr as usize,
-            result_len as usize,
-        )?;
+        // 5. Read result from memory, then free both buffers whatever the
+        // outcome so a rejected result does not leak guest memory
+        let limit = self.config.max_payload_bytes;
+        let result_bytes = check_payload("result", result_len as usize, limit)
+            .and_then(|()| self.read_guest(result_ptr, result_len));
+        self.dealloc(args_ptr, args_len, "args");
+        self.dealloc(result_ptr, result_len, "result");
 
         let result: ExecuteResult =
-            rmp_serde::from_slice(&result_bytes).map_err(LoaderError::Deserialization)?;
-
-        // 6. Deallocate args and result memory
-        if let Err(e) = self.dealloc_fn.call(&mut self.store, (args_ptr, args_len)) {
-            tracing::warn!(error = %e, ptr = args_ptr, len = args_len, "Failed to deallocate args memory in plugin");
-        }
-        if let Err(e) = self
-            .dealloc_fn
-            .call(&mut self.store, (result_ptr, result_len))
-        {
-            tracing::warn!(error = %e, ptr = result_ptr, len = result_len, "Failed to deallocate result memory in plugin");
-        }
+            rmp_serde::from_slice(&result_bytes?).map_err(LoaderError::Deserialization)?;
 
         Ok(result)
     }
@@ -320,6 +682,11 @@ impl PluginInstance {
         let result_bytes = rmp_serde::to_vec_named(result).map_err(|e| {
             LoaderError::MemoryAccess(format!("Failed to serialize effect result: {}", e))
         })?;
+        check_payload(
+            "effect result",
+            result_bytes.len(),
+            self.config.max_payload_bytes,
+        )?;
 
         // 2. Allocate memory in guest
         let result_len: i32 = result_bytes.len().try_into().map_err(|_| {
@@ -328,12 +695,12 @@ impl PluginInstance {
                 result_bytes.len()
             ))
         })?;
-        let result_ptr = self
-            .alloc_fn
-            .call(&mut self.store, result_len)
-            .map_err(|e| LoaderError::FunctionCall {
-                function: "plugin_alloc",
-                source: e,
+        let result_ptr =
+            call_unmetered(&self.alloc_fn, &mut self.store, result_len).map_err(|e| {
+                LoaderError::FunctionCall {
+                    function: "plugin_alloc",
+                    source: e,
+                }
             })?;
 
         // 3. Write result to guest memory
@@ -349,25 +716,11 @@ impl PluginInstance {
             .get_typed_func::<(u32, i32, i32), i64>(&mut self.store, "plugin_resume")
             .map_err(|_| LoaderError::FunctionNotFound("plugin_resume".to_string()))?;
 
-        // Reset fuel for execution
-        self.store
-            .set_fuel(10_000_000)
-            .map_err(|e| LoaderError::StoreConfig(format!("Failed to reset fuel: {}", e)))?;
-
-        let packed = resume_fn
-            .call(&mut self.store, (effect_id, result_ptr, result_len))
-            .map_err(|e| {
-                if e.downcast_ref::<Trap>()
-                    .is_some_and(|t| *t == Trap::OutOfFuel)
-                {
-                    LoaderError::FuelExhausted
-                } else {
-                    LoaderError::FunctionCall {
-                        function: "plugin_resume",
-                        source: e,
-                    }
-                }
-            })?;
+        let packed = self.call_metered(
+            &resume_fn,
+            (effect_id, result_ptr, result_len),
+            "plugin_resume",
+        )?;
 
         let (exec_result_ptr, exec_result_len) = unpack_ptr_len(packed);
 
@@ -379,32 +732,163 @@ impl PluginInstance {
             )));
         }
 
-        // 5. Read result from memory
-        let exec_result_bytes = PluginLoader::read_memory(
-            &self.store,
-            &self.memory,
-            exec_result_ptr as usize,
-            exec_result_len as usize,
-        )?;
+        // 5. Read result from memory, then free both buffers whatever the
+        // outcome
+        let limit = self.config.max_payload_bytes;
+        let exec_result_bytes = check_payload("result", exec_result_len as usize, limit)
+            .and_then(|()| self.read_guest(exec_result_ptr, exec_result_len));
+        self.dealloc(result_ptr, result_len, "effect result");
+        self.dealloc(exec_result_ptr, exec_result_len, "resume result");
 
         let exec_result: ExecuteResult =
-            rmp_serde::from_slice(&exec_result_bytes).map_err(LoaderError::Deserialization)?;
-
-        // 6. Deallocate memory
-        if let Err(e) = self
-            .dealloc_fn
-            .call(&mut self.store, (result_ptr, result_len))
-        {
-            tracing::warn!(error = %e, ptr = result_ptr, len = result_len, "Failed to deallocate effect result memory");
+            rmp_serde::from_slice(&exec_result_bytes?).map_err(LoaderError::Deserialization)?;
+
+        Ok(exec_result)
+    }
+
+    /// Copy a guest buffer out of linear memory
+    fn read_guest(&self, ptr: i32, len: i32) -> Result<Vec<u8>, LoaderError> {
+        PluginLoader::read_memory(&self.store, &self.memory, ptr as usize, len as usize)
+    }
+
+    /// Free a guest buffer, logging failures
+    fn dealloc(&mut self, ptr: i32, len: i32, what: &'static str) {
+        if let Err(e) = call_unmetered(&self.dealloc_fn, &mut self.store, (ptr, len)) {
+            tracing::warn!(error = %e, ptr, len, "Failed to deallocate {} memory in plugin", what);
         }
-        if let Err(e) = self
-            .dealloc_fn
-            .call(&mut self.store, (exec_result_ptr, exec_result_len))
-        {
-            tracing::warn!(error = %e, ptr = exec_result_ptr, len = exec_result_len, "Failed to deallocate resume result memory");
+    }
+
+    /// Execute the plugin and report the resources the call used
+    pub fn execute_with_stats(
+        &mut self,
+        args: &[String],
+    ) -> Result<(ExecuteResult, PluginStats), LoaderError> {
+        let result = self.execute(args)?;
+        Ok((result, self.stats()))
+    }
+
+    /// Resource usage of the most recent call
+    ///
+    /// Covers the last `execute` and every `resume` since, so after an effect
+    /// loop it reports the whole command.
+    pub fn stats(&self) -> PluginStats {
+        PluginStats {
+            fuel_consumed: self.fuel_consumed,
+            memory_peak_bytes: self.memory.data_size(&self.store),
+            stdout_truncated: false,
+            stderr_truncated: false,
         }
+    }
 
-        Ok(exec_result)
+    /// Execute the plugin, delivering streaming effects to `on_stream`
+    ///
+    /// `Effect::Emit` and `Effect::Progress` are passed to `on_stream` and the
+    /// plugin is resumed with `EffectResult::Emitted`, until it returns a
+    /// terminal result or requests any other effect.
+    pub fn execute_streaming(
+        &mut self,
+        args: &[String],
+        mut on_stream: impl FnMut(&Effect),
+    ) -> Result<ExecuteResult, LoaderError> {
+        let result = self.execute(args)?;
+        self.drain_stream(result, &mut on_stream)
+    }
+
+    /// Execute the plugin and drive its effect loop to completion
+    ///
+    /// Streaming effects go to `EffectHandler::stream`; all other effects are
+    /// performed by `EffectHandler::handle` and the plugin is resumed with the
+    /// result. Returns the plugin's terminal `Success` or `Error`.
+    pub async fn execute_with_effects(
+        &mut self,
+        args: &[String],
+        handler: &dyn EffectHandler,
+    ) -> Result<ExecuteResult, LoaderError> {
+        let mut on_stream = |effect: &Effect| handler.stream(effect);
+        let mut result = self.execute_streaming(args, &mut on_stream)?;
+
+        while let ExecuteResult::Effect(effect) = result {
+            let effect_id = effect.id();
+            let effect_result = handler.handle(effect).await;
+            let next = self.resume(effect_id, &effect_result)?;
+            result = self.drain_stream(next, &mut on_stream)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Resume through streaming effects until a non-streaming result
+    pub(crate) fn drain_stream(
+        &mut self,
+        mut result: ExecuteResult,
+        on_stream: &mut dyn FnMut(&Effect),
+    ) -> Result<ExecuteResult, LoaderError> {
+        loop {
+            match result {
+                ExecuteResult::Effect(effect) if effect.is_stream() => {
+                    on_stream(&effect);
+                    result = self.resume(effect.id(), &EffectResult::Emitted)?;
+                }
+                other => return Ok(other),
+            }
+        }
+    }
+
+    /// Call a guest function with a fresh fuel budget
+    ///
+    /// The call pauses each time it has used up another `fuel_limit`; it
+    /// continues while `on_fuel_exhausted` grants more and is cancelled
+    /// otherwise. Afterwards the store is left with one `fuel_limit` for the
+    /// unmetered alloc/dealloc calls.
+    fn call_metered<Params, Results>(
+        &mut self,
+        func: &TypedFunc<Params, Results>,
+        params: Params,
+        function: &'static str,
+    ) -> Result<Results, LoaderError>
+    where
+        Params: WasmParams,
+        Results: WasmResults,
+    {
+        let fuel_limit = self.config.fuel_limit;
+        let set_fuel = |store: &mut Store<()>, fuel| {
+            store
+                .set_fuel(fuel)
+                .map_err(|e| LoaderError::StoreConfig(format!("Failed to reset fuel: {}", e)))
+        };
+
+        // The reserve is never reached: the hook is consulted every
+        // `fuel_limit` and denying it cancels the call
+        set_fuel(&mut self.store, METERED_FUEL_RESERVE)?;
+        let hook = self.config.on_fuel_exhausted.clone();
+        let result = drive(func.call_async(&mut self.store, params), || {
+            let granted = hook.as_ref().is_some_and(|hook| hook());
+            if granted {
+                tracing::info!(function, fuel_limit, "Fuel exhausted, granted more fuel");
+            }
+            granted
+        });
+        let remaining = self.store.get_fuel().unwrap_or(0);
+        self.fuel_consumed = self
+            .fuel_consumed
+            .saturating_add(METERED_FUEL_RESERVE.saturating_sub(remaining));
+        set_fuel(&mut self.store, fuel_limit)?;
+
+        result.map_err(|e| {
+            if is_out_of_fuel(&e) {
+                LoaderError::FuelExhausted
+            } else {
+                LoaderError::FunctionCall {
+                    function,
+                    source: e,
+                }
+            }
+        })
+    }
+
+    /// Get the loader configuration this instance was created with
+    pub fn config(&self) -> &LoaderConfig {
+        &self.config
     }
 
     /// Check if plugin supports effects (has plugin_resume function)
@@ -413,6 +897,33 @@ impl PluginInstance {
             .get_typed_func::<(u32, i32, i32), i64>(&mut self.store, "plugin_resume")
             .is_ok()
     }
+
+    /// Call the plugin's `plugin_shutdown` export, if it has one
+    ///
+    /// Runs at most once per instance: later calls do nothing. Returns
+    /// whether the export was called. The export gets one `fuel_limit` and
+    /// is never granted more, so a hook that does not finish fails with
+    /// [`LoaderError::FuelExhausted`]. The plugin should not be executed
+    /// afterwards.
+    pub fn shutdown(&mut self) -> Result<bool, LoaderError> {
+        let Some(shutdown_fn) = self.shutdown_fn.take() else {
+            return Ok(false);
+        };
+        self.store
+            .set_fuel(self.config.fuel_limit)
+            .map_err(|e| LoaderError::StoreConfig(format!("Failed to reset fuel: {}", e)))?;
+        call_unmetered(&shutdown_fn, &mut self.store, ()).map_err(|e| {
+            if is_out_of_fuel(&e) {
+                LoaderError::FuelExhausted
+            } else {
+                LoaderError::FunctionCall {
+                    function: "plugin_shutdown",
+                    source: e,
+                }
+            }
+        })?;
+        Ok(true)
+    }
 }
 
 /// Effect handler trait for processing plugin effects
@@ -423,11 +934,155 @@ impl PluginInstance {
 pub trait EffectHandler: Send + Sync {
     /// Handle an effect and return the result
     async fn handle(&self, effect: Effect) -> EffectResult;
+
+    /// Deliver a streaming effect (`Emit` or `Progress`)
+    ///
+    /// The default only logs the effect at debug level through `tracing`;
+    /// hosts that display streamed output override it (the router bridge
+    /// forwards chunks to sen's `Emitter` and progress to `Progress`).
+    fn stream(&self, effect: &Effect) {
+        match effect {
+            Effect::Emit(chunk) => tracing::debug!(chunk = %chunk, "Plugin emitted output"),
+            Effect::Progress { done, total } => {
+                tracing::debug!(done, total, "Plugin reported progress")
+            }
+            _ => {}
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{packed, WatPlugin, RESULT_OFFSET};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// WAT plugin whose `plugin_execute` spins `iterations` times (~6 fuel
+    /// each), counting its runs in the exported `runs` global
+    fn looping_plugin(iterations: u32) -> Vec<u8> {
+        let result = rmp_serde::to_vec(&ExecuteResult::success("done")).unwrap();
+
+        WatPlugin::command("spin", "Spins")
+            .field(r#"(global $runs (export "runs") (mut i32) (i32.const 0))"#)
+            .data(RESULT_OFFSET, &result)
+            .execute(format!(
+                r#"(local $i i32)
+                (global.set $runs (i32.add (global.get $runs) (i32.const 1)))
+                (local.set $i (i32.const {iterations}))
+                (loop $spin
+                    (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+                    (br_if $spin (local.get $i)))
+                (i64.const {result_packed})"#,
+                result_packed = packed(RESULT_OFFSET, result.len()),
+            ))
+            .build()
+    }
+
+    /// WAT plugin that returns `steps` in order: the first from
+    /// `plugin_execute`, each following one from `plugin_resume`
+    ///
+    /// `plugin_dealloc` counts its calls in the exported `frees` global.
+    fn scripted_plugin(steps: &[ExecuteResult]) -> Vec<u8> {
+        let mut plugin = WatPlugin::command("script", "Scripted")
+            .field("(global $step (mut i32) (i32.const 0))")
+            .field(r#"(global $frees (export "frees") (mut i32) (i32.const 0))"#)
+            .dealloc("(global.set $frees (i32.add (global.get $frees) (i32.const 1)))");
+
+        // Step payloads from offset 1024, followed by a table of packed results
+        let mut table = Vec::new();
+        let mut offset = 1024;
+        for step in steps {
+            let bytes = rmp_serde::to_vec(step).unwrap();
+            table.extend_from_slice(&packed(offset, bytes.len()).to_le_bytes());
+            plugin = plugin.data(offset, &bytes);
+            offset += bytes.len() as u32;
+        }
+        let table_offset = offset;
+
+        plugin
+            .data(table_offset, &table)
+            .execute(format!(
+                "(global.set $step (i32.const 0))
+                (i64.load (i32.const {table_offset}))"
+            ))
+            .field(format!(
+                r#"(func (export "plugin_resume") (param i32 i32 i32) (result i64)
+                    (global.set $step (i32.add (global.get $step) (i32.const 1)))
+                    (i64.load (i32.add
+                        (i32.const {table_offset})
+                        (i32.mul (global.get $step) (i32.const 8)))))"#
+            ))
+            .build()
+    }
+
+    /// WAT plugin whose `plugin_shutdown` counts its calls in the exported
+    /// `shutdowns` global and then spins forever
+    fn shutdown_plugin() -> Vec<u8> {
+        WatPlugin::command("teardown", "Tears down")
+            .field(r#"(global $shutdowns (export "shutdowns") (mut i32) (i32.const 0))"#)
+            .field(
+                r#"(func (export "plugin_shutdown")
+                    (global.set $shutdowns (i32.add (global.get $shutdowns) (i32.const 1)))
+                    (loop $spin (br $spin)))"#,
+            )
+            .build()
+    }
+
+    /// WAT plugin with a function using a SIMD instruction
+    fn simd_plugin() -> Vec<u8> {
+        WatPlugin::command("sum", "Sums")
+            .field(
+                "(func $sum (result i32)
+                    (i32x4.extract_lane 0
+                        (i32x4.add (v128.const i32x4 1 2 3 4) (v128.const i32x4 5 6 7 8))))",
+            )
+            .build()
+    }
+
+    /// Loader whose fuel hook counts how often it is consulted
+    fn hook_counting_loader() -> (PluginLoader, Arc<AtomicUsize>) {
+        let (hook, calls) = counting_hook(0);
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 10_000,
+            on_fuel_exhausted: Some(hook),
+            ..Default::default()
+        })
+        .unwrap();
+        (loader, calls)
+    }
+
+    /// Effect handler that records what it was asked to do
+    #[derive(Default)]
+    struct RecordingHandler {
+        handled: std::sync::Mutex<Vec<u32>>,
+        streamed: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EffectHandler for RecordingHandler {
+        async fn handle(&self, effect: Effect) -> EffectResult {
+            self.handled.lock().unwrap().push(effect.id());
+            EffectResult::SleepComplete
+        }
+
+        fn stream(&self, effect: &Effect) {
+            let entry = match effect {
+                Effect::Emit(chunk) => chunk.clone(),
+                Effect::Progress { done, total } => format!("{}/{}", done, total),
+                other => panic!("non-streaming effect streamed: {:?}", other),
+            };
+            self.streamed.lock().unwrap().push(entry);
+        }
+    }
+
+    /// Hook that grants `grants` times, then denies; counts invocations
+    fn counting_hook(grants: usize) -> (FuelExhaustedHook, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let hook: FuelExhaustedHook =
+            Arc::new(move || counter.fetch_add(1, Ordering::SeqCst) < grants);
+        (hook, calls)
+    }
 
     #[test]
     fn test_loader_creation() {
@@ -435,6 +1090,56 @@ mod tests {
         assert!(loader.is_ok());
     }
 
+    #[test]
+    fn test_loader_with_config() {
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 500_000,
+            max_stack_size: 512 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(loader.config().fuel_limit, 500_000);
+        assert_eq!(loader.config().max_stack_size, 512 * 1024);
+    }
+
+    #[test]
+    fn test_disabled_simd_rejects_simd_plugin() {
+        let restricted = PluginLoader::with_config(LoaderConfig {
+            wasm_features: WasmFeatures {
+                simd: false,
+                threads: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        let result = restricted.load(&simd_plugin());
+        assert!(matches!(
+            result,
+            Err(LoaderError::UnsupportedFeature {
+                feature: "simd",
+                ..
+            })
+        ));
+
+        // Plugins without SIMD still load, and the default allows SIMD
+        assert!(restricted.load(&shutdown_plugin()).is_ok());
+        let plugin = PluginLoader::new().unwrap().load(&simd_plugin()).unwrap();
+        assert_eq!(plugin.manifest.command.name, "sum");
+
+        let minimal = PluginLoader::with_config(LoaderConfig {
+            wasm_features: WasmFeatures {
+                simd: false,
+                bulk_memory: false,
+                reference_types: false,
+                threads: false,
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(minimal.load(&shutdown_plugin()).is_ok());
+    }
+
     #[test]
     fn test_pack_unpack() {
         let ptr = 0x12345678_i32;
@@ -444,4 +1149,295 @@ mod tests {
         assert_eq!(up, ptr);
         assert_eq!(ul, len);
     }
+
+    #[test]
+    fn test_fuel_exhausted_without_hook() {
+        // ~600k fuel needed
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 400_000,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&looping_plugin(100_000)).unwrap();
+
+        let result = plugin.instance.execute(&[]);
+        assert!(matches!(result, Err(LoaderError::FuelExhausted)));
+    }
+
+    #[test]
+    fn test_fuel_exhausted_hook_grants_and_completes() {
+        // ~600k fuel needed: 400k fails, one grant raises the budget to 800k
+        let (hook, calls) = counting_hook(1);
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 400_000,
+            on_fuel_exhausted: Some(hook),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&looping_plugin(100_000)).unwrap();
+
+        let result = plugin.instance.execute(&[]).unwrap();
+        assert!(matches!(result, ExecuteResult::Success(ref s) if s == "done"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_stats_count_granted_fuel() {
+        // ~600k fuel needed: the call continues past 400k on the grant
+        let (hook, _calls) = counting_hook(2);
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 400_000,
+            on_fuel_exhausted: Some(hook),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&looping_plugin(100_000)).unwrap();
+
+        let (_, stats) = plugin.instance.execute_with_stats(&[]).unwrap();
+        assert!(stats.fuel_consumed > 500_000 && stats.fuel_consumed < 800_000);
+        assert_eq!(stats.memory_peak_bytes, 65536);
+
+        // The next call starts from zero
+        let (_, again) = plugin.instance.execute_with_stats(&[]).unwrap();
+        assert_eq!(again.fuel_consumed, stats.fuel_consumed);
+    }
+
+    #[test]
+    fn test_granted_fuel_continues_instead_of_restarting() {
+        // A restart after the grant would count two runs
+        let (hook, calls) = counting_hook(1);
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 400_000,
+            on_fuel_exhausted: Some(hook),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&looping_plugin(100_000)).unwrap();
+
+        plugin.instance.execute(&[]).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let instance = &mut plugin.instance;
+        let runs = instance
+            .instance
+            .get_global(&mut instance.store, "runs")
+            .unwrap();
+        assert_eq!(runs.get(&mut instance.store).i32(), Some(1));
+    }
+
+    #[test]
+    fn test_fuel_exhausted_hook_grants_once_then_denies() {
+        // ~600k fuel needed: 250k fails, one grant gives 500k which still fails
+        let (hook, calls) = counting_hook(1);
+        let loader = PluginLoader::with_config(LoaderConfig {
+            fuel_limit: 250_000,
+            on_fuel_exhausted: Some(hook),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&looping_plugin(100_000)).unwrap();
+
+        let result = plugin.instance.execute(&[]);
+        assert!(matches!(result, Err(LoaderError::FuelExhausted)));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_oversized_result_is_rejected() {
+        let loader = PluginLoader::with_config(LoaderConfig {
+            max_payload_bytes: 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let big = ExecuteResult::success("x".repeat(4096));
+        let mut plugin = loader.load(&scripted_plugin(&[big.clone(), big])).unwrap();
+        let frees = |instance: &mut PluginInstance| {
+            let global = instance
+                .instance
+                .get_global(&mut instance.store, "frees")
+                .unwrap();
+            global.get(&mut instance.store).i32().unwrap()
+        };
+        let after_load = frees(&mut plugin.instance);
+
+        let result = plugin.instance.execute(&[]);
+        assert!(matches!(
+            result,
+            Err(LoaderError::PayloadTooLarge { kind: "result", len, limit: 1024 }) if len > 4096
+        ));
+        // Both the args and the rejected result were handed back
+        assert_eq!(frees(&mut plugin.instance), after_load + 2);
+
+        let result = plugin.instance.resume(0, &EffectResult::Emitted);
+        assert!(matches!(
+            result,
+            Err(LoaderError::PayloadTooLarge { kind: "result", .. })
+        ));
+        assert_eq!(frees(&mut plugin.instance), after_load + 4);
+    }
+
+    #[test]
+    fn test_oversized_manifest_and_args_are_rejected() {
+        let plugin = scripted_plugin(&[ExecuteResult::success("ok")]);
+
+        let tiny = PluginLoader::with_config(LoaderConfig {
+            max_payload_bytes: 8,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(
+            tiny.load(&plugin),
+            Err(LoaderError::PayloadTooLarge {
+                kind: "manifest",
+                ..
+            })
+        ));
+
+        let loader = PluginLoader::with_config(LoaderConfig {
+            max_payload_bytes: 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut plugin = loader.load(&plugin).unwrap();
+        let result = plugin.instance.execute(&["y".repeat(2048)]);
+        assert!(matches!(
+            result,
+            Err(LoaderError::PayloadTooLarge {
+                kind: "arguments",
+                ..
+            })
+        ));
+
+        // The instance is still usable after a rejected call
+        let result = plugin.instance.execute(&["small".to_string()]).unwrap();
+        assert!(matches!(result, ExecuteResult::Success(ref s) if s == "ok"));
+    }
+
+    #[test]
+    fn test_execute_streaming_delivers_chunks() {
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader
+            .load(&scripted_plugin(&[
+                ExecuteResult::emit("chunk 1"),
+                ExecuteResult::progress(1, 2),
+                ExecuteResult::emit("chunk 2"),
+                ExecuteResult::success("done"),
+            ]))
+            .unwrap();
+
+        let mut streamed = Vec::new();
+        let result = plugin
+            .instance
+            .execute_streaming(&[], |effect| streamed.push(effect.clone()))
+            .unwrap();
+
+        assert!(matches!(result, ExecuteResult::Success(ref s) if s == "done"));
+        assert_eq!(streamed.len(), 3);
+        assert!(matches!(streamed[0], Effect::Emit(ref c) if c == "chunk 1"));
+        assert!(matches!(
+            streamed[1],
+            Effect::Progress { done: 1, total: 2 }
+        ));
+        assert!(matches!(streamed[2], Effect::Emit(ref c) if c == "chunk 2"));
+    }
+
+    #[test]
+    fn test_execute_streaming_stops_at_other_effects() {
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader
+            .load(&scripted_plugin(&[
+                ExecuteResult::emit("chunk 1"),
+                ExecuteResult::sleep(7, 10),
+            ]))
+            .unwrap();
+
+        let result = plugin.instance.execute_streaming(&[], |_| {}).unwrap();
+        assert!(matches!(
+            result,
+            ExecuteResult::Effect(Effect::Sleep { id: 7, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_effects_runs_to_completion() {
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader
+            .load(&scripted_plugin(&[
+                ExecuteResult::emit("chunk 1"),
+                ExecuteResult::sleep(7, 10),
+                ExecuteResult::emit("chunk 2"),
+                ExecuteResult::progress(2, 2),
+                ExecuteResult::success("done"),
+            ]))
+            .unwrap();
+
+        let handler = RecordingHandler::default();
+        let result = plugin
+            .instance
+            .execute_with_effects(&[], &handler)
+            .await
+            .unwrap();
+
+        assert!(matches!(result, ExecuteResult::Success(ref s) if s == "done"));
+        assert_eq!(*handler.handled.lock().unwrap(), vec![7]);
+        assert_eq!(
+            *handler.streamed.lock().unwrap(),
+            vec!["chunk 1", "chunk 2", "2/2"]
+        );
+    }
+
+    /// Calls of `plugin_shutdown` recorded by [`shutdown_plugin`]
+    fn shutdowns(instance: &mut PluginInstance) -> Option<i32> {
+        let global = instance
+            .instance
+            .get_global(&mut instance.store, "shutdowns")?;
+        global.get(&mut instance.store).i32()
+    }
+
+    #[test]
+    fn test_shutdown_calls_export_once_without_extra_fuel() {
+        let (loader, hook_calls) = hook_counting_loader();
+        let mut plugin = loader.load(&shutdown_plugin()).unwrap();
+
+        let result = plugin.instance.shutdown();
+        assert!(matches!(result, Err(LoaderError::FuelExhausted)));
+        assert_eq!(shutdowns(&mut plugin.instance), Some(1));
+        // The fuel hook is for commands, not teardown
+        assert_eq!(hook_calls.load(Ordering::SeqCst), 0);
+
+        assert!(!plugin.instance.shutdown().unwrap());
+        assert_eq!(shutdowns(&mut plugin.instance), Some(1));
+    }
+
+    #[test]
+    fn test_drop_runs_no_guest_code() {
+        let (loader, hook_calls) = hook_counting_loader();
+        let mut plugin = loader.load(&shutdown_plugin()).unwrap();
+        assert_eq!(shutdowns(&mut plugin.instance), Some(0));
+        drop(plugin);
+        assert_eq!(hook_calls.load(Ordering::SeqCst), 0);
+
+        // Plugins without the export just free their store
+        let mut plugin = loader.load(&looping_plugin(1)).unwrap();
+        assert!(!plugin.instance.shutdown().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_registry_unload_calls_shutdown_export() {
+        let (loader, hook_calls) = hook_counting_loader();
+        let registry = crate::PluginRegistry::new().unwrap();
+        registry
+            .register(loader.load(&shutdown_plugin()).unwrap())
+            .await;
+
+        // The spinning hook runs out of its fuel and the plugin is removed
+        assert!(registry.unload("teardown").await);
+        assert!(!registry.has_command("teardown").await);
+        assert_eq!(hook_calls.load(Ordering::SeqCst), 0);
+
+        registry
+            .register(loader.load(&shutdown_plugin()).unwrap())
+            .await;
+        assert_eq!(registry.unload_all().await, vec!["teardown".to_string()]);
+        assert!(registry.is_empty().await);
+    }
 }
@@ -17,6 +17,7 @@
 //! │  │ - Permissive│ │ - ReadOnly  │ │ - Recording │ │ - Null              ││
 //! │  │ - CI        │ │             │ │             │ │ - Composite         ││
 //! │  │ - TrustAll  │ │             │ │             │ │                     ││
+//! │  │ - Policy    │ │             │ │             │ │                     ││
 //! │  └─────────────┘ └─────────────┘ └─────────────┘ └─────────────────────┘│
 //! └─────────────────────────────────────────────────────────────────────────┘
 //! ```
@@ -73,6 +74,15 @@
 //! | Permissive | Plugin | Network only | Development |
 //! | CI | Plugin | Never | CI/CD |
 //! | TrustAll | Plugin | Never | Testing only |
+//! | PolicyFile | Plugin | Per rule | Ops-governed deployments |
+//!
+//! `PolicyFileStrategy` loads ordered `allow`/`deny`/`prompt` rules from a JSON
+//! file, or YAML/TOML with the `config` feature (see [`policy`]), so plugin
+//! trust can be governed without recompiling:
+//!
+//! ```rust,ignore
+//! let config = PermissionPresets::policy("myapp", "/etc/myapp/plugin-policy.json")?;
+//! ```
 //!
 //! ## Store
 //!
@@ -81,6 +91,17 @@
 //! - `FilePermissionStore`: JSON file in config directory
 //! - `MemoryPermissionStore`: In-memory (session only)
 //! - `ReadOnlyPermissionStore`: Wrapper that prevents writes
+//! - `PregrantedPermissionStore`: Wrapper serving pre-granted capabilities
+//!
+//! In CI, trusted plugins can be pre-granted their declared capabilities so
+//! they run without a prompt under strict or CI strategies:
+//!
+//! ```rust,ignore
+//! let config = PermissionConfigBuilder::new()
+//!     .strategy(CiPermissionStrategy)
+//!     .pregrant("hello", Capabilities::default().with_stdio(StdioCapability::stdout_only()))
+//!     .build()?;
+//! ```
 //!
 //! ## Prompt
 //!
@@ -105,6 +126,7 @@
 //! myapp --yolo run  # Trust all (if configured)
 //! ```
 
+pub mod policy;
 pub mod presets;
 pub mod prompt;
 pub mod store;
@@ -112,10 +134,13 @@ pub mod strategy;
 pub mod trust;
 
 // Re-exports for convenience
+pub use policy::{PolicyAction, PolicyCapability, PolicyError, PolicyFileStrategy, PolicyRule};
 pub use presets::{PermissionConfig, PermissionConfigBuilder, PermissionPresets, PresetError};
 pub use prompt::{AutoPromptHandler, RecordingPromptHandler, TerminalPromptHandler};
 pub use prompt::{PromptError, PromptHandler, PromptResult};
-pub use store::{FilePermissionStore, MemoryPermissionStore, ReadOnlyPermissionStore};
+pub use store::{
+    FilePermissionStore, MemoryPermissionStore, PregrantedPermissionStore, ReadOnlyPermissionStore,
+};
 pub use store::{PermissionStore, StoreError, StoredPermission, StoredTrustLevel};
 pub use strategy::{
     CiPermissionStrategy, DefaultPermissionStrategy, PermissivePermissionStrategy,
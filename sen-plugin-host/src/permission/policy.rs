@@ -0,0 +1,1028 @@
+//! Policy-file permission strategy
+//!
+//! Lets operators govern plugin trust with a declarative policy file instead
+//! of selecting a strategy in code. Files are JSON, or YAML and TOML with the
+//! `config` feature; the format is picked from the extension.
+//!
+//! # Policy File Format
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "default": "prompt",
+//!   "rules": [
+//!     "allow fs_read ./data",
+//!     { "plugin": "uploader", "rule": "allow net https://*.example.com:443" },
+//!     "deny net *",
+//!     "prompt fs_write **"
+//!   ]
+//! }
+//! ```
+//!
+//! The same policy in YAML:
+//!
+//! ```yaml
+//! version: 1
+//! default: prompt
+//! rules:
+//!   - allow fs_read ./data
+//!   - { plugin: uploader, rule: "allow net https://*.example.com:443" }
+//!   - deny net *
+//!   - prompt fs_write **
+//! ```
+//!
+//! Each rule is `<action> <capability> <pattern>`:
+//!
+//! | Part | Values |
+//! |------|--------|
+//! | action | `allow`, `deny`, `prompt` |
+//! | capability | `fs_read`, `fs_write`, `env_read`, `net`, `stdio`, `invoke`, `tmp` |
+//! | pattern | See below |
+//!
+//! Patterns depend on the capability:
+//!
+//! - `fs_read` / `fs_write`: a path. A plain path covers itself and everything
+//!   below it; a glob is matched segment by segment, so `*` stays within one
+//!   directory and `**` spans any number (`./data/**/*.csv`, or `**` for every
+//!   path). Paths are normalized first and `..` is not allowed in rules.
+//! - `net`: `[protocol://]host[:port]`, where host is `*`, `*.suffix`, or a
+//!   name. An omitted protocol or port matches any.
+//! - `env_read`, `stdio`, `invoke`, `tmp`: a name glob (`*` wildcard) over the
+//!   variable, stream, invoked command name, or `/tmp`.
+//!
+//! A rule may be scoped to plugins with `{ "plugin": "<glob>", "rule": "..." }`.
+//!
+//! # Evaluation
+//!
+//! Every requested capability item (each path, env pattern, host, and stdio
+//! stream) is matched against the rules in order; the first match decides its
+//! action, otherwise `default` applies (`prompt` if omitted). An `allow` rule
+//! matches an item it fully covers; `deny` and `prompt` rules match any item
+//! they overlap, so `deny fs_read ./secret` also catches `./secret/*.key`
+//! and `./**`. The decisions are then combined:
+//!
+//! | Items | Decision |
+//! |-------|----------|
+//! | All allowed | `Allow` |
+//! | Some prompt, none denied | `Prompt` (items already granted count as allowed) |
+//! | Some denied, rest allowed | `AllowPartial` with the allowed items |
+//! | Some denied, none allowed or any prompt | `Deny` |
+
+use std::path::{Path, PathBuf};
+
+use sen_plugin_api::{
+    wildcard_match, Capabilities, NetPattern, NetProtocol, PathPattern, StdioCapability,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::strategy::{
+    PermissionContext, PermissionDecision, PermissionGranularity, PermissionStrategy,
+};
+
+/// Supported policy file version
+pub const POLICY_VERSION: u32 = 1;
+
+/// Policy file extensions accepted by [`PolicyFileStrategy::from_file`]
+#[cfg(feature = "config")]
+const FORMATS: &str = ".json, .yaml, .yml or .toml";
+#[cfg(not(feature = "config"))]
+const FORMATS: &str = ".json; enable the `config` feature for YAML and TOML";
+
+/// Error type for policy file loading and validation
+#[derive(Debug, Error)]
+pub enum PolicyError {
+    #[error("Failed to read policy file '{path}': {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse policy file: {0}")]
+    Parse(String),
+
+    #[error("Unsupported policy file format '{0}' (expected {FORMATS})")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("Unsupported policy version {0} (expected {POLICY_VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid rule #{index} ({rule}): {reason}")]
+    InvalidRule {
+        index: usize,
+        rule: String,
+        reason: String,
+    },
+}
+
+/// Action taken when a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Allow,
+    Deny,
+    Prompt,
+}
+
+impl PolicyAction {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "allow" => Some(Self::Allow),
+            "deny" => Some(Self::Deny),
+            "prompt" => Some(Self::Prompt),
+            _ => None,
+        }
+    }
+}
+
+/// Capability kind a rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyCapability {
+    FsRead,
+    FsWrite,
+    EnvRead,
+    Net,
+    Stdio,
+    Invoke,
+    Tmp,
+}
+
+impl PolicyCapability {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "fs_read" => Some(Self::FsRead),
+            "fs_write" => Some(Self::FsWrite),
+            "env_read" => Some(Self::EnvRead),
+            "net" => Some(Self::Net),
+            "stdio" => Some(Self::Stdio),
+            "invoke" => Some(Self::Invoke),
+            "tmp" => Some(Self::Tmp),
+            _ => None,
+        }
+    }
+
+    /// Name as written in policy files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FsRead => "fs_read",
+            Self::FsWrite => "fs_write",
+            Self::EnvRead => "env_read",
+            Self::Net => "net",
+            Self::Stdio => "stdio",
+            Self::Invoke => "invoke",
+            Self::Tmp => "tmp",
+        }
+    }
+}
+
+/// A single validated policy rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRule {
+    /// Action when the rule matches
+    pub action: PolicyAction,
+    /// Capability kind
+    pub capability: PolicyCapability,
+    /// Pattern as written in the policy file
+    pub pattern: String,
+    /// Optional plugin name glob
+    pub plugin: Option<String>,
+    target: RuleTarget,
+}
+
+/// Parsed form of a rule's pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RuleTarget {
+    Path(PathPattern),
+    Net {
+        protocol: Option<NetProtocol>,
+        host: String,
+        port: Option<u16>,
+    },
+    Name(String),
+}
+
+/// A requested capability item, in the form rules match against
+#[derive(Debug, Clone)]
+enum Item {
+    Path(PathPattern),
+    Net(NetPattern),
+    Name(String),
+}
+
+impl Item {
+    fn parse(capability: PolicyCapability, item: &str) -> Self {
+        match capability {
+            PolicyCapability::FsRead | PolicyCapability::FsWrite => {
+                Self::Path(PathPattern::new(item))
+            }
+            PolicyCapability::Net => {
+                let (protocol, host, port) = split_net(item);
+                Self::Net(NetPattern {
+                    host: host.to_string(),
+                    port: port.and_then(|p| p.parse().ok()),
+                    protocol: protocol.and_then(parse_protocol).unwrap_or_default(),
+                })
+            }
+            _ => Self::Name(item.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(p) if p.recursive => write!(f, "{} (recursive)", p.pattern),
+            Self::Path(p) => f.write_str(&p.pattern),
+            Self::Net(n) => {
+                write!(f, "{}://{}", protocol_name(n.protocol), n.host)?;
+                match n.port {
+                    Some(port) => write!(f, ":{}", port),
+                    None => Ok(()),
+                }
+            }
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+impl PolicyRule {
+    /// Parse a `<action> <capability> <pattern>` rule
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = rule.split_whitespace().collect();
+        let [action, capability, pattern] = parts.as_slice() else {
+            return Err(format!(
+                "expected '<action> <capability> <pattern>', found {} part(s)",
+                parts.len()
+            ));
+        };
+
+        let action = PolicyAction::parse(action).ok_or_else(|| {
+            format!(
+                "unknown action '{}' (expected allow, deny or prompt)",
+                action
+            )
+        })?;
+        let capability = PolicyCapability::parse(capability).ok_or_else(|| {
+            format!(
+                "unknown capability '{}' (expected fs_read, fs_write, env_read, net, stdio, invoke or tmp)",
+                capability
+            )
+        })?;
+
+        let target = match capability {
+            PolicyCapability::FsRead | PolicyCapability::FsWrite => parse_path_target(pattern)?,
+            PolicyCapability::Net => parse_net_target(pattern)?,
+            PolicyCapability::Stdio if !["stdin", "stdout", "stderr", "*"].contains(pattern) => {
+                return Err(format!(
+                    "invalid stdio pattern '{}' (expected stdin, stdout, stderr or *)",
+                    pattern
+                ));
+            }
+            _ => RuleTarget::Name(pattern.to_string()),
+        };
+
+        Ok(Self {
+            action,
+            capability,
+            pattern: pattern.to_string(),
+            plugin: None,
+            target,
+        })
+    }
+
+    /// Scope this rule to plugins matching a glob
+    pub fn for_plugin(mut self, plugin: impl Into<String>) -> Self {
+        self.plugin = Some(plugin.into());
+        self
+    }
+
+    /// Whether the rule decides `item`: `allow` must cover all of it, while
+    /// `deny` and `prompt` claim anything they overlap
+    fn matches(&self, plugin: &str, capability: PolicyCapability, item: &Item) -> bool {
+        if self.capability != capability
+            || !self
+                .plugin
+                .as_deref()
+                .is_none_or(|p| wildcard_match(p, plugin))
+        {
+            return false;
+        }
+        let allow = self.action == PolicyAction::Allow;
+        match (&self.target, item) {
+            (RuleTarget::Path(rule), Item::Path(path)) if allow => rule.contains(path),
+            (RuleTarget::Path(rule), Item::Path(path)) => rule.overlaps(path),
+            (
+                RuleTarget::Net {
+                    protocol,
+                    host,
+                    port,
+                },
+                Item::Net(net),
+            ) => {
+                if protocol.is_some_and(|p| p != net.protocol) {
+                    return false;
+                }
+                let ports_match = if allow {
+                    port.is_none() || *port == net.port
+                } else {
+                    port.is_none() || net.port.is_none() || *port == net.port
+                };
+                let hosts_match =
+                    host_covers(host, &net.host) || (!allow && host_covers(&net.host, host));
+                ports_match && hosts_match
+            }
+            (RuleTarget::Name(glob), Item::Name(name)) => wildcard_match(glob, name),
+            _ => false,
+        }
+    }
+}
+
+/// A plain path rule covers its whole tree; globs keep their own meaning
+fn parse_path_target(pattern: &str) -> Result<RuleTarget, String> {
+    if pattern.split('/').any(|segment| segment == "..") {
+        return Err(format!("'..' is not allowed in path pattern '{}'", pattern));
+    }
+    let path = PathPattern::new(pattern);
+    Ok(RuleTarget::Path(if path.is_glob() {
+        path
+    } else {
+        path.recursive()
+    }))
+}
+
+fn parse_net_target(pattern: &str) -> Result<RuleTarget, String> {
+    let (protocol, host, port) = split_net(pattern);
+    let protocol = protocol
+        .map(|p| {
+            parse_protocol(p)
+                .ok_or_else(|| format!("unknown protocol '{}' (expected https, http or tcp)", p))
+        })
+        .transpose()?;
+    let port = port
+        .map(|p| {
+            p.parse::<u16>()
+                .map_err(|_| format!("invalid port '{}' in '{}'", p, pattern))
+        })
+        .transpose()?;
+    if host.is_empty() || host.trim_start_matches("*.").contains('*') && host != "*" {
+        return Err(format!(
+            "invalid host pattern '{}' (expected *, *.<domain> or a host name)",
+            host
+        ));
+    }
+    Ok(RuleTarget::Net {
+        protocol,
+        host: host.to_string(),
+        port,
+    })
+}
+
+/// Split `[protocol://]host[:port]`
+fn split_net(pattern: &str) -> (Option<&str>, &str, Option<&str>) {
+    let (protocol, rest) = match pattern.split_once("://") {
+        Some((protocol, rest)) => (Some(protocol), rest),
+        None => (None, pattern),
+    };
+    match rest.rsplit_once(':') {
+        Some((host, port)) => (protocol, host, Some(port)),
+        None => (protocol, rest, None),
+    }
+}
+
+fn parse_protocol(protocol: &str) -> Option<NetProtocol> {
+    match protocol {
+        "https" => Some(NetProtocol::Https),
+        "http" => Some(NetProtocol::Http),
+        "tcp" => Some(NetProtocol::Tcp),
+        _ => None,
+    }
+}
+
+fn protocol_name(protocol: NetProtocol) -> &'static str {
+    match protocol {
+        NetProtocol::Https => "https",
+        NetProtocol::Http => "http",
+        NetProtocol::Tcp => "tcp",
+    }
+}
+
+/// Host matching as in [`NetPattern::contains`]
+fn host_covers(pattern: &str, host: &str) -> bool {
+    NetPattern::https(pattern).contains(&NetPattern::https(host))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicy {
+    version: u32,
+    #[serde(default = "default_action")]
+    default: PolicyAction,
+    #[serde(default)]
+    rules: Vec<serde_json::Value>,
+}
+
+fn default_action() -> PolicyAction {
+    PolicyAction::Prompt
+}
+
+/// Permission strategy driven by a policy file
+///
+/// # Example
+///
+/// ```rust
+/// use sen_plugin_host::permission::PolicyFileStrategy;
+///
+/// let strategy = PolicyFileStrategy::from_json(r#"{
+///     "version": 1,
+///     "rules": ["allow fs_read ./data", "deny net *"]
+/// }"#).unwrap();
+/// assert_eq!(strategy.rules().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PolicyFileStrategy {
+    default: PolicyAction,
+    rules: Vec<PolicyRule>,
+}
+
+impl PolicyFileStrategy {
+    /// Create a strategy from already-validated rules
+    pub fn new(default: PolicyAction, rules: Vec<PolicyRule>) -> Self {
+        Self { default, rules }
+    }
+
+    /// Load and validate a policy file, picking the format from its extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PolicyError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|e| e.to_str());
+        let parse: fn(&str) -> Result<Self, PolicyError> = match extension {
+            Some("json") => Self::from_json,
+            #[cfg(feature = "config")]
+            Some("yaml" | "yml") => Self::from_yaml,
+            #[cfg(feature = "config")]
+            Some("toml") => Self::from_toml,
+            _ => return Err(PolicyError::UnsupportedFormat(path.to_path_buf())),
+        };
+        let content = std::fs::read_to_string(path).map_err(|source| PolicyError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        parse(&content)
+    }
+
+    /// Parse and validate a policy from JSON
+    pub fn from_json(json: &str) -> Result<Self, PolicyError> {
+        Self::from_raw(serde_json::from_str(json).map_err(|e| PolicyError::Parse(e.to_string()))?)
+    }
+
+    /// Parse and validate a policy from YAML
+    #[cfg(feature = "config")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, PolicyError> {
+        Self::from_raw(serde_yaml::from_str(yaml).map_err(|e| PolicyError::Parse(e.to_string()))?)
+    }
+
+    /// Parse and validate a policy from TOML
+    #[cfg(feature = "config")]
+    pub fn from_toml(toml: &str) -> Result<Self, PolicyError> {
+        Self::from_raw(toml::from_str(toml).map_err(|e| PolicyError::Parse(e.to_string()))?)
+    }
+
+    fn from_raw(raw: RawPolicy) -> Result<Self, PolicyError> {
+        if raw.version != POLICY_VERSION {
+            return Err(PolicyError::UnsupportedVersion(raw.version));
+        }
+
+        let rules = raw
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                parse_rule_value(value).map_err(|reason| PolicyError::InvalidRule {
+                    index: i + 1,
+                    rule: value.to_string(),
+                    reason,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(raw.default, rules))
+    }
+
+    /// Action for items no rule matches
+    pub fn default_action(&self) -> PolicyAction {
+        self.default
+    }
+
+    /// Rules in evaluation order
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    /// Evaluate a single capability item
+    ///
+    /// `item` is written like a rule pattern: a path, `[protocol://]host[:port]`
+    /// (https if no protocol is given), or a name.
+    pub fn evaluate(&self, plugin: &str, capability: PolicyCapability, item: &str) -> PolicyAction {
+        self.evaluate_item(plugin, capability, &Item::parse(capability, item))
+    }
+
+    fn evaluate_item(
+        &self,
+        plugin: &str,
+        capability: PolicyCapability,
+        item: &Item,
+    ) -> PolicyAction {
+        self.rules
+            .iter()
+            .find(|r| r.matches(plugin, capability, item))
+            .map(|r| r.action)
+            .unwrap_or(self.default)
+    }
+
+    fn decide(&self, ctx: &PermissionContext) -> PermissionDecision {
+        let mut allowed = Capabilities::none();
+        let mut denied = Vec::new();
+        let mut needs_prompt = false;
+
+        for (capability, item, single) in requested_items(ctx.requested) {
+            match self.evaluate_item(ctx.plugin_name, capability, &item) {
+                PolicyAction::Allow => allowed = allowed.union(single),
+                PolicyAction::Deny => denied.push(format!("{} {}", capability.as_str(), item)),
+                PolicyAction::Prompt => match ctx.granted {
+                    Some(granted) if single.is_subset_of(granted) => {
+                        allowed = allowed.union(single)
+                    }
+                    _ => needs_prompt = true,
+                },
+            }
+        }
+
+        if denied.is_empty() {
+            if needs_prompt {
+                PermissionDecision::Prompt
+            } else {
+                PermissionDecision::Allow
+            }
+        } else if needs_prompt || allowed.is_empty() {
+            PermissionDecision::Deny(format!("Denied by policy: {}", denied.join(", ")))
+        } else {
+            PermissionDecision::AllowPartial(allowed)
+        }
+    }
+}
+
+impl PermissionStrategy for PolicyFileStrategy {
+    fn granularity(&self) -> PermissionGranularity {
+        PermissionGranularity::Plugin
+    }
+
+    fn inherit_capabilities(&self) -> bool {
+        false
+    }
+
+    fn check(&self, ctx: &PermissionContext) -> PermissionDecision {
+        self.decide(ctx)
+    }
+
+    fn on_escalation(&self, ctx: &PermissionContext) -> PermissionDecision {
+        // Escalated items are not covered by the grant, so rules decide them
+        self.decide(ctx)
+    }
+}
+
+fn parse_rule_value(value: &serde_json::Value) -> Result<PolicyRule, String> {
+    match value {
+        serde_json::Value::String(rule) => PolicyRule::parse(rule),
+        serde_json::Value::Object(map) => {
+            if let Some(key) = map.keys().find(|k| *k != "rule" && *k != "plugin") {
+                return Err(format!(
+                    "unknown field '{}' (expected 'rule' and optional 'plugin')",
+                    key
+                ));
+            }
+            let rule = match map.get("rule") {
+                Some(serde_json::Value::String(rule)) => PolicyRule::parse(rule)?,
+                Some(_) => return Err("'rule' must be a string".into()),
+                None => return Err("missing 'rule' field".into()),
+            };
+            match map.get("plugin") {
+                None => Ok(rule),
+                Some(serde_json::Value::String(p)) if !p.is_empty() => Ok(rule.for_plugin(p)),
+                Some(_) => Err("'plugin' must be a non-empty string".into()),
+            }
+        }
+        _ => Err("expected a rule string or an object with 'rule'".into()),
+    }
+}
+
+/// Flatten capabilities into (kind, item, single-item capabilities)
+fn requested_items(caps: &Capabilities) -> Vec<(PolicyCapability, Item, Capabilities)> {
+    let mut items = Vec::new();
+
+    for p in &caps.fs_read {
+        items.push((
+            PolicyCapability::FsRead,
+            Item::Path(p.clone()),
+            Capabilities::default().with_fs_read(vec![p.clone()]),
+        ));
+    }
+    for p in &caps.fs_write {
+        items.push((
+            PolicyCapability::FsWrite,
+            Item::Path(p.clone()),
+            Capabilities::default().with_fs_write(vec![p.clone()]),
+        ));
+    }
+    for e in &caps.env_read {
+        items.push((
+            PolicyCapability::EnvRead,
+            Item::Name(e.clone()),
+            Capabilities::default().with_env_read(vec![e.clone()]),
+        ));
+    }
+    for n in &caps.net {
+        items.push((
+            PolicyCapability::Net,
+            Item::Net(n.clone()),
+            Capabilities::default().with_net(vec![n.clone()]),
+        ));
+    }
+
+    let streams = [
+        ("stdin", caps.stdio.stdin),
+        ("stdout", caps.stdio.stdout),
+        ("stderr", caps.stdio.stderr),
+    ];
+    for (name, requested) in streams {
+        if requested {
+            let stdio = StdioCapability {
+                stdin: name == "stdin",
+                stdout: name == "stdout",
+                stderr: name == "stderr",
+            };
+            items.push((
+                PolicyCapability::Stdio,
+                Item::Name(name.to_string()),
+                Capabilities::default().with_stdio(stdio),
+            ));
+        }
+    }
+
+    for command in &caps.invoke {
+        items.push((
+            PolicyCapability::Invoke,
+            Item::Name(command.clone()),
+            Capabilities::default().with_invoke(vec![command.clone()]),
+        ));
+    }
+
+    if caps.tmp {
+        items.push((
+            PolicyCapability::Tmp,
+            Item::Name("/tmp".to_string()),
+            Capabilities::default().with_tmp(true),
+        ));
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sen_plugin_api::{NetPattern, PathPattern};
+
+    const POLICY: &str = r#"{
+        "version": 1,
+        "default": "prompt",
+        "rules": [
+            "allow fs_read ./data",
+            { "plugin": "uploader", "rule": "allow net *.example.com" },
+            "deny net *",
+            "prompt fs_write **",
+            "allow stdio *"
+        ]
+    }"#;
+
+    fn check(
+        strategy: &PolicyFileStrategy,
+        plugin: &str,
+        requested: &Capabilities,
+        granted: Option<&Capabilities>,
+    ) -> PermissionDecision {
+        strategy.check(&PermissionContext {
+            plugin_name: plugin,
+            command_path: &[],
+            requested,
+            granted,
+            interactive: true,
+        })
+    }
+
+    #[test]
+    fn test_path_rules_match_by_segment() {
+        let strategy = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "default": "deny", "rules": ["allow fs_read ./data/*.csv", "allow fs_read ./logs"] }"#,
+        )
+        .unwrap();
+        let eval = |path| strategy.evaluate("any", PolicyCapability::FsRead, path);
+
+        assert_eq!(eval("./data/a.csv"), PolicyAction::Allow);
+        assert_eq!(eval("data/a.csv"), PolicyAction::Allow);
+        // `*` stays within one directory
+        assert_eq!(eval("./data/sub/a.csv"), PolicyAction::Deny);
+        // A plain path covers its tree
+        assert_eq!(eval("./logs/2024/app.log"), PolicyAction::Allow);
+        // `..` is resolved before matching
+        assert_eq!(eval("./logs/../secret"), PolicyAction::Deny);
+        assert_eq!(eval("./data/../../etc/x.csv"), PolicyAction::Deny);
+        assert_eq!(eval("./data/x/../a.csv"), PolicyAction::Allow);
+    }
+
+    #[test]
+    fn test_deny_rules_catch_overlapping_globs() {
+        let strategy = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "rules": ["deny fs_read ./secret", "allow fs_read **"] }"#,
+        )
+        .unwrap();
+
+        for requested in ["./secret/*.key", "./**", "./secret/a/b"] {
+            let caps = Capabilities::default().with_fs_read(vec![PathPattern::new(requested)]);
+            assert!(
+                matches!(
+                    check(&strategy, "any", &caps, None),
+                    PermissionDecision::Deny(_)
+                ),
+                "{}",
+                requested
+            );
+        }
+        let caps = Capabilities::default().with_fs_read(vec![PathPattern::new("./data/*.csv")]);
+        assert_eq!(
+            check(&strategy, "any", &caps, None),
+            PermissionDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_net_rules_check_protocol_and_port() {
+        let strategy = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "default": "deny", "rules": [
+                "deny net tcp://*:22",
+                "allow net https://api.example.com:443",
+                "allow net *.internal"
+            ] }"#,
+        )
+        .unwrap();
+        let eval = |item| strategy.evaluate("any", PolicyCapability::Net, item);
+
+        assert_eq!(eval("https://api.example.com:443"), PolicyAction::Allow);
+        assert_eq!(eval("api.example.com:443"), PolicyAction::Allow);
+        assert_eq!(eval("https://api.example.com:8443"), PolicyAction::Deny);
+        // No port requested means any port, which the rule does not cover
+        assert_eq!(eval("https://api.example.com"), PolicyAction::Deny);
+        assert_eq!(eval("http://api.example.com:443"), PolicyAction::Deny);
+        assert_eq!(eval("tcp://db.internal:5432"), PolicyAction::Allow);
+        // The deny overlaps a request for any port
+        assert_eq!(eval("tcp://db.internal"), PolicyAction::Deny);
+        assert_eq!(eval("tcp://db.internal:22"), PolicyAction::Deny);
+    }
+
+    #[test]
+    fn test_allow_rule() {
+        let strategy = PolicyFileStrategy::from_json(POLICY).unwrap();
+        let caps = Capabilities::default()
+            .with_fs_read(vec![PathPattern::new("./data").recursive()])
+            .with_stdio(StdioCapability::stdout_stderr());
+
+        assert_eq!(
+            check(&strategy, "any", &caps, None),
+            PermissionDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let strategy = PolicyFileStrategy::from_json(POLICY).unwrap();
+        let caps = Capabilities::default().with_net(vec![NetPattern::https("api.example.com")]);
+
+        // Scoped allow precedes the blanket deny
+        assert_eq!(
+            check(&strategy, "uploader", &caps, None),
+            PermissionDecision::Allow
+        );
+        // Other plugins fall through to the deny
+        assert!(matches!(
+            check(&strategy, "other", &caps, None),
+            PermissionDecision::Deny(msg) if msg.contains("net https://api.example.com")
+        ));
+    }
+
+    #[test]
+    fn test_invoke_rules() {
+        let strategy = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "default": "deny", "rules": ["allow invoke format*"] }"#,
+        )
+        .unwrap();
+
+        let caps = Capabilities::default().with_invoke(vec!["formatter".into()]);
+        assert_eq!(
+            check(&strategy, "pipeline", &caps, None),
+            PermissionDecision::Allow
+        );
+
+        let caps = Capabilities::default().with_invoke(vec!["deploy".into()]);
+        assert!(matches!(
+            check(&strategy, "pipeline", &caps, None),
+            PermissionDecision::Deny(msg) if msg.contains("invoke deploy")
+        ));
+    }
+
+    #[test]
+    fn test_tmp_rules() {
+        let strategy = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "default": "deny", "rules": ["allow tmp *"] }"#,
+        )
+        .unwrap();
+
+        let caps = Capabilities::default().with_tmp(true);
+        assert_eq!(
+            check(&strategy, "scratch", &caps, None),
+            PermissionDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_partial_allow_when_some_items_denied() {
+        let strategy = PolicyFileStrategy::from_json(POLICY).unwrap();
+        let caps = Capabilities::default()
+            .with_fs_read(vec![PathPattern::new("./data")])
+            .with_net(vec![NetPattern::https("evil.com")]);
+
+        match check(&strategy, "other", &caps, None) {
+            PermissionDecision::AllowPartial(allowed) => {
+                assert_eq!(allowed.fs_read, vec![PathPattern::new("./data")]);
+                assert!(allowed.net.is_empty());
+            }
+            other => panic!("Expected AllowPartial, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_prompt_and_grant_precedence() {
+        let strategy = PolicyFileStrategy::from_json(POLICY).unwrap();
+        let caps = Capabilities::default().with_fs_write(vec![PathPattern::new("./out")]);
+
+        assert_eq!(
+            check(&strategy, "any", &caps, None),
+            PermissionDecision::Prompt
+        );
+        // Already-granted items no longer prompt
+        assert_eq!(
+            check(&strategy, "any", &caps, Some(&caps)),
+            PermissionDecision::Allow
+        );
+
+        // Deny outranks prompt: nothing is partially allowed while prompting
+        let mixed = caps.clone().with_net(vec![NetPattern::https("evil.com")]);
+        assert!(matches!(
+            check(&strategy, "any", &mixed, None),
+            PermissionDecision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_default_action_applies_to_unmatched_items() {
+        let strategy =
+            PolicyFileStrategy::from_json(r#"{ "version": 1, "default": "deny" }"#).unwrap();
+        let caps = Capabilities::default().with_env_read(vec!["HOME".into()]);
+
+        assert!(matches!(
+            check(&strategy, "any", &caps, None),
+            PermissionDecision::Deny(msg) if msg.contains("env_read HOME")
+        ));
+        assert_eq!(
+            check(&strategy, "any", &Capabilities::none(), None),
+            PermissionDecision::Allow
+        );
+    }
+
+    #[test]
+    fn test_malformed_rules_report_position() {
+        let err = PolicyFileStrategy::from_json(
+            r#"{ "version": 1, "rules": ["allow fs_read ./data", "allw net *"] }"#,
+        )
+        .unwrap_err();
+        match err {
+            PolicyError::InvalidRule { index, reason, .. } => {
+                assert_eq!(index, 2);
+                assert!(reason.contains("unknown action 'allw'"));
+            }
+            other => panic!("Expected InvalidRule, got {:?}", other),
+        }
+
+        let cases = [
+            (r#""deny net""#, "found 2 part(s)"),
+            (r#""allow disk *""#, "unknown capability 'disk'"),
+            (r#""allow stdio tty""#, "invalid stdio pattern 'tty'"),
+            (r#""allow fs_read ./data/../etc""#, "'..' is not allowed"),
+            (
+                r#""allow net api.*.com""#,
+                "invalid host pattern 'api.*.com'",
+            ),
+            (r#""allow net ftp://example.com""#, "unknown protocol 'ftp'"),
+            (r#""allow net example.com:http""#, "invalid port 'http'"),
+            (
+                r#"{ "rule": "deny net *", "user": "bob" }"#,
+                "unknown field 'user'",
+            ),
+            (r#"{ "plugin": "x" }"#, "missing 'rule' field"),
+            ("42", "expected a rule string"),
+        ];
+        for (rule, expected) in cases {
+            let json = format!(r#"{{ "version": 1, "rules": [{}] }}"#, rule);
+            let err = PolicyFileStrategy::from_json(&json).unwrap_err();
+            assert!(err.to_string().contains(expected), "{} -> {}", rule, err);
+        }
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        assert!(matches!(
+            PolicyFileStrategy::from_json(r#"{ "version": 2 }"#),
+            Err(PolicyError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            PolicyFileStrategy::from_json(r#"{ "version": 1, "default": "maybe" }"#),
+            Err(PolicyError::Parse(_))
+        ));
+        assert!(matches!(
+            PolicyFileStrategy::from_json(r#"{ "version": 1, "rulez": [] }"#),
+            Err(PolicyError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(&path, POLICY).unwrap();
+
+        let strategy = PolicyFileStrategy::from_file(&path).unwrap();
+        assert_eq!(strategy.rules().len(), 5);
+        assert_eq!(strategy.default_action(), PolicyAction::Prompt);
+
+        assert!(matches!(
+            PolicyFileStrategy::from_file(dir.path().join("missing.json")),
+            Err(PolicyError::Read { .. })
+        ));
+        assert!(matches!(
+            PolicyFileStrategy::from_file(dir.path().join("policy.ini")),
+            Err(PolicyError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_yaml_and_toml_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let yaml = dir.path().join("policy.yaml");
+        std::fs::write(
+            &yaml,
+            r#"
+version: 1
+default: deny
+rules:
+  - allow fs_read ./data
+  - { plugin: uploader, rule: "allow net *.example.com" }
+"#,
+        )
+        .unwrap();
+        let toml = dir.path().join("policy.toml");
+        std::fs::write(
+            &toml,
+            r#"
+version = 1
+default = "deny"
+rules = [
+    "allow fs_read ./data",
+    { plugin = "uploader", rule = "allow net *.example.com" },
+]
+"#,
+        )
+        .unwrap();
+
+        for path in [yaml, toml] {
+            let strategy = PolicyFileStrategy::from_file(&path).unwrap();
+            assert_eq!(strategy.default_action(), PolicyAction::Deny);
+            assert_eq!(strategy.rules()[1].plugin.as_deref(), Some("uploader"));
+            assert_eq!(
+                strategy.evaluate("uploader", PolicyCapability::Net, "api.example.com"),
+                PolicyAction::Allow
+            );
+        }
+
+        let err = PolicyFileStrategy::from_yaml("version: 1\nrules: [allow disk x]").unwrap_err();
+        assert!(err.to_string().contains("unknown capability 'disk'"));
+    }
+}
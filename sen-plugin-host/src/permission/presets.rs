@@ -3,12 +3,15 @@
 //! Provides ready-to-use configurations that framework users can use
 //! directly or as starting points for customization.
 
-use std::path::PathBuf;
+use sen_plugin_api::Capabilities;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use super::policy::PolicyFileStrategy;
 use super::prompt::{AutoPromptHandler, PromptHandler, TerminalPromptHandler};
 use super::store::{
-    FilePermissionStore, MemoryPermissionStore, PermissionStore, ReadOnlyPermissionStore,
+    FilePermissionStore, MemoryPermissionStore, PermissionStore, PregrantedPermissionStore,
+    ReadOnlyPermissionStore,
 };
 use super::strategy::{
     CiPermissionStrategy, DefaultPermissionStrategy, PermissionStrategy,
@@ -56,6 +59,32 @@ impl PermissionConfig {
             trust_flags,
         }
     }
+
+    /// Pre-grant capabilities to plugins without prompting
+    ///
+    /// Each plugin is allowed to run with exactly the given capabilities, as
+    /// if the user had approved them. A plugin declaring a different set is
+    /// treated as an escalation. Pre-grants are held in memory and never
+    /// written to the store, so they combine with read-only CI stores:
+    ///
+    /// ```rust,ignore
+    /// let config = PermissionPresets::ci("myapp", None)?
+    ///     .with_pregrants([("hello", Capabilities::default().with_stdio(StdioCapability::stdout_only()))]);
+    /// ```
+    pub fn with_pregrants<I, P>(mut self, grants: I) -> Self
+    where
+        I: IntoIterator<Item = (P, Capabilities)>,
+        P: AsRef<str>,
+    {
+        let granularity = self.strategy.granularity();
+        let mut store = PregrantedPermissionStore::new(self.store.clone());
+        for (plugin, capabilities) in grants {
+            let key = self.store.make_key(plugin.as_ref(), None, granularity);
+            store = store.grant(key, capabilities);
+        }
+        self.store = Arc::new(store);
+        self
+    }
 }
 
 /// Builder for permission configurations
@@ -66,6 +95,7 @@ pub struct PermissionConfigBuilder {
     audit: Option<Arc<dyn AuditSink>>,
     trust_flags: TrustFlagConfig,
     app_name: Option<String>,
+    pregrants: Vec<(String, Capabilities)>,
 }
 
 impl PermissionConfigBuilder {
@@ -78,6 +108,7 @@ impl PermissionConfigBuilder {
             audit: None,
             trust_flags: TrustFlagConfig::default(),
             app_name: None,
+            pregrants: Vec::new(),
         }
     }
 
@@ -117,6 +148,12 @@ impl PermissionConfigBuilder {
         self
     }
 
+    /// Pre-grant capabilities to a plugin (see [`PermissionConfig::with_pregrants`])
+    pub fn pregrant(mut self, plugin: impl Into<String>, capabilities: Capabilities) -> Self {
+        self.pregrants.push((plugin.into(), capabilities));
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> Result<PermissionConfig, PresetError> {
         let app_name = self.app_name.as_deref().unwrap_or("plugin-host");
@@ -130,7 +167,7 @@ impl PermissionConfigBuilder {
             }
         };
 
-        Ok(PermissionConfig {
+        let config = PermissionConfig {
             strategy: self
                 .strategy
                 .unwrap_or_else(|| Arc::new(DefaultPermissionStrategy)),
@@ -140,7 +177,13 @@ impl PermissionConfigBuilder {
                 .unwrap_or_else(|| Arc::new(TerminalPromptHandler::new())),
             audit: self.audit.unwrap_or_else(|| Arc::new(NullAuditSink)),
             trust_flags: self.trust_flags,
-        })
+        };
+
+        if self.pregrants.is_empty() {
+            Ok(config)
+        } else {
+            Ok(config.with_pregrants(self.pregrants))
+        }
     }
 }
 
@@ -259,6 +302,42 @@ impl PermissionPresets {
         })
     }
 
+    /// Policy-file governed mode
+    ///
+    /// - Policy file strategy (rules from `policy_path`)
+    /// - File-based storage
+    /// - Terminal prompts for `prompt` rules
+    /// - File-based audit log
+    /// - Standard trust flags
+    ///
+    /// Returns [`PresetError::InvalidConfig`] if the policy file is missing
+    /// or malformed.
+    pub fn policy(
+        app_name: &str,
+        policy_path: impl AsRef<Path>,
+    ) -> Result<PermissionConfig, PresetError> {
+        let strategy = PolicyFileStrategy::from_file(policy_path)
+            .map_err(|e| PresetError::InvalidConfig(e.to_string()))?;
+
+        let config_dir = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from(".config"))
+            .join(app_name);
+
+        let store = FilePermissionStore::new(config_dir.join("permissions.json"))
+            .map_err(|e| PresetError::StoreInit(e.to_string()))?;
+
+        let audit = FileAuditSink::new(config_dir.join("audit.jsonl"))
+            .map_err(|e| PresetError::AuditInit(e.to_string()))?;
+
+        Ok(PermissionConfig {
+            strategy: Arc::new(strategy),
+            store: Arc::new(store),
+            prompt: Arc::new(TerminalPromptHandler::new()),
+            audit: Arc::new(audit),
+            trust_flags: TrustFlagPresets::standard(),
+        })
+    }
+
     /// Permissive development mode
     ///
     /// - Permissive strategy (allows non-network without prompt)
@@ -347,6 +426,16 @@ mod tests {
         assert!(!config.prompt.is_interactive());
     }
 
+    #[test]
+    fn test_policy_preset_rejects_malformed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(&path, r#"{ "version": 1, "rules": ["allow everything"] }"#).unwrap();
+
+        let err = PermissionPresets::policy("test-app", &path).unwrap_err();
+        assert!(matches!(err, PresetError::InvalidConfig(msg) if msg.contains("rule #1")));
+    }
+
     #[test]
     fn test_trust_all_dangerous() {
         let config = PermissionPresets::trust_all_dangerous();
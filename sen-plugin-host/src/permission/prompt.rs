@@ -183,6 +183,15 @@ impl TerminalPromptHandler {
             lines.push("  - Write to stderr".to_string());
         }
 
+        if !caps.invoke.is_empty() {
+            let commands = caps.invoke.join(", ");
+            lines.push(format!("  - Invoke plugins: {}", commands));
+        }
+
+        if caps.tmp {
+            lines.push("  - Use a private temp directory".to_string());
+        }
+
         lines.join("\n")
     }
 }
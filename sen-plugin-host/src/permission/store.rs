@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
 use super::strategy::PermissionGranularity;
@@ -22,6 +22,17 @@ pub enum StoreError {
     #[error("Failed to parse permission store: {0}")]
     ParseError(#[from] serde_json::Error),
 
+    #[error(
+        "Permission store {} has format version {found}, but only versions up to {supported} \
+         can be read; delete the file to reset stored permissions",
+        path.display()
+    )]
+    IncompatibleVersion {
+        path: PathBuf,
+        found: u64,
+        supported: u32,
+    },
+
     #[error("Permission not found for: {0}")]
     NotFound(String),
 
@@ -119,6 +130,12 @@ pub trait PermissionStore: Send + Sync {
 // File-based Permission Store
 // ============================================================================
 
+/// Format version written by [`FilePermissionStore`]
+///
+/// Bump this when the file layout or [`StoredPermission`] changes, and add a
+/// step to [`PermissionFileData::migrate`] that upgrades the previous version.
+const PERMISSION_FILE_VERSION: u32 = 1;
+
 /// Persistent file data structure
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct PermissionFileData {
@@ -129,10 +146,34 @@ struct PermissionFileData {
 impl PermissionFileData {
     fn new() -> Self {
         Self {
-            version: 1,
+            version: PERMISSION_FILE_VERSION,
             plugins: HashMap::new(),
         }
     }
+
+    /// Read file contents of any supported version, upgrading them to
+    /// [`PERMISSION_FILE_VERSION`]
+    ///
+    /// A file without a `version` field is treated as version 1.
+    fn migrate(path: &Path, mut value: serde_json::Value) -> Result<Self, StoreError> {
+        let version = match value.get("version") {
+            Some(version) => serde_json::from_value::<u64>(version.clone())?,
+            None => 1,
+        };
+        if version == 0 || version > u64::from(PERMISSION_FILE_VERSION) {
+            return Err(StoreError::IncompatibleVersion {
+                path: path.to_path_buf(),
+                found: version,
+                supported: PERMISSION_FILE_VERSION,
+            });
+        }
+
+        // Upgrade steps go here, one per version, e.g.
+        // `if version < 2 { value = migrate_v1_to_v2(value); }`
+
+        value["version"] = PERMISSION_FILE_VERSION.into();
+        Ok(serde_json::from_value(value)?)
+    }
 }
 
 /// File-based permission store
@@ -152,7 +193,7 @@ impl FilePermissionStore {
         let data = if path.exists() {
             let file = File::open(&path)?;
             let reader = BufReader::new(file);
-            serde_json::from_reader(reader)?
+            PermissionFileData::migrate(&path, serde_json::from_reader(reader)?)?
         } else {
             PermissionFileData::new()
         };
@@ -164,6 +205,9 @@ impl FilePermissionStore {
     }
 
     /// Create a store in the default location for an application
+    ///
+    /// The file is `permissions.json` in the platform config directory, the
+    /// same directory as `sen::paths::config_dir(app_name)`.
     pub fn default_for_app(app_name: &str) -> Result<Self, StoreError> {
         let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from(".config"));
         let path = config_dir.join(app_name).join("permissions.json");
@@ -398,10 +442,95 @@ impl<S: PermissionStore + std::fmt::Debug> std::fmt::Debug for ReadOnlyPermissio
     }
 }
 
+// ============================================================================
+// Pre-granted Permission Store
+// ============================================================================
+
+/// Wrapper that serves pre-granted permissions ahead of any permission store
+///
+/// Pre-grants come from configuration (e.g. a CI setup) rather than from a
+/// prompt. They take precedence over the inner store and are never written to
+/// it, so they also work on top of a `ReadOnlyPermissionStore`. Writes go to
+/// the inner store as usual.
+pub struct PregrantedPermissionStore {
+    inner: Arc<dyn PermissionStore>,
+    grants: HashMap<String, StoredPermission>,
+}
+
+impl PregrantedPermissionStore {
+    /// Wrap `inner` with no pre-grants
+    pub fn new(inner: Arc<dyn PermissionStore>) -> Self {
+        Self {
+            inner,
+            grants: HashMap::new(),
+        }
+    }
+
+    /// Pre-grant `capabilities` under a storage key
+    pub fn grant(mut self, key: impl Into<String>, capabilities: Capabilities) -> Self {
+        self.grants.insert(
+            key.into(),
+            StoredPermission::new(capabilities, StoredTrustLevel::Session),
+        );
+        self
+    }
+}
+
+impl PermissionStore for PregrantedPermissionStore {
+    fn get(&self, key: &str) -> Result<Option<StoredPermission>, StoreError> {
+        match self.grants.get(key) {
+            Some(permission) => Ok(Some(permission.clone())),
+            None => self.inner.get(key),
+        }
+    }
+
+    fn set(&self, key: &str, permission: StoredPermission) -> Result<(), StoreError> {
+        self.inner.set(key, permission)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.remove(key)
+    }
+
+    fn list(&self) -> Result<Vec<(String, StoredPermission)>, StoreError> {
+        let mut list: Vec<_> = self
+            .inner
+            .list()?
+            .into_iter()
+            .filter(|(key, _)| !self.grants.contains_key(key))
+            .collect();
+        list.extend(self.grants.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(list)
+    }
+
+    fn clear(&self) -> Result<(), StoreError> {
+        self.inner.clear()
+    }
+
+    fn make_key(
+        &self,
+        plugin: &str,
+        command: Option<&str>,
+        granularity: PermissionGranularity,
+    ) -> String {
+        self.inner.make_key(plugin, command, granularity)
+    }
+}
+
+impl std::fmt::Debug for PregrantedPermissionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<_> = self.grants.keys().collect();
+        keys.sort();
+        f.debug_struct("PregrantedPermissionStore")
+            .field("grants", &keys)
+            .finish_non_exhaustive()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sen_plugin_api::PathPattern;
+    use sen_plugin_api::{PathPattern, StdioCapability};
 
     #[test]
     fn test_memory_store() {
@@ -465,6 +594,34 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_pregranted_store_overlays_read_only_store() {
+        let inner = MemoryPermissionStore::new();
+        let stale = Capabilities::default().with_fs_read(vec![PathPattern::new("./old")]);
+        inner
+            .set(
+                "hello",
+                StoredPermission::new(stale, StoredTrustLevel::Permanent),
+            )
+            .unwrap();
+
+        let caps = Capabilities::default().with_stdio(StdioCapability::stdout_only());
+        let store = PregrantedPermissionStore::new(Arc::new(ReadOnlyPermissionStore::new(inner)))
+            .grant("hello", caps.clone());
+
+        // Pre-grant wins over the stored permission
+        let retrieved = store.get("hello").unwrap().unwrap();
+        assert!(!retrieved.has_escalated(&caps));
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        // Writes still go to the (read-only) inner store
+        let new_perm = StoredPermission::new(Capabilities::none(), StoredTrustLevel::Session);
+        assert!(matches!(
+            store.set("other", new_perm),
+            Err(StoreError::ReadOnly)
+        ));
+    }
+
     #[test]
     fn test_escalation_detection() {
         let caps1 = Capabilities::default().with_fs_read(vec![PathPattern::new("./data")]);
@@ -494,4 +651,70 @@ mod tests {
         let key = store.make_key("hello", None, PermissionGranularity::Command);
         assert_eq!(key, "hello");
     }
+
+    /// A file as written by format version 1. Kept verbatim so changes to
+    /// `StoredPermission` must come with a migration.
+    const V1_FILE: &str = r#"{
+  "version": 1,
+  "plugins": {
+    "hello:greet": {
+      "granted_at": 1718000000,
+      "capabilities_hash": "0123456789abcdef",
+      "capabilities": {
+        "fs_read": [{ "pattern": "./data", "recursive": true }],
+        "env_read": ["HOME"]
+      },
+      "trust_level": "permanent"
+    }
+  }
+}"#;
+
+    #[test]
+    fn test_v1_file_grants_survive_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("permissions.json");
+        std::fs::write(&path, V1_FILE).unwrap();
+
+        let store = FilePermissionStore::new(&path).unwrap();
+        let perm = store.get("hello:greet").unwrap().unwrap();
+        assert_eq!(perm.granted_at, 1718000000);
+        assert_eq!(perm.capabilities_hash, "0123456789abcdef");
+        assert_eq!(perm.capabilities.fs_read[0].pattern, "./data");
+        assert_eq!(perm.capabilities.env_read, ["HOME"]);
+        assert_eq!(perm.trust_level, StoredTrustLevel::Permanent);
+
+        // Saving writes the current version and keeps the grant
+        store.remove("other").unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], PERMISSION_FILE_VERSION);
+        let store = FilePermissionStore::new(&path).unwrap();
+        assert!(store.get("hello:greet").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_unversioned_file_is_read_as_v1() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("permissions.json");
+        let mut file: serde_json::Value = serde_json::from_str(V1_FILE).unwrap();
+        file.as_object_mut().unwrap().remove("version");
+        std::fs::write(&path, file.to_string()).unwrap();
+
+        let store = FilePermissionStore::new(&path).unwrap();
+        assert!(store.get("hello:greet").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_newer_version_is_incompatible() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("permissions.json");
+        std::fs::write(&path, r#"{ "version": 99, "grants": [] }"#).unwrap();
+
+        let err = FilePermissionStore::new(&path).unwrap_err();
+        assert!(matches!(
+            err,
+            StoreError::IncompatibleVersion { found: 99, .. }
+        ));
+        assert!(err.to_string().contains("delete the file to reset"));
+    }
 }
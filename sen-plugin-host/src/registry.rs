@@ -21,14 +21,30 @@
 //! ```
 
 use crate::audit::{self, TrustLevel};
+use crate::loader::decode_plugin_file;
 use crate::permission::{
     PermissionConfig, PermissionContext, PermissionDecision, StoredPermission, StoredTrustLevel,
 };
-use crate::{LoadedPlugin, LoaderError, PluginLoader};
+use crate::{
+    EffectHandler, LoadedPlugin, LoaderConfig, LoaderError, PluginInstance, PluginLoader,
+    PluginStats,
+};
+use sen_plugin_api::{Capabilities, Effect, EffectResult, ExecuteResult, PluginManifest};
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
+
+/// Longest chain of plugins calling each other via `Effect::InvokePlugin`
+pub const MAX_INVOKE_DEPTH: usize = 8;
+
+/// Events buffered per subscriber before the oldest are dropped
+const EVENT_CAPACITY: usize = 256;
+
+/// Maps a plugin's declared capabilities to the ones it is allowed
+type CapabilityPolicy = Arc<dyn Fn(&str, &Capabilities) -> Capabilities + Send + Sync>;
 
 /// A thread-safe registry for managing loaded plugins
 #[derive(Clone)]
@@ -36,6 +52,21 @@ pub struct PluginRegistry {
     inner: Arc<RwLock<RegistryInner>>,
     loader: Arc<PluginLoader>,
     permission: Option<Arc<PermissionConfig>>,
+    capability_policy: Option<CapabilityPolicy>,
+    events: broadcast::Sender<RegistryEvent>,
+}
+
+/// A registry lifecycle event, as seen by [`PluginRegistry::subscribe`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    /// A plugin was loaded, reloaded, or registered
+    Loaded { command: String },
+    /// A plugin was removed
+    Unloaded { command: String },
+    /// A plugin ran; `success` is false if it returned an error or trapped
+    Executed { command: String, success: bool },
+    /// The permission check refused to run a plugin
+    Denied { command: String, reason: String },
 }
 
 struct RegistryInner {
@@ -46,8 +77,34 @@ struct RegistryInner {
 }
 
 struct PluginEntry {
-    plugin: LoadedPlugin,
+    manifest: PluginManifest,
+    /// Locked while the plugin runs, so the registry itself is not
+    instance: Arc<Mutex<PluginInstance>>,
     source_path: Option<PathBuf>,
+    /// Permission configuration overriding the registry's for this plugin
+    permission: Option<Arc<PermissionConfig>>,
+}
+
+impl PluginEntry {
+    fn new(
+        plugin: LoadedPlugin,
+        source_path: Option<PathBuf>,
+        permission: Option<Arc<PermissionConfig>>,
+    ) -> Self {
+        Self {
+            manifest: plugin.manifest,
+            instance: Arc::new(Mutex::new(plugin.instance)),
+            source_path,
+            permission,
+        }
+    }
+}
+
+/// What a call needs from a [`PluginEntry`], taken out of the registry
+struct Checkout {
+    instance: Arc<Mutex<PluginInstance>>,
+    capabilities: Capabilities,
+    permission: Option<Arc<PermissionConfig>>,
 }
 
 impl PluginRegistry {
@@ -60,6 +117,8 @@ impl PluginRegistry {
             })),
             loader: Arc::new(PluginLoader::new()?),
             permission: None,
+            capability_policy: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
         })
     }
 
@@ -72,6 +131,8 @@ impl PluginRegistry {
             })),
             loader: Arc::new(loader),
             permission: None,
+            capability_policy: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
@@ -87,6 +148,8 @@ impl PluginRegistry {
             })),
             loader: Arc::new(PluginLoader::new()?),
             permission: Some(Arc::new(config)),
+            capability_policy: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
         })
     }
 
@@ -95,53 +158,156 @@ impl PluginRegistry {
         self.permission = Some(Arc::new(config));
     }
 
+    /// Limit plugin capabilities by an external policy
+    ///
+    /// `policy` receives the command name and the capabilities declared in
+    /// the manifest, and returns the capabilities the plugin actually gets.
+    /// It runs when a plugin is loaded or registered, so permission checks,
+    /// `invoke` checks, and [`get_manifest`](Self::get_manifest) all see the
+    /// result. A manifest can't grant itself more than the policy returns.
+    ///
+    /// Plugins already registered are not affected; set the policy first.
+    ///
+    /// ```rust,ignore
+    /// let registry = PluginRegistry::new()?.with_capability_policy(|command, declared| {
+    ///     policy_file.allowed(command).intersect(declared)
+    /// });
+    /// ```
+    pub fn with_capability_policy(
+        mut self,
+        policy: impl Fn(&str, &Capabilities) -> Capabilities + Send + Sync + 'static,
+    ) -> Self {
+        self.capability_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Replace `plugin`'s declared capabilities with what the policy allows
+    fn apply_capability_policy(&self, plugin: &mut LoadedPlugin) {
+        if let Some(policy) = &self.capability_policy {
+            let manifest = &mut plugin.manifest;
+            manifest.capabilities = policy(&manifest.command.name, &manifest.capabilities);
+        }
+    }
+
+    /// Subscribe to registry lifecycle events
+    ///
+    /// Events are broadcast without waiting for subscribers. A subscriber
+    /// that falls more than 256 events behind skips the oldest ones and gets
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) once.
+    ///
+    /// ```rust,ignore
+    /// let mut events = registry.subscribe();
+    /// tokio::spawn(async move {
+    ///     loop {
+    ///         match events.recv().await {
+    ///             Ok(event) => dashboard.record(event),
+    ///             Err(RecvError::Lagged(skipped)) => dashboard.note_gap(skipped),
+    ///             Err(RecvError::Closed) => break,
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send `event` to current subscribers, if any
+    fn emit(&self, event: RegistryEvent) {
+        // Fails only when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
     /// Load and register a plugin from a file path
     pub async fn load_plugin(&self, path: impl AsRef<Path>) -> Result<String, LoaderError> {
-        let path = path.as_ref();
+        self.load_plugin_with(path, &self.loader).await
+    }
+
+    /// Load and register a plugin from a file path using a specific loader
+    ///
+    /// Use this when a plugin needs limits other than the registry's default
+    /// loader, e.g. a tighter fuel limit for user-supplied plugins.
+    pub async fn load_plugin_with(
+        &self,
+        path: impl AsRef<Path>,
+        loader: &PluginLoader,
+    ) -> Result<String, LoaderError> {
+        self.load_plugin_from(path.as_ref(), Some(loader), None)
+            .await
+    }
+
+    /// Load a plugin with an optional loader and permission override
+    ///
+    /// `None` falls back to the registry's default loader and permission
+    /// configuration respectively.
+    pub(crate) async fn load_plugin_from(
+        &self,
+        path: &Path,
+        loader: Option<&PluginLoader>,
+        permission: Option<Arc<PermissionConfig>>,
+    ) -> Result<String, LoaderError> {
+        let loader = loader.unwrap_or(&self.loader);
         let wasm_bytes = tokio::fs::read(path).await.map_err(|e| {
             LoaderError::MemoryAccess(format!("Failed to read file {}: {}", path.display(), e))
         })?;
 
-        let plugin = self.loader.load(&wasm_bytes)?;
+        let wasm_bytes =
+            decode_plugin_file(path, wasm_bytes, loader.config().max_decompressed_bytes)?;
+
+        let mut plugin = loader.load(&wasm_bytes)?;
+        self.apply_capability_policy(&mut plugin);
         let command_name = plugin.manifest.command.name.clone();
 
         let mut inner = self.inner.write().await;
 
         // Remove old mapping if exists
-        if let Some(old_cmd) = inner.path_to_command.remove(path) {
-            inner.plugins.remove(&old_cmd);
-        }
+        let renamed = inner
+            .path_to_command
+            .remove(path)
+            .and_then(|old_cmd| Some((inner.plugins.remove(&old_cmd)?, old_cmd)));
 
         // Add new mappings
         inner
             .path_to_command
             .insert(path.to_path_buf(), command_name.clone());
-        inner.plugins.insert(
+        let replaced = inner.plugins.insert(
             command_name.clone(),
-            PluginEntry {
-                plugin,
-                source_path: Some(path.to_path_buf()),
-            },
+            PluginEntry::new(plugin, Some(path.to_path_buf()), permission),
         );
+        drop(inner);
+
+        if let Some((old, old_cmd)) = renamed {
+            shutdown(&old_cmd, old).await;
+        }
+        if let Some(old) = replaced {
+            shutdown(&command_name, old).await;
+        }
 
         tracing::info!(command = %command_name, path = %path.display(), "Plugin loaded");
+        self.emit(RegistryEvent::Loaded {
+            command: command_name.clone(),
+        });
         Ok(command_name)
     }
 
     /// Register a pre-loaded plugin (without file path tracking)
-    pub async fn register(&self, plugin: LoadedPlugin) -> String {
+    pub async fn register(&self, mut plugin: LoadedPlugin) -> String {
+        self.apply_capability_policy(&mut plugin);
         let command_name = plugin.manifest.command.name.clone();
 
-        let mut inner = self.inner.write().await;
-        inner.plugins.insert(
-            command_name.clone(),
-            PluginEntry {
-                plugin,
-                source_path: None,
-            },
-        );
+        let replaced = self
+            .inner
+            .write()
+            .await
+            .plugins
+            .insert(command_name.clone(), PluginEntry::new(plugin, None, None));
+        if let Some(old) = replaced {
+            shutdown(&command_name, old).await;
+        }
 
         tracing::info!(command = %command_name, "Plugin registered");
+        self.emit(RegistryEvent::Loaded {
+            command: command_name.clone(),
+        });
         command_name
     }
 
@@ -151,8 +317,15 @@ impl PluginRegistry {
         let mut inner = self.inner.write().await;
 
         if let Some(command_name) = inner.path_to_command.remove(path) {
-            inner.plugins.remove(&command_name);
+            let entry = inner.plugins.remove(&command_name);
+            drop(inner);
+            if let Some(entry) = entry {
+                shutdown(&command_name, entry).await;
+            }
             tracing::info!(command = %command_name, path = %path.display(), "Plugin unloaded");
+            self.emit(RegistryEvent::Unloaded {
+                command: command_name.clone(),
+            });
             Some(command_name)
         } else {
             None
@@ -164,21 +337,67 @@ impl PluginRegistry {
         let mut inner = self.inner.write().await;
 
         if let Some(entry) = inner.plugins.remove(command_name) {
-            if let Some(path) = entry.source_path {
-                inner.path_to_command.remove(&path);
+            if let Some(ref path) = entry.source_path {
+                inner.path_to_command.remove(path);
             }
+            drop(inner);
+            shutdown(command_name, entry).await;
             tracing::info!(command = %command_name, "Plugin unloaded");
+            self.emit(RegistryEvent::Unloaded {
+                command: command_name.to_string(),
+            });
             true
         } else {
             false
         }
     }
 
+    /// Unload every plugin, calling each one's `plugin_shutdown`
+    ///
+    /// Dropping the registry frees plugins without running guest code, so
+    /// call this first if their shutdown hooks matter. Returns the unloaded
+    /// command names.
+    pub async fn unload_all(&self) -> Vec<String> {
+        let entries: Vec<(String, PluginEntry)> = {
+            let mut inner = self.inner.write().await;
+            inner.path_to_command.clear();
+            inner.plugins.drain().collect()
+        };
+
+        let mut commands = Vec::with_capacity(entries.len());
+        for (command_name, entry) in entries {
+            shutdown(&command_name, entry).await;
+            tracing::info!(command = %command_name, "Plugin unloaded");
+            self.emit(RegistryEvent::Unloaded {
+                command: command_name.clone(),
+            });
+            commands.push(command_name);
+        }
+        commands
+    }
+
     /// Reload a plugin from its source path
     pub async fn reload_by_path(&self, path: impl AsRef<Path>) -> Result<String, LoaderError> {
         self.load_plugin(path).await
     }
 
+    /// Get the loader configuration a command was loaded with
+    pub async fn loader_config(&self, command_name: &str) -> Option<LoaderConfig> {
+        let instance = self.checkout(command_name).await.ok()?.instance;
+        let config = instance.lock().await.config().clone();
+        Some(config)
+    }
+
+    /// Get the resource usage of a command's most recent execution
+    ///
+    /// Returns `None` if the command is not registered. Before the first
+    /// execution the fuel count is zero.
+    pub async fn last_stats(&self, command_name: &str) -> Option<PluginStats> {
+        let instance = self.checkout(command_name).await.ok()?.instance;
+        let stats = instance.lock().await.stats();
+        Some(stats)
+    }
+
     /// Get a list of all registered command names
     pub async fn list_commands(&self) -> Vec<String> {
         let inner = self.inner.read().await;
@@ -204,158 +423,426 @@ impl PluginRegistry {
         command_name: &str,
         args: &[String],
     ) -> Result<sen_plugin_api::ExecuteResult, RegistryError> {
-        let mut inner = self.inner.write().await;
+        let plugin = self.checkout(command_name).await?;
+        self.check_permission(
+            command_name,
+            plugin.permission.as_deref(),
+            &plugin.capabilities,
+        )?;
+
+        let result = plugin
+            .instance
+            .lock()
+            .await
+            .execute(args)
+            .map_err(RegistryError::Execution);
+        self.emit(RegistryEvent::Executed {
+            command: command_name.to_string(),
+            success: matches!(result, Ok(ExecuteResult::Success(_))),
+        });
+        result
+    }
 
+    /// Clone what a call to `command_name` needs, releasing the registry lock
+    async fn checkout(&self, command_name: &str) -> Result<Checkout, RegistryError> {
+        let inner = self.inner.read().await;
         let entry = inner
             .plugins
-            .get_mut(command_name)
+            .get(command_name)
             .ok_or_else(|| RegistryError::CommandNotFound(command_name.to_string()))?;
+        Ok(Checkout {
+            instance: Arc::clone(&entry.instance),
+            capabilities: entry.manifest.capabilities.clone(),
+            permission: entry.permission.clone(),
+        })
+    }
 
-        // Check permissions if configured
-        if let Some(ref perm_config) = self.permission {
-            let capabilities = &entry.plugin.manifest.capabilities;
-
-            // Record permission request audit event
-            let _ = perm_config
-                .audit
-                .record(audit::permission_requested(command_name, capabilities));
-
-            // Get stored permission
-            let key =
-                perm_config
-                    .store
-                    .make_key(command_name, None, perm_config.strategy.granularity());
-            let stored = perm_config.store.get(&key).ok().flatten();
-
-            // Build context for strategy
-            let ctx = PermissionContext {
-                plugin_name: command_name,
-                command_path: &[],
-                requested: capabilities,
-                granted: stored.as_ref().map(|s| &s.capabilities),
-                interactive: perm_config.prompt.is_interactive(),
-            };
+    /// Execute a plugin command and drive its effect loop to completion
+    ///
+    /// Like [`PluginInstance::execute_with_effects`](crate::PluginInstance::execute_with_effects),
+    /// except that `Effect::InvokePlugin` is handled here: the named plugin
+    /// is executed (with its own permission check and effect loop) and the
+    /// caller is resumed with `EffectResult::PluginOutput`. The caller is
+    /// resumed with `EffectResult::Error` instead when:
+    ///
+    /// - the command is missing from the caller's `invoke` capability
+    /// - the command is already running in this chain (a cycle)
+    /// - the chain would exceed [`MAX_INVOKE_DEPTH`] plugins
+    /// - the invoked plugin fails
+    ///
+    /// The registry is only locked to look plugins up, so other commands can
+    /// run, and plugins be loaded or unloaded, while an effect is handled.
+    /// Each plugin's own instance stays locked until its call finishes; an
+    /// invoked plugin that is busy with another call fails instead of
+    /// waiting, since waiting could deadlock two chains calling each other.
+    ///
+    /// ```rust,ignore
+    /// let result = registry
+    ///     .execute_with_effects("compose", &["World".into()], &handler)
+    ///     .await?;
+    /// ```
+    pub async fn execute_with_effects(
+        &self,
+        command_name: &str,
+        args: &[String],
+        handler: &dyn EffectHandler,
+    ) -> Result<ExecuteResult, RegistryError> {
+        self.drive_effects(command_name, args, handler, &[]).await
+    }
 
-            // Check for escalation
-            let decision = if let Some(ref stored_perm) = stored {
-                if stored_perm.has_escalated(capabilities) {
-                    // Record escalation audit event
-                    let _ = perm_config.audit.record(audit::escalation_detected(
-                        command_name,
-                        &stored_perm.capabilities,
-                        capabilities,
-                    ));
-                    perm_config.strategy.on_escalation(&ctx)
-                } else {
-                    perm_config.strategy.check(&ctx)
+    /// Run `command_name` to a terminal result; `chain` lists its callers
+    fn drive_effects<'a>(
+        &'a self,
+        command_name: &'a str,
+        args: &'a [String],
+        handler: &'a dyn EffectHandler,
+        chain: &'a [String],
+    ) -> Pin<Box<dyn Future<Output = Result<ExecuteResult, RegistryError>> + Send + 'a>> {
+        Box::pin(async move {
+            let plugin = self.checkout(command_name).await?;
+            self.check_permission(
+                command_name,
+                plugin.permission.as_deref(),
+                &plugin.capabilities,
+            )?;
+
+            let result = self
+                .run_effects(&plugin, command_name, args, handler, chain)
+                .await;
+            self.emit(RegistryEvent::Executed {
+                command: command_name.to_string(),
+                success: matches!(result, Ok(ExecuteResult::Success(_))),
+            });
+            result
+        })
+    }
+
+    /// Body of [`drive_effects`](Self::drive_effects), after the permission check
+    async fn run_effects(
+        &self,
+        plugin: &Checkout,
+        command_name: &str,
+        args: &[String],
+        handler: &dyn EffectHandler,
+        chain: &[String],
+    ) -> Result<ExecuteResult, RegistryError> {
+        let mut instance = if chain.is_empty() {
+            plugin.instance.lock().await
+        } else {
+            plugin
+                .instance
+                .try_lock()
+                .map_err(|_| RegistryError::Busy(command_name.to_string()))?
+        };
+        let mut on_stream = |effect: &Effect| handler.stream(effect);
+        let mut result = instance
+            .execute_streaming(args, &mut on_stream)
+            .map_err(RegistryError::Execution)?;
+
+        let mut callers = chain.to_vec();
+        callers.push(command_name.to_string());
+
+        while let ExecuteResult::Effect(effect) = result {
+            let effect_id = effect.id();
+            let effect_result = match effect {
+                Effect::InvokePlugin { command, args, .. } => {
+                    self.invoke_plugin(&plugin.capabilities, &callers, &command, &args, handler)
+                        .await
                 }
+                other => handler.handle(other).await,
+            };
+
+            let next = instance
+                .resume(effect_id, &effect_result)
+                .map_err(RegistryError::Execution)?;
+            result = instance
+                .drain_stream(next, &mut on_stream)
+                .map_err(RegistryError::Execution)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Run `command` for the last plugin in `callers`, as an effect result
+    ///
+    /// `capabilities` are the caller's.
+    async fn invoke_plugin(
+        &self,
+        capabilities: &Capabilities,
+        callers: &[String],
+        command: &str,
+        args: &[String],
+        handler: &dyn EffectHandler,
+    ) -> EffectResult {
+        let caller = callers.last().map(String::as_str).unwrap_or_default();
+        if !capabilities.invoke.iter().any(|c| c == command) {
+            return EffectResult::Error(format!(
+                "Plugin '{}' may not invoke '{}' (missing from its invoke capability)",
+                caller, command
+            ));
+        }
+        if callers.iter().any(|c| c == command) {
+            return EffectResult::Error(format!(
+                "Plugin invocation cycle: {} -> {}",
+                callers.join(" -> "),
+                command
+            ));
+        }
+        if callers.len() >= MAX_INVOKE_DEPTH {
+            return EffectResult::Error(format!(
+                "Plugin invocation depth limit ({}) exceeded: {} -> {}",
+                MAX_INVOKE_DEPTH,
+                callers.join(" -> "),
+                command
+            ));
+        }
+
+        match self.drive_effects(command, args, handler, callers).await {
+            Ok(ExecuteResult::Success(output)) => EffectResult::PluginOutput(output),
+            Ok(ExecuteResult::Error(error)) => EffectResult::Error(error.message),
+            Ok(ExecuteResult::Effect(_)) => {
+                EffectResult::Error(format!("Plugin '{}' did not finish", command))
+            }
+            Err(e) => EffectResult::Error(e.to_string()),
+        }
+    }
+
+    /// Check a plugin's capabilities against the permission configuration
+    ///
+    /// `permission` is the plugin's own configuration, if it was loaded with
+    /// one; otherwise the registry's applies. Does nothing when neither is
+    /// set. A refusal is broadcast as [`RegistryEvent::Denied`].
+    fn check_permission(
+        &self,
+        command_name: &str,
+        permission: Option<&PermissionConfig>,
+        capabilities: &Capabilities,
+    ) -> Result<(), RegistryError> {
+        let permission = permission.or(self.permission.as_deref());
+        let checked = Self::decide_permission(permission, command_name, capabilities);
+        if let Err(RegistryError::PermissionDenied { reason, .. }) = &checked {
+            self.emit(RegistryEvent::Denied {
+                command: command_name.to_string(),
+                reason: reason.clone(),
+            });
+        }
+        checked
+    }
+
+    /// Apply the permission strategy, prompting and auditing as needed
+    fn decide_permission(
+        permission: Option<&PermissionConfig>,
+        command_name: &str,
+        capabilities: &Capabilities,
+    ) -> Result<(), RegistryError> {
+        let Some(perm_config) = permission else {
+            return Ok(());
+        };
+
+        // Record permission request audit event
+        let _ = perm_config
+            .audit
+            .record(audit::permission_requested(command_name, capabilities));
+
+        // Get stored permission
+        let key =
+            perm_config
+                .store
+                .make_key(command_name, None, perm_config.strategy.granularity());
+        let stored = perm_config.store.get(&key).ok().flatten();
+
+        // Build context for strategy
+        let ctx = PermissionContext {
+            plugin_name: command_name,
+            command_path: &[],
+            requested: capabilities,
+            granted: stored.as_ref().map(|s| &s.capabilities),
+            interactive: perm_config.prompt.is_interactive(),
+        };
+
+        // Check for escalation
+        let decision = if let Some(ref stored_perm) = stored {
+            if stored_perm.has_escalated(capabilities) {
+                // Record escalation audit event
+                let _ = perm_config.audit.record(audit::escalation_detected(
+                    command_name,
+                    &stored_perm.capabilities,
+                    capabilities,
+                ));
+                perm_config.strategy.on_escalation(&ctx)
             } else {
                 perm_config.strategy.check(&ctx)
-            };
+            }
+        } else {
+            perm_config.strategy.check(&ctx)
+        };
 
-            // Handle decision
-            match decision {
-                PermissionDecision::Allow => {
-                    let _ = perm_config.audit.record(audit::permission_granted(
-                        command_name,
-                        capabilities,
-                        TrustLevel::Permanent,
-                    ));
-                }
-                PermissionDecision::Deny(reason) => {
-                    let _ = perm_config.audit.record(audit::permission_denied(
+        // Handle decision
+        match decision {
+            PermissionDecision::Allow => {
+                let _ = perm_config.audit.record(audit::permission_granted(
+                    command_name,
+                    capabilities,
+                    TrustLevel::Permanent,
+                ));
+            }
+            PermissionDecision::Deny(reason) => {
+                let _ = perm_config.audit.record(audit::permission_denied(
+                    command_name,
+                    capabilities,
+                    &reason,
+                ));
+                return Err(RegistryError::PermissionDenied {
+                    plugin: command_name.to_string(),
+                    reason,
+                });
+            }
+            PermissionDecision::Prompt => {
+                // Prompt user
+                let prompt_result = if let Some(ref stored_perm) = stored {
+                    perm_config.prompt.prompt_escalation(
                         command_name,
+                        &stored_perm.capabilities,
                         capabilities,
-                        &reason,
-                    ));
-                    return Err(RegistryError::PermissionDenied {
-                        plugin: command_name.to_string(),
-                        reason,
-                    });
-                }
-                PermissionDecision::Prompt => {
-                    // Prompt user
-                    let prompt_result = if let Some(ref stored_perm) = stored {
-                        perm_config.prompt.prompt_escalation(
+                    )
+                } else {
+                    perm_config.prompt.prompt(command_name, capabilities)
+                };
+
+                match prompt_result {
+                    Ok(result) if result.is_allowed() => {
+                        // Store permission if should persist
+                        if result.should_persist() {
+                            let trust_level =
+                                result.to_trust_level().unwrap_or(StoredTrustLevel::Session);
+                            let stored_perm =
+                                StoredPermission::new(capabilities.clone(), trust_level);
+                            let _ = perm_config.store.set(&key, stored_perm);
+                        }
+
+                        let audit_trust = match result.to_trust_level() {
+                            Some(StoredTrustLevel::Permanent) => TrustLevel::Permanent,
+                            Some(StoredTrustLevel::Session) => TrustLevel::Session,
+                            None => TrustLevel::Once,
+                        };
+                        let _ = perm_config.audit.record(audit::permission_granted(
                             command_name,
-                            &stored_perm.capabilities,
                             capabilities,
-                        )
-                    } else {
-                        perm_config.prompt.prompt(command_name, capabilities)
-                    };
-
-                    match prompt_result {
-                        Ok(result) if result.is_allowed() => {
-                            // Store permission if should persist
-                            if result.should_persist() {
-                                let trust_level =
-                                    result.to_trust_level().unwrap_or(StoredTrustLevel::Session);
-                                let stored_perm =
-                                    StoredPermission::new(capabilities.clone(), trust_level);
-                                let _ = perm_config.store.set(&key, stored_perm);
-                            }
-
-                            let audit_trust = match result.to_trust_level() {
-                                Some(StoredTrustLevel::Permanent) => TrustLevel::Permanent,
-                                Some(StoredTrustLevel::Session) => TrustLevel::Session,
-                                None => TrustLevel::Once,
-                            };
-                            let _ = perm_config.audit.record(audit::permission_granted(
-                                command_name,
-                                capabilities,
-                                audit_trust,
-                            ));
-                        }
-                        Ok(_) | Err(_) => {
-                            let _ = perm_config.audit.record(audit::permission_denied(
-                                command_name,
-                                capabilities,
-                                "User denied permission",
-                            ));
-                            return Err(RegistryError::PermissionDenied {
-                                plugin: command_name.to_string(),
-                                reason: "User denied permission".to_string(),
-                            });
-                        }
+                            audit_trust,
+                        ));
+                    }
+                    Ok(_) | Err(_) => {
+                        let _ = perm_config.audit.record(audit::permission_denied(
+                            command_name,
+                            capabilities,
+                            "User denied permission",
+                        ));
+                        return Err(RegistryError::PermissionDenied {
+                            plugin: command_name.to_string(),
+                            reason: "User denied permission".to_string(),
+                        });
                     }
                 }
-                PermissionDecision::AllowPartial(_reduced) => {
-                    // For now, treat partial as full allow
-                    // Future: could pass reduced capabilities to plugin
-                    let _ = perm_config.audit.record(audit::permission_granted(
-                        command_name,
-                        capabilities,
-                        TrustLevel::Once,
-                    ));
+            }
+            PermissionDecision::AllowPartial(_reduced) => {
+                // For now, treat partial as full allow
+                // Future: could pass reduced capabilities to plugin
+                let _ = perm_config.audit.record(audit::permission_granted(
+                    command_name,
+                    capabilities,
+                    TrustLevel::Once,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run several commands in sequence, feeding each output to the next
+    ///
+    /// Each stage runs through [`execute`](Self::execute), so permission
+    /// checks and audit events apply per stage. A stage's `Success` output is
+    /// passed to the next stage as its first argument; use
+    /// [`execute_pipeline_with`](Self::execute_pipeline_with) to change that.
+    ///
+    /// ```rust,ignore
+    /// let result = registry
+    ///     .execute_pipeline(&[("greet", vec!["World".into()]), ("upper", vec![])])
+    ///     .await?;
+    /// // ExecuteResult::Success("HELLO, WORLD!")
+    /// ```
+    pub async fn execute_pipeline(
+        &self,
+        stages: &[(&str, Vec<String>)],
+    ) -> Result<sen_plugin_api::ExecuteResult, RegistryError> {
+        self.execute_pipeline_with(stages, PipelineInput::default())
+            .await
+    }
+
+    /// Run several commands in sequence, passing outputs as `input` says
+    ///
+    /// Stops at the first stage that fails, returning
+    /// [`RegistryError::PipelineStage`] with the index of that stage. A stage
+    /// that returns an error or requests an effect counts as failed. On
+    /// success, returns the last stage's result (an empty pipeline succeeds
+    /// with empty output).
+    pub async fn execute_pipeline_with(
+        &self,
+        stages: &[(&str, Vec<String>)],
+        input: PipelineInput,
+    ) -> Result<sen_plugin_api::ExecuteResult, RegistryError> {
+        let mut previous: Option<String> = None;
+
+        for (stage, (command, args)) in stages.iter().enumerate() {
+            let mut args = args.clone();
+            if let Some(output) = previous.take() {
+                match input {
+                    PipelineInput::FirstArg => args.insert(0, output),
+                    PipelineInput::LastArg => args.push(output),
+                    PipelineInput::Ignore => {}
+                }
+            }
+
+            let failed = |source| RegistryError::PipelineStage {
+                stage,
+                command: command.to_string(),
+                source: Box::new(source),
+            };
+
+            match self.execute(command, &args).await.map_err(failed)? {
+                sen_plugin_api::ExecuteResult::Success(output) => previous = Some(output),
+                sen_plugin_api::ExecuteResult::Error(error) => {
+                    return Err(failed(RegistryError::Plugin(error)))
+                }
+                sen_plugin_api::ExecuteResult::Effect(effect) => {
+                    return Err(failed(RegistryError::Plugin(
+                        sen_plugin_api::ExecuteError {
+                            code: 101,
+                            message: format!(
+                                "Plugin requested effect {} which is not supported in pipelines",
+                                effect.id()
+                            ),
+                        },
+                    )))
                 }
             }
         }
 
-        entry
-            .plugin
-            .instance
-            .execute(args)
-            .map_err(RegistryError::Execution)
+        Ok(sen_plugin_api::ExecuteResult::Success(
+            previous.unwrap_or_default(),
+        ))
     }
 
     /// Get plugin manifest for a command
     pub async fn get_manifest(&self, command_name: &str) -> Option<sen_plugin_api::PluginManifest> {
         let inner = self.inner.read().await;
-        inner
-            .plugins
-            .get(command_name)
-            .map(|e| e.plugin.manifest.clone())
+        inner.plugins.get(command_name).map(|e| e.manifest.clone())
     }
 
     /// Get all plugin manifests
     pub async fn get_all_manifests(&self) -> Vec<sen_plugin_api::PluginManifest> {
         let inner = self.inner.read().await;
-        inner
-            .plugins
-            .values()
-            .map(|e| e.plugin.manifest.clone())
-            .collect()
+        inner.plugins.values().map(|e| e.manifest.clone()).collect()
     }
 
     /// Get the number of loaded plugins
@@ -371,6 +858,16 @@ impl PluginRegistry {
     }
 }
 
+/// Run the `plugin_shutdown` hook of a removed plugin; failures are logged
+///
+/// Callers release the registry lock first, so a slow hook never blocks
+/// other commands. A call still running on the plugin finishes first.
+async fn shutdown(command_name: &str, entry: PluginEntry) {
+    if let Err(e) = entry.instance.lock().await.shutdown() {
+        tracing::warn!(command = %command_name, error = %e, "Plugin shutdown failed");
+    }
+}
+
 /// Errors that can occur during registry operations
 #[derive(Debug, thiserror::Error)]
 pub enum RegistryError {
@@ -382,6 +879,34 @@ pub enum RegistryError {
 
     #[error("Permission denied for plugin '{plugin}': {reason}")]
     PermissionDenied { plugin: String, reason: String },
+
+    #[error("Plugin returned an error (code {}): {}", .0.code, .0.message)]
+    Plugin(sen_plugin_api::ExecuteError),
+
+    /// An invoked plugin was already running another call
+    #[error("Plugin '{0}' is busy with another call")]
+    Busy(String),
+
+    #[error("Pipeline stage {stage} ('{command}') failed: {source}")]
+    PipelineStage {
+        /// Index of the failed stage
+        stage: usize,
+        command: String,
+        #[source]
+        source: Box<RegistryError>,
+    },
+}
+
+/// How [`PluginRegistry::execute_pipeline_with`] passes a stage's output on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PipelineInput {
+    /// Insert the output before the next stage's own arguments
+    #[default]
+    FirstArg,
+    /// Append the output after the next stage's own arguments
+    LastArg,
+    /// Run the stages in sequence without passing outputs
+    Ignore,
 }
 
 #[cfg(test)]
@@ -392,6 +917,7 @@ mod tests {
         AutoPromptHandler, MemoryPermissionStore, PermissionPresets, PermissionStore, PromptResult,
         RecordingPromptHandler,
     };
+    use crate::testing::{packed, WatPlugin};
 
     const HELLO_PLUGIN_WASM: &[u8] = include_bytes!(
         "../../examples/hello-plugin/target/wasm32-unknown-unknown/release/hello_plugin.wasm"
@@ -423,6 +949,68 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_registry_last_stats() {
+        let registry = PluginRegistry::new().unwrap();
+        let loader = PluginLoader::new().unwrap();
+        registry
+            .register(loader.load(HELLO_PLUGIN_WASM).unwrap())
+            .await;
+
+        assert_eq!(registry.last_stats("hello").await.unwrap().fuel_consumed, 0);
+        registry
+            .execute("hello", &["World".to_string()])
+            .await
+            .unwrap();
+        assert!(registry.last_stats("hello").await.unwrap().fuel_consumed > 0);
+        assert!(registry.last_stats("missing").await.is_none());
+    }
+
+    /// Effect handler that runs `hello` while the caller is suspended
+    struct NestedExecute(PluginRegistry);
+
+    #[async_trait::async_trait]
+    impl EffectHandler for NestedExecute {
+        async fn handle(&self, _effect: Effect) -> EffectResult {
+            match self.0.execute("hello", &["World".to_string()]).await {
+                Ok(ExecuteResult::Success(output)) => EffectResult::PluginOutput(output),
+                other => EffectResult::Error(format!("{:?}", other)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_is_not_locked_while_effects_run() {
+        let registry = PluginRegistry::new().unwrap();
+        let loader = PluginLoader::new().unwrap();
+        registry
+            .register(loader.load(HELLO_PLUGIN_WASM).unwrap())
+            .await;
+
+        // Sleeps once, then succeeds
+        let done = rmp_serde::to_vec(&ExecuteResult::success("done")).unwrap();
+        let waiter = WatPlugin::command("wait", "Sleeps once")
+            .returns(&ExecuteResult::Effect(Effect::sleep(1, 0)))
+            .data(4096, &done)
+            .field(format!(
+                r#"(func (export "plugin_resume") (param i32 i32 i32) (result i64)
+                    (i64.const {}))"#,
+                packed(4096, done.len())
+            ))
+            .build();
+        registry.register(loader.load(&waiter).unwrap()).await;
+
+        let handler = NestedExecute(registry.clone());
+        let run = registry.execute_with_effects("wait", &[], &handler);
+        let result = tokio::time::timeout(std::time::Duration::from_secs(10), run)
+            .await
+            .expect("registry stayed locked during the effect");
+        match result.unwrap() {
+            ExecuteResult::Success(output) => assert_eq!(output, "done"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_registry_unload() {
         let registry = PluginRegistry::new().unwrap();
@@ -448,6 +1036,65 @@ mod tests {
         assert_eq!(commands, vec!["hello"]);
     }
 
+    #[tokio::test]
+    async fn test_registry_subscribe_receives_lifecycle_events() {
+        let registry = PluginRegistry::new().unwrap();
+        let mut events = registry.subscribe();
+        let loader = PluginLoader::new().unwrap();
+
+        registry
+            .register(loader.load(HELLO_PLUGIN_WASM).unwrap())
+            .await;
+        registry
+            .execute("hello", &["World".to_string()])
+            .await
+            .unwrap();
+        registry.unload("hello").await;
+
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::Loaded {
+                command: "hello".into()
+            }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::Executed {
+                command: "hello".into(),
+                success: true
+            }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::Unloaded {
+                command: "hello".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_lagging_subscriber_does_not_block() {
+        let registry = PluginRegistry::new().unwrap();
+        let mut events = registry.subscribe();
+        let loader = PluginLoader::new().unwrap();
+        registry
+            .register(loader.load(HELLO_PLUGIN_WASM).unwrap())
+            .await;
+
+        for _ in 0..EVENT_CAPACITY + 10 {
+            registry.execute("hello", &[]).await.unwrap();
+        }
+
+        assert!(matches!(
+            events.recv().await,
+            Err(broadcast::error::RecvError::Lagged(_))
+        ));
+        assert!(matches!(
+            events.recv().await,
+            Ok(RegistryEvent::Executed { .. })
+        ));
+    }
+
     // ========================================================================
     // Permission Integration Tests
     // ========================================================================
@@ -596,6 +1243,45 @@ mod tests {
         assert!(stored.is_some(), "Permission should be stored after grant");
     }
 
+    #[tokio::test]
+    async fn test_registry_capability_policy_strips_fs_write() {
+        let store = std::sync::Arc::new(MemoryPermissionStore::new());
+        let config = PermissionConfig {
+            strategy: std::sync::Arc::new(crate::permission::DefaultPermissionStrategy),
+            store: store.clone(),
+            prompt: std::sync::Arc::new(AutoPromptHandler::always_allow()),
+            audit: std::sync::Arc::new(crate::audit::NullAuditSink),
+            trust_flags: crate::permission::TrustFlagConfig::default(),
+        };
+
+        let registry = PluginRegistry::with_permissions(config)
+            .unwrap()
+            .with_capability_policy(|_, declared| {
+                let mut allowed = declared.clone();
+                allowed.fs_write.clear();
+                allowed
+            });
+
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader.load(HELLO_PLUGIN_WASM).unwrap();
+        plugin.manifest.capabilities = sen_plugin_api::Capabilities::default()
+            .with_stdio(sen_plugin_api::StdioCapability::stdout_only())
+            .with_fs_write(vec![sen_plugin_api::PathPattern::new("/")]);
+        registry.register(plugin).await;
+
+        let manifest = registry.get_manifest("hello").await.unwrap();
+        assert!(manifest.capabilities.fs_write.is_empty());
+        assert!(manifest.capabilities.stdio.stdout);
+
+        // Approving the plugin approves only what the policy allows
+        registry
+            .execute("hello", &["World".to_string()])
+            .await
+            .unwrap();
+        let stored = store.get("hello").unwrap().unwrap();
+        assert!(stored.capabilities.fs_write.is_empty());
+    }
+
     #[tokio::test]
     async fn test_registry_with_permissions_prompt_recording() {
         // Verify prompts are triggered correctly
@@ -630,6 +1316,76 @@ mod tests {
         assert_eq!(prompts[0].plugin, "hello");
     }
 
+    #[tokio::test]
+    async fn test_registry_pregrant_runs_under_strict_strategy() {
+        // Non-interactive strict mode denies unless capabilities are pre-granted
+        let stdout_caps = sen_plugin_api::Capabilities::default()
+            .with_stdio(sen_plugin_api::StdioCapability::stdout_only());
+        let prompt_handler = std::sync::Arc::new(RecordingPromptHandler::new(PromptResult::Deny));
+
+        let mut config = crate::permission::PermissionConfigBuilder::new()
+            .strategy(crate::permission::StrictPermissionStrategy)
+            .store(crate::permission::ReadOnlyPermissionStore::new(
+                MemoryPermissionStore::new(),
+            ))
+            .audit(crate::audit::NullAuditSink)
+            .pregrant("hello", stdout_caps.clone())
+            .build()
+            .unwrap();
+        config.prompt = prompt_handler.clone();
+
+        let registry = PluginRegistry::with_permissions(config).unwrap();
+
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader.load(HELLO_PLUGIN_WASM).unwrap();
+        plugin.manifest.capabilities = stdout_caps;
+        registry.register(plugin).await;
+
+        let result = registry
+            .execute("hello", &["World".to_string()])
+            .await
+            .unwrap();
+        match result {
+            sen_plugin_api::ExecuteResult::Success(output) => {
+                assert_eq!(output, "Hello, World!");
+            }
+            _ => panic!("Expected success with pre-granted capabilities"),
+        }
+        assert_eq!(prompt_handler.prompt_count(), 0, "Should not prompt");
+    }
+
+    #[tokio::test]
+    async fn test_registry_strict_denies_without_pregrant() {
+        let config = crate::permission::PermissionConfigBuilder::new()
+            .strategy(crate::permission::StrictPermissionStrategy)
+            .store(MemoryPermissionStore::new())
+            .prompt(AutoPromptHandler::always_deny())
+            .audit(crate::audit::NullAuditSink)
+            // Pre-grant for a different plugin does not apply
+            .pregrant("other", sen_plugin_api::Capabilities::default())
+            .build()
+            .unwrap();
+
+        let registry = PluginRegistry::with_permissions(config).unwrap();
+
+        let loader = PluginLoader::new().unwrap();
+        let mut plugin = loader.load(HELLO_PLUGIN_WASM).unwrap();
+        plugin.manifest.capabilities = sen_plugin_api::Capabilities::default()
+            .with_stdio(sen_plugin_api::StdioCapability::stdout_only());
+        registry.register(plugin).await;
+
+        let mut events = registry.subscribe();
+        let result = registry.execute("hello", &["World".to_string()]).await;
+        assert!(matches!(
+            result,
+            Err(RegistryError::PermissionDenied { .. })
+        ));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(RegistryEvent::Denied { command, .. }) if command == "hello"
+        ));
+    }
+
     #[tokio::test]
     async fn test_registry_without_permissions_skips_check() {
         // Registry without permission config should skip all checks
@@ -0,0 +1,175 @@
+//! WAT plugin fixtures for tests (requires the `testing` feature).
+//!
+//! [`WatPlugin`] assembles a plugin module from text so tests can exercise
+//! the host without a `wasm32` toolchain. Every plugin gets a one-page
+//! `memory`, its manifest at offset 0, a bump `plugin_alloc` starting at
+//! [`HEAP_START`], and `plugin_dealloc`/`plugin_manifest`/`plugin_execute`
+//! exports; the builder methods override or extend those pieces:
+//!
+//! ```ignore
+//! use sen_plugin_host::testing::WatPlugin;
+//!
+//! let wasm = WatPlugin::command("hello", "Says hello")
+//!     .returns(&ExecuteResult::success("Hello!"))
+//!     .build();
+//! let plugin = PluginLoader::new()?.load(&wasm)?;
+//! ```
+
+use sen_plugin_api::{CommandSpec, ExecuteResult, PluginManifest};
+
+/// Where `plugin_alloc` starts handing out memory; fixture data goes below.
+pub const HEAP_START: u32 = 8192;
+
+/// Where [`WatPlugin::returns`] places its result.
+pub const RESULT_OFFSET: u32 = 2048;
+
+/// Escape bytes for a WAT data segment.
+pub fn wat_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("\\{:02x}", b)).collect()
+}
+
+/// Pack a pointer and a length the way plugin exports return them.
+pub fn packed(ptr: u32, len: usize) -> i64 {
+    ((ptr as i64) << 32) | len as i64
+}
+
+/// Builder for a WAT plugin module.
+#[derive(Debug, Clone)]
+pub struct WatPlugin {
+    manifest: Vec<u8>,
+    imports: Vec<String>,
+    fields: Vec<String>,
+    execute: String,
+    dealloc: String,
+    omitted: Vec<&'static str>,
+}
+
+impl WatPlugin {
+    /// Plugin serving `manifest`; `plugin_execute` returns a null result
+    /// until [`WatPlugin::execute`] or [`WatPlugin::returns`] is called.
+    pub fn new(manifest: &PluginManifest) -> Self {
+        Self {
+            manifest: rmp_serde::to_vec_named(manifest).unwrap(),
+            imports: Vec::new(),
+            fields: Vec::new(),
+            execute: "(i64.const 0)".to_string(),
+            dealloc: String::new(),
+            omitted: Vec::new(),
+        }
+    }
+
+    /// Plugin for a command without arguments or capabilities.
+    pub fn command(name: &str, about: &str) -> Self {
+        Self::new(&PluginManifest::new(CommandSpec::new(name, about)))
+    }
+
+    /// Import a host function, e.g.
+    /// `(import "wasi_snapshot_preview1" "fd_write" (func $fd_write ...))`.
+    pub fn import(mut self, wat: impl Into<String>) -> Self {
+        self.imports.push(wat.into());
+        self
+    }
+
+    /// Add a module field: a global, a function, a data segment.
+    pub fn field(mut self, wat: impl Into<String>) -> Self {
+        self.fields.push(wat.into());
+        self
+    }
+
+    /// Place `bytes` in memory at `offset`.
+    pub fn data(self, offset: u32, bytes: &[u8]) -> Self {
+        let segment = format!("(data (i32.const {}) \"{}\")", offset, wat_bytes(bytes));
+        self.field(segment)
+    }
+
+    /// Body of `plugin_execute (param i32 i32) (result i64)`.
+    pub fn execute(mut self, body: impl Into<String>) -> Self {
+        self.execute = body.into();
+        self
+    }
+
+    /// Make `plugin_execute` return `result`, stored at [`RESULT_OFFSET`].
+    pub fn returns(self, result: &ExecuteResult) -> Self {
+        let result = rmp_serde::to_vec(result).unwrap();
+        let body = format!("(i64.const {})", packed(RESULT_OFFSET, result.len()));
+        self.data(RESULT_OFFSET, &result).execute(body)
+    }
+
+    /// Body of `plugin_dealloc (param i32 i32)`; empty by default.
+    pub fn dealloc(mut self, body: impl Into<String>) -> Self {
+        self.dealloc = body.into();
+        self
+    }
+
+    /// Leave out one of the required exports (`plugin_alloc`, ...).
+    pub fn without_export(mut self, name: &'static str) -> Self {
+        self.omitted.push(name);
+        self
+    }
+
+    /// The module as WAT text.
+    pub fn to_wat(&self) -> String {
+        let exports = [
+            (
+                "plugin_alloc",
+                "(func (export \"plugin_alloc\") (param $size i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $heap))
+                    (global.set $heap (i32.add (global.get $heap) (local.get $size)))
+                    (local.get $ptr))"
+                    .to_string(),
+            ),
+            (
+                "plugin_dealloc",
+                format!(
+                    "(func (export \"plugin_dealloc\") (param i32 i32) {})",
+                    self.dealloc
+                ),
+            ),
+            (
+                "plugin_manifest",
+                format!(
+                    "(func (export \"plugin_manifest\") (result i64) (i64.const {}))",
+                    self.manifest.len()
+                ),
+            ),
+            (
+                "plugin_execute",
+                format!(
+                    "(func (export \"plugin_execute\") (param i32 i32) (result i64) {})",
+                    self.execute
+                ),
+            ),
+        ];
+        let exports = exports
+            .iter()
+            .filter(|(name, _)| !self.omitted.contains(name))
+            .map(|(_, wat)| wat.as_str());
+
+        let mut module = String::from("(module\n");
+        for line in self.imports.iter().map(String::as_str) {
+            module.push_str(line);
+            module.push('\n');
+        }
+        module.push_str("(memory (export \"memory\") 1)\n");
+        module.push_str(&format!(
+            "(global $heap (mut i32) (i32.const {}))\n",
+            HEAP_START
+        ));
+        module.push_str(&format!(
+            "(data (i32.const 0) \"{}\")\n",
+            wat_bytes(&self.manifest)
+        ));
+        for line in self.fields.iter().map(String::as_str).chain(exports) {
+            module.push_str(line);
+            module.push('\n');
+        }
+        module.push(')');
+        module
+    }
+
+    /// The module as WAT bytes, which the loaders accept like a binary.
+    pub fn build(&self) -> Vec<u8> {
+        self.to_wat().into_bytes()
+    }
+}
@@ -0,0 +1,547 @@
+//! Static validation of plugin modules
+//!
+//! [`PluginLoader::validate`] checks a plugin without running its commands and
+//! collects every problem it finds instead of stopping at the first one:
+//!
+//! - Required exports (`memory`, `plugin_manifest`, `plugin_execute`,
+//!   `plugin_alloc`, `plugin_dealloc`) with the expected signatures
+//! - Unresolved imports (plugins needing WASI must use the WASI loader)
+//! - Manifest decoding and API version compatibility
+//! - Command and subcommand names usable as route keys
+//! - Well-formed capability patterns
+//!
+//! Errors mean the plugin cannot be loaded or routed; warnings flag
+//! declarations that load but are likely mistakes.
+
+use crate::loader::{LoaderError, PluginLoader};
+use sen_plugin_api::{Capabilities, CommandSpec, PluginManifest};
+use serde::Serialize;
+use std::collections::HashSet;
+use wasmtime::{ExternType, FuncType, Module, ValType};
+
+/// Severity of a validation issue
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The plugin cannot be loaded or routed
+    Error,
+    /// The plugin loads, but a declaration is likely a mistake
+    Warning,
+}
+
+/// A single problem found during validation
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Error or warning
+    pub severity: Severity,
+    /// Stable machine-readable code (e.g. `missing_export`)
+    pub code: &'static str,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Result of [`PluginLoader::validate`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// Decoded manifest, if the plugin got far enough to produce one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<PluginManifest>,
+    /// All issues found, in check order
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether the plugin has no errors (warnings are allowed)
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Issues with [`Severity::Error`]
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    /// Issues with [`Severity::Warning`]
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+
+    fn error(&mut self, code: &'static str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, code: &'static str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Warning,
+            code,
+            message: message.into(),
+        });
+    }
+}
+
+/// Required function exports and their `(params, results)` signatures
+const REQUIRED_FUNCTIONS: &[(&str, &[ValType], &[ValType])] = &[
+    ("plugin_manifest", &[], &[ValType::I64]),
+    (
+        "plugin_execute",
+        &[ValType::I32, ValType::I32],
+        &[ValType::I64],
+    ),
+    ("plugin_alloc", &[ValType::I32], &[ValType::I32]),
+    ("plugin_dealloc", &[ValType::I32, ValType::I32], &[]),
+];
+
+impl PluginLoader {
+    /// Validate a plugin module without executing any of its commands
+    ///
+    /// Only `plugin_manifest` is called, to decode the manifest. All problems
+    /// are collected into the returned report; use
+    /// [`ValidationReport::is_valid`] to decide whether the plugin is usable.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = loader.validate(&std::fs::read("plugin.wasm")?);
+    /// for issue in report.errors() {
+    ///     eprintln!("error[{}]: {}", issue.code, issue.message);
+    /// }
+    /// ```
+    pub fn validate(&self, wasm_bytes: &[u8]) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let module = match Module::new(self.engine(), wasm_bytes) {
+            Ok(module) => module,
+            Err(e) => {
+                report.error(
+                    "invalid_module",
+                    format!("Module compilation failed: {}", e),
+                );
+                return report;
+            }
+        };
+
+        check_exports(&module, &mut report);
+
+        let imports: Vec<String> = module
+            .imports()
+            .map(|i| format!("{}::{}", i.module(), i.name()))
+            .collect();
+        if !imports.is_empty() {
+            report.error(
+                "unresolved_import",
+                format!(
+                    "Module imports {}; plugins needing WASI must be loaded with WasiPluginLoader",
+                    imports.join(", ")
+                ),
+            );
+        }
+
+        // The manifest can only be read from an instantiable module
+        if !report.is_valid() {
+            return report;
+        }
+
+        match self.load(wasm_bytes) {
+            Ok(plugin) => report.manifest = Some(plugin.manifest),
+            Err(LoaderError::ApiVersionMismatch { expected, actual }) => report.error(
+                "api_version",
+                format!(
+                    "Plugin targets API v{}, host supports v{} to v{}; rebuild with a matching sen-plugin-sdk",
+                    actual,
+                    sen_plugin_api::MIN_SUPPORTED_API_VERSION,
+                    expected
+                ),
+            ),
+            Err(LoaderError::Deserialization(e)) => report.error(
+                "manifest_decode",
+                format!("Manifest could not be decoded: {}", e),
+            ),
+            Err(e) => report.error("manifest_call", e.to_string()),
+        }
+
+        if let Some(manifest) = &report.manifest {
+            let mut issues = check_manifest(manifest);
+            report.issues.append(&mut issues);
+        }
+
+        report
+    }
+}
+
+fn check_exports(module: &Module, report: &mut ValidationReport) {
+    match module.get_export("memory") {
+        Some(ExternType::Memory(_)) => {}
+        Some(_) => report.error("export_type", "Export 'memory' is not a memory"),
+        None => report.error("missing_export", "Missing required export 'memory'"),
+    }
+
+    for (name, params, results) in REQUIRED_FUNCTIONS {
+        match module.get_export(name) {
+            Some(ExternType::Func(ty)) => {
+                if !signature_matches(&ty, params, results) {
+                    report.error(
+                        "export_type",
+                        format!(
+                            "Export '{}' has signature {}, expected {}",
+                            name,
+                            describe(ty.params(), ty.results()),
+                            describe(params.iter().cloned(), results.iter().cloned())
+                        ),
+                    );
+                }
+            }
+            Some(_) => report.error(
+                "export_type",
+                format!("Export '{}' is not a function", name),
+            ),
+            None => report.error(
+                "missing_export",
+                format!("Missing required export '{}'", name),
+            ),
+        }
+    }
+}
+
+fn signature_matches(ty: &FuncType, params: &[ValType], results: &[ValType]) -> bool {
+    fn same(actual: impl ExactSizeIterator<Item = ValType>, expected: &[ValType]) -> bool {
+        actual.len() == expected.len() && actual.zip(expected).all(|(a, e)| ValType::eq(&a, e))
+    }
+    same(ty.params(), params) && same(ty.results(), results)
+}
+
+fn describe(
+    params: impl Iterator<Item = ValType>,
+    results: impl Iterator<Item = ValType>,
+) -> String {
+    let list = |types: Vec<String>| format!("({})", types.join(", "));
+    format!(
+        "{} -> {}",
+        list(params.map(|t| t.to_string()).collect()),
+        list(results.map(|t| t.to_string()).collect())
+    )
+}
+
+/// Check a decoded manifest for routing and capability problems
+pub(crate) fn check_manifest(manifest: &PluginManifest) -> Vec<ValidationIssue> {
+    let mut report = ValidationReport::default();
+
+    check_command(&manifest.command, "", &mut report);
+    check_capabilities(&manifest.capabilities, &mut report);
+
+    report.issues
+}
+
+fn check_command(spec: &CommandSpec, parent: &str, report: &mut ValidationReport) {
+    let path = if parent.is_empty() {
+        spec.name.clone()
+    } else {
+        format!("{} {}", parent, spec.name)
+    };
+
+    if let Err(reason) = check_route_segment(&spec.name) {
+        report.error(
+            "invalid_command_name",
+            format!(
+                "Command name '{}' is not a valid route key: {}",
+                path, reason
+            ),
+        );
+    }
+
+    if spec.about.trim().is_empty() {
+        report.warning(
+            "missing_about",
+            format!("Command '{}' has no description", path),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    for arg in &spec.args {
+        if !seen.insert(arg.name.as_str()) {
+            report.error(
+                "duplicate_arg",
+                format!("Command '{}' declares argument '{}' twice", path, arg.name),
+            );
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for sub in &spec.subcommands {
+        if !seen.insert(sub.name.as_str()) {
+            report.error(
+                "duplicate_subcommand",
+                format!(
+                    "Command '{}' declares subcommand '{}' twice",
+                    path, sub.name
+                ),
+            );
+        }
+        check_command(sub, &path, report);
+    }
+}
+
+/// A route key segment: ASCII alphanumerics, `-` and `_`, not starting with `-`
+fn check_route_segment(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() {
+        return Err("name is empty");
+    }
+    if name.starts_with('-') {
+        return Err("name must not start with '-'");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("only ASCII letters, digits, '-' and '_' are allowed");
+    }
+    Ok(())
+}
+
+fn check_capabilities(caps: &Capabilities, report: &mut ValidationReport) {
+    for (kind, patterns) in [("fs_read", &caps.fs_read), ("fs_write", &caps.fs_write)] {
+        for pattern in patterns {
+            if pattern.pattern.trim().is_empty() {
+                report.error("invalid_capability", format!("Empty {} path pattern", kind));
+            } else if pattern.pattern.split(['/', '\\']).any(|c| c == "..") {
+                report.warning(
+                    "path_traversal",
+                    format!(
+                        "{} pattern '{}' contains '..'; declare the resolved path instead",
+                        kind, pattern.pattern
+                    ),
+                );
+            }
+        }
+    }
+
+    for var in &caps.env_read {
+        if var.is_empty() || var.contains('=') || var.contains('\0') {
+            report.error(
+                "invalid_capability",
+                format!("Invalid env_read variable name '{}'", var),
+            );
+        }
+    }
+
+    for net in &caps.net {
+        if let Err(reason) = check_host(&net.host) {
+            report.error(
+                "invalid_capability",
+                format!("Invalid net host '{}': {}", net.host, reason),
+            );
+        }
+        if net.port == Some(0) {
+            report.error(
+                "invalid_capability",
+                format!("Invalid net port 0 for host '{}'", net.host),
+            );
+        }
+    }
+}
+
+/// A host name, optionally with a leading `*.` wildcard
+fn check_host(host: &str) -> Result<(), &'static str> {
+    if host.is_empty() {
+        return Err("host is empty");
+    }
+    if host.contains("://") {
+        return Err("host must not include a scheme");
+    }
+    if host.contains('/') {
+        return Err("host must not include a path");
+    }
+    if host.chars().any(char::is_whitespace) {
+        return Err("host must not contain whitespace");
+    }
+    let rest = host.strip_prefix("*.").unwrap_or(host);
+    if rest.contains('*') {
+        return Err("'*' is only allowed as a leading '*.' wildcard");
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::WatPlugin;
+    use sen_plugin_api::{
+        ArgSpec, NetPattern, PathPattern, API_VERSION, MIN_SUPPORTED_API_VERSION,
+    };
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<&'static str> {
+        issues.iter().map(|i| i.code).collect()
+    }
+
+    #[test]
+    fn test_validate_accepts_well_formed_plugin() {
+        let loader = PluginLoader::new().unwrap();
+        let manifest = PluginManifest::new(CommandSpec::new("hello", "Says hello"));
+
+        let report = loader.validate(&WatPlugin::new(&manifest).build());
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert!(report.issues.is_empty());
+        assert_eq!(report.manifest.unwrap().command.name, "hello");
+    }
+
+    #[test]
+    fn test_validate_reports_missing_export() {
+        let loader = PluginLoader::new().unwrap();
+        let manifest = PluginManifest::new(CommandSpec::new("hello", "Says hello"));
+
+        let report = loader.validate(
+            &WatPlugin::new(&manifest)
+                .without_export("plugin_dealloc")
+                .build(),
+        );
+        assert!(!report.is_valid());
+        assert!(report.manifest.is_none());
+        let errors: Vec<_> = report.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "missing_export");
+        assert!(errors[0].message.contains("plugin_dealloc"));
+    }
+
+    #[test]
+    fn test_validate_reports_wrong_signature() {
+        let loader = PluginLoader::new().unwrap();
+        let wasm = br#"(module
+            (memory (export "memory") 1)
+            (func (export "plugin_alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "plugin_dealloc") (param i32 i32))
+            (func (export "plugin_manifest") (result i32) (i32.const 0))
+            (func (export "plugin_execute") (param i32 i32) (result i64) (i64.const 0)))"#;
+
+        let report = loader.validate(wasm);
+        assert_eq!(codes(&report.issues), vec!["export_type"]);
+        assert!(report.issues[0].message.contains("() -> (i32)"));
+    }
+
+    #[test]
+    fn test_validate_reports_unresolved_imports() {
+        let loader = PluginLoader::new().unwrap();
+        let wasm = br#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "plugin_alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "plugin_dealloc") (param i32 i32))
+            (func (export "plugin_manifest") (result i64) (i64.const 0))
+            (func (export "plugin_execute") (param i32 i32) (result i64) (i64.const 0)))"#;
+
+        let report = loader.validate(wasm);
+        assert_eq!(codes(&report.issues), vec!["unresolved_import"]);
+        assert!(report.issues[0].message.contains("WasiPluginLoader"));
+    }
+
+    #[test]
+    fn test_validate_reports_api_version_mismatch() {
+        let loader = PluginLoader::new().unwrap();
+        let mut manifest = PluginManifest::new(CommandSpec::new("hello", "Says hello"));
+        manifest.api_version = API_VERSION + 1;
+
+        let report = loader.validate(&WatPlugin::new(&manifest).build());
+        assert_eq!(codes(&report.issues), vec!["api_version"]);
+
+        manifest.api_version = MIN_SUPPORTED_API_VERSION - 1;
+        let report = loader.validate(&WatPlugin::new(&manifest).build());
+        assert_eq!(codes(&report.issues), vec!["api_version"]);
+    }
+
+    #[test]
+    fn test_validate_accepts_older_supported_api_version() {
+        let loader = PluginLoader::new().unwrap();
+        let mut manifest = PluginManifest::new(CommandSpec::new("hello", "Says hello"));
+        manifest.api_version = MIN_SUPPORTED_API_VERSION;
+
+        let report = loader.validate(&WatPlugin::new(&manifest).build());
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(
+            report.manifest.unwrap().api_version,
+            MIN_SUPPORTED_API_VERSION
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_non_wasm() {
+        let loader = PluginLoader::new().unwrap();
+        let report = loader.validate(b"not wasm");
+        assert_eq!(codes(&report.issues), vec!["invalid_module"]);
+    }
+
+    #[test]
+    fn test_check_manifest_route_keys() {
+        let spec = CommandSpec::new("db", "Database")
+            .subcommand(CommandSpec::new("mi grate", "Migrate"))
+            .subcommand(CommandSpec::new("-seed", "Seed"));
+        let issues = check_manifest(&PluginManifest::new(spec));
+
+        assert_eq!(
+            codes(&issues),
+            vec!["invalid_command_name", "invalid_command_name"]
+        );
+        assert!(issues[0].message.contains("'db mi grate'"));
+    }
+
+    #[test]
+    fn test_check_manifest_duplicates_and_warnings() {
+        let spec = CommandSpec::new("greet", "")
+            .arg(ArgSpec::positional("name"))
+            .arg(ArgSpec::positional("name"));
+        let issues = check_manifest(&PluginManifest::new(spec));
+
+        assert_eq!(codes(&issues), vec!["missing_about", "duplicate_arg"]);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_check_manifest_capability_patterns() {
+        let caps = Capabilities {
+            fs_read: vec![PathPattern::new(""), PathPattern::new("../secrets")],
+            env_read: vec!["HOME".into(), "A=B".into()],
+            net: vec![
+                NetPattern::https("*.github.com"),
+                NetPattern::https("https://api.example.com"),
+                NetPattern::https("api.*.com"),
+            ],
+            ..Default::default()
+        };
+        let issues = check_manifest(&PluginManifest::with_capabilities(
+            CommandSpec::new("fetch", "Fetch"),
+            caps,
+        ));
+
+        assert_eq!(
+            codes(&issues),
+            vec![
+                "invalid_capability",
+                "path_traversal",
+                "invalid_capability",
+                "invalid_capability",
+                "invalid_capability",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_serializes() {
+        let mut report = ValidationReport::default();
+        report.error("missing_export", "Missing required export 'memory'");
+        report.warning("missing_about", "Command 'x' has no description");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["issues"][0]["severity"], "error");
+        assert_eq!(json["issues"][0]["code"], "missing_export");
+        assert_eq!(json["issues"][1]["severity"], "warning");
+        assert!(json.get("manifest").is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Size-capped capture of plugin stdout/stderr
+//!
+//! A plugin granted `stdio` normally writes straight to the host's streams.
+//! With [`WasiLoaderConfig::max_stdout_bytes`](super::WasiLoaderConfig::max_stdout_bytes)
+//! (or `max_stderr_bytes`) set, the stream is captured into an
+//! [`OutputCapture`] instead:
+//!
+//! - The first `limit` bytes are kept; everything after is dropped
+//! - Writes never fail, so a chatty plugin keeps running
+//! - After the call the kept bytes are forwarded to the host stream, followed
+//!   by [`TRUNCATION_MARKER`] if anything was dropped
+
+use bytes::Bytes;
+use std::sync::{Arc, Mutex};
+use wasmtime_wasi::{HostOutputStream, StdoutStream, StreamResult, Subscribe};
+
+/// Appended to captured output that exceeded its limit
+pub const TRUNCATION_MARKER: &str = "... (truncated)";
+
+/// Largest write a plugin is offered at once; the pipe accepts any amount
+const WRITE_BUDGET: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+struct Buffer {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+/// In-memory plugin output stream that keeps at most `limit` bytes
+///
+/// Clones share the same buffer, so the host keeps one handle while the
+/// WASI context writes through another.
+#[derive(Debug, Clone)]
+pub struct OutputCapture {
+    limit: usize,
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+impl OutputCapture {
+    /// Create a capture that keeps at most `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            buffer: Arc::new(Mutex::new(Buffer::default())),
+        }
+    }
+
+    /// Whether output beyond the limit was dropped
+    pub fn truncated(&self) -> bool {
+        self.lock().truncated
+    }
+
+    /// Captured output, ending with [`TRUNCATION_MARKER`] if truncated
+    pub fn contents(&self) -> Vec<u8> {
+        let buffer = self.lock();
+        let mut contents = buffer.bytes.clone();
+        if buffer.truncated {
+            if !contents.is_empty() && !contents.ends_with(b"\n") {
+                contents.push(b'\n');
+            }
+            contents.extend_from_slice(TRUNCATION_MARKER.as_bytes());
+            contents.push(b'\n');
+        }
+        contents
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buffer> {
+        // The buffer is only appended to, so a poisoned lock is still usable
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HostOutputStream for OutputCapture {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let mut buffer = self.lock();
+        let room = self.limit.saturating_sub(buffer.bytes.len());
+        if bytes.len() > room {
+            buffer.truncated = true;
+        }
+        buffer
+            .bytes
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(WRITE_BUDGET)
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for OutputCapture {
+    async fn ready(&mut self) {}
+}
+
+impl StdoutStream for OutputCapture {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_keeps_limit_and_marks_truncation() {
+        let capture = OutputCapture::new(8);
+        let mut stream = capture.stream();
+        stream.write(Bytes::from_static(b"hello ")).unwrap();
+        assert!(!capture.truncated());
+
+        stream.write(Bytes::from_static(b"world")).unwrap();
+        stream.write(Bytes::from_static(b"!!!")).unwrap();
+        assert!(capture.truncated());
+        assert_eq!(capture.contents(), b"hello wo\n... (truncated)\n");
+    }
+
+    #[test]
+    fn test_capture_under_limit_is_unchanged() {
+        let capture = OutputCapture::new(64);
+        capture
+            .stream()
+            .write(Bytes::from_static(b"line\n"))
+            .unwrap();
+        assert!(!capture.truncated());
+        assert_eq!(capture.contents(), b"line\n");
+    }
+}
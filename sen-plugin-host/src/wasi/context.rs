@@ -60,6 +60,11 @@
 //!
 //! Note: A path in `fs_write` also gets read permissions (write-only is rare).
 //!
+//! A glob pattern (`./data/**/*.csv`) preopens the directory before its
+//! first wildcard (`./data`). WASI grants whole directories, so the glob
+//! narrows permission decisions (what a grant covers) but not what the
+//! plugin can open inside that directory.
+//!
 //! # Guest Path Mapping
 //!
 //! Preopened directories have both a host path and a guest path:
@@ -99,15 +104,46 @@
 //! # Security Considerations
 //!
 //! 1. **All paths are validated** through [`SandboxValidator`] before use
-//! 2. **Environment variables are filtered** - only declared patterns are passed
+//! 2. **Environment variables are filtered** - only declared patterns are passed,
+//!    minus anything matching the host's `env_deny` list
 //! 3. **Stdio is opt-in** - plugins cannot access stdin/stdout/stderr by default
-//! 4. **No network access** - WASI Preview 1 doesn't support sockets
-//! 5. **Clock/random allowed** - these are safe and commonly needed
+//! 4. **No raw network access** - WASI Preview 1 doesn't support sockets; declared
+//!    `net` patterns are carried in [`WasiSpec::net`] for the host HTTP proxy
+//! 5. **Clock/random allowed** - these are safe and commonly needed, unless
+//!    [`WasiConfigurer::deterministic`] replaces them (see below)
+//!
+//! # Deterministic Mode
+//!
+//! With [`WasiConfigurer::deterministic`], the context gets fixed inputs in
+//! place of the host clocks and RNG, using these `WasiCtxBuilder` knobs:
+//!
+//! | Knob | Value |
+//! |------|-------|
+//! | `wall_clock` | Always [`DETERMINISTIC_WALL_CLOCK`] (2000-01-01T00:00:00Z) |
+//! | `monotonic_clock` | Always 0 |
+//! | `secure_random` | `wasmtime_wasi::Deterministic` over [`DETERMINISTIC_RANDOM_SEED`] |
+//! | `insecure_random` | Same as `secure_random` |
+//! | `insecure_random_seed` | 0 |
+//!
+//! Every run of a plugin then sees the same time and the same "random"
+//! bytes, which makes output reproducible (golden tests) and keeps plugins
+//! from fingerprinting the host time. The random bytes are not random at
+//! all, so plugins that need real entropy must not run in this mode.
 
+use super::capture::OutputCapture;
 use super::error::WasiError;
-use super::sandbox::{expand_env_pattern, SandboxConfig, SandboxValidator};
-use sen_plugin_api::Capabilities;
+use super::sandbox::{env_glob_matches, expand_env_pattern, SandboxConfig, SandboxValidator};
+use super::scratch::{ScratchDir, SCRATCH_GUEST_PATH};
+use sen_plugin_api::{Capabilities, NetPattern};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Wall-clock time seen by plugins in deterministic mode (2000-01-01T00:00:00Z)
+pub const DETERMINISTIC_WALL_CLOCK: Duration = Duration::from_secs(946_684_800);
+
+/// Bytes cycled by the random generators in deterministic mode
+pub const DETERMINISTIC_RANDOM_SEED: [u8; 8] = *b"sen-rs\x00\x01";
 
 /// Configuration for WASI context building
 #[derive(Debug, Clone)]
@@ -126,6 +162,17 @@ pub struct WasiConfig {
 
     /// Program name (argv[0])
     pub program_name: String,
+
+    /// Environment variable deny-list (glob patterns, e.g. `*_TOKEN`)
+    ///
+    /// Applied after `env_read` expansion: matching variables are never
+    /// passed to the plugin, regardless of what it requested.
+    pub env_deny: Vec<String>,
+
+    /// Replace clocks and random generators with fixed values
+    ///
+    /// See the module docs for what the plugin sees.
+    pub deterministic: bool,
 }
 
 impl Default for WasiConfig {
@@ -136,6 +183,8 @@ impl Default for WasiConfig {
             require_existence: true,
             args: Vec::new(),
             program_name: "plugin".to_string(),
+            env_deny: Vec::new(),
+            deterministic: false,
         }
     }
 }
@@ -190,6 +239,18 @@ impl WasiConfigurer {
         self
     }
 
+    /// Set environment variable patterns that are never passed to the plugin
+    pub fn with_env_deny(mut self, patterns: Vec<String>) -> Self {
+        self.config.env_deny = patterns;
+        self
+    }
+
+    /// Give the plugin a fixed clock and seeded random generators
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.config.deterministic = deterministic;
+        self
+    }
+
     /// Configure symlink following
     pub fn follow_symlinks(mut self, follow: bool) -> Self {
         self.config.follow_symlinks = follow;
@@ -211,6 +272,8 @@ impl WasiConfigurer {
     ///
     /// Returns [`WasiError`] if:
     /// - Working directory is not set
+    /// - A filesystem pattern is a glob (preopens cannot grant less than a
+    ///   whole directory)
     /// - Path validation fails
     /// - Environment pattern is invalid
     pub fn build(self) -> Result<WasiSpec, WasiError> {
@@ -227,6 +290,20 @@ impl WasiConfigurer {
 
         let caps = self.capabilities.unwrap_or_default();
         let mut spec = WasiSpec::new(self.config.program_name, self.config.args);
+        spec.deterministic = self.config.deterministic;
+
+        if let Some(glob) = caps
+            .fs_read
+            .iter()
+            .chain(&caps.fs_write)
+            .find(|p| p.is_glob())
+        {
+            return Err(WasiError::UnsupportedCapability(format!(
+                "glob filesystem pattern '{}' (WASI preopens whole directories; request '{}' instead)",
+                glob.pattern,
+                glob.base_dir()
+            )));
+        }
 
         // Process filesystem read paths
         for pattern in &caps.fs_read {
@@ -261,10 +338,32 @@ impl WasiConfigurer {
             }
         }
 
+        // Private temp directory, fresh for every spec
+        if caps.tmp {
+            let scratch = ScratchDir::new()?;
+            spec.preopened_dirs.push(PreopenedDir {
+                host_path: scratch.path().to_path_buf(),
+                guest_path: SCRATCH_GUEST_PATH.to_string(),
+                writable: true,
+            });
+            spec.scratch_dir = Some(Arc::new(scratch));
+        }
+
         // Process environment variables
         for pattern in &caps.env_read {
             let vars = expand_env_pattern(pattern)?;
             for (key, value) in vars {
+                // Host deny-list wins over the plugin's request
+                if self
+                    .config
+                    .env_deny
+                    .iter()
+                    .any(|deny| env_glob_matches(deny, &key))
+                {
+                    tracing::debug!(key = %key, "Environment variable denied by host");
+                    continue;
+                }
+
                 // Avoid duplicates
                 if !spec.env_vars.iter().any(|(k, _)| k == &key) {
                     spec.env_vars.push((key, value));
@@ -277,13 +376,8 @@ impl WasiConfigurer {
         spec.inherit_stdout = caps.stdio.stdout;
         spec.inherit_stderr = caps.stdio.stderr;
 
-        // Note: Network access is not supported in WASI Preview 1
-        if !caps.net.is_empty() {
-            tracing::warn!(
-                "Network capabilities declared but not supported in WASI Preview 1. \
-                 Network access will be denied."
-            );
-        }
+        // Network: no sockets in WASI Preview 1; served by the host HTTP proxy
+        spec.net = caps.net.clone();
 
         Ok(spec)
     }
@@ -323,6 +417,22 @@ pub struct WasiSpec {
 
     /// Inherit stderr from host
     pub inherit_stderr: bool,
+
+    /// Capture stdout into a size-capped buffer instead of inheriting it
+    pub stdout_capture: Option<OutputCapture>,
+
+    /// Capture stderr into a size-capped buffer instead of inheriting it
+    pub stderr_capture: Option<OutputCapture>,
+
+    /// Network patterns allowed through the host HTTP proxy
+    pub net: Vec<NetPattern>,
+
+    /// Use fixed clocks and seeded random generators
+    pub deterministic: bool,
+
+    /// Temp directory preopened at `/tmp`; removed once the last clone is
+    /// dropped, so keep it alive until the plugin call finishes
+    pub scratch_dir: Option<Arc<ScratchDir>>,
 }
 
 impl WasiSpec {
@@ -336,6 +446,11 @@ impl WasiSpec {
             inherit_stdin: false,
             inherit_stdout: false,
             inherit_stderr: false,
+            stdout_capture: None,
+            stderr_capture: None,
+            net: Vec::new(),
+            deterministic: false,
+            scratch_dir: None,
         }
     }
 
@@ -380,10 +495,14 @@ impl WasiSpec {
         if self.inherit_stdin {
             builder.inherit_stdin();
         }
-        if self.inherit_stdout {
+        if let Some(capture) = &self.stdout_capture {
+            builder.stdout(capture.clone());
+        } else if self.inherit_stdout {
             builder.inherit_stdout();
         }
-        if self.inherit_stderr {
+        if let Some(capture) = &self.stderr_capture {
+            builder.stderr(capture.clone());
+        } else if self.inherit_stderr {
             builder.inherit_stderr();
         }
 
@@ -409,6 +528,10 @@ impl WasiSpec {
                 })?;
         }
 
+        if self.deterministic {
+            configure_determinism(&mut builder);
+        }
+
         Ok(builder.build_p1())
     }
 
@@ -447,10 +570,14 @@ impl WasiSpec {
         if self.inherit_stdin {
             builder.inherit_stdin();
         }
-        if self.inherit_stdout {
+        if let Some(capture) = &self.stdout_capture {
+            builder.stdout(capture.clone());
+        } else if self.inherit_stdout {
             builder.inherit_stdout();
         }
-        if self.inherit_stderr {
+        if let Some(capture) = &self.stderr_capture {
+            builder.stderr(capture.clone());
+        } else if self.inherit_stderr {
             builder.inherit_stderr();
         }
 
@@ -476,6 +603,10 @@ impl WasiSpec {
                 })?;
         }
 
+        if self.deterministic {
+            configure_determinism(&mut builder);
+        }
+
         Ok(builder.build())
     }
 
@@ -548,6 +679,11 @@ impl WasiSpec {
             parts.push(format!("stdio: [{}]", stdio.join(", ")));
         }
 
+        if !self.net.is_empty() {
+            let hosts: Vec<_> = self.net.iter().map(|p| p.host.as_str()).collect();
+            parts.push(format!("net: [{}]", hosts.join(", ")));
+        }
+
         if parts.is_empty() {
             "none".to_string()
         } else {
@@ -569,6 +705,41 @@ pub struct PreopenedDir {
     pub writable: bool,
 }
 
+/// Swap host clocks and randomness for fixed ones (deterministic mode)
+fn configure_determinism(builder: &mut wasmtime_wasi::WasiCtxBuilder) {
+    use wasmtime_wasi::Deterministic;
+
+    builder
+        .wall_clock(FixedClock)
+        .monotonic_clock(FixedClock)
+        .secure_random(Deterministic::new(DETERMINISTIC_RANDOM_SEED.to_vec()))
+        .insecure_random(Deterministic::new(DETERMINISTIC_RANDOM_SEED.to_vec()))
+        .insecure_random_seed(0);
+}
+
+/// Clock that never advances, for deterministic mode
+struct FixedClock;
+
+impl wasmtime_wasi::HostWallClock for FixedClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        DETERMINISTIC_WALL_CLOCK
+    }
+}
+
+impl wasmtime_wasi::HostMonotonicClock for FixedClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
 /// Derive guest path from the original pattern
 ///
 /// ```text
@@ -662,4 +833,71 @@ mod tests {
         assert!(spec.inherit_stdout);
         assert!(spec.inherit_stderr);
     }
+
+    #[test]
+    fn test_glob_pattern_is_rejected() {
+        for caps in [
+            Capabilities::default().with_fs_read(vec![PathPattern::new("./data/**/*.csv")]),
+            Capabilities::default().with_fs_write(vec![PathPattern::new("./data/*.csv")]),
+        ] {
+            let err = WasiConfigurer::new()
+                .with_capabilities(&caps)
+                .with_working_directory(PathBuf::from("/tmp"))
+                .require_existence(false)
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, WasiError::UnsupportedCapability(_)));
+            assert!(err.to_string().contains("request './data' instead"));
+        }
+    }
+
+    #[test]
+    fn test_tmp_preopens_fresh_scratch_dir() {
+        let caps = Capabilities::default().with_tmp(true);
+        let build = || {
+            WasiConfigurer::new()
+                .with_capabilities(&caps)
+                .with_working_directory(PathBuf::from("/tmp"))
+                .build()
+                .unwrap()
+        };
+
+        let spec = build();
+        assert_eq!(spec.preopened_dirs.len(), 1);
+        let dir = &spec.preopened_dirs[0];
+        assert_eq!(dir.guest_path, "/tmp");
+        assert!(dir.writable);
+        assert!(dir.host_path.is_dir());
+        assert_ne!(dir.host_path, build().preopened_dirs[0].host_path);
+
+        let host_path = dir.host_path.clone();
+        drop(spec);
+        assert!(!host_path.exists());
+    }
+
+    #[test]
+    fn test_env_deny_filters_requested_vars() {
+        std::env::set_var("SENDENY_NAME", "visible");
+        std::env::set_var("SENDENY_API_TOKEN", "hidden");
+        std::env::set_var("SECRET_SENDENY_KEY", "hidden");
+
+        // Bare `*` is rejected, so request every test variable by prefix
+        let caps =
+            Capabilities::default().with_env_read(vec!["SENDENY_*".into(), "SECRET_*".into()]);
+
+        let spec = WasiConfigurer::new()
+            .with_capabilities(&caps)
+            .with_working_directory(PathBuf::from("/tmp"))
+            .with_env_deny(vec!["SECRET_*".into(), "*_TOKEN".into()])
+            .build()
+            .unwrap();
+
+        let keys: Vec<_> = spec
+            .env_vars
+            .iter()
+            .filter(|(k, _)| k.contains("SENDENY"))
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(keys, vec!["SENDENY_NAME"]);
+    }
 }
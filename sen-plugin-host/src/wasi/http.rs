@@ -0,0 +1,504 @@
+//! Host-proxy HTTP for WASI plugins
+//!
+//! WASI Preview 1 has no sockets, so plugins reach the network through the
+//! `host_http_request` import instead. The host parses the URL, checks it
+//! against the plugin's declared `net` capabilities, and performs the request
+//! on the plugin's behalf.
+//!
+//! # Guest ABI
+//!
+//! ```text
+//! (import "sen" "host_http_request" (func (param i32 i32) (result i64)))
+//!
+//! Plugin: host_http_request(req_ptr, req_len)    // MessagePack HttpRequest
+//! Host:   parse URL → check against `net` patterns → perform request
+//! Host:   plugin_alloc(n), write MessagePack HttpResult
+//! Plugin: receives packed (ptr << 32 | len), reads result, plugin_dealloc
+//! ```
+//!
+//! # Security
+//!
+//! - Only `http://` and `https://` URLs are accepted
+//! - The URL's protocol, host, and port must be covered by a declared
+//!   [`NetPattern`]; URLs without an explicit port use the scheme default
+//! - Redirects are not followed, so a permitted host cannot bounce the
+//!   request to an undeclared one
+//! - Every request is bounded by the proxy timeout
+//! - Guest-supplied `Host`, `Content-Length`, and hop-by-hop headers are
+//!   dropped; the host derives them from the URL and body, so a plugin cannot
+//!   address another virtual host or desync the connection framing
+//!
+//! Denied requests never touch the network: the plugin receives an
+//! [`HttpError`] with [`HttpErrorKind::Denied`].
+
+use super::loader::WasiState;
+use sen_plugin_api::{
+    HttpError, HttpErrorKind, HttpRequest, HttpResponse, HttpResult, NetPattern, NetProtocol,
+    HOST_IMPORT_MODULE,
+};
+use std::time::Duration;
+use wasmtime::{Caller, Linker};
+
+/// Name of the HTTP host import
+pub const HOST_HTTP_REQUEST: &str = "host_http_request";
+
+/// Default timeout for proxied requests
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Request headers the host sets itself and never takes from the guest
+const HOST_CONTROLLED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// HTTP proxy bound to a plugin's declared network capabilities
+#[derive(Debug, Clone)]
+pub struct HttpProxy {
+    allowed: Vec<NetPattern>,
+    timeout: Duration,
+}
+
+impl HttpProxy {
+    /// Create a proxy that allows only URLs covered by `allowed`
+    pub fn new(allowed: Vec<NetPattern>) -> Self {
+        Self {
+            allowed,
+            timeout: DEFAULT_HTTP_TIMEOUT,
+        }
+    }
+
+    /// Create a proxy that denies every request
+    pub fn deny_all() -> Self {
+        Self::new(Vec::new())
+    }
+
+    /// Set the per-request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Declared network patterns
+    pub fn allowed(&self) -> &[NetPattern] {
+        &self.allowed
+    }
+
+    /// Per-request timeout
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Check a URL against the declared network patterns
+    ///
+    /// Returns the concrete target the URL resolves to on success.
+    pub fn check(&self, url: &str) -> Result<NetPattern, HttpError> {
+        let target = url_target(url)?;
+
+        if self.allowed.iter().any(|p| p.contains(&target)) {
+            Ok(target)
+        } else {
+            Err(HttpError::new(
+                HttpErrorKind::Denied,
+                format!("'{}' is not covered by the plugin's net capabilities", url),
+            ))
+        }
+    }
+
+    /// Validate and perform a request
+    ///
+    /// The request runs synchronously and blocks the calling thread for up
+    /// to [`HttpProxy::timeout`]. Plugins reach it from inside their own
+    /// execution, so async callers must run WASI plugins off the runtime's
+    /// worker threads (e.g. with `tokio::task::spawn_blocking`).
+    pub fn request(&self, request: &HttpRequest) -> HttpResult {
+        if let Err(e) = self.check(&request.url) {
+            tracing::warn!(url = %request.url, "Plugin HTTP request denied");
+            return Err(e);
+        }
+
+        tracing::debug!(method = %request.method, url = %request.url, "Proxying plugin HTTP request");
+
+        let mut builder = ureq::http::Request::builder()
+            .method(request.method.to_uppercase().as_str())
+            .uri(&request.url);
+        for (name, value) in &request.headers {
+            if HOST_CONTROLLED_HEADERS
+                .iter()
+                .any(|h| name.eq_ignore_ascii_case(h))
+            {
+                tracing::debug!(header = %name, "Dropping host-controlled header from plugin request");
+                continue;
+            }
+            builder = builder.header(name, value);
+        }
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(self.timeout))
+            .http_status_as_error(false)
+            .max_redirects(0)
+            .allow_non_standard_methods(true)
+            .build()
+            .into();
+
+        let result = match &request.body {
+            Some(body) => builder
+                .body(body.as_str())
+                .map_err(invalid_request)
+                .and_then(|req| agent.run(req).map_err(transport_error)),
+            None => builder
+                .body(())
+                .map_err(invalid_request)
+                .and_then(|req| agent.run(req).map_err(transport_error)),
+        };
+        let mut response = result?;
+
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_string(), v.to_string()))
+            })
+            .collect();
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .map_err(transport_error)?;
+
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            body,
+            headers,
+        })
+    }
+}
+
+impl Default for HttpProxy {
+    fn default() -> Self {
+        Self::deny_all()
+    }
+}
+
+/// Resolve a URL to the concrete protocol/host/port it targets
+fn url_target(url: &str) -> Result<NetPattern, HttpError> {
+    let uri: ureq::http::Uri = url.parse().map_err(invalid_request)?;
+
+    let (protocol, default_port) = match uri.scheme_str() {
+        Some("https") => (NetProtocol::Https, 443),
+        Some("http") => (NetProtocol::Http, 80),
+        _ => {
+            return Err(HttpError::new(
+                HttpErrorKind::InvalidRequest,
+                format!("URL must start with http:// or https://: {}", url),
+            ))
+        }
+    };
+
+    let host = uri.host().filter(|h| !h.is_empty()).ok_or_else(|| {
+        HttpError::new(
+            HttpErrorKind::InvalidRequest,
+            format!("URL has no host: {}", url),
+        )
+    })?;
+
+    Ok(NetPattern {
+        host: host.to_ascii_lowercase(),
+        port: Some(uri.port_u16().unwrap_or(default_port)),
+        protocol,
+    })
+}
+
+fn invalid_request(e: impl std::fmt::Display) -> HttpError {
+    HttpError::new(HttpErrorKind::InvalidRequest, e.to_string())
+}
+
+fn transport_error(e: ureq::Error) -> HttpError {
+    match e {
+        ureq::Error::Timeout(_) => HttpError::new(HttpErrorKind::Timeout, e.to_string()),
+        ureq::Error::Io(ref io) if io.kind() == std::io::ErrorKind::TimedOut => {
+            HttpError::new(HttpErrorKind::Timeout, e.to_string())
+        }
+        _ => HttpError::new(HttpErrorKind::Transport, e.to_string()),
+    }
+}
+
+/// Register the `host_http_request` import on a WASI linker
+pub(crate) fn add_to_linker(linker: &mut Linker<WasiState>) -> anyhow::Result<()> {
+    linker.func_wrap(
+        HOST_IMPORT_MODULE,
+        HOST_HTTP_REQUEST,
+        |mut caller: Caller<'_, WasiState>, req_ptr: i32, req_len: i32| -> anyhow::Result<i64> {
+            let memory = caller
+                .get_export("memory")
+                .and_then(|e| e.into_memory())
+                .ok_or_else(|| anyhow::anyhow!("Plugin does not export memory"))?;
+
+            let request_bytes = {
+                let data = memory.data(&caller);
+                let start = usize::try_from(req_ptr)?;
+                let end = start
+                    .checked_add(usize::try_from(req_len)?)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Integer overflow: ptr={}, len={}", req_ptr, req_len)
+                    })?;
+                data.get(start..end)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Out of bounds: ptr={}, len={}, memory_size={}",
+                            req_ptr,
+                            req_len,
+                            data.len()
+                        )
+                    })?
+                    .to_vec()
+            };
+
+            let result: HttpResult = match rmp_serde::from_slice::<HttpRequest>(&request_bytes) {
+                Ok(request) => caller.data().http.request(&request),
+                Err(e) => Err(HttpError::new(
+                    HttpErrorKind::InvalidRequest,
+                    format!("Failed to decode request: {}", e),
+                )),
+            };
+
+            let result_bytes = rmp_serde::to_vec_named(&result)?;
+            let result_len = i32::try_from(result_bytes.len())?;
+
+            let alloc_fn = caller
+                .get_export("plugin_alloc")
+                .and_then(|e| e.into_func())
+                .ok_or_else(|| anyhow::anyhow!("Plugin does not export plugin_alloc"))?
+                .typed::<i32, i32>(&caller)?;
+            let result_ptr = alloc_fn.call(&mut caller, result_len)?;
+            memory.write(&mut caller, usize::try_from(result_ptr)?, &result_bytes)?;
+
+            Ok(((result_ptr as i64) << 32) | (result_len as i64 & 0xFFFFFFFF))
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+    use wasmtime::{Engine, Module, Store};
+
+    /// Serve one canned HTTP response and return the raw request received
+    fn mock_server(response: &'static str) -> (u16, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_check_against_net_patterns() {
+        let proxy = HttpProxy::new(vec![
+            NetPattern::https("*.example.com"),
+            NetPattern::http_port("localhost", 8080),
+        ]);
+
+        assert!(proxy.check("https://api.example.com/v1").is_ok());
+        assert!(proxy.check("http://localhost:8080/").is_ok());
+
+        // Wrong protocol, port, or host
+        assert!(proxy
+            .check("http://api.example.com/")
+            .unwrap_err()
+            .is_denied());
+        assert!(proxy
+            .check("http://localhost:9090/")
+            .unwrap_err()
+            .is_denied());
+        assert!(proxy.check("https://evil.com/").unwrap_err().is_denied());
+
+        let err = proxy.check("ftp://api.example.com/").unwrap_err();
+        assert_eq!(err.kind, HttpErrorKind::InvalidRequest);
+    }
+
+    #[test]
+    fn test_default_port_matches_explicit_pattern() {
+        let proxy = HttpProxy::new(vec![NetPattern::https_port("api.example.com", 443)]);
+        assert!(proxy.check("https://api.example.com/").is_ok());
+        assert!(proxy
+            .check("https://api.example.com:8443/")
+            .unwrap_err()
+            .is_denied());
+    }
+
+    #[test]
+    fn test_request_to_mock_server() {
+        let (port, server) =
+            mock_server("HTTP/1.1 201 Created\r\nContent-Length: 5\r\nX-Mock: yes\r\n\r\nhello");
+        let proxy = HttpProxy::new(vec![NetPattern::http_port("127.0.0.1", port)]);
+
+        let response = proxy
+            .request(
+                &HttpRequest::post(format!("http://127.0.0.1:{}/items", port), "{}")
+                    .header("Content-Type", "application/json"),
+            )
+            .unwrap();
+
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, "hello");
+        assert!(response
+            .headers
+            .iter()
+            .any(|(k, v)| k == "x-mock" && v == "yes"));
+
+        let raw = server.join().unwrap();
+        assert!(raw.starts_with("POST /items HTTP/1.1"));
+        assert!(raw
+            .to_ascii_lowercase()
+            .contains("content-type: application/json"));
+    }
+
+    #[test]
+    fn test_host_controlled_headers_are_dropped() {
+        let (port, server) = mock_server("HTTP/1.1 204 No Content\r\n\r\n");
+        let proxy = HttpProxy::new(vec![NetPattern::http_port("127.0.0.1", port)]);
+
+        let response = proxy
+            .request(
+                &HttpRequest::get(format!("http://127.0.0.1:{}/", port))
+                    .header("Host", "internal.example.com")
+                    .header("Transfer-Encoding", "chunked")
+                    .header("Connection", "Upgrade")
+                    .header("Upgrade", "websocket")
+                    .header("X-Trace", "abc"),
+            )
+            .unwrap();
+        assert_eq!(response.status, 204);
+
+        let raw = server.join().unwrap().to_ascii_lowercase();
+        assert!(
+            raw.contains(&format!("host: 127.0.0.1:{}", port)),
+            "{}",
+            raw
+        );
+        assert!(!raw.contains("internal.example.com"));
+        assert!(!raw.contains("transfer-encoding"));
+        assert!(!raw.contains("upgrade"));
+        assert!(raw.contains("x-trace: abc"));
+    }
+
+    #[test]
+    fn test_denied_request_never_connects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        listener.set_nonblocking(true).unwrap();
+
+        let proxy = HttpProxy::new(vec![NetPattern::https("api.example.com")]);
+        let err = proxy
+            .request(&HttpRequest::get(format!("http://127.0.0.1:{}/", port)))
+            .unwrap_err();
+
+        assert!(err.is_denied());
+        assert!(listener.accept().is_err());
+    }
+
+    #[test]
+    fn test_request_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Accept but never respond
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            std::thread::sleep(Duration::from_millis(500));
+            drop(stream);
+        });
+
+        let proxy = HttpProxy::new(vec![NetPattern::http("127.0.0.1")])
+            .with_timeout(Duration::from_millis(100));
+        let err = proxy
+            .request(&HttpRequest::get(format!("http://127.0.0.1:{}/", port)))
+            .unwrap_err();
+
+        assert_eq!(err.kind, HttpErrorKind::Timeout);
+        server.join().unwrap();
+    }
+
+    /// Guest that forwards a request from memory to `host_http_request`
+    const PROXY_GUEST: &str = r#"
+        (module
+            (import "sen" "host_http_request" (func $http (param i32 i32) (result i64)))
+            (memory (export "memory") 1)
+            (global $bump (mut i32) (i32.const 4096))
+            (func (export "plugin_alloc") (param $size i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $bump))
+                (global.set $bump (i32.add (global.get $bump) (local.get $size)))
+                (local.get $ptr))
+            (func (export "call") (param i32 i32) (result i64)
+                (call $http (local.get 0) (local.get 1))))
+    "#;
+
+    fn call_guest(proxy: HttpProxy, request: &HttpRequest) -> HttpResult {
+        let engine = Engine::default();
+        let module = Module::new(&engine, PROXY_GUEST).unwrap();
+        let linker = super::super::loader::create_linker(&engine).unwrap();
+        let mut state = WasiState::empty();
+        state.http = proxy;
+        let mut store = Store::new(&engine, state);
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+
+        let bytes = rmp_serde::to_vec_named(request).unwrap();
+        memory.write(&mut store, 0, &bytes).unwrap();
+        let call = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "call")
+            .unwrap();
+        let packed = call.call(&mut store, (0, bytes.len() as i32)).unwrap();
+
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xFFFFFFFF) as usize);
+        rmp_serde::from_slice(&memory.data(&store)[ptr..ptr + len]).unwrap()
+    }
+
+    #[test]
+    fn test_host_import_roundtrip() {
+        let (port, server) = mock_server("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let proxy = HttpProxy::new(vec![NetPattern::http_port("127.0.0.1", port)]);
+
+        let response = call_guest(
+            proxy,
+            &HttpRequest::get(format!("http://127.0.0.1:{}/ping", port)),
+        )
+        .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+        assert!(server.join().unwrap().starts_with("GET /ping HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_host_import_returns_structured_denial() {
+        let err = call_guest(
+            HttpProxy::deny_all(),
+            &HttpRequest::get("https://api.example.com/"),
+        )
+        .unwrap_err();
+
+        assert!(err.is_denied());
+        assert!(err.message.contains("api.example.com"));
+    }
+}
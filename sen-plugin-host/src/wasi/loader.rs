@@ -20,7 +20,7 @@
 //! │     ├─ Build WasiSpec from capabilities                                     │
 //! │     ├─ Create WasiCtx with configured permissions                           │
 //! │     ├─ Create Store with WasiCtx                                            │
-//! │     ├─ Link WASI imports                                                    │
+//! │     ├─ Link WASI imports + host HTTP proxy                                  │
 //! │     ├─ Call plugin_execute()                                                │
 //! │     └─ Plugin can now access granted resources                              │
 //! │                                                                             │
@@ -36,6 +36,7 @@
 //! | Stdio | Denied | Based on capabilities |
 //! | Store state | `()` | `WasiState` |
 //! | WASI imports | None | Full WASI Preview 1 |
+//! | Network | Denied | `host_http_request` for declared `net` patterns |
 //!
 //! # Example
 //!
@@ -60,15 +61,25 @@
 //!
 //! - **Filesystem**: Only declared paths are accessible
 //! - **Environment**: Only declared variables are visible
-//! - **Network**: Not available (WASI Preview 1 limitation)
+//! - **Network**: HTTP(S) only, via the host proxy (see [`super::http`])
 //! - **CPU**: Still limited by fuel (10M instructions)
 //! - **Stack**: Still limited (1MB)
 
+use super::capture::OutputCapture;
 use super::context::{WasiConfigurer, WasiSpec};
 use super::error::WasiError;
-use crate::loader::LoaderError;
-use sen_plugin_api::{Capabilities, ExecuteResult, PluginManifest, API_VERSION};
+use super::http::{HttpProxy, DEFAULT_HTTP_TIMEOUT};
+use super::scratch::ScratchDir;
+use crate::loader::{
+    check_payload, LoaderError, PluginStats, WasmFeatures, DEFAULT_MAX_PAYLOAD_BYTES,
+};
+use sen_plugin_api::{
+    Capabilities, ExecuteResult, PluginManifest, API_VERSION, MIN_SUPPORTED_API_VERSION,
+};
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use wasmtime::*;
 use wasmtime_wasi::preview1::WasiP1Ctx;
 use wasmtime_wasi::WasiCtxBuilder;
@@ -90,6 +101,45 @@ pub struct WasiLoaderConfig {
 
     /// Maximum WASM stack size in bytes
     pub max_stack_size: usize,
+
+    /// Largest manifest, result, or argument payload passed between host
+    /// and plugin, in bytes
+    ///
+    /// See [`LoaderConfig::max_payload_bytes`](crate::LoaderConfig::max_payload_bytes).
+    pub max_payload_bytes: usize,
+
+    /// Environment variable deny-list (glob patterns)
+    ///
+    /// See [`WasiConfig::env_deny`](super::WasiConfig::env_deny).
+    pub env_deny: Vec<String>,
+
+    /// Timeout for requests made through the host HTTP proxy
+    pub http_timeout: Duration,
+
+    /// Run plugins with a fixed clock and seeded random generators
+    ///
+    /// Makes runs reproducible, e.g. for golden tests or untrusted plugins
+    /// that should not see the host time. See
+    /// [`WasiConfigurer::deterministic`](super::WasiConfigurer::deterministic).
+    pub deterministic: bool,
+
+    /// Cap on the stdout a plugin may produce per call, in bytes
+    ///
+    /// When set, stdout is captured instead of inherited; output past the
+    /// cap is dropped and replaced by a `"... (truncated)"` marker when the
+    /// capture is written to the host's stdout after the call. `None`
+    /// inherits stdout unbounded. See [`super::capture`].
+    pub max_stdout_bytes: Option<usize>,
+
+    /// Cap on the stderr a plugin may produce per call, in bytes
+    ///
+    /// Works like [`max_stdout_bytes`](Self::max_stdout_bytes).
+    pub max_stderr_bytes: Option<usize>,
+
+    /// Wasm proposals plugins may use
+    ///
+    /// See [`LoaderConfig::wasm_features`](crate::LoaderConfig::wasm_features).
+    pub wasm_features: WasmFeatures,
 }
 
 impl Default for WasiLoaderConfig {
@@ -100,29 +150,59 @@ impl Default for WasiLoaderConfig {
             require_existence: true,
             fuel_limit: 10_000_000,
             max_stack_size: 1024 * 1024, // 1MB
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            env_deny: Vec::new(),
+            http_timeout: DEFAULT_HTTP_TIMEOUT,
+            deterministic: false,
+            max_stdout_bytes: None,
+            max_stderr_bytes: None,
+            wasm_features: WasmFeatures::default(),
         }
     }
 }
 
 /// State held by the WASI store
 ///
-/// This struct contains the WASI Preview 1 context needed for plugin execution.
+/// This struct contains the WASI Preview 1 context and the host HTTP proxy
+/// needed for plugin execution.
 pub struct WasiState {
     /// WASI Preview 1 context with configured capabilities
     pub wasi: WasiP1Ctx,
+
+    /// HTTP proxy restricted to the plugin's declared `net` patterns
+    pub http: HttpProxy,
+
+    /// Temp directory for the `tmp` capability, removed with the store
+    pub scratch_dir: Option<Arc<ScratchDir>>,
 }
 
 impl WasiState {
     /// Create a new WASI state from a specification
     pub fn from_spec(spec: WasiSpec) -> Result<Self, WasiError> {
+        let http = HttpProxy::new(spec.net.clone());
+        let scratch_dir = spec.scratch_dir.clone();
         let wasi = spec.build_p1_ctx()?;
-        Ok(Self { wasi })
+        Ok(Self {
+            wasi,
+            http,
+            scratch_dir,
+        })
     }
 
     /// Create an empty WASI state (no capabilities)
     pub fn empty() -> Self {
         let wasi = WasiCtxBuilder::new().build_p1();
-        Self { wasi }
+        Self {
+            wasi,
+            http: HttpProxy::deny_all(),
+            scratch_dir: None,
+        }
+    }
+
+    /// Set the timeout for proxied HTTP requests
+    pub fn with_http_timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.with_timeout(timeout);
+        self
     }
 }
 
@@ -183,6 +263,8 @@ impl WasiPluginLoader {
         // Disable memory64 for wasm32 compatibility
         engine_config.wasm_memory64(false);
 
+        config.wasm_features.apply(&mut engine_config);
+
         let engine = Engine::new(&engine_config).map_err(LoaderError::EngineCreation)?;
 
         Ok(Self { engine, config })
@@ -203,8 +285,8 @@ impl WasiPluginLoader {
     /// based on the plugin's declared capabilities.
     pub fn load(&self, wasm_bytes: &[u8]) -> Result<WasiLoadedPlugin, LoaderError> {
         // 1. Compile module
-        let module =
-            Module::new(&self.engine, wasm_bytes).map_err(LoaderError::ModuleCompilation)?;
+        let module = Module::new(&self.engine, wasm_bytes)
+            .map_err(|e| self.config.wasm_features.compile_error(e))?;
 
         // 2. Create temporary store to read manifest (no WASI needed for this)
         let mut store = Store::new(&self.engine, WasiState::empty());
@@ -213,10 +295,7 @@ impl WasiPluginLoader {
             .map_err(|e| LoaderError::StoreConfig(format!("Failed to set fuel: {}", e)))?;
 
         // 3. Create linker with WASI Preview 1 imports
-        let mut linker: Linker<WasiState> = Linker::new(&self.engine);
-        wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi).map_err(
-            |e| LoaderError::Instantiation(anyhow::anyhow!("Failed to add WASI to linker: {}", e)),
-        )?;
+        let linker = create_linker(&self.engine)?;
 
         // 4. Instantiate to read manifest
         let instance = linker
@@ -261,12 +340,13 @@ impl WasiPluginLoader {
         }
 
         // 8. Read manifest from memory
+        check_payload("manifest", len as usize, self.config.max_payload_bytes)?;
         let manifest_bytes = read_memory(&store, &memory, ptr as usize, len as usize)?;
         let manifest: PluginManifest =
             rmp_serde::from_slice(&manifest_bytes).map_err(LoaderError::Deserialization)?;
 
         // 9. Validate API version
-        if manifest.api_version != API_VERSION {
+        if !(MIN_SUPPORTED_API_VERSION..=API_VERSION).contains(&manifest.api_version) {
             return Err(LoaderError::ApiVersionMismatch {
                 expected: API_VERSION,
                 actual: manifest.api_version,
@@ -296,27 +376,83 @@ impl WasiPluginInstance {
     /// Execute the plugin with given arguments
     ///
     /// This creates a fresh WASI context for each execution, configured
-    /// with the plugin's declared capabilities.
+    /// with the plugin's declared capabilities. Proxied HTTP requests block
+    /// the calling thread (see [`HttpProxy::request`](super::HttpProxy::request)),
+    /// so call this from `spawn_blocking` when inside an async runtime.
     pub fn execute(&self, args: &[String]) -> Result<ExecuteResult, LoaderError> {
+        self.execute_with_stats(args).map(|(result, _)| result)
+    }
+
+    /// Execute the plugin and report the resources the call used
+    ///
+    /// Besides fuel and memory, the stats record whether captured stdout or
+    /// stderr hit [`WasiLoaderConfig::max_stdout_bytes`] /
+    /// [`WasiLoaderConfig::max_stderr_bytes`] and was truncated.
+    pub fn execute_with_stats(
+        &self,
+        args: &[String],
+    ) -> Result<(ExecuteResult, PluginStats), LoaderError> {
+        let stdout = self
+            .config
+            .max_stdout_bytes
+            .filter(|_| self.capabilities.stdio.stdout)
+            .map(OutputCapture::new);
+        let stderr = self
+            .config
+            .max_stderr_bytes
+            .filter(|_| self.capabilities.stdio.stderr)
+            .map(OutputCapture::new);
+
+        let result = self.run(args, stdout.clone(), stderr.clone());
+
+        // Forward captured output even if the call failed
+        if let Some(capture) = &stdout {
+            let mut out = std::io::stdout().lock();
+            let _ = out.write_all(&capture.contents());
+            let _ = out.flush();
+        }
+        if let Some(capture) = &stderr {
+            let _ = std::io::stderr().write_all(&capture.contents());
+        }
+
+        let (result, mut stats) = result?;
+        stats.stdout_truncated = stdout.is_some_and(|c| c.truncated());
+        stats.stderr_truncated = stderr.is_some_and(|c| c.truncated());
+        Ok((result, stats))
+    }
+
+    /// Run one call in a fresh store, writing stdout/stderr to the captures
+    /// if given
+    fn run(
+        &self,
+        args: &[String],
+        stdout: Option<OutputCapture>,
+        stderr: Option<OutputCapture>,
+    ) -> Result<(ExecuteResult, PluginStats), LoaderError> {
         // 1. Build WASI spec from capabilities
-        let spec = WasiConfigurer::new()
+        let mut spec = WasiConfigurer::new()
             .with_capabilities(&self.capabilities)
             .with_working_directory(self.config.working_directory.clone())
             .with_args(args.to_vec())
+            .with_env_deny(self.config.env_deny.clone())
             .follow_symlinks(self.config.follow_symlinks)
             .require_existence(self.config.require_existence)
+            .deterministic(self.config.deterministic)
             .build()
             .map_err(|e| LoaderError::StoreConfig(format!("WASI configuration failed: {}", e)))?;
 
+        spec.stdout_capture = stdout;
+        spec.stderr_capture = stderr;
+
         tracing::debug!(
             capabilities = %spec.permission_summary(),
             "Executing plugin with WASI capabilities"
         );
 
         // 2. Create WASI state
-        let wasi_state = WasiState::from_spec(spec).map_err(|e| {
-            LoaderError::StoreConfig(format!("WASI context creation failed: {}", e))
-        })?;
+        let wasi_state = WasiState::from_spec(spec)
+            .map_err(|e| LoaderError::StoreConfig(format!("WASI context creation failed: {}", e)))?
+            .with_http_timeout(self.config.http_timeout);
 
         // 3. Create store with WASI state
         let mut store = Store::new(&self.engine, wasi_state);
@@ -325,10 +461,7 @@ impl WasiPluginInstance {
             .map_err(|e| LoaderError::StoreConfig(format!("Failed to set fuel: {}", e)))?;
 
         // 4. Create linker with WASI Preview 1 imports
-        let mut linker: Linker<WasiState> = Linker::new(&self.engine);
-        wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi).map_err(
-            |e| LoaderError::Instantiation(anyhow::anyhow!("Failed to add WASI to linker: {}", e)),
-        )?;
+        let linker = create_linker(&self.engine)?;
 
         // 5. Instantiate module
         let instance = linker
@@ -355,6 +488,7 @@ impl WasiPluginInstance {
         // 7. Serialize arguments
         let args_bytes = rmp_serde::to_vec(args)
             .map_err(|e| LoaderError::MemoryAccess(format!("Failed to serialize args: {}", e)))?;
+        check_payload("arguments", args_bytes.len(), self.config.max_payload_bytes)?;
 
         let args_len: i32 = args_bytes.len().try_into().map_err(|_| {
             LoaderError::MemoryAccess(format!(
@@ -402,6 +536,7 @@ impl WasiPluginInstance {
         }
 
         // 10. Read result
+        check_payload("result", result_len as usize, self.config.max_payload_bytes)?;
         let result_bytes = read_memory(&store, &memory, result_ptr as usize, result_len as usize)?;
         let result: ExecuteResult =
             rmp_serde::from_slice(&result_bytes).map_err(LoaderError::Deserialization)?;
@@ -414,7 +549,16 @@ impl WasiPluginInstance {
             tracing::warn!(error = %e, "Failed to deallocate result memory");
         }
 
-        Ok(result)
+        let stats = PluginStats {
+            fuel_consumed: self
+                .config
+                .fuel_limit
+                .saturating_sub(store.get_fuel().unwrap_or(0)),
+            memory_peak_bytes: memory.data_size(&store),
+            ..PluginStats::default()
+        };
+
+        Ok((result, stats))
     }
 
     /// Get the capabilities declared by this plugin
@@ -423,6 +567,21 @@ impl WasiPluginInstance {
     }
 }
 
+/// Create a linker with WASI Preview 1 imports and the host HTTP proxy
+pub(crate) fn create_linker(engine: &Engine) -> Result<Linker<WasiState>, LoaderError> {
+    let mut linker: Linker<WasiState> = Linker::new(engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi).map_err(
+        |e| LoaderError::Instantiation(anyhow::anyhow!("Failed to add WASI to linker: {}", e)),
+    )?;
+    super::http::add_to_linker(&mut linker).map_err(|e| {
+        LoaderError::Instantiation(anyhow::anyhow!(
+            "Failed to add host HTTP import to linker: {}",
+            e
+        ))
+    })?;
+    Ok(linker)
+}
+
 fn read_memory(
     store: &Store<WasiState>,
     memory: &Memory,
@@ -447,6 +606,7 @@ fn read_memory(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{packed, WatPlugin, RESULT_OFFSET};
 
     #[test]
     fn test_wasi_loader_creation() {
@@ -459,4 +619,254 @@ mod tests {
         let _state = WasiState::empty();
         // Just verify it doesn't panic
     }
+
+    /// Guest that writes the wall-clock time and 8 random bytes to memory
+    const CLOCK_GUEST: &str = r#"
+        (module
+            (import "wasi_snapshot_preview1" "clock_time_get"
+                (func $clock (param i32 i64 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "random_get"
+                (func $random (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "run")
+                (drop (call $clock (i32.const 0) (i64.const 1) (i32.const 0)))
+                (drop (call $random (i32.const 8) (i32.const 8)))))
+    "#;
+
+    /// Run [`CLOCK_GUEST`] in a fresh store; returns (clock nanos, random bytes)
+    fn read_clock(deterministic: bool) -> (u64, Vec<u8>) {
+        let engine = Engine::default();
+        let module = Module::new(&engine, CLOCK_GUEST).unwrap();
+        let linker = create_linker(&engine).unwrap();
+        let spec = WasiConfigurer::new()
+            .with_working_directory(PathBuf::from("/tmp"))
+            .require_existence(false)
+            .deterministic(deterministic)
+            .build()
+            .unwrap();
+        let mut store = Store::new(&engine, WasiState::from_spec(spec).unwrap());
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+        let run = instance
+            .get_typed_func::<(), ()>(&mut store, "run")
+            .unwrap();
+        run.call(&mut store, ()).unwrap();
+
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        let data = memory.data(&store);
+        let nanos = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        (nanos, data[8..16].to_vec())
+    }
+
+    const PATH_OPEN: &str = r#"(import "wasi_snapshot_preview1" "path_open"
+        (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))"#;
+    const FD_WRITE: &str = r#"(import "wasi_snapshot_preview1" "fd_write"
+        (func $fd_write (param i32 i32 i32 i32) (result i32)))"#;
+
+    /// iovec `{ buf, len }` as little-endian bytes
+    fn iovec(buf: u32, len: u32) -> Vec<u8> {
+        let mut bytes = buf.to_le_bytes().to_vec();
+        bytes.extend(len.to_le_bytes());
+        bytes
+    }
+
+    /// Plugin with stdout access that writes a 40-byte line to stdout
+    /// `writes` times, then returns `Success("done")`
+    fn chatty_plugin(writes: usize) -> Vec<u8> {
+        use sen_plugin_api::{CommandSpec, StdioCapability};
+
+        let manifest = PluginManifest::with_capabilities(
+            CommandSpec::new("chatty", "Writes to stdout"),
+            Capabilities::default().with_stdio(StdioCapability::stdout_only()),
+        );
+        let result = rmp_serde::to_vec(&ExecuteResult::success("done")).unwrap();
+        let line = b"0123456789abcdefghijklmnopqrstuvwxyzABC\n";
+        let write =
+            "(drop (call $fd_write (i32.const 1) (i32.const 3584) (i32.const 1) (i32.const 3600)))";
+
+        WatPlugin::new(&manifest)
+            .import(FD_WRITE)
+            .data(RESULT_OFFSET, &result)
+            .data(3072, line)
+            .data(3584, &iovec(3072, line.len() as u32))
+            .execute(format!(
+                "{}(i64.const {})",
+                write.repeat(writes),
+                packed(RESULT_OFFSET, result.len())
+            ))
+            .build()
+    }
+
+    /// Plugin with the `tmp` capability that writes `/tmp/scratch.txt`,
+    /// reads it back, and returns its contents as `Success`
+    fn scratch_plugin() -> Vec<u8> {
+        let manifest = PluginManifest::with_capabilities(
+            sen_plugin_api::CommandSpec::new("scratch", "Uses a temp file"),
+            Capabilities::default().with_tmp(true),
+        );
+        // The file is read straight over the placeholder in the result
+        let result = rmp_serde::to_vec(&ExecuteResult::success("_______")).unwrap();
+        let placeholder = result.windows(7).position(|w| w == b"_______").unwrap();
+
+        WatPlugin::new(&manifest)
+            .import(PATH_OPEN)
+            .import(FD_WRITE)
+            .import(
+                r#"(import "wasi_snapshot_preview1" "fd_seek"
+                    (func $fd_seek (param i32 i64 i32 i32) (result i32)))"#,
+            )
+            .import(
+                r#"(import "wasi_snapshot_preview1" "fd_read"
+                    (func $fd_read (param i32 i32 i32 i32) (result i32)))"#,
+            )
+            .data(RESULT_OFFSET, &result)
+            .data(3072, b"scratch.txt")
+            .data(3104, b"scratch")
+            .data(3584, &iovec(3104, 7))
+            .data(3592, &iovec(RESULT_OFFSET + placeholder as u32, 7))
+            .execute(format!(
+                "(local $fd i32)
+                ;; fd 3 is the only preopen: /tmp; O_CREAT | O_TRUNC,
+                ;; rights FD_READ | FD_SEEK | FD_WRITE
+                (drop (call $path_open (i32.const 3) (i32.const 0)
+                    (i32.const 3072) (i32.const 11) (i32.const 9)
+                    (i64.const 70) (i64.const 0) (i32.const 0) (i32.const 3600)))
+                (local.set $fd (i32.load (i32.const 3600)))
+                (drop (call $fd_write (local.get $fd) (i32.const 3584) (i32.const 1) (i32.const 3604)))
+                (drop (call $fd_seek (local.get $fd) (i64.const 0) (i32.const 0) (i32.const 3608)))
+                (drop (call $fd_read (local.get $fd) (i32.const 3592) (i32.const 1) (i32.const 3604)))
+                (i64.const {})",
+                packed(RESULT_OFFSET, result.len())
+            ))
+            .build()
+    }
+
+    /// Plugin with `caps` that opens `target` for writing relative to its
+    /// first preopened directory and writes to it; returns
+    /// `Success("written")`, or `Success("denied")` if WASI refused either
+    /// call
+    fn writer_plugin(caps: Capabilities, target: &str) -> Vec<u8> {
+        let manifest = PluginManifest::with_capabilities(
+            sen_plugin_api::CommandSpec::new("writer", "Writes a file"),
+            caps,
+        );
+        let written = rmp_serde::to_vec(&ExecuteResult::success("written")).unwrap();
+        let denied = rmp_serde::to_vec(&ExecuteResult::success("denied")).unwrap();
+
+        WatPlugin::new(&manifest)
+            .import(PATH_OPEN)
+            .import(FD_WRITE)
+            .data(RESULT_OFFSET, &written)
+            .data(2560, &denied)
+            .data(3072, target.as_bytes())
+            .data(3104, b"data")
+            .data(3584, &iovec(3104, 4))
+            .execute(format!(
+                ";; O_CREAT | O_TRUNC, rights FD_WRITE
+                (if (call $path_open (i32.const 3) (i32.const 0)
+                        (i32.const 3072) (i32.const {target_len}) (i32.const 9)
+                        (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 3600))
+                    (then (return (i64.const {denied_packed}))))
+                (if (call $fd_write (i32.load (i32.const 3600)) (i32.const 3584)
+                        (i32.const 1) (i32.const 3604))
+                    (then (return (i64.const {denied_packed}))))
+                (i64.const {written_packed})",
+                target_len = target.len(),
+                denied_packed = packed(2560, denied.len()),
+                written_packed = packed(RESULT_OFFSET, written.len()),
+            ))
+            .build()
+    }
+
+    #[test]
+    fn test_write_is_denied_outside_granted_scope() {
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(root.path().join("out")).unwrap();
+        let loader = WasiPluginLoader::new(WasiLoaderConfig {
+            working_directory: root.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        let run = |caps: Capabilities, target: &str| {
+            let plugin = loader.load(&writer_plugin(caps, target)).unwrap();
+            match plugin.instance.execute(&[]).unwrap() {
+                ExecuteResult::Success(outcome) => outcome,
+                other => panic!("unexpected result: {:?}", other),
+            }
+        };
+        let read_out = || {
+            Capabilities::default().with_fs_read(vec![sen_plugin_api::PathPattern::new("./out")])
+        };
+        let write_out = || {
+            Capabilities::default().with_fs_write(vec![sen_plugin_api::PathPattern::new("./out")])
+        };
+
+        // A read grant does not allow writing
+        assert_eq!(run(read_out(), "a.txt"), "denied");
+        assert!(!root.path().join("out/a.txt").exists());
+
+        // A write grant stops at its directory
+        assert_eq!(run(write_out(), "../escaped.txt"), "denied");
+        assert!(!root.path().join("escaped.txt").exists());
+
+        // Inside the granted directory the same write succeeds
+        assert_eq!(run(write_out(), "b.txt"), "written");
+        assert_eq!(
+            std::fs::read(root.path().join("out/b.txt")).unwrap(),
+            b"data"
+        );
+    }
+
+    #[test]
+    fn test_tmp_capability_gives_writable_scratch_dir() {
+        let loader = WasiPluginLoader::new(WasiLoaderConfig {
+            working_directory: PathBuf::from("/tmp"),
+            require_existence: false,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let plugin = loader.load(&scratch_plugin()).unwrap();
+        assert!(plugin.manifest.capabilities.tmp);
+        for _ in 0..2 {
+            let result = plugin.instance.execute(&[]).unwrap();
+            assert!(matches!(result, ExecuteResult::Success(ref s) if s == "scratch"));
+        }
+    }
+
+    #[test]
+    fn test_stdout_over_cap_is_truncated() {
+        let loader = WasiPluginLoader::new(WasiLoaderConfig {
+            working_directory: PathBuf::from("/tmp"),
+            require_existence: false,
+            max_stdout_bytes: Some(64),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let plugin = loader.load(&chatty_plugin(4)).unwrap();
+        let (result, stats) = plugin.instance.execute_with_stats(&[]).unwrap();
+        assert!(matches!(result, ExecuteResult::Success(ref s) if s == "done"));
+        assert!(stats.stdout_truncated);
+        assert!(!stats.stderr_truncated);
+        assert!(stats.fuel_consumed > 0);
+
+        let plugin = loader.load(&chatty_plugin(1)).unwrap();
+        let (_, stats) = plugin.instance.execute_with_stats(&[]).unwrap();
+        assert!(!stats.stdout_truncated);
+    }
+
+    #[test]
+    fn test_deterministic_runs_are_identical() {
+        let first = read_clock(true);
+        let second = read_clock(true);
+        assert_eq!(first, second);
+        assert_eq!(
+            first.0,
+            super::super::DETERMINISTIC_WALL_CLOCK.as_nanos() as u64
+        );
+
+        // The host clock is well past 2000-01-01
+        let (host_nanos, _) = read_clock(false);
+        assert!(host_nanos > first.0);
+    }
 }
@@ -52,12 +52,13 @@
 //! |----------|---------|-----------------|
 //! | Filesystem Read | Denied | Allowed for declared paths only |
 //! | Filesystem Write | Denied | Allowed for declared paths only |
-//! | Environment Vars | Denied | Allowed for declared patterns |
+//! | Environment Vars | Denied | Allowed for declared patterns, minus host `env_deny` |
 //! | Stdin | Denied | Allowed if `stdio.stdin = true` |
 //! | Stdout | Denied | Allowed if `stdio.stdout = true` |
 //! | Stderr | Denied | Allowed if `stdio.stderr = true` |
-//! | Network | Denied | Future: WASI Preview 2 |
-//! | Clock/Random | Allowed | Always available (safe) |
+//! | Network | Denied | HTTP(S) to declared `net` patterns via host proxy |
+//! | Temp Directory | Denied | Private `/tmp`, removed after each execution, if `tmp = true` |
+//! | Clock/Random | Allowed | Fixed values if `deterministic` is set |
 //!
 //! ## Path Security
 //!
@@ -99,7 +100,9 @@
 //! stdio.stdout: true              ──►    inherit_stdout()
 //! stdio.stderr: true              ──►    inherit_stderr()
 //!
-//! net: ["api.example.com"]        ──►    (Not yet implemented - Preview 2)
+//! net: ["api.example.com"]        ──►    host_http_request import (HttpProxy)
+//!
+//! tmp: true                       ──►    preopened_dir(<fresh temp dir>, "/tmp", DirPerms::all())
 //! ```
 //!
 //! # Usage
@@ -147,27 +150,25 @@
 //! let mut store = Store::new(&engine, wasi_ctx);
 //! ```
 //!
-//! # Network Access (Future)
+//! # Network Access
 //!
-//! Network access via WASI is planned for a future release using WASI Preview 2:
+//! WASI Preview 1 has no sockets, so network access uses a host proxy
+//! (see [`http`]):
 //!
 //! ```text
-//! Option A: WASI Preview 2 Sockets (complex, full network stack)
-//! ├── wasi:sockets/tcp
-//! ├── wasi:sockets/udp
-//! └── wasi:sockets/dns
-//!
-//! Option B: Host-Proxy HTTP (simpler, HTTP-only)
-//! ├── Plugin calls: host_http_request(url, method, body)
-//! ├── Host validates: net capability contains url's host
-//! └── Host executes: actual HTTP request
-//!
-//! Current recommendation: Option B for MVP (simpler, sufficient for most CLIs)
+//! Host-Proxy HTTP (HTTP-only)
+//! ├── Plugin calls: host_http_request(HttpRequest)  (import module "sen")
+//! ├── Host validates: net capability contains url's protocol/host/port
+//! ├── Host executes: actual HTTP request (with timeout, no redirects)
+//! └── Plugin receives: HttpResult (response or structured HttpError)
 //! ```
 //!
+//! Raw sockets (WASI Preview 2 `wasi:sockets`) are not supported.
+//!
 //! # Module Structure
 //!
 //! - [`context`]: WASI context builder from Capabilities
+//! - [`http`]: Host-proxy HTTP import for declared network access
 //! - [`sandbox`]: Path validation and sandbox enforcement
 //! - [`error`]: WASI-specific error types
 //!
@@ -180,12 +181,21 @@
 //! sen-plugin-host = { version = "0.8", features = ["wasi"] }
 //! ```
 
+pub mod capture;
 pub mod context;
 pub mod error;
+pub mod http;
 pub mod loader;
 pub mod sandbox;
+pub mod scratch;
 
-pub use context::{PreopenedDir, WasiConfig, WasiConfigurer, WasiSpec};
+pub use capture::{OutputCapture, TRUNCATION_MARKER};
+pub use context::{
+    PreopenedDir, WasiConfig, WasiConfigurer, WasiSpec, DETERMINISTIC_RANDOM_SEED,
+    DETERMINISTIC_WALL_CLOCK,
+};
 pub use error::WasiError;
+pub use http::HttpProxy;
 pub use loader::{WasiLoadedPlugin, WasiLoaderConfig, WasiPluginInstance, WasiPluginLoader};
 pub use sandbox::{SandboxConfig, SandboxValidator};
+pub use scratch::{ScratchDir, SCRATCH_GUEST_PATH};
@@ -336,6 +336,40 @@ pub fn expand_env_pattern(pattern: &str) -> Result<Vec<(String, String)>, WasiEr
     }
 }
 
+/// Check whether an environment variable name matches a glob pattern
+///
+/// Unlike capability patterns, `*` may appear anywhere and any number of
+/// times, so deny-lists can express patterns like `*_TOKEN` or `*SECRET*`.
+///
+/// ```rust,ignore
+/// assert!(env_glob_matches("*_TOKEN", "GITHUB_TOKEN"));
+/// assert!(env_glob_matches("SECRET_*", "SECRET_KEY"));
+/// assert!(!env_glob_matches("SECRET_*", "MY_SECRET"));
+/// ```
+pub fn env_glob_matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -369,6 +403,30 @@ mod tests {
         assert!(validate_env_pattern("MY.VAR").is_err());
     }
 
+    #[test]
+    fn test_env_glob_matches() {
+        assert!(env_glob_matches(
+            "AWS_SECRET_ACCESS_KEY",
+            "AWS_SECRET_ACCESS_KEY"
+        ));
+        assert!(!env_glob_matches(
+            "AWS_SECRET_ACCESS_KEY",
+            "AWS_SECRET_ACCESS_KEY_ID"
+        ));
+
+        assert!(env_glob_matches("SECRET_*", "SECRET_KEY"));
+        assert!(env_glob_matches("SECRET_*", "SECRET_"));
+        assert!(!env_glob_matches("SECRET_*", "MY_SECRET_KEY"));
+
+        assert!(env_glob_matches("*_TOKEN", "GITHUB_TOKEN"));
+        assert!(!env_glob_matches("*_TOKEN", "GITHUB_TOKEN_PATH"));
+
+        assert!(env_glob_matches("*SECRET*", "MY_SECRET_KEY"));
+        assert!(env_glob_matches("A*B*C", "A_B_C"));
+        assert!(!env_glob_matches("A*B*C", "A_C_B"));
+        assert!(env_glob_matches("*", "ANYTHING"));
+    }
+
     #[test]
     fn test_normalize_path() {
         let validator = SandboxValidator::new(SandboxConfig {
@@ -0,0 +1,82 @@
+//! Private temp directory for plugins granted the `tmp` capability
+//!
+//! Each execution gets a fresh, empty directory under the host's temp dir,
+//! preopened read-write at [`SCRATCH_GUEST_PATH`]. The directory and
+//! everything the plugin left in it are removed when the [`ScratchDir`] is
+//! dropped, which the loader does when the call's store goes away.
+
+use super::error::WasiError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Guest path of the scratch directory
+pub const SCRATCH_GUEST_PATH: &str = "/tmp";
+
+/// Distinguishes directories created by one host process
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A temp directory that is removed on drop
+#[derive(Debug)]
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Create a new, empty directory under [`std::env::temp_dir`]
+    pub fn new() -> Result<Self, WasiError> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let path = std::env::temp_dir().join(format!(
+            "sen-plugin-{}-{}-{:08x}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            nanos
+        ));
+
+        std::fs::create_dir(&path).map_err(|source| WasiError::PreopenFailed {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Self { path })
+    }
+
+    /// Host path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            tracing::warn!(
+                path = %self.path.display(),
+                error = %e,
+                "Failed to remove plugin temp directory"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_dirs_are_unique_and_removed() {
+        let first = ScratchDir::new().unwrap();
+        let second = ScratchDir::new().unwrap();
+        assert_ne!(first.path(), second.path());
+
+        let path = first.path().to_path_buf();
+        std::fs::create_dir(path.join("nested")).unwrap();
+        std::fs::write(path.join("nested/file.txt"), "data").unwrap();
+
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+}
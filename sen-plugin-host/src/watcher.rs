@@ -3,11 +3,14 @@
 //! Watches plugin directories for changes and automatically
 //! loads, reloads, or unloads plugins.
 
-use crate::{LoaderError, PluginRegistry};
+use crate::permission::PermissionConfig;
+use crate::{LoaderConfig, LoaderError, PluginLoader, PluginRegistry};
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 
 /// Configuration for the hot reload watcher
 #[derive(Debug, Clone)]
@@ -27,6 +30,63 @@ impl Default for WatcherConfig {
     }
 }
 
+/// A watched directory with its own watcher, loader, and permission settings
+///
+/// A tuple `(dir, WatcherConfig, Option<LoaderConfig>)` converts into a
+/// source without a permission override.
+#[derive(Debug)]
+pub struct WatchSource {
+    /// Directory to watch (non-recursively)
+    pub directory: PathBuf,
+    /// Debounce and initial-load settings for this directory
+    pub config: WatcherConfig,
+    /// Loader limits for plugins from this directory, or `None` for the
+    /// registry's default loader
+    pub loader: Option<LoaderConfig>,
+    /// Permission configuration for plugins from this directory, or `None`
+    /// for the registry's
+    pub permissions: Option<PermissionConfig>,
+}
+
+impl WatchSource {
+    /// Watch `directory` with default settings
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            config: WatcherConfig::default(),
+            loader: None,
+            permissions: None,
+        }
+    }
+
+    /// Set the debounce and initial-load settings
+    pub fn with_config(mut self, config: WatcherConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Load this directory's plugins with their own limits
+    pub fn with_loader(mut self, config: LoaderConfig) -> Self {
+        self.loader = Some(config);
+        self
+    }
+
+    /// Check this directory's plugins with their own permission strategy
+    pub fn with_permissions(mut self, config: PermissionConfig) -> Self {
+        self.permissions = Some(config);
+        self
+    }
+}
+
+impl<P: AsRef<Path>> From<(P, WatcherConfig, Option<LoaderConfig>)> for WatchSource {
+    fn from((directory, config, loader): (P, WatcherConfig, Option<LoaderConfig>)) -> Self {
+        Self {
+            loader,
+            ..Self::new(directory).with_config(config)
+        }
+    }
+}
+
 /// Hot reload watcher for plugin directories
 pub struct HotReloadWatcher {
     registry: PluginRegistry,
@@ -34,6 +94,33 @@ pub struct HotReloadWatcher {
     shutdown_tx: mpsc::Sender<()>,
 }
 
+/// A [`WatchSource`] with its loader built
+struct Source {
+    directory: PathBuf,
+    canonical: Option<PathBuf>,
+    config: WatcherConfig,
+    /// Dedicated loader, or `None` to use the registry's default loader
+    loader: Option<PluginLoader>,
+    /// Dedicated permissions, or `None` to use the registry's
+    permissions: Option<Arc<PermissionConfig>>,
+}
+
+impl Source {
+    /// Check if a file lives directly in this source's directory
+    fn contains(&self, path: &Path) -> bool {
+        path.parent().is_some_and(|parent| {
+            parent == self.directory || Some(parent) == self.canonical.as_deref()
+        })
+    }
+}
+
+/// Changed files waiting out one source's debounce
+#[derive(Default)]
+struct Pending {
+    paths: Vec<PathBuf>,
+    deadline: Option<Instant>,
+}
+
 impl HotReloadWatcher {
     /// Create a new hot reload watcher for the given directories
     pub async fn new(
@@ -41,17 +128,67 @@ impl HotReloadWatcher {
         directories: impl IntoIterator<Item = impl AsRef<Path>>,
         config: WatcherConfig,
     ) -> Result<Self, WatcherError> {
-        let directories: Vec<PathBuf> = directories
+        let sources: Vec<_> = directories
             .into_iter()
-            .map(|p| p.as_ref().to_path_buf())
+            .map(|dir| WatchSource::new(dir).with_config(config.clone()))
             .collect();
 
+        Self::with_sources(sources, registry).await
+    }
+
+    /// Create a hot reload watcher with per-directory configuration
+    ///
+    /// Each [`WatchSource`] pairs a directory with its own [`WatcherConfig`],
+    /// and optionally a [`LoaderConfig`] and [`PermissionConfig`]. Plugins
+    /// loaded from a directory use that directory's loader limits and
+    /// permission strategy, falling back to the registry's. Each directory
+    /// debounces its own events, so a busy directory does not hold back
+    /// reloads in another.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let watcher = HotReloadWatcher::with_sources(
+    ///     vec![
+    ///         // Bundled plugins: default limits and permissions
+    ///         WatchSource::new("/usr/share/myapp/plugins"),
+    ///         // User plugins: tighter CPU budget, prompt for capabilities
+    ///         WatchSource::new("~/.myapp/plugins")
+    ///             .with_loader(LoaderConfig {
+    ///                 fuel_limit: 1_000_000,
+    ///                 ..Default::default()
+    ///             })
+    ///             .with_permissions(PermissionPresets::interactive("myapp")?),
+    ///     ],
+    ///     registry,
+    /// )
+    /// .await?;
+    /// ```
+    pub async fn with_sources(
+        sources: impl IntoIterator<Item = impl Into<WatchSource>>,
+        registry: PluginRegistry,
+    ) -> Result<Self, WatcherError> {
+        let mut watch_sources = Vec::new();
+        for source in sources {
+            let WatchSource {
+                directory,
+                config,
+                loader,
+                permissions,
+            } = source.into();
+            watch_sources.push(Source {
+                canonical: directory.canonicalize().ok(),
+                directory,
+                config,
+                loader: loader.map(PluginLoader::with_config).transpose()?,
+                permissions: permissions.map(Arc::new),
+            });
+        }
+
         // Load existing plugins if configured
-        if config.load_existing {
-            for dir in &directories {
-                if dir.exists() && dir.is_dir() {
-                    Self::load_directory(&registry, dir).await?;
-                }
+        for source in &watch_sources {
+            if source.config.load_existing && source.directory.is_dir() {
+                Self::load_directory(&registry, source).await?;
             }
         }
 
@@ -69,7 +206,8 @@ impl HotReloadWatcher {
         .map_err(WatcherError::WatcherInit)?;
 
         // Watch directories
-        for dir in &directories {
+        for source in &watch_sources {
+            let dir = &source.directory;
             if dir.exists() {
                 watcher
                     .watch(dir, RecursiveMode::NonRecursive)
@@ -82,12 +220,15 @@ impl HotReloadWatcher {
 
         // Spawn event processing task
         let registry_clone = registry.clone();
-        let debounce = config.debounce;
         tokio::spawn(async move {
-            let mut pending_events: Vec<PathBuf> = Vec::new();
-            let mut debounce_timer: Option<tokio::time::Instant> = None;
+            // One slot per source, plus one for files matching no source
+            let mut pending: Vec<Pending> = std::iter::repeat_with(Pending::default)
+                .take(watch_sources.len() + 1)
+                .collect();
 
             loop {
+                let next_deadline = pending.iter().filter_map(|p| p.deadline).min();
+
                 tokio::select! {
                     // Check for shutdown
                     _ = shutdown_rx.recv() => {
@@ -99,27 +240,38 @@ impl HotReloadWatcher {
                     Some(WatchEvent::FileEvent(event)) = event_rx.recv() => {
                         for path in event.paths {
                             if Self::is_wasm_file(&path) {
-                                if !pending_events.contains(&path) {
-                                    pending_events.push(path);
+                                let index = Self::source_index(&watch_sources, &path);
+                                let debounce = watch_sources
+                                    .get(index)
+                                    .map_or_else(|| WatcherConfig::default().debounce, |s| s.config.debounce);
+                                let slot = &mut pending[index];
+                                if !slot.paths.contains(&path) {
+                                    slot.paths.push(path);
                                 }
-                                debounce_timer = Some(tokio::time::Instant::now() + debounce);
+                                slot.deadline = Some(Instant::now() + debounce);
                             }
                         }
                     }
 
-                    // Check debounce timer
+                    // Check debounce timers
                     _ = async {
-                        if let Some(deadline) = debounce_timer {
+                        if let Some(deadline) = next_deadline {
                             tokio::time::sleep_until(deadline).await;
                         } else {
                             std::future::pending::<()>().await;
                         }
                     } => {
-                        // Process pending events
-                        for path in pending_events.drain(..) {
-                            Self::handle_file_change(&registry_clone, &path).await;
+                        // Process the sources whose debounce has elapsed
+                        let now = Instant::now();
+                        for (index, slot) in pending.iter_mut().enumerate() {
+                            if slot.deadline.is_some_and(|deadline| deadline <= now) {
+                                slot.deadline = None;
+                                let source = watch_sources.get(index);
+                                for path in slot.paths.drain(..) {
+                                    Self::handle_file_change(&registry_clone, source, &path).await;
+                                }
+                            }
                         }
-                        debounce_timer = None;
                     }
                 }
             }
@@ -132,8 +284,36 @@ impl HotReloadWatcher {
         })
     }
 
+    /// Index of the source a file belongs to, or `sources.len()` if none
+    fn source_index(sources: &[Source], path: &Path) -> usize {
+        sources
+            .iter()
+            .position(|s| s.contains(path))
+            .unwrap_or(sources.len())
+    }
+
+    /// Load a plugin with the source's loader and permissions, or the
+    /// registry's
+    async fn load_from_source(
+        registry: &PluginRegistry,
+        source: Option<&Source>,
+        path: &Path,
+    ) -> Result<String, LoaderError> {
+        registry
+            .load_plugin_from(
+                path,
+                source.and_then(|s| s.loader.as_ref()),
+                source.and_then(|s| s.permissions.clone()),
+            )
+            .await
+    }
+
     /// Load all plugins from a directory
-    async fn load_directory(registry: &PluginRegistry, dir: &Path) -> Result<(), WatcherError> {
+    async fn load_directory(
+        registry: &PluginRegistry,
+        source: &Source,
+    ) -> Result<(), WatcherError> {
+        let dir = &source.directory;
         let entries = std::fs::read_dir(dir).map_err(|e| {
             WatcherError::Io(format!("Failed to read directory {}: {}", dir.display(), e))
         })?;
@@ -141,7 +321,7 @@ impl HotReloadWatcher {
         for entry in entries.flatten() {
             let path = entry.path();
             if Self::is_wasm_file(&path) {
-                match registry.load_plugin(&path).await {
+                match Self::load_from_source(registry, Some(source), &path).await {
                     Ok(cmd) => {
                         tracing::info!(command = %cmd, path = %path.display(), "Loaded plugin");
                     }
@@ -156,10 +336,10 @@ impl HotReloadWatcher {
     }
 
     /// Handle a file change event
-    async fn handle_file_change(registry: &PluginRegistry, path: &Path) {
+    async fn handle_file_change(registry: &PluginRegistry, source: Option<&Source>, path: &Path) {
         if path.exists() {
             // File created or modified - load/reload
-            match registry.reload_by_path(path).await {
+            match Self::load_from_source(registry, source, path).await {
                 Ok(cmd) => {
                     tracing::info!(command = %cmd, path = %path.display(), "Plugin reloaded");
                 }
@@ -175,9 +355,9 @@ impl HotReloadWatcher {
         }
     }
 
-    /// Check if a path is a wasm file
+    /// Check if a path is a plugin file (`.wasm`, or `.wasm.gz` with `compression`)
     fn is_wasm_file(path: &Path) -> bool {
-        path.extension().map(|ext| ext == "wasm").unwrap_or(false)
+        crate::loader::is_plugin_file(path)
     }
 
     /// Get a reference to the plugin registry
@@ -275,4 +455,139 @@ mod tests {
         // Should have unloaded the plugin
         assert!(!registry.has_command("hello").await);
     }
+
+    #[tokio::test]
+    async fn test_watcher_per_source_loader_config() {
+        let system_dir = TempDir::new().unwrap();
+        let user_dir = TempDir::new().unwrap();
+        fs::write(system_dir.path().join("hello.wasm"), HELLO_PLUGIN_WASM).unwrap();
+
+        let fast = WatcherConfig {
+            debounce: Duration::from_millis(100),
+            load_existing: true,
+        };
+        let registry = PluginRegistry::new().unwrap();
+        let _watcher = HotReloadWatcher::with_sources(
+            vec![
+                (system_dir.path(), fast.clone(), None),
+                (
+                    user_dir.path(),
+                    fast,
+                    Some(LoaderConfig {
+                        fuel_limit: 2_000_000,
+                        ..Default::default()
+                    }),
+                ),
+            ],
+            registry.clone(),
+        )
+        .await
+        .unwrap();
+
+        // Loaded from the system dir with the registry's default limits
+        let system_limit = registry.loader_config("hello").await.unwrap().fuel_limit;
+        assert_eq!(system_limit, LoaderConfig::default().fuel_limit);
+
+        // The same plugin dropped into the user dir picks up that dir's limits
+        fs::write(user_dir.path().join("hello.wasm"), HELLO_PLUGIN_WASM).unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let user_limit = registry.loader_config("hello").await.unwrap().fuel_limit;
+        assert_eq!(user_limit, 2_000_000);
+        assert_ne!(system_limit, user_limit);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_per_source_permissions() {
+        use crate::permission::{
+            PermissionContext, PermissionDecision, PermissionGranularity, PermissionPresets,
+            PermissionStrategy,
+        };
+        use crate::RegistryError;
+
+        struct DenyAll;
+        impl PermissionStrategy for DenyAll {
+            fn granularity(&self) -> PermissionGranularity {
+                PermissionGranularity::Plugin
+            }
+            fn inherit_capabilities(&self) -> bool {
+                false
+            }
+            fn check(&self, _ctx: &PermissionContext) -> PermissionDecision {
+                PermissionDecision::Deny("untrusted directory".into())
+            }
+        }
+
+        let system_dir = TempDir::new().unwrap();
+        let user_dir = TempDir::new().unwrap();
+        fs::write(system_dir.path().join("hello.wasm"), HELLO_PLUGIN_WASM).unwrap();
+
+        let fast = WatcherConfig {
+            debounce: Duration::from_millis(100),
+            load_existing: true,
+        };
+        let registry = PluginRegistry::new().unwrap();
+        let _watcher = HotReloadWatcher::with_sources(
+            vec![
+                WatchSource::new(system_dir.path()).with_config(fast.clone()),
+                WatchSource::new(user_dir.path())
+                    .with_config(fast)
+                    .with_permissions(PermissionConfig {
+                        strategy: Arc::new(DenyAll),
+                        ..PermissionPresets::testing()
+                    }),
+            ],
+            registry.clone(),
+        )
+        .await
+        .unwrap();
+
+        // The registry has no permission config, so the system copy runs
+        let args = ["World".to_string()];
+        assert!(registry.execute("hello", &args).await.is_ok());
+
+        // The same plugin from the user dir is checked by that dir's strategy
+        fs::write(user_dir.path().join("hello.wasm"), HELLO_PLUGIN_WASM).unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let result = registry.execute("hello", &args).await;
+        assert!(
+            matches!(result, Err(RegistryError::PermissionDenied { ref reason, .. }) if reason == "untrusted directory"),
+            "unexpected result: {:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watcher_debounces_each_source_separately() {
+        let fast_dir = TempDir::new().unwrap();
+        let slow_dir = TempDir::new().unwrap();
+
+        let registry = PluginRegistry::new().unwrap();
+        let _watcher = HotReloadWatcher::with_sources(
+            vec![
+                WatchSource::new(fast_dir.path()).with_config(WatcherConfig {
+                    debounce: Duration::from_millis(100),
+                    load_existing: true,
+                }),
+                WatchSource::new(slow_dir.path()).with_config(WatcherConfig {
+                    debounce: Duration::from_secs(30),
+                    load_existing: true,
+                }),
+            ],
+            registry.clone(),
+        )
+        .await
+        .unwrap();
+
+        // A change in the slow dir must not push back the fast dir's reload
+        fs::write(fast_dir.path().join("hello.wasm"), HELLO_PLUGIN_WASM).unwrap();
+        fs::write(slow_dir.path().join("other.wasm"), HELLO_PLUGIN_WASM).unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+
+        assert!(
+            registry.has_command("hello").await,
+            "fast dir plugin was not loaded"
+        );
+    }
 }
@@ -1,16 +1,31 @@
 //! Integration tests for plugin-router bridge
 //!
-//! These tests require the `sen-integration` feature to be enabled.
+//! These tests require the `sen-integration` and `testing` features to be
+//! enabled.
 
-#![cfg(feature = "sen-integration")]
+#![cfg(all(feature = "sen-integration", feature = "testing"))]
 
 use sen::Router;
-use sen_plugin_host::{PluginLoader, RouterPluginExt};
+use sen_plugin_api::{ArgSpec, CommandSpec, ExecuteResult, PluginManifest};
+use sen_plugin_host::testing::{packed, WatPlugin};
+use sen_plugin_host::{register_registry_plugins, PluginLoader, PluginRegistry, RouterPluginExt};
 
 const HELLO_PLUGIN_WASM: &[u8] = include_bytes!(
     "../../examples/hello-plugin/target/wasm32-unknown-unknown/release/hello_plugin.wasm"
 );
 
+const UPPER_PLUGIN_WASM: &[u8] = include_bytes!(
+    "../../examples/upper-plugin/target/wasm32-unknown-unknown/release/upper_plugin.wasm"
+);
+
+const GREET_PLUGIN_WASM: &[u8] = include_bytes!(
+    "../../examples/greet-plugin/target/wasm32-unknown-unknown/release/greet_plugin.wasm"
+);
+
+const COMPOSE_PLUGIN_WASM: &[u8] = include_bytes!(
+    "../../examples/compose-plugin/target/wasm32-unknown-unknown/release/compose_plugin.wasm"
+);
+
 #[derive(Clone)]
 struct TestState;
 
@@ -18,6 +33,38 @@ fn args(strs: &[&str]) -> Vec<String> {
     strs.iter().map(|s| s.to_string()).collect()
 }
 
+/// Build a WAT plugin whose `plugin_execute` always returns `result`
+fn fixed_result_plugin(command: &str, result: &ExecuteResult) -> Vec<u8> {
+    WatPlugin::command(command, "Fixed result plugin")
+        .returns(result)
+        .build()
+}
+
+/// Build a WAT plugin whose `plugin_execute` returns `first` and whose
+/// `plugin_resume` returns `resumed`
+fn two_step_plugin(command: &str, first: &ExecuteResult, resumed: &ExecuteResult) -> Vec<u8> {
+    let resumed = rmp_serde::to_vec(resumed).unwrap();
+    let resumed_ptr = 4096;
+
+    WatPlugin::command(command, "Two step plugin")
+        .returns(first)
+        .data(resumed_ptr, &resumed)
+        .field(format!(
+            r#"(func (export "plugin_resume") (param i32 i32 i32) (result i64)
+                (i64.const {}))"#,
+            packed(resumed_ptr, resumed.len())
+        ))
+        .build()
+}
+
+/// Build a WAT plugin for `spec` whose `plugin_execute` traps, so any call
+/// that reaches the plugin fails with exit code 101
+fn trapping_plugin(spec: CommandSpec) -> Vec<u8> {
+    WatPlugin::new(&PluginManifest::new(spec))
+        .execute("(unreachable)")
+        .build()
+}
+
 #[tokio::test]
 async fn test_router_plugin_integration() {
     let loader = PluginLoader::new().expect("Failed to create loader");
@@ -137,3 +184,218 @@ async fn test_plugin_help_integration() {
         _ => panic!("Expected text output for help"),
     }
 }
+
+#[tokio::test]
+async fn test_plugin_user_error_maps_to_exit_code() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader
+        .load(&fixed_result_plugin(
+            "fail",
+            &ExecuteResult::user_error("Missing required argument: file"),
+        ))
+        .expect("Failed to load plugin");
+
+    let router = Router::new().plugin(plugin).with_state(TestState);
+    let response = router.execute_with(&args(&["test", "fail"])).await;
+
+    assert_eq!(response.exit_code, 1);
+    match &response.output {
+        sen::Output::Text(s) => assert_eq!(s, "Missing required argument: file"),
+        _ => panic!("Expected text output"),
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&response.to_agent_json()).unwrap();
+    assert_eq!(json["result"], "error");
+    assert_eq!(json["exit_code"], 1);
+    assert_eq!(json["output"], "Missing required argument: file");
+}
+
+#[tokio::test]
+async fn test_plugin_error_codes_pass_through() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+
+    for (code, message) in [(101u8, "Internal failure"), (42u8, "Custom failure")] {
+        let plugin = loader
+            .load(&fixed_result_plugin(
+                "fail",
+                &ExecuteResult::Error(sen_plugin_api::ExecuteError {
+                    code,
+                    message: message.to_string(),
+                }),
+            ))
+            .expect("Failed to load plugin");
+
+        let router = Router::new().plugin(plugin).with_state(TestState);
+        let response = router.execute_with(&args(&["test", "fail"])).await;
+
+        assert_eq!(response.exit_code, code as i32);
+        match &response.output {
+            sen::Output::Text(s) => assert_eq!(s, message),
+            _ => panic!("Expected text output"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_plugin_progress_forwarded_to_router() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader
+        .load(&two_step_plugin(
+            "stream",
+            &ExecuteResult::progress(1, 4),
+            &ExecuteResult::success("done"),
+        ))
+        .expect("Failed to load plugin");
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let router = Router::new()
+        .plugin(plugin)
+        .with_progress_sink(tx)
+        .with_state(TestState);
+    let response = router.execute_with(&args(&["test", "stream"])).await;
+
+    assert_eq!(response.exit_code, 0);
+    match &response.output {
+        sen::Output::Text(s) => assert_eq!(s, "done"),
+        _ => panic!("Expected text output"),
+    }
+
+    let event = rx.try_recv().expect("Expected a progress event");
+    assert_eq!(event.fraction, 0.25);
+    assert_eq!(event.message, "1/4");
+}
+
+#[tokio::test]
+async fn test_plugin_emit_written_to_router_writer() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader
+        .load(&two_step_plugin(
+            "stream",
+            &ExecuteResult::emit("chunk 1"),
+            &ExecuteResult::success("done"),
+        ))
+        .expect("Failed to load plugin");
+
+    let buffer = std::sync::Arc::new(std::sync::Mutex::new(Vec::<u8>::new()));
+    let router = Router::new()
+        .plugin(plugin)
+        .with_writer(buffer.clone())
+        .with_state(TestState);
+    let response = router.execute_with(&args(&["test", "stream"])).await;
+
+    assert_eq!(response.exit_code, 0);
+    let captured = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+    assert_eq!(captured, "chunk 1\n");
+}
+
+#[tokio::test]
+async fn test_registry_routes_invoke_other_plugins() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let registry = PluginRegistry::new().unwrap();
+    for wasm in [GREET_PLUGIN_WASM, UPPER_PLUGIN_WASM, COMPOSE_PLUGIN_WASM] {
+        registry.register(loader.load(wasm).unwrap()).await;
+    }
+
+    let router = register_registry_plugins(Router::new(), &registry)
+        .await
+        .with_state(TestState);
+    let response = router
+        .execute_with(&args(&["test", "compose", "Rust"]))
+        .await;
+
+    assert_eq!(response.exit_code, 0);
+    match &response.output {
+        sen::Output::Text(s) => assert_eq!(s, "Hello, Rust! | HELLO, RUST!"),
+        _ => panic!("Expected text output"),
+    }
+}
+
+#[tokio::test]
+async fn test_owned_plugin_invocation_points_to_registry_routes() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader.load(COMPOSE_PLUGIN_WASM).unwrap();
+
+    let router = Router::new().plugin(plugin).with_state(TestState);
+    let response = router
+        .execute_with(&args(&["test", "compose", "Rust"]))
+        .await;
+
+    assert_eq!(response.exit_code, 101);
+    match &response.output {
+        sen::Output::Text(s) => assert!(s.contains("WasmHandler::from_registry"), "{}", s),
+        _ => panic!("Expected text output"),
+    }
+}
+
+#[tokio::test]
+async fn test_dry_run_rejected_for_plugins_without_the_option() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader
+        .load(UPPER_PLUGIN_WASM)
+        .expect("Failed to load plugin");
+
+    let router = Router::new()
+        .plugin(plugin)
+        .with_dry_run_flag()
+        .with_state(TestState);
+
+    let response = router.execute_with(&args(&["test", "upper", "hi"])).await;
+    match &response.output {
+        sen::Output::Text(s) => assert_eq!(s, "HI"),
+        _ => panic!("Expected text output"),
+    }
+
+    // upper declares no --dry-run option, so the router refuses to run it
+    let response = router
+        .execute_with(&args(&["test", "--dry-run", "upper", "hi"]))
+        .await;
+    assert_eq!(response.exit_code, 1);
+    match &response.output {
+        sen::Output::Text(s) => assert!(s.contains("'upper' does not support --dry-run"), "{}", s),
+        _ => panic!("Expected text output"),
+    }
+}
+
+#[tokio::test]
+async fn test_missing_required_args_rejected_before_execution() {
+    let spec = CommandSpec::new("copy", "Copy a file")
+        .arg(ArgSpec::positional("source").required())
+        .arg(ArgSpec::positional("dest").required())
+        .arg(ArgSpec::option("mode", "mode").required());
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let plugin = loader
+        .load(&trapping_plugin(spec))
+        .expect("Failed to load plugin");
+    let router = Router::new().plugin(plugin).with_state(TestState);
+
+    let response = router
+        .execute_with(&args(&["test", "copy", "a.txt", "--mode", "fast"]))
+        .await;
+    assert_eq!(response.exit_code, 1);
+    match &response.output {
+        sen::Output::Text(s) => {
+            assert!(s.contains("Missing required argument: dest"), "{}", s);
+            assert!(!s.contains("source"));
+            assert!(!s.contains("--mode"));
+        }
+        _ => panic!("Expected text output"),
+    }
+
+    let response = router
+        .execute_with(&args(&["test", "copy", "a.txt", "b.txt", "c.txt"]))
+        .await;
+    assert_eq!(response.exit_code, 1);
+    match &response.output {
+        sen::Output::Text(s) => {
+            assert!(s.contains("Missing required option: --mode"), "{}", s);
+            assert!(s.contains("Expected at most 2 positional arguments, got 3"));
+        }
+        _ => panic!("Expected text output"),
+    }
+
+    // Complete args reach the plugin (which traps)
+    let response = router
+        .execute_with(&args(&["test", "copy", "--mode=fast", "a.txt", "b.txt"]))
+        .await;
+    assert_eq!(response.exit_code, 101);
+}
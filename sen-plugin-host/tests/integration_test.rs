@@ -1,12 +1,17 @@
 //! Integration tests for plugin loading and execution
 
-use sen_plugin_api::{Capabilities, ExecuteResult, PathPattern, StdioCapability};
+use async_trait::async_trait;
+use sen_plugin_api::{
+    Capabilities, Effect, EffectResult, ExecuteResult, PathPattern, StdioCapability,
+};
 use sen_plugin_host::permission::{
     AutoPromptHandler, CiPermissionStrategy, DefaultPermissionStrategy, MemoryPermissionStore,
     PermissionConfig, PermissionPresets, PermissionStore, PromptResult, RecordingPromptHandler,
     TrustFlagConfig,
 };
-use sen_plugin_host::{audit, PluginLoader, PluginRegistry};
+use sen_plugin_host::{
+    audit, EffectHandler, PipelineInput, PluginLoader, PluginRegistry, RegistryError,
+};
 
 const HELLO_PLUGIN_WASM: &[u8] = include_bytes!(
     "../../examples/hello-plugin/target/wasm32-unknown-unknown/release/hello_plugin.wasm"
@@ -16,6 +21,14 @@ const GREET_PLUGIN_WASM: &[u8] = include_bytes!(
     "../../examples/greet-plugin/target/wasm32-unknown-unknown/release/greet_plugin.wasm"
 );
 
+const UPPER_PLUGIN_WASM: &[u8] = include_bytes!(
+    "../../examples/upper-plugin/target/wasm32-unknown-unknown/release/upper_plugin.wasm"
+);
+
+const COMPOSE_PLUGIN_WASM: &[u8] = include_bytes!(
+    "../../examples/compose-plugin/target/wasm32-unknown-unknown/release/compose_plugin.wasm"
+);
+
 #[test]
 fn test_load_hello_plugin() {
     let loader = PluginLoader::new().expect("Failed to create loader");
@@ -53,6 +66,25 @@ fn test_execute_hello_plugin_default() {
     }
 }
 
+#[test]
+fn test_execute_hello_plugin_reports_stats() {
+    let loader = PluginLoader::new().expect("Failed to create loader");
+    let mut plugin = loader
+        .load(HELLO_PLUGIN_WASM)
+        .expect("Failed to load plugin");
+
+    let (result, stats) = plugin
+        .instance
+        .execute_with_stats(&["Rust".to_string()])
+        .expect("Execution failed");
+
+    assert!(matches!(result, ExecuteResult::Success(ref s) if s == "Hello, Rust!"));
+    assert!(stats.fuel_consumed > 0);
+    assert!(stats.fuel_consumed <= loader.config().fuel_limit);
+    assert!(stats.memory_peak_bytes > 0);
+    assert_eq!(plugin.instance.stats(), stats);
+}
+
 #[test]
 fn test_execute_hello_plugin_with_name() {
     let loader = PluginLoader::new().expect("Failed to create loader");
@@ -149,6 +181,136 @@ fn test_execute_greet_plugin_sdk() {
     }
 }
 
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+async fn pipeline_registry(registry: PluginRegistry) -> PluginRegistry {
+    let loader = PluginLoader::new().unwrap();
+    registry
+        .register(loader.load(GREET_PLUGIN_WASM).unwrap())
+        .await;
+    registry
+        .register(loader.load(UPPER_PLUGIN_WASM).unwrap())
+        .await;
+    registry
+}
+
+#[tokio::test]
+async fn test_pipeline_chains_greet_into_upper() {
+    let registry = pipeline_registry(PluginRegistry::new().unwrap()).await;
+
+    let result = registry
+        .execute_pipeline(&[("greet", vec!["Rust".to_string()]), ("upper", vec![])])
+        .await
+        .unwrap();
+
+    match result {
+        ExecuteResult::Success(output) => assert_eq!(output, "HELLO, RUST!"),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_last_arg_input() {
+    let registry = pipeline_registry(PluginRegistry::new().unwrap()).await;
+
+    // greet takes [name, greeting]; upper's output becomes the greeting
+    let result = registry
+        .execute_pipeline_with(
+            &[
+                ("upper", vec!["hey".to_string()]),
+                ("greet", vec!["Rust".to_string()]),
+            ],
+            PipelineInput::LastArg,
+        )
+        .await
+        .unwrap();
+
+    match result {
+        ExecuteResult::Success(output) => assert_eq!(output, "HEY, Rust!"),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_reports_failed_stage() {
+    let registry = pipeline_registry(PluginRegistry::new().unwrap()).await;
+
+    let err = registry
+        .execute_pipeline(&[("greet", vec![]), ("missing", vec![]), ("upper", vec![])])
+        .await
+        .unwrap_err();
+    match err {
+        RegistryError::PipelineStage {
+            stage,
+            command,
+            source,
+        } => {
+            assert_eq!(stage, 1);
+            assert_eq!(command, "missing");
+            assert!(matches!(*source, RegistryError::CommandNotFound(_)));
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+
+    // A plugin-level error also stops the pipeline: upper requires its text
+    let err = registry
+        .execute_pipeline_with(
+            &[("greet", vec![]), ("upper", vec![])],
+            PipelineInput::Ignore,
+        )
+        .await
+        .unwrap_err();
+    match err {
+        RegistryError::PipelineStage { stage, source, .. } => {
+            assert_eq!(stage, 1);
+            match *source {
+                RegistryError::Plugin(error) => {
+                    assert_eq!(error.code, 1);
+                    assert_eq!(error.message, "Missing required argument: text");
+                }
+                other => panic!("Unexpected stage error: {:?}", other),
+            }
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_checks_permissions_per_stage() {
+    let config = PermissionConfig {
+        strategy: std::sync::Arc::new(CiPermissionStrategy),
+        store: std::sync::Arc::new(MemoryPermissionStore::new()),
+        prompt: std::sync::Arc::new(AutoPromptHandler::always_deny()),
+        audit: std::sync::Arc::new(audit::NullAuditSink),
+        trust_flags: TrustFlagConfig::disabled(),
+    };
+    let registry = PluginRegistry::with_permissions(config).unwrap();
+
+    let loader = PluginLoader::new().unwrap();
+    registry
+        .register(loader.load(GREET_PLUGIN_WASM).unwrap())
+        .await;
+    let mut upper = loader.load(UPPER_PLUGIN_WASM).unwrap();
+    upper.manifest.capabilities =
+        Capabilities::default().with_fs_read(vec![PathPattern::new("/sensitive")]);
+    registry.register(upper).await;
+
+    // greet needs no capabilities and runs; upper is denied in CI mode
+    let err = registry
+        .execute_pipeline(&[("greet", vec![]), ("upper", vec![])])
+        .await
+        .unwrap_err();
+    match err {
+        RegistryError::PipelineStage { stage, source, .. } => {
+            assert_eq!(stage, 1);
+            assert!(matches!(*source, RegistryError::PermissionDenied { .. }));
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+}
+
 // ============================================================================
 // Permission Integration Tests
 // ============================================================================
@@ -156,6 +318,58 @@ fn test_execute_greet_plugin_sdk() {
 /// Test: Full permission workflow with registry
 ///
 /// Verifies: load plugin -> check capabilities -> prompt -> store -> execute
+/// Effect handler for plugins that only invoke other plugins
+struct NoEffects;
+
+#[async_trait]
+impl EffectHandler for NoEffects {
+    async fn handle(&self, effect: Effect) -> EffectResult {
+        EffectResult::Error(format!("Unexpected effect: {:?}", effect))
+    }
+}
+
+#[tokio::test]
+async fn test_plugin_invokes_other_plugins() {
+    let registry = pipeline_registry(PluginRegistry::new().unwrap()).await;
+    let loader = PluginLoader::new().unwrap();
+    registry
+        .register(loader.load(COMPOSE_PLUGIN_WASM).unwrap())
+        .await;
+
+    let result = registry
+        .execute_with_effects("compose", &["Rust".to_string()], &NoEffects)
+        .await
+        .unwrap();
+
+    match result {
+        ExecuteResult::Success(output) => assert_eq!(output, "Hello, Rust! | HELLO, RUST!"),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_plugin_invocation_failure_resumes_caller() {
+    // Only compose is registered, so invoking greet fails
+    let registry = PluginRegistry::new().unwrap();
+    let loader = PluginLoader::new().unwrap();
+    registry
+        .register(loader.load(COMPOSE_PLUGIN_WASM).unwrap())
+        .await;
+
+    let result = registry
+        .execute_with_effects("compose", &["Rust".to_string()], &NoEffects)
+        .await
+        .unwrap();
+
+    match result {
+        ExecuteResult::Error(e) => {
+            assert!(e.message.contains("Invocation failed"), "{}", e.message);
+            assert!(e.message.contains("greet"), "{}", e.message);
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
 #[tokio::test]
 async fn test_permission_workflow_end_to_end() {
     // Setup: registry with recording prompt and memory store
@@ -0,0 +1,497 @@
+//! Derive macro for sen-plugin-sdk plugins.
+//!
+//! Use it through the SDK re-export (`sen_plugin_sdk::Plugin`) rather than
+//! depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Data, DeriveInput, Fields, Token,
+};
+
+/// Derives the `Plugin` trait and the Wasm exports for a struct.
+///
+/// # Usage
+///
+/// ```ignore
+/// use sen_plugin_sdk::prelude::*;
+///
+/// /// Greets a person with a custom message
+/// #[derive(Plugin)]
+/// #[plugin(command = "greet", version = "1.0.0")]
+/// struct Greet {
+///     /// Name to greet
+///     #[arg(positional, default = "World")]
+///     name: String,
+///
+///     /// Custom greeting
+///     #[arg(short = 'g', default = "Hello")]
+///     greeting: String,
+///
+///     /// Print in upper case
+///     shout: bool,
+/// }
+///
+/// impl Greet {
+///     fn run(self) -> ExecuteResult {
+///         ExecuteResult::success(format!("{}, {}!", self.greeting, self.name))
+///     }
+/// }
+/// ```
+///
+/// This generates:
+///
+/// - `Plugin::manifest()` with one `ArgSpec` per field
+/// - `Plugin::execute()`, which parses the arguments into the struct and
+///   calls its `run(self) -> ExecuteResult` method
+/// - The exports of `export_plugin!`
+///
+/// # Attributes
+///
+/// On the struct, `#[plugin(...)]`:
+/// - `command = "..."` (required): command name
+/// - `about = "..."`: description (defaults to the struct's doc comment)
+/// - `version = "..."`: command version
+///
+/// On fields, `#[arg(...)]` (optional):
+/// - `positional`: positional argument instead of an option
+/// - `long = "..."`: long option name (defaults to the field name in kebab-case)
+/// - `short = 'x'`: short option name
+/// - `default = "..."`: default value
+/// - `help = "..."`: help text (defaults to the field's doc comment)
+///
+/// # Field Types
+///
+/// - `String`, `PathBuf`, `char`, integer and float types: a value,
+///   required unless it has a default
+/// - `Option<T>` of those: an optional value
+/// - `bool`: a flag option (`--shout`), `true` when present
+///
+/// Any other type is a compile error.
+#[proc_macro_derive(Plugin, attributes(plugin, arg))]
+pub fn derive_plugin(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_plugin(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_plugin(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let struct_name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(syn::DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                struct_name,
+                "#[derive(Plugin)] only supports structs with named fields",
+            ))
+        }
+    };
+
+    let attrs = plugin_attrs(input)?;
+    let command = &attrs.command;
+    let about = match attrs.about.or_else(|| doc_comment(&input.attrs)) {
+        Some(about) => about,
+        None => {
+            return Err(syn::Error::new_spanned(
+                struct_name,
+                "missing description: add `about = \"...\"` to #[plugin(...)] or a doc comment",
+            ))
+        }
+    };
+    let version_call = attrs.version.map(|v| quote! { .version(#v) });
+
+    let mut arg_specs = Vec::new();
+    let mut flags = Vec::new();
+    let mut field_inits = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let name = ident.to_string().trim_start_matches("r#").to_string();
+        let kind = field_kind(&field.ty)?;
+        let arg = arg_attrs(field)?;
+
+        if kind == FieldKind::Flag && arg.positional {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "bool fields are flags and cannot be positional",
+            ));
+        }
+        if kind != FieldKind::Value && arg.default.is_some() {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "`default` is only supported on non-Option value fields",
+            ));
+        }
+
+        let mut spec = if arg.positional {
+            quote! { ::sen_plugin_sdk::ArgSpec::positional(#name) }
+        } else {
+            let long = arg.long.unwrap_or_else(|| name.replace('_', "-"));
+            quote! { ::sen_plugin_sdk::ArgSpec::option(#name, #long) }
+        };
+        if let Some(short) = arg.short {
+            spec.extend(quote! { .short(#short) });
+        }
+        if let Some(help) = arg.help.or_else(|| doc_comment(&field.attrs)) {
+            spec.extend(quote! { .help(#help) });
+        }
+        if let Some(default) = &arg.default {
+            spec.extend(quote! { .default(#default) });
+        }
+        if kind == FieldKind::Value && arg.default.is_none() {
+            spec.extend(quote! { .required() });
+        }
+        arg_specs.push(spec);
+
+        field_inits.push(match kind {
+            FieldKind::Flag => {
+                flags.push(name.clone());
+                quote! { #ident: parsed.flag(#name) }
+            }
+            FieldKind::Value => quote! { #ident: parsed.value(#name)? },
+            FieldKind::Optional => quote! { #ident: parsed.optional(#name)? },
+        });
+    }
+
+    Ok(quote! {
+        impl ::sen_plugin_sdk::Plugin for #struct_name {
+            fn manifest() -> ::sen_plugin_sdk::PluginManifest {
+                ::sen_plugin_sdk::PluginManifest::new(
+                    ::sen_plugin_sdk::CommandSpec::new(#command, #about)
+                        #version_call
+                        #(.arg(#arg_specs))*
+                )
+            }
+
+            fn execute(args: Vec<String>) -> ::sen_plugin_sdk::ExecuteResult {
+                let spec = <Self as ::sen_plugin_sdk::Plugin>::manifest().command;
+                let plugin = ::sen_plugin_sdk::args::ParsedArgs::parse(&spec, &[#(#flags),*], &args)
+                    .and_then(|parsed| -> ::std::result::Result<Self, String> {
+                        Ok(Self { #(#field_inits),* })
+                    });
+                match plugin {
+                    Ok(plugin) => plugin.run(),
+                    Err(message) => ::sen_plugin_sdk::ExecuteResult::user_error(message),
+                }
+            }
+        }
+
+        ::sen_plugin_sdk::export_plugin!(#struct_name);
+    })
+}
+
+/// How a field is parsed from the arguments
+#[derive(Debug, PartialEq, Eq)]
+enum FieldKind {
+    /// `bool`: present or not
+    Flag,
+    /// A value parsed with `FromStr`
+    Value,
+    /// `Option<T>` of a value
+    Optional,
+}
+
+/// Types parsed with `FromStr`
+const VALUE_TYPES: &[&str] = &[
+    "String", "PathBuf", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32",
+    "u64", "u128", "usize", "f32", "f64",
+];
+
+/// Classify a field type, rejecting types the argument parser cannot fill
+fn field_kind(ty: &syn::Type) -> syn::Result<FieldKind> {
+    let unsupported = || {
+        syn::Error::new_spanned(
+            ty,
+            "unsupported field type for #[derive(Plugin)]: expected String, PathBuf, char, \
+             bool, an integer or float type, or Option of a non-bool type",
+        )
+    };
+
+    let segment = match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            type_path.path.segments.last().ok_or_else(unsupported)?
+        }
+        _ => return Err(unsupported()),
+    };
+    let ident = segment.ident.to_string();
+
+    if ident == "bool" && segment.arguments.is_empty() {
+        return Ok(FieldKind::Flag);
+    }
+    if VALUE_TYPES.contains(&ident.as_str()) && segment.arguments.is_empty() {
+        return Ok(FieldKind::Value);
+    }
+    if ident == "Option" {
+        if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+            if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                if field_kind(inner).map_err(|_| unsupported())? == FieldKind::Value {
+                    return Ok(FieldKind::Optional);
+                }
+            }
+        }
+    }
+    Err(unsupported())
+}
+
+/// Join the `///` lines of an item into one line
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
+
+/// Parsed #[plugin(...)] attribute
+struct PluginAttrs {
+    command: String,
+    about: Option<String>,
+    version: Option<String>,
+}
+
+fn plugin_attrs(input: &DeriveInput) -> syn::Result<PluginAttrs> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("plugin"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &input.ident,
+                "missing #[plugin(command = \"...\")] attribute",
+            )
+        })?;
+    attr.parse_args()
+}
+
+impl Parse for PluginAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut command = None;
+        let mut about = None;
+        let mut version = None;
+
+        while !input.is_empty() {
+            let ident: syn::Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value: syn::LitStr = input.parse()?;
+
+            match ident.to_string().as_str() {
+                "command" => command = Some(value.value()),
+                "about" => about = Some(value.value()),
+                "version" => version = Some(value.value()),
+                other => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!("unknown plugin attribute `{}`", other),
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let command = command.ok_or_else(|| {
+            syn::Error::new(
+                input.span(),
+                "missing `command = \"...\"` in #[plugin(...)]",
+            )
+        })?;
+
+        Ok(PluginAttrs {
+            command,
+            about,
+            version,
+        })
+    }
+}
+
+/// Parsed #[arg(...)] attribute
+#[derive(Default)]
+struct ArgAttrs {
+    positional: bool,
+    long: Option<String>,
+    short: Option<char>,
+    default: Option<String>,
+    help: Option<String>,
+}
+
+fn arg_attrs(field: &syn::Field) -> syn::Result<ArgAttrs> {
+    match field.attrs.iter().find(|attr| attr.path().is_ident("arg")) {
+        Some(attr) => attr.parse_args(),
+        None => Ok(ArgAttrs::default()),
+    }
+}
+
+impl Parse for ArgAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = ArgAttrs::default();
+
+        while !input.is_empty() {
+            let ident: syn::Ident = input.parse()?;
+
+            match ident.to_string().as_str() {
+                "positional" => attrs.positional = true,
+                "long" => {
+                    input.parse::<Token![=]>()?;
+                    attrs.long = Some(input.parse::<syn::LitStr>()?.value());
+                }
+                "short" => {
+                    input.parse::<Token![=]>()?;
+                    attrs.short = Some(input.parse::<syn::LitChar>()?.value());
+                }
+                "default" => {
+                    input.parse::<Token![=]>()?;
+                    attrs.default = Some(input.parse::<syn::LitStr>()?.value());
+                }
+                "help" => {
+                    input.parse::<Token![=]>()?;
+                    attrs.help = Some(input.parse::<syn::LitStr>()?.value());
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!("unknown arg attribute `{}`", other),
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        if attrs.positional && (attrs.long.is_some() || attrs.short.is_some()) {
+            return Err(syn::Error::new(
+                input.span(),
+                "positional arguments cannot have `long` or `short`",
+            ));
+        }
+
+        Ok(attrs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(source: &str) -> syn::Result<String> {
+        let input: DeriveInput = syn::parse_str(source)?;
+        expand_plugin(&input).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn test_field_kinds() {
+        let kind = |ty: &str| field_kind(&syn::parse_str(ty).unwrap());
+        assert_eq!(kind("String").unwrap(), FieldKind::Value);
+        assert_eq!(kind("u32").unwrap(), FieldKind::Value);
+        assert_eq!(kind("std::path::PathBuf").unwrap(), FieldKind::Value);
+        assert_eq!(kind("bool").unwrap(), FieldKind::Flag);
+        assert_eq!(kind("Option<String>").unwrap(), FieldKind::Optional);
+        assert!(kind("Vec<String>").is_err());
+        assert!(kind("Option<bool>").is_err());
+        assert!(kind("Option<Option<String>>").is_err());
+        assert!(kind("&str").is_err());
+    }
+
+    #[test]
+    fn test_parse_arg_attrs() {
+        let attrs: ArgAttrs = syn::parse_str(r#"short = 'g', default = "Hello""#).unwrap();
+        assert!(!attrs.positional);
+        assert_eq!(attrs.short, Some('g'));
+        assert_eq!(attrs.default.as_deref(), Some("Hello"));
+
+        let attrs: ArgAttrs = syn::parse_str(r#"positional, help = "Name""#).unwrap();
+        assert!(attrs.positional);
+        assert_eq!(attrs.help.as_deref(), Some("Name"));
+
+        assert!(syn::parse_str::<ArgAttrs>(r#"positional, short = 'n'"#).is_err());
+        assert!(syn::parse_str::<ArgAttrs>(r#"flag"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_attrs_requires_command() {
+        let attrs: PluginAttrs = syn::parse_str(r#"command = "greet", version = "1.0.0""#).unwrap();
+        assert_eq!(attrs.command, "greet");
+        assert_eq!(attrs.version.as_deref(), Some("1.0.0"));
+        assert!(attrs.about.is_none());
+
+        assert!(syn::parse_str::<PluginAttrs>(r#"about = "Greets""#).is_err());
+    }
+
+    #[test]
+    fn test_expand_uses_doc_comments() {
+        let tokens = expand(
+            r#"
+            /// Greets a person
+            #[plugin(command = "greet")]
+            struct Greet {
+                /// Name to greet
+                #[arg(positional, default = "World")]
+                name: String,
+                dry_run: bool,
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(tokens.contains(r#"CommandSpec :: new ("greet" , "Greets a person")"#));
+        assert!(tokens.contains(r#". help ("Name to greet")"#));
+        assert!(tokens.contains(r#"ArgSpec :: option ("dry_run" , "dry-run")"#));
+        assert!(tokens.contains(r#"export_plugin ! (Greet)"#));
+    }
+
+    #[test]
+    fn test_expand_rejects_unsupported_fields() {
+        let err = expand(
+            r#"
+            #[plugin(command = "tags", about = "Tags")]
+            struct Tags { tags: Vec<String> }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unsupported field type"));
+
+        let err = expand(
+            r#"
+            #[plugin(command = "greet", about = "Greets")]
+            struct Greet(String);
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("named fields"));
+
+        let err = expand(
+            r#"
+            #[plugin(command = "greet")]
+            struct Greet { name: String }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing description"));
+    }
+}
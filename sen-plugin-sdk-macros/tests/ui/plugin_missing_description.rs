@@ -0,0 +1,9 @@
+use sen_plugin_sdk::Plugin;
+
+#[derive(Plugin)]
+#[plugin(command = "greet")]
+struct Greet {
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use sen_plugin_sdk::Plugin;
+
+/// Greets someone
+#[derive(Plugin)]
+#[plugin(command = "greet")]
+struct Greet {
+    /// Shout the greeting
+    #[arg(positional)]
+    loud: bool,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use sen_plugin_sdk::Plugin;
+
+/// Greets someone
+#[derive(Plugin)]
+#[plugin(command = "greet")]
+struct Greet(String);
+
+fn main() {}
@@ -8,6 +8,7 @@
 //!
 //! - [Project Setup](#project-setup)
 //! - [Quick Start](#quick-start)
+//! - [Derive Macro](#derive-macro)
 //! - [Arguments](#arguments)
 //! - [Error Handling](#error-handling)
 //! - [Advanced Usage](#advanced-usage)
@@ -91,6 +92,56 @@
 //! export_plugin!(HelloPlugin);
 //! ```
 //!
+//! # Derive Macro
+//!
+//! `#[derive(Plugin)]` builds the manifest from a struct's fields, parses the
+//! arguments into the struct, and generates the `export_plugin!` exports.
+//! Implement `run(self)` on the struct:
+//!
+//! ```rust,ignore
+//! use sen_plugin_sdk::prelude::*;
+//!
+//! /// Greets a person with a custom message
+//! #[derive(Plugin)]
+//! #[plugin(command = "greet", version = "1.0.0")]
+//! struct Greet {
+//!     /// Name to greet
+//!     #[arg(positional, default = "World")]
+//!     name: String,
+//!
+//!     /// Custom greeting
+//!     #[arg(short = 'g', default = "Hello")]
+//!     greeting: String,
+//!
+//!     /// Number of times to greet
+//!     #[arg(short = 'n', default = "1")]
+//!     count: u32,
+//!
+//!     /// Greet in upper case
+//!     shout: bool,
+//! }
+//!
+//! impl Greet {
+//!     fn run(self) -> ExecuteResult {
+//!         let line = format!("{}, {}!", self.greeting, self.name);
+//!         let line = if self.shout { line.to_uppercase() } else { line };
+//!         ExecuteResult::success(vec![line; self.count as usize].join("\n"))
+//!     }
+//! }
+//! ```
+//!
+//! Usage: `greet Alice -g Hi --count 2 --shout`
+//!
+//! Fields without `#[arg(positional)]` are options named after the field
+//! (`dry_run` becomes `--dry-run`). Doc comments become the command
+//! description and argument help. Supported field types are `String`,
+//! `PathBuf`, `char`, integers, floats, `Option` of those, and `bool` (a flag).
+//! A field without a default is required unless it is an `Option` or `bool`.
+//! Invalid values are reported as user errors before `run` is called.
+//!
+//! Plugins that use effects implement [`Plugin`] by hand, since the derive
+//! does not generate `resume`.
+//!
 //! # Arguments
 //!
 //! ## Positional Arguments
@@ -401,18 +452,27 @@
 //!
 //! - `examples/hello-plugin/`: Manual implementation (no SDK)
 //! - `examples/greet-plugin/`: SDK-based with options
+//! - `examples/greet-derive-plugin/`: SDK-based with `#[derive(Plugin)]`
+//! - `examples/http-plugin/`: Network access via [`http`] (WASI host proxy)
 
 use std::alloc::{alloc, dealloc, Layout};
 
+// Lets `#[derive(Plugin)]` output (which uses `::sen_plugin_sdk` paths) compile here too
+extern crate self as sen_plugin_sdk;
+
 // Re-export everything from sen-plugin-api
 pub use sen_plugin_api::*;
 
+/// Derive macro for [`Plugin`]; see the [derive section](crate#derive-macro)
+pub use sen_plugin_sdk_macros::Plugin;
+
 /// Prelude module for convenient imports
 pub mod prelude {
-    pub use crate::{export_plugin, memory, Plugin};
+    pub use crate::{args, export_plugin, http, memory, Plugin};
     pub use sen_plugin_api::{
         ArgSpec, Capabilities, CommandSpec, Effect, EffectResult, ExecuteError, ExecuteResult,
-        HttpResponse, NetPattern, PathPattern, PluginManifest, StdioCapability, API_VERSION,
+        HttpError, HttpErrorKind, HttpRequest, HttpResponse, HttpResult, NetPattern, PathPattern,
+        PluginManifest, StdioCapability, API_VERSION,
     };
 }
 
@@ -455,6 +515,12 @@ pub trait Plugin {
     fn resume(_effect_id: u32, _result: EffectResult) -> ExecuteResult {
         ExecuteResult::system_error("Plugin does not support effects")
     }
+
+    /// Release resources before the host unloads the plugin
+    ///
+    /// Called once when the plugin is unloaded or replaced by a reload, with
+    /// a single fuel budget. Default implementation does nothing.
+    fn shutdown() {}
 }
 
 /// Memory utilities for Wasm plugin development
@@ -619,10 +685,259 @@ pub mod memory {
     }
 }
 
+/// Host-proxy HTTP for WASI plugins
+///
+/// Performs a blocking HTTP request through the host's `host_http_request`
+/// import. The host only allows URLs covered by the plugin's declared `net`
+/// capabilities; anything else returns an [`HttpError`] with
+/// [`HttpErrorKind::Denied`].
+///
+/// Plugins using this module must be loaded with the host's
+/// `WasiPluginLoader`, which provides the import.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn execute(args: Vec<String>) -> ExecuteResult {
+///     match http::get("https://api.example.com/status") {
+///         Ok(resp) if resp.is_success() => ExecuteResult::success(resp.body),
+///         Ok(resp) => ExecuteResult::user_error(format!("HTTP {}", resp.status)),
+///         Err(e) => ExecuteResult::user_error(e.to_string()),
+///     }
+/// }
+/// ```
+pub mod http {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "sen")]
+    extern "C" {
+        fn host_http_request(req_ptr: i32, req_len: i32) -> i64;
+    }
+
+    /// Send a request through the host proxy
+    pub fn request(request: &HttpRequest) -> HttpResult {
+        let bytes = rmp_serde::to_vec_named(request).map_err(|e| {
+            HttpError::new(
+                HttpErrorKind::InvalidRequest,
+                format!("Failed to encode request: {}", e),
+            )
+        })?;
+        let len: i32 = bytes.len().try_into().map_err(|_| {
+            HttpError::new(
+                HttpErrorKind::InvalidRequest,
+                format!("Request too large: {} bytes", bytes.len()),
+            )
+        })?;
+        call_host(bytes.as_ptr() as i32, len)
+    }
+
+    /// Send a GET request through the host proxy
+    pub fn get(url: impl Into<String>) -> HttpResult {
+        request(&HttpRequest::get(url))
+    }
+
+    /// Send a POST request through the host proxy
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> HttpResult {
+        request(&HttpRequest::post(url, body))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn call_host(req_ptr: i32, req_len: i32) -> HttpResult {
+        // SAFETY: req_ptr/req_len describe a live buffer owned by the caller
+        let packed = unsafe { host_http_request(req_ptr, req_len) };
+        let ptr = (packed >> 32) as i32;
+        let len = (packed & 0xFFFFFFFF) as i32;
+
+        // SAFETY: The host wrote `len` bytes at `ptr` via plugin_alloc
+        let result = unsafe { memory::deserialize_from_ptr::<HttpResult>(ptr, len) };
+        memory::plugin_dealloc(ptr, len);
+
+        result.unwrap_or_else(|e| {
+            Err(HttpError::new(
+                HttpErrorKind::Transport,
+                format!("Invalid host response: {}", e),
+            ))
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn call_host(_req_ptr: i32, _req_len: i32) -> HttpResult {
+        Err(HttpError::new(
+            HttpErrorKind::Transport,
+            "host_http_request is only available inside a wasm32 plugin",
+        ))
+    }
+}
+
+/// Argument parsing against a [`CommandSpec`]
+///
+/// The host passes a plugin its raw arguments (e.g. `["Alice", "-g", "Hi"]`).
+/// [`ParsedArgs`](args::ParsedArgs) resolves them against the command's
+/// `ArgSpec`s: positionals in declaration order, options by `--long`,
+/// `--long=value` or `-s`, then defaults and required checks. `#[derive(Plugin)]`
+/// uses it for its generated `execute`; manual plugins can call it too.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn execute(args: Vec<String>) -> ExecuteResult {
+///     let spec = Self::manifest().command;
+///     let parsed = match args::ParsedArgs::parse(&spec, &["shout"], &args) {
+///         Ok(parsed) => parsed,
+///         Err(e) => return ExecuteResult::user_error(e),
+///     };
+///     let count: u32 = match parsed.value("count") {
+///         Ok(count) => count,
+///         Err(e) => return ExecuteResult::user_error(e),
+///     };
+///     // ...
+/// }
+/// ```
+pub mod args {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    /// Arguments resolved against a [`CommandSpec`]
+    #[derive(Debug, Default)]
+    pub struct ParsedArgs {
+        values: HashMap<String, String>,
+        flags: HashSet<String>,
+    }
+
+    impl ParsedArgs {
+        /// Parse `args` against the args of `spec`
+        ///
+        /// Options named in `flags` take no value. `--` ends option parsing.
+        ///
+        /// # Errors
+        ///
+        /// Returns a user-facing message for unknown options, missing option
+        /// values, extra positionals, and missing required arguments.
+        pub fn parse(spec: &CommandSpec, flags: &[&str], args: &[String]) -> Result<Self, String> {
+            let mut parsed = Self::default();
+            let mut positionals = spec.args.iter().filter(|arg| arg.long.is_none());
+            let mut options_done = false;
+            let mut rest = args.iter();
+
+            while let Some(arg) = rest.next() {
+                if !options_done && arg == "--" {
+                    options_done = true;
+                    continue;
+                }
+
+                let option = if options_done {
+                    None
+                } else if let Some(long) = arg.strip_prefix("--") {
+                    let (name, inline) = match long.split_once('=') {
+                        Some((name, value)) => (name, Some(value)),
+                        None => (long, None),
+                    };
+                    let spec_arg = spec.args.iter().find(|a| a.long.as_deref() == Some(name));
+                    Some((spec_arg, inline))
+                } else {
+                    short_option(arg).and_then(|short| {
+                        let spec_arg = spec.args.iter().find(|a| a.short == Some(short));
+                        // Unmatched "-5" is a negative number, not an option
+                        (spec_arg.is_some() || !short.is_ascii_digit()).then_some((spec_arg, None))
+                    })
+                };
+
+                let Some((spec_arg, inline)) = option else {
+                    match positionals.next() {
+                        Some(positional) => {
+                            parsed.values.insert(positional.name.clone(), arg.clone());
+                        }
+                        None => return Err(format!("Unexpected argument: {}", arg)),
+                    }
+                    continue;
+                };
+
+                let spec_arg = spec_arg.ok_or_else(|| format!("Unknown option: {}", arg))?;
+                if flags.contains(&spec_arg.name.as_str()) {
+                    if inline.is_some() {
+                        return Err(format!("Flag '{}' does not take a value", arg));
+                    }
+                    parsed.flags.insert(spec_arg.name.clone());
+                } else {
+                    let value = match inline {
+                        Some(value) => value.to_string(),
+                        None => rest
+                            .next()
+                            .cloned()
+                            .ok_or_else(|| format!("Option '{}' requires a value", arg))?,
+                    };
+                    parsed.values.insert(spec_arg.name.clone(), value);
+                }
+            }
+
+            for arg in &spec.args {
+                if parsed.values.contains_key(&arg.name) || flags.contains(&arg.name.as_str()) {
+                    continue;
+                }
+                if let Some(default) = &arg.default_value {
+                    parsed.values.insert(arg.name.clone(), default.clone());
+                } else if arg.required {
+                    return Err(format!("Missing required argument: {}", arg.name));
+                }
+            }
+
+            Ok(parsed)
+        }
+
+        /// Raw value of `name`, after defaults
+        pub fn get(&self, name: &str) -> Option<&str> {
+            self.values.get(name).map(String::as_str)
+        }
+
+        /// Whether the flag `name` was given
+        pub fn flag(&self, name: &str) -> bool {
+            self.flags.contains(name)
+        }
+
+        /// Value of `name` parsed as `T`; an error if it is missing or invalid
+        pub fn value<T>(&self, name: &str) -> Result<T, String>
+        where
+            T: FromStr,
+            T::Err: Display,
+        {
+            self.optional(name)?
+                .ok_or_else(|| format!("Missing required argument: {}", name))
+        }
+
+        /// Value of `name` parsed as `T`, if given; an error if it is invalid
+        pub fn optional<T>(&self, name: &str) -> Result<Option<T>, String>
+        where
+            T: FromStr,
+            T::Err: Display,
+        {
+            self.get(name)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| format!("Invalid value '{}' for '{}': {}", value, name, e))
+                })
+                .transpose()
+        }
+    }
+
+    /// `x` for an argument of the form `-x`
+    fn short_option(arg: &str) -> Option<char> {
+        let mut chars = arg.strip_prefix('-')?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(short), None) => Some(short),
+            _ => None,
+        }
+    }
+}
+
 /// Macro to export all required plugin functions
 ///
 /// This macro generates the `plugin_manifest`, `plugin_execute`, `plugin_resume`,
-/// `plugin_alloc`, and `plugin_dealloc` functions required by the host.
+/// `plugin_shutdown`, `plugin_alloc`, and `plugin_dealloc` functions required by
+/// the host.
 ///
 /// # Example
 ///
@@ -702,6 +1017,12 @@ macro_rules! export_plugin {
             $crate::memory::serialize_and_return(&result)
         }
 
+        /// Tear down the plugin before the host unloads it
+        #[no_mangle]
+        pub extern "C" fn plugin_shutdown() {
+            <$plugin as $crate::Plugin>::shutdown()
+        }
+
         #[no_mangle]
         pub extern "C" fn plugin_alloc(size: i32) -> i32 {
             $crate::memory::plugin_alloc(size)
@@ -732,6 +1053,12 @@ mod tests {
         assert_eq!(unpacked_len, len);
     }
 
+    #[test]
+    fn test_http_request_outside_wasm() {
+        let err = http::get("https://api.example.com/").unwrap_err();
+        assert_eq!(err.kind, HttpErrorKind::Transport);
+    }
+
     #[test]
     fn test_alloc_edge_cases() {
         // Test zero/negative edge cases - these should return 0
@@ -739,6 +1066,126 @@ mod tests {
         assert_eq!(memory::plugin_alloc(-1), 0);
     }
 
+    /// Greets a person
+    #[derive(Plugin)]
+    #[plugin(command = "greet", version = "1.0.0")]
+    struct Greet {
+        /// Name to greet
+        #[arg(positional, default = "World")]
+        name: String,
+        #[arg(short = 'g', default = "Hello")]
+        greeting: String,
+        #[arg(short = 'n', default = "1")]
+        count: u32,
+        suffix: Option<String>,
+        shout: bool,
+    }
+
+    impl Greet {
+        fn run(self) -> ExecuteResult {
+            let line = format!(
+                "{}, {}{}",
+                self.greeting,
+                self.name,
+                self.suffix.as_deref().unwrap_or("!")
+            );
+            let line = if self.shout {
+                line.to_uppercase()
+            } else {
+                line
+            };
+            ExecuteResult::success(vec![line; self.count as usize].join("\n"))
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn output(result: ExecuteResult) -> String {
+        match result {
+            ExecuteResult::Success(output) => output,
+            ExecuteResult::Error(e) => e.message,
+            ExecuteResult::Effect(e) => panic!("Unexpected effect: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_derive_manifest() {
+        let command = <Greet as Plugin>::manifest().command;
+        assert_eq!(command.name, "greet");
+        assert_eq!(command.about, "Greets a person");
+        assert_eq!(command.version.as_deref(), Some("1.0.0"));
+
+        let names: Vec<_> = command.args.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["name", "greeting", "count", "suffix", "shout"]);
+        assert_eq!(command.args[0].long, None);
+        assert_eq!(command.args[0].help, "Name to greet");
+        assert_eq!(command.args[1].long.as_deref(), Some("greeting"));
+        assert_eq!(command.args[1].short, Some('g'));
+        assert!(!command.args[3].required);
+    }
+
+    #[test]
+    fn test_derive_execute_parses_into_struct() {
+        assert_eq!(output(<Greet as Plugin>::execute(vec![])), "Hello, World!");
+        assert_eq!(
+            output(<Greet as Plugin>::execute(strings(&[
+                "Alice",
+                "-g",
+                "Hi",
+                "--count=2",
+                "--suffix",
+                "?",
+                "--shout"
+            ]))),
+            "HI, ALICE?\nHI, ALICE?"
+        );
+    }
+
+    #[test]
+    fn test_derive_execute_reports_invalid_args() {
+        let result = <Greet as Plugin>::execute(strings(&["--count", "many"]));
+        match result {
+            ExecuteResult::Error(e) => {
+                assert_eq!(e.code, 1);
+                assert!(e.message.starts_with("Invalid value 'many' for 'count'"));
+            }
+            other => panic!("Expected user error, got {:?}", other),
+        }
+        assert_eq!(
+            output(<Greet as Plugin>::execute(strings(&["--loud"]))),
+            "Unknown option: --loud"
+        );
+        assert_eq!(
+            output(<Greet as Plugin>::execute(strings(&["Alice", "Bob"]))),
+            "Unexpected argument: Bob"
+        );
+    }
+
+    #[test]
+    fn test_parsed_args_required_and_separator() {
+        let spec = CommandSpec::new("copy", "Copy files")
+            .arg(ArgSpec::positional("source").required())
+            .arg(ArgSpec::positional("offset"))
+            .arg(ArgSpec::option("mode", "mode").short('m'));
+
+        let err = args::ParsedArgs::parse(&spec, &[], &[]).unwrap_err();
+        assert_eq!(err, "Missing required argument: source");
+
+        let err = args::ParsedArgs::parse(&spec, &[], &strings(&["a", "-m"])).unwrap_err();
+        assert_eq!(err, "Option '-m' requires a value");
+
+        let parsed = args::ParsedArgs::parse(&spec, &[], &strings(&["--", "--a", "-5"])).unwrap();
+        assert_eq!(parsed.get("source"), Some("--a"));
+        assert_eq!(parsed.value::<i32>("offset"), Ok(-5));
+        assert_eq!(parsed.optional::<String>("mode"), Ok(None));
+
+        // Negative numbers are positionals, not unknown short options
+        let parsed = args::ParsedArgs::parse(&spec, &[], &strings(&["a", "-5"])).unwrap();
+        assert_eq!(parsed.value::<i32>("offset"), Ok(-5));
+    }
+
     // Note: Full allocation tests run via integration tests with actual Wasm plugins.
     // The memory functions are designed for Wasm linear memory and may behave
     // differently in native test environments.
@@ -1,8 +1,10 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::{
     parse::{Parse, ParseStream, Parser},
-    parse_macro_input, Data, DeriveInput, Fields, ItemFn, Token,
+    parse_macro_input,
+    spanned::Spanned,
+    Data, DeriveInput, Fields, ItemFn, Token,
 };
 
 /// Derives the SenRouter trait for an enum, generating the `execute()` method.
@@ -33,84 +35,299 @@ use syn::{
 ///     }
 /// }
 /// ```
+///
+/// # Router generation
+///
+/// Adding `name` (and optionally `version`, `about`, `agent_mode`) to the
+/// enum attribute also generates `Commands::router(state) -> Router<()>`.
+/// Every variant becomes a route, its `desc` becomes the route description,
+/// and tuple variants parse their field with `FromArgs`.
+///
+/// These enums also implement `sen::SenRouterTrait`, so
+/// `Router::from_enum_router::<Commands>()` registers the same routes on a
+/// router you configure yourself.
+///
+/// Command names are the variant name in kebab-case (`Build` → `build`,
+/// `DbCreate` → `db-create`), in both `router()` and `commands_metadata()`.
+/// Use `#[sen(rename = "db:create")]` on a variant to pick the name yourself:
+///
+/// ```ignore
+/// #[derive(SenRouter)]
+/// #[sen(state = AppState, name = "myctl", version = "1.0.0", about = "My CLI", agent_mode)]
+/// enum Commands {
+///     #[sen(handler = handlers::status, desc = "Show status")]
+///     Status,
+///
+///     #[sen(handler = handlers::build)]
+///     Build(BuildArgs),
+///
+///     #[sen(handler = handlers::db_create, rename = "db:create")]
+///     DbCreate(CreateArgs),
+/// }
+///
+/// let response = Commands::router(State::new(state))
+///     .execute_with(&args)
+///     .await;
+/// ```
+///
+/// # Parsed args on unit variants
+///
+/// A unit variant with `#[sen(args = T)]` parses `T` with `FromArgs` from the
+/// trailing args and calls `handler(state, Args(parsed))`. `router()` passes
+/// the args left after the command name; `execute_with_args(state, args)`
+/// takes them explicitly (`execute(state)` parses an empty list):
+///
+/// ```ignore
+/// #[derive(SenRouter)]
+/// #[sen(state = AppState)]
+/// enum Commands {
+///     #[sen(handler = handlers::deploy, args = DeployArgs)]
+///     Deploy,
+/// }
+///
+/// // async fn deploy(state: State<AppState>, Args(args): Args<DeployArgs>) -> CliResult<String>
+/// let response = Commands::Deploy
+///     .execute_with_args(state, vec!["prod".to_string()])
+///     .await;
+/// ```
 #[proc_macro_derive(SenRouter, attributes(sen))]
 pub fn derive_sen_router(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_sen_router(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expand `#[derive(SenRouter)]`, reporting misuse at the offending tokens
+fn expand_sen_router(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let enum_name = &input.ident;
 
-    // Extract state type from #[sen(state = AppState)]
-    let state_type = extract_state_type(&input.attrs)
-        .expect("Missing #[sen(state = YourStateType)] attribute on enum");
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                enum_name,
+                "SenRouter can only be derived for enums",
+            ))
+        }
+    };
+
+    // Extract state type and router options from #[sen(state = AppState, ...)]
+    let options = parse_router_options(&input.attrs)?;
+    let state_type = options.state.clone().ok_or_else(|| {
+        syn::Error::new_spanned(
+            enum_name,
+            "Missing #[sen(state = YourStateType)] attribute on enum",
+        )
+    })?;
 
     // Generate match arms and collect metadata for each variant
-    let (match_arms, help_entries) = match &input.data {
-        Data::Enum(data) => {
-            let mut arms = Vec::new();
-            let mut help = Vec::new();
-
-            for variant in &data.variants {
-                let variant_name = &variant.ident;
-                let handler_path = extract_handler(&variant.attrs).unwrap_or_else(|| {
-                    panic!(
-                        "Missing #[sen(handler = path::to::handler)] attribute on variant {}",
-                        variant_name
-                    )
-                });
+    let mut match_arms = Vec::new();
+    let mut help_entries = Vec::new();
+    let mut route_wrappers = Vec::new();
+    let mut route_registrations = Vec::new();
+
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let handler_path = extract_handler(&variant.attrs)?.ok_or_else(|| {
+            syn::Error::new_spanned(
+                variant_name,
+                format!(
+                    "Missing #[sen(handler = path::to::handler)] attribute on variant {}",
+                    variant_name
+                ),
+            )
+        })?;
+
+        // Extract description if available
+        let desc = extract_str(&variant.attrs, "desc");
 
-                // Extract description if available
-                let desc = extract_desc(&variant.attrs);
+        // Args parsed from the trailing CLI tokens: #[sen(args = T)] (unit variants only)
+        let parsed_args = extract_args_type(&variant.attrs)?;
+        if let (Some(ty), false) = (&parsed_args, matches!(variant.fields, Fields::Unit)) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "#[sen(args = T)] is only supported on unit variants; tuple variants already carry their args",
+            ));
+        }
 
-                // Convert variant name to command name (e.g., "Build" -> "build")
-                let cmd_name = variant_name.to_string().to_lowercase();
+        // Command name: #[sen(rename = "...")] or the kebab-cased variant
+        // name (e.g., "Build" -> "build", "DbCreate" -> "db-create")
+        let default_name = to_kebab_case(&variant_name.to_string());
+        let cmd_name =
+            extract_str(&variant.attrs, "rename").unwrap_or_else(|| default_name.clone());
 
-                // Generate help entry
-                let help_entry = if let Some(d) = desc {
-                    quote! {
-                        (#cmd_name, Some(#d))
+        // Route for router(): a wrapper that calls the handler like execute() does
+        let route_name = &cmd_name;
+        let wrapper = syn::Ident::new(
+            &format!("__sen_route_{}", default_name.replace('-', "_")),
+            variant_name.span(),
+        );
+        route_wrappers.push(match &variant.fields {
+            _ if parsed_args.is_some() => {
+                let args_type = &parsed_args;
+                quote! {
+                    async fn #wrapper(
+                        state: sen::State<#state_type>,
+                        args: sen::Args<#args_type>,
+                    ) -> sen::Response {
+                        #handler_path(state, args).await.into_response()
                     }
-                } else {
-                    quote! {
-                        (#cmd_name, None)
+                }
+            }
+            Fields::Unnamed(fields) if !fields.unnamed.is_empty() => {
+                let args_type = &fields.unnamed[0].ty;
+                quote! {
+                    async fn #wrapper(
+                        state: sen::State<#state_type>,
+                        sen::Args(args): sen::Args<#args_type>,
+                    ) -> sen::Response {
+                        #handler_path(state, args).await.into_response()
                     }
-                };
-                help.push(help_entry);
-
-                // Generate match arm
-                let arm = match &variant.fields {
-                    Fields::Unit => {
-                        // No args, only inject state
-                        quote! {
-                            #enum_name::#variant_name => {
-                                #handler_path(state).await.into_response()
-                            }
+                }
+            }
+            _ => quote! {
+                async fn #wrapper(state: sen::State<#state_type>) -> sen::Response {
+                    #handler_path(state).await.into_response()
+                }
+            },
+        });
+        route_registrations.push(quote! { .route(#route_name, #wrapper) });
+        if let Some(d) = &desc {
+            route_registrations.push(quote! { .describe(#route_name, #d) });
+        }
+
+        // Generate help entry
+        let help_entry = if let Some(d) = desc {
+            quote! {
+                (#cmd_name, Some(#d))
+            }
+        } else {
+            quote! {
+                (#cmd_name, None)
+            }
+        };
+        help_entries.push(help_entry);
+
+        // Generate match arm
+        let arm = match &variant.fields {
+            Fields::Unit if parsed_args.is_some() => {
+                // Parse the declared args type from the trailing args
+                quote! {
+                    #enum_name::#variant_name => {
+                        match <#parsed_args as sen::FromArgs>::from_args(&args) {
+                            Ok(parsed) => #handler_path(state, sen::Args(parsed)).await.into_response(),
+                            Err(e) => sen::CliResult::<()>::Err(e).into_response(),
                         }
                     }
-                    Fields::Unnamed(_) => {
-                        // Has args, inject state and args
-                        quote! {
-                            #enum_name::#variant_name(args) => {
-                                #handler_path(state, args).await.into_response()
-                            }
-                        }
+                }
+            }
+            Fields::Unit => {
+                // No args, only inject state
+                quote! {
+                    #enum_name::#variant_name => {
+                        #handler_path(state).await.into_response()
                     }
-                    Fields::Named(_) => {
-                        panic!("Named fields are not supported in SenRouter. Use tuple variants or unit variants.");
+                }
+            }
+            Fields::Unnamed(_) => {
+                // Has args, inject state and args
+                quote! {
+                    #enum_name::#variant_name(args) => {
+                        #handler_path(state, args).await.into_response()
                     }
-                };
-                arms.push(arm);
+                }
+            }
+            Fields::Named(fields) => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "Named fields are not supported in SenRouter. Use tuple variants or unit variants.",
+                ));
+            }
+        };
+        match_arms.push(arm);
+    }
+
+    let wants_router = options.wants_router();
+    let router_fn = if wants_router {
+        let name = options.name.ok_or_else(|| {
+            syn::Error::new_spanned(
+                enum_name,
+                "Generating router() needs a name: #[sen(name = \"myctl\")]",
+            )
+        })?;
+        let version = option_tokens(options.version);
+        let about = option_tokens(options.about);
+        let agent_mode = if options.agent_mode {
+            quote! { .with_agent_mode() }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            /// Build a router with every command registered as a route.
+            pub fn router(state: sen::State<#state_type>) -> sen::Router<()> {
+                <Self as sen::SenRouterTrait>::register_routes(sen::Router::new())
+                    .with_metadata(sen::RouterMetadata {
+                        name: #name,
+                        version: #version,
+                        about: #about,
+                    })
+                    #agent_mode
+                    .with_shared_state(state)
             }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Routes shared with the dynamic Router, only for enums that build one
+    let trait_impl = if wants_router {
+        quote! {
+            impl sen::SenRouterTrait for #enum_name {
+                type State = #state_type;
 
-            (arms, help)
+                fn commands_metadata() -> Vec<(&'static str, Option<&'static str>)> {
+                    #enum_name::commands_metadata()
+                }
+
+                fn register_routes(router: sen::Router<#state_type>) -> sen::Router<#state_type> {
+                    use sen::IntoResponse;
+
+                    #(#route_wrappers)*
+
+                    router
+                        #(#route_registrations)*
+                }
+            }
         }
-        _ => panic!("SenRouter can only be derived for enums"),
+    } else {
+        quote! {}
     };
 
     // Generate the implementation
-    let expanded = quote! {
+    Ok(quote! {
+        #trait_impl
+
         impl #enum_name {
+            #router_fn
+
             /// Execute the command with the given state.
             pub async fn execute(self, state: sen::State<#state_type>) -> sen::Response {
+                self.execute_with_args(state, Vec::new()).await
+            }
+
+            /// Execute the command with the given state and trailing args.
+            ///
+            /// The args are parsed for unit variants declared with
+            /// `#[sen(args = T)]` and ignored by every other variant.
+            pub async fn execute_with_args(
+                self,
+                state: sen::State<#state_type>,
+                args: Vec<String>,
+            ) -> sen::Response {
                 use sen::IntoResponse;
+                let _ = &args;
 
                 match self {
                     #(#match_arms)*
@@ -144,65 +361,159 @@ pub fn derive_sen_router(input: TokenStream) -> TokenStream {
                 help
             }
         }
-    };
+    })
+}
 
-    TokenStream::from(expanded)
+/// Options of the `#[sen(...)]` attribute on a `SenRouter` enum
+#[derive(Default)]
+struct RouterOptions {
+    state: Option<syn::Type>,
+    name: Option<String>,
+    version: Option<String>,
+    about: Option<String>,
+    agent_mode: bool,
+}
+
+impl RouterOptions {
+    /// Whether any option that only affects `router()` was given
+    fn wants_router(&self) -> bool {
+        self.name.is_some() || self.version.is_some() || self.about.is_some() || self.agent_mode
+    }
+}
+
+/// Parse #[sen(state = T, name = "...", version = "...", about = "...", agent_mode)]
+fn parse_router_options(attrs: &[syn::Attribute]) -> syn::Result<RouterOptions> {
+    let mut options = RouterOptions::default();
+    for attr in attrs {
+        if !attr.path().is_ident("sen") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("state") {
+                let value = meta.value()?;
+                options.state = Some(value.parse().map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        "Expected a type for `state`, e.g. #[sen(state = AppState)]",
+                    )
+                })?);
+            } else if meta.path.is_ident("name") {
+                options.name = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("version") {
+                options.version = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("about") {
+                options.about = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("agent_mode") {
+                options.agent_mode = true;
+            } else {
+                return Err(meta.error(
+                    "Unknown SenRouter option; expected state, name, version, about, or agent_mode",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// `Some("...")` or `None` for an optional string literal
+fn option_tokens(value: Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(v) => quote! { Some(#v) },
+        None => quote! { None },
+    }
+}
+
+/// Convert a variant name to a command name (`DbCreate` → `db-create`,
+/// `HTTPServer` → `http-server`)
+fn to_kebab_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // Word boundary: "bC", "9C", or the last capital of an acronym ("PServer")
+            if !prev.is_uppercase() || next_is_lower {
+                out.push('-');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
 }
 
-/// Extract the state type from #[sen(state = T)]
-fn extract_state_type(attrs: &[syn::Attribute]) -> Option<syn::Type> {
+/// Extract the handler path from #[sen(handler = path::to::handler)]
+fn extract_handler(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::Path>> {
     for attr in attrs {
         if attr.path().is_ident("sen") {
             if let Ok(meta_list) = attr.meta.require_list() {
-                // Parse the tokens inside sen(...)
                 let tokens = &meta_list.tokens;
-                let parsed: Result<syn::MetaNameValue, _> = syn::parse2(tokens.clone());
-
-                if let Ok(nv) = parsed {
-                    if nv.path.is_ident("state") {
-                        if let syn::Expr::Path(expr_path) = nv.value {
-                            return Some(syn::Type::Path(syn::TypePath {
-                                qself: None,
-                                path: expr_path.path,
-                            }));
+
+                // Try to parse as comma-separated list of name=value pairs
+                let parsed =
+                    syn::punctuated::Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated
+                        .parse2(tokens.clone());
+
+                if let Ok(pairs) = parsed {
+                    for nv in &pairs {
+                        if nv.path.is_ident("handler") {
+                            return match &nv.value {
+                                syn::Expr::Path(expr_path) => Ok(Some(expr_path.path.clone())),
+                                other => Err(syn::Error::new_spanned(
+                                    other,
+                                    "Expected a handler path, e.g. #[sen(handler = handlers::status)]",
+                                )),
+                            };
                         }
                     }
                 }
             }
         }
     }
-    None
+    Ok(None)
 }
 
-/// Extract the handler path from #[sen(handler = path::to::handler)]
-fn extract_handler(attrs: &[syn::Attribute]) -> Option<syn::Path> {
+/// Extract the args type from #[sen(args = T)]
+///
+/// `T` is parsed like the handler path, so generic types need turbofish
+/// syntax (`args = Vec::<String>`) or a type alias.
+fn extract_args_type(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::Type>> {
     for attr in attrs {
         if attr.path().is_ident("sen") {
             if let Ok(meta_list) = attr.meta.require_list() {
                 let tokens = &meta_list.tokens;
 
-                // Try to parse as comma-separated list of name=value pairs
                 let parsed =
                     syn::punctuated::Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated
                         .parse2(tokens.clone());
 
                 if let Ok(pairs) = parsed {
                     for nv in &pairs {
-                        if nv.path.is_ident("handler") {
-                            if let syn::Expr::Path(expr_path) = &nv.value {
-                                return Some(expr_path.path.clone());
-                            }
+                        if nv.path.is_ident("args") {
+                            return match &nv.value {
+                                syn::Expr::Path(expr_path) => {
+                                    Ok(Some(syn::Type::Path(syn::TypePath {
+                                        qself: expr_path.qself.clone(),
+                                        path: expr_path.path.clone(),
+                                    })))
+                                }
+                                other => Err(syn::Error::new_spanned(
+                                    other,
+                                    "Expected an args type, e.g. #[sen(args = DeployArgs)]",
+                                )),
+                            };
                         }
                     }
                 }
             }
         }
     }
-    None
+    Ok(None)
 }
 
-/// Extract the description from #[sen(desc = "...")]
-fn extract_desc(attrs: &[syn::Attribute]) -> Option<String> {
+/// Extract a string option such as #[sen(desc = "...")] or #[sen(rename = "...")]
+fn extract_str(attrs: &[syn::Attribute], key: &str) -> Option<String> {
     for attr in attrs {
         if attr.path().is_ident("sen") {
             if let Ok(meta_list) = attr.meta.require_list() {
@@ -215,7 +526,7 @@ fn extract_desc(attrs: &[syn::Attribute]) -> Option<String> {
 
                 if let Ok(pairs) = parsed {
                     for nv in &pairs {
-                        if nv.path.is_ident("desc") {
+                        if nv.path.is_ident(key) {
                             if let syn::Expr::Lit(expr_lit) = &nv.value {
                                 if let syn::Lit::Str(lit_str) = &expr_lit.lit {
                                     return Some(lit_str.value());
@@ -238,6 +549,10 @@ struct SenAttrs {
     desc: Option<String>,
     tier: Option<String>,
     tags: Option<Vec<String>>,
+    examples: Vec<String>,
+    aliases: Vec<String>,
+    env: Vec<String>,
+    timeout: Option<String>,
 }
 
 impl Parse for SenAttrs {
@@ -248,6 +563,10 @@ impl Parse for SenAttrs {
         let mut desc = None;
         let mut tier = None;
         let mut tags = None;
+        let mut examples = Vec::new();
+        let mut aliases = Vec::new();
+        let mut env = Vec::new();
+        let mut timeout = None;
 
         while !input.is_empty() {
             let ident: syn::Ident = input.parse()?;
@@ -275,18 +594,22 @@ impl Parse for SenAttrs {
                     tier = Some(value.value());
                 }
                 "tags" => {
-                    // Parse array of strings: ["tag1", "tag2", "tag3"]
-                    let content;
-                    syn::bracketed!(content in input);
-                    let mut tag_list = Vec::new();
-                    while !content.is_empty() {
-                        let tag: syn::LitStr = content.parse()?;
-                        tag_list.push(tag.value());
-                        if !content.is_empty() {
-                            content.parse::<Token![,]>()?;
-                        }
-                    }
-                    tags = Some(tag_list);
+                    tags = Some(parse_string_array(input)?);
+                }
+                "aliases" => {
+                    aliases = parse_string_array(input)?;
+                }
+                "env" => {
+                    env = parse_string_array(input)?;
+                }
+                "example" => {
+                    // Repeatable: each occurrence adds one example
+                    let value: syn::LitStr = input.parse()?;
+                    examples.push(value.value());
+                }
+                "timeout" => {
+                    let value: syn::LitStr = input.parse()?;
+                    timeout = Some(value.value());
                 }
                 _ => {
                     // Skip unknown attributes
@@ -306,10 +629,29 @@ impl Parse for SenAttrs {
             desc,
             tier,
             tags,
+            examples,
+            aliases,
+            env,
+            timeout,
         })
     }
 }
 
+/// Parse an array of string literals: `["a", "b", "c"]`
+fn parse_string_array(input: ParseStream) -> syn::Result<Vec<String>> {
+    let content;
+    syn::bracketed!(content in input);
+    let mut values = Vec::new();
+    while !content.is_empty() {
+        let value: syn::LitStr = content.parse()?;
+        values.push(value.value());
+        if !content.is_empty() {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    Ok(values)
+}
+
 /// Attribute macro for Router functions to attach CLI metadata.
 ///
 /// # Usage
@@ -390,7 +732,13 @@ pub fn sen(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// # Usage
 ///
 /// ```ignore
-/// #[sen::handler(desc = "Create a new database")]
+/// #[sen::handler(
+///     desc = "Create a new database",
+///     example = "myctl db create mydb",
+///     example = "myctl db create mydb --size 20GB",
+///     env = ["MYCTL_TOKEN"],
+///     timeout = "5m"
+/// )]
 /// pub async fn create(
 ///     state: State<AppState>,
 ///     Args(args): Args<DbCreateArgs>
@@ -399,12 +747,17 @@ pub fn sen(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// `timeout` takes anything `sen::parse_duration` accepts (`"30s"`,
+/// `"1h30m"`); an invalid value panics when the handler is constructed.
+///
 /// Expands to:
 ///
 /// ```ignore
 /// pub fn create() -> HandlerWithMeta<impl Handler<...>, ...> {
 ///     async fn create_impl(...) -> CliResult<String> { ... }
-///     HandlerWithMeta::new(create_impl, HandlerMetadata { desc: Some("...") })
+///     let mut m = HandlerMetadata::default();
+///     m.desc = Some("...");
+///     HandlerWithMeta::new(create_impl, m)
 /// }
 /// ```
 #[proc_macro_attribute]
@@ -434,6 +787,21 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
     // Create implementation function name
     let impl_name = syn::Ident::new(&format!("{}_impl", fn_name), fn_name.span());
 
+    // Static assertion that the return type implements IntoResponse; the
+    // helper is named after the handler so the diagnostic points back to it
+    let assert_name = syn::Ident::new(
+        &format!("{}_must_return_into_response", fn_name),
+        fn_name.span(),
+    );
+    let return_assertion = match fn_output {
+        syn::ReturnType::Default => quote_spanned! { fn_name.span()=>
+            const _: fn() = #assert_name::<()>;
+        },
+        syn::ReturnType::Type(_, ty) => quote_spanned! { ty.span()=>
+            const _: fn() = #assert_name::<#ty>;
+        },
+    };
+
     // Build metadata (prefer desc, fallback to about or name)
     let desc_expr = if let Some(d) = attrs.desc.or(attrs.about.clone()).or(attrs.name.clone()) {
         quote! { Some(#d) }
@@ -460,8 +828,43 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
         quote! { None }
     };
 
+    // Build examples expression
+    let examples = &attrs.examples;
+    let examples_expr = quote! {
+        vec![#(#examples),*]
+    };
+
+    // Build aliases expression
+    let aliases = &attrs.aliases;
+    let aliases_expr = quote! {
+        vec![#(#aliases),*]
+    };
+
+    // Build env expression
+    let env = &attrs.env;
+    let env_expr = quote! {
+        vec![#(#env),*]
+    };
+
+    // Build timeout expression
+    // Parsed by sen itself, so the accepted units match `sen::parse_duration`
+    let timeout_expr = match &attrs.timeout {
+        Some(timeout) => {
+            let message = format!("Invalid timeout on handler '{}'", fn_name);
+            quote! {
+                Some(sen::parse_duration(#timeout).unwrap_or_else(|e| panic!("{}: {}", #message, e)))
+            }
+        }
+        None => quote! { None },
+    };
+
     // Generate code with concrete return type
     let expanded = quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        fn #assert_name<T: sen::IntoResponse>() {}
+        #return_assertion
+
         #fn_vis fn #fn_name() -> sen::HandlerWithMeta<
             impl sen::Handler<(sen::State<#state_type>, sen::Args<#args_type>), #state_type>,
             (sen::State<#state_type>, sen::Args<#args_type>),
@@ -470,15 +873,17 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
             // Implementation function (same signature as original)
             async fn #impl_name(#fn_inputs) #fn_output #fn_block
 
+
             // Return wrapped handler with metadata
-            sen::HandlerWithMeta::new(
-                #impl_name,
-                sen::HandlerMetadata {
-                    desc: #desc_expr,
-                    tier: #tier_expr,
-                    tags: #tags_expr,
-                }
-            )
+            let mut m = sen::HandlerMetadata::default();
+            m.desc = #desc_expr;
+            m.tier = #tier_expr;
+            m.tags = #tags_expr;
+            m.examples = #examples_expr;
+            m.aliases = #aliases_expr;
+            m.env = #env_expr;
+            m.timeout = #timeout_expr;
+            sen::HandlerWithMeta::new(#impl_name, m)
         }
     };
 
@@ -538,3 +943,188 @@ fn extract_generic_type(ty: &syn::Type, expected_ident: &str) -> Result<syn::Typ
 
     Err(format!("Could not extract type from {}", expected_ident))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repeated_examples() {
+        let attrs: SenAttrs = syn::parse_str(
+            r#"desc = "Create a database",
+               example = "myctl db create mydb",
+               tags = ["db"],
+               example = "myctl db create mydb --size 20GB""#,
+        )
+        .unwrap();
+
+        assert_eq!(attrs.desc.as_deref(), Some("Create a database"));
+        assert_eq!(attrs.tags, Some(vec!["db".to_string()]));
+        assert_eq!(
+            attrs.examples,
+            vec!["myctl db create mydb", "myctl db create mydb --size 20GB"]
+        );
+    }
+
+    #[test]
+    fn test_parse_without_examples() {
+        let attrs: SenAttrs = syn::parse_str(r#"desc = "Show status""#).unwrap();
+        assert!(attrs.examples.is_empty());
+        assert!(attrs.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        let attrs: SenAttrs =
+            syn::parse_str(r#"desc = "List items", aliases = ["ls", "l"], tags = []"#).unwrap();
+
+        assert_eq!(attrs.aliases, vec!["ls", "l"]);
+        assert_eq!(attrs.tags, Some(vec![]));
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let attrs: SenAttrs =
+            syn::parse_str(r#"desc = "Edit config", env = ["EDITOR", "MYCTL_TOKEN"]"#).unwrap();
+        assert_eq!(attrs.env, vec!["EDITOR", "MYCTL_TOKEN"]);
+
+        let attrs: SenAttrs = syn::parse_str(r#"desc = "Edit config""#).unwrap();
+        assert!(attrs.env.is_empty());
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let attrs: SenAttrs = syn::parse_str(r#"desc = "Deploy", timeout = "30s""#).unwrap();
+        assert_eq!(attrs.timeout.as_deref(), Some("30s"));
+
+        let attrs: SenAttrs = syn::parse_str(r#"desc = "Deploy""#).unwrap();
+        assert_eq!(attrs.timeout, None);
+    }
+
+    fn sen_router_error(input: DeriveInput) -> String {
+        expand_sen_router(&input)
+            .expect_err("expected SenRouter to reject the input")
+            .to_string()
+    }
+
+    #[test]
+    fn test_sen_router_expands_valid_enum() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[sen(state = AppState)]
+            enum Commands {
+                #[sen(handler = handlers::status, desc = "Show status")]
+                Status,
+                #[sen(handler = handlers::build)]
+                Build(BuildArgs),
+            }
+        };
+        assert!(expand_sen_router(&input).is_ok());
+    }
+
+    #[test]
+    fn test_sen_router_misuse_is_a_compile_error() {
+        assert_eq!(
+            sen_router_error(syn::parse_quote! {
+                #[sen(state = AppState)]
+                struct Commands;
+            }),
+            "SenRouter can only be derived for enums"
+        );
+        assert_eq!(
+            sen_router_error(syn::parse_quote! {
+                enum Commands {
+                    #[sen(handler = handlers::status)]
+                    Status,
+                }
+            }),
+            "Missing #[sen(state = YourStateType)] attribute on enum"
+        );
+        assert_eq!(
+            sen_router_error(syn::parse_quote! {
+                #[sen(state = AppState)]
+                enum Commands {
+                    Status,
+                }
+            }),
+            "Missing #[sen(handler = path::to::handler)] attribute on variant Status"
+        );
+        assert_eq!(
+            sen_router_error(syn::parse_quote! {
+                #[sen(state = AppState)]
+                enum Commands {
+                    #[sen(handler = handlers::build)]
+                    Build { target: String },
+                }
+            }),
+            "Named fields are not supported in SenRouter. Use tuple variants or unit variants."
+        );
+        assert!(sen_router_error(syn::parse_quote! {
+            #[sen(state = AppState)]
+            enum Commands {
+                #[sen(handler = "handlers::status")]
+                Status,
+            }
+        })
+        .starts_with("Expected a handler path"));
+    }
+
+    #[test]
+    fn test_sen_router_router_options() {
+        assert_eq!(to_kebab_case("DbCreate"), "db-create");
+        assert_eq!(to_kebab_case("Status"), "status");
+        assert_eq!(to_kebab_case("HTTPServer"), "http-server");
+        assert_eq!(to_kebab_case("Ipv4Lookup"), "ipv4-lookup");
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[sen(state = AppState, name = "myctl", agent_mode)]
+            enum Commands {
+                #[sen(handler = handlers::status)]
+                Status,
+            }
+        };
+        let expanded = expand_sen_router(&input).unwrap().to_string();
+        assert!(expanded.contains("pub fn router"));
+        assert!(expanded.contains("with_agent_mode"));
+
+        assert_eq!(
+            sen_router_error(syn::parse_quote! {
+                #[sen(state = AppState, agent_mode)]
+                enum Commands {
+                    #[sen(handler = handlers::status)]
+                    Status,
+                }
+            }),
+            "Generating router() needs a name: #[sen(name = \"myctl\")]"
+        );
+        assert!(sen_router_error(syn::parse_quote! {
+            #[sen(state = AppState, agnet_mode)]
+            enum Commands {
+                #[sen(handler = handlers::status)]
+                Status,
+            }
+        })
+        .starts_with("Unknown SenRouter option"));
+    }
+
+    #[test]
+    fn test_sen_router_args_on_unit_variant() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[sen(state = AppState)]
+            enum Commands {
+                #[sen(handler = handlers::deploy, args = DeployArgs)]
+                Deploy,
+            }
+        };
+        let expanded = expand_sen_router(&input).unwrap().to_string();
+        assert!(expanded.contains("< DeployArgs as sen :: FromArgs > :: from_args"));
+
+        assert!(sen_router_error(syn::parse_quote! {
+            #[sen(state = AppState)]
+            enum Commands {
+                #[sen(handler = handlers::build, args = BuildArgs)]
+                Build(BuildArgs),
+            }
+        })
+        .starts_with("#[sen(args = T)] is only supported on unit variants"));
+    }
+}
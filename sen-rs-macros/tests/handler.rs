@@ -0,0 +1,33 @@
+//! Metadata generated by `#[sen::handler]`.
+
+use std::time::Duration;
+
+/// Raw arguments, parsed the same way with and without the `clap` feature
+struct Raw;
+
+impl sen::FromArgs for Raw {
+    fn from_args(_args: &[String]) -> Result<Self, sen::CliError> {
+        Ok(Raw)
+    }
+}
+
+#[sen::handler(desc = "Deploy", timeout = "1h30m")]
+async fn deploy(_state: sen::State<()>, _args: sen::Args<Raw>) -> sen::CliResult<String> {
+    Ok("deployed".to_string())
+}
+
+#[sen::handler(timeout = "soon")]
+async fn stall(_state: sen::State<()>, _args: sen::Args<Raw>) -> sen::CliResult<String> {
+    Ok("stalled".to_string())
+}
+
+#[test]
+fn test_timeout_is_parsed_by_sen() {
+    assert_eq!(deploy().metadata.timeout, Some(Duration::from_secs(5400)));
+}
+
+#[test]
+#[should_panic(expected = "Invalid timeout on handler 'stall'")]
+fn test_invalid_timeout_panics() {
+    let _ = stall();
+}
@@ -0,0 +1,8 @@
+//! Compile-fail tests: misuse of the macros must be reported at the
+//! offending tokens. Run with `TRYBUILD=overwrite` to update the snapshots.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
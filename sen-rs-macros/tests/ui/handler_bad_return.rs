@@ -0,0 +1,6 @@
+#[sen::handler]
+async fn count(_state: sen::State<()>, _args: sen::Args<Vec<String>>) -> usize {
+    42
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use sen::SenRouter;
+
+#[derive(Clone)]
+struct App;
+
+#[derive(SenRouter)]
+#[sen(state = App)]
+enum Commands {
+    Status,
+}
+
+fn main() {}
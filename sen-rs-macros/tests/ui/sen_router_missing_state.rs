@@ -0,0 +1,13 @@
+use sen::SenRouter;
+
+async fn status(_state: sen::State<()>) -> String {
+    String::new()
+}
+
+#[derive(SenRouter)]
+enum Commands {
+    #[sen(handler = status)]
+    Status,
+}
+
+fn main() {}
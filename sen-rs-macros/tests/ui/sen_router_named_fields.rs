@@ -0,0 +1,17 @@
+use sen::SenRouter;
+
+#[derive(Clone)]
+struct App;
+
+async fn build(_state: sen::State<App>, _args: String) -> String {
+    String::new()
+}
+
+#[derive(SenRouter)]
+#[sen(state = App)]
+enum Commands {
+    #[sen(handler = build)]
+    Build { target: String },
+}
+
+fn main() {}
@@ -83,7 +83,46 @@ pub fn target() -> &'static str {
     built_info::TARGET
 }
 
+/// Get version information as JSON.
+///
+/// Same data as [`version_info`], for `version --json`:
+///
+/// ```text
+/// {
+///   "name": "sen",
+///   "version": "0.1.0",
+///   "target": "x86_64-apple-darwin",
+///   "built_at": "Sun, 24 Nov 2024 12:34:56 +0000",
+///   "git_sha": "a1b2c3d...",
+///   "rustc": "rustc 1.75.0 (82e1608df 2023-12-21)"
+/// }
+/// ```
+///
+/// `git_sha` is omitted when the build was not made from a git checkout.
+#[cfg(feature = "build-info")]
+pub fn version_info_json() -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "name": built_info::PKG_NAME,
+        "version": built_info::PKG_VERSION,
+        "target": built_info::TARGET,
+        "built_at": built_info::BUILT_TIME_UTC,
+        "rustc": built_info::RUSTC_VERSION,
+    });
+    if let Some(sha) = built_info::GIT_COMMIT_HASH {
+        json["git_sha"] = serde_json::json!(sha);
+    }
+    json
+}
+
 // Fallback implementations when build-info feature is disabled
+#[cfg(not(feature = "build-info"))]
+pub fn version_info_json() -> serde_json::Value {
+    serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
 #[cfg(not(feature = "build-info"))]
 pub fn version_info() -> String {
     format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
@@ -109,6 +148,19 @@ mod tests {
         assert!(!info.is_empty());
     }
 
+    #[test]
+    fn test_version_info_json() {
+        let json = version_info_json();
+        assert_eq!(json["name"], "sen");
+        assert_eq!(json["version"], version_short());
+
+        #[cfg(feature = "build-info")]
+        match git_commit() {
+            Some(sha) => assert_eq!(json["git_sha"], sha),
+            None => assert!(json.get("git_sha").is_none()),
+        }
+    }
+
     #[test]
     fn test_version_short() {
         let version = version_short();
@@ -0,0 +1,86 @@
+//! Built-in `version`, `doctor`, and `history` commands.
+//!
+//! The router answers these itself before routing, unless a registered
+//! route has the same name. `<command> --json` prints the structured form.
+
+use crate::{history, sort_json_keys, Output, Response, Router};
+
+impl Router<()> {
+    /// Run the built-in command `args` invoke, if any.
+    pub(crate) fn run_builtin(&self, args: &[String]) -> Option<Response> {
+        let (name, json) = match args {
+            [name] => (name.as_str(), false),
+            [name, flag] if flag == "--json" => (name.as_str(), true),
+            _ => return None,
+        };
+
+        if self.is_builtin_version(name) {
+            return Some(self.handle_version(json));
+        }
+        if self.is_builtin_doctor(name) {
+            return Some(self.doctor().into_response(json));
+        }
+        match &self.config.history {
+            Some(path) if self.is_builtin_history(name) => Some(history::response(path, json)),
+            _ => None,
+        }
+    }
+
+    /// Handle version command.
+    ///
+    /// With `json`, returns the app's `name` and `version` from the router
+    /// metadata, with sen's own build info from [`crate::version_info_json`]
+    /// nested under `"sen"`.
+    fn handle_version(&self, json: bool) -> Response {
+        if json {
+            let mut info = serde_json::json!({ "sen": crate::build_info::version_info_json() });
+            if let Some(meta) = &self.config.metadata {
+                info["name"] = serde_json::json!(meta.name);
+                if let Some(version) = meta.version {
+                    info["version"] = serde_json::json!(version);
+                }
+            }
+            let mut response = Response::silent();
+            response.output = Output::Json(
+                serde_json::to_string_pretty(&sort_json_keys(info))
+                    .unwrap_or_else(|_| "{}".to_string()),
+            );
+            return response;
+        }
+
+        if let Some(meta) = &self.config.metadata {
+            if let Some(version) = meta.version {
+                return Response::text(format!("{} {}", meta.name, version));
+            }
+        }
+
+        #[cfg(feature = "build-info")]
+        {
+            Response::text(crate::version_info())
+        }
+
+        #[cfg(not(feature = "build-info"))]
+        Response::text("version information not available")
+    }
+
+    /// Whether `arg` invokes the built-in version command.
+    ///
+    /// A registered route under the version command name takes precedence.
+    fn is_builtin_version(&self, arg: &str) -> bool {
+        match self.config.builtins.version {
+            Some(name) if arg == name => !self.routes.contains_key(name),
+            Some(_) => arg == "--version" || arg == "-V",
+            None => false,
+        }
+    }
+
+    /// Whether `arg` invokes the built-in doctor command.
+    fn is_builtin_doctor(&self, arg: &str) -> bool {
+        self.config.builtins.doctor == Some(arg) && !self.routes.contains_key(arg)
+    }
+
+    /// Whether `arg` invokes the built-in history command.
+    fn is_builtin_history(&self, arg: &str) -> bool {
+        self.config.builtins.history == Some(arg) && !self.routes.contains_key(arg)
+    }
+}
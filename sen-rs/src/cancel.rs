@@ -0,0 +1,125 @@
+//! Cooperative cancellation for long-running handlers.
+//!
+//! When a router is built with `Router::with_cancellation()`, Ctrl-C cancels
+//! the running command instead of killing the process. Handlers that take a
+//! `Cancel` extractor can observe this and clean up (stop child processes,
+//! roll back transactions) before returning:
+//!
+//! - The router waits for the handler to return, then reports a
+//!   `UserError::Cancelled` (exit code 130)
+//! - A second Ctrl-C stops waiting and returns immediately
+//!
+//! Outside of a cancellable router, `Cancel` is never cancelled.
+
+use tokio::sync::watch;
+
+// ============================================================================
+// Cancel Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static CANCEL_SIGNAL: watch::Receiver<bool>;
+}
+
+/// Cancellation token extractor for handler functions.
+///
+/// Similar to `Progress`, this can be used in handler signatures to observe
+/// whether the user has interrupted the command.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, Cancel, CliError, CliResult, State};
+///
+/// async fn migrate(
+///     state: State<AppState>,
+///     cancel: Cancel,
+///     Args(args): Args<MigrateArgs>,
+/// ) -> CliResult<String> {
+///     let tx = state.read().await.db.begin().await?;
+///     tokio::select! {
+///         result = run_migrations(&tx, &args) => {
+///             tx.commit().await?;
+///             result
+///         }
+///         _ = cancel.cancelled() => {
+///             tx.rollback().await?;
+///             Err(CliError::user("Migration rolled back"))
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cancel {
+    rx: Option<watch::Receiver<bool>>,
+}
+
+impl Cancel {
+    /// Get the cancellation token for the currently executing command.
+    ///
+    /// Outside of a cancellable router this returns a token that is never
+    /// cancelled.
+    pub fn current() -> Self {
+        Self {
+            rx: CANCEL_SIGNAL.try_with(|rx| rx.clone()).ok(),
+        }
+    }
+
+    /// Check whether the command has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.rx.as_ref().is_some_and(|rx| *rx.borrow())
+    }
+
+    /// Wait until the command is cancelled.
+    ///
+    /// Never resolves if cancellation is not enabled.
+    pub async fn cancelled(&self) {
+        if let Some(mut rx) = self.rx.clone() {
+            if rx.wait_for(|cancelled| *cancelled).await.is_ok() {
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+impl crate::FromContext for Cancel {
+    fn from_context() -> Result<Self, crate::CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with cancellation observed through `rx`.
+pub(crate) async fn scope<F: std::future::Future>(rx: watch::Receiver<bool>, fut: F) -> F::Output {
+    CANCEL_SIGNAL.scope(rx, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_outside_router_is_never_cancelled() {
+        let cancel = Cancel::current();
+        assert!(cancel.rx.is_none());
+        assert!(!cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_observes_signal() {
+        let (tx, rx) = watch::channel(false);
+        scope(rx, async {
+            let cancel = Cancel::current();
+            assert!(!cancel.is_cancelled());
+
+            tx.send(true).unwrap();
+            cancel.cancelled().await;
+            assert!(cancel.is_cancelled());
+        })
+        .await;
+    }
+}
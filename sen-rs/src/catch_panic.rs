@@ -0,0 +1,109 @@
+//! Turning handler panics into errors (opt-in).
+//!
+//! With `Router::catch_panics()`, a handler that panics yields
+//! `SystemError::Internal("handler panicked: ...")` (exit 101) instead of
+//! unwinding through `execute_with`. A REPL or other embedding keeps running.
+//!
+//! - The panic hook still runs, so the usual message goes to stderr
+//! - With the `tracing` feature the panic is also logged as an error
+//! - State the handler was mutating may be left half-updated; that is the
+//!   caller's `UnwindSafe` promise when enabling this
+//! - Only unwinding panics are caught; `panic = "abort"` still aborts
+
+use crate::{CliError, Response, SystemError};
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Run `fut`, converting a panic while polling it into an internal error.
+pub(crate) async fn catch<F>(route_key: &str, fut: F) -> Result<Response, CliError>
+where
+    F: Future<Output = Result<Response, CliError>>,
+{
+    let mut fut = std::pin::pin!(fut);
+    let result = std::future::poll_fn(|cx: &mut Context<'_>| poll_caught(fut.as_mut(), cx)).await;
+    result.unwrap_or_else(|payload| {
+        let message = panic_message(payload.as_ref());
+        #[cfg(feature = "tracing")]
+        tracing::error!(command = %route_key.replace(':', " "), panic = %message, "Handler panicked");
+        #[cfg(not(feature = "tracing"))]
+        let _ = route_key;
+        Err(CliError::System(SystemError::Internal(format!(
+            "handler panicked: {}",
+            message
+        ))))
+    })
+}
+
+fn poll_caught<T>(
+    fut: Pin<&mut impl Future<Output = T>>,
+    cx: &mut Context<'_>,
+) -> Poll<Result<T, Box<dyn Any + Send>>> {
+    match catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+        Ok(Poll::Pending) => Poll::Pending,
+        Ok(Poll::Ready(value)) => Poll::Ready(Ok(value)),
+        Err(payload) => Poll::Ready(Err(payload)),
+    }
+}
+
+/// The message passed to `panic!`, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{CliResult, Output, Router, State};
+
+    async fn boom(_state: State<()>) -> CliResult<String> {
+        panic!("index out of range");
+    }
+
+    async fn fine(_state: State<()>) -> CliResult<String> {
+        Ok("fine".to_string())
+    }
+
+    fn args(command: &str) -> Vec<String> {
+        vec!["test".to_string(), command.to_string()]
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_returns_101() {
+        let router = Router::new()
+            .route("boom", boom)
+            .route("fine", fine)
+            .catch_panics()
+            .with_state(());
+
+        let response = router.execute_with(&args("boom")).await;
+        assert_eq!(response.exit_code, 101);
+        assert_eq!(response.error.map(|e| e.kind), Some("internal"));
+        match response.output {
+            Output::Text(text) => assert!(text.contains("handler panicked: index out of range")),
+            other => panic!("Expected text output, got {:?}", other),
+        }
+
+        // The router is still usable afterwards
+        let response = router.execute_with(&args("fine")).await;
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "index out of range")]
+    async fn test_panics_propagate_by_default() {
+        let router = Router::new().route("boom", boom).with_state(());
+        router.execute_with(&args("boom")).await;
+    }
+}
@@ -0,0 +1,125 @@
+//! Dynamic completion of argument values.
+//!
+//! Shell completion scripts know the command names but not values such as
+//! the databases that exist right now. Register a `Completer` for a route
+//! with `Router::completer`, and have the script call the hidden command
+//!
+//! ```text
+//! myctl __complete <command words...> <partial>
+//! ```
+//!
+//! which prints one candidate per line:
+//!
+//! - If the words name a route with a completer, the completer's candidates
+//!   for `<partial>`
+//! - Otherwise the subcommands under those words that start with `<partial>`
+//!
+//! Pass an empty `<partial>` (`''`) to list everything. A route registered as
+//! `__complete` takes precedence over the built-in.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Hidden command that prints completion candidates.
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// Completion candidates, as returned by a [`Completer`].
+pub type CompletionFuture<'a> = Pin<Box<dyn Future<Output = Vec<String>> + Send + 'a>>;
+
+/// Supplies argument values for one command.
+///
+/// Closures `Fn(String, String) -> impl Future<Output = Vec<String>>` taking
+/// the command and the partial input implement it too.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{CompletionFuture, Completer};
+///
+/// struct DatabaseNames(Db);
+///
+/// impl Completer for DatabaseNames {
+///     fn complete<'a>(&'a self, _command: &'a str, current: &'a str) -> CompletionFuture<'a> {
+///         Box::pin(async move {
+///             self.0.list().await
+///                 .into_iter()
+///                 .filter(|name| name.starts_with(current))
+///                 .collect()
+///         })
+///     }
+/// }
+///
+/// let router = Router::new()
+///     .route("db:delete", handlers::delete)
+///     .completer("db:delete", DatabaseNames(db.clone()))
+///     .with_state(state);
+/// ```
+pub trait Completer: Send + Sync + 'static {
+    /// Candidates for `current`, the partially typed argument of `command`
+    /// (e.g. `"db delete"`).
+    fn complete<'a>(&'a self, command: &'a str, current: &'a str) -> CompletionFuture<'a>;
+}
+
+impl<F, Fut> Completer for F
+where
+    F: Fn(String, String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Vec<String>> + Send + 'static,
+{
+    fn complete<'a>(&'a self, command: &'a str, current: &'a str) -> CompletionFuture<'a> {
+        Box::pin(self(command.to_string(), current.to_string()))
+    }
+}
+
+/// Completer as stored by the router.
+pub(crate) type SharedCompleter = Arc<dyn Completer>;
+
+/// Next command segments under `words` that start with `partial`, sorted.
+pub(crate) fn subcommands<'a>(
+    route_keys: impl Iterator<Item = &'a String>,
+    words: &[String],
+    partial: &str,
+) -> Vec<String> {
+    let prefix = words.join(":");
+    let mut candidates: Vec<String> = route_keys
+        .filter_map(|key| {
+            let rest = if prefix.is_empty() {
+                key.as_str()
+            } else {
+                key.strip_prefix(&prefix)?.strip_prefix(':')?
+            };
+            let segment = rest.split(':').next()?;
+            segment.starts_with(partial).then(|| segment.to_string())
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn test_subcommands_lists_next_segment() {
+        let routes = keys(&["db:create", "db:delete", "db:backup:run", "status"]);
+
+        assert_eq!(subcommands(routes.iter(), &[], ""), ["db", "status"]);
+        assert_eq!(subcommands(routes.iter(), &keys(&["db"]), "de"), ["delete"]);
+        assert_eq!(
+            subcommands(routes.iter(), &keys(&["db"]), ""),
+            ["backup", "create", "delete"]
+        );
+        assert!(subcommands(routes.iter(), &keys(&["dbx"]), "").is_empty());
+    }
+}
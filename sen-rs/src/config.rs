@@ -0,0 +1,338 @@
+//! Typed config file loading (requires `config` feature).
+//!
+//! Loads a file and deserializes it into your config type, mapping failures
+//! to `CliError` the same way everywhere:
+//!
+//! - Read failures (missing file, permissions) → `SystemError::Io`
+//! - Malformed content → `SystemError::ConfigParse`
+//!
+//! Both include the file path in the message. A leading `~` in the path is
+//! expanded (see [`crate::paths::tilde_expand`]); use
+//! [`crate::paths::config_dir`] for the platform's default location.
+//!
+//! For tools that switch whole config sets (like `aws --profile prod`),
+//! [`Profiles`] loads one named section from a file; the name comes from
+//! the global `--profile <name>` flag (see [`crate::profile`]).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct ProjectConfig {
+//!     build_command: String,
+//!     test_command: String,
+//! }
+//!
+//! let config: ProjectConfig = sen::config::load_toml("sen.toml")?;
+//! ```
+
+use crate::{CliError, CliResult, SystemError, UserError};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub use crate::profile::{ProfileFlag, DEFAULT_PROFILE};
+
+/// Load a TOML config file.
+pub fn load_toml<T: DeserializeOwned>(path: impl AsRef<Path>) -> CliResult<T> {
+    let path = &crate::paths::tilde_expand(path);
+    let content = read(path)?;
+    toml::from_str(&content).map_err(|e| parse_error(path, e))
+}
+
+/// Load a YAML config file.
+pub fn load_yaml<T: DeserializeOwned>(path: impl AsRef<Path>) -> CliResult<T> {
+    let path = &crate::paths::tilde_expand(path);
+    let content = read(path)?;
+    serde_yaml::from_str(&content).map_err(|e| parse_error(path, e))
+}
+
+/// Load a JSON config file.
+pub fn load_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> CliResult<T> {
+    let path = &crate::paths::tilde_expand(path);
+    let content = read(path)?;
+    serde_json::from_str(&content).map_err(|e| parse_error(path, e))
+}
+
+/// Load a config file, picking the format from its extension
+/// (`.toml`, `.yaml`/`.yml`, or `.json`).
+fn load_any<T: DeserializeOwned>(path: &Path) -> CliResult<T> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => load_toml(path),
+        Some("yaml" | "yml") => load_yaml(path),
+        Some("json") => load_json(path),
+        _ => Err(parse_error(
+            path,
+            "unsupported config format (expected .toml, .yaml, .yml, or .json)",
+        )),
+    }
+}
+
+/// Check that a config file reads and parses, without a target type.
+pub(crate) fn check_file(path: &Path) -> CliResult<()> {
+    load_any::<serde::de::IgnoredAny>(path).map(|_| ())
+}
+
+fn read(path: &Path) -> CliResult<String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        let error = std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e));
+        CliError::System(SystemError::Io(error))
+    })
+}
+
+fn parse_error(path: &Path, error: impl std::fmt::Display) -> CliError {
+    CliError::System(SystemError::ConfigParse(format!(
+        "{}: {}",
+        path.display(),
+        error
+    )))
+}
+
+// ============================================================================
+// Profiles
+// ============================================================================
+
+/// Named config sets loaded from a single file.
+///
+/// The file maps profile names to sections of the same shape:
+///
+/// ```toml
+/// [default]
+/// endpoint = "http://localhost:8080"
+///
+/// [prod]
+/// endpoint = "https://api.example.com"
+/// ```
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::config::{ProfileFlag, Profiles};
+/// use sen::FromGlobalArgs;
+///
+/// let args: Vec<String> = std::env::args().skip(1).collect();
+/// let (flag, remaining_args) = ProfileFlag::from_global_args(&args)?;
+///
+/// // --profile, then $MYCTL_PROFILE, then "default"
+/// let profile = flag.resolve("MYCTL_PROFILE");
+/// let config: ApiConfig = Profiles::load("myctl.toml", &profile)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Profiles<T> {
+    profiles: BTreeMap<String, T>,
+}
+
+impl<T: DeserializeOwned> Profiles<T> {
+    /// Load every profile from `path` (format picked from the extension).
+    pub fn from_file(path: impl AsRef<Path>) -> CliResult<Self> {
+        Ok(Self {
+            profiles: load_any(path.as_ref())?,
+        })
+    }
+
+    /// Load only the profile named `profile` from `path`.
+    pub fn load(path: impl AsRef<Path>, profile: &str) -> CliResult<T> {
+        Self::from_file(path)?.into_profile(profile)
+    }
+}
+
+impl<T> Profiles<T> {
+    /// Names of the available profiles, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    /// Get a profile by name.
+    ///
+    /// Unknown names produce a `UserError` listing the available profiles.
+    pub fn get(&self, name: &str) -> CliResult<&T> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| self.unknown_profile(name))
+    }
+
+    /// Take a profile by name, dropping the others.
+    pub fn into_profile(mut self, name: &str) -> CliResult<T> {
+        match self.profiles.remove(name) {
+            Some(profile) => Ok(profile),
+            None => Err(self.unknown_profile(name)),
+        }
+    }
+
+    fn unknown_profile(&self, name: &str) -> CliError {
+        let available = if self.profiles.is_empty() {
+            "none".to_string()
+        } else {
+            self.names().join(", ")
+        };
+        CliError::User(UserError::InvalidArgument {
+            arg: "--profile".to_string(),
+            reason: format!(
+                "Unknown profile '{}'. Available profiles: {}",
+                name, available
+            ),
+        })
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ProjectConfig {
+        build_command: String,
+        jobs: u32,
+    }
+
+    fn expected() -> ProjectConfig {
+        ProjectConfig {
+            build_command: "cargo build".to_string(),
+            jobs: 4,
+        }
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_valid_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let toml_path = write(
+            dir.path(),
+            "sen.toml",
+            "build_command = \"cargo build\"\njobs = 4\n",
+        );
+        let yaml_path = write(
+            dir.path(),
+            "sen.yaml",
+            "build_command: cargo build\njobs: 4\n",
+        );
+        let json_path = write(
+            dir.path(),
+            "sen.json",
+            r#"{"build_command": "cargo build", "jobs": 4}"#,
+        );
+
+        assert_eq!(load_toml::<ProjectConfig>(&toml_path).unwrap(), expected());
+        assert_eq!(load_yaml::<ProjectConfig>(&yaml_path).unwrap(), expected());
+        assert_eq!(load_json::<ProjectConfig>(&json_path).unwrap(), expected());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ApiConfig {
+        endpoint: String,
+        retries: u32,
+    }
+
+    const PROFILES_TOML: &str = r#"
+[default]
+endpoint = "http://localhost:8080"
+retries = 0
+
+[prod]
+endpoint = "https://api.example.com"
+retries = 3
+"#;
+
+    #[test]
+    fn test_profiles_are_loaded_in_isolation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "myctl.toml", PROFILES_TOML);
+
+        let default: ApiConfig = Profiles::load(&path, DEFAULT_PROFILE).unwrap();
+        let prod: ApiConfig = Profiles::load(&path, "prod").unwrap();
+
+        assert_eq!(
+            default,
+            ApiConfig {
+                endpoint: "http://localhost:8080".to_string(),
+                retries: 0,
+            }
+        );
+        assert_eq!(
+            prod,
+            ApiConfig {
+                endpoint: "https://api.example.com".to_string(),
+                retries: 3,
+            }
+        );
+
+        let profiles = Profiles::<ApiConfig>::from_file(&path).unwrap();
+        assert_eq!(profiles.names(), vec!["default", "prod"]);
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_available() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "myctl.toml", PROFILES_TOML);
+
+        let err = Profiles::<ApiConfig>::load(&path, "staging").unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        assert_eq!(
+            err.to_string(),
+            "Error: Invalid argument '--profile'\n\nUnknown profile 'staging'. Available profiles: default, prod"
+        );
+    }
+
+    #[test]
+    fn test_missing_file_is_io_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.toml");
+
+        let err = load_toml::<ProjectConfig>(&path).unwrap_err();
+        assert_eq!(err.exit_code(), 101);
+        match err {
+            CliError::System(SystemError::Io(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+                assert!(e.to_string().contains("missing.toml"));
+            }
+            other => panic!("Expected Io error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tilde_in_path_is_expanded() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let err = load_toml::<ProjectConfig>("~/sen-definitely-missing.toml").unwrap_err();
+        let expected = home.join("sen-definitely-missing.toml");
+        assert!(err.to_string().contains(&expected.display().to_string()));
+    }
+
+    #[test]
+    fn test_malformed_file_is_config_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "sen.toml",
+            "build_command = \"cargo build\"\njobs = \"four\"\n",
+        );
+
+        match load_toml::<ProjectConfig>(&path).unwrap_err() {
+            CliError::System(SystemError::ConfigParse(message)) => {
+                assert!(message.starts_with(&path.display().to_string()));
+                assert!(message.contains("jobs"));
+            }
+            other => panic!("Expected ConfigParse error, got {:?}", other),
+        }
+
+        let path = write(dir.path(), "sen.json", "{ not json");
+        assert!(matches!(
+            load_json::<ProjectConfig>(&path),
+            Err(CliError::System(SystemError::ConfigParse(_)))
+        ));
+    }
+}
@@ -0,0 +1,430 @@
+//! Self-check of a CLI's environment and command table.
+//!
+//! `Router::doctor()` returns a [`DoctorReport`]; the built-in `doctor`
+//! command prints it (`doctor --json` for the structured form). Checks:
+//!
+//! - Tools declared with `Router::requires_tool` are on `PATH`
+//! - The file given to `Router::with_config_file` exists and parses
+//!   (parsing needs the `config` feature)
+//! - No two commands differ only in case or `_`/`-`, and no command
+//!   shadows a built-in
+//! - Every command has a description
+//!
+//! Errors make the command exit with 1; warnings alone don't.
+
+use crate::{complete, normalize_segment, Output, Response, RouteMetadata, Router};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A tool the CLI needs at runtime (see `Router::requires_tool`).
+#[derive(Debug, Clone)]
+pub(crate) struct RequiredTool {
+    pub(crate) name: String,
+    pub(crate) install_hint: String,
+}
+
+/// What a finding is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// A required tool is not on `PATH`
+    MissingDependency,
+    /// The config file does not exist
+    MissingConfig,
+    /// The config file can't be read or parsed
+    InvalidConfig,
+    /// Two commands match the same input, or a command hides a built-in
+    RouteCollision,
+    /// A command has no description
+    MissingDescription,
+}
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The CLI won't work as intended
+    Error,
+    /// Worth fixing, but nothing is broken
+    Warning,
+}
+
+/// One problem found by `Router::doctor()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    /// The tool, file, or command the finding is about
+    pub subject: String,
+    pub message: String,
+}
+
+/// Result of `Router::doctor()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// Whether there are no error findings (warnings are allowed).
+    pub fn is_healthy(&self) -> bool {
+        !self
+            .findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error)
+    }
+
+    /// Findings of the given kind.
+    pub fn of_kind(&self, kind: FindingKind) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(move |f| f.kind == kind)
+    }
+
+    /// Structured form: `{ healthy, findings: [{ kind, severity, subject, message }] }`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "healthy": self.is_healthy(),
+            "findings": self.findings,
+        })
+    }
+
+    /// Human-readable form, one line per finding plus a summary.
+    pub fn to_text(&self) -> String {
+        if self.findings.is_empty() {
+            return "No problems found".to_string();
+        }
+
+        let mut text = String::new();
+        for finding in &self.findings {
+            let label = match finding.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            text.push_str(&format!(
+                "{}: {}: {}\n",
+                label, finding.subject, finding.message
+            ));
+        }
+        let errors = self
+            .findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count();
+        let warnings = self.findings.len() - errors;
+        text.push_str(&format!(
+            "\n{} error{}, {} warning{}",
+            errors,
+            if errors == 1 { "" } else { "s" },
+            warnings,
+            if warnings == 1 { "" } else { "s" },
+        ));
+        text
+    }
+
+    /// Response for the built-in command: exit 1 when unhealthy, the report
+    /// as `data` in agent JSON.
+    pub(crate) fn into_response(self, json: bool) -> Response {
+        let report = self.to_json();
+        let mut response = if json {
+            let mut response = Response::silent();
+            response.output = Output::Json(
+                serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string()),
+            );
+            response
+        } else {
+            Response::text(self.to_text())
+        };
+        if !self.is_healthy() {
+            response.exit_code = 1;
+        }
+        response.changed(false).with_data(report)
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        kind: FindingKind,
+        severity: Severity,
+        subject: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.findings.push(Finding {
+            kind,
+            severity,
+            subject: subject.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Check that each required tool is on `PATH`.
+    pub(crate) fn check_tools(&mut self, tools: &[RequiredTool]) {
+        for tool in tools {
+            if find_on_path(&tool.name).is_none() {
+                self.push(
+                    FindingKind::MissingDependency,
+                    Severity::Error,
+                    &tool.name,
+                    format!("not found on PATH ({})", tool.install_hint),
+                );
+            }
+        }
+    }
+
+    /// Check that the config file exists and, with the `config` feature,
+    /// parses as TOML, YAML, or JSON (by extension).
+    pub(crate) fn check_config(&mut self, path: &Path) {
+        let expanded = crate::paths::tilde_expand(path);
+        let problem = if expanded.exists() {
+            config_error(&expanded).map(|e| (FindingKind::InvalidConfig, e))
+        } else {
+            let missing = "config file does not exist".to_string();
+            Some((FindingKind::MissingConfig, missing))
+        };
+        if let Some((kind, message)) = problem {
+            self.push(kind, Severity::Error, path.display().to_string(), message);
+        }
+    }
+}
+
+impl<S> Router<S> {
+    /// Check the CLI's environment and command table.
+    ///
+    /// Reports required tools missing from `PATH` ([`Router::requires_tool`]),
+    /// a missing or unparsable config file ([`Router::with_config_file`]),
+    /// commands that differ only in case or `_`/`-` or that replace a
+    /// built-in, and commands without a description. The built-in `doctor`
+    /// command prints this report (`doctor --json` for JSON).
+    pub fn doctor(&self) -> DoctorReport {
+        let mut report = DoctorReport::default();
+        report.check_tools(&self.config.required_tools);
+        if let Some(path) = &self.config.config_file {
+            report.check_config(path);
+        }
+
+        let mut keys: Vec<&String> = self.routes.keys().collect();
+        keys.sort();
+
+        // Aliases are checked with the routes, since they are typed the same
+        let mut names: Vec<&String> = keys.iter().copied().chain(self.aliases.keys()).collect();
+        names.sort();
+
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        for key in &names {
+            let normalized: Vec<String> = key.split(':').map(normalize_segment).collect();
+            if let Some(first) = seen.get(&normalized.join(":")) {
+                report.push(
+                    FindingKind::RouteCollision,
+                    Severity::Warning,
+                    key.replace(':', " "),
+                    format!(
+                        "differs from '{}' only in case or '_'/'-'",
+                        first.replace(':', " ")
+                    ),
+                );
+            } else {
+                seen.insert(normalized.join(":"), key);
+            }
+        }
+
+        let builtins = [
+            self.config.builtins.version,
+            self.config.builtins.doctor,
+            self.config
+                .history
+                .as_ref()
+                .and(self.config.builtins.history),
+            Some(complete::COMPLETE_COMMAND),
+        ];
+        for name in builtins.into_iter().flatten() {
+            if self.routes.contains_key(name) || self.aliases.contains_key(name) {
+                report.push(
+                    FindingKind::RouteCollision,
+                    Severity::Warning,
+                    name,
+                    format!("replaces the built-in '{}' command", name),
+                );
+            }
+        }
+
+        for key in &keys {
+            let described = self
+                .route_metadata
+                .get(key.as_str())
+                .and_then(RouteMetadata::get_description)
+                .is_some();
+            if !described {
+                report.push(
+                    FindingKind::MissingDescription,
+                    Severity::Warning,
+                    key.replace(':', " "),
+                    "no description",
+                );
+            }
+        }
+        report
+    }
+}
+
+#[cfg(feature = "config")]
+fn config_error(path: &Path) -> Option<String> {
+    crate::config::check_file(path).err().map(|e| e.to_string())
+}
+
+#[cfg(not(feature = "config"))]
+fn config_error(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Full path of `tool` if it is an executable file on `PATH` (or, when it
+/// contains a path separator, at that path).
+pub(crate) fn find_on_path(tool: &str) -> Option<PathBuf> {
+    let candidates: Vec<PathBuf> = if tool.contains(['/', std::path::MAIN_SEPARATOR]) {
+        vec![PathBuf::from(tool)]
+    } else {
+        let path = std::env::var_os("PATH")?;
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(tool))
+            .collect()
+    };
+
+    candidates.into_iter().find_map(|candidate| {
+        if is_executable(&candidate) {
+            return Some(candidate);
+        }
+        let exe = candidate.with_extension(std::env::consts::EXE_EXTENSION);
+        (cfg!(windows) && is_executable(&exe)).then_some(exe)
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CliResult, Router, State};
+
+    async fn status(_state: State<()>) -> CliResult<String> {
+        Ok("ok".to_string())
+    }
+
+    #[test]
+    fn test_missing_dependency_and_config_are_distinct_findings() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = Router::new()
+            .route("status", status)
+            .describe("status", "Show status")
+            .requires_tool("sh", "ships with every Unix")
+            .requires_tool("sen-no-such-tool", "cargo install sen-no-such-tool")
+            .with_config_file(dir.path().join("missing.toml"))
+            .with_state(());
+
+        let report = router.doctor();
+        assert!(!report.is_healthy());
+
+        let missing: Vec<_> = report.of_kind(FindingKind::MissingDependency).collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].subject, "sen-no-such-tool");
+        assert!(missing[0]
+            .message
+            .contains("cargo install sen-no-such-tool"));
+
+        let config: Vec<_> = report.of_kind(FindingKind::MissingConfig).collect();
+        assert_eq!(config.len(), 1);
+        assert!(config[0].subject.ends_with("missing.toml"));
+        assert_eq!(report.findings.len(), 2);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_unparsable_config_is_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "name = ").unwrap();
+        let router = Router::new()
+            .route("status", status)
+            .describe("status", "Show status")
+            .with_config_file(&path)
+            .with_state(());
+
+        let report = router.doctor();
+        assert_eq!(report.of_kind(FindingKind::InvalidConfig).count(), 1);
+
+        std::fs::write(&path, "name = \"app\"").unwrap();
+        assert_eq!(router.doctor(), DoctorReport::default());
+    }
+
+    #[test]
+    fn test_route_collisions_and_missing_descriptions() {
+        let router = Router::new()
+            .route("db:create", status)
+            .route("db:Create", status)
+            .route("version", status)
+            .describe("db:create", "Create a database")
+            .describe("db:Create", "Create a database")
+            .with_state(());
+
+        let report = router.doctor();
+        // Warnings only
+        assert!(report.is_healthy());
+        let collisions: Vec<_> = report
+            .of_kind(FindingKind::RouteCollision)
+            .map(|f| f.subject.as_str())
+            .collect();
+        // Keys are compared in sorted order, so the lowercase one is reported
+        assert_eq!(collisions, ["db create", "version"]);
+        let undocumented: Vec<_> = report
+            .of_kind(FindingKind::MissingDescription)
+            .map(|f| f.subject.as_str())
+            .collect();
+        assert_eq!(undocumented, ["version"]);
+    }
+
+    #[tokio::test]
+    async fn test_builtin_doctor_command() {
+        let router = Router::new()
+            .route("status", status)
+            .requires_tool("sen-no-such-tool", "see the docs")
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        let response = run(&["doctor"]).await;
+        assert_eq!(response.exit_code, 1);
+        match response.output {
+            Output::Text(text) => {
+                assert!(text.contains("error: sen-no-such-tool: not found on PATH"));
+                assert!(text.contains("warning: status: no description"));
+                assert!(text.ends_with("1 error, 1 warning"));
+            }
+            other => panic!("Expected text output, got {:?}", other),
+        }
+
+        let response = run(&["doctor", "--json"]).await;
+        let json: serde_json::Value = match response.output {
+            Output::Json(json) => serde_json::from_str(&json).unwrap(),
+            other => panic!("Expected JSON output, got {:?}", other),
+        };
+        assert_eq!(json["healthy"], false);
+        assert_eq!(json["findings"][0]["kind"], "missing_dependency");
+        assert_eq!(json["findings"][1]["kind"], "missing_description");
+    }
+}
@@ -0,0 +1,94 @@
+//! Framework-level `--dry-run` for destructive commands.
+//!
+//! When a router is built with `Router::with_dry_run_flag()`, `--dry-run` is
+//! recognized for commands that can honor it:
+//!
+//! - The router strips it from the arguments (like `--agent-mode`), so
+//!   handlers parse their args as usual
+//! - Handlers that take a `DryRun` extractor branch on it
+//! - Handlers returning `FileOutput` write nothing
+//! - Plugin-backed commands get `--dry-run` forwarded in their args if the
+//!   plugin declares a `--dry-run` option
+//! - The response is marked `changed: false`
+//! - Any other command fails with `UserError::InvalidArgument` instead of
+//!   running for real
+//!
+//! Outside of a dry run, `DryRun` is `DryRun(false)`.
+
+/// The global flag recognized by `Router::with_dry_run_flag`.
+pub const DRY_RUN_FLAG: &str = "--dry-run";
+
+// ============================================================================
+// DryRun Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static DRY_RUN: bool;
+}
+
+/// Dry-run extractor for handler functions.
+///
+/// Holds `true` when the command was invoked with `--dry-run` on a router
+/// built with `Router::with_dry_run_flag()`.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, CliResult, DryRun, State};
+///
+/// async fn drop_table(
+///     state: State<AppState>,
+///     DryRun(dry_run): DryRun,
+///     Args(args): Args<DropArgs>,
+/// ) -> CliResult<String> {
+///     if dry_run {
+///         return Ok(format!("Would drop {}", args.table));
+///     }
+///     state.read().await.db.drop_table(&args.table).await?;
+///     Ok(format!("Dropped {}", args.table))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DryRun(pub bool);
+
+impl DryRun {
+    /// Get the dry-run flag for the currently executing command.
+    pub fn current() -> Self {
+        DryRun(DRY_RUN.try_with(|dry_run| *dry_run).unwrap_or(false))
+    }
+}
+
+impl crate::FromContext for DryRun {
+    const HANDLES_DRY_RUN: bool = true;
+
+    fn from_context() -> Result<Self, crate::CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with `dry_run` available to `DryRun` extractors.
+pub(crate) async fn scope<F: std::future::Future>(dry_run: bool, fut: F) -> F::Output {
+    DRY_RUN.scope(dry_run, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry_run_outside_router_is_false() {
+        assert_eq!(DryRun::current(), DryRun(false));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_observes_scope() {
+        scope(true, async {
+            assert_eq!(DryRun::current(), DryRun(true));
+        })
+        .await;
+    }
+}
@@ -0,0 +1,151 @@
+//! Writing a command's result to a file the user named.
+//!
+//! Commands like `export --output report.json` return a [`FileOutput`]
+//! instead of opening the file themselves. The framework then:
+//!
+//! - Creates missing parent directories and writes the contents
+//! - Reports `Wrote N bytes to <path>` in text mode
+//! - Adds `{ written_path, bytes }` as `data` in agent mode
+//! - Writes nothing under `--dry-run` and reports `Would write ...` instead
+//!
+//! Write failures are `SystemError::Io` errors (exit code 101).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use sen::{Args, CliResult, FileOutput, State};
+//!
+//! async fn export(state: State<AppState>, Args(args): Args<ExportArgs>) -> CliResult<FileOutput> {
+//!     let rows = state.read().await.db.dump().await?;
+//!     Ok(FileOutput {
+//!         path: args.output,
+//!         contents: serde_json::to_vec_pretty(&rows)?,
+//!     })
+//! }
+//! ```
+
+use crate::{CliError, DryRun, IntoResponse, Response, SystemError};
+use std::path::PathBuf;
+
+/// File contents for the framework to write to `path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileOutput {
+    /// Destination; relative paths are resolved against the working directory
+    pub path: PathBuf,
+
+    /// Bytes to write, replacing any existing file
+    pub contents: Vec<u8>,
+}
+
+impl FileOutput {
+    /// Create a file output.
+    pub fn new(path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+
+    fn write(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, &self.contents)
+    }
+}
+
+impl IntoResponse for FileOutput {
+    const HANDLES_DRY_RUN: bool = true;
+
+    fn into_response(self) -> Response {
+        self.into_result()
+            .unwrap_or_else(|e| Err::<Response, _>(e).into_response())
+    }
+
+    fn into_result(self) -> Result<Response, CliError> {
+        let DryRun(dry_run) = DryRun::current();
+        let bytes = self.contents.len();
+        let verb = if dry_run {
+            "Would write"
+        } else {
+            self.write().map_err(SystemError::Io)?;
+            "Wrote"
+        };
+
+        Ok(Response::text(format!(
+            "{} {} bytes to {}",
+            verb,
+            bytes,
+            self.path.display()
+        ))
+        .with_data(serde_json::json!({
+            "written_path": self.path.display().to_string(),
+            "bytes": bytes,
+        })))
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Output;
+
+    fn text(response: &Response) -> &str {
+        match &response.output {
+            Output::Text(text) => text,
+            other => panic!("Expected text output, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_file_output_creates_parents_and_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports/2024/report.json");
+
+        let response = FileOutput::new(&path, r#"{"rows":3}"#).into_response();
+
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(std::fs::read(&path).unwrap(), br#"{"rows":3}"#);
+        assert_eq!(
+            text(&response),
+            format!("Wrote 10 bytes to {}", path.display())
+        );
+        assert_eq!(
+            response.data,
+            Some(serde_json::json!({
+                "written_path": path.display().to_string(),
+                "bytes": 10,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_file_output_write_failure_is_io_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("file");
+        std::fs::write(&blocker, "").unwrap();
+
+        let output = FileOutput::new(blocker.join("report.json"), "data");
+        let result = output.clone().into_result();
+        assert!(matches!(result, Err(CliError::System(SystemError::Io(_)))));
+        assert_eq!(output.into_response().exit_code, 101);
+    }
+
+    #[tokio::test]
+    async fn test_file_output_skips_write_in_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+
+        let response = crate::dry_run::scope(true, async {
+            FileOutput::new(&path, "data").into_response()
+        })
+        .await;
+
+        assert!(!path.exists());
+        assert!(text(&response).starts_with("Would write 4 bytes to "));
+    }
+}
@@ -0,0 +1,122 @@
+//! Opt-in router flags taken off the arguments before routing.
+//!
+//! `--quiet`, `--dry-run`, `--template`, and `--profile` are enabled with
+//! `Router::with_quiet_flag()` and friends. The router strips them so
+//! handlers parse their own arguments as usual, then applies them to the
+//! command's result.
+
+use crate::{dry_run, profile, template, CliError, FromGlobalArgs, Output, Response, Router, Tier};
+
+/// The opt-in flags found on one command line.
+pub(crate) struct GlobalFlags {
+    pub(crate) quiet: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) template: Option<template::Template>,
+    /// Resolved profile name ([`profile::DEFAULT_PROFILE`] when disabled)
+    pub(crate) profile: String,
+}
+
+impl Router<()> {
+    /// Strip the enabled flags from `args`.
+    pub(crate) fn take_global_flags(
+        &self,
+        args: Vec<String>,
+    ) -> Result<(GlobalFlags, Vec<String>), CliError> {
+        // Detect and strip --quiet/-q if the quiet flag is enabled
+        let (quiet, args) = if self.config.quiet_flag_enabled {
+            take_switch(args, |arg| arg == "--quiet" || arg == "-q")
+        } else {
+            (false, args)
+        };
+
+        // Detect and strip --dry-run if the dry-run flag is enabled
+        let (dry_run, args) = if self.config.dry_run_flag_enabled {
+            take_switch(args, |arg| arg == dry_run::DRY_RUN_FLAG)
+        } else {
+            (false, args)
+        };
+
+        // Detect and strip --template <TEMPLATE> if the template flag is enabled
+        let (template, args) = if self.config.template_flag_enabled {
+            template::take_flag(args)?
+        } else {
+            (None, args)
+        };
+
+        // Detect and strip --profile <NAME> if the profile flag is enabled
+        let (profile, args) = match &self.config.profile_env {
+            Some(env_var) => {
+                let (flag, args) = profile::ProfileFlag::from_global_args(&args)?;
+                (flag.resolve(env_var), args)
+            }
+            None => (profile::DEFAULT_PROFILE.to_string(), args),
+        };
+
+        let flags = GlobalFlags {
+            quiet,
+            dry_run,
+            template,
+            profile,
+        };
+        Ok((flags, args))
+    }
+
+    /// Apply `flags` to the result of the command at `route_key`.
+    ///
+    /// Renders `--template` (except in agent mode), silences successful
+    /// output for `--quiet`, and fills in `changed`: `false` for dry runs
+    /// and failures, else the tier's default unless the handler set it.
+    pub(crate) fn apply_global_flags(
+        &self,
+        flags: &GlobalFlags,
+        route_key: &str,
+        agent_mode: bool,
+        mut result: Result<Response, CliError>,
+    ) -> Result<Response, CliError> {
+        if let (Some(template), false) = (&flags.template, agent_mode) {
+            result = result.and_then(|response| template.apply(response));
+        }
+        if flags.quiet {
+            // Command help is still an `Err` here, so it stays visible
+            result = result.map(|mut response| {
+                if response.exit_code == 0 {
+                    response.output = Output::Silent;
+                }
+                response
+            });
+        }
+        // Handlers only get here with `dry_run` set if they saw it. Without a
+        // tier there is no default; agent JSON then reports `false`
+        let tier_default = self.route_tier(route_key).map(|tier| tier != Tier::Safe);
+        result.map(|mut response| {
+            if flags.dry_run || response.exit_code != 0 {
+                response.changed = Some(false);
+            } else if let Some(changed) = tier_default {
+                response.changed.get_or_insert(changed);
+            }
+            response
+        })
+    }
+}
+
+/// Remove every argument matching `is_flag`, reporting whether any did.
+///
+/// Scanning stops at `--`; it and everything after it are kept verbatim.
+fn take_switch(args: Vec<String>, is_flag: impl Fn(&str) -> bool) -> (bool, Vec<String>) {
+    let mut found = false;
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            remaining.push(arg);
+            remaining.extend(iter);
+            break;
+        }
+        if is_flag(&arg) {
+            found = true;
+        } else {
+            remaining.push(arg);
+        }
+    }
+    (found, remaining)
+}
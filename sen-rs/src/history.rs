@@ -0,0 +1,429 @@
+//! Trail of critical commands (opt-in).
+//!
+//! `Router::with_history(path)` appends one JSON line each time a command
+//! with the `critical` tier completes and changes something:
+//!
+//! ```text
+//! {"timestamp_ms":1718000000000,"command":"db drop","args":["users","--token","***"],"undo_hint":"myctl db restore users"}
+//! ```
+//!
+//! - Unlike the usage log this keeps arguments, with the values of secret
+//!   looking flags (`--token`, `--password`, `--api-key`, ...) replaced by `***`
+//! - Short forms (`-p`) are redacted when the route's args schema maps them
+//!   to a secret long option; without a schema they are kept as given, so
+//!   redaction is best-effort
+//! - The file is created readable by its owner only (mode 0600 on Unix)
+//! - Handlers may add `undo_hint` with `Response::with_undo_hint`
+//! - Failures, dry runs, and responses the handler marks `changed: false`
+//!   are not recorded
+//! - The built-in `history` command lists recent entries (`history --json`)
+//! - Writing never affects the command: errors are ignored
+
+use crate::{CliError, Output, Response, RouteMetadata, Router, Tier};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries shown by the built-in `history` command.
+const RECENT_ENTRIES: usize = 20;
+
+/// Replacement for redacted values.
+const REDACTED: &str = "***";
+
+/// Flag name parts that mark the flag's value as a secret.
+const SECRET_WORDS: &[&str] = &[
+    "apikey",
+    "auth",
+    "credential",
+    "credentials",
+    "key",
+    "passwd",
+    "password",
+    "secret",
+    "token",
+];
+
+/// One line of the history file.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Entry {
+    pub(crate) timestamp_ms: u128,
+    pub(crate) command: String,
+    pub(crate) args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) undo_hint: Option<String>,
+}
+
+impl Router<()> {
+    /// Record a command's result in the history, if one is kept.
+    ///
+    /// Only a critical command that ran for real (not a dry run it saw) and
+    /// did not report `changed: false` itself is recorded.
+    pub(crate) fn record_history(
+        &self,
+        route_key: &str,
+        args: &[String],
+        dry_run: bool,
+        result: &Result<Response, CliError>,
+    ) {
+        let (Some(path), Ok(response)) = (&self.config.history, result) else {
+            return;
+        };
+        if dry_run
+            || response.exit_code != 0
+            || response.changed == Some(false)
+            || self.route_tier(route_key) != Some(Tier::Critical)
+        {
+            return;
+        }
+        let schema = self
+            .route_metadata
+            .get(route_key)
+            .and_then(RouteMetadata::get_args_schema);
+        append(
+            path,
+            route_key,
+            args,
+            &secret_shorts(schema),
+            response.undo_hint.as_deref(),
+        );
+    }
+}
+
+/// Append an entry for `route_key` to the history at `path`, ignoring failures.
+///
+/// `secret_shorts` are short flags (`-p`) whose values are redacted too.
+pub(crate) fn append(
+    path: &Path,
+    route_key: &str,
+    args: &[String],
+    secret_shorts: &[String],
+    undo_hint: Option<&str>,
+) {
+    let entry = Entry {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+        command: route_key.replace(':', " "),
+        args: redact_args(args, secret_shorts),
+        undo_hint: undo_hint.map(String::from),
+    };
+    let _ = write_line(path, &entry);
+}
+
+fn write_line(path: &Path, entry: &Entry) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(&line)
+}
+
+/// Short forms (`-p`) of the secret options in a route's args schema.
+pub(crate) fn secret_shorts(schema: Option<&serde_json::Value>) -> Vec<String> {
+    schema
+        .and_then(|schema| schema["options"].as_array())
+        .into_iter()
+        .flatten()
+        .filter(|option| option["name"].as_str().is_some_and(is_secret_name))
+        .filter_map(|option| option["short"].as_str().map(String::from))
+        .collect()
+}
+
+/// Up to `limit` most recent entries, newest first. A missing file or
+/// unreadable lines yield fewer entries.
+pub(crate) fn recent(path: &Path, limit: usize) -> Vec<Entry> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+/// Response for the built-in `history` command.
+pub(crate) fn response(path: &Path, json: bool) -> Response {
+    let entries = recent(path, RECENT_ENTRIES);
+    let data = serde_json::to_value(&entries).unwrap_or_default();
+    let mut response = if json {
+        let mut response = Response::silent();
+        response.output =
+            Output::Json(serde_json::to_string_pretty(&data).unwrap_or_else(|_| "[]".to_string()));
+        response
+    } else if entries.is_empty() {
+        Response::text("No critical commands recorded")
+    } else {
+        let lines: Vec<String> = entries.iter().map(format_entry).collect();
+        Response::text(lines.join("\n"))
+    };
+    response.data = Some(data);
+    response.changed(false)
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let mut line = format!("{}  {}", format_utc(entry.timestamp_ms), entry.command);
+    for arg in &entry.args {
+        line.push(' ');
+        line.push_str(arg);
+    }
+    if let Some(hint) = &entry.undo_hint {
+        line.push_str(&format!("\n    undo: {}", hint));
+    }
+    line
+}
+
+/// `args` with the values of secret-looking flags replaced by `***`.
+///
+/// Covers `--token value`, `--token=value`, and `TOKEN=value` arguments, and
+/// `-p value`, `-p=value`, and `-pvalue` for the short flags in
+/// `secret_shorts`.
+pub(crate) fn redact_args(args: &[String], secret_shorts: &[String]) -> Vec<String> {
+    let is_secret = |name: &str| is_secret_name(name) || secret_shorts.iter().any(|s| s == name);
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut redact_next = false;
+    for arg in args {
+        if std::mem::take(&mut redact_next) && !arg.starts_with('-') {
+            redacted.push(REDACTED.to_string());
+            continue;
+        }
+        let attached_short = secret_shorts
+            .iter()
+            .find(|short| arg.len() > short.len() && arg.starts_with(short.as_str()));
+        match (arg.split_once('='), attached_short) {
+            (Some((name, _)), _) if is_secret(name) => {
+                redacted.push(format!("{}={}", name, REDACTED));
+            }
+            // `-pvalue`
+            (_, Some(short)) if !arg.starts_with("--") => {
+                redacted.push(format!("{}{}", short, REDACTED));
+            }
+            (Some(_), _) => redacted.push(arg.clone()),
+            (None, _) => {
+                redact_next = arg.starts_with('-') && is_secret(arg);
+                redacted.push(arg.clone());
+            }
+        }
+    }
+    redacted
+}
+
+/// Whether a flag or variable name (`--api-key`, `DB_PASSWORD`) names a secret.
+fn is_secret_name(name: &str) -> bool {
+    let name = name.trim_start_matches('-').to_ascii_lowercase();
+    name.split(['-', '_', '.'])
+        .any(|part| SECRET_WORDS.contains(&part))
+        || SECRET_WORDS
+            .iter()
+            .any(|word| word.len() > 4 && name.contains(word))
+}
+
+/// `YYYY-MM-DD HH:MM:SS` (UTC) for a Unix timestamp in milliseconds.
+fn format_utc(timestamp_ms: u128) -> String {
+    let secs = (timestamp_ms / 1000) as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CliResult, DryRun, HandlerMetadata, HandlerWithMeta, Router, State, Tier};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn tiered<H: crate::Handler<T, ()>, T>(handler: H, tier: Tier) -> HandlerWithMeta<H, T, ()> {
+        HandlerWithMeta::new(
+            handler,
+            HandlerMetadata {
+                tier: Some(tier),
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn drop_db(_state: State<()>) -> CliResult<Response> {
+        Ok(Response::text("Dropped").with_undo_hint("myctl db restore users"))
+    }
+
+    async fn list(_state: State<()>) -> CliResult<String> {
+        Ok("users".to_string())
+    }
+
+    #[test]
+    fn test_redact_args() {
+        let redacted = redact_args(
+            &args(&[
+                "users",
+                "--token",
+                "abc",
+                "--api-key=xyz",
+                "DB_PASSWORD=hunter2",
+                "--force",
+                "--password",
+                "--verbose",
+                "--keys-only",
+            ]),
+            &[],
+        );
+        assert_eq!(
+            redacted,
+            [
+                "users",
+                "--token",
+                "***",
+                "--api-key=***",
+                "DB_PASSWORD=***",
+                "--force",
+                "--password",
+                "--verbose",
+                "--keys-only",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact_short_flags_of_secret_options() {
+        let schema = serde_json::json!({
+            "options": [
+                { "name": "--password", "short": "-p" },
+                { "name": "--port", "short": "-P" },
+            ]
+        });
+        let shorts = secret_shorts(Some(&schema));
+        assert_eq!(shorts, ["-p"]);
+
+        let redacted = redact_args(
+            &args(&["-p", "hunter2", "-phunter2", "-p=hunter2", "-P", "5432"]),
+            &shorts,
+        );
+        assert_eq!(redacted, ["-p", "***", "-p***", "-p=***", "-P", "5432"]);
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00");
+        assert_eq!(format_utc(1_718_000_000_000), "2024-06-10 06:13:20");
+    }
+
+    #[tokio::test]
+    async fn test_critical_command_writes_redacted_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let router = Router::new()
+            .route("db:drop", tiered(drop_db, Tier::Critical))
+            .route("db:list", tiered(list, Tier::Safe))
+            .with_history(&path)
+            .with_state(());
+
+        let response = router
+            .execute_with(&args(&["test", "db", "drop", "users", "--token", "s3cret"]))
+            .await;
+        assert_eq!(response.exit_code, 0);
+        router.execute_with(&args(&["test", "db", "list"])).await;
+
+        let entries = recent(&path, 10);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "db drop");
+        assert_eq!(entries[0].args, ["users", "--token", "***"]);
+        assert_eq!(
+            entries[0].undo_hint.as_deref(),
+            Some("myctl db restore users")
+        );
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("s3cret"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // The built-in lists it
+        let response = router.execute_with(&args(&["test", "history"])).await;
+        match response.output {
+            Output::Text(text) => {
+                assert!(text.contains("db drop users --token ***"));
+                assert!(text.contains("undo: myctl db restore users"));
+            }
+            other => panic!("Expected text output, got {:?}", other),
+        }
+        let response = router
+            .execute_with(&args(&["test", "history", "--json"]))
+            .await;
+        let json: serde_json::Value = match response.output {
+            Output::Json(json) => serde_json::from_str(&json).unwrap(),
+            other => panic!("Expected JSON output, got {:?}", other),
+        };
+        assert_eq!(json[0]["command"], "db drop");
+    }
+
+    #[tokio::test]
+    async fn test_safe_command_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let router = Router::new()
+            .route("db:list", tiered(list, Tier::Safe))
+            .route("db:prune", list)
+            .with_history(&path)
+            .with_state(());
+
+        router.execute_with(&args(&["test", "db", "list"])).await;
+        router.execute_with(&args(&["test", "db", "prune"])).await;
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        async fn drop_table(_state: State<()>, DryRun(dry_run): DryRun) -> CliResult<String> {
+            Ok(if dry_run { "Would drop" } else { "Dropped" }.to_string())
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let router = Router::new()
+            .route("db:drop", tiered(drop_table, Tier::Critical))
+            .with_dry_run_flag()
+            .with_history(&path)
+            .with_state(());
+
+        let response = router
+            .execute_with(&args(&["test", "db", "drop", "--dry-run"]))
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(!path.exists());
+
+        router.execute_with(&args(&["test", "db", "drop"])).await;
+        assert_eq!(recent(&path, 10).len(), 1);
+    }
+}
@@ -63,11 +63,74 @@ use std::sync::Arc;
 // Re-export macros
 pub use sen_rs_macros::handler;
 pub use sen_rs_macros::sen;
+/// Derive `execute()` for a command enum.
+///
+/// Misuse is reported at the offending tokens, e.g. a variant without a
+/// handler:
+///
+/// ```ignore
+/// use sen::SenRouter;
+///
+/// #[derive(Clone)]
+/// struct App;
+///
+/// #[derive(SenRouter)]
+/// #[sen(state = App)]
+/// enum Commands {
+///     Status,
+/// }
+/// ```
+///
+/// or a variant with named fields:
+///
+/// ```ignore
+/// use sen::SenRouter;
+///
+/// #[derive(Clone)]
+/// struct App;
+///
+/// async fn build(_state: sen::State<App>, _args: String) -> String {
+///     String::new()
+/// }
+///
+/// #[derive(SenRouter)]
+/// #[sen(state = App)]
+/// enum Commands {
+///     #[sen(handler = build)]
+///     Build { target: String },
+/// }
+/// ```
+///
+/// The exact diagnostics are checked by the UI tests in
+/// `sen-rs-macros/tests/ui`.
 pub use sen_rs_macros::SenRouter;
 
 // Optional modules
 pub mod build_info;
+mod builtins;
+pub mod cancel;
+mod catch_panic;
+pub mod complete;
+pub mod doctor;
+pub mod dry_run;
+pub mod file_output;
+mod global_flags;
+mod history;
+pub mod multi;
+pub mod output_mode;
+pub mod paths;
+pub mod process;
+pub mod profile;
+pub mod progress;
+pub mod reload;
+pub mod scoped;
+pub mod stream;
+mod template;
 pub mod tracing_support;
+pub mod units;
+mod usage_log;
+pub mod validate;
+pub mod warnings;
 
 #[cfg(feature = "sensors")]
 pub mod sensors;
@@ -75,6 +138,12 @@ pub mod sensors;
 #[cfg(feature = "mcp")]
 pub mod mcp;
 
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 // Re-export tracing itself (required for #[instrument] macro)
 #[cfg(feature = "tracing")]
 pub use tracing_support::tracing;
@@ -87,7 +156,23 @@ pub use tracing_support::{
 };
 
 #[cfg(feature = "build-info")]
-pub use build_info::{version_info, version_short};
+pub use build_info::{version_info, version_info_json, version_short};
+
+pub use cancel::Cancel;
+pub use complete::{Completer, CompletionFuture};
+pub use doctor::DoctorReport;
+pub use dry_run::DryRun;
+pub use file_output::FileOutput;
+pub use multi::{FailurePolicy, MultiResult, TargetOutcome};
+pub use output_mode::OutputMode;
+pub use profile::Profile;
+pub use progress::{Progress, ProgressEvent};
+pub use reload::ReloadableRouter;
+pub use scoped::Scoped;
+pub use stream::{Emitter, SharedWriter};
+pub use units::{parse_duration, parse_size};
+pub use validate::{Validate, Validated};
+pub use warnings::Warnings;
 
 #[cfg(feature = "sensors")]
 pub use sensors::{GitSensor, SensorData, Sensors};
@@ -267,7 +352,7 @@ pub enum CliError {
     #[error(transparent)]
     User(#[from] UserError),
 
-    /// System-level failures (exit code 101).
+    /// System-level failures (exit code 101, or 124 for timeouts).
     ///
     /// These indicate bugs or environmental issues that users can't fix.
     #[error(transparent)]
@@ -279,13 +364,26 @@ impl CliError {
     pub fn exit_code(&self) -> i32 {
         match self {
             CliError::User(user_err) => match user_err {
-                UserError::Help(_) => 0, // Help is a successful exit
+                UserError::Help(_) => 0,     // Help is a successful exit
+                UserError::Cancelled => 130, // 128 + SIGINT
                 _ => 1,
             },
+            CliError::System(SystemError::Timeout { .. }) => 124, // same as timeout(1)
             CliError::System(_) => 101,
         }
     }
 
+    /// Stable machine-readable code for this error (e.g. `"unknown_command"`).
+    ///
+    /// Used in agent-mode JSON and MCP responses so programmatic clients can
+    /// branch on the failure without parsing messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CliError::User(user_err) => user_err.kind(),
+            CliError::System(sys_err) => sys_err.kind(),
+        }
+    }
+
     /// Convenience constructor for user errors.
     pub fn user(message: impl Into<String>) -> Self {
         CliError::User(UserError::Generic(message.into()))
@@ -297,6 +395,30 @@ impl CliError {
     }
 }
 
+/// I/O failures are system errors (exit code 101), so `?` works in handlers.
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        CliError::System(SystemError::Io(err))
+    }
+}
+
+/// JSON that fails to parse or serialize is reported as a config parse error
+/// (exit code 101).
+impl From<serde_json::Error> for CliError {
+    fn from(err: serde_json::Error) -> Self {
+        CliError::System(SystemError::ConfigParse(err.to_string()))
+    }
+}
+
+/// YAML that fails to parse or serialize is reported as a config parse error
+/// (exit code 101).
+#[cfg(feature = "config")]
+impl From<serde_yaml::Error> for CliError {
+    fn from(err: serde_yaml::Error) -> Self {
+        CliError::System(SystemError::ConfigParse(err.to_string()))
+    }
+}
+
 /// User-fixable errors (exit code 1).
 #[derive(Debug, thiserror::Error)]
 pub enum UserError {
@@ -324,6 +446,43 @@ pub enum UserError {
     /// Prerequisite not met.
     #[error("Error: Prerequisite not met: {check}\n\nHint: {fix_hint}")]
     PrerequisiteNotMet { check: String, fix_hint: String },
+
+    /// Command was interrupted (exit code 130).
+    /// Returned by routers built with `with_cancellation()` on Ctrl-C.
+    #[error("Error: Interrupted")]
+    Cancelled,
+
+    /// No route matches the command.
+    /// `suggestions` holds close matches (e.g. `"db create"`), best first.
+    #[error("Error: Unknown command: {command}{}", did_you_mean_hint(.suggestions))]
+    UnknownCommand {
+        command: String,
+        suggestions: Vec<String>,
+    },
+}
+
+impl UserError {
+    /// Stable machine-readable code for this error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UserError::Generic(_) => "user_error",
+            UserError::Help(_) => "help",
+            UserError::InvalidArgument { .. } => "invalid_argument",
+            UserError::MissingDependency { .. } => "missing_dependency",
+            UserError::ValidationFailed { .. } => "validation_failed",
+            UserError::PrerequisiteNotMet { .. } => "prerequisite_not_met",
+            UserError::Cancelled => "cancelled",
+            UserError::UnknownCommand { .. } => "unknown_command",
+        }
+    }
+}
+
+fn did_you_mean_hint(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!("\n\nDid you mean: {}?", suggestions.join(", "))
+    }
 }
 
 /// System-level failures (exit code 101).
@@ -340,6 +499,26 @@ pub enum SystemError {
     /// Configuration parsing error.
     #[error("Internal Error: Config parse failed\n\n{0}\n\nThis is likely a bug.")]
     ConfigParse(String),
+
+    /// Command exceeded its time budget (exit code 124).
+    /// Returned by the router when a handler timeout expires.
+    #[error("Error: Command '{command}' timed out after {budget:?}")]
+    Timeout {
+        command: String,
+        budget: std::time::Duration,
+    },
+}
+
+impl SystemError {
+    /// Stable machine-readable code for this error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SystemError::Internal(_) => "internal",
+            SystemError::Io(_) => "io",
+            SystemError::ConfigParse(_) => "config_parse",
+            SystemError::Timeout { .. } => "timeout",
+        }
+    }
 }
 
 // ============================================================================
@@ -362,6 +541,49 @@ pub struct Response {
     /// Optional metadata for agent mode (tier, tags, sensors).
     #[cfg(feature = "sensors")]
     pub metadata: Option<ResponseMetadata>,
+
+    /// Structured error details, set when the response comes from a `CliError`.
+    pub error: Option<ErrorInfo>,
+
+    /// Whether the command changed anything (see [`Response::changed`]).
+    ///
+    /// `None` means the handler did not say; the router then fills in the
+    /// default for the command's tier, if it has one.
+    pub changed: Option<bool>,
+
+    /// Structured result for agent JSON (see [`Response::with_data`]).
+    pub data: Option<serde_json::Value>,
+
+    /// Non-fatal problems to report alongside the output; set with
+    /// [`Response::warn`], read with [`Response::warnings`].
+    warnings: Vec<String>,
+
+    /// How to reverse what the command did (see [`Response::with_undo_hint`]).
+    pub undo_hint: Option<String>,
+}
+
+/// Machine-readable error details attached to error responses.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ErrorInfo {
+    /// Stable error code (see [`CliError::kind`])
+    pub kind: &'static str,
+
+    /// Close matches for an unknown command, best first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub did_you_mean: Vec<String>,
+}
+
+impl From<&CliError> for ErrorInfo {
+    fn from(error: &CliError) -> Self {
+        let did_you_mean = match error {
+            CliError::User(UserError::UnknownCommand { suggestions, .. }) => suggestions.clone(),
+            _ => Vec::new(),
+        };
+        Self {
+            kind: error.kind(),
+            did_you_mean,
+        }
+    }
 }
 
 /// Metadata attached to Response for AI agents.
@@ -387,6 +609,11 @@ impl Response {
             agent_mode: false,
             #[cfg(feature = "sensors")]
             metadata: None,
+            error: None,
+            changed: None,
+            data: None,
+            warnings: Vec::new(),
+            undo_hint: None,
         }
     }
 
@@ -398,6 +625,11 @@ impl Response {
             agent_mode: false,
             #[cfg(feature = "sensors")]
             metadata: None,
+            error: None,
+            changed: None,
+            data: None,
+            warnings: Vec::new(),
+            undo_hint: None,
         }
     }
 
@@ -409,7 +641,116 @@ impl Response {
             agent_mode: false,
             #[cfg(feature = "sensors")]
             metadata: None,
+            error: None,
+            changed: None,
+            data: None,
+            warnings: Vec::new(),
+            undo_hint: None,
+        }
+    }
+
+    /// Print this response and exit the process with its exit code.
+    ///
+    /// Agent-mode responses are printed as JSON (with the `sensors` feature).
+    /// Otherwise output goes to stdout on success and stderr on failure;
+    /// `Output::Binary` is written as raw bytes without a trailing newline.
+    /// Warnings are printed to stderr before the output.
+    pub fn exit(self) -> ! {
+        #[cfg(feature = "sensors")]
+        if self.agent_mode {
+            println!("{}", self.to_agent_json());
+            std::process::exit(self.exit_code);
+        }
+
+        for warning in &self.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        if let Output::Binary(bytes) = &self.output {
+            // Raw bytes, no trailing newline, so `cmd > file.tar` is exact
+            let result = if self.exit_code == 0 {
+                write_raw(&mut std::io::stdout().lock(), bytes)
+            } else {
+                write_raw(&mut std::io::stderr().lock(), bytes)
+            };
+            if let Err(e) = result {
+                eprintln!("Error: Failed to write output: {}", e);
+                std::process::exit(101);
+            }
+        } else if !self.output.is_empty() {
+            if self.exit_code == 0 {
+                println!("{}", self.output);
+            } else {
+                eprintln!("{}", self.output);
+            }
         }
+        std::process::exit(self.exit_code)
+    }
+
+    /// Mark whether the command changed anything.
+    ///
+    /// Surfaced as `changed` in agent JSON, so an agent can skip follow-ups
+    /// after a no-op (like Terraform's "No changes").
+    ///
+    /// ```ignore
+    /// async fn apply(state: State<App>) -> CliResult<Response> {
+    ///     let updated = state.read().await.sync()?;
+    ///     Ok(Response::text(format!("{} updated", updated)).changed(updated > 0))
+    /// }
+    /// ```
+    ///
+    /// When unset, commands with the `standard` or `critical` tier report
+    /// `true` and all others `false`. A failed command (non-zero exit code)
+    /// always reports `false`.
+    pub fn changed(mut self, changed: bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+
+    /// Attach a structured result, emitted as `data` in agent JSON.
+    ///
+    /// `output` stays the human-readable rendering; agents get the same
+    /// result without parsing it.
+    ///
+    /// ```ignore
+    /// Response::text("3 databases").with_data(json!(["users", "orders", "logs"]))
+    /// ```
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Say how to reverse what the command did.
+    ///
+    /// Recorded with the command in the history of critical commands (see
+    /// [`Router::with_history`]) and emitted as `undo_hint` in agent JSON.
+    ///
+    /// ```ignore
+    /// Response::text("Dropped users").with_undo_hint("myctl db restore users --from backup-42")
+    /// ```
+    pub fn with_undo_hint(mut self, hint: impl Into<String>) -> Self {
+        self.undo_hint = Some(hint.into());
+        self
+    }
+
+    /// Add a warning: something the user should know about that did not
+    /// stop the command.
+    ///
+    /// Printed to stderr as `Warning: ...` interactively and emitted as
+    /// `warnings` in agent JSON; the exit code is unaffected. Handlers that
+    /// don't build a `Response` can use the [`Warnings`] extractor instead.
+    ///
+    /// ```ignore
+    /// Response::text("Deployed").warn("No --region given, using us-east-1")
+    /// ```
+    pub fn warn(mut self, message: impl Into<String>) -> Self {
+        self.warnings.push(message.into());
+        self
+    }
+
+    /// Warnings added with [`Response::warn`] or the [`Warnings`] extractor.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
 
     /// Attach metadata to this response (for agent mode).
@@ -424,22 +765,60 @@ impl Response {
     /// Returns a JSON object with:
     /// - `result`: "success" or "error"
     /// - `exit_code`: numeric exit code
-    /// - `output`: command output
+    /// - `output`: command output (base64 for `Output::Binary`)
+    /// - `output_encoding`: `"base64"` when `output` is binary
+    /// - `changed`: whether the command changed anything (see
+    ///   [`Response::changed`]; `true` only for a successful command that
+    ///   said so or has a `standard`/`critical` tier)
     /// - `tier`: safety tier (if available)
     /// - `tags`: command tags (if available)
     /// - `sensors`: environment data (if available)
+    /// - `data`: structured result (see [`Response::with_data`])
+    /// - `warnings`: non-fatal problems (see [`Response::warn`]; omitted when
+    ///   there are none)
+    /// - `undo_hint`: how to reverse the command (see
+    ///   [`Response::with_undo_hint`]; if set)
+    /// - `error`: `{ kind, did_you_mean }` for error responses (if available)
+    ///
+    /// `output` is never truncated; see [`Response::to_agent_json_with_limit`].
     #[cfg(feature = "sensors")]
     pub fn to_agent_json(&self) -> String {
+        self.to_agent_json_with_limit(None)
+    }
+
+    /// Like [`Response::to_agent_json`], but with `output` cut to `limit`
+    /// bytes (on a UTF-8 character boundary; binary output is cut before
+    /// encoding).
+    ///
+    /// A cut output adds `truncated: true` and `total_bytes`. Routers apply
+    /// their `Router::agent_output_limit()` through [`Router::to_agent_json`].
+    #[cfg(feature = "sensors")]
+    pub fn to_agent_json_with_limit(&self, limit: Option<usize>) -> String {
         let result = if self.exit_code == 0 {
             "success"
         } else {
             "error"
         };
 
-        let output = match &self.output {
-            Output::Silent => String::new(),
-            Output::Text(s) => s.clone(),
-            Output::Json(s) => s.clone(),
+        let (output, shown_bytes, total_bytes) = match &self.output {
+            Output::Binary(bytes) => {
+                let shown = match limit {
+                    Some(limit) if bytes.len() > limit => &bytes[..limit],
+                    _ => bytes.as_slice(),
+                };
+                (encode_base64(shown), shown.len(), bytes.len())
+            }
+            other => {
+                let text = match other {
+                    Output::Text(s) | Output::Json(s) => s.as_str(),
+                    _ => "",
+                };
+                let shown = match limit {
+                    Some(limit) if text.len() > limit => truncate_to_char_boundary(text, limit),
+                    _ => text,
+                };
+                (shown.to_string(), shown.len(), text.len())
+            }
         };
 
         let mut json = serde_json::json!({
@@ -448,6 +827,20 @@ impl Response {
             "output": output,
         });
 
+        if matches!(self.output, Output::Binary(_)) {
+            json["output_encoding"] = serde_json::json!("base64");
+        }
+
+        let tier = self.metadata.as_ref().and_then(|m| m.tier);
+        let mutating = matches!(tier, Some("standard" | "critical"));
+        let changed = self.exit_code == 0 && self.changed.unwrap_or(mutating);
+        json["changed"] = serde_json::json!(changed);
+
+        if shown_bytes < total_bytes {
+            json["truncated"] = serde_json::json!(true);
+            json["total_bytes"] = serde_json::json!(total_bytes);
+        }
+
         if let Some(ref metadata) = self.metadata {
             if let Some(tier) = metadata.tier {
                 json["tier"] = serde_json::json!(tier);
@@ -460,8 +853,48 @@ impl Response {
             }
         }
 
-        serde_json::to_string_pretty(&json).unwrap_or_else(|_| "{}".to_string())
+        if let Some(ref data) = self.data {
+            json["data"] = data.clone();
+        }
+
+        if !self.warnings.is_empty() {
+            json["warnings"] = serde_json::json!(self.warnings);
+        }
+
+        if let Some(ref hint) = self.undo_hint {
+            json["undo_hint"] = serde_json::json!(hint);
+        }
+
+        if let Some(ref error) = self.error {
+            json["error"] = serde_json::to_value(error).unwrap_or(serde_json::json!(null));
+        }
+
+        serde_json::to_string_pretty(&sort_json_keys(json)).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Longest prefix of `s` that is at most `max_bytes` long and ends on a
+/// character boundary.
+#[cfg(feature = "sensors")]
+fn truncate_to_char_boundary(s: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
     }
+    &s[..end]
+}
+
+/// Write `bytes` as-is and flush.
+fn write_raw(writer: &mut impl std::io::Write, bytes: &[u8]) -> std::io::Result<()> {
+    writer.write_all(bytes)?;
+    writer.flush()
+}
+
+/// Standard base64 (with padding) for binary output in JSON.
+#[cfg(any(feature = "sensors", feature = "mcp"))]
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
 }
 
 /// Output type for responses.
@@ -475,24 +908,119 @@ pub enum Output {
 
     /// JSON output (for machine-readable responses).
     Json(String),
+
+    /// Raw bytes, e.g. an exported file or a tarball.
+    ///
+    /// Written to stdout unchanged by [`Response::exit`]; `Display` writes
+    /// nothing. Agent JSON carries it base64-encoded.
+    Binary(Vec<u8>),
+
+    /// Delegate to another command (resolved by the router, never printed).
+    Redirect(Redirect),
 }
 
 impl Output {
     /// Check if output is empty.
     pub fn is_empty(&self) -> bool {
-        matches!(self, Output::Silent)
+        match self {
+            Output::Silent | Output::Redirect(_) => true,
+            Output::Binary(bytes) => bytes.is_empty(),
+            Output::Text(_) | Output::Json(_) => false,
+        }
     }
 }
 
 impl std::fmt::Display for Output {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Output::Silent => Ok(()),
+            // Bytes can't be displayed; see Response::exit
+            Output::Silent | Output::Redirect(_) | Output::Binary(_) => Ok(()),
             Output::Text(s) | Output::Json(s) => write!(f, "{}", s),
         }
     }
 }
 
+/// Maximum number of redirects followed for one command.
+const MAX_REDIRECTS: usize = 8;
+
+/// Delegation from one command to another.
+///
+/// A handler that returns a `Redirect` does not produce output itself; the
+/// router runs `target` with `args` instead, as if the user had typed it.
+/// [`Router::route_redirect`] registers an alias that always redirects.
+///
+/// # Example
+///
+/// ```ignore
+/// async fn deploy_prod(Args(args): Args<Vec<String>>) -> CliResult<Redirect> {
+///     Ok(Redirect::to("deploy").arg("--env=production").args(args))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// Route key of the target command (e.g. `"deploy"` or `"db:create"`)
+    pub target: String,
+    /// Arguments passed to the target command
+    pub args: Vec<String>,
+}
+
+impl Redirect {
+    /// Redirect to the command registered as `target`.
+    pub fn to(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Add an argument for the target command.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add several arguments for the target command.
+    pub fn args<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+}
+
+/// Recursively sort object keys so JSON output is byte-stable.
+///
+/// `serde_json` only sorts keys when its `preserve_order` feature is off, and
+/// any crate in the dependency graph can turn it on. Rebuilding every object
+/// in key order gives the same bytes either way.
+fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_json_keys(v)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(sort_json_keys).collect())
+        }
+        other => other,
+    }
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher`, stable across Rust releases.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
 // ============================================================================
 // Response Conversion Trait
 // ============================================================================
@@ -500,9 +1028,46 @@ impl std::fmt::Display for Output {
 /// Trait for converting handler return values into responses.
 ///
 /// Implemented for common return types like `String`, `()`, and `Result<T, E>`.
+/// `#[sen::handler]` checks the declared return type against this trait, so a
+/// handler returning anything else fails to compile with an error naming the
+/// handler and the type:
+///
+/// ```ignore
+/// #[sen::handler]
+/// async fn count(_state: sen::State<()>, _args: sen::Args<Vec<String>>) -> usize {
+///     42
+/// }
+/// ```
+///
+/// (see `sen-rs-macros/tests/ui/handler_bad_return.stderr`)
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be returned from a sen handler",
+    label = "does not implement `IntoResponse`",
+    note = "return `String`, `()`, `Response`, or `CliResult<T>`, or implement `IntoResponse` for `{Self}`"
+)]
 pub trait IntoResponse {
+    /// Whether converting honors `--dry-run` (for example by skipping a
+    /// write), so handlers returning this type support the flag.
+    const HANDLES_DRY_RUN: bool = false;
+
     /// Convert into a response.
     fn into_response(self) -> Response;
+
+    /// Convert into a response, keeping errors typed.
+    ///
+    /// Used by [`Router::try_execute_with`]. The default never fails.
+    fn into_result(self) -> Result<Response, CliError>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_response())
+    }
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
 }
 
 impl IntoResponse for String {
@@ -511,13 +1076,31 @@ impl IntoResponse for String {
     }
 }
 
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        let mut response = Response::silent();
+        response.output = Output::Binary(self);
+        response
+    }
+}
+
 impl IntoResponse for () {
     fn into_response(self) -> Response {
         Response::silent()
     }
 }
 
+impl IntoResponse for Redirect {
+    fn into_response(self) -> Response {
+        let mut response = Response::silent();
+        response.output = Output::Redirect(self);
+        response
+    }
+}
+
 impl<T: IntoResponse> IntoResponse for CliResult<T> {
+    const HANDLES_DRY_RUN: bool = T::HANDLES_DRY_RUN;
+
     fn into_response(self) -> Response {
         match self {
             Ok(value) => value.into_response(),
@@ -526,20 +1109,111 @@ impl<T: IntoResponse> IntoResponse for CliResult<T> {
                 let message = match &e {
                     CliError::User(UserError::Help(help_text)) => {
                         // Help is a successful operation, return as text response with exit code 0
-                        return Response {
-                            output: Output::Text(help_text.clone()),
-                            exit_code: 0,
-                            agent_mode: false,
-                            metadata: None,
-                        };
+                        return Response::text(help_text.clone());
                     }
                     CliError::User(user_err) => format!("{}", user_err),
                     CliError::System(sys_err) => format!("{}", sys_err),
                 };
-                Response::error(exit_code, message)
+                let mut response = Response::error(exit_code, message);
+                response.error = Some(ErrorInfo::from(&e));
+                response
+            }
+        }
+    }
+
+    fn into_result(self) -> Result<Response, CliError> {
+        match self {
+            Ok(value) => value.into_result(),
+            // Help is a successful exit, not an error
+            Err(e @ CliError::User(UserError::Help(_))) => Ok(error_response(e)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Convert a typed error into its response form.
+fn error_response(error: CliError) -> Response {
+    let result: CliResult<()> = Err(error);
+    result.into_response()
+}
+
+/// Remove ANSI escape sequences and control characters other than `\n` and
+/// `\t` from `text`.
+fn strip_control_chars(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '\t' => out.push(c),
+            '\x1b' => match chars.next() {
+                // CSI (`ESC [ ... final`), e.g. colors and cursor movement
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC (`ESC ] ... BEL` or `ESC ] ... ESC \`), e.g. hyperlinks
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // Two-character escapes (`ESC c`, `ESC 7`, ...)
+                _ => {}
+            },
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Command segment compared case-insensitively, with `_` read as `-`.
+fn normalize_segment(segment: &str) -> String {
+    segment.to_lowercase().replace('_', "-")
+}
+
+/// Route `key` with each segment normalized by [`normalize_segment`].
+fn normalized_key(key: &str) -> String {
+    key.split(':')
+        .map(normalize_segment)
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Edit distance counting insertions, deletions, substitutions, and adjacent
+/// transpositions (so `"stauts"` is one edit from `"status"`).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
             }
+            rows[i][j] = best;
         }
     }
+    rows[a.len()][b.len()]
 }
 
 // ============================================================================
@@ -554,6 +1228,9 @@ use std::pin::Pin;
 /// Boxed future for type erasure
 type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
+/// Matched route: (route key, handler)
+type RouteMatch<'a> = (&'a str, &'a dyn ErasedHandler<()>);
+
 /// Safety tier for CLI commands.
 ///
 /// Determines the risk level of a command and whether it requires
@@ -637,10 +1314,62 @@ pub struct RouterMetadata {
     pub about: Option<&'static str>,
 }
 
-/// Metadata for individual command handlers.
+/// Configuration of the built-in help and version handling.
+///
+/// Passed to [`Router::builtins`]. A route registered under the version
+/// command name always wins over the built-in, so an app's own `version`
+/// command is never shadowed.
+///
+/// # Example
 ///
-/// This is used by the `#[sen::handler(...)]` attribute macro.
+/// ```ignore
+/// // `myctl info` prints the version; `--version` and `-V` still work
+/// let router = Router::new()
+///     .route("status", handlers::status)
+///     .builtins(BuiltinsConfig {
+///         version: Some("info"),
+///         ..Default::default()
+///     })
+///     .with_state(state);
+/// ```
 #[derive(Debug, Clone)]
+pub struct BuiltinsConfig {
+    /// Handle `--help`/`-h` (and `--help --md`, `--help --json`) before routing.
+    ///
+    /// Running the CLI without a command always prints help.
+    pub help: bool,
+
+    /// Command name of the built-in version command, also reachable as
+    /// `--version`/`-V`. `None` disables built-in version handling entirely.
+    pub version: Option<&'static str>,
+
+    /// Command name of the built-in self-check (see [`Router::doctor`]).
+    /// `None` disables it.
+    pub doctor: Option<&'static str>,
+
+    /// Command name of the built-in listing of recent critical commands,
+    /// available once [`Router::with_history`] is set. `None` disables it.
+    pub history: Option<&'static str>,
+}
+
+impl Default for BuiltinsConfig {
+    fn default() -> Self {
+        Self {
+            help: true,
+            version: Some("version"),
+            doctor: Some("doctor"),
+            history: Some("history"),
+        }
+    }
+}
+
+/// Metadata for individual command handlers.
+///
+/// This is used by the `#[sen::handler(...)]` attribute macro. Build one
+/// from [`HandlerMetadata::default`] and set the fields you need; new fields
+/// may be added without a breaking change.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct HandlerMetadata {
     /// Short description of what this handler does
     pub desc: Option<&'static str>,
@@ -648,6 +1377,14 @@ pub struct HandlerMetadata {
     pub tier: Option<Tier>,
     /// Tags for command categorization and discovery
     pub tags: Option<Vec<&'static str>>,
+    /// Usage examples shown in command help and the JSON schema
+    pub examples: Vec<&'static str>,
+    /// Alternative command names (see [`Router::route_with_aliases`])
+    pub aliases: Vec<&'static str>,
+    /// Environment variables the handler reads (e.g. `EDITOR`)
+    pub env: Vec<&'static str>,
+    /// Time budget for this command (see [`Router::with_default_timeout`])
+    pub timeout: Option<std::time::Duration>,
 }
 
 /// Metadata for a specific route in the router.
@@ -656,7 +1393,7 @@ pub struct HandlerMetadata {
 pub struct RouteMetadata {
     /// Handler-level metadata (from #[sen::handler])
     handler_meta: Option<HandlerMetadata>,
-    /// Route description (can be set via .describe())
+    /// Route description (set via [`Router::describe`])
     description: Option<String>,
     /// CLI argument schema (from Clap, if available)
     args_schema: Option<serde_json::Value>,
@@ -672,10 +1409,57 @@ impl RouteMetadata {
             .or_else(|| self.handler_meta.as_ref()?.desc)
     }
 
-    /// Get the CLI argument schema for this route
+    /// Whether the handler is tagged with `tag` (from `#[sen::handler(tags = [...])]`)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.handler_meta
+            .as_ref()
+            .and_then(|h| h.tags.as_ref())
+            .is_some_and(|tags| tags.contains(&tag))
+    }
+
+    /// Get the CLI argument schema for this route
     pub fn get_args_schema(&self) -> Option<&serde_json::Value> {
         self.args_schema.as_ref()
     }
+
+    /// Get the usage examples for this route (from `#[sen::handler(example = ...)]`)
+    pub fn get_examples(&self) -> &[&'static str] {
+        self.handler_meta
+            .as_ref()
+            .map(|h| h.examples.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the alternative names of this route (from `#[sen::handler(aliases = [...])]`)
+    pub fn get_aliases(&self) -> &[&'static str] {
+        self.handler_meta
+            .as_ref()
+            .map(|h| h.aliases.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the environment variables this route reads
+    ///
+    /// Combines the handler's declared variables (`#[sen::handler(env = [...])]`)
+    /// with the `env` of its options in the argument schema (e.g. clap's
+    /// `#[arg(env = "...")]`), sorted and without duplicates.
+    pub fn get_env(&self) -> Vec<String> {
+        let declared = self
+            .handler_meta
+            .iter()
+            .flat_map(|h| h.env.iter().map(|var| var.to_string()));
+        let from_options = self
+            .args_schema
+            .iter()
+            .filter_map(|schema| schema["options"].as_array())
+            .flatten()
+            .filter_map(|option| option["env"].as_str().map(str::to_string));
+
+        let mut env: Vec<String> = declared.chain(from_options).collect();
+        env.sort();
+        env.dedup();
+        env
+    }
 }
 
 /// Handler trait - allows functions with various signatures to be used as handlers.
@@ -689,6 +1473,18 @@ pub trait Handler<T, S>: Clone + Send + Sync + Sized + 'static {
     /// Call the handler with state and arguments
     fn call(self, state: State<S>, args: Vec<String>) -> Self::Future;
 
+    /// Call the handler, returning errors as typed `CliError`s.
+    ///
+    /// The default implementation wraps [`Handler::call`] and never fails.
+    fn try_call(
+        self,
+        state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'static, Result<Response, CliError>> {
+        let future = self.call(state, args);
+        Box::pin(async move { Ok(future.await) })
+    }
+
     /// Get handler metadata (optional)
     fn metadata(&self) -> Option<HandlerMetadata> {
         None
@@ -698,6 +1494,15 @@ pub trait Handler<T, S>: Clone + Send + Sync + Sized + 'static {
     fn args_schema(&self) -> Option<serde_json::Value> {
         None
     }
+
+    /// Whether the handler sees `--dry-run`, through the [`DryRun`]
+    /// extractor or a response type that honors it.
+    ///
+    /// The router rejects `--dry-run` for handlers that don't, rather than
+    /// run them for real.
+    fn handles_dry_run(&self) -> bool {
+        false
+    }
 }
 
 /// Wrapper that attaches metadata to a handler.
@@ -747,6 +1552,14 @@ where
         self.handler.call(state, args)
     }
 
+    fn try_call(
+        self,
+        state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'static, Result<Response, CliError>> {
+        self.handler.try_call(state, args)
+    }
+
     fn metadata(&self) -> Option<HandlerMetadata> {
         Some(self.metadata.clone())
     }
@@ -754,11 +1567,19 @@ where
     fn args_schema(&self) -> Option<serde_json::Value> {
         self.handler.args_schema()
     }
+
+    fn handles_dry_run(&self) -> bool {
+        self.handler.handles_dry_run()
+    }
 }
 
 /// Type-erased handler for storage in Router
 trait ErasedHandler<S>: Send + Sync {
-    fn call_boxed<'a>(&'a self, state: State<S>, args: Vec<String>) -> BoxFuture<'a, Response>;
+    fn try_call_boxed<'a>(
+        &'a self,
+        state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'a, Result<Response, CliError>>;
 
     fn clone_box(&self) -> Box<dyn ErasedHandler<S>>;
 
@@ -767,6 +1588,8 @@ trait ErasedHandler<S>: Send + Sync {
 
     #[allow(dead_code)]
     fn args_schema(&self) -> Option<serde_json::Value>;
+
+    fn handles_dry_run(&self) -> bool;
 }
 
 impl<S> Clone for Box<dyn ErasedHandler<S>> {
@@ -775,6 +1598,43 @@ impl<S> Clone for Box<dyn ErasedHandler<S>> {
     }
 }
 
+/// Handler registered by [`Router::route_redirect`]
+#[derive(Clone)]
+struct RedirectHandler {
+    target: String,
+    extra_args: Vec<String>,
+}
+
+impl<S> ErasedHandler<S> for RedirectHandler {
+    fn try_call_boxed<'a>(
+        &'a self,
+        _state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'a, Result<Response, CliError>> {
+        let redirect = Redirect::to(self.target.clone())
+            .args(self.extra_args.iter().cloned())
+            .args(args);
+        Box::pin(async move { Ok(redirect.into_response()) })
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedHandler<S>> {
+        Box::new(self.clone())
+    }
+
+    fn metadata(&self) -> Option<HandlerMetadata> {
+        None
+    }
+
+    fn args_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    // The redirect target is checked when it is called
+    fn handles_dry_run(&self) -> bool {
+        true
+    }
+}
+
 /// Wrapper that implements ErasedHandler for any Handler
 struct HandlerService<H, T, S> {
     handler: H,
@@ -808,9 +1668,13 @@ where
     S: Send + Sync + 'static,
     T: 'static,
 {
-    fn call_boxed<'a>(&'a self, state: State<S>, args: Vec<String>) -> BoxFuture<'a, Response> {
+    fn try_call_boxed<'a>(
+        &'a self,
+        state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'a, Result<Response, CliError>> {
         let handler = self.handler.clone();
-        Box::pin(async move { handler.call(state, args).await })
+        Box::pin(async move { handler.try_call(state, args).await })
     }
 
     fn clone_box(&self) -> Box<dyn ErasedHandler<S>> {
@@ -824,6 +1688,28 @@ where
     fn args_schema(&self) -> Option<serde_json::Value> {
         self.handler.args_schema()
     }
+
+    fn handles_dry_run(&self) -> bool {
+        self.handler.handles_dry_run()
+    }
+}
+
+/// Commands of a `#[derive(SenRouter)]` enum, as routes for a [`Router`].
+///
+/// Derived for enums that also generate `router()` (those with `name`,
+/// `version`, `about`, or `agent_mode` in `#[sen(...)]`). A router built with
+/// [`Router::from_enum_router`] then has the enum's command names,
+/// descriptions, and argument schemas, while already parsed variants can
+/// still be dispatched with the enum's `execute()`.
+pub trait SenRouterTrait {
+    /// State type the handlers take.
+    type State: Send + Sync + Clone + 'static;
+
+    /// Name and description of every command, in declaration order.
+    fn commands_metadata() -> Vec<(&'static str, Option<&'static str>)>;
+
+    /// Register every command as a route on `router`.
+    fn register_routes(router: Router<Self::State>) -> Router<Self::State>;
 }
 
 /// Router for CLI commands.
@@ -850,13 +1736,118 @@ where
 pub struct Router<S = ()> {
     routes: HashMap<String, Box<dyn ErasedHandler<S>>>,
     route_metadata: HashMap<String, RouteMetadata>,
+    /// Help descriptions of command groups by prefix; the empty key is this
+    /// router's own description, which `nest` moves under its prefix
+    group_descriptions: HashMap<String, String>,
+    /// Parsers for namespace-scoped options, by prefix
+    scoped_args: HashMap<String, scoped::ScopedParser>,
+    /// Argument value completers, by route
+    completers: HashMap<String, complete::SharedCompleter>,
+    /// Route each alias from [`Router::route_with_aliases`] resolves to
+    aliases: HashMap<String, String>,
+    config: RouterConfig,
+    /// Parses the state from stdin when `--state-stdin` is passed
+    stdin_state: Option<StdinStateParser<S>>,
+    _marker: PhantomData<S>,
+}
+
+/// Router settings other than the routes.
+///
+/// Kept together so that building a router from another one's routes
+/// (`with_state`, [`ReloadableRouter::swap_routes`]) carries them over as a
+/// unit.
+#[derive(Clone)]
+struct RouterConfig {
     metadata: Option<RouterMetadata>,
     agent_mode_enabled: bool,
+    agent_output_limit: Option<usize>,
+    sanitize_agent_output: bool,
+    quiet_flag_enabled: bool,
+    dry_run_flag_enabled: bool,
+    template_flag_enabled: bool,
+    /// Environment variable consulted for `--profile`, if the flag is enabled
+    profile_env: Option<String>,
+    prefix_matching: bool,
+    /// Match commands ignoring case and `_`/`-` differences
+    normalize_commands: bool,
+    /// Nesting separator in keys passed to the builder and shown in help;
+    /// keys are stored with `:`
+    separator: char,
+    #[cfg(feature = "clap")]
+    infer_long_args: bool,
+    builtins: BuiltinsConfig,
     #[cfg(feature = "mcp")]
     mcp_enabled: bool,
-    _marker: PhantomData<S>,
+    /// Name of the MCP catalog tool
+    #[cfg(feature = "mcp")]
+    mcp_describe_tool: String,
+    progress_sink: Option<tokio::sync::mpsc::UnboundedSender<ProgressEvent>>,
+    cancel_signal: Option<CancelSignal>,
+    default_timeout: Option<std::time::Duration>,
+    writer: Option<SharedWriter>,
+    /// JSONL file that gets a line per routed command
+    usage_log: Option<std::path::PathBuf>,
+    usage_log_args: bool,
+    /// History file of critical commands
+    history: Option<std::path::PathBuf>,
+    /// Turn handler panics into `SystemError::Internal`
+    catch_panics: bool,
+    /// Tools checked by `doctor`
+    required_tools: Vec<doctor::RequiredTool>,
+    /// Config file checked by `doctor`
+    config_file: Option<std::path::PathBuf>,
+}
+
+impl Default for RouterConfig {
+    fn default() -> Self {
+        Self {
+            metadata: None,
+            agent_mode_enabled: false,
+            agent_output_limit: None,
+            sanitize_agent_output: false,
+            quiet_flag_enabled: false,
+            dry_run_flag_enabled: false,
+            template_flag_enabled: false,
+            profile_env: None,
+            prefix_matching: false,
+            normalize_commands: false,
+            separator: ':',
+            #[cfg(feature = "clap")]
+            infer_long_args: false,
+            builtins: BuiltinsConfig::default(),
+            #[cfg(feature = "mcp")]
+            mcp_enabled: false,
+            #[cfg(feature = "mcp")]
+            mcp_describe_tool: crate::mcp::DESCRIBE_TOOL.to_string(),
+            progress_sink: None,
+            cancel_signal: None,
+            default_timeout: None,
+            writer: None,
+            usage_log: None,
+            usage_log_args: false,
+            history: None,
+            catch_panics: false,
+            required_tools: Vec::new(),
+            config_file: None,
+        }
+    }
+}
+
+/// Whether route `key` is `command` or nested under it (`db:create` under `db`).
+fn route_is_under(key: &str, command: &str) -> bool {
+    key.strip_prefix(command)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
 }
 
+/// Signal source that cancels the running command when it resolves.
+type CancelSignal = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Builds the state from the text read on stdin.
+type StdinStateParser<S> = Arc<dyn Fn(&str) -> Result<S, CliError> + Send + Sync>;
+
+/// Flag that makes [`Router::with_stdin_state`] read the state from stdin.
+const STATE_STDIN_FLAG: &str = "--state-stdin";
+
 impl<S> Default for Router<S>
 where
     S: Send + Sync + Clone + 'static,
@@ -875,14 +1866,96 @@ where
         Self {
             routes: HashMap::new(),
             route_metadata: HashMap::new(),
-            metadata: None,
-            agent_mode_enabled: false,
-            #[cfg(feature = "mcp")]
-            mcp_enabled: false,
+            group_descriptions: HashMap::new(),
+            scoped_args: HashMap::new(),
+            completers: HashMap::new(),
+            aliases: HashMap::new(),
+            config: RouterConfig::default(),
+            stdin_state: None,
             _marker: PhantomData,
         }
     }
 
+    /// Create a router with the commands of a `#[derive(SenRouter)]` enum.
+    ///
+    /// Unlike the generated `Commands::router(state)`, this leaves metadata,
+    /// agent mode, and state to you, and more routes can be added.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::from_enum_router::<Commands>()
+    ///     .route("plugin:run", handlers::run_plugin)
+    ///     .with_metadata(RouterMetadata { name: "myctl", version: None, about: None })
+    ///     .with_agent_mode()
+    ///     .with_state(state);
+    /// ```
+    pub fn from_enum_router<C>() -> Self
+    where
+        C: SenRouterTrait<State = S>,
+    {
+        C::register_routes(Self::new())
+    }
+
+    /// Create a router with a route per subcommand of a clap `Subcommand` enum.
+    ///
+    /// Nested subcommands become nested routes (`db create` is `db:create`).
+    /// Each route carries the subcommand's `about` as its description and
+    /// its arguments in the CLI schema, so help, `--help --json`, and MCP
+    /// tools work as for any other route. Running a route parses the args
+    /// into `E` and calls `dispatch` with the result.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[derive(clap::Subcommand)]
+    /// enum Command {
+    ///     /// Create a database
+    ///     Create { name: String },
+    ///     /// Drop a database
+    ///     Drop { name: String, #[arg(long)] force: bool },
+    /// }
+    ///
+    /// async fn run(state: State<AppState>, command: Command) -> CliResult<String> {
+    ///     match command {
+    ///         Command::Create { name } => Ok(format!("Created {}", name)),
+    ///         Command::Drop { name, .. } => Ok(format!("Dropped {}", name)),
+    ///     }
+    /// }
+    ///
+    /// let router = Router::from_clap_subcommand::<Command, _, _, _>(run).with_state(state);
+    /// // $ myapp drop users --force
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn from_clap_subcommand<E, F, Fut, Res>(dispatch: F) -> Self
+    where
+        E: clap::Subcommand + Send + 'static,
+        F: Fn(State<S>, E) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = Res> + Send + 'static,
+        Res: IntoResponse + 'static,
+    {
+        let root = E::augment_subcommands(clap::Command::new("cmd"));
+        let mut leaves = Vec::new();
+        clap_leaf_subcommands(&root, &mut Vec::new(), &mut leaves);
+
+        leaves
+            .into_iter()
+            .fold(Self::new(), |router, (path, subcommand)| {
+                let route_key = path.join(":");
+                let handler = ClapSubcommandHandler {
+                    path: path.into(),
+                    schema: Arc::new(clap_command_to_json(&subcommand)),
+                    dispatch: dispatch.clone(),
+                    _marker: PhantomData::<fn() -> E>,
+                };
+                let router = router.route(route_key.clone(), handler);
+                match subcommand.get_about() {
+                    Some(about) => router.describe(&route_key, about.to_string()),
+                    None => router,
+                }
+            })
+    }
+
     /// Register a handler for a command.
     ///
     /// # Example
@@ -894,10 +1967,8 @@ where
     where
         H: Handler<T, S>,
     {
-        let command_name = command.into();
-        if self.routes.contains_key(&command_name) {
-            panic!("Duplicate route: {}", command_name);
-        }
+        let command_name = self.canonical_key(&command.into());
+        self.assert_new_route(&command_name);
 
         // Collect handler metadata and schema information
         let handler_meta = handler.metadata();
@@ -918,6 +1989,195 @@ where
         self
     }
 
+    /// Register `command` as an alias that redirects to `target`.
+    ///
+    /// Running `command` runs `target` with `extra_args` followed by the
+    /// arguments the user passed, e.g. `deploy:prod --dry-run` runs
+    /// `deploy --env=production --dry-run`. `target` is a route key and is
+    /// resolved when the alias runs, so it may be registered later.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("deploy", handlers::deploy)
+    ///     .route_redirect("deploy:prod", "deploy", ["--env=production"]);
+    /// ```
+    pub fn route_redirect<I, A>(
+        mut self,
+        command: impl Into<String>,
+        target: impl Into<String>,
+        extra_args: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        let command_name = self.canonical_key(&command.into());
+        self.assert_new_route(&command_name);
+
+        let handler = RedirectHandler {
+            target: self.canonical_key(&target.into()),
+            extra_args: extra_args.into_iter().map(Into::into).collect(),
+        };
+        let mut alias_of = vec![handler.target.replace(':', " ")];
+        alias_of.extend(handler.extra_args.iter().cloned());
+
+        self.route_metadata.insert(
+            command_name.clone(),
+            RouteMetadata {
+                handler_meta: None,
+                description: Some(format!("Alias for '{}'", alias_of.join(" "))),
+                args_schema: None,
+            },
+        );
+        self.routes.insert(command_name, Box::new(handler));
+        self
+    }
+
+    /// Register a handler with an explicit argument schema.
+    ///
+    /// Use this for hand-written `FromArgs` types, which have no schema of
+    /// their own, so their arguments still appear in `--help --json`. The
+    /// schema uses the same shape as the clap-derived one:
+    ///
+    /// ```ignore
+    /// router.route_with_schema(
+    ///     "deploy",
+    ///     handlers::deploy,
+    ///     json!({
+    ///         "arguments": [
+    ///             { "name": "env", "type": "String", "required": true, "description": "Target environment" }
+    ///         ],
+    ///         "options": [
+    ///             { "name": "--force", "type": "bool", "required": false, "description": "Skip checks" }
+    ///         ]
+    ///     }),
+    /// )
+    /// ```
+    ///
+    /// Entries may also list `conflicts_with` and `requires` (see
+    /// [`FromArgs::cli_schema`]). The given schema replaces any schema derived
+    /// from the handler's args.
+    pub fn route_with_schema<H, T: 'static>(
+        self,
+        command: impl Into<String>,
+        handler: H,
+        schema: serde_json::Value,
+    ) -> Self
+    where
+        H: Handler<T, S>,
+    {
+        let command_name = self.canonical_key(&command.into());
+        let mut router = self.route(command_name.clone(), handler);
+        if let Some(meta) = router.route_metadata.get_mut(&command_name) {
+            meta.args_schema = Some(schema);
+        }
+        router
+    }
+
+    /// Set the help description of a registered command.
+    ///
+    /// For handlers without `#[sen::handler(desc = ...)]`, or to override it:
+    /// the route-level description takes precedence over the handler's in
+    /// help, `--help --json`, and MCP tool listings. Panics if `command` has
+    /// not been registered.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("status", handlers::status)
+    ///     .describe("status", "Show service status")
+    ///     .with_state(state);
+    /// ```
+    pub fn describe(mut self, command: &str, desc: impl Into<String>) -> Self {
+        match self.route_metadata.get_mut(&self.canonical_key(command)) {
+            Some(meta) => meta.description = Some(desc.into()),
+            None => panic!("Cannot describe unknown route: {}", command),
+        }
+        self
+    }
+
+    /// Complete argument values of `command` with `completer`.
+    ///
+    /// Shell completion scripts get the candidates from the hidden
+    /// `__complete` command (see [`complete`]). Panics if `command` has not
+    /// been registered.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:delete", handlers::delete)
+    ///     .completer("db:delete", |_command, current: String| async move {
+    ///         list_databases()
+    ///             .await
+    ///             .into_iter()
+    ///             .filter(|name| name.starts_with(&current))
+    ///             .collect()
+    ///     })
+    ///     .with_state(state);
+    ///
+    /// // $ myctl __complete db delete us
+    /// // users
+    /// ```
+    pub fn completer(mut self, command: &str, completer: impl Completer) -> Self {
+        let command = self.canonical_key(command);
+        if !self.routes.contains_key(&command) {
+            panic!("Cannot add a completer to unknown route: {}", command);
+        }
+        self.completers.insert(command, Arc::new(completer));
+        self
+    }
+
+    /// Register a handler under its name and every alias in its metadata.
+    ///
+    /// Aliases come from `#[sen::handler(aliases = [...])]` and name siblings
+    /// of the command: for `db:list` with alias `ls`, `db ls` runs `db:list`.
+    /// They are not routes of their own: help, the JSON schema, MCP tools
+    /// and `doctor` list the command once, with its aliases as metadata, and
+    /// the command runs under its canonical name.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[sen::handler(desc = "List items", aliases = ["ls", "l"])]
+    /// async fn list(state: State<AppState>) -> CliResult<String> { ... }
+    ///
+    /// // Registers "list", "ls" and "l"
+    /// router.route_with_aliases("list", list())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or any alias is already registered.
+    pub fn route_with_aliases<H, T: 'static>(
+        mut self,
+        command: impl Into<String>,
+        handler: H,
+    ) -> Self
+    where
+        H: Handler<T, S>,
+    {
+        let command_name = self.canonical_key(&command.into());
+        let aliases = handler
+            .metadata()
+            .map(|meta| meta.aliases)
+            .unwrap_or_default();
+        let parent = command_name
+            .rsplit_once(':')
+            .map(|(parent, _)| format!("{}:", parent))
+            .unwrap_or_default();
+
+        for alias in aliases {
+            let alias = format!("{}{}", parent, alias);
+            self.assert_new_route(&alias);
+            self.aliases.insert(alias, command_name.clone());
+        }
+        self.route(command_name, handler)
+    }
+
     /// Nest a router under a prefix.
     ///
     /// This allows organizing commands into hierarchies (subcommands).
@@ -938,7 +2198,7 @@ where
     /// // Routes: "db:create", "db:list", "db:delete", "server:start", ...
     /// ```
     pub fn nest(mut self, prefix: impl Into<String>, router: Router<S>) -> Self {
-        let prefix = prefix.into();
+        let prefix = self.canonical_key(&prefix.into());
 
         // Add all routes from the nested router with the prefix
         for (path, handler) in router.routes {
@@ -948,9 +2208,7 @@ where
                 format!("{}:{}", prefix, path)
             };
 
-            if self.routes.contains_key(&nested_path) {
-                panic!("Duplicate route: {}", nested_path);
-            }
+            self.assert_new_route(&nested_path);
 
             self.routes.insert(nested_path.clone(), handler);
 
@@ -960,2080 +2218,7684 @@ where
             }
         }
 
+        // Carry group descriptions over, including the router's own
+        for (group, desc) in router.group_descriptions {
+            let nested_group = if group.is_empty() {
+                prefix.clone()
+            } else {
+                format!("{}:{}", prefix, group)
+            };
+            self.group_descriptions.insert(nested_group, desc);
+        }
+
+        for (scope, parser) in router.scoped_args {
+            let nested_scope = if scope.is_empty() {
+                prefix.clone()
+            } else {
+                format!("{}:{}", prefix, scope)
+            };
+            self.scoped_args.insert(nested_scope, parser);
+        }
+
+        for (path, completer) in router.completers {
+            self.completers
+                .insert(format!("{}:{}", prefix, path), completer);
+        }
+
+        for (alias, target) in router.aliases {
+            let alias = format!("{}:{}", prefix, alias);
+            self.assert_new_route(&alias);
+            self.aliases.insert(alias, format!("{}:{}", prefix, target));
+        }
+
         self
     }
 
-    /// Attach metadata to the router.
+    /// Nest a router under a prefix, with options shared by all its commands.
     ///
-    /// This is typically used by the `#[sen(...)]` attribute macro to provide
-    /// CLI metadata for help generation.
+    /// Like [`Router::nest`], but every command under `prefix` first has its
+    /// args run through `T::from_global_args`. The parsed options reach the
+    /// handler through the [`Scoped`] extractor, and only the remaining args
+    /// are passed on. Commands outside the prefix never parse `T`.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// let router = Router::new()
-    ///     .route("status", handlers::status)
-    ///     .with_metadata(RouterMetadata {
-    ///         name: "myctl",
-    ///         version: Some("1.0.0"),
-    ///         about: Some("My CLI tool"),
-    ///     })
+    /// let app = Router::new()
+    ///     .nest_with_args::<DbOpts>("db", db_router)
+    ///     .nest("server", server_router)
     ///     .with_state(state);
+    ///
+    /// // `db create users --namespace prod` -> Scoped<DbOpts>, Args(["users"])
     /// ```
-    pub fn with_metadata(mut self, metadata: RouterMetadata) -> Self {
-        self.metadata = Some(metadata);
-        self
+    pub fn nest_with_args<T>(mut self, prefix: impl Into<String>, router: Router<S>) -> Self
+    where
+        T: FromGlobalArgs + Send + Sync + 'static,
+    {
+        let prefix = self.canonical_key(&prefix.into());
+        self.scoped_args
+            .insert(prefix.clone(), scoped::parser::<T>());
+        self.nest(prefix, router)
     }
 
-    /// Enable automatic agent mode support.
+    /// Describe the command group this router forms when nested.
     ///
-    /// When enabled, the router will:
-    /// - Automatically detect `--agent-mode` flag in arguments
-    /// - Strip the flag before routing to handlers
-    /// - Set `agent_mode` in the Response for automatic JSON output
+    /// `generate_help_text` shows the description next to the group heading
+    /// once the router is passed to [`Router::nest`].
     ///
     /// # Example
     ///
     /// ```ignore
-    /// let router = Router::new()
-    ///     .route("build", handlers::build)
-    ///     .with_agent_mode()
-    ///     .with_state(state);
+    /// let db_router = Router::new()
+    ///     .route("create", handlers::db::create)
+    ///     .describe_group("Database operations");
     ///
-    /// // User runs: myapp --agent-mode build
-    /// // Router automatically handles the flag and outputs JSON
+    /// let app = Router::new().nest("db", db_router).with_state(state);
+    ///
+    /// // Help: "Database Commands: Database operations"
     /// ```
-    pub fn with_agent_mode(mut self) -> Self {
-        self.agent_mode_enabled = true;
+    pub fn describe_group(mut self, desc: impl Into<String>) -> Self {
+        self.group_descriptions.insert(String::new(), desc.into());
         self
     }
 
-    /// Enable MCP (Model Context Protocol) support.
+    /// Remove a command, e.g. one a deployment's config turns off.
     ///
-    /// When enabled, the router will recognize and handle MCP-specific flags:
-    /// - `--mcp-server`: Start in MCP server mode (JSON-RPC over stdio)
-    /// - `--mcp-init <client>`: Generate MCP configuration for the specified client
+    /// `command` may be written with spaces or colons (`"db create"` or
+    /// `"db:create"`); naming a group (`"db"`) removes every command under it.
+    /// Removed commands are reported as unknown and left out of help. Names
+    /// that match nothing are ignored, so a config can list commands this
+    /// build does not have.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// let router = Router::new()
-    ///     .route("build", handlers::build)
-    ///     .with_mcp()
-    ///     .with_state(state);
+    /// let mut router = Router::new()
+    ///     .route("status", handlers::status)
+    ///     .route("deploy", handlers::deploy);
     ///
-    /// // Usage:
-    /// // $ mycli --mcp-server              # Start MCP server
-    /// // $ mycli --mcp-init claude         # Generate claude_desktop_config.json
+    /// if config.read_only {
+    ///     router = router.disable("deploy");
+    /// }
     /// ```
-    #[cfg(feature = "mcp")]
-    pub fn with_mcp(mut self) -> Self {
-        self.mcp_enabled = true;
+    pub fn disable(mut self, command: &str) -> Self {
+        let command = self.canonical_key(&command.replace(' ', ":"));
+        self.retain_routes(|key| !route_is_under(key, &command));
         self
     }
 
-    /// Provide the application state, converting `Router<S>` to `Router<()>`.
+    /// Keep only the listed commands (and everything under listed groups).
     ///
-    /// This follows Axum's pattern where the type system ensures all required
-    /// state is provided before the router can execute requests.
-    pub fn with_state(self, state: S) -> Router<()> {
-        let routes: HashMap<String, Box<dyn ErasedHandler<()>>> = self
-            .routes
-            .into_iter()
-            .map(|(cmd, handler)| {
-                let state = state.clone();
-                let boxed: Box<dyn ErasedHandler<()>> =
-                    Box::new(StatefulHandler { handler, state });
-                (cmd, boxed)
-            })
+    /// The whitelist counterpart of [`Router::disable`], with the same name
+    /// rules: `enable_only(&["status", "db"])` keeps `status` and every `db`
+    /// command.
+    pub fn enable_only(mut self, commands: &[&str]) -> Self {
+        let commands: Vec<String> = commands
+            .iter()
+            .map(|c| self.canonical_key(&c.replace(' ', ":")))
             .collect();
+        self.retain_routes(|key| commands.iter().any(|c| route_is_under(key, c)));
+        self
+    }
 
-        Router {
-            routes,
-            route_metadata: self.route_metadata,
-            metadata: self.metadata,
-            agent_mode_enabled: self.agent_mode_enabled,
-            #[cfg(feature = "mcp")]
-            mcp_enabled: self.mcp_enabled,
-            _marker: PhantomData,
-        }
+    fn retain_routes(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.routes.retain(|key, _| keep(key));
+        let routes = &self.routes;
+        self.route_metadata
+            .retain(|key, _| routes.contains_key(key));
+        self.aliases.retain(|_, target| routes.contains_key(target));
+        self.completers.retain(|key, _| routes.contains_key(key));
+        self.scoped_args
+            .retain(|scope, _| routes.keys().any(|key| route_is_under(key, scope)));
     }
-}
 
-/// Handler that has been bound to a state
-struct StatefulHandler<S> {
-    handler: Box<dyn ErasedHandler<S>>,
-    state: S,
-}
+    /// Merge another router's routes into this one at the same level.
+    ///
+    /// This is the flat counterpart of [`Router::nest`]: routes are copied
+    /// without a prefix, together with their metadata. Like `route` and
+    /// `nest`, this panics if a command already exists, and likewise if both
+    /// routers declare scoped args for the same prefix.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let core = Router::new()
+    ///     .route("status", handlers::status)
+    ///     .route("build", handlers::build);
+    ///
+    /// let extras = Router::new()
+    ///     .route("deploy", handlers::deploy);
+    ///
+    /// let app = core.merge(extras).with_state(state);
+    ///
+    /// // Routes: "status", "build", "deploy"
+    /// ```
+    pub fn merge(mut self, other: Router<S>) -> Self {
+        for (path, handler) in other.routes {
+            self.assert_new_route(&path);
 
-impl<S> Clone for StatefulHandler<S>
-where
-    S: Clone,
-{
-    fn clone(&self) -> Self {
-        Self {
-            handler: self.handler.clone(),
-            state: self.state.clone(),
+            // Transfer route metadata if exists
+            if let Some(meta) = other.route_metadata.get(&path) {
+                self.route_metadata.insert(path.clone(), meta.clone());
+            }
+
+            self.routes.insert(path, handler);
         }
-    }
-}
 
-impl<S> ErasedHandler<()> for StatefulHandler<S>
-where
-    S: Clone + Send + Sync + 'static,
-{
-    fn call_boxed<'a>(&'a self, _state: State<()>, args: Vec<String>) -> BoxFuture<'a, Response> {
-        let handler = self.handler.clone();
-        let state = State::new(self.state.clone());
-        Box::pin(async move { handler.call_boxed(state, args).await })
-    }
+        for (group, desc) in other.group_descriptions {
+            self.group_descriptions.entry(group).or_insert(desc);
+        }
+        for (scope, parser) in other.scoped_args {
+            if self.scoped_args.contains_key(&scope) {
+                panic!("Duplicate scoped args: {}", scope);
+            }
+            self.scoped_args.insert(scope, parser);
+        }
+        self.completers.extend(other.completers);
+        for (alias, target) in other.aliases {
+            self.assert_new_route(&alias);
+            self.aliases.insert(alias, target);
+        }
 
-    fn clone_box(&self) -> Box<dyn ErasedHandler<()>> {
-        Box::new(self.clone())
+        self
     }
 
-    fn metadata(&self) -> Option<HandlerMetadata> {
-        self.handler.metadata()
+    /// Attach metadata to the router.
+    ///
+    /// This is typically used by the `#[sen(...)]` attribute macro to provide
+    /// CLI metadata for help generation.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("status", handlers::status)
+    ///     .with_metadata(RouterMetadata {
+    ///         name: "myctl",
+    ///         version: Some("1.0.0"),
+    ///         about: Some("My CLI tool"),
+    ///     })
+    ///     .with_state(state);
+    /// ```
+    pub fn with_metadata(mut self, metadata: RouterMetadata) -> Self {
+        self.config.metadata = Some(metadata);
+        self
     }
 
-    fn args_schema(&self) -> Option<serde_json::Value> {
-        self.handler.args_schema()
+    /// Enable automatic agent mode support.
+    ///
+    /// When enabled, the router will:
+    /// - Automatically detect `--agent-mode` flag in arguments
+    /// - Strip the flag before routing to handlers
+    /// - Set `agent_mode` in the Response for automatic JSON output
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .with_agent_mode()
+    ///     .with_state(state);
+    ///
+    /// // User runs: myapp --agent-mode build
+    /// // Router automatically handles the flag and outputs JSON
+    /// ```
+    pub fn with_agent_mode(mut self) -> Self {
+        self.config.agent_mode_enabled = true;
+        self
     }
-}
 
-impl Router<()> {
-    /// Execute a command using environment arguments.
+    /// Cap the command output included in agent-mode JSON at `bytes`.
     ///
-    /// This is the most common usage - automatically reads from `std::env::args()`.
+    /// Longer output is cut on a UTF-8 character boundary and the JSON gets
+    /// `"truncated": true` and `"total_bytes"`, so an agent knows more exists
+    /// and can ask for the rest (e.g. through the command's own
+    /// `--offset`/`--limit` options). Unlimited by default; human-readable
+    /// output is never truncated.
+    ///
+    /// The limit is applied when the router serializes a response with
+    /// [`Router::to_agent_json`].
     ///
     /// # Example
     ///
     /// ```ignore
-    /// #[tokio::main]
-    /// async fn main() {
-    ///     let router = Router::new()
-    ///         .route("build", build_handler)
-    ///         .route("test", test_handler);
+    /// let router = Router::new()
+    ///     .route("logs", handlers::logs)
+    ///     .with_agent_mode()
+    ///     .agent_output_limit(64 * 1024)
+    ///     .with_state(state);
     ///
-    ///     let response = router.execute().await;
-    ///     std::process::exit(response.exit_code);
+    /// let response = router.execute().await;
+    /// if response.agent_mode {
+    ///     println!("{}", router.to_agent_json(&response));
     /// }
     /// ```
-    pub async fn execute(&self) -> Response {
-        let args: Vec<String> = std::env::args().collect();
-        self.execute_with(&args).await
+    pub fn agent_output_limit(mut self, bytes: usize) -> Self {
+        self.config.agent_output_limit = Some(bytes);
+        self
     }
 
-    /// Execute a command with custom arguments.
+    /// Strip terminal escapes and control characters from agent-mode text.
     ///
-    /// Useful for testing or when you need to provide arguments programmatically.
-    /// The first element (`args[0]`) should be the program name (like `std::env::args()`).
+    /// A handler that shells out to a colorizing tool can pass ANSI escape
+    /// sequences through, which end up in the JSON envelope and confuse
+    /// agents. With this enabled, `Output::Text` of a command run with
+    /// `--agent-mode` has escape sequences removed, along with every control
+    /// character except `\n` and `\t`. Interactive output keeps its colors.
     ///
-    /// Supports both flat and nested command structures:
-    /// - `["myapp", "build"]` → matches route "build"
-    /// - `["myapp", "db", "create"]` → matches route "db:create"
-    /// - `["myapp", "db", "backup", "create"]` → matches route "db:backup:create"
+    /// # Example
     ///
-    /// Special handling:
-    /// - `--help` or `-h` → displays help message
-    /// - `version` → displays version (if metadata.version is set)
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("lint", handlers::lint) // runs a colorizing linter
+    ///     .with_agent_mode()
+    ///     .sanitize_agent_output()
+    ///     .with_state(state);
+    /// ```
+    pub fn sanitize_agent_output(mut self) -> Self {
+        self.config.sanitize_agent_output = true;
+        self
+    }
+
+    /// Enable the global `--quiet` / `-q` flag.
+    ///
+    /// When enabled, the router will:
+    /// - Detect and strip `--quiet` and `-q` from the arguments (like
+    ///   `--agent-mode`), so handlers never see them
+    /// - Replace the output of a successful command with `Output::Silent`
+    ///
+    /// Errors, exit codes, and help text are unaffected. Don't enable this if
+    /// a handler uses `-q` for something else.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// // Testing
-    /// let response = router.execute_with(&["myapp", "build", "--release"]).await;
-    /// assert_eq!(response.exit_code, 0);
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .with_quiet_flag()
+    ///     .with_state(state);
+    ///
+    /// // User runs: myapp build -q
+    /// // Nothing is printed on success; failures still print
     /// ```
+    pub fn with_quiet_flag(mut self) -> Self {
+        self.config.quiet_flag_enabled = true;
+        self
+    }
+
+    /// Enable the global `--dry-run` flag.
     ///
-    /// Returns a Response with exit code and output.
-    pub async fn execute_with(&self, args: &[String]) -> Response {
-        // Skip program name (args[0])
-        let command_args = if args.is_empty() { &[] } else { &args[1..] };
+    /// When enabled, the router will:
+    /// - Detect and strip `--dry-run` from the arguments (like
+    ///   `--agent-mode`), so handlers never see it in their args
+    /// - Expose it to handlers through the [`DryRun`] extractor
+    /// - Mark the response `changed: false` (see [`Response::changed`])
+    /// - List `--dry-run` once under the options in `--help`
+    ///
+    /// Plugin-backed commands receive `--dry-run` in their args instead,
+    /// since they can't use the extractor.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:drop", handlers::drop_table)
+    ///     .with_dry_run_flag()
+    ///     .with_state(state);
+    ///
+    /// // User runs: myapp db drop users --dry-run
+    /// // The handler gets DryRun(true) and args ["users"]
+    /// ```
+    pub fn with_dry_run_flag(mut self) -> Self {
+        self.config.dry_run_flag_enabled = true;
+        self
+    }
 
-        // Detect and strip --agent-mode flag if agent_mode is enabled
-        let (agent_mode_active, command_args) = if self.agent_mode_enabled {
-            let agent_mode = command_args.contains(&"--agent-mode".to_string());
-            let filtered: Vec<String> = command_args
-                .iter()
-                .filter(|arg| *arg != "--agent-mode")
-                .cloned()
-                .collect();
-            (agent_mode, filtered)
-        } else {
-            (false, command_args.to_vec())
-        };
+    /// Enable the global `--template <TEMPLATE>` flag.
+    ///
+    /// When enabled, the router will:
+    /// - Detect and strip `--template <t>` / `--template=<t>` from the
+    ///   arguments, so handlers never see it
+    /// - Render a successful command's structured result (its
+    ///   [`Response::with_data`] value, else `Output::Json`) through the
+    ///   template as text, once per element for an array
+    /// - List `--template` under the options in `--help`
+    ///
+    /// Templates insert fields with `{{name}}`, `{{a.b}}` or `{{items.0}}`.
+    /// An invalid template or a missing field is a
+    /// [`UserError::InvalidArgument`]. Text-only output and agent mode are
+    /// unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:list", handlers::list) // returns Response::text(..).with_data(..)
+    ///     .with_template_flag()
+    ///     .with_state(state);
+    ///
+    /// // User runs: myapp db list --template '{{name}} ({{engine}})'
+    /// // users (postgres)
+    /// // orders (mysql)
+    /// ```
+    pub fn with_template_flag(mut self) -> Self {
+        self.config.template_flag_enabled = true;
+        self
+    }
 
-        let command_args_slice: &[String] = &command_args;
+    /// Enable the global `--profile <NAME>` flag.
+    ///
+    /// When enabled, the router will:
+    /// - Detect and strip `--profile <name>` / `--profile=<name>` from the
+    ///   arguments, so handlers never see it
+    /// - Resolve the profile from the flag, then the `env_var` environment
+    ///   variable, then [`profile::DEFAULT_PROFILE`]
+    /// - Expose it to handlers through the [`Profile`] extractor
+    /// - List `--profile` under the options in `--help`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("deploy", handlers::deploy) // takes Profile(profile): Profile
+    ///     .with_profile_flag("MYCTL_PROFILE")
+    ///     .with_state(state);
+    ///
+    /// // User runs: myapp deploy --profile prod
+    /// // or:        MYCTL_PROFILE=prod myapp deploy
+    /// ```
+    pub fn with_profile_flag(mut self, env_var: impl Into<String>) -> Self {
+        self.config.profile_env = Some(env_var.into());
+        self
+    }
 
-        // Handle MCP flags if MCP is enabled
-        #[cfg(feature = "mcp")]
-        if self.mcp_enabled {
-            // Handle --mcp-server flag
-            if command_args_slice.contains(&"--mcp-server".to_string()) {
-                // Convert route_metadata to MCP tool schemas
-                let tools: Vec<crate::mcp::McpTool> = self
-                    .route_metadata
-                    .iter()
-                    .map(|(name, metadata)| {
-                        crate::mcp::McpTool::from_route_metadata(name.clone(), metadata)
-                    })
-                    .collect();
+    /// Append a JSON line per routed command to the file at `path`.
+    ///
+    /// Each line has `timestamp_ms`, `command`, `exit_code`, `duration_ms`,
+    /// and `agent_mode`; arguments are left out unless
+    /// [`Router::with_usage_log_args`] is set. Help, version, and unknown
+    /// commands are not recorded. Failing to write the log never affects
+    /// the command.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:create", handlers::db_create)
+    ///     .with_usage_log(sen::paths::data_dir("myctl").join("usage.jsonl"))
+    ///     .with_state(state);
+    /// ```
+    pub fn with_usage_log(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.usage_log = Some(path.into());
+        self
+    }
 
-                // Get program name for building full command args
-                let program_name = args
-                    .get(0)
-                    .cloned()
-                    .unwrap_or_else(|| "program".to_string());
+    /// Also record each command's arguments in the usage log.
+    ///
+    /// Arguments may contain tokens or passwords; only enable this when the
+    /// log stays on the user's machine.
+    pub fn with_usage_log_args(mut self) -> Self {
+        self.config.usage_log_args = true;
+        self
+    }
 
-                return crate::mcp::run_mcp_server(tools, |tool_name, tool_args| {
-                    // Build full args: ["program_name", "command", "subcommand", ...tool_args]
-                    let mut full_args = vec![program_name.clone()];
+    /// Keep a history of critical commands in the file at `path`.
+    ///
+    /// Each time a command with the `critical` tier succeeds and changes
+    /// something (not on `--dry-run` or `changed: false`), a JSON line with
+    /// `timestamp_ms`, `command`, `args`, and the handler's
+    /// [`Response::with_undo_hint`] is appended. Values of secret-looking
+    /// flags such as `--token` or `--password` are recorded as `***`. The
+    /// built-in `history` command lists the most recent entries. Failing to
+    /// write the file never affects the command.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:drop", handlers::db_drop) // #[sen::handler(tier = "critical")]
+    ///     .with_history(sen::paths::data_dir("myctl").join("history.jsonl"))
+    ///     .with_state(state);
+    ///
+    /// // $ myctl history
+    /// // 2024-06-10 06:13:20  db drop users
+    /// //     undo: myctl db restore users --from backup-42
+    /// ```
+    pub fn with_history(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.history = Some(path.into());
+        self
+    }
 
-                    // Split tool_name by ":" to get command path (e.g., "db:create" -> ["db", "create"])
-                    for part in tool_name.split(':') {
-                        full_args.push(part.to_string());
-                    }
+    /// Report a panicking handler as an error instead of unwinding.
+    ///
+    /// The panic becomes `SystemError::Internal("handler panicked: ...")`
+    /// with exit code 101, and is logged via `tracing` when that feature is
+    /// on. Useful when the router runs inside a REPL or a long-lived process.
+    ///
+    /// Off by default: state the handler was in the middle of changing is
+    /// left as the panic found it, so only enable this if handlers keep
+    /// shared state consistent (or never reuse it after an error).
+    pub fn catch_panics(mut self) -> Self {
+        self.config.catch_panics = true;
+        self
+    }
 
-                    // Add tool arguments
-                    full_args.extend(tool_args);
+    /// Declare an external tool the CLI runs, checked by [`Router::doctor`].
+    ///
+    /// `install_hint` tells the user how to get it, as in
+    /// `UserError::MissingDependency`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("deploy", handlers::deploy)
+    ///     .requires_tool("kubectl", "https://kubernetes.io/docs/tasks/tools/")
+    ///     .with_state(state);
+    /// ```
+    pub fn requires_tool(
+        mut self,
+        name: impl Into<String>,
+        install_hint: impl Into<String>,
+    ) -> Self {
+        self.config.required_tools.push(doctor::RequiredTool {
+            name: name.into(),
+            install_hint: install_hint.into(),
+        });
+        self
+    }
 
-                    // Execute the command (block on async function)
-                    futures::executor::block_on(self.execute_with(&full_args))
-                });
-            }
+    /// Name the CLI's config file, checked by [`Router::doctor`].
+    ///
+    /// The file must exist; with the `config` feature it must also parse
+    /// (TOML, YAML, or JSON by extension). A leading `~` is expanded.
+    pub fn with_config_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.config_file = Some(path.into());
+        self
+    }
 
-            // Handle --mcp-init flag
-            if let Some(pos) = command_args_slice
-                .iter()
-                .position(|arg| arg == "--mcp-init")
-            {
-                // Get client name (next argument after --mcp-init)
-                let client = command_args_slice
-                    .get(pos + 1)
-                    .map(|s| s.as_str())
-                    .unwrap_or("claude");
-
-                // Get command path (first arg in original args, or current executable)
-                let command_path = args
-                    .get(0)
-                    .cloned()
-                    .or_else(|| {
-                        std::env::current_exe()
-                            .ok()
-                            .and_then(|p| p.to_str().map(String::from))
-                    })
-                    .unwrap_or_else(|| "myctl".to_string());
+    /// Safety tier of `command`, from `#[sen::handler(tier = ...)]`.
+    ///
+    /// `command` is written as typed (`"db delete"`) or as a route key
+    /// (`"db:delete"`). Returns `None` for unknown commands and commands
+    /// without a tier, so an orchestrator can gate a command before running it.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if router.tier_of("db delete").is_some_and(|tier| tier.requires_approval()) {
+    ///     // ask a human first
+    /// }
+    /// ```
+    pub fn tier_of(&self, command: &str) -> Option<Tier> {
+        let key = command.split_whitespace().collect::<Vec<_>>().join(":");
+        self.route_metadata
+            .get(&self.canonical_key(&key))
+            .and_then(|meta| meta.handler_meta.as_ref())
+            .and_then(|h| h.tier)
+    }
 
-                // Convert route_metadata to MCP tool schemas
-                let tools: Vec<crate::mcp::McpTool> = self
-                    .route_metadata
-                    .iter()
-                    .map(|(name, metadata)| {
-                        crate::mcp::McpTool::from_route_metadata(name.clone(), metadata)
-                    })
-                    .collect();
+    /// Resolve unique command prefixes (like `cargo b` for `cargo build`).
+    ///
+    /// When enabled, each command segment may be abbreviated to any prefix
+    /// that matches exactly one registered command at that nesting level
+    /// (`db cr` resolves to `db:create`). Prefixes are only tried when no
+    /// route matches exactly at any depth, so with a `db` route, `db cr`
+    /// runs `db` with the argument `cr`. An ambiguous prefix is a user error
+    /// listing the candidates.
+    ///
+    /// This is opt-in because adding a command can make a previously unique
+    /// prefix ambiguous.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .route("bench", handlers::bench)
+    ///     .with_prefix_matching()
+    ///     .with_state(state);
+    ///
+    /// // $ myapp bu      -> build
+    /// // $ myapp b       -> Error: ambiguous (bench, build)
+    /// ```
+    pub fn with_prefix_matching(mut self) -> Self {
+        self.config.prefix_matching = true;
+        self
+    }
 
-                return crate::mcp::generate_mcp_config(client, command_path, tools);
+    /// Match command names ignoring case and treating `_` and `-` alike.
+    ///
+    /// `DB Create`, `db CREATE` and `db_create` then all resolve to a
+    /// `db:create` route. Each segment is compared this way; a single token
+    /// like `db_create` is also tried as the segments `db create` when no
+    /// command matches it as written. Exact names always win.
+    ///
+    /// A command typed exactly as registered is always matched first, at
+    /// any nesting depth; normalized matching is only tried when no route
+    /// matches exactly.
+    ///
+    /// This is opt-in so scripts relying on exact matching keep their
+    /// meaning.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two routes differ only in case or `_`/`-` (such as
+    /// `db:create` and `DB:Create`), whether registered before or after this
+    /// call, since a normalized command could not tell them apart.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("db:create", handlers::db_create)
+    ///     .normalize_commands()
+    ///     .with_state(state);
+    ///
+    /// // $ myapp DB Create   -> db:create
+    /// // $ myapp db_create   -> db:create
+    /// ```
+    pub fn normalize_commands(mut self) -> Self {
+        self.config.normalize_commands = true;
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        let mut keys: Vec<&String> = self.routes.keys().chain(self.aliases.keys()).collect();
+        keys.sort();
+        for key in keys {
+            if let Some(other) = seen.insert(normalized_key(key), key) {
+                panic!(
+                    "Routes '{}' and '{}' collide when command names are normalized",
+                    other, key
+                );
             }
         }
+        self
+    }
 
-        // Handle --help flag ONLY if no command is specified yet
-        // If a command is specified (e.g., "build --help"), let Clap handle it
-        if command_args_slice.is_empty()
-            || (command_args_slice.len() == 1
-                && (command_args_slice[0] == "--help" || command_args_slice[0] == "-h"))
-        {
-            // Show general CLI help
-            let mut response = self.generate_help(&[], false);
-            response.agent_mode = agent_mode_active;
-            return response;
-        }
-
-        // Handle --help --json for schema output
-        if command_args_slice.contains(&"--help".to_string())
-            && command_args_slice.contains(&"--json".to_string())
-        {
-            let mut response = self.generate_cli_schema_json();
-            response.agent_mode = agent_mode_active;
-            return response;
-        }
-
-        // Handle --help --md for AI/Agent-friendly markdown output
-        if command_args_slice.contains(&"--help".to_string())
-            && command_args_slice.contains(&"--md".to_string())
-        {
-            let mut response = self.generate_help_markdown();
-            response.agent_mode = agent_mode_active;
-            return response;
-        }
+    /// Use `separator` instead of `:` between nested command names.
+    ///
+    /// Applies to keys passed to `route`, `nest`, `describe`, `completer`,
+    /// `disable` and the other builder methods, and to command names shown
+    /// in help. Call it before registering routes. Commands are still typed
+    /// space-separated (`myctl db create`), and `--help --json` and MCP tool
+    /// names keep using `:`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .with_separator('/')
+    ///     .route("db/create", handlers::db_create)
+    ///     .nest("db/user", user_router)
+    ///     .with_state(state);
+    /// ```
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.config.separator = separator;
+        self
+    }
 
-        // Handle built-in version command
-        if command_args_slice.len() == 1
-            && (command_args_slice[0] == "version"
-                || command_args_slice[0] == "--version"
-                || command_args_slice[0] == "-V")
-        {
-            let mut response = self.handle_version();
-            response.agent_mode = agent_mode_active;
-            return response;
+    /// Panic if `key` is already a route or alias, or (with
+    /// `normalize_commands`) if it differs from one only in case or `_`/`-`.
+    fn assert_new_route(&self, key: &str) {
+        if self.routes.contains_key(key) || self.aliases.contains_key(key) {
+            panic!("Duplicate route: {}", key);
         }
-
-        // Try to match nested commands first (longest match wins)
-        // e.g., ["db", "create", "--flag"] tries:
-        //   1. "db:create" (found!)
-        //   2. "db" (fallback)
-        let (matched_handler, remaining_args) = self.find_route(command_args_slice);
-
-        let mut response = match matched_handler {
-            Some(handler) => {
-                let state = State::new(());
-                handler.call_boxed(state, remaining_args).await
-            }
-            None => {
-                let command = command_args_slice.join(" ");
-                let err: CliResult<()> =
-                    Err(CliError::user(format!("Unknown command: {}", command)));
-                err.into_response()
+        if self.config.normalize_commands {
+            let normalized = normalized_key(key);
+            let mut names = self.routes.keys().chain(self.aliases.keys());
+            if let Some(other) = names.find(|k| normalized_key(k) == normalized) {
+                panic!(
+                    "Routes '{}' and '{}' collide when command names are normalized",
+                    other, key
+                );
             }
-        };
+        }
+    }
 
-        // Set agent_mode flag if it was detected
-        response.agent_mode = agent_mode_active;
-        response
+    /// `command` with the configured separator replaced by `:`.
+    fn canonical_key(&self, command: &str) -> String {
+        command.replace(self.config.separator, ":")
     }
 
-    /// Generate help message based on router metadata and available commands.
-    fn generate_help(&self, _args: &[String], json_output: bool) -> Response {
-        if json_output {
-            self.generate_cli_schema_json()
-        } else {
-            self.generate_help_text()
-        }
+    /// Route `key` written with the configured separator, for help output.
+    fn display_key(&self, key: &str) -> String {
+        key.replace(':', &self.config.separator.to_string())
     }
 
-    /// Generate clean, human-readable help text for terminal display.
+    /// Accept unambiguous abbreviations of long flags in clap-parsed args.
     ///
-    /// Supports colored output when stdout is a terminal.
-    fn generate_help_text(&self) -> Response {
-        use anstyle::{AnsiColor, Effects, Style};
-
-        // Define styles
-        let use_color = std::io::IsTerminal::is_terminal(&std::io::stdout());
-
-        let header_style = if use_color {
-            Style::new()
-                .fg_color(Some(AnsiColor::Green.into()))
-                .effects(Effects::BOLD)
-        } else {
-            Style::new()
-        };
+    /// Applies to `Args<T>` and `nest_with_args::<T>` where `T: clap::Parser`:
+    /// `--rel` resolves to `--release`, while an abbreviation shared by
+    /// several flags (`--re` for `--release` and `--remote`) is still an
+    /// error. Off by default, because adding a flag can make a previously
+    /// unique abbreviation ambiguous.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .infer_long_args(true)
+    ///     .with_state(state);
+    ///
+    /// // $ myapp build --rel   -> BuildArgs { release: true, .. }
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn infer_long_args(mut self, enabled: bool) -> Self {
+        self.config.infer_long_args = enabled;
+        self
+    }
 
-        let section_style = if use_color {
-            Style::new()
-                .fg_color(Some(AnsiColor::Yellow.into()))
-                .effects(Effects::BOLD)
-        } else {
-            Style::new()
-        };
+    /// Enable, disable, or rename the built-in help and version handling.
+    ///
+    /// By default `--help`/`-h` print the generated help and `version`,
+    /// `--version` and `-V` print the version. Disable a built-in when the
+    /// app handles it itself.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("version", handlers::version)
+    ///     .route("status", handlers::status)
+    ///     .builtins(BuiltinsConfig {
+    ///         version: None,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_state(state);
+    ///
+    /// // $ myctl version    -> handlers::version
+    /// // $ myctl --version  -> Error: unknown command
+    /// ```
+    pub fn builtins(mut self, config: BuiltinsConfig) -> Self {
+        self.config.builtins = config;
+        self
+    }
 
-        let cmd_style = if use_color {
-            Style::new().fg_color(Some(AnsiColor::Cyan.into()))
-        } else {
-            Style::new()
-        };
+    /// Enable MCP (Model Context Protocol) support.
+    ///
+    /// When enabled, the router will recognize and handle MCP-specific flags:
+    /// - `--mcp-server`: Start in MCP server mode (JSON-RPC over stdio)
+    /// - `--mcp-init <client>`: Generate MCP configuration for the specified client
+    /// - `--mcp-call <tool> '<json-args>'`: Run one tool call through the MCP
+    ///   server code path, print the JSON-RPC response, and exit
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .with_mcp()
+    ///     .with_state(state);
+    ///
+    /// // Usage:
+    /// // $ mycli --mcp-server              # Start MCP server
+    /// // $ mycli --mcp-init claude         # Generate claude_desktop_config.json
+    /// // $ mycli --mcp-call build '{"release": true}'
+    /// ```
+    #[cfg(feature = "mcp")]
+    pub fn with_mcp(mut self) -> Self {
+        self.config.mcp_enabled = true;
+        self
+    }
 
-        let dim_style = if use_color {
-            Style::new().effects(Effects::DIMMED)
-        } else {
-            Style::new()
-        };
+    /// Rename the MCP tool that returns the command catalog.
+    ///
+    /// `with_mcp` registers a `__describe` tool next to the command tools.
+    /// Calling it returns the same JSON as `--help --json`, optionally
+    /// limited with a `--tag` argument. A route with the same name takes
+    /// precedence over the tool.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("build", handlers::build)
+    ///     .with_mcp()
+    ///     .with_mcp_describe_tool("catalog")
+    ///     .with_state(state);
+    /// ```
+    #[cfg(feature = "mcp")]
+    pub fn with_mcp_describe_tool(mut self, name: impl Into<String>) -> Self {
+        self.config.mcp_describe_tool = name.into();
+        self
+    }
 
-        let reset = if use_color {
-            Style::new().render_reset().to_string()
-        } else {
-            String::new()
-        };
+    /// Forward handler progress events to a channel instead of rendering them.
+    ///
+    /// By default the router renders `Progress` updates to stderr (as a status
+    /// line in interactive terminals, or as NDJSON in agent mode). Use this to
+    /// drive a custom spinner or to collect events in tests.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    /// let router = Router::new()
+    ///     .route("deploy", handlers::deploy)
+    ///     .with_progress_sink(tx)
+    ///     .with_state(state);
+    /// ```
+    pub fn with_progress_sink(
+        mut self,
+        sink: tokio::sync::mpsc::UnboundedSender<ProgressEvent>,
+    ) -> Self {
+        self.config.progress_sink = Some(sink);
+        self
+    }
 
-        let mut help = String::new();
+    /// Send streamed output and rendered progress to `writer`.
+    ///
+    /// By default, `Emitter` lines go to stdout and progress is rendered to
+    /// stderr (only in interactive terminals or agent mode). With a writer set,
+    /// both go to the writer, and progress is always rendered one event per line
+    /// (NDJSON in agent mode). This lets tests and embedding programs capture
+    /// everything the router emits besides the final `Response`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+    /// let router = Router::new()
+    ///     .route("deploy", handlers::deploy)
+    ///     .with_writer(buffer.clone())
+    ///     .with_state(state);
+    /// ```
+    pub fn with_writer<W>(mut self, writer: Arc<std::sync::Mutex<W>>) -> Self
+    where
+        W: std::io::Write + Send + 'static,
+    {
+        self.config.writer = Some(writer);
+        self
+    }
 
-        // Header: name + version + about
-        if let Some(meta) = &self.metadata {
-            help.push_str(&format!("{}", header_style.render()));
-            help.push_str(meta.name);
-            if let Some(version) = meta.version {
-                help.push_str(&format!(" {}", version));
+    /// Cancel the running command on Ctrl-C instead of killing the process.
+    ///
+    /// Installs a `tokio::signal::ctrl_c` listener while a handler runs. On
+    /// signal, the handler's `Cancel` token is cancelled and the router waits
+    /// for the handler to finish its cleanup; `execute_with` then returns a
+    /// `UserError::Cancelled` response (exit code 130). A second Ctrl-C stops
+    /// waiting.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// async fn sync(state: State<AppState>, cancel: Cancel, Args(args): Args<SyncArgs>) -> CliResult<String> {
+    ///     tokio::select! {
+    ///         result = do_sync(&args) => result,
+    ///         _ = cancel.cancelled() => {
+    ///             cleanup().await;
+    ///             Err(CliError::User(UserError::Cancelled))
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let router = Router::new()
+    ///     .route("sync", sync)
+    ///     .with_cancellation()
+    ///     .with_state(state);
+    /// ```
+    pub fn with_cancellation(self) -> Self {
+        self.with_cancellation_signal(|| async {
+            if tokio::signal::ctrl_c().await.is_err() {
+                // No signal handler available: never cancel
+                std::future::pending::<()>().await;
             }
-            help.push_str(&reset);
-            help.push('\n');
+        })
+    }
 
-            if let Some(about) = meta.about {
-                help.push_str(about);
-                help.push('\n');
-            }
-            help.push('\n');
-        }
+    /// Cancel the running command when `signal` resolves.
+    ///
+    /// Like [`Router::with_cancellation`], but with a custom signal source
+    /// (e.g. SIGTERM, a shutdown channel, or a test trigger). `signal` is
+    /// called once per wait, so it should return a fresh future each time.
+    pub fn with_cancellation_signal<F, Fut>(mut self, signal: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.config.cancel_signal = Some(Arc::new(move || Box::pin(signal())));
+        self
+    }
 
-        // Usage line
-        let cli_name = self
-            .metadata
-            .as_ref()
-            .map(|m| m.name)
-            .unwrap_or("<command>");
-        help.push_str(&format!(
-            "{}Usage:{} {} [OPTIONS] <COMMAND>\n\n",
-            section_style.render(),
-            reset,
-            cli_name
-        ));
+    /// Fail commands that run longer than `timeout`.
+    ///
+    /// A handler's own `#[sen::handler(timeout = "...")]` overrides this
+    /// default. When the budget expires, the handler future is dropped and the
+    /// command fails with `SystemError::Timeout` (exit code 124), so handlers
+    /// must be cancellation-safe: anything that has to happen on every exit
+    /// belongs in a guard's `Drop`, not after an `.await`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let router = Router::new()
+    ///     .route("sync", sync)
+    ///     .with_default_timeout(Duration::from_secs(60))
+    ///     .with_state(state);
+    /// ```
+    pub fn with_default_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.default_timeout = Some(timeout);
+        self
+    }
 
-        // Group commands by prefix
-        let grouped_commands = self.group_commands_by_prefix();
-
-        // Calculate global max length for consistent alignment across all groups
-        let global_max_len = grouped_commands
-            .iter()
-            .flat_map(|(_, cmds)| cmds.iter().map(|(name, _, _)| name.len()))
-            .max()
-            .unwrap_or(8)
-            .max(8); // Minimum 8 chars
-
-        // Display grouped commands
-        for (group_name, commands) in &grouped_commands {
-            if !group_name.is_empty() {
-                help.push_str(&format!(
-                    "{}{}:{}\n",
-                    section_style.render(),
-                    group_name,
-                    reset
-                ));
-            } else {
-                help.push_str(&format!("{}Commands:{}\n", section_style.render(), reset));
-            }
+    /// Provide the application state, converting `Router<S>` to `Router<()>`.
+    ///
+    /// This follows Axum's pattern where the type system ensures all required
+    /// state is provided before the router can execute requests.
+    ///
+    /// The state is wrapped in a single [`State`] shared by every command, so
+    /// a `state.write()` in one command is visible to the next one run on the
+    /// same router (e.g. in a REPL or server).
+    pub fn with_state(self, state: S) -> Router<()> {
+        self.with_shared_state(State::new(state))
+    }
 
-            for (name, _full_name, desc) in commands {
-                if desc.is_empty() {
-                    help.push_str(&format!("  {}{}{}\n", cmd_style.render(), name, reset));
-                } else {
-                    help.push_str(&format!(
-                        "  {}{:width$}{}  {}{}{}\n",
-                        cmd_style.render(),
-                        name,
-                        reset,
-                        dim_style.render(),
-                        desc,
-                        reset,
-                        width = global_max_len
-                    ));
-                }
-            }
-            help.push('\n');
-        }
+    /// Provide an already built [`State`] handle, converting `Router<S>` to
+    /// `Router<()>`.
+    ///
+    /// Keep a clone of the handle to read or update the state from outside
+    /// the router, or share one state between several routers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let state = State::new(AppState::default());
+    /// let router = Router::new()
+    ///     .route("add", add)
+    ///     .with_shared_state(state.clone());
+    ///
+    /// router.execute_with(&args).await;
+    /// println!("{} items", state.read().await.items.len());
+    /// ```
+    pub fn with_shared_state(self, state: State<S>) -> Router<()> {
+        let routes: HashMap<String, Box<dyn ErasedHandler<()>>> = self
+            .routes
+            .into_iter()
+            .map(|(cmd, handler)| {
+                let state = state.clone();
+                let boxed: Box<dyn ErasedHandler<()>> =
+                    Box::new(StatefulHandler { handler, state });
+                (cmd, boxed)
+            })
+            .collect();
 
-        // Options section
-        help.push_str(&format!("{}Options:{}\n", section_style.render(), reset));
-        help.push_str(&format!(
-            "  {}-h{}, {}--help{}            Print help\n",
-            cmd_style.render(),
-            reset,
-            cmd_style.render(),
-            reset
-        ));
-        if self.metadata.as_ref().and_then(|m| m.version).is_some() {
-            help.push_str(&format!(
-                "  {}-V{}, {}--version{}         Print version\n",
-                cmd_style.render(),
-                reset,
-                cmd_style.render(),
-                reset
-            ));
+        Router {
+            routes,
+            route_metadata: self.route_metadata,
+            group_descriptions: self.group_descriptions,
+            scoped_args: self.scoped_args,
+            completers: self.completers,
+            aliases: self.aliases,
+            config: self.config,
+            stdin_state: None,
+            _marker: PhantomData,
         }
-        help.push('\n');
-
-        // Alternative formats for automation
-        help.push_str(&format!(
-            "{}For AI/Agent:{}\n",
-            section_style.render(),
-            reset
-        ));
-        help.push_str(&format!(
-            "      {}--help --md{}       Print help in Markdown format\n",
-            cmd_style.render(),
-            reset
-        ));
-        help.push('\n');
-        help.push_str(&format!(
-            "{}For Programs:{}\n",
-            section_style.render(),
-            reset
-        ));
-        help.push_str(&format!(
-            "      {}--help --json{}     Print CLI schema in JSON format\n",
-            cmd_style.render(),
-            reset
-        ));
-
-        Response::text(help)
     }
 
-    /// Generate Markdown-formatted help for AI/Agent consumption.
+    /// Accept the whole state as JSON on stdin when `--state-stdin` is passed.
     ///
-    /// This format is designed to be:
-    /// - Parseable by LLMs and agents
-    /// - Rich with metadata (tiers, tags, arguments)
-    /// - Suitable for documentation generation
-    fn generate_help_markdown(&self) -> Response {
-        let mut md = String::new();
-
-        // Header
-        let cli_name = self.metadata.as_ref().map(|m| m.name).unwrap_or("CLI");
-        let version = self
-            .metadata
-            .as_ref()
-            .and_then(|m| m.version)
-            .unwrap_or("0.0.0");
-        let about = self
-            .metadata
-            .as_ref()
-            .and_then(|m| m.about)
-            .unwrap_or("Command-line interface");
-
-        md.push_str(&format!("# {} v{}\n\n", cli_name, version));
-        md.push_str(&format!("{}\n\n", about));
-
-        // Usage
-        md.push_str("## Usage\n\n");
-        md.push_str(&format!("```\n{} [OPTIONS] <COMMAND>\n```\n\n", cli_name));
+    /// Applies to [`Router::try_run`] and [`Router::try_run_with`]: with the
+    /// flag, stdin is read to the end, deserialized as `T`, and converted
+    /// into the state, and the state future passed to `try_run` (files,
+    /// env, ...) is never polled. `T` is usually `S` itself, or a smaller
+    /// struct with `impl From<T> for S`. Malformed input fails with
+    /// `SystemError::ConfigParse`.
+    ///
+    /// Without the flag stdin is left untouched, so handlers can still read
+    /// piped data.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // echo '{"env":"prod"}' | myctl --state-stdin status
+    /// Router::new()
+    ///     .route("status", handlers::status)
+    ///     .with_stdin_state::<AppState>()
+    ///     .try_run(AppState::load())
+    ///     .await
+    ///     .exit();
+    /// ```
+    pub fn with_stdin_state<T>(mut self) -> Self
+    where
+        T: serde::de::DeserializeOwned + Into<S> + 'static,
+    {
+        self.stdin_state = Some(Arc::new(|input| {
+            serde_json::from_str::<T>(input)
+                .map(Into::into)
+                .map_err(|e| {
+                    CliError::System(SystemError::ConfigParse(format!(
+                        "Invalid state on stdin: {}",
+                        e
+                    )))
+                })
+        }));
+        self
+    }
 
-        // Commands by group
-        md.push_str("## Commands\n\n");
+    /// Build the state asynchronously, then execute a command from
+    /// `std::env::args()`.
+    ///
+    /// If the state cannot be built, its `CliError` is returned as an error
+    /// `Response` (flagged for agent mode when `--agent-mode` was passed to a
+    /// router built with `with_agent_mode()`), so `main` needs no error
+    /// formatting of its own.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     Router::new()
+    ///         .route("status", handlers::status)
+    ///         .try_run(AppState::load())
+    ///         .await
+    ///         .exit();
+    /// }
+    /// ```
+    pub async fn try_run<F>(self, state: F) -> Response
+    where
+        F: Future<Output = Result<S, CliError>>,
+    {
+        let args: Vec<String> = std::env::args().collect();
+        self.try_run_with(&args, state).await
+    }
 
-        let grouped_commands = self.group_commands_by_prefix();
+    /// Like [`Router::try_run`], with custom arguments (`args[0]` is the
+    /// program name).
+    pub async fn try_run_with<F>(self, args: &[String], state: F) -> Response
+    where
+        F: Future<Output = Result<S, CliError>>,
+    {
+        self.try_run_from(args, tokio::io::stdin(), state).await
+    }
 
-        for (group_name, commands) in &grouped_commands {
-            if !group_name.is_empty() && group_name != "Other Commands" {
-                md.push_str(&format!("### {}\n\n", group_name));
-            } else if group_name == "Other Commands" {
-                md.push_str("### Other Commands\n\n");
+    /// [`Router::try_run_with`] reading `--state-stdin` input from `stdin`.
+    async fn try_run_from<R, F>(self, args: &[String], stdin: R, state: F) -> Response
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        F: Future<Output = Result<S, CliError>>,
+    {
+        let from_stdin =
+            self.stdin_state.is_some() && args.iter().skip(1).any(|a| a == STATE_STDIN_FLAG);
+        let (args, state) = match &self.stdin_state {
+            Some(parse) if from_stdin => {
+                let args: Vec<String> = args
+                    .iter()
+                    .filter(|a| *a != STATE_STDIN_FLAG)
+                    .cloned()
+                    .collect();
+                (args, read_stdin_state(stdin, parse.as_ref()).await)
             }
+            _ => (args.to_vec(), state.await),
+        };
 
-            md.push_str("| Command | Description | Tier | Tags |\n");
-            md.push_str("|---------|-------------|------|------|\n");
-
-            for (_name, full_name, desc) in commands {
-                // Get metadata for this command
-                let meta = self.route_metadata.get(full_name.as_str());
-                let handler_meta = meta.and_then(|m| m.handler_meta.as_ref());
-
-                let tier = handler_meta
-                    .and_then(|h| h.tier)
-                    .map(|t| t.as_str())
-                    .unwrap_or("-");
-
-                let tags = handler_meta
-                    .and_then(|h| h.tags.as_ref())
-                    .map(|t| t.join(", "))
-                    .unwrap_or_else(|| "-".to_string());
-
-                let desc_escaped = if desc.is_empty() {
-                    "-".to_string()
-                } else {
-                    desc.replace('|', "\\|")
-                };
-
-                md.push_str(&format!(
-                    "| `{}` | {} | {} | {} |\n",
-                    full_name, desc_escaped, tier, tags
-                ));
+        match state {
+            Ok(state) => self.with_state(state).execute_argv(&args).await,
+            Err(e) => {
+                let mut response = error_response(e);
+                response.agent_mode = self.config.agent_mode_enabled
+                    && args.iter().skip(1).any(|a| a == "--agent-mode");
+                response
             }
-            md.push('\n');
-        }
-
-        // Options
-        md.push_str("## Options\n\n");
-        md.push_str("| Option | Description |\n");
-        md.push_str("|--------|-------------|\n");
-        md.push_str("| `-h, --help` | Print help |\n");
-        md.push_str("| `--help --md` | Print help (Markdown format) |\n");
-        md.push_str("| `--help --json` | Print CLI schema (JSON format) |\n");
-        if self.metadata.as_ref().and_then(|m| m.version).is_some() {
-            md.push_str("| `-V, --version` | Print version |\n");
         }
-        md.push('\n');
-
-        // Command Details (for AI context)
-        md.push_str("## Command Details\n\n");
-
-        let mut sorted_commands: Vec<_> = self.routes.keys().collect();
-        sorted_commands.sort();
-
-        for cmd in sorted_commands {
-            let meta = self.route_metadata.get(cmd.as_str());
-            let handler_meta = meta.and_then(|m| m.handler_meta.as_ref());
-            let desc = handler_meta.and_then(|h| h.desc).unwrap_or("");
+    }
+}
 
-            md.push_str(&format!("### `{}`\n\n", cmd));
+/// Read `stdin` to the end and parse it into the state.
+async fn read_stdin_state<S, R>(
+    mut stdin: R,
+    parse: &(dyn Fn(&str) -> Result<S, CliError> + Send + Sync),
+) -> Result<S, CliError>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut input = String::new();
+    stdin.read_to_string(&mut input).await.map_err(|e| {
+        CliError::System(SystemError::ConfigParse(format!(
+            "Failed to read state from stdin: {}",
+            e
+        )))
+    })?;
+    parse(&input)
+}
 
-            if !desc.is_empty() {
-                md.push_str(&format!("{}\n\n", desc));
-            }
+/// Handler that has been bound to a state
+struct StatefulHandler<S> {
+    handler: Box<dyn ErasedHandler<S>>,
+    state: State<S>,
+}
 
-            // Show usage
-            md.push_str(&format!(
-                "```\n{} {}\n```\n\n",
-                cli_name,
-                cmd.replace(':', " ")
-            ));
+impl<S> Clone for StatefulHandler<S> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            state: self.state.clone(),
         }
+    }
+}
 
-        Response::text(md)
+impl<S> ErasedHandler<()> for StatefulHandler<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn try_call_boxed<'a>(
+        &'a self,
+        _state: State<()>,
+        args: Vec<String>,
+    ) -> BoxFuture<'a, Result<Response, CliError>> {
+        let handler = self.handler.clone();
+        let state = self.state.clone();
+        Box::pin(async move { handler.try_call_boxed(state, args).await })
     }
 
-    /// Group commands by their prefix (e.g., "db:*" -> "Database Commands").
-    /// Returns groups in order: named groups first (sorted), then "Other Commands" last.
-    fn group_commands_by_prefix(&self) -> GroupedCommands {
-        use std::collections::HashMap;
+    fn clone_box(&self) -> Box<dyn ErasedHandler<()>> {
+        Box::new(self.clone())
+    }
 
-        let mut groups: HashMap<String, Vec<CommandEntry>> = HashMap::new();
-        let mut commands: Vec<_> = self.routes.keys().collect();
-        commands.sort();
+    fn metadata(&self) -> Option<HandlerMetadata> {
+        self.handler.metadata()
+    }
 
-        for cmd in commands {
-            let desc = self
-                .route_metadata
-                .get(cmd.as_str())
-                .and_then(|meta| meta.get_description())
-                .unwrap_or("");
+    fn args_schema(&self) -> Option<serde_json::Value> {
+        self.handler.args_schema()
+    }
 
-            // Check if command has a prefix (e.g., "db:create" -> prefix "db")
-            if let Some(colon_pos) = cmd.find(':') {
-                let prefix = &cmd[..colon_pos];
-                let suffix = &cmd[colon_pos + 1..];
+    fn handles_dry_run(&self) -> bool {
+        self.handler.handles_dry_run()
+    }
+}
 
-                // Generate group name (e.g., "db" -> "Database Commands")
-                let group_name = self.format_group_name(prefix);
+impl Router<()> {
+    /// Execute a command using environment arguments.
+    ///
+    /// This is the most common usage - automatically reads from `std::env::args()`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let router = Router::new()
+    ///         .route("build", build_handler)
+    ///         .route("test", test_handler);
+    ///
+    ///     let response = router.execute().await;
+    ///     std::process::exit(response.exit_code);
+    /// }
+    /// ```
+    pub async fn execute(&self) -> Response {
+        let args: Vec<String> = std::env::args().collect();
+        self.execute_argv(&args).await
+    }
 
-                groups.entry(group_name).or_default().push((
-                    suffix.to_string(),
-                    cmd.to_string(),
-                    desc.to_string(),
-                ));
-            } else {
-                // No prefix - add to "Other Commands"
-                groups
-                    .entry("Other Commands".to_string())
-                    .or_default()
-                    .push((cmd.to_string(), cmd.to_string(), desc.to_string()));
+    /// Execute a command from the process's own arguments.
+    ///
+    /// Like [`Router::execute_with`], but first expands response files:
+    ///
+    /// - `@file` → replaced by the arguments read from `file` (one per line)
+    /// - `@@value` → passed through as the literal `@value`
+    ///
+    /// [`Router::execute`] and [`Router::try_run`] use this. Only pass
+    /// arguments the user typed: `@path` reads any file the process can.
+    /// MCP tool calls go through [`Router::execute_with`] and are never
+    /// expanded.
+    pub async fn execute_argv(&self, args: &[String]) -> Response {
+        match expand_response_files(args) {
+            Ok(expanded) => self.execute_with(&expanded).await,
+            Err(e) => {
+                let agent_mode = self.config.agent_mode_enabled
+                    && args.iter().skip(1).any(|a| a == "--agent-mode");
+                let mut response = error_response(e);
+                self.finish_response(&mut response, agent_mode);
+                response
             }
         }
+    }
 
-        // Sort commands within each group
-        for (_, commands) in groups.iter_mut() {
-            commands.sort_by(|a, b| a.0.cmp(&b.0));
-        }
+    /// Execute a command with custom arguments.
+    ///
+    /// Useful for testing or when you need to provide arguments programmatically.
+    /// The first element (`args[0]`) should be the program name (like `std::env::args()`).
+    ///
+    /// Supports both flat and nested command structures:
+    /// - `["myapp", "build"]` → matches route "build"
+    /// - `["myapp", "db", "create"]` → matches route "db:create"
+    /// - `["myapp", "db", "backup", "create"]` → matches route "db:backup:create"
+    ///
+    /// Special handling:
+    /// - `--help` or `-h` → displays help message
+    /// - `version` → displays version (if metadata.version is set)
+    ///
+    /// `@file` response files are left as they are; see [`Router::execute_argv`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Testing
+    /// let response = router.execute_with(&["myapp", "build", "--release"]).await;
+    /// assert_eq!(response.exit_code, 0);
+    /// ```
+    ///
+    /// Returns a Response with exit code and output.
+    pub async fn execute_with(&self, args: &[String]) -> Response {
+        let (result, agent_mode_active) = self.dispatch(args).await;
+        let mut response = result.unwrap_or_else(error_response);
+        self.finish_response(&mut response, agent_mode_active);
+        response
+    }
 
-        // Sort groups: named groups alphabetically, "Other Commands" last
-        let mut result: Vec<_> = groups.into_iter().collect();
-        result.sort_by(|a, b| {
-            if a.0 == "Other Commands" {
-                std::cmp::Ordering::Greater
-            } else if b.0 == "Other Commands" {
-                std::cmp::Ordering::Less
-            } else {
-                a.0.cmp(&b.0)
-            }
-        });
+    /// Execute a command, returning errors as typed [`CliError`]s.
+    ///
+    /// Behaves like [`Router::execute_with`], except that failures (unknown
+    /// commands, argument parsing errors, and handler errors) are returned as
+    /// `Err` instead of being folded into an error `Response`. This lets
+    /// programs that embed a router inspect `UserError`/`SystemError` variants.
+    ///
+    /// Help output (`UserError::Help`) is a successful exit and is returned as
+    /// `Ok`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// match router.try_execute_with(&args).await {
+    ///     Ok(response) => println!("{}", response.output),
+    ///     Err(CliError::User(UserError::InvalidArgument { arg, .. })) => {
+    ///         eprintln!("bad argument: {}", arg);
+    ///     }
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// ```
+    pub async fn try_execute_with(&self, args: &[String]) -> Result<Response, CliError> {
+        let (result, agent_mode_active) = self.dispatch(args).await;
+        result.map(|mut response| {
+            self.finish_response(&mut response, agent_mode_active);
+            response
+        })
+    }
 
-        result
+    /// Serialize `response` to agent JSON, with `output` cut to this
+    /// router's [`Router::agent_output_limit`].
+    #[cfg(feature = "sensors")]
+    pub fn to_agent_json(&self, response: &Response) -> String {
+        response.to_agent_json_with_limit(self.config.agent_output_limit)
     }
 
-    /// Format a command prefix into a nice group name.
-    /// Examples: "db" -> "Database Commands", "config" -> "Configuration Commands"
-    fn format_group_name(&self, prefix: &str) -> String {
-        let capitalized = match prefix {
-            "db" => "Database",
-            "config" => "Configuration",
-            "deploy" => "Deployment",
-            "server" => "Server",
-            "network" => "Network",
-            "storage" => "Storage",
-            _ => {
-                // Default: capitalize first letter
-                let mut chars = prefix.chars();
-                match chars.next() {
-                    None => return "Commands".to_string(),
-                    Some(first) => {
-                        return format!(
-                            "{}{} Commands",
-                            first.to_uppercase(),
-                            chars.collect::<String>()
-                        );
-                    }
-                }
+    /// Apply the router's agent-mode settings to a command's response.
+    fn finish_response(&self, response: &mut Response, agent_mode_active: bool) {
+        response.agent_mode = agent_mode_active;
+        if agent_mode_active && self.config.sanitize_agent_output {
+            if let Output::Text(text) = &mut response.output {
+                *text = strip_control_chars(text);
             }
-        };
-        format!("{} Commands", capitalized)
+        }
     }
 
-    /// Generate CLI schema JSON specification.
+    /// Route and run a command.
     ///
-    /// Outputs a CLI-friendly JSON format that includes all commands with their
-    /// arguments, options, and metadata in a single dump.
-    fn generate_cli_schema_json(&self) -> Response {
-        use serde_json::json;
-
-        let name = self.metadata.as_ref().map(|m| m.name).unwrap_or("cli");
-        let version = self
-            .metadata
-            .as_ref()
-            .and_then(|m| m.version)
-            .unwrap_or("unknown");
-        let description = self.metadata.as_ref().and_then(|m| m.about);
-
-        let mut commands = serde_json::Map::new();
-
-        // Collect all routes and their metadata
-        let mut command_names: Vec<_> = self.routes.keys().collect();
-        command_names.sort();
-
-        for cmd in command_names {
-            // Get handler metadata
-            let handler_meta = self
-                .route_metadata
-                .get(cmd)
-                .and_then(|meta| meta.handler_meta.as_ref());
-
-            // Get handler description
-            let desc = handler_meta
-                .and_then(|h| h.desc)
-                .unwrap_or("No description available");
+    /// Returns the result together with whether agent mode was requested, so
+    /// callers can flag error responses as well.
+    async fn dispatch(&self, args: &[String]) -> (Result<Response, CliError>, bool) {
+        // Skip program name (args[0])
+        let command_args = if args.is_empty() { &[] } else { &args[1..] };
 
-            // Get tier information
-            let tier = handler_meta.and_then(|h| h.tier).map(|t| t.as_str());
+        // Detect and strip --agent-mode flag if agent_mode is enabled
+        let (agent_mode_active, command_args) = if self.config.agent_mode_enabled {
+            let agent_mode = command_args.contains(&"--agent-mode".to_string());
+            let filtered: Vec<String> = command_args
+                .iter()
+                .filter(|arg| *arg != "--agent-mode")
+                .cloned()
+                .collect();
+            (agent_mode, filtered)
+        } else {
+            (false, command_args.to_vec())
+        };
 
-            // Get tags
-            let tags = handler_meta.and_then(|h| h.tags.as_ref());
+        // Strip the opt-in flags (--quiet, --dry-run, --template, --profile)
+        let (flags, command_args) = match self.take_global_flags(command_args) {
+            Ok(found) => found,
+            Err(e) => return (Err(e), agent_mode_active),
+        };
 
-            // Build usage string
-            let usage = format!("{} {}", name, cmd.replace(':', " "));
+        let command_args_slice: &[String] = &command_args;
 
-            let mut command_schema = json!({
-                "description": desc,
-                "usage": usage,
-            });
+        // Handle MCP flags if MCP is enabled
+        #[cfg(feature = "mcp")]
+        if self.config.mcp_enabled {
+            // Get program name for building full command args
+            let program_name = args
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "program".to_string());
 
-            // Add tier if available
-            if let Some(tier_str) = tier {
-                command_schema["tier"] = json!(tier_str);
-                command_schema["requires_approval"] = json!(Tier::parse(tier_str)
-                    .map(|t| t.requires_approval())
-                    .unwrap_or(false));
+            // Handle --mcp-server flag
+            if command_args_slice.contains(&"--mcp-server".to_string()) {
+                let response =
+                    crate::mcp::run_mcp_server(self.mcp_tools(), |tool_name, tool_args| {
+                        self.execute_mcp_tool(&program_name, tool_name, tool_args)
+                    });
+                return (Ok(response), false);
             }
 
-            // Add tags if available
-            if let Some(tag_list) = tags {
-                command_schema["tags"] = json!(tag_list);
+            // Handle --mcp-call <tool> '<json-args>' (one call, for debugging)
+            if let Some(pos) = command_args_slice
+                .iter()
+                .position(|arg| arg == "--mcp-call")
+            {
+                let Some(tool_name) = command_args_slice.get(pos + 1) else {
+                    return (
+                        Err(CliError::user("--mcp-call requires a tool name")),
+                        false,
+                    );
+                };
+                let arguments = command_args_slice
+                    .get(pos + 2)
+                    .map(|s| s.as_str())
+                    .unwrap_or("{}");
+
+                let response = crate::mcp::call_mcp_tool(
+                    &self.mcp_tools(),
+                    tool_name,
+                    arguments,
+                    |tool_name, tool_args| {
+                        self.execute_mcp_tool(&program_name, tool_name, tool_args)
+                    },
+                );
+                return (Ok(response), false);
             }
 
-            // Add argument schema if available
-            if let Some(meta) = self.route_metadata.get(cmd) {
-                if let Some(args_schema) = &meta.args_schema {
-                    command_schema["arguments"] = args_schema["arguments"].clone();
-                    command_schema["options"] = args_schema["options"].clone();
-                }
-            }
+            // Handle --mcp-init flag
+            if let Some(pos) = command_args_slice
+                .iter()
+                .position(|arg| arg == "--mcp-init")
+            {
+                // Get client name (next argument after --mcp-init)
+                let client = command_args_slice
+                    .get(pos + 1)
+                    .map(|s| s.as_str())
+                    .unwrap_or("claude");
 
-            commands.insert(cmd.to_string(), command_schema);
-        }
+                // Get command path (first arg in original args, or current executable)
+                let command_path = args
+                    .first()
+                    .cloned()
+                    .or_else(|| {
+                        std::env::current_exe()
+                            .ok()
+                            .and_then(|p| p.to_str().map(String::from))
+                    })
+                    .unwrap_or_else(|| "myctl".to_string());
 
-        let spec = json!({
-            "name": name,
-            "version": version,
-            "description": description.unwrap_or(""),
-            "commands": commands,
-        });
+                let response =
+                    crate::mcp::generate_mcp_config(client, command_path, self.mcp_tools());
+                return (Ok(response), false);
+            }
+        }
 
-        match serde_json::to_string_pretty(&spec) {
-            Ok(json) => Response::text(json),
-            Err(e) => Response::error(1, format!("Failed to generate JSON: {}", e)),
+        // Handle `__complete <words...> <partial>` for shell completion scripts
+        if command_args_slice.first().map(String::as_str) == Some(complete::COMPLETE_COMMAND)
+            && !self.routes.contains_key(complete::COMPLETE_COMMAND)
+        {
+            let candidates = self.complete(&command_args_slice[1..]).await;
+            return (Ok(Response::text(candidates.join("\n"))), agent_mode_active);
         }
-    }
 
-    /// Handle version command.
-    fn handle_version(&self) -> Response {
-        if let Some(meta) = &self.metadata {
-            if let Some(version) = meta.version {
-                return Response::text(format!("{} {}", meta.name, version));
+        // Strip a --tag filter given with --help (e.g., `--tag database --help`)
+        let help_enabled = self.config.builtins.help;
+        let (tag_filter, command_args) = if help_enabled
+            && command_args_slice
+                .iter()
+                .any(|arg| arg == "--help" || arg == "-h")
+        {
+            match take_tag_filter(command_args_slice) {
+                Ok(found) => found,
+                Err(e) => return (Err(e), agent_mode_active),
             }
+        } else {
+            (None, command_args_slice.to_vec())
+        };
+        let command_args_slice: &[String] = &command_args;
+        let tag_filter = tag_filter.as_deref();
+
+        // Handle --help flag ONLY if no command is specified yet
+        // If a command is specified (e.g., "build --help"), let Clap handle it
+        if command_args_slice.is_empty()
+            || (help_enabled
+                && command_args_slice.len() == 1
+                && (command_args_slice[0] == "--help" || command_args_slice[0] == "-h"))
+        {
+            // Show general CLI help
+            return (Ok(self.generate_help(tag_filter, false)), agent_mode_active);
         }
 
-        #[cfg(feature = "build-info")]
+        // Handle --help --json for schema output
+        if help_enabled
+            && command_args_slice.contains(&"--help".to_string())
+            && command_args_slice.contains(&"--json".to_string())
         {
-            Response::text(crate::version_info())
+            return (
+                Ok(self.generate_cli_schema_json(tag_filter)),
+                agent_mode_active,
+            );
         }
 
-        #[cfg(not(feature = "build-info"))]
-        Response::text("version information not available")
-    }
+        // Handle --help --md for AI/Agent-friendly markdown output
+        if help_enabled
+            && command_args_slice.contains(&"--help".to_string())
+            && command_args_slice.contains(&"--md".to_string())
+        {
+            return (
+                Ok(self.generate_help_markdown(tag_filter)),
+                agent_mode_active,
+            );
+        }
 
-    /// Find the longest matching route for the given arguments.
-    ///
-    /// Returns the matched handler and remaining arguments.
-    fn find_route(&self, args: &[String]) -> (Option<&dyn ErasedHandler<()>>, Vec<String>) {
-        // Try matching from longest to shortest
-        for depth in (1..=args.len()).rev() {
-            let route_parts = &args[..depth];
-            let route_key = route_parts.join(":");
+        // Handle built-in version, doctor, and history commands
+        if let Some(response) = self.run_builtin(command_args_slice) {
+            return (Ok(response), agent_mode_active);
+        }
 
-            if let Some(handler) = self.routes.get(&route_key) {
+        // Try to match nested commands first (longest match wins)
+        // e.g., ["db", "create", "--flag"] tries:
+        //   1. "db:create" (found!)
+        //   2. "db" (fallback)
+        // Prefixes only stand in for commands that match no route exactly
+        let (matched_handler, remaining_args) = match self.find_route(command_args_slice) {
+            (None, _) if self.config.prefix_matching => {
+                match self.find_route_by_prefix(command_args_slice) {
+                    Ok(found) => found,
+                    Err(e) => return (Err(e), agent_mode_active),
+                }
+            }
+            found => found,
+        };
+
+        let result = match matched_handler {
+            Some((mut route_key, handler)) => {
+                let started = std::time::Instant::now();
+                let invoked = route_key;
+                let invoked_args = remaining_args.clone();
+                let mut redirected_args = None;
+                let state = State::new(());
+                let run = self.call_with_progress(
+                    route_key,
+                    handler,
+                    state,
+                    remaining_args,
+                    agent_mode_active,
+                    flags.dry_run,
+                );
+                let mut result = profile::scope(flags.profile.clone(), run).await;
+
+                // Follow redirects, giving up on cycles or long chains
+                let mut chain = vec![route_key.replace(':', " ")];
+                while let Ok(Response {
+                    output: Output::Redirect(redirect),
+                    ..
+                }) = &result
+                {
+                    let redirect = redirect.clone();
+                    chain.push(redirect.target.replace(':', " "));
+                    if chain.len() > MAX_REDIRECTS + 1 {
+                        result = Err(CliError::system(format!(
+                            "Too many redirects: {}",
+                            chain.join(" -> ")
+                        )));
+                        break;
+                    }
+                    let Some((target_key, target)) = self.routes.get_key_value(&redirect.target)
+                    else {
+                        result = Err(CliError::system(format!(
+                            "Redirect target '{}' is not a registered command",
+                            redirect.target
+                        )));
+                        break;
+                    };
+                    route_key = target_key;
+                    redirected_args = Some(redirect.args.clone());
+                    let run = self.call_with_progress(
+                        route_key,
+                        target.as_ref(),
+                        State::new(()),
+                        redirect.args,
+                        agent_mode_active,
+                        flags.dry_run,
+                    );
+                    result = profile::scope(flags.profile.clone(), run).await;
+                }
+
+                // Record what the handler did, before output rendering
+                let route_args = redirected_args.as_deref().unwrap_or(&invoked_args);
+                self.record_history(route_key, route_args, flags.dry_run, &result);
+
+                let result = self.apply_global_flags(&flags, route_key, agent_mode_active, result);
+                let result = self.finish_command_help(route_key, result);
+                self.log_usage(invoked, &invoked_args, started, agent_mode_active, &result);
+                result
+            }
+            None => Err(CliError::User(UserError::UnknownCommand {
+                command: command_args_slice.join(" "),
+                suggestions: self.suggest_commands(command_args_slice),
+            })),
+        };
+
+        (result, agent_mode_active)
+    }
+
+    /// Call a handler while draining and rendering its progress events.
+    async fn call_with_progress(
+        &self,
+        route_key: &str,
+        handler: &dyn ErasedHandler<()>,
+        state: State<()>,
+        args: Vec<String>,
+        agent_mode: bool,
+        dry_run: bool,
+    ) -> Result<Response, CliError> {
+        // Running a handler that never sees the flag would do it for real
+        if dry_run && !handler.handles_dry_run() {
+            return Err(CliError::User(UserError::InvalidArgument {
+                arg: dry_run::DRY_RUN_FLAG.to_string(),
+                reason: format!(
+                    "'{}' does not support {}",
+                    route_key.replace(':', " "),
+                    dry_run::DRY_RUN_FLAG
+                ),
+            }));
+        }
+
+        #[cfg(feature = "clap")]
+        let (options, args) = INFER_LONG_ARGS.sync_scope(self.config.infer_long_args, || {
+            self.parse_scoped_args(route_key, args)
+        })?;
+        #[cfg(not(feature = "clap"))]
+        let (options, args) = self.parse_scoped_args(route_key, args)?;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ProgressEvent>();
+        let interactive = std::io::IsTerminal::is_terminal(&std::io::stderr());
+
+        let run = handler.try_call_boxed(state, args);
+        let run = if self.config.catch_panics {
+            Box::pin(catch_panic::catch(route_key, run))
+        } else {
+            run
+        };
+        let run = scoped::scope(options, run);
+        let run = dry_run::scope(dry_run, run);
+        let run = output_mode::scope(agent_mode, run);
+        let run = warnings::scope(run);
+        let command_name = format!("{} {}", self.help_header().0, route_key.replace(':', " "));
+        let run = COMMAND_NAME.scope(command_name, run);
+        #[cfg(feature = "clap")]
+        let run = INFER_LONG_ARGS.scope(self.config.infer_long_args, run);
+        // Boxed so the nested scopes don't all live on the caller's stack
+        let run = Box::pin(progress::scope(tx, run));
+        let run = async {
+            match self.config.writer {
+                Some(ref writer) => stream::scope(writer.clone(), run).await,
+                None => run.await,
+            }
+        };
+        let run = async {
+            let Some(budget) = self.handler_timeout(route_key) else {
+                return run.await;
+            };
+            tokio::time::timeout(budget, run).await.unwrap_or_else(|_| {
+                Err(CliError::System(SystemError::Timeout {
+                    command: route_key.replace(':', " "),
+                    budget,
+                }))
+            })
+        };
+        let run = async {
+            let Some(signal) = &self.config.cancel_signal else {
+                return run.await;
+            };
+
+            let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+            let run = cancel::scope(cancel_rx, run);
+            tokio::pin!(run);
+
+            tokio::select! {
+                result = &mut run => return result,
+                () = signal() => {}
+            }
+
+            // Let the handler clean up; a second signal stops waiting
+            let _ = cancel_tx.send(true);
+            tokio::select! {
+                _ = &mut run => {}
+                () = signal() => {}
+            }
+            Err(CliError::User(UserError::Cancelled))
+        };
+        // Drain events until the handler finishes, not until every sender is
+        // dropped: a `Progress` moved into a spawned task may outlive it
+        tokio::pin!(run);
+        let mut rendered_line = false;
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                Some(event) = rx.recv() => {
+                    rendered_line |= self.render_progress(event, agent_mode, interactive);
+                }
+            }
+        };
+        while let Ok(event) = rx.try_recv() {
+            rendered_line |= self.render_progress(event, agent_mode, interactive);
+        }
+        if rendered_line {
+            eprintln!();
+        }
+        result
+    }
+
+    /// Render one progress event; returns whether a status line was drawn.
+    fn render_progress(&self, event: ProgressEvent, agent_mode: bool, interactive: bool) -> bool {
+        if let Some(ref sink) = self.config.progress_sink {
+            let _ = sink.send(event);
+        } else if let Some(ref writer) = self.config.writer {
+            let line = if agent_mode {
+                event.to_ndjson()
+            } else {
+                event.to_status_line()
+            };
+            stream::write_line(writer, &line);
+        } else if agent_mode {
+            eprintln!("{}", event.to_ndjson());
+        } else if interactive {
+            eprint!("\r\x1b[2K{}", event.to_status_line());
+            return true;
+        }
+        false
+    }
+
+    /// Parse the options of every namespace `route_key` lives in, outermost
+    /// first, and return them with the args left for the handler.
+    fn parse_scoped_args(
+        &self,
+        route_key: &str,
+        mut args: Vec<String>,
+    ) -> Result<(Vec<scoped::ScopedValue>, Vec<String>), CliError> {
+        let mut options = Vec::new();
+        if self.scoped_args.is_empty() {
+            return Ok((options, args));
+        }
+
+        let segments: Vec<&str> = route_key.split(':').collect();
+        for depth in 1..=segments.len() {
+            if let Some(parser) = self.scoped_args.get(&segments[..depth].join(":")) {
+                let (value, remaining) = parser(&args)?;
+                options.push(value);
+                args = remaining;
+            }
+        }
+        Ok((options, args))
+    }
+
+    /// Convert route metadata to MCP tool schemas, plus the catalog tool.
+    #[cfg(feature = "mcp")]
+    fn mcp_tools(&self) -> Vec<crate::mcp::McpTool> {
+        let mut tools: Vec<_> = self
+            .route_metadata
+            .iter()
+            .map(|(name, metadata)| {
+                crate::mcp::McpTool::from_route_metadata(name.clone(), metadata)
+            })
+            .collect();
+        if self.is_mcp_describe_tool(&self.config.mcp_describe_tool) {
+            tools.push(crate::mcp::McpTool::describe(
+                &self.config.mcp_describe_tool,
+            ));
+        }
+        tools
+    }
+
+    /// Whether `tool_name` is the catalog tool rather than a route.
+    #[cfg(feature = "mcp")]
+    fn is_mcp_describe_tool(&self, tool_name: &str) -> bool {
+        tool_name == self.config.mcp_describe_tool && !self.routes.contains_key(tool_name)
+    }
+
+    /// Execute an MCP tool call as the command it names.
+    #[cfg(feature = "mcp")]
+    fn execute_mcp_tool(
+        &self,
+        program_name: &str,
+        tool_name: &str,
+        tool_args: Vec<String>,
+    ) -> Response {
+        if self.is_mcp_describe_tool(tool_name) {
+            return match take_tag_filter(&tool_args) {
+                Ok((tag, _)) => self.generate_cli_schema_json(tag.as_deref()),
+                Err(e) => error_response(e),
+            };
+        }
+
+        // Build full args: ["program_name", "command", "subcommand", ...tool_args]
+        // Split tool_name by ":" to get command path (e.g., "db:create" -> ["db", "create"])
+        let mut full_args = vec![program_name.to_string()];
+        full_args.extend(tool_name.split(':').map(String::from));
+        full_args.extend(tool_args);
+
+        // Execute the command (block on async function)
+        futures::executor::block_on(self.execute_with(&full_args))
+    }
+
+    /// Safety tier of `route_key`, from `#[sen::handler(tier = ...)]`.
+    fn route_tier(&self, route_key: &str) -> Option<Tier> {
+        self.route_metadata
+            .get(route_key)
+            .and_then(|meta| meta.handler_meta.as_ref())
+            .and_then(|h| h.tier)
+    }
+
+    /// Time budget for `route_key`: the handler's own timeout, else the default.
+    fn handler_timeout(&self, route_key: &str) -> Option<std::time::Duration> {
+        self.route_metadata
+            .get(route_key)
+            .and_then(|meta| meta.handler_meta.as_ref())
+            .and_then(|h| h.timeout)
+            .or(self.config.default_timeout)
+    }
+
+    /// Generate help message based on router metadata and available commands.
+    fn generate_help(&self, tag: Option<&str>, json_output: bool) -> Response {
+        if json_output {
+            self.generate_cli_schema_json(tag)
+        } else {
+            self.generate_help_text(tag)
+        }
+    }
+
+    /// Name and version shown in the top-level help header.
+    ///
+    /// Without `RouterMetadata`, falls back to the executable's file name
+    /// (or `<command>` if unknown) and shows no version.
+    fn help_header(&self) -> (String, Option<&'static str>) {
+        if let Some(meta) = &self.config.metadata {
+            return (meta.name.to_string(), meta.version);
+        }
+
+        let name = std::env::args_os()
+            .next()
+            .and_then(|arg0| {
+                std::path::Path::new(&arg0)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "<command>".to_string());
+
+        (name, None)
+    }
+
+    /// Generate clean, human-readable help text for terminal display.
+    ///
+    /// Supports colored output when stdout is a terminal. With a `tag`, only
+    /// commands carrying that tag are listed.
+    fn generate_help_text(&self, tag: Option<&str>) -> Response {
+        use anstyle::{AnsiColor, Effects, Style};
+
+        // Define styles
+        let use_color = std::io::IsTerminal::is_terminal(&std::io::stdout());
+
+        let header_style = if use_color {
+            Style::new()
+                .fg_color(Some(AnsiColor::Green.into()))
+                .effects(Effects::BOLD)
+        } else {
+            Style::new()
+        };
+
+        let section_style = if use_color {
+            Style::new()
+                .fg_color(Some(AnsiColor::Yellow.into()))
+                .effects(Effects::BOLD)
+        } else {
+            Style::new()
+        };
+
+        let cmd_style = if use_color {
+            Style::new().fg_color(Some(AnsiColor::Cyan.into()))
+        } else {
+            Style::new()
+        };
+
+        let dim_style = if use_color {
+            Style::new().effects(Effects::DIMMED)
+        } else {
+            Style::new()
+        };
+
+        let reset = if use_color {
+            Style::new().render_reset().to_string()
+        } else {
+            String::new()
+        };
+
+        let mut help = String::new();
+
+        // Header: name + version + about
+        let (cli_name, version) = self.help_header();
+        help.push_str(&format!("{}", header_style.render()));
+        help.push_str(&cli_name);
+        if let Some(version) = version {
+            help.push_str(&format!(" {}", version));
+        }
+        help.push_str(&reset);
+        help.push('\n');
+
+        if let Some(about) = self.config.metadata.as_ref().and_then(|m| m.about) {
+            help.push_str(about);
+            help.push('\n');
+        }
+        help.push('\n');
+
+        // Usage line
+        help.push_str(&format!(
+            "{}Usage:{} {} [OPTIONS] <COMMAND>\n\n",
+            section_style.render(),
+            reset,
+            cli_name
+        ));
+
+        // Group commands by prefix
+        let grouped_commands = self.group_commands_by_prefix(tag);
+
+        // Calculate global max length for consistent alignment across all groups
+        let global_max_len = grouped_commands
+            .iter()
+            .flat_map(|(_, cmds)| cmds.iter().map(|(name, _, _)| name.len()))
+            .max()
+            .unwrap_or(8)
+            .max(8); // Minimum 8 chars
+
+        // Display grouped commands
+        for (group_name, commands) in &grouped_commands {
+            let group_desc = commands
+                .first()
+                .and_then(|(_, full_name, _)| full_name.split_once(':'))
+                .and_then(|(prefix, _)| self.group_descriptions.get(prefix));
+
+            if let Some(desc) = group_desc {
+                help.push_str(&format!(
+                    "{}{}:{} {}{}{}\n",
+                    section_style.render(),
+                    group_name,
+                    reset,
+                    dim_style.render(),
+                    desc,
+                    reset
+                ));
+            } else if !group_name.is_empty() {
+                help.push_str(&format!(
+                    "{}{}:{}\n",
+                    section_style.render(),
+                    group_name,
+                    reset
+                ));
+            } else {
+                help.push_str(&format!("{}Commands:{}\n", section_style.render(), reset));
+            }
+
+            for (name, _full_name, desc) in commands {
+                if desc.is_empty() {
+                    help.push_str(&format!("  {}{}{}\n", cmd_style.render(), name, reset));
+                } else {
+                    help.push_str(&format!(
+                        "  {}{:width$}{}  {}{}{}\n",
+                        cmd_style.render(),
+                        name,
+                        reset,
+                        dim_style.render(),
+                        desc,
+                        reset,
+                        width = global_max_len
+                    ));
+                }
+            }
+            help.push('\n');
+        }
+
+        // Options section (only the enabled built-ins)
+        let help_enabled = self.config.builtins.help;
+        let version_enabled = self.config.builtins.version.is_some();
+        let show_version = version_enabled
+            && self
+                .config
+                .metadata
+                .as_ref()
+                .and_then(|m| m.version)
+                .is_some();
+        let show_dry_run = self.config.dry_run_flag_enabled;
+        let show_template = self.config.template_flag_enabled;
+        let show_profile = self.config.profile_env.is_some();
+        let show_options =
+            help_enabled || show_version || show_dry_run || show_template || show_profile;
+        if show_options {
+            help.push_str(&format!("{}Options:{}\n", section_style.render(), reset));
+        }
+        if help_enabled {
+            help.push_str(&format!(
+                "  {}-h{}, {}--help{}            Print help\n",
+                cmd_style.render(),
+                reset,
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if show_version {
+            help.push_str(&format!(
+                "  {}-V{}, {}--version{}         Print version\n",
+                cmd_style.render(),
+                reset,
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if show_dry_run {
+            help.push_str(&format!(
+                "      {}--dry-run{}         Show what would change without changing it\n",
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if show_template {
+            help.push_str(&format!(
+                "      {}--template{} <TPL>  Render structured output, e.g. '{{{{name}}}}'\n",
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if show_profile {
+            help.push_str(&format!(
+                "      {}--profile{} <NAME>  Config profile to use\n",
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if show_options {
+            help.push('\n');
+        }
+
+        // Alternative formats for automation
+        if help_enabled {
+            help.push_str(&format!(
+                "{}For AI/Agent:{}\n",
+                section_style.render(),
+                reset
+            ));
+            help.push_str(&format!(
+                "      {}--help --md{}       Print help in Markdown format\n",
+                cmd_style.render(),
+                reset
+            ));
+            help.push('\n');
+        }
+        if help_enabled || version_enabled {
+            help.push_str(&format!(
+                "{}For Programs:{}\n",
+                section_style.render(),
+                reset
+            ));
+        }
+        if help_enabled {
+            help.push_str(&format!(
+                "      {}--help --json{}     Print CLI schema in JSON format\n",
+                cmd_style.render(),
+                reset
+            ));
+        }
+        if version_enabled {
+            help.push_str(&format!(
+                "      {}--version --json{}  Print build info in JSON format\n",
+                cmd_style.render(),
+                reset
+            ));
+        }
+
+        Response::text(help)
+    }
+
+    /// Generate Markdown-formatted help for AI/Agent consumption.
+    ///
+    /// This format is designed to be:
+    /// - Parseable by LLMs and agents
+    /// - Rich with metadata (tiers, tags, arguments)
+    /// - Suitable for documentation generation
+    ///
+    /// With a `tag`, only commands carrying that tag are listed.
+    fn generate_help_markdown(&self, tag: Option<&str>) -> Response {
+        let mut md = String::new();
+
+        // Header
+        let cli_name = self
+            .config
+            .metadata
+            .as_ref()
+            .map(|m| m.name)
+            .unwrap_or("CLI");
+        let version = self
+            .config
+            .metadata
+            .as_ref()
+            .and_then(|m| m.version)
+            .unwrap_or("0.0.0");
+        let about = self
+            .config
+            .metadata
+            .as_ref()
+            .and_then(|m| m.about)
+            .unwrap_or("Command-line interface");
+
+        md.push_str(&format!("# {} v{}\n\n", cli_name, version));
+        md.push_str(&format!("{}\n\n", about));
+
+        // Usage
+        md.push_str("## Usage\n\n");
+        md.push_str(&format!("```\n{} [OPTIONS] <COMMAND>\n```\n\n", cli_name));
+
+        // Commands by group
+        md.push_str("## Commands\n\n");
+
+        let grouped_commands = self.group_commands_by_prefix(tag);
+
+        for (group_name, commands) in &grouped_commands {
+            if !group_name.is_empty() && group_name != "Other Commands" {
+                md.push_str(&format!("### {}\n\n", group_name));
+            } else if group_name == "Other Commands" {
+                md.push_str("### Other Commands\n\n");
+            }
+
+            md.push_str("| Command | Description | Tier | Tags |\n");
+            md.push_str("|---------|-------------|------|------|\n");
+
+            for (_name, full_name, desc) in commands {
+                // Get metadata for this command
+                let meta = self.route_metadata.get(full_name.as_str());
+                let handler_meta = meta.and_then(|m| m.handler_meta.as_ref());
+
+                let tier = handler_meta
+                    .and_then(|h| h.tier)
+                    .map(|t| t.as_str())
+                    .unwrap_or("-");
+
+                let tags = handler_meta
+                    .and_then(|h| h.tags.as_ref())
+                    .map(|t| t.join(", "))
+                    .unwrap_or_else(|| "-".to_string());
+
+                let desc_escaped = if desc.is_empty() {
+                    "-".to_string()
+                } else {
+                    desc.replace('|', "\\|")
+                };
+
+                md.push_str(&format!(
+                    "| `{}` | {} | {} | {} |\n",
+                    self.display_key(full_name),
+                    desc_escaped,
+                    tier,
+                    tags
+                ));
+            }
+            md.push('\n');
+        }
+
+        // Options
+        md.push_str("## Options\n\n");
+        md.push_str("| Option | Description |\n");
+        md.push_str("|--------|-------------|\n");
+        md.push_str("| `-h, --help` | Print help |\n");
+        md.push_str("| `--help --md` | Print help (Markdown format) |\n");
+        md.push_str("| `--help --json` | Print CLI schema (JSON format) |\n");
+        if self.config.builtins.version.is_some() {
+            md.push_str("| `--version --json` | Print build info (JSON format) |\n");
+            if self
+                .config
+                .metadata
+                .as_ref()
+                .and_then(|m| m.version)
+                .is_some()
+            {
+                md.push_str("| `-V, --version` | Print version |\n");
+            }
+        }
+        md.push('\n');
+
+        // Command Details (for AI context)
+        md.push_str("## Command Details\n\n");
+
+        let mut sorted_commands: Vec<_> = self.routes.keys().collect();
+        sorted_commands.sort();
+
+        for cmd in sorted_commands {
+            let meta = self.route_metadata.get(cmd.as_str());
+            let handler_meta = meta.and_then(|m| m.handler_meta.as_ref());
+            let desc = handler_meta.and_then(|h| h.desc).unwrap_or("");
+
+            md.push_str(&format!("### `{}`\n\n", self.display_key(cmd)));
+
+            if !desc.is_empty() {
+                md.push_str(&format!("{}\n\n", desc));
+            }
+
+            let aliases = meta.map(RouteMetadata::get_aliases).unwrap_or_default();
+            if !aliases.is_empty() {
+                let aliases: Vec<String> = aliases.iter().map(|a| format!("`{}`", a)).collect();
+                md.push_str(&format!("**Aliases:** {}\n\n", aliases.join(", ")));
+            }
+
+            // Show usage
+            md.push_str(&format!(
+                "```\n{} {}\n```\n\n",
+                cli_name,
+                cmd.replace(':', " ")
+            ));
+        }
+
+        Response::text(md)
+    }
+
+    /// Whether `route_key` passes the help tag filter (no filter passes all).
+    fn route_has_tag(&self, route_key: &str, tag: Option<&str>) -> bool {
+        let Some(tag) = tag else {
+            return true;
+        };
+        self.route_metadata
+            .get(route_key)
+            .is_some_and(|meta| meta.has_tag(tag))
+    }
+
+    /// Group commands by their prefix (e.g., "db:*" -> "Database Commands").
+    /// Returns groups in order: named groups first (sorted), then "Other Commands" last.
+    /// With a `tag`, only commands carrying that tag are included.
+    fn group_commands_by_prefix(&self, tag: Option<&str>) -> GroupedCommands {
+        use std::collections::HashMap;
+
+        let mut groups: HashMap<String, Vec<CommandEntry>> = HashMap::new();
+        let mut commands: Vec<_> = self
+            .routes
+            .keys()
+            .filter(|cmd| self.route_has_tag(cmd, tag))
+            .collect();
+        commands.sort();
+
+        for cmd in commands {
+            let meta = self.route_metadata.get(cmd.as_str());
+            let mut desc = meta
+                .and_then(|meta| meta.get_description())
+                .unwrap_or("")
+                .to_string();
+            let aliases = meta.map(RouteMetadata::get_aliases).unwrap_or_default();
+            if !aliases.is_empty() {
+                if !desc.is_empty() {
+                    desc.push(' ');
+                }
+                desc.push_str(&format!("(aliases: {})", aliases.join(", ")));
+            }
+
+            // Check if command has a prefix (e.g., "db:create" -> prefix "db")
+            if let Some(colon_pos) = cmd.find(':') {
+                let prefix = &cmd[..colon_pos];
+                let suffix = &cmd[colon_pos + 1..];
+
+                // Generate group name (e.g., "db" -> "Database Commands")
+                let group_name = self.format_group_name(prefix);
+
+                groups.entry(group_name).or_default().push((
+                    self.display_key(suffix),
+                    cmd.to_string(),
+                    desc,
+                ));
+            } else {
+                // No prefix - add to "Other Commands"
+                groups
+                    .entry("Other Commands".to_string())
+                    .or_default()
+                    .push((cmd.to_string(), cmd.to_string(), desc));
+            }
+        }
+
+        // Sort commands within each group
+        for (_, commands) in groups.iter_mut() {
+            commands.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        // Sort groups: named groups alphabetically, "Other Commands" last
+        let mut result: Vec<_> = groups.into_iter().collect();
+        result.sort_by(|a, b| {
+            if a.0 == "Other Commands" {
+                std::cmp::Ordering::Greater
+            } else if b.0 == "Other Commands" {
+                std::cmp::Ordering::Less
+            } else {
+                a.0.cmp(&b.0)
+            }
+        });
+
+        result
+    }
+
+    /// Format a command prefix into a nice group name.
+    /// Examples: "db" -> "Database Commands", "config" -> "Configuration Commands"
+    fn format_group_name(&self, prefix: &str) -> String {
+        let capitalized = match prefix {
+            "db" => "Database",
+            "config" => "Configuration",
+            "deploy" => "Deployment",
+            "server" => "Server",
+            "network" => "Network",
+            "storage" => "Storage",
+            _ => {
+                // Default: capitalize first letter
+                let mut chars = prefix.chars();
+                match chars.next() {
+                    None => return "Commands".to_string(),
+                    Some(first) => {
+                        return format!(
+                            "{}{} Commands",
+                            first.to_uppercase(),
+                            chars.collect::<String>()
+                        );
+                    }
+                }
+            }
+        };
+        format!("{} Commands", capitalized)
+    }
+
+    /// Stable hash of the command catalog, for cache invalidation.
+    ///
+    /// Covers every command's name, description, tier, tags, and argument
+    /// schema (the `commands` of `--help --json`, which also reports it as
+    /// `catalog_hash`). Registration order doesn't matter, and an unchanged
+    /// binary always produces the same hash, so completion caches and agent
+    /// tool registries can compare it and regenerate only when it changes.
+    ///
+    /// ```ignore
+    /// if cache.catalog_hash != router.catalog_hash() {
+    ///     cache.rebuild(&router);
+    /// }
+    /// ```
+    pub fn catalog_hash(&self) -> String {
+        let catalog = sort_json_keys(serde_json::Value::Object(self.command_schemas(None)));
+        format!("{:016x}", fnv1a_64(catalog.to_string().as_bytes()))
+    }
+
+    /// Schema of every command (carrying `tag`, if given), keyed by route.
+    fn command_schemas(&self, tag: Option<&str>) -> serde_json::Map<String, serde_json::Value> {
+        use serde_json::json;
+
+        let name = self
+            .config
+            .metadata
+            .as_ref()
+            .map(|m| m.name)
+            .unwrap_or("cli");
+        let mut commands = serde_json::Map::new();
+
+        // Collect all routes and their metadata
+        let mut command_names: Vec<_> = self
+            .routes
+            .keys()
+            .filter(|cmd| self.route_has_tag(cmd, tag))
+            .collect();
+        command_names.sort();
+
+        for cmd in command_names {
+            // Get handler metadata
+            let handler_meta = self
+                .route_metadata
+                .get(cmd)
+                .and_then(|meta| meta.handler_meta.as_ref());
+
+            // Get handler description
+            let desc = handler_meta
+                .and_then(|h| h.desc)
+                .unwrap_or("No description available");
+
+            // Get tier information
+            let tier = handler_meta.and_then(|h| h.tier).map(|t| t.as_str());
+
+            // Get tags
+            let tags = handler_meta.and_then(|h| h.tags.as_ref());
+
+            // Build usage string
+            let usage = format!("{} {}", name, cmd.replace(':', " "));
+
+            let mut command_schema = json!({
+                "description": desc,
+                "usage": usage,
+            });
+
+            // Add tier if available
+            if let Some(tier_str) = tier {
+                command_schema["tier"] = json!(tier_str);
+                command_schema["requires_approval"] = json!(Tier::parse(tier_str)
+                    .map(|t| t.requires_approval())
+                    .unwrap_or(false));
+            }
+
+            // Add tags if available
+            if let Some(tag_list) = tags {
+                command_schema["tags"] = json!(tag_list);
+            }
+
+            // Add usage examples if available
+            if let Some(examples) = handler_meta.map(|h| &h.examples) {
+                if !examples.is_empty() {
+                    command_schema["examples"] = json!(examples);
+                }
+            }
+
+            // Add argument schema, aliases and environment variables if available
+            if let Some(meta) = self.route_metadata.get(cmd) {
+                let aliases = meta.get_aliases();
+                if !aliases.is_empty() {
+                    command_schema["aliases"] = json!(aliases);
+                }
+
+                if let Some(args_schema) = &meta.args_schema {
+                    command_schema["arguments"] = args_schema["arguments"].clone();
+                    command_schema["options"] = args_schema["options"].clone();
+                }
+
+                let env = meta.get_env();
+                if !env.is_empty() {
+                    command_schema["env"] = json!(env);
+                }
+            }
+
+            commands.insert(cmd.to_string(), command_schema);
+        }
+
+        commands
+    }
+
+    /// Generate CLI schema JSON specification.
+    ///
+    /// Outputs a CLI-friendly JSON format that includes all commands with their
+    /// arguments, options, and metadata in a single dump. With a `tag`, only
+    /// commands carrying that tag are included.
+    fn generate_cli_schema_json(&self, tag: Option<&str>) -> Response {
+        use serde_json::json;
+
+        let name = self
+            .config
+            .metadata
+            .as_ref()
+            .map(|m| m.name)
+            .unwrap_or("cli");
+        let version = self
+            .config
+            .metadata
+            .as_ref()
+            .and_then(|m| m.version)
+            .unwrap_or("unknown");
+        let description = self.config.metadata.as_ref().and_then(|m| m.about);
+
+        let spec = json!({
+            "name": name,
+            "version": version,
+            "description": description.unwrap_or(""),
+            "commands": self.command_schemas(tag),
+            "catalog_hash": self.catalog_hash(),
+        });
+
+        match serde_json::to_string_pretty(&sort_json_keys(spec)) {
+            Ok(json) => Response::text(json),
+            Err(e) => Response::error(1, format!("Failed to generate JSON: {}", e)),
+        }
+    }
+
+    /// Turn a command's `--help` output into a successful response.
+    ///
+    /// Appends argument constraints, the environment variables the command
+    /// reads, and the handler's usage examples (if any) to the help text.
+    fn finish_command_help(
+        &self,
+        route_key: &str,
+        result: Result<Response, CliError>,
+    ) -> Result<Response, CliError> {
+        match result {
+            Err(CliError::User(UserError::Help(help))) => {
+                let meta = self.route_metadata.get(route_key);
+                let examples = meta.map(|meta| meta.get_examples()).unwrap_or_default();
+                let env = meta.map(|meta| meta.get_env()).unwrap_or_default();
+                let constraints = meta
+                    .and_then(|meta| meta.args_schema.as_ref())
+                    .map(arg_constraints)
+                    .unwrap_or_default();
+
+                let help = if constraints.is_empty() {
+                    help
+                } else {
+                    let mut help = format!("{}\n\nConstraints:\n", help.trim_end());
+                    for constraint in constraints {
+                        help.push_str(&format!("  {}\n", constraint));
+                    }
+                    help
+                };
+
+                let help = if env.is_empty() {
+                    help
+                } else {
+                    format!("{}\n\nEnvironment: {}\n", help.trim_end(), env.join(", "))
+                };
+
+                let help = if examples.is_empty() {
+                    help
+                } else {
+                    let mut help = format!("{}\n\nExamples:\n", help.trim_end());
+                    for example in examples {
+                        help.push_str(&format!("  {}\n", example));
+                    }
+                    help
+                };
+
+                Ok(error_response(CliError::User(UserError::Help(help))))
+            }
+            other => other,
+        }
+    }
+
+    /// Find the longest matching route for the given arguments.
+    ///
+    /// Returns the matched route key and handler, and the remaining arguments.
+    fn find_route(&self, args: &[String]) -> (Option<RouteMatch<'_>>, Vec<String>) {
+        // Try matching from longest to shortest
+        for depth in (1..=args.len()).rev() {
+            let mut route_key = args[..depth].join(":");
+            if let Some(target) = self.aliases.get(&route_key) {
+                route_key.clone_from(target);
+            }
+
+            if let Some((key, handler)) = self.routes.get_key_value(&route_key) {
                 let remaining = args[depth..].to_vec();
-                return (Some(handler.as_ref()), remaining);
+                return (Some((key.as_str(), handler.as_ref())), remaining);
+            }
+        }
+
+        if !self.config.normalize_commands {
+            return (None, args.to_vec());
+        }
+
+        // No exact match at any depth: compare normalized segments
+        for depth in (1..=args.len()).rev() {
+            if let Some(found) = self.find_normalized_key(&args[..depth]) {
+                return (Some(found), args[depth..].to_vec());
+            }
+        }
+
+        // `db_create` for `db:create`: split the first token into segments
+        if let Some(first) = args.first() {
+            let segments: Vec<String> = first
+                .split(['_', '-'])
+                .filter(|seg| !seg.is_empty())
+                .map(String::from)
+                .collect();
+            if segments.len() > 1 {
+                let expanded: Vec<String> =
+                    segments.into_iter().chain(args[1..].to_vec()).collect();
+                for depth in (1..=expanded.len()).rev() {
+                    if let Some(found) = self.find_normalized_key(&expanded[..depth]) {
+                        return (Some(found), expanded[depth..].to_vec());
+                    }
+                }
+            }
+        }
+
+        (None, args.to_vec())
+    }
+
+    /// The route whose segments equal `parts` ignoring case and `_`/`-`.
+    ///
+    /// At most one route can match, since `normalize_commands` rejects
+    /// routes that collide this way.
+    fn find_normalized_key(&self, parts: &[String]) -> Option<RouteMatch<'_>> {
+        let wanted = normalized_key(&parts.join(":"));
+        self.routes
+            .iter()
+            .find(|(key, _)| normalized_key(key) == wanted)
+            .map(|(key, handler)| (key.as_str(), handler.as_ref()))
+    }
+
+    /// Completion candidates for the last of `words`, given the ones before.
+    async fn complete(&self, words: &[String]) -> Vec<String> {
+        let (partial, words) = match words.split_last() {
+            Some((partial, words)) => (partial.as_str(), words),
+            None => ("", words),
+        };
+
+        if let (Some((route_key, _)), _) = self.find_route(words) {
+            if let Some(completer) = self.completers.get(route_key) {
+                return completer
+                    .complete(&route_key.replace(':', " "), partial)
+                    .await;
+            }
+        }
+        complete::subcommands(self.routes.keys(), words, partial)
+    }
+
+    /// Suggest routes close to an unknown command, best match first.
+    ///
+    /// Each route is compared against the same number of leading arguments,
+    /// so `["db", "craete", "--size"]` is matched as `db craete`.
+    fn suggest_commands(&self, args: &[String]) -> Vec<String> {
+        const MAX_SUGGESTIONS: usize = 3;
+
+        let mut scored: Vec<(usize, String)> = self
+            .routes
+            .keys()
+            .filter_map(|key| {
+                let route = key.replace(':', " ");
+                let depth = key.split(':').count().min(args.len());
+                let typed = args[..depth].join(" ");
+                let distance = edit_distance(&typed, &route);
+                (distance <= (route.len() / 3).max(1)).then_some((distance, route))
+            })
+            .collect();
+        scored.sort();
+        scored.dedup_by(|a, b| a.1 == b.1);
+        scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, route)| route)
+            .collect()
+    }
+
+    /// Find the longest route whose segments are exact names or unique prefixes.
+    ///
+    /// Walks the arguments one segment at a time. At each level an exact
+    /// segment name wins; otherwise the argument must be a prefix of exactly
+    /// one segment among the routes still in play. Returns an error when a
+    /// prefix is ambiguous. Only used when [`find_route`](Self::find_route)
+    /// finds no exact match.
+    fn find_route_by_prefix(
+        &self,
+        args: &[String],
+    ) -> Result<(Option<RouteMatch<'_>>, Vec<String>), CliError> {
+        let mut candidates: Vec<Vec<&str>> = self
+            .routes
+            .keys()
+            .map(|key| key.split(':').collect())
+            .collect();
+        let mut resolved: Vec<&str> = Vec::new();
+        let mut best: Option<(RouteMatch<'_>, usize)> = None;
+
+        for (depth, token) in args.iter().enumerate() {
+            let mut segments: Vec<&str> = candidates
+                .iter()
+                .filter_map(|segs| segs.get(depth).copied())
+                .collect();
+            segments.sort_unstable();
+            segments.dedup();
+
+            let segment = if segments.contains(&token.as_str()) {
+                token.as_str()
+            } else {
+                let matches: Vec<&str> = segments
+                    .into_iter()
+                    .filter(|seg| seg.starts_with(token.as_str()))
+                    .collect();
+                match matches.as_slice() {
+                    [] => break,
+                    [only] => *only,
+                    _ => {
+                        let parent = resolved.join(" ");
+                        let options: Vec<String> = matches
+                            .iter()
+                            .map(|seg| {
+                                if parent.is_empty() {
+                                    seg.to_string()
+                                } else {
+                                    format!("{} {}", parent, seg)
+                                }
+                            })
+                            .collect();
+                        return Err(CliError::user(format!(
+                            "Ambiguous command '{}': could be {}",
+                            token,
+                            options.join(", ")
+                        )));
+                    }
+                }
+            };
+
+            resolved.push(segment);
+            candidates.retain(|segs| segs.get(depth) == Some(&segment));
+
+            if let Some((key, handler)) = self.routes.get_key_value(&resolved.join(":")) {
+                best = Some(((key.as_str(), handler.as_ref()), depth + 1));
+            }
+        }
+
+        Ok(match best {
+            Some((found, depth)) => (Some(found), args[depth..].to_vec()),
+            None => (None, args.to_vec()),
+        })
+    }
+}
+
+// ============================================================================
+// Args Extractor (Axum-style)
+// ============================================================================
+
+/// Extractor for command-line arguments.
+///
+/// Similar to Axum's `Path` or `Query`, this allows handlers to receive
+/// parsed arguments.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// struct BuildArgs {
+///     release: bool,
+/// }
+///
+/// impl FromArgs for BuildArgs {
+///     fn from_args(args: &[String]) -> Result<Self, CliError> {
+///         Ok(BuildArgs {
+///             release: args.get(0).map(|s| s == "--release").unwrap_or(false),
+///         })
+///     }
+/// }
+///
+/// async fn build(State(app): State<AppState>, Args(args): Args<BuildArgs>) -> CliResult<String> {
+///     if args.release {
+///         Ok("Release build".to_string())
+///     } else {
+///         Ok("Debug build".to_string())
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Args<T>(pub T);
+
+/// Extractor for the arguments left over after `Args<T>` is parsed.
+///
+/// Taken together with `Args<T>`, the router parses `T` with
+/// [`FromArgs::from_args_prefix`] and hands the unconsumed tokens to the
+/// handler unparsed. By default that is everything after a `--` separator,
+/// which suits wrappers that pass arguments on to another program.
+///
+/// # Example
+///
+/// ```ignore
+/// // $ myapp exec --env prod -- ls -la
+/// async fn exec(
+///     state: State<AppState>,
+///     Args(args): Args<ExecArgs>,
+///     Rest(command): Rest,
+/// ) -> CliResult<String> {
+///     // args.env == "prod", command == ["ls", "-la"]
+///     run_in(&args.env, &command).await
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rest(pub Vec<String>);
+
+/// Extractor taken from the executing command's context rather than its
+/// arguments.
+///
+/// Implemented by [`Progress`], [`Cancel`], [`DryRun`], [`OutputMode`],
+/// [`Warnings`], and [`Scoped`]. A handler takes any number of them (up to
+/// four), in any order, between `State<S>` and its optional `Args<T>`:
+///
+/// ```ignore
+/// async fn drop_table(
+///     state: State<AppState>,
+///     DryRun(dry_run): DryRun,
+///     warnings: Warnings,
+///     Args(args): Args<DropArgs>,
+/// ) -> CliResult<String> {
+///     // ...
+/// }
+/// ```
+pub trait FromContext: Sized + Send + 'static {
+    /// Whether a handler taking this extractor sees `--dry-run`, and so
+    /// supports the flag.
+    const HANDLES_DRY_RUN: bool = false;
+
+    /// Extract the value for the currently executing command.
+    ///
+    /// Called before the handler runs, while the router's task-locals are set.
+    fn from_context() -> Result<Self, CliError>;
+}
+
+/// Trait for parsing command-line arguments into a type.
+///
+/// This is similar to Axum's `FromRequest` trait and provides a lightweight
+/// way to parse per-command arguments.
+///
+/// # When to use `FromArgs`
+///
+/// Use `FromArgs` when:
+/// - ✅ You have simple per-command arguments (e.g., `--release`, `--output file.txt`)
+/// - ✅ You don't need global flags that apply to all commands
+/// - ✅ You want the framework to handle argument injection automatically
+///
+/// **Don't use `FromArgs` when:**
+/// - ❌ You need global flags (e.g., `--verbose`, `--config`) → use `FromGlobalArgs`
+/// - ❌ You need complex validation or conflicting flag logic → use `clap` directly
+/// - ❌ You're building a production CLI with many commands → see `examples/practical-cli`
+///
+/// # Example
+///
+/// ```rust
+/// use sen::{Args, FromArgs, CliError, CliResult};
+///
+/// #[derive(Debug)]
+/// struct BuildArgs {
+///     release: bool,
+///     output: Option<String>,
+/// }
+///
+/// impl FromArgs for BuildArgs {
+///     fn from_args(args: &[String]) -> Result<Self, CliError> {
+///         let mut release = false;
+///         let mut output = None;
+///
+///         let mut iter = args.iter();
+///         while let Some(arg) = iter.next() {
+///             match arg.as_str() {
+///                 "--release" => release = true,
+///                 "--output" => output = iter.next().map(|s| s.clone()),
+///                 _ => {}
+///             }
+///         }
+///
+///         Ok(BuildArgs { release, output })
+///     }
+/// }
+///
+/// // Use in handler
+/// async fn build(Args(args): Args<BuildArgs>) -> CliResult<String> {
+///     let mode = if args.release { "release" } else { "debug" };
+///     Ok(format!("Building in {} mode", mode))
+/// }
+/// ```
+///
+/// # Comparison with `FromGlobalArgs`
+///
+/// | Feature | `FromArgs` | `FromGlobalArgs` |
+/// |---------|-----------|------------------|
+/// | Scope | Per-command | All commands |
+/// | Injection | Via `Args<T>` extractor | Via `State<AppState>` |
+/// | Use case | Simple flags | Global configuration |
+/// | Example | `--release`, `--output` | `--verbose`, `--config` |
+///
+/// See README.md § "Argument Parsing: FromArgs vs Global Options" for detailed guide.
+pub trait FromArgs: Sized {
+    /// Parse arguments into Self, or return an error.
+    fn from_args(args: &[String]) -> Result<Self, CliError>;
+
+    /// Parse arguments for the command `name`, e.g. `"myctl db create"`.
+    ///
+    /// This is what the router calls; the name lets parsers print accurate
+    /// usage in errors. The default ignores it and calls [`from_args`].
+    ///
+    /// [`from_args`]: FromArgs::from_args
+    fn from_args_named(name: &str, args: &[String]) -> Result<Self, CliError> {
+        let _ = name;
+        Self::from_args(args)
+    }
+
+    /// Parse the leading arguments, returning how many tokens were consumed.
+    ///
+    /// Used when a handler also takes [`Rest`]: the tokens after the
+    /// consumed ones become the rest. The default parses everything before
+    /// the first `--` and consumes it along with the separator, or consumes
+    /// all arguments if there is none. Override it for a parser that can
+    /// stop on its own, e.g. at the first unknown token.
+    fn from_args_prefix(args: &[String]) -> Result<(Self, usize), CliError> {
+        match args.iter().position(|arg| arg == "--") {
+            Some(separator) => Ok((Self::from_args(&args[..separator])?, separator + 1)),
+            None => Ok((Self::from_args(args)?, args.len())),
+        }
+    }
+
+    /// Get CLI schema information for this argument type (optional).
+    ///
+    /// Returns JSON representation of the command structure, including
+    /// arguments, options, and descriptions.
+    ///
+    /// Each argument or option may also list `conflicts_with` and `requires`
+    /// arrays of other argument names (e.g. `"--yaml"`). These show up in
+    /// command help and in the MCP tool input schema.
+    ///
+    /// A trailing positional that takes several values is marked with
+    /// `"variadic": true` and `"max_values"` (`null` when unbounded).
+    /// Entries may carry `"possible_values"` (allowed values) and
+    /// `"default"` (as a string).
+    fn cli_schema() -> Option<serde_json::Value> {
+        None
+    }
+}
+
+/// Trait for parsing global options from command-line arguments.
+///
+/// Global options are flags that apply to **all commands** in your CLI,
+/// such as `--verbose`, `--config`, or `--output-format`.
+///
+/// # When to use `FromGlobalArgs`
+///
+/// Use `FromGlobalArgs` when:
+/// - ✅ You have flags that apply to **all** commands (e.g., `--verbose`, `--config`)
+/// - ✅ You want to avoid repeating the same flags in every handler
+/// - ✅ You're building a production CLI with multiple commands
+/// - ✅ You need integration with `clap` or other complex parsers
+///
+/// # Comparison with `FromArgs`
+///
+/// | Feature | `FromArgs` | `FromGlobalArgs` |
+/// |---------|-----------|------------------|
+/// | Scope | Per-command | All commands |
+/// | Injection | Via `Args<T>` extractor | Via `State<AppState>` |
+/// | Parsing time | During handler call | Before routing |
+/// | Use case | Command-specific flags | CLI-wide configuration |
+///
+/// # Example: Production CLI Pattern
+///
+/// ```ignore
+/// use sen::FromGlobalArgs;
+///
+/// #[derive(Clone)]
+/// struct GlobalOpts {
+///     verbose: bool,
+///     config: Option<String>,
+/// }
+///
+/// impl FromGlobalArgs for GlobalOpts {
+///     fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
+///         let mut verbose = false;
+///         let mut config = None;
+///         let mut remaining = Vec::new();
+///
+///         for arg in args {
+///             match arg.as_str() {
+///                 "--verbose" | "-v" => verbose = true,
+///                 s if s.starts_with("--config=") => {
+///                     config = Some(s.strip_prefix("--config=").unwrap().to_string());
+///                 }
+///                 _ => remaining.push(arg.clone()),
+///             }
+///         }
+///
+///         Ok((GlobalOpts { verbose, config }, remaining))
+///     }
+/// }
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     global: GlobalOpts,
+///     // ... other state fields
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let args: Vec<String> = std::env::args().skip(1).collect();
+///     let (global_opts, remaining) = GlobalOpts::from_global_args(&args).unwrap();
+///
+///     let state = AppState { global: global_opts };
+///     let router = Router::new()
+///         .route("build", handlers::build)
+///         .with_state(state);
+///
+///     // Execute with remaining args (global flags already parsed)
+///     let program_name = std::env::args().next().unwrap_or_default();
+///     let mut execute_args = vec![program_name];
+///     execute_args.extend(remaining);
+///     let response = router.execute_with(&execute_args).await;
+/// }
+/// ```
+///
+/// # Real-World Pattern
+///
+/// This pattern mirrors production CLIs like `kubectl`, `docker`, `aws`:
+///
+/// ```bash
+/// kubectl --context=prod get pods          # global: --context, command: get
+/// docker --debug run nginx                 # global: --debug, command: run
+/// myctl --verbose --config=prod db create  # global: --verbose --config, command: db create
+/// ```
+///
+/// See `examples/practical-cli` for a complete implementation with nested commands.
+///
+/// See README.md § "Argument Parsing: FromArgs vs Global Options" for detailed guide.
+pub trait FromGlobalArgs: Sized + Clone {
+    /// Parse global options from command-line arguments.
+    ///
+    /// This is called before routing, so it receives all arguments.
+    /// It should extract global flags and return the remaining non-global args.
+    ///
+    /// Returns `(parsed_options, remaining_args)` where `remaining_args` should be
+    /// passed to the router for command routing.
+    fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError>;
+}
+
+// ============================================================================
+// Clap Integration (when clap feature is enabled)
+// ============================================================================
+
+tokio::task_local! {
+    /// Program name and command path of the running command, e.g. `"myctl db create"`
+    static COMMAND_NAME: String;
+}
+
+/// Parse a handler's `args` as `T`, passing the running command's name when
+/// there is one.
+fn parse_args<T: FromArgs>(args: &[String]) -> Result<T, CliError> {
+    match COMMAND_NAME.try_with(|name| name.clone()) {
+        Ok(name) => T::from_args_named(&name, args),
+        Err(_) => T::from_args(args),
+    }
+}
+
+#[cfg(feature = "clap")]
+tokio::task_local! {
+    /// Set from `Router::infer_long_args` while a command runs
+    static INFER_LONG_ARGS: bool;
+}
+
+#[cfg(feature = "clap")]
+/// Parse `args` (without the command name) as `T`.
+///
+/// Usage lines in errors show `name`, or a dummy `cmd` without one.
+/// Long-flag abbreviations are accepted when the running router enabled
+/// `infer_long_args`.
+fn clap_parse<T: clap::Parser>(name: Option<&str>, args: &[String]) -> Result<T, clap::Error> {
+    // Clap expects the program name as the first argument
+    let args_with_cmd = std::iter::once("cmd".to_string()).chain(args.iter().cloned());
+
+    let mut cmd = T::command();
+    if INFER_LONG_ARGS.try_with(|infer| *infer).unwrap_or(false) {
+        cmd = cmd.infer_long_args(true);
+    }
+    if let Some(name) = name {
+        cmd = cmd.bin_name(name);
+    }
+    let mut matches = cmd.try_get_matches_from_mut(args_with_cmd)?;
+    T::from_arg_matches_mut(&mut matches).map_err(|e| e.format(&mut cmd))
+}
+
+#[cfg(feature = "clap")]
+/// Convert a clap parse failure into a `CliError`.
+fn clap_error(e: clap::Error) -> CliError {
+    // Clap's DisplayHelp and DisplayVersion are not errors - they're successful exits
+    // We want to preserve the formatted output, not treat it as an error
+    use clap::error::ErrorKind;
+    match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => {
+            // Extract the formatted help/version text and return as Help variant
+            // This will result in exit code 0
+            CliError::User(UserError::Help(e.to_string()))
+        }
+        _ => CliError::user(e.to_string()),
+    }
+}
+
+#[cfg(feature = "clap")]
+/// Collect the subcommands of `cmd` that have no subcommands of their own,
+/// with their paths from the root (e.g. `["db", "create"]`).
+fn clap_leaf_subcommands(
+    cmd: &clap::Command,
+    path: &mut Vec<String>,
+    leaves: &mut Vec<(Vec<String>, clap::Command)>,
+) {
+    for subcommand in cmd.get_subcommands() {
+        path.push(subcommand.get_name().to_string());
+        if subcommand.has_subcommands() {
+            clap_leaf_subcommands(subcommand, path, leaves);
+        } else {
+            leaves.push((path.clone(), subcommand.clone()));
+        }
+        path.pop();
+    }
+}
+
+#[cfg(feature = "clap")]
+/// Route for one subcommand of a clap `Subcommand` enum
+/// (see [`Router::from_clap_subcommand`]).
+struct ClapSubcommandHandler<E, F> {
+    /// Subcommand names from the root, e.g. `["db", "create"]`
+    path: Arc<[String]>,
+    schema: Arc<serde_json::Value>,
+    dispatch: F,
+    _marker: PhantomData<fn() -> E>,
+}
+
+#[cfg(feature = "clap")]
+impl<E, F: Clone> Clone for ClapSubcommandHandler<E, F> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            schema: self.schema.clone(),
+            dispatch: self.dispatch.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<E, F> ClapSubcommandHandler<E, F>
+where
+    E: clap::Subcommand,
+{
+    /// Parse the route's args as this subcommand of `E`.
+    fn parse(&self, args: &[String]) -> Result<E, CliError> {
+        let infer = INFER_LONG_ARGS.try_with(|infer| *infer).unwrap_or(false);
+        let mut cmd = E::augment_subcommands(clap::Command::new("cmd"))
+            .subcommand_required(true)
+            .infer_long_args(infer);
+        let argv = std::iter::once("cmd".to_string())
+            .chain(self.path.iter().cloned())
+            .chain(args.iter().cloned());
+        let mut matches = cmd.try_get_matches_from_mut(argv).map_err(clap_error)?;
+        E::from_arg_matches_mut(&mut matches).map_err(|e| clap_error(e.format(&mut cmd)))
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<E, F, Fut, S, Res> Handler<(State<S>, E), S> for ClapSubcommandHandler<E, F>
+where
+    E: clap::Subcommand + Send + 'static,
+    F: Fn(State<S>, E) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Res> + Send + 'static,
+    Res: IntoResponse + 'static,
+    S: Send + Sync + Clone + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
+        Box::pin(async move {
+            self.try_call(state, args)
+                .await
+                .unwrap_or_else(error_response)
+        })
+    }
+
+    fn try_call(
+        self,
+        state: State<S>,
+        args: Vec<String>,
+    ) -> BoxFuture<'static, Result<Response, CliError>> {
+        Box::pin(async move {
+            let command = self.parse(&args)?;
+            (self.dispatch)(state, command).await.into_result()
+        })
+    }
+
+    fn args_schema(&self) -> Option<serde_json::Value> {
+        Some(self.schema.as_ref().clone())
+    }
+}
+
+#[cfg(feature = "clap")]
+/// Blanket implementation: any type implementing `clap::Parser` can be used with `Args<T>`.
+///
+/// This allows seamless integration with clap's derive macros:
+///
+/// ```ignore
+/// use clap::Parser;
+/// use sen::{Args, CliResult, State};
+///
+/// #[derive(Parser)]
+/// struct BuildArgs {
+///     /// Database name
+///     name: String,
+///
+///     /// Build in release mode
+///     #[arg(long)]
+///     release: bool,
+///
+///     /// Target architecture (can also be set via env var)
+///     #[arg(long, env = "BUILD_TARGET")]
+///     target: Option<String>,
+/// }
+///
+/// async fn build(
+///     state: State<AppState>,
+///     Args(args): Args<BuildArgs>  // Clap automatically parses!
+/// ) -> CliResult<String> {
+///     if args.release {
+///         Ok("Building in release mode".to_string())
+///     } else {
+///         Ok("Building in debug mode".to_string())
+///     }
+/// }
+/// ```
+impl<T> FromArgs for T
+where
+    T: clap::Parser,
+{
+    fn from_args(args: &[String]) -> Result<Self, CliError> {
+        clap_parse::<T>(None, args).map_err(clap_error)
+    }
+
+    fn from_args_named(name: &str, args: &[String]) -> Result<Self, CliError> {
+        clap_parse::<T>(Some(name), args).map_err(clap_error)
+    }
+
+    fn cli_schema() -> Option<serde_json::Value> {
+        let cmd = T::command();
+        Some(clap_command_to_json(&cmd))
+    }
+}
+
+/// Describe the `conflicts_with` and `requires` entries of an args schema.
+///
+/// Each conflicting pair is listed once, whichever side declares it.
+fn arg_constraints(schema: &serde_json::Value) -> Vec<String> {
+    let entries = ["arguments", "options"]
+        .iter()
+        .filter_map(|key| schema.get(*key).and_then(|v| v.as_array()))
+        .flatten();
+    let names = |entry: &serde_json::Value, key: &str| -> Vec<String> {
+        entry
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut conflicts = std::collections::BTreeSet::new();
+    let mut lines = Vec::new();
+    for entry in entries {
+        let Some(name) = entry.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        for other in names(entry, "conflicts_with") {
+            let pair = if name < other.as_str() {
+                (name.to_string(), other)
+            } else {
+                (other, name.to_string())
+            };
+            conflicts.insert(pair);
+        }
+        for required in names(entry, "requires") {
+            lines.push(format!("{} requires {}", name, required));
+        }
+    }
+
+    conflicts
+        .into_iter()
+        .map(|(a, b)| format!("{} cannot be used with {}", a, b))
+        .chain(lines)
+        .collect()
+}
+
+#[cfg(feature = "clap")]
+/// Convert a clap::Command to a JSON representation.
+///
+/// `conflicts_with` is filled from `conflicts_with` and exclusive `ArgGroup`s.
+/// clap does not expose `requires` publicly, so it is never emitted here.
+fn clap_command_to_json(cmd: &clap::Command) -> serde_json::Value {
+    use serde_json::json;
+
+    fn schema_name(arg: &clap::Arg) -> String {
+        if arg.is_positional() {
+            arg.get_id().as_str().to_string()
+        } else {
+            format!("--{}", arg.get_id().as_str())
+        }
+    }
+
+    // Args that cannot be combined with `arg`: explicit `conflicts_with`
+    // plus the other members of any exclusive (non-`multiple`) group
+    let conflicts_of = |arg: &clap::Arg| -> Vec<String> {
+        let mut names: Vec<String> = cmd
+            .get_arg_conflicts_with(arg)
+            .into_iter()
+            .map(schema_name)
+            .collect();
+        for group in cmd.get_groups() {
+            let members: Vec<_> = group.get_args().collect();
+            if group.clone().is_multiple() || !members.contains(&arg.get_id()) {
+                continue;
+            }
+            for id in members {
+                if let Some(other) = cmd.get_arguments().find(|a| a.get_id() == id) {
+                    if other.get_id() != arg.get_id() {
+                        names.push(schema_name(other));
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    };
+
+    // Allowed values (e.g. from a `ValueEnum`); flags take no values
+    fn possible_values(arg: &clap::Arg) -> Vec<String> {
+        if !arg.get_action().takes_values() {
+            return Vec::new();
+        }
+        arg.get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect()
+    }
+
+    // Extract positional arguments
+    let mut positionals = Vec::new();
+    let mut options = Vec::new();
+
+    for arg in cmd.get_arguments() {
+        let conflicts = conflicts_of(arg);
+        let possible = possible_values(arg);
+        if arg.is_positional() {
+            let mut positional = json!({
+                "name": schema_name(arg),
+                "type": format!("{:?}", arg.get_value_parser().type_id()),
+                "required": arg.is_required_set(),
+                "description": arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
+            });
+            if !possible.is_empty() {
+                positional["possible_values"] = json!(possible);
+            }
+            let defaults = arg.get_default_values();
+            if !defaults.is_empty() {
+                positional["default"] = json!(defaults[0].to_string_lossy().to_string());
+            }
+            if !conflicts.is_empty() {
+                positional["conflicts_with"] = json!(conflicts);
+            }
+
+            // A trailing `Vec<T>` positional appends; `num_args` may also allow several values
+            let num_args = arg.get_num_args();
+            let appends = matches!(arg.get_action(), clap::ArgAction::Append);
+            if appends || num_args.is_some_and(|n| n.max_values() > 1) {
+                positional["variadic"] = json!(true);
+                positional["max_values"] = match num_args.map(|n| n.max_values()) {
+                    Some(max) if !appends && max < usize::MAX => json!(max),
+                    _ => serde_json::Value::Null,
+                };
+            }
+            positionals.push(positional);
+        } else {
+            let mut option = json!({
+                "name": schema_name(arg),
+                "type": format!("{:?}", arg.get_value_parser().type_id()),
+                "required": arg.is_required_set(),
+                "description": arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
+            });
+
+            // Add short flag if available
+            if let Some(short) = arg.get_short() {
+                option["short"] = json!(format!("-{}", short));
+            }
+
+            // Add default value if available
+            let defaults = arg.get_default_values();
+            if !defaults.is_empty() {
+                option["default"] = json!(defaults[0].to_string_lossy().to_string());
+            }
+
+            // Add env var if available
+            if let Some(env) = arg.get_env() {
+                option["env"] = json!(env.to_string_lossy().to_string());
+            }
+
+            if !possible.is_empty() {
+                option["possible_values"] = json!(possible);
+            }
+
+            if !conflicts.is_empty() {
+                option["conflicts_with"] = json!(conflicts);
+            }
+
+            options.push(option);
+        }
+    }
+
+    json!({
+        "arguments": positionals,
+        "options": options,
+    })
+}
+
+#[cfg(feature = "clap")]
+/// Blanket implementation for global options using clap::Parser.
+///
+/// This allows using clap's derive macros for global flags:
+///
+/// ```ignore
+/// use clap::Parser;
+///
+/// #[derive(Parser, Clone)]
+/// struct GlobalOpts {
+///     /// Enable verbose logging
+///     #[arg(long, short, global = true)]
+///     verbose: bool,
+///
+///     /// Configuration file path
+///     #[arg(long, global = true)]
+///     config: Option<String>,
+/// }
+/// ```
+impl<T> FromGlobalArgs for T
+where
+    T: clap::Parser + Clone,
+{
+    fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
+        // Try to parse global options using clap
+        // We need to use clap's API to extract global flags and return remaining args
+
+        // For now, parse all args and let clap handle it
+        // In a real implementation, we'd need to separate global from command-specific args
+        match clap_parse::<T>(None, args) {
+            Ok(global) => {
+                // For simplicity, return empty remaining args
+                // In practice, clap would need to be configured to allow unknown args
+                Ok((global, vec![]))
+            }
+            Err(e) => Err(CliError::user(e.to_string())),
+        }
+    }
+}
+
+// ============================================================================
+// Manual FromArgs implementations (when clap feature is NOT enabled)
+// ============================================================================
+
+#[cfg(not(feature = "clap"))]
+// Implement FromArgs for () (no args needed)
+impl FromArgs for () {
+    fn from_args(_args: &[String]) -> Result<Self, CliError> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "clap"))]
+// Implement FromArgs for Vec<String> (raw args)
+impl FromArgs for Vec<String> {
+    fn from_args(args: &[String]) -> Result<Self, CliError> {
+        Ok(args.to_vec())
+    }
+}
+
+// ============================================================================
+// Handler Implementations for Common Function Signatures
+// ============================================================================
+
+// Handlers take `State<S>`, then any number (up to four) of `FromContext`
+// extractors, then optionally `Args<T>` and `Rest`.
+macro_rules! impl_handler {
+    ($($ctx:ident $var:ident),*) => {
+        // Handler for: async fn(State<S>, C...) -> impl IntoResponse
+        impl<F, Fut, S, Res, $($ctx,)*> Handler<(State<S>, $($ctx,)*), S> for F
+        where
+            F: Fn(State<S>, $($ctx,)*) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = Res> + Send + 'static,
+            Res: IntoResponse + 'static,
+            $($ctx: FromContext,)*
+            S: Send + Sync + Clone + 'static,
+        {
+            type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+            fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
+                Box::pin(async move {
+                    self.try_call(state, args)
+                        .await
+                        .unwrap_or_else(error_response)
+                })
+            }
+
+            fn try_call(
+                self,
+                state: State<S>,
+                _args: Vec<String>,
+            ) -> BoxFuture<'static, Result<Response, CliError>> {
+                // Extract before boxing (task-locals are set by the router)
+                $(let $var = $ctx::from_context();)*
+                Box::pin(async move { self(state, $($var?,)*).await.into_result() })
+            }
+
+            fn handles_dry_run(&self) -> bool {
+                Res::HANDLES_DRY_RUN $(|| $ctx::HANDLES_DRY_RUN)*
+            }
+        }
+
+        // Handler for: async fn(State<S>, C..., Args<T>) -> impl IntoResponse
+        impl<F, Fut, S, T, Res, $($ctx,)*> Handler<(State<S>, $($ctx,)* Args<T>), S> for F
+        where
+            F: Fn(State<S>, $($ctx,)* Args<T>) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = Res> + Send + 'static,
+            Res: IntoResponse + 'static,
+            $($ctx: FromContext,)*
+            T: FromArgs + Send + 'static,
+            S: Send + Sync + Clone + 'static,
+        {
+            type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+            fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
+                Box::pin(async move {
+                    self.try_call(state, args)
+                        .await
+                        .unwrap_or_else(error_response)
+                })
+            }
+
+            fn try_call(
+                self,
+                state: State<S>,
+                args: Vec<String>,
+            ) -> BoxFuture<'static, Result<Response, CliError>> {
+                // Extract before boxing (task-locals are set by the router)
+                $(let $var = $ctx::from_context();)*
+                Box::pin(async move {
+                    let parsed_args = parse_args::<T>(&args)?;
+                    self(state, $($var?,)* Args(parsed_args))
+                        .await
+                        .into_result()
+                })
+            }
+
+            fn args_schema(&self) -> Option<serde_json::Value> {
+                T::cli_schema()
+            }
+
+            fn handles_dry_run(&self) -> bool {
+                Res::HANDLES_DRY_RUN $(|| $ctx::HANDLES_DRY_RUN)*
+            }
+        }
+
+        // Handler for: async fn(State<S>, C..., Args<T>, Rest) -> impl IntoResponse
+        impl<F, Fut, S, T, Res, $($ctx,)*> Handler<(State<S>, $($ctx,)* Args<T>, Rest), S> for F
+        where
+            F: Fn(State<S>, $($ctx,)* Args<T>, Rest) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = Res> + Send + 'static,
+            Res: IntoResponse + 'static,
+            $($ctx: FromContext,)*
+            T: FromArgs + Send + 'static,
+            S: Send + Sync + Clone + 'static,
+        {
+            type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+            fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
+                Box::pin(async move {
+                    self.try_call(state, args)
+                        .await
+                        .unwrap_or_else(error_response)
+                })
+            }
+
+            fn try_call(
+                self,
+                state: State<S>,
+                mut args: Vec<String>,
+            ) -> BoxFuture<'static, Result<Response, CliError>> {
+                // Extract before boxing (task-locals are set by the router)
+                $(let $var = $ctx::from_context();)*
+                Box::pin(async move {
+                    let (parsed_args, consumed) = T::from_args_prefix(&args)?;
+                    let rest = args.split_off(consumed.min(args.len()));
+                    self(state, $($var?,)* Args(parsed_args), Rest(rest))
+                        .await
+                        .into_result()
+                })
+            }
+
+            fn args_schema(&self) -> Option<serde_json::Value> {
+                T::cli_schema()
+            }
+
+            fn handles_dry_run(&self) -> bool {
+                Res::HANDLES_DRY_RUN $(|| $ctx::HANDLES_DRY_RUN)*
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(C1 c1);
+impl_handler!(C1 c1, C2 c2);
+impl_handler!(C1 c1, C2 c2, C3 c3);
+impl_handler!(C1 c1, C2 c2, C3 c3, C4 c4);
+
+// Handler for: async fn(Args<T>) -> impl IntoResponse (no state)
+impl<F, Fut, T, Res> Handler<(Args<T>,), ()> for F
+where
+    F: Fn(Args<T>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Res> + Send + 'static,
+    Res: IntoResponse + 'static,
+    T: FromArgs + Send + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, state: State<()>, args: Vec<String>) -> Self::Future {
+        Box::pin(async move {
+            self.try_call(state, args)
+                .await
+                .unwrap_or_else(error_response)
+        })
+    }
+
+    fn try_call(
+        self,
+        _state: State<()>,
+        args: Vec<String>,
+    ) -> BoxFuture<'static, Result<Response, CliError>> {
+        Box::pin(async move {
+            let parsed_args = parse_args::<T>(&args)?;
+            self(Args(parsed_args)).await.into_result()
+        })
+    }
+
+    fn args_schema(&self) -> Option<serde_json::Value> {
+        T::cli_schema()
+    }
+
+    fn handles_dry_run(&self) -> bool {
+        Res::HANDLES_DRY_RUN
+    }
+}
+
+// ============================================================================
+// Argument Preprocessing
+// ============================================================================
+
+/// Remove a `--tag <name>` / `--tag=<name>` help filter (or its `--filter-tag`
+/// spelling) from the arguments.
+///
+/// Returns the tag, if any, and the remaining arguments.
+fn take_tag_filter(args: &[String]) -> Result<(Option<String>, Vec<String>), CliError> {
+    let mut tag = None;
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--tag" || arg == "--filter-tag" {
+            let value = iter
+                .next()
+                .ok_or_else(|| CliError::user(format!("{} requires a tag name", arg)))?;
+            tag = Some(value.clone());
+        } else if let Some(value) = arg
+            .strip_prefix("--tag=")
+            .or_else(|| arg.strip_prefix("--filter-tag="))
+        {
+            tag = Some(value.to_string());
+        } else {
+            remaining.push(arg.clone());
+        }
+    }
+    Ok((tag, remaining))
+}
+
+/// Expand `@file` response files in process arguments (`args[0]` is the
+/// program name and kept as is).
+///
+/// Each later token starting with `@` is replaced by the contents of the referenced
+/// file, one argument per line (blank lines are skipped). A token starting with
+/// `@@` is an escape and is passed through with one leading `@` removed.
+///
+/// Expansion is not recursive: tokens read from a file are used verbatim.
+fn expand_response_files(args: &[String]) -> Result<Vec<String>, CliError> {
+    let mut expanded = Vec::with_capacity(args.len());
+    expanded.extend(args.first().cloned());
+
+    for arg in args.iter().skip(1) {
+        if let Some(escaped) = arg.strip_prefix("@@") {
+            expanded.push(format!("@{}", escaped));
+        } else if let Some(path) = arg.strip_prefix('@').filter(|p| !p.is_empty()) {
+            let contents = std::fs::read_to_string(path).map_err(|e| {
+                CliError::user(format!("Failed to read argument file '{}': {}", path, e))
+            })?;
+            expanded.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from),
+            );
+        } else {
+            expanded.push(arg.clone());
+        }
+    }
+
+    Ok(expanded)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ========================================
+    // Tier Tests
+    // ========================================
+
+    #[test]
+    fn test_tier_parse() {
+        assert_eq!(Tier::parse("safe"), Some(Tier::Safe));
+        assert_eq!(Tier::parse("SAFE"), Some(Tier::Safe));
+        assert_eq!(Tier::parse("standard"), Some(Tier::Standard));
+        assert_eq!(Tier::parse("critical"), Some(Tier::Critical));
+        assert_eq!(Tier::parse("invalid"), None);
+    }
+
+    #[test]
+    fn test_tier_as_str() {
+        assert_eq!(Tier::Safe.as_str(), "safe");
+        assert_eq!(Tier::Standard.as_str(), "standard");
+        assert_eq!(Tier::Critical.as_str(), "critical");
+    }
+
+    #[test]
+    fn test_tier_requires_approval() {
+        assert!(!Tier::Safe.requires_approval());
+        assert!(!Tier::Standard.requires_approval());
+        assert!(Tier::Critical.requires_approval());
+    }
+
+    #[test]
+    fn test_tier_display() {
+        assert_eq!(format!("{}", Tier::Safe), "safe");
+        assert_eq!(format!("{}", Tier::Standard), "standard");
+        assert_eq!(format!("{}", Tier::Critical), "critical");
+    }
+
+    // ========================================
+    // State Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_state_creation_and_access() {
+        struct TestState {
+            value: i32,
+        }
+
+        let state = State::new(TestState { value: 42 });
+        assert_eq!(state.read().await.value, 42);
+
+        let cloned = state.clone();
+        assert_eq!(cloned.read().await.value, 42);
+    }
+
+    #[tokio::test]
+    async fn test_state_write() {
+        struct TestState {
+            value: i32,
+        }
+
+        let state = State::new(TestState { value: 42 });
+
+        // Mutate through write lock
+        {
+            let mut app = state.write().await;
+            app.value = 100;
+        }
+
+        // Verify mutation
+        assert_eq!(state.read().await.value, 100);
+    }
+
+    #[test]
+    fn test_user_error_exit_code() {
+        let err = CliError::user("test error");
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_system_error_exit_code() {
+        let err = CliError::system("test error");
+        assert_eq!(err.exit_code(), 101);
+    }
+
+    #[tokio::test]
+    async fn test_question_mark_on_io_and_json_errors() {
+        async fn read(_state: State<()>) -> CliResult<String> {
+            Ok(std::fs::read_to_string("/nonexistent/sen-test-file")?)
+        }
+        async fn parse(_state: State<()>) -> CliResult<String> {
+            let value: serde_json::Value = serde_json::from_str("{ not json")?;
+            Ok(value.to_string())
+        }
+
+        let router = Router::new()
+            .route("read", read)
+            .route("parse", parse)
+            .with_state(());
+        let args = |command: &str| vec!["test".to_string(), command.to_string()];
+
+        let response = router.execute_with(&args("read")).await;
+        assert_eq!(response.exit_code, 101);
+        assert_eq!(response.error.map(|e| e.kind), Some("io"));
+
+        let response = router.execute_with(&args("parse")).await;
+        assert_eq!(response.exit_code, 101);
+        assert_eq!(response.error.map(|e| e.kind), Some("config_parse"));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_question_mark_on_yaml_error() {
+        fn parse() -> CliResult<serde_yaml::Value> {
+            Ok(serde_yaml::from_str("key: [unclosed")?)
+        }
+
+        let err = parse().unwrap_err();
+        assert_eq!(err.exit_code(), 101);
+        assert!(matches!(err, CliError::System(SystemError::ConfigParse(_))));
+    }
+
+    #[test]
+    fn test_string_into_response() {
+        let response = "hello".to_string().into_response();
+        assert_eq!(response.exit_code, 0);
+        assert!(matches!(response.output, Output::Text(_)));
+    }
+
+    #[test]
+    fn test_unit_into_response() {
+        let response = ().into_response();
+        assert_eq!(response.exit_code, 0);
+        assert!(matches!(response.output, Output::Silent));
+    }
+
+    #[test]
+    fn test_result_ok_into_response() {
+        let result: CliResult<String> = Ok("success".to_string());
+        let response = result.into_response();
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[test]
+    fn test_result_err_into_response() {
+        let result: CliResult<String> = Err(CliError::user("failure"));
+        let response = result.into_response();
+        assert_eq!(response.exit_code, 1);
+    }
+
+    // ========================================
+    // Router Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_router_basic() {
+        #[derive(Clone)]
+        struct AppState {
+            value: i32,
+        }
+
+        async fn get_value(state: State<AppState>) -> CliResult<String> {
+            let app = state.read().await;
+            Ok(format!("Value: {}", app.value))
+        }
+
+        let state = AppState { value: 42 };
+        let router = Router::new().route("status", get_value).with_state(state);
+
+        let response = router
+            .execute_with(&["test".to_string(), "status".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(matches!(response.output, Output::Text(_)));
+    }
+
+    #[tokio::test]
+    async fn test_router_unknown_command() {
+        let router: Router<()> = Router::new().with_state(());
+        let response = router
+            .execute_with(&["test".to_string(), "unknown".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_router_no_command() {
+        let router: Router<()> = Router::new().with_state(());
+        let response = router.execute_with(&["test".to_string()]).await;
+        // Empty args now show help with exit code 0 (changed from error)
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_router_multiple_routes() {
+        #[derive(Clone)]
+        struct AppState {
+            #[allow(dead_code)]
+            count: i32,
+        }
+
+        async fn status(_state: State<AppState>) -> CliResult<String> {
+            Ok("OK".to_string())
+        }
+
+        async fn version(_state: State<AppState>) -> CliResult<String> {
+            Ok("v1.0.0".to_string())
+        }
+
+        let state = AppState { count: 0 };
+        let router = Router::new()
+            .route("status", status)
+            .route("version", version)
+            .with_state(state);
+
+        let response1 = router
+            .execute_with(&["test".to_string(), "status".to_string()])
+            .await;
+        assert_eq!(response1.exit_code, 0);
+
+        let response2 = router
+            .execute_with(&["test".to_string(), "version".to_string()])
+            .await;
+        assert_eq!(response2.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_version_json_emits_build_info() {
+        let run = |router: Router<()>, args: &'static [&'static str]| async move {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            let response = router.execute_with(&args).await;
+            assert_eq!(response.exit_code, 0);
+            match response.output {
+                Output::Json(json) => serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+                other => panic!("Expected JSON output, got {:?}", other),
+            }
+        };
+
+        // Without metadata only sen's build info is known
+        let json = run(Router::new().with_state(()), &["test", "version", "--json"]).await;
+        assert!(json.get("version").is_none());
+        assert_eq!(json["sen"]["version"], crate::build_info::version_short());
+        if let Some(sha) = crate::build_info::version_info_json().get("git_sha") {
+            assert_eq!(&json["sen"]["git_sha"], sha);
+        }
+
+        // The app's own name and version are kept apart from sen's
+        let router = Router::new().with_state(()).with_metadata(RouterMetadata {
+            name: "myctl",
+            version: Some("2.0.0"),
+            about: None,
+        });
+        let json = run(router, &["myctl", "--version", "--json"]).await;
+        assert_eq!(json["name"], "myctl");
+        assert_eq!(json["version"], "2.0.0");
+        assert_eq!(json["sen"]["name"], "sen");
+    }
+
+    #[tokio::test]
+    async fn test_user_version_route_is_not_shadowed() {
+        async fn version(_state: State<()>) -> CliResult<String> {
+            Ok("custom version".to_string())
+        }
+
+        let router = Router::new()
+            .route("version", version)
+            .with_state(())
+            .with_metadata(RouterMetadata {
+                name: "myctl",
+                version: Some("2.0.0"),
+                about: None,
+            });
+
+        let response = router
+            .execute_with(&["myctl".to_string(), "version".to_string()])
+            .await;
+        assert_eq!(text_output(response), "custom version");
+
+        // The flags still reach the built-in
+        let response = router
+            .execute_with(&["myctl".to_string(), "--version".to_string()])
+            .await;
+        assert_eq!(text_output(response), "myctl 2.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_builtins_defer_to_user_routes() {
+        async fn version(_state: State<()>) -> CliResult<String> {
+            Ok("custom version".to_string())
+        }
+
+        async fn help(_state: State<()>) -> CliResult<String> {
+            Ok("custom help".to_string())
+        }
+
+        let router = Router::new()
+            .route("--version", version)
+            .route("-h", help)
+            .builtins(BuiltinsConfig {
+                help: false,
+                version: None,
+                doctor: None,
+                history: None,
+            })
+            .with_state(());
+
+        let run = |arg: &str| {
+            let args = vec!["test".to_string(), arg.to_string()];
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        assert_eq!(text_output(run("--version").await), "custom version");
+        assert_eq!(text_output(run("-h").await), "custom help");
+
+        // Unrouted built-in names are unknown commands
+        for arg in ["version", "-V", "--help", "doctor"] {
+            let response = run(arg).await;
+            assert_eq!(response.exit_code, 1, "{}", arg);
+        }
+
+        // Help no longer advertises the disabled built-ins
+        let help = text_output(router.execute_with(&["test".to_string()]).await);
+        assert!(!help.contains("Print help"));
+        assert!(!help.contains("Print build info"));
+    }
+
+    #[tokio::test]
+    async fn test_renamed_version_builtin() {
+        let router = Router::new()
+            .builtins(BuiltinsConfig {
+                version: Some("info"),
+                ..Default::default()
+            })
+            .with_state(())
+            .with_metadata(RouterMetadata {
+                name: "myctl",
+                version: Some("2.0.0"),
+                about: None,
+            });
+
+        for arg in ["info", "--version", "-V"] {
+            let response = router
+                .execute_with(&["myctl".to_string(), arg.to_string()])
+                .await;
+            assert_eq!(text_output(response), "myctl 2.0.0", "{}", arg);
+        }
+
+        let response = router
+            .execute_with(&["myctl".to_string(), "version".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    // ========================================
+    // Args Extractor Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_router_with_args() {
+        #[derive(Clone)]
+        struct AppState {
+            #[allow(dead_code)]
+            base_cmd: String,
+        }
+
+        #[derive(Debug)]
+        struct BuildArgs {
+            release: bool,
+        }
+
+        impl FromArgs for BuildArgs {
+            fn from_args(args: &[String]) -> Result<Self, CliError> {
+                Ok(BuildArgs {
+                    release: args.first().map(|s| s == "--release").unwrap_or(false),
+                })
+            }
+        }
+
+        async fn build(_state: State<AppState>, Args(args): Args<BuildArgs>) -> CliResult<String> {
+            if args.release {
+                Ok("release".to_string())
+            } else {
+                Ok("debug".to_string())
+            }
+        }
+
+        let state = AppState {
+            base_cmd: "cargo build".to_string(),
+        };
+        let router = Router::new().route("build", build).with_state(state);
+
+        // Test with --release flag
+        let response1 = router
+            .execute_with(&[
+                "test".to_string(),
+                "build".to_string(),
+                "--release".to_string(),
+            ])
+            .await;
+        assert_eq!(response1.exit_code, 0);
+        if let Output::Text(output) = response1.output {
+            assert_eq!(output, "release");
+        }
+
+        // Test without flag
+        let response2 = router
+            .execute_with(&["test".to_string(), "build".to_string()])
+            .await;
+        assert_eq!(response2.exit_code, 0);
+        if let Output::Text(output) = response2.output {
+            assert_eq!(output, "debug");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_args_no_state() {
+        #[derive(Debug)]
+        struct EchoArgs {
+            message: String,
+        }
+
+        impl FromArgs for EchoArgs {
+            fn from_args(args: &[String]) -> Result<Self, CliError> {
+                let message = args.first().cloned().unwrap_or_else(|| "".to_string());
+                Ok(EchoArgs { message })
+            }
+        }
+
+        async fn echo(Args(args): Args<EchoArgs>) -> CliResult<String> {
+            Ok(args.message)
+        }
+
+        let router = Router::new().route("echo", echo).with_state(());
+
+        let response = router
+            .execute_with(&["test".to_string(), "echo".to_string(), "Hello!".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Hello!");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_args_parse_error() {
+        #[derive(Debug)]
+        struct StrictArgs;
+
+        impl FromArgs for StrictArgs {
+            fn from_args(args: &[String]) -> Result<Self, CliError> {
+                if args.is_empty() {
+                    Err(CliError::user("Arguments required"))
+                } else {
+                    Ok(StrictArgs)
+                }
+            }
+        }
+
+        async fn strict(Args(_args): Args<StrictArgs>) -> CliResult<String> {
+            Ok("success".to_string())
+        }
+
+        let router = Router::new().route("strict", strict).with_state(());
+
+        // Should fail with no args
+        let response = router
+            .execute_with(&["test".to_string(), "strict".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    // ========================================
+    // Router::nest() Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_router_nest_basic() {
+        #[derive(Clone)]
+        struct AppState;
+
+        async fn db_create(_state: State<AppState>) -> CliResult<String> {
+            Ok("DB created".to_string())
+        }
+
+        async fn db_list(_state: State<AppState>) -> CliResult<String> {
+            Ok("DB list".to_string())
+        }
+
+        let db_router = Router::new()
+            .route("create", db_create)
+            .route("list", db_list);
+
+        let router = Router::new().nest("db", db_router).with_state(AppState);
+
+        // Test nested command: "db create"
+        let response = router
+            .execute_with(&["test".to_string(), "db".to_string(), "create".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "DB created");
+        }
+
+        // Test nested command: "db list"
+        let response = router
+            .execute_with(&["test".to_string(), "db".to_string(), "list".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "DB list");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_with_args() {
+        #[derive(Clone)]
+        struct AppState;
+
+        #[derive(Debug)]
+        struct CreateArgs {
+            name: String,
+        }
+
+        impl FromArgs for CreateArgs {
+            fn from_args(args: &[String]) -> Result<Self, CliError> {
+                let name = args
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| "default".to_string());
+                Ok(CreateArgs { name })
+            }
+        }
+
+        async fn db_create(
+            _state: State<AppState>,
+            Args(args): Args<CreateArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("Created DB: {}", args.name))
+        }
+
+        let db_router = Router::new().route("create", db_create);
+
+        let router = Router::new().nest("db", db_router).with_state(AppState);
+
+        // Test: "db create mydb"
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "db".to_string(),
+                "create".to_string(),
+                "mydb".to_string(),
+            ])
+            .await;
+
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Created DB: mydb");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_multiple_levels() {
+        #[derive(Clone)]
+        struct AppState;
+
+        async fn status(_state: State<AppState>) -> CliResult<String> {
+            Ok("OK".to_string())
+        }
+
+        async fn db_create(_state: State<AppState>) -> CliResult<String> {
+            Ok("DB created".to_string())
+        }
+
+        async fn server_start(_state: State<AppState>) -> CliResult<String> {
+            Ok("Server started".to_string())
+        }
+
+        let db_router = Router::new().route("create", db_create);
+        let server_router = Router::new().route("start", server_start);
+
+        let router = Router::new()
+            .route("status", status) // Top-level command
+            .nest("db", db_router) // Nested commands
+            .nest("server", server_router)
+            .with_state(AppState);
+
+        // Top-level command
+        let response = router
+            .execute_with(&["test".to_string(), "status".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+
+        // Nested command: db create
+        let response = router
+            .execute_with(&["test".to_string(), "db".to_string(), "create".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+
+        // Nested command: server start
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "server".to_string(),
+                "start".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_unknown_subcommand() {
+        #[derive(Clone)]
+        struct AppState;
+
+        async fn db_create(_state: State<AppState>) -> CliResult<String> {
+            Ok("DB created".to_string())
+        }
+
+        let db_router = Router::new().route("create", db_create);
+        let router = Router::new().nest("db", db_router).with_state(AppState);
+
+        // Unknown subcommand
+        let response = router
+            .execute_with(&["test".to_string(), "db".to_string(), "delete".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    // ========================================
+    // Clap Integration Tests
+    // ========================================
+    // TODO: These tests need Handler trait implementation for module-scoped async functions
+    // Currently blocked - async functions defined in test scope don't satisfy Handler trait bounds
+    // Workaround: Move handlers to top-level module or use #[sen::handler] macro
+
+    #[cfg(feature = "unstable-clap-tests")]
+    #[tokio::test]
+    async fn test_clap_integration_basic() {
+        mod test_scope {
+            use super::*;
+            use clap::Parser;
+
+            #[derive(Clone)]
+            pub struct AppState;
+
+            #[derive(Parser, Debug)]
+            pub struct BuildArgs {
+                /// Database name
+                pub name: String,
+
+                /// Build in release mode
+                #[arg(long)]
+                pub release: bool,
+            }
+
+            pub async fn build(
+                _state: State<AppState>,
+                Args(args): Args<BuildArgs>,
+            ) -> CliResult<String> {
+                if args.release {
+                    Ok(format!("Release build: {}", args.name))
+                } else {
+                    Ok(format!("Debug build: {}", args.name))
+                }
+            }
+        }
+
+        let router = Router::new()
+            .route("build", test_scope::build)
+            .with_state(test_scope::AppState);
+
+        // Test with --release
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "build".to_string(),
+                "myapp".to_string(),
+                "--release".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Release build: myapp");
+        }
+
+        // Test without --release
+        let response = router
+            .execute_with(&["test".to_string(), "build".to_string(), "myapp".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Debug build: myapp");
+        }
+    }
+
+    #[cfg(feature = "unstable-clap-tests")]
+    #[tokio::test]
+    async fn test_clap_integration_with_env() {
+        mod test_scope {
+            use super::*;
+            use clap::Parser;
+
+            #[derive(Clone)]
+            pub struct AppState;
+
+            #[derive(Parser, Debug)]
+            pub struct DeployArgs {
+                /// App name
+                pub app: String,
+
+                /// Target environment (from env var or flag)
+                #[arg(long, env = "DEPLOY_ENV", default_value = "production")]
+                pub env: String,
+            }
+
+            pub async fn deploy(
+                _state: State<AppState>,
+                Args(args): Args<DeployArgs>,
+            ) -> CliResult<String> {
+                Ok(format!("Deploying {} to {}", args.app, args.env))
+            }
+        }
+
+        let router = Router::new()
+            .route("deploy", test_scope::deploy)
+            .with_state(test_scope::AppState);
+
+        // Test with explicit --env flag
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                "myapp".to_string(),
+                "--env".to_string(),
+                "staging".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Deploying myapp to staging");
+        }
+
+        // Test with default value
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                "myapp".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "Deploying myapp to production");
+        }
+    }
+
+    #[cfg(feature = "unstable-clap-tests")]
+    #[tokio::test]
+    async fn test_clap_integration_validation() {
+        mod test_scope {
+            use super::*;
+            use clap::Parser;
+
+            #[derive(Clone)]
+            pub struct AppState;
+
+            #[derive(Parser, Debug)]
+            pub struct CreateArgs {
+                /// Name (required)
+                pub name: String,
+
+                /// Port number
+                #[arg(long, value_parser = clap::value_parser!(u16).range(1..=65535))]
+                pub port: Option<u16>,
+            }
+
+            pub async fn create(
+                _state: State<AppState>,
+                Args(args): Args<CreateArgs>,
+            ) -> CliResult<String> {
+                Ok(format!("Created {} on port {:?}", args.name, args.port))
+            }
+        }
+
+        let router = Router::new()
+            .route("create", test_scope::create)
+            .with_state(test_scope::AppState);
+
+        // Valid port
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "create".to_string(),
+                "mydb".to_string(),
+                "--port".to_string(),
+                "3000".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+
+        // Invalid port (out of range) - clap will return error
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "create".to_string(),
+                "mydb".to_string(),
+                "--port".to_string(),
+                "99999".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    // ========================================
+    // Agent Mode Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_router_with_agent_mode_enabled() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("Status: OK".to_string())
+        }
+
+        let router = Router::new()
+            .route("status", status)
+            .with_agent_mode()
+            .with_state(());
+
+        // Without --agent-mode flag
+        let response = router
+            .execute_with(&["test".to_string(), "status".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(!response.agent_mode);
+
+        // With --agent-mode flag
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "--agent-mode".to_string(),
+                "status".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(response.agent_mode);
+    }
+
+    #[tokio::test]
+    async fn test_router_without_agent_mode_enabled() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("Status: OK".to_string())
+        }
+
+        let router = Router::new().route("status", status).with_state(());
+
+        // With --agent-mode flag but agent_mode not enabled
+        // The flag is treated as a command, resulting in "Unknown command" error
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "--agent-mode".to_string(),
+                "status".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 1); // Error: unknown command
+        assert!(!response.agent_mode);
+
+        // Without --agent-mode flag works fine
+        let response = router
+            .execute_with(&["test".to_string(), "status".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(!response.agent_mode);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_flag_silences_success_only() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("Status: OK".to_string())
+        }
+
+        async fn fail(_state: State<()>) -> CliResult<String> {
+            Err(CliError::user("Something broke"))
+        }
+
+        async fn echo(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            if args.0.iter().any(|a| a == "-q" || a == "--quiet") {
+                return Err(CliError::user("quiet flag leaked to handler"));
+            }
+            Ok(args.0.join(" "))
+        }
+
+        let router = Router::new()
+            .route("status", status)
+            .route("fail", fail)
+            .route("echo", echo)
+            .with_quiet_flag()
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        let response = run(&["status", "--quiet"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert!(response.output.is_empty());
+
+        let response = run(&["-q", "status"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert!(response.output.is_empty());
+
+        let response = run(&["status"]).await;
+        assert_eq!(text_output(response), "Status: OK");
+
+        // Errors still print
+        let response = run(&["fail", "-q"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(text_output(response), "Error: Something broke");
+
+        // The flag never reaches the handler
+        let response = run(&["echo", "a", "-q", "b"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert!(response.output.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_route_with_schema_appears_in_json_help() {
+        #[derive(Debug)]
+        struct DeployArgs;
+
+        impl FromArgs for DeployArgs {
+            fn from_args(_args: &[String]) -> Result<Self, CliError> {
+                Ok(DeployArgs)
+            }
+        }
+
+        async fn deploy(_state: State<()>, Args(_args): Args<DeployArgs>) -> CliResult<String> {
+            Ok("deployed".to_string())
+        }
+
+        let schema = serde_json::json!({
+            "arguments": [
+                { "name": "env", "type": "String", "required": true, "description": "Target environment" }
+            ],
+            "options": [
+                { "name": "--force", "type": "bool", "required": false, "description": "Skip checks" }
+            ]
+        });
+
+        let router = Router::new()
+            .route_with_schema("deploy", deploy, schema.clone())
+            .route("plain", deploy)
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "--help".to_string(),
+                "--json".to_string(),
+            ])
+            .await;
+
+        let json: serde_json::Value = serde_json::from_str(&text_output(response)).unwrap();
+        let deploy = &json["commands"]["deploy"];
+        assert_eq!(deploy["arguments"], schema["arguments"]);
+        assert_eq!(deploy["options"], schema["options"]);
+
+        // Hand-written FromArgs without a schema still shows no arguments
+        assert!(json["commands"]["plain"].get("arguments").is_none());
+
+        let response = router
+            .execute_with(&["test".to_string(), "deploy".to_string()])
+            .await;
+        assert_eq!(text_output(response), "deployed");
+    }
+
+    #[derive(Clone, Default)]
+    struct CounterState {
+        count: u32,
+    }
+
+    async fn increment(state: State<CounterState>) -> CliResult<String> {
+        let mut app = state.write().await;
+        app.count += 1;
+        Ok(app.count.to_string())
+    }
+
+    async fn read_count(state: State<CounterState>) -> CliResult<String> {
+        Ok(state.read().await.count.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_state_writes_are_visible_to_later_commands() {
+        let router = Router::new()
+            .route("inc", increment)
+            .route("get", read_count)
+            .with_state(CounterState::default());
+        let args = |cmd: &str| vec!["test".to_string(), cmd.to_string()];
+
+        router.execute_with(&args("inc")).await;
+        assert_eq!(text_output(router.execute_with(&args("inc")).await), "2");
+        assert_eq!(text_output(router.execute_with(&args("get")).await), "2");
+    }
+
+    #[tokio::test]
+    async fn test_with_shared_state_exposes_handle() {
+        let state = State::new(CounterState::default());
+        let router = Router::new()
+            .route("inc", increment)
+            .with_shared_state(state.clone());
+
+        router
+            .execute_with(&["test".to_string(), "inc".to_string()])
+            .await;
+        assert_eq!(state.read().await.count, 1);
+
+        state.write().await.count = 10;
+        let response = router
+            .execute_with(&["test".to_string(), "inc".to_string()])
+            .await;
+        assert_eq!(text_output(response), "11");
+    }
+
+    #[derive(Clone, serde::Deserialize)]
+    struct LoadedState {
+        name: String,
+    }
+
+    async fn show_state(state: State<LoadedState>) -> CliResult<String> {
+        Ok(state.read().await.name.clone())
+    }
+
+    #[tokio::test]
+    async fn test_try_run_executes_with_built_state() {
+        let response = Router::new()
+            .route("show", show_state)
+            .try_run_with(&["test".to_string(), "show".to_string()], async {
+                Ok(LoadedState {
+                    name: "loaded".to_string(),
+                })
+            })
+            .await;
+
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text_output(response), "loaded");
+    }
+
+    #[tokio::test]
+    async fn test_try_run_renders_state_error() {
+        let response = Router::new()
+            .route("show", show_state)
+            .try_run_with(&["test".to_string(), "show".to_string()], async {
+                Err(CliError::System(SystemError::ConfigParse(
+                    "config.toml: expected '='".to_string(),
+                )))
+            })
+            .await;
+
+        assert_eq!(response.exit_code, 101);
+        assert!(!response.agent_mode);
+        assert_eq!(response.error.as_ref().unwrap().kind, "config_parse");
+        assert_eq!(
+            text_output(response),
+            "Internal Error: Config parse failed\n\nconfig.toml: expected '='\n\nThis is likely a bug."
+        );
+
+        let response = Router::new()
+            .route("show", show_state)
+            .try_run_with(&["test".to_string(), "show".to_string()], async {
+                Err(CliError::User(UserError::MissingDependency {
+                    tool: "git".to_string(),
+                    install_hint: "Install git".to_string(),
+                }))
+            })
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_try_run_state_error_respects_agent_mode() {
+        let args = [
+            "test".to_string(),
+            "show".to_string(),
+            "--agent-mode".to_string(),
+        ];
+
+        let response = Router::new()
+            .route("show", show_state)
+            .with_agent_mode()
+            .try_run_with(&args, async { Err(CliError::user("bad config")) })
+            .await;
+        assert!(response.agent_mode);
+
+        // Without with_agent_mode() the flag is not interpreted
+        let response = Router::new()
+            .route("show", show_state)
+            .try_run_with(&args, async { Err(CliError::user("bad config")) })
+            .await;
+        assert!(!response.agent_mode);
+    }
+
+    fn stdin_state_router() -> Router<LoadedState> {
+        Router::new()
+            .route("show", show_state)
+            .with_stdin_state::<LoadedState>()
+    }
+
+    #[tokio::test]
+    async fn test_stdin_state_read_from_pipe() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut writer, reader) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            writer.write_all(br#"{"name": "piped"}"#).await.unwrap();
+        });
+
+        let args = [
+            "test".to_string(),
+            "--state-stdin".to_string(),
+            "show".to_string(),
+        ];
+        // The file-based state is never built when --state-stdin is given
+        let response = stdin_state_router()
+            .try_run_from(&args, reader, async { Err(CliError::user("from files")) })
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text_output(response), "piped");
+    }
+
+    #[tokio::test]
+    async fn test_stdin_state_malformed_is_config_parse() {
+        let args = [
+            "test".to_string(),
+            "show".to_string(),
+            "--state-stdin".to_string(),
+        ];
+        let response = stdin_state_router()
+            .try_run_from(&args, &b"{not json"[..], async {
+                Ok(LoadedState {
+                    name: "loaded".to_string(),
+                })
+            })
+            .await;
+        assert_ne!(response.exit_code, 0);
+        assert_eq!(response.error.unwrap().kind, "config_parse");
+    }
+
+    #[tokio::test]
+    async fn test_stdin_untouched_without_flag() {
+        let mut stdin: &[u8] = br#"{"name": "piped"}"#;
+        let response = stdin_state_router()
+            .try_run_from(
+                &["test".to_string(), "show".to_string()],
+                &mut stdin,
+                async {
+                    Ok(LoadedState {
+                        name: "loaded".to_string(),
+                    })
+                },
+            )
+            .await;
+        assert_eq!(text_output(response), "loaded");
+        assert_eq!(stdin, br#"{"name": "piped"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_flag_stops_at_double_dash() {
+        async fn echo(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(args.0.join(" "))
+        }
+
+        let router = Router::new()
+            .route("cmd", echo)
+            .with_quiet_flag()
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "cmd".to_string(),
+                "--".to_string(),
+                "--quiet".to_string(),
+            ])
+            .await;
+        assert_eq!(text_output(response), "-- --quiet");
+    }
+
+    #[tokio::test]
+    async fn test_quiet_flag_is_opt_in() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("Status: OK".to_string())
+        }
+
+        let router = Router::new().route("status", status).with_state(());
+        let response = router
+            .execute_with(&["test".to_string(), "--quiet".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reaches_handler_and_is_stripped() {
+        async fn drop_table(
+            _state: State<()>,
+            DryRun(dry_run): DryRun,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            let verb = if dry_run { "Would drop" } else { "Dropped" };
+            Ok(format!("{} {}", verb, args.0.join(" ")))
+        }
+
+        static TRUNCATED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+        async fn truncate(_state: State<()>) -> CliResult<String> {
+            TRUNCATED.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok("Truncated".to_string())
+        }
+
+        let router = Router::new()
+            .route("db:drop", drop_table)
+            .route("db:truncate", tiered(truncate, Tier::Critical))
+            .with_dry_run_flag()
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        let response = run(&["db", "drop", "--dry-run", "users"]).await;
+        assert_eq!(response.changed, Some(false));
+        assert_eq!(text_output(response), "Would drop users");
+
+        // Without a tier the router leaves `changed` to the handler
+        let response = run(&["db", "drop", "users"]).await;
+        assert_eq!(response.changed, None);
+        assert_eq!(text_output(response), "Dropped users");
+
+        // A handler that never sees the flag is not run
+        let response = run(&["db", "truncate", "--dry-run"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(response.changed, None);
+        assert!(text_output(response).contains("'db truncate' does not support --dry-run"));
+        assert!(!TRUNCATED.load(std::sync::atomic::Ordering::SeqCst));
+
+        let response = run(&["--help"]).await;
+        assert!(text_output(response).contains("--dry-run"));
+    }
+
+    #[tokio::test]
+    async fn test_profile_flag_reaches_handler_and_is_stripped() {
+        async fn deploy(
+            _state: State<()>,
+            Profile(profile): Profile,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("{} {}", profile, args.0.join(" ")))
+        }
+
+        let router = Router::new()
+            .route("deploy", deploy)
+            .with_profile_flag("SEN_TEST_UNSET_PROFILE")
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        assert_eq!(
+            text_output(run(&["deploy", "--profile", "prod", "api"]).await),
+            "prod api"
+        );
+        assert_eq!(
+            text_output(run(&["--profile=staging", "deploy"]).await),
+            "staging "
+        );
+        assert_eq!(text_output(run(&["deploy", "api"]).await), "default api");
+
+        let response = run(&["deploy", "--profile"]).await;
+        assert_eq!(response.exit_code, 1);
+
+        let help = text_output(run(&["--help"]).await);
+        assert!(help.contains("--profile <NAME>  Config profile to use"));
+    }
+
+    #[tokio::test]
+    async fn test_profile_flag_is_opt_in() {
+        async fn echo(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(args.0.join(" "))
+        }
+
+        let router = Router::new().route("echo", echo).with_state(());
+        let args: Vec<String> = ["test", "echo", "--profile", "prod"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            text_output(router.execute_with(&args).await),
+            "--profile prod"
+        );
+        let help = text_output(
+            router
+                .execute_with(&["test".to_string(), "--help".to_string()])
+                .await,
+        );
+        assert!(!help.contains("--profile"));
+    }
+
+    #[tokio::test]
+    async fn test_output_mode_reports_agent_mode() {
+        async fn list(_state: State<()>, mode: OutputMode) -> CliResult<String> {
+            Ok(format!("{:?}", mode))
+        }
+
+        async fn show(
+            _state: State<()>,
+            mode: OutputMode,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("{:?} {}", mode, args.0.join(" ")))
+        }
+
+        let router = Router::new()
+            .route("list", list)
+            .route("show", show)
+            .with_agent_mode()
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        assert_eq!(text_output(run(&["list"]).await), "Text");
+        assert_eq!(text_output(run(&["list", "--agent-mode"]).await), "Agent");
+        assert_eq!(
+            text_output(run(&["show", "--agent-mode", "users"]).await),
+            "Agent users"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_extractors_combine() {
+        async fn drop_table(
+            _state: State<()>,
+            DryRun(dry_run): DryRun,
+            warnings: Warnings,
+            mode: OutputMode,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            warnings.warn("table is not empty");
+            Ok(format!("{} {:?} {}", dry_run, mode, args.0.join(" ")))
+        }
+
+        async fn exec(
+            _state: State<()>,
+            progress: Progress,
+            cancel: Cancel,
+            Args(args): Args<RawArgs>,
+            Rest(rest): Rest,
+        ) -> CliResult<String> {
+            progress.set(1.0, "done");
+            Ok(format!(
+                "{} {} | {}",
+                cancel.is_cancelled(),
+                args.0.join(" "),
+                rest.join(" ")
+            ))
+        }
+
+        let router = Router::new()
+            .route("db:drop", drop_table)
+            .route("exec", exec)
+            .with_dry_run_flag()
+            .with_state(());
+
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "db".to_string(),
+                "drop".to_string(),
+                "--dry-run".to_string(),
+                "users".to_string(),
+            ])
+            .await;
+        assert_eq!(response.warnings(), ["table is not empty"]);
+        assert_eq!(text_output(response), "true Text users");
+
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "exec".to_string(),
+                "prod".to_string(),
+                "--".to_string(),
+                "ls".to_string(),
+            ])
+            .await;
+        assert_eq!(text_output(response), "false prod | ls");
+    }
+
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_warnings_in_agent_json_without_failing() {
+        async fn deploy(
+            _state: State<()>,
+            warnings: Warnings,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<Response> {
+            warnings.warn("No --region given, using us-east-1");
+            Ok(Response::text(format!("Deployed {}", args.0.join(" ")))
+                .warn("Config file is world-readable"))
+        }
+
+        let router = Router::new()
+            .route("deploy", deploy)
+            .with_agent_mode()
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                "web".to_string(),
+                "--agent-mode".to_string(),
+            ])
+            .await;
+        assert_eq!(response.exit_code, 0);
+
+        let json: serde_json::Value = serde_json::from_str(&response.to_agent_json()).unwrap();
+        assert_eq!(json["result"], "success");
+        assert_eq!(json["output"], "Deployed web");
+        assert_eq!(
+            json["warnings"],
+            serde_json::json!([
+                "Config file is world-readable",
+                "No --region given, using us-east-1"
+            ])
+        );
+
+        let quiet: serde_json::Value =
+            serde_json::from_str(&Response::text("ok").to_agent_json()).unwrap();
+        assert!(quiet.get("warnings").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_flag_stops_at_double_dash() {
+        async fn echo(
+            _state: State<()>,
+            DryRun(dry_run): DryRun,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("{} {}", dry_run, args.0.join(" ")))
+        }
+
+        let router = Router::new()
+            .route("cmd", echo)
+            .with_dry_run_flag()
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "cmd".to_string(),
+                "--".to_string(),
+                "--dry-run".to_string(),
+            ])
+            .await;
+        assert_eq!(response.changed, None);
+        assert_eq!(text_output(response), "false -- --dry-run");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_flag_is_opt_in() {
+        async fn echo(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(args.0.join(" "))
+        }
+
+        let router = Router::new().route("echo", echo).with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "echo".to_string(),
+                "--dry-run".to_string(),
+            ])
+            .await;
+        assert_eq!(text_output(response), "--dry-run");
+
+        let response = router
+            .execute_with(&["test".to_string(), "--help".to_string()])
+            .await;
+        assert!(!text_output(response).contains("--dry-run"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_mode_flag_stripped_from_args() {
+        #[derive(Debug)]
+        struct TestArgs {
+            message: String,
+        }
+
+        impl FromArgs for TestArgs {
+            fn from_args(args: &[String]) -> Result<Self, CliError> {
+                // --agent-mode should NOT appear in args
+                for arg in args {
+                    if arg == "--agent-mode" {
+                        return Err(CliError::user(
+                            "Unexpected --agent-mode flag in handler args",
+                        ));
+                    }
+                }
+
+                let message = args.first().cloned().unwrap_or_else(|| "empty".to_string());
+                Ok(TestArgs { message })
+            }
+        }
+
+        async fn echo(Args(args): Args<TestArgs>) -> CliResult<String> {
+            Ok(args.message)
+        }
+
+        let router = Router::new()
+            .route("echo", echo)
+            .with_agent_mode()
+            .with_state(());
+
+        // --agent-mode should be stripped before passing to handler
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "--agent-mode".to_string(),
+                "echo".to_string(),
+                "hello".to_string(),
+            ])
+            .await;
+
+        assert_eq!(response.exit_code, 0);
+        assert!(response.agent_mode);
+        // Should receive "hello", not "--agent-mode" or error
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "hello");
+        } else {
+            panic!("Expected text output");
+        }
+    }
+
+    // ========================================
+    // Alias Tests
+    // ========================================
+
+    fn aliased<H: Handler<T, ()>, T>(
+        handler: H,
+        aliases: Vec<&'static str>,
+    ) -> HandlerWithMeta<H, T, ()> {
+        HandlerWithMeta::new(
+            handler,
+            HandlerMetadata {
+                desc: Some("List items"),
+                tier: None,
+                tags: None,
+                examples: Vec::new(),
+                aliases,
+                env: Vec::new(),
+                timeout: None,
+            },
+        )
+    }
+
+    async fn list(_state: State<()>) -> CliResult<String> {
+        Ok("listed".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_route_with_aliases() {
+        let router = Router::new()
+            .route_with_aliases("list", aliased(list, vec!["ls", "l"]))
+            .nest(
+                "db",
+                Router::new().route_with_aliases("tables", aliased(list, vec!["t"])),
+            )
+            .with_state(());
+
+        for args in [
+            vec!["list"],
+            vec!["ls"],
+            vec!["l"],
+            vec!["db", "tables"],
+            vec!["db", "t"],
+        ] {
+            let mut full_args = vec!["test".to_string()];
+            full_args.extend(args.iter().map(|s| s.to_string()));
+            let response = router.execute_with(&full_args).await;
+            assert_eq!(response.exit_code, 0, "args: {:?}", args);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_with_aliases_nested_name() {
+        let router = Router::new()
+            .route_with_aliases("db:tables", aliased(list, vec!["t"]))
+            .with_state(());
+
+        let response = router
+            .execute_with(&["test".to_string(), "db".to_string(), "t".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate route: ls")]
+    fn test_route_with_aliases_duplicate_panics() {
+        let _: Router<()> = Router::new()
+            .route("ls", list)
+            .route_with_aliases("list", aliased(list, vec!["ls"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate route: ls")]
+    fn test_route_after_alias_duplicate_panics() {
+        let _: Router<()> = Router::new()
+            .route_with_aliases("list", aliased(list, vec!["ls"]))
+            .route("ls", list);
+    }
+
+    #[tokio::test]
+    async fn test_aliases_are_metadata_not_routes() {
+        let router = Router::new()
+            .route_with_aliases("list", aliased(list, vec!["ls", "version"]))
+            .with_metadata(RouterMetadata {
+                name: "myctl",
+                version: Some("1.0.0"),
+                about: None,
+            })
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("myctl")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        let schema: serde_json::Value =
+            serde_json::from_str(&text_output(run(&["--help", "--json"]).await)).unwrap();
+        let commands = schema["commands"].as_object().unwrap();
+        assert_eq!(commands.keys().collect::<Vec<_>>(), ["list"]);
+        assert_eq!(
+            commands["list"]["aliases"],
+            serde_json::json!(["ls", "version"])
+        );
+
+        let help = text_output(run(&["--help"]).await);
+        assert!(
+            help.contains("List items (aliases: ls, version)"),
+            "{}",
+            help
+        );
+        assert!(!help.contains("  ls "), "{}", help);
+
+        let report = router.doctor();
+        assert_eq!(
+            report
+                .findings
+                .iter()
+                .map(|f| (f.subject.as_str(), f.message.as_str()))
+                .collect::<Vec<_>>(),
+            [("version", "replaces the built-in 'version' command")]
+        );
+
+        #[cfg(feature = "mcp")]
+        {
+            let tools = router.mcp_tools();
+            assert!(!tools.iter().any(|t| t.name == "ls"));
+            let list = tools.iter().find(|t| t.name == "list").unwrap();
+            assert!(list.description.contains("Aliases: ls, version"));
+        }
+    }
+
+    // ========================================
+    // Prefix Matching Tests
+    // ========================================
+
+    fn prefix_router() -> Router<()> {
+        async fn build(_state: State<()>) -> CliResult<String> {
+            Ok("build".to_string())
+        }
+
+        async fn bench(_state: State<()>) -> CliResult<String> {
+            Ok("bench".to_string())
+        }
+
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("status".to_string())
+        }
+
+        async fn db_create(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("db:create {}", args.0.join(" ")))
+        }
+
+        async fn db_clean(_state: State<()>) -> CliResult<String> {
+            Ok("db:clean".to_string())
+        }
+
+        Router::new()
+            .route("build", build)
+            .route("bench", bench)
+            .route("st", status)
+            .route("status", status)
+            .route("db:create", db_create)
+            .route("db:clean", db_clean)
+            .route("db:drop", db_clean)
+            .with_prefix_matching()
+            .with_state(())
+    }
+
+    async fn run_prefix(router: &Router<()>, args: &[&str]) -> Response {
+        let mut full_args = vec!["test".to_string()];
+        full_args.extend(args.iter().map(|s| s.to_string()));
+        router.execute_with(&full_args).await
+    }
+
+    fn text_output(response: Response) -> String {
+        match response.output {
+            Output::Text(s) => s,
+            _ => panic!("Expected text output"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_matching_unique() {
+        let router = prefix_router();
+
+        let response = run_prefix(&router, &["bu"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text_output(response), "build");
+
+        // Each nested segment is resolved, remaining args are passed through
+        let response = run_prefix(&router, &["d", "cr", "mydb", "--size", "20GB"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text_output(response), "db:create mydb --size 20GB");
+
+        // Exact segment names win over longer commands sharing the prefix
+        let response = run_prefix(&router, &["st"]).await;
+        assert_eq!(text_output(response), "status");
+        let response = run_prefix(&router, &["sta"]).await;
+        assert_eq!(text_output(response), "status");
+    }
+
+    #[tokio::test]
+    async fn test_prefix_matching_ambiguous() {
+        let router = prefix_router();
+
+        let response = run_prefix(&router, &["b"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(
+            text_output(response),
+            "Error: Ambiguous command 'b': could be bench, build"
+        );
+
+        let response = run_prefix(&router, &["db", "c"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(
+            text_output(response),
+            "Error: Ambiguous command 'c': could be db clean, db create"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefix_matching_yields_to_exact_nested_routes() {
+        async fn db(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("db {}", args.0.join(" ")))
+        }
+        async fn db_create(_state: State<()>) -> CliResult<String> {
+            Ok("db:create".to_string())
+        }
+        async fn db_clean(_state: State<()>) -> CliResult<String> {
+            Ok("db:clean".to_string())
+        }
+
+        let router = Router::new()
+            .route("db", db)
+            .route("db:create", db_create)
+            .route("db:clean", db_clean)
+            .with_prefix_matching()
+            .with_state(());
+
+        // `db` matches exactly, so `c` is its argument rather than an
+        // ambiguous prefix of `clean`/`create`
+        let response = run_prefix(&router, &["db", "c"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text_output(response), "db c");
+        let response = run_prefix(&router, &["db", "create"]).await;
+        assert_eq!(text_output(response), "db:create");
+
+        // Without an exact match the nested prefix is still ambiguous
+        let response = run_prefix(&router, &["d", "c"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(
+            text_output(response),
+            "Error: Ambiguous command 'c': could be db clean, db create"
+        );
+        let response = run_prefix(&router, &["d", "cr"]).await;
+        assert_eq!(text_output(response), "db:create");
+    }
+
+    #[tokio::test]
+    async fn test_prefix_matching_no_match() {
+        let router = prefix_router();
+
+        let response = run_prefix(&router, &["xyz"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(text_output(response), "Error: Unknown command: xyz");
+    }
+
+    #[tokio::test]
+    async fn test_normalized_command_matching() {
+        async fn db_create(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("db:create {}", args.0.join(" ")))
+        }
+        async fn db(_state: State<()>) -> CliResult<String> {
+            Ok("db".to_string())
+        }
+
+        let routes = || Router::new().route("db", db).route("db:create", db_create);
+        let strict = routes().with_state(());
+        let normalized = routes().normalize_commands().with_state(());
+
+        for typed in [
+            &["DB_Create", "users"][..],
+            &["DB", "Create", "users"],
+            &["db", "create", "users"],
+        ] {
+            let response = run_prefix(&normalized, typed).await;
+            assert_eq!(text_output(response), "db:create users", "{:?}", typed);
+        }
+        let response = run_prefix(&normalized, &["Db"]).await;
+        assert_eq!(text_output(response), "db");
+
+        // Exact matching only without normalization
+        let response = run_prefix(&strict, &["DB_Create", "users"]).await;
+        assert_eq!(response.exit_code, 1);
+        let response = run_prefix(&strict, &["db", "Create", "users"]).await;
+        assert_eq!(text_output(response), "db");
+    }
+
+    #[tokio::test]
+    async fn test_normalized_matching_prefers_exact_at_any_depth() {
+        async fn db(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("db {}", args.0.join(" ")))
+        }
+        async fn db_create(_state: State<()>) -> CliResult<String> {
+            Ok("db:create".to_string())
+        }
+
+        let router = Router::new()
+            .route("db", db)
+            .route("db:create", db_create)
+            .normalize_commands()
+            .with_state(());
+
+        // `db` matches exactly, so `Create` is its argument, not `db:create`
+        let response = run_prefix(&router, &["db", "Create"]).await;
+        assert_eq!(text_output(response), "db Create");
+        let response = run_prefix(&router, &["DB", "Create"]).await;
+        assert_eq!(text_output(response), "db:create");
+    }
+
+    #[test]
+    #[should_panic(expected = "Routes 'DB:Create' and 'db:create' collide")]
+    fn test_normalize_commands_rejects_colliding_routes() {
+        async fn noop(_state: State<()>) -> CliResult<String> {
+            Ok(String::new())
+        }
+        let _ = Router::<()>::new()
+            .route("db:create", noop)
+            .route("DB:Create", noop)
+            .normalize_commands();
+    }
+
+    #[test]
+    #[should_panic(expected = "Routes 'db:create' and 'db:Create' collide")]
+    fn test_normalize_commands_rejects_colliding_route_added_later() {
+        async fn noop(_state: State<()>) -> CliResult<String> {
+            Ok(String::new())
+        }
+        let _ = Router::<()>::new()
+            .normalize_commands()
+            .route("db:create", noop)
+            .route("db:Create", noop);
+    }
+
+    #[tokio::test]
+    async fn test_custom_separator() {
+        async fn db_create(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("created {}", args.0.join(" ")))
+        }
+        async fn grant(_state: State<()>) -> CliResult<String> {
+            Ok("granted".to_string())
+        }
+
+        let users = Router::new().route("grant", grant);
+        let router = Router::new()
+            .with_separator('/')
+            .route("db/create", db_create)
+            .describe("db/create", "Create a database")
+            .nest("db/user", users)
+            .with_state(());
+
+        assert!(router.routes.contains_key("db:create"));
+        assert!(router.routes.contains_key("db:user:grant"));
+
+        // Input stays space-separated
+        let response = run_prefix(&router, &["db", "create", "users"]).await;
+        assert_eq!(text_output(response), "created users");
+        let response = run_prefix(&router, &["db", "user", "grant"]).await;
+        assert_eq!(text_output(response), "granted");
+
+        // Help shows the separator
+        let help = text_output(run_prefix(&router, &["--help"]).await);
+        assert!(help.contains("user/grant"), "{}", help);
+        assert!(!help.contains("user:grant"));
+        let markdown = text_output(run_prefix(&router, &["--help", "--md"]).await);
+        assert!(markdown.contains("`db/create`"));
+        assert!(!markdown.contains("db:create"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command_suggests_close_routes() {
+        let router = prefix_router();
+
+        let err = router
+            .try_execute_with(&["test".to_string(), "db".to_string(), "craete".to_string()])
+            .await
+            .err()
+            .expect("Expected unknown command error");
+        assert_eq!(err.kind(), "unknown_command");
+        match err {
+            CliError::User(UserError::UnknownCommand {
+                command,
+                suggestions,
+            }) => {
+                assert_eq!(command, "db craete");
+                assert_eq!(suggestions, vec!["db create"]);
             }
+            other => panic!("Expected UnknownCommand, got {:?}", other),
         }
 
-        (None, args.to_vec())
+        let response = run_prefix(&router, &["db", "craete"]).await;
+        assert_eq!(
+            text_output(response),
+            "Error: Unknown command: db craete\n\nDid you mean: db create?"
+        );
     }
-}
 
-// ============================================================================
-// Args Extractor (Axum-style)
-// ============================================================================
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_unknown_command_agent_json_is_structured() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("ok".to_string())
+        }
 
-/// Extractor for command-line arguments.
-///
-/// Similar to Axum's `Path` or `Query`, this allows handlers to receive
-/// parsed arguments.
-///
-/// # Example
-///
-/// ```ignore
-/// #[derive(Debug)]
-/// struct BuildArgs {
-///     release: bool,
-/// }
-///
-/// impl FromArgs for BuildArgs {
-///     fn from_args(args: &[String]) -> Result<Self, CliError> {
-///         Ok(BuildArgs {
-///             release: args.get(0).map(|s| s == "--release").unwrap_or(false),
-///         })
-///     }
-/// }
-///
-/// async fn build(State(app): State<AppState>, Args(args): Args<BuildArgs>) -> CliResult<String> {
-///     if args.release {
-///         Ok("Release build".to_string())
-///     } else {
-///         Ok("Debug build".to_string())
-///     }
-/// }
-/// ```
-#[derive(Debug, Clone)]
-pub struct Args<T>(pub T);
+        let router = Router::new()
+            .route("status", status)
+            .with_agent_mode()
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "stauts".to_string(),
+                "--agent-mode".to_string(),
+            ])
+            .await;
+        assert!(response.agent_mode);
+        assert_eq!(
+            response.error,
+            Some(ErrorInfo {
+                kind: "unknown_command",
+                did_you_mean: vec!["status".to_string()],
+            })
+        );
 
-/// Trait for parsing command-line arguments into a type.
-///
-/// This is similar to Axum's `FromRequest` trait and provides a lightweight
-/// way to parse per-command arguments.
-///
-/// # When to use `FromArgs`
-///
-/// Use `FromArgs` when:
-/// - ✅ You have simple per-command arguments (e.g., `--release`, `--output file.txt`)
-/// - ✅ You don't need global flags that apply to all commands
-/// - ✅ You want the framework to handle argument injection automatically
-///
-/// **Don't use `FromArgs` when:**
-/// - ❌ You need global flags (e.g., `--verbose`, `--config`) → use `FromGlobalArgs`
-/// - ❌ You need complex validation or conflicting flag logic → use `clap` directly
-/// - ❌ You're building a production CLI with many commands → see `examples/practical-cli`
-///
-/// # Example
-///
-/// ```rust
-/// use sen::{Args, FromArgs, CliError, CliResult};
-///
-/// #[derive(Debug)]
-/// struct BuildArgs {
-///     release: bool,
-///     output: Option<String>,
-/// }
-///
-/// impl FromArgs for BuildArgs {
-///     fn from_args(args: &[String]) -> Result<Self, CliError> {
-///         let mut release = false;
-///         let mut output = None;
-///
-///         let mut iter = args.iter();
-///         while let Some(arg) = iter.next() {
-///             match arg.as_str() {
-///                 "--release" => release = true,
-///                 "--output" => output = iter.next().map(|s| s.clone()),
-///                 _ => {}
-///             }
-///         }
-///
-///         Ok(BuildArgs { release, output })
-///     }
-/// }
-///
-/// // Use in handler
-/// async fn build(Args(args): Args<BuildArgs>) -> CliResult<String> {
-///     let mode = if args.release { "release" } else { "debug" };
-///     Ok(format!("Building in {} mode", mode))
-/// }
-/// ```
-///
-/// # Comparison with `FromGlobalArgs`
-///
-/// | Feature | `FromArgs` | `FromGlobalArgs` |
-/// |---------|-----------|------------------|
-/// | Scope | Per-command | All commands |
-/// | Injection | Via `Args<T>` extractor | Via `State<AppState>` |
-/// | Use case | Simple flags | Global configuration |
-/// | Example | `--release`, `--output` | `--verbose`, `--config` |
-///
-/// See README.md § "Argument Parsing: FromArgs vs Global Options" for detailed guide.
-pub trait FromArgs: Sized {
-    /// Parse arguments into Self, or return an error.
-    fn from_args(args: &[String]) -> Result<Self, CliError>;
+        let json: serde_json::Value = serde_json::from_str(&response.to_agent_json()).unwrap();
+        assert_eq!(json["result"], "error");
+        assert_eq!(json["error"]["kind"], "unknown_command");
+        assert_eq!(json["error"]["did_you_mean"], serde_json::json!(["status"]));
+    }
+
+    #[cfg(feature = "sensors")]
+    #[test]
+    fn test_agent_json_truncates_output_on_char_boundary() {
+        // "é" is 2 bytes at offset 3, so a 4-byte cut lands mid-character
+        let output = format!("abcé{}", "xyz".repeat(3000));
+        let total = output.len();
+        let response = Response::text(output);
+        let json_with_limit = |limit| -> serde_json::Value {
+            serde_json::from_str(&response.to_agent_json_with_limit(Some(limit))).unwrap()
+        };
+
+        let json = json_with_limit(4);
+        assert_eq!(json["output"], "abc");
+        assert_eq!(json["truncated"], true);
+        assert_eq!(json["total_bytes"], total);
+
+        let json = json_with_limit(1024);
+        assert_eq!(json["output"].as_str().unwrap().len(), 1024);
+        assert_eq!(json["total_bytes"], total);
+
+        // Output within the limit carries no truncation fields
+        let json = json_with_limit(total);
+        assert!(json.get("truncated").is_none());
+        assert!(json.get("total_bytes").is_none());
+    }
+
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_router_agent_output_limit() {
+        async fn logs(_state: State<()>) -> CliResult<String> {
+            Ok("line\n".repeat(10_000))
+        }
+
+        let args = [
+            "test".to_string(),
+            "logs".to_string(),
+            "--agent-mode".to_string(),
+        ];
+
+        let router = Router::new()
+            .route("logs", logs)
+            .with_agent_mode()
+            .agent_output_limit(100)
+            .with_state(());
+        let response = router.execute_with(&args).await;
+        let json: serde_json::Value =
+            serde_json::from_str(&router.to_agent_json(&response)).unwrap();
+        assert_eq!(json["output"].as_str().unwrap().len(), 100);
+        assert_eq!(json["truncated"], true);
+        assert_eq!(json["total_bytes"], 50_000);
+        // Only the agent JSON is truncated
+        assert_eq!(text_output(response).len(), 50_000);
+
+        // Unlimited by default
+        let router = Router::new()
+            .route("logs", logs)
+            .with_agent_mode()
+            .with_state(());
+        let response = router.execute_with(&args).await;
+        let json: serde_json::Value =
+            serde_json::from_str(&router.to_agent_json(&response)).unwrap();
+        assert_eq!(json["output"].as_str().unwrap().len(), 50_000);
+        assert!(json.get("truncated").is_none());
+    }
+
+    #[test]
+    fn test_strip_control_chars() {
+        assert_eq!(
+            strip_control_chars("\x1b[1;31merror\x1b[0m: bad\r\n\tok\x07"),
+            "error: bad\n\tok"
+        );
+        assert_eq!(
+            strip_control_chars("\x1b]8;;https://x.dev\x1b\\link\x1b]8;;\x07 \x1bcdone"),
+            "link done"
+        );
+        assert_eq!(strip_control_chars("plain ✓ text"), "plain ✓ text");
+    }
+
+    #[tokio::test]
+    async fn test_sanitize_agent_output_only_in_agent_mode() {
+        async fn lint(_state: State<()>) -> CliResult<String> {
+            Ok("\x1b[32mok\x1b[0m\tall\x00 clean\n".to_string())
+        }
+
+        let router = Router::new()
+            .route("lint", lint)
+            .with_agent_mode()
+            .sanitize_agent_output()
+            .with_state(());
+        let args = |agent: bool| {
+            let mut args = vec!["test".to_string(), "lint".to_string()];
+            if agent {
+                args.push("--agent-mode".to_string());
+            }
+            args
+        };
+
+        let response = router.execute_with(&args(true)).await;
+        assert_eq!(text_output(response), "ok\tall clean\n");
+
+        // Interactive output keeps its colors
+        let response = router.execute_with(&args(false)).await;
+        assert_eq!(text_output(response), "\x1b[32mok\x1b[0m\tall\x00 clean\n");
+
+        // Opt-in: agent output is untouched by default
+        let router = Router::new()
+            .route("lint", lint)
+            .with_agent_mode()
+            .with_state(());
+        let response = router.execute_with(&args(true)).await;
+        assert!(text_output(response).contains('\x1b'));
+    }
+
+    // ========================================
+    // Binary Output Tests
+    // ========================================
+
+    /// Bytes that are not valid UTF-8
+    const TARBALL: &[u8] = &[0x1f, 0x8b, 0x08, 0x00, 0xff, 0xfe, 0x00, 0x80];
+
+    async fn export(_state: State<()>) -> CliResult<Vec<u8>> {
+        Ok(TARBALL.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_binary_output_round_trip() {
+        let router = Router::new().route("export", export).with_state(());
+        let response = router
+            .execute_with(&["test".to_string(), "export".to_string()])
+            .await;
+
+        assert_eq!(response.exit_code, 0);
+        assert!(!response.output.is_empty());
+        assert_eq!(response.output.to_string(), "");
+
+        let mut stdout = Vec::new();
+        match &response.output {
+            Output::Binary(bytes) => write_raw(&mut stdout, bytes).unwrap(),
+            other => panic!("Expected binary output, got {:?}", other),
+        }
+        assert_eq!(stdout, TARBALL);
+    }
+
+    #[test]
+    fn test_binary_output_is_empty_only_without_bytes() {
+        assert!(Output::Binary(Vec::new()).is_empty());
+        assert!(!Output::Binary(vec![0]).is_empty());
+    }
+
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_agent_json_encodes_binary_output() {
+        use base64::Engine;
+        let decode = |json: &serde_json::Value| {
+            base64::engine::general_purpose::STANDARD
+                .decode(json["output"].as_str().unwrap())
+                .unwrap()
+        };
+
+        let router = Router::new()
+            .route("export", export)
+            .with_agent_mode()
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "export".to_string(),
+                "--agent-mode".to_string(),
+            ])
+            .await;
+
+        let json: serde_json::Value = serde_json::from_str(&response.to_agent_json()).unwrap();
+        assert_eq!(json["result"], "success");
+        assert_eq!(json["output_encoding"], "base64");
+        assert_eq!(decode(&json), TARBALL);
+        assert!(json.get("truncated").is_none());
+
+        // The limit applies to the raw bytes, so the output still decodes
+        let json: serde_json::Value =
+            serde_json::from_str(&response.to_agent_json_with_limit(Some(3))).unwrap();
+        assert_eq!(decode(&json), &TARBALL[..3]);
+        assert_eq!(json["truncated"], true);
+        assert_eq!(json["total_bytes"], TARBALL.len());
+
+        // Text output has no encoding field
+        let json: serde_json::Value =
+            serde_json::from_str(&Response::text("ok").to_agent_json()).unwrap();
+        assert!(json.get("output_encoding").is_none());
+    }
+
+    // ========================================
+    // Changed Marker Tests
+    // ========================================
+
+    fn tiered<H: Handler<T, ()>, T>(handler: H, tier: Tier) -> HandlerWithMeta<H, T, ()> {
+        HandlerWithMeta::new(
+            handler,
+            HandlerMetadata {
+                tier: Some(tier),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_changed_marker_round_trips() {
+        async fn sync(_state: State<()>) -> CliResult<Response> {
+            Ok(Response::text("Already up to date").changed(false))
+        }
+        async fn show(_state: State<()>) -> CliResult<String> {
+            Ok("config".to_string())
+        }
+        async fn apply(_state: State<()>) -> CliResult<String> {
+            Ok("applied".to_string())
+        }
+        async fn break_(_state: State<()>) -> CliResult<Response> {
+            Ok(Response::error(2, "half done").changed(true))
+        }
+
+        let router = Router::new()
+            .route("sync", tiered(sync, Tier::Standard))
+            .route("show", tiered(show, Tier::Safe))
+            .route("update", tiered(apply, Tier::Standard))
+            .route("apply", apply)
+            .route("break", tiered(break_, Tier::Critical))
+            .with_state(());
+        let run = |command: &str| {
+            let args = vec!["test".to_string(), command.to_string()];
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        // Set by the handler: wins over the tier default
+        let response = run("sync").await;
+        assert_eq!(response.changed, Some(false));
+        assert_eq!(text_output(response), "Already up to date");
+
+        // Unset: false for safe commands, true for mutating ones, left
+        // unset without a tier
+        assert_eq!(run("show").await.changed, Some(false));
+        assert_eq!(run("update").await.changed, Some(true));
+        assert_eq!(run("apply").await.changed, None);
+
+        // A failed command changed nothing, whatever its tier or handler says
+        assert_eq!(run("break").await.changed, Some(false));
+    }
+
+    #[test]
+    fn test_tier_of() {
+        async fn drop_db(_state: State<()>) -> CliResult<String> {
+            Ok("dropped".to_string())
+        }
+        async fn list(_state: State<()>) -> CliResult<String> {
+            Ok("users".to_string())
+        }
+
+        let router = Router::new()
+            .route("db:delete", tiered(drop_db, Tier::Critical))
+            .route("db:list", list)
+            .with_state(());
+
+        assert_eq!(router.tier_of("db delete"), Some(Tier::Critical));
+        assert_eq!(router.tier_of("db:delete"), Some(Tier::Critical));
+        assert_eq!(router.tier_of("db list"), None);
+        assert_eq!(router.tier_of("db missing"), None);
+    }
+
+    #[cfg(feature = "sensors")]
+    #[test]
+    fn test_agent_json_changed_field() {
+        let json = |response: Response| -> serde_json::Value {
+            serde_json::from_str(&response.to_agent_json()).unwrap()
+        };
+        let with_tier = |tier| ResponseMetadata {
+            tier,
+            tags: None,
+            sensors: None,
+        };
+
+        assert_eq!(json(Response::text("ok").changed(false))["changed"], false);
+        assert_eq!(json(Response::text("ok").changed(true))["changed"], true);
+
+        // Unset outside the router: true only for a mutating tier
+        assert_eq!(json(Response::text("ok"))["changed"], false);
+        let safe = Response::text("ok").with_metadata(with_tier(Some("safe")));
+        assert_eq!(json(safe)["changed"], false);
+        let critical = Response::text("ok").with_metadata(with_tier(Some("critical")));
+        assert_eq!(json(critical)["changed"], true);
+
+        // Errors never report a change
+        let failed = Response::error(1, "boom").with_metadata(with_tier(Some("critical")));
+        assert_eq!(json(failed)["changed"], false);
+        assert_eq!(
+            json(Response::error(1, "boom").changed(true))["changed"],
+            false
+        );
+        let unknown = error_response(CliError::user("unknown command"));
+        assert_eq!(json(unknown)["changed"], false);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("status", "status"), 0);
+        assert_eq!(edit_distance("stauts", "status"), 1);
+        assert_eq!(edit_distance("stat", "status"), 2);
+        assert_eq!(edit_distance("", "db"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_prefix_matching_is_opt_in() {
+        async fn build(_state: State<()>) -> CliResult<String> {
+            Ok("build".to_string())
+        }
+
+        let router = Router::new().route("build", build).with_state(());
+        let response = run_prefix(&router, &["bu"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert_eq!(text_output(response), "Error: Unknown command: bu");
+    }
+
+    // ========================================
+    // Stable JSON Output Tests
+    // ========================================
+
+    #[test]
+    fn test_sort_json_keys_recursive() {
+        let value = serde_json::json!({
+            "zeta": 1,
+            "alpha": [{"y": 1, "x": 2}],
+            "mid": {"b": 1, "a": 2},
+        });
+        let json = serde_json::to_string(&sort_json_keys(value)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"alpha":[{"x":2,"y":1}],"mid":{"a":2,"b":1},"zeta":1}"#
+        );
+    }
 
-    /// Get CLI schema information for this argument type (optional).
-    ///
-    /// Returns JSON representation of the command structure, including
-    /// arguments, options, and descriptions.
-    fn cli_schema() -> Option<serde_json::Value> {
-        None
+    #[cfg(feature = "sensors")]
+    #[test]
+    fn test_agent_json_is_byte_stable() {
+        fn render() -> String {
+            Response::text("ok")
+                .with_metadata(ResponseMetadata {
+                    tier: Some("safe"),
+                    tags: Some(vec!["db", "read"]),
+                    sensors: Some(crate::sensors::SensorData {
+                        timestamp: "2025-01-01T00:00:00Z".to_string(),
+                        cwd: std::path::PathBuf::from("/work"),
+                        os_arch: "linux-x86_64".to_string(),
+                        git: Some(crate::sensors::GitSensor {
+                            branch: "main".to_string(),
+                            status: "clean".to_string(),
+                            dirty: false,
+                        }),
+                        docker: None,
+                    }),
+                })
+                .to_agent_json()
+        }
+
+        let first = render();
+        assert_eq!(first, render());
+
+        let golden = r#"{
+  "changed": false,
+  "exit_code": 0,
+  "output": "ok",
+  "result": "success",
+  "sensors": {
+    "cwd": "/work",
+    "git": {
+      "branch": "main",
+      "dirty": false,
+      "status": "clean"
+    },
+    "os_arch": "linux-x86_64",
+    "timestamp": "2025-01-01T00:00:00Z"
+  },
+  "tags": [
+    "db",
+    "read"
+  ],
+  "tier": "safe"
+}"#;
+        assert_eq!(first, golden);
     }
-}
 
-/// Trait for parsing global options from command-line arguments.
-///
-/// Global options are flags that apply to **all commands** in your CLI,
-/// such as `--verbose`, `--config`, or `--output-format`.
-///
-/// # When to use `FromGlobalArgs`
-///
-/// Use `FromGlobalArgs` when:
-/// - ✅ You have flags that apply to **all** commands (e.g., `--verbose`, `--config`)
-/// - ✅ You want to avoid repeating the same flags in every handler
-/// - ✅ You're building a production CLI with multiple commands
-/// - ✅ You need integration with `clap` or other complex parsers
-///
-/// # Comparison with `FromArgs`
-///
-/// | Feature | `FromArgs` | `FromGlobalArgs` |
-/// |---------|-----------|------------------|
-/// | Scope | Per-command | All commands |
-/// | Injection | Via `Args<T>` extractor | Via `State<AppState>` |
-/// | Parsing time | During handler call | Before routing |
-/// | Use case | Command-specific flags | CLI-wide configuration |
-///
-/// # Example: Production CLI Pattern
-///
-/// ```ignore
-/// use sen::FromGlobalArgs;
-///
-/// #[derive(Clone)]
-/// struct GlobalOpts {
-///     verbose: bool,
-///     config: Option<String>,
-/// }
-///
-/// impl FromGlobalArgs for GlobalOpts {
-///     fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
-///         let mut verbose = false;
-///         let mut config = None;
-///         let mut remaining = Vec::new();
-///
-///         for arg in args {
-///             match arg.as_str() {
-///                 "--verbose" | "-v" => verbose = true,
-///                 s if s.starts_with("--config=") => {
-///                     config = Some(s.strip_prefix("--config=").unwrap().to_string());
-///                 }
-///                 _ => remaining.push(arg.clone()),
-///             }
-///         }
-///
-///         Ok((GlobalOpts { verbose, config }, remaining))
-///     }
-/// }
-///
-/// #[derive(Clone)]
-/// struct AppState {
-///     global: GlobalOpts,
-///     // ... other state fields
-/// }
-///
-/// #[tokio::main]
-/// async fn main() {
-///     let args: Vec<String> = std::env::args().skip(1).collect();
-///     let (global_opts, remaining) = GlobalOpts::from_global_args(&args).unwrap();
-///
-///     let state = AppState { global: global_opts };
-///     let router = Router::new()
-///         .route("build", handlers::build)
-///         .with_state(state);
-///
-///     // Execute with remaining args (global flags already parsed)
-///     let program_name = std::env::args().next().unwrap_or_default();
-///     let mut execute_args = vec![program_name];
-///     execute_args.extend(remaining);
-///     let response = router.execute_with(&execute_args).await;
-/// }
-/// ```
-///
-/// # Real-World Pattern
-///
-/// This pattern mirrors production CLIs like `kubectl`, `docker`, `aws`:
-///
-/// ```bash
-/// kubectl --context=prod get pods          # global: --context, command: get
-/// docker --debug run nginx                 # global: --debug, command: run
-/// myctl --verbose --config=prod db create  # global: --verbose --config, command: db create
-/// ```
-///
-/// See `examples/practical-cli` for a complete implementation with nested commands.
-///
-/// See README.md § "Argument Parsing: FromArgs vs Global Options" for detailed guide.
-pub trait FromGlobalArgs: Sized + Clone {
-    /// Parse global options from command-line arguments.
-    ///
-    /// This is called before routing, so it receives all arguments.
-    /// It should extract global flags and return the remaining non-global args.
-    ///
-    /// Returns `(parsed_options, remaining_args)` where `remaining_args` should be
-    /// passed to the router for command routing.
-    fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError>;
-}
+    #[tokio::test]
+    async fn test_schema_json_is_byte_stable() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("Status: OK".to_string())
+        }
 
-// ============================================================================
-// Clap Integration (when clap feature is enabled)
-// ============================================================================
+        let router = Router::new()
+            .route("status", status)
+            .route("db:create", status)
+            .with_state(());
 
-#[cfg(feature = "clap")]
-/// Blanket implementation: any type implementing `clap::Parser` can be used with `Args<T>`.
-///
-/// This allows seamless integration with clap's derive macros:
-///
-/// ```ignore
-/// use clap::Parser;
-/// use sen::{Args, CliResult, State};
-///
-/// #[derive(Parser)]
-/// struct BuildArgs {
-///     /// Database name
-///     name: String,
-///
-///     /// Build in release mode
-///     #[arg(long)]
-///     release: bool,
-///
-///     /// Target architecture (can also be set via env var)
-///     #[arg(long, env = "BUILD_TARGET")]
-///     target: Option<String>,
-/// }
-///
-/// async fn build(
-///     state: State<AppState>,
-///     Args(args): Args<BuildArgs>  // Clap automatically parses!
-/// ) -> CliResult<String> {
-///     if args.release {
-///         Ok("Building in release mode".to_string())
-///     } else {
-///         Ok("Building in debug mode".to_string())
-///     }
-/// }
-/// ```
-impl<T> FromArgs for T
-where
-    T: clap::Parser,
-{
-    fn from_args(args: &[String]) -> Result<Self, CliError> {
-        // Clap expects the command name as the first argument
-        // Since we're parsing subcommand args, we need to prepend a dummy command name
-        let args_with_cmd = std::iter::once("cmd".to_string())
-            .chain(args.iter().cloned())
-            .collect::<Vec<_>>();
-
-        T::try_parse_from(args_with_cmd).map_err(|e| {
-            // Clap's DisplayHelp and DisplayVersion are not errors - they're successful exits
-            // We want to preserve the formatted output, not treat it as an error
-            use clap::error::ErrorKind;
-            match e.kind() {
-                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => {
-                    // Extract the formatted help/version text and return as Help variant
-                    // This will result in exit code 0
-                    CliError::User(UserError::Help(e.to_string()))
-                }
-                _ => CliError::user(e.to_string()),
+        let args: Vec<String> = ["test", "--help", "--json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let render = |response: Response| match response.output {
+            Output::Text(s) => s,
+            _ => panic!("Expected text output"),
+        };
+
+        let first = render(router.execute_with(&args).await);
+        let second = render(router.execute_with(&args).await);
+        assert_eq!(first, second);
+
+        let commands = first.find("\"commands\"").unwrap();
+        let description = first.find("\"description\"").unwrap();
+        let name = first.find("\"name\"").unwrap();
+        let version = first.find("\"version\"").unwrap();
+        assert!(commands < description && description < name && name < version);
+    }
+
+    // ========================================
+    // Usage Example Tests
+    // ========================================
+
+    #[derive(Debug)]
+    struct HelpArgs;
+
+    impl FromArgs for HelpArgs {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            if args.iter().any(|a| a == "--help") {
+                return Err(CliError::User(UserError::Help(
+                    "Usage: db create <NAME>\n".to_string(),
+                )));
             }
-        })
+            Ok(HelpArgs)
+        }
     }
 
-    fn cli_schema() -> Option<serde_json::Value> {
-        let cmd = T::command();
-        Some(clap_command_to_json(&cmd))
+    fn router_with_examples() -> Router<()> {
+        async fn create(_state: State<()>, _args: Args<HelpArgs>) -> CliResult<String> {
+            Ok("created".to_string())
+        }
+
+        Router::new()
+            .route(
+                "db:create",
+                HandlerWithMeta::new(
+                    create,
+                    HandlerMetadata {
+                        desc: Some("Create a database"),
+                        examples: vec!["myctl db create mydb", "myctl db create mydb --size 20GB"],
+                        ..Default::default()
+                    },
+                ),
+            )
+            .with_state(())
     }
-}
 
-#[cfg(feature = "clap")]
-/// Convert a clap::Command to a JSON representation.
-fn clap_command_to_json(cmd: &clap::Command) -> serde_json::Value {
-    use serde_json::json;
+    #[tokio::test]
+    async fn test_command_help_renders_examples() {
+        let router = router_with_examples();
+        let args: Vec<String> = ["test", "db", "create", "--help"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
 
-    // Extract positional arguments
-    let mut positionals = Vec::new();
-    let mut options = Vec::new();
+        let response = router.execute_with(&args).await;
+        assert_eq!(response.exit_code, 0);
+        match response.output {
+            Output::Text(help) => assert_eq!(
+                help,
+                "Usage: db create <NAME>\n\nExamples:\n  myctl db create mydb\n  myctl db create mydb --size 20GB\n"
+            ),
+            _ => panic!("Expected help text"),
+        }
 
-    for arg in cmd.get_arguments() {
-        if arg.is_positional() {
-            positionals.push(json!({
-                "name": arg.get_id().as_str(),
-                "type": format!("{:?}", arg.get_value_parser().type_id()),
-                "required": arg.is_required_set(),
-                "description": arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
-            }));
-        } else {
-            let mut option = json!({
-                "name": format!("--{}", arg.get_id().as_str()),
-                "type": format!("{:?}", arg.get_value_parser().type_id()),
-                "required": arg.is_required_set(),
-                "description": arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
-            });
+        // Command help is a successful exit for try_execute_with as well
+        let response = router
+            .try_execute_with(&args)
+            .await
+            .expect("Help should be Ok");
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_schema_json_includes_examples() {
+        let router = router_with_examples();
+        let args: Vec<String> = ["test", "--help", "--json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let response = router.execute_with(&args).await;
+        let schema: serde_json::Value = match response.output {
+            Output::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected JSON text"),
+        };
+        assert_eq!(
+            schema["commands"]["db:create"]["examples"],
+            serde_json::json!(["myctl db create mydb", "myctl db create mydb --size 20GB"])
+        );
+    }
+
+    fn router_with_tags() -> Router<()> {
+        async fn ok(_state: State<()>) -> CliResult<String> {
+            Ok("ok".to_string())
+        }
+
+        fn tagged(desc: &'static str, tags: Vec<&'static str>) -> HandlerMetadata {
+            HandlerMetadata {
+                desc: Some(desc),
+                tags: Some(tags),
+                ..Default::default()
+            }
+        }
+
+        Router::new()
+            .route(
+                "db:create",
+                HandlerWithMeta::new(ok, tagged("Create a database", vec!["database", "write"])),
+            )
+            .route(
+                "db:list",
+                HandlerWithMeta::new(ok, tagged("List databases", vec!["database"])),
+            )
+            .route(
+                "server:start",
+                HandlerWithMeta::new(ok, tagged("Start the server", vec!["server"])),
+            )
+            .route("status", ok)
+            .with_state(())
+    }
+
+    #[tokio::test]
+    async fn test_help_filters_commands_by_tag() {
+        let router = router_with_tags();
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+
+        let help = text_output(run(&["--tag", "database", "--help"]).await);
+        assert!(help.contains("Create a database"));
+        assert!(help.contains("List databases"));
+        assert!(!help.contains("Start the server"));
+        assert!(!help.contains("status"));
+
+        let help = text_output(run(&["--help", "--filter-tag=server"]).await);
+        assert!(help.contains("Start the server"));
+        assert!(!help.contains("Create a database"));
+
+        let schema: serde_json::Value = serde_json::from_str(&text_output(
+            run(&["--help", "--json", "--tag", "database"]).await,
+        ))
+        .unwrap();
+        let commands: Vec<&String> = schema["commands"].as_object().unwrap().keys().collect();
+        assert_eq!(commands, vec!["db:create", "db:list"]);
+
+        let response = run(&["--help", "--tag"]).await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_help_and_schema_list_environment_variables() {
+        async fn edit(_state: State<()>, _args: Args<HelpArgs>) -> CliResult<String> {
+            Ok("edited".to_string())
+        }
+
+        let schema = serde_json::json!({
+            "arguments": [],
+            "options": [
+                { "name": "--token", "env": "MYCTL_TOKEN" },
+                { "name": "--editor", "env": "EDITOR" },
+                { "name": "--verbose" }
+            ]
+        });
+        let handler = HandlerWithMeta::new(
+            edit,
+            HandlerMetadata {
+                desc: Some("Edit the config"),
+                env: vec!["EDITOR", "VISUAL"],
+                ..Default::default()
+            },
+        );
+        let router = Router::new()
+            .route_with_schema("config:edit", handler, schema)
+            .with_state(());
+
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let response = router
+            .execute_with(&args(&["test", "config", "edit", "--help"]))
+            .await;
+        assert_eq!(response.exit_code, 0);
+        assert!(text_output(response).ends_with("\n\nEnvironment: EDITOR, MYCTL_TOKEN, VISUAL\n"));
+
+        let response = router
+            .execute_with(&args(&["test", "--help", "--json"]))
+            .await;
+        let schema: serde_json::Value = serde_json::from_str(&text_output(response)).unwrap();
+        assert_eq!(
+            schema["commands"]["config:edit"]["env"],
+            serde_json::json!(["EDITOR", "MYCTL_TOKEN", "VISUAL"])
+        );
+    }
 
-            // Add short flag if available
-            if let Some(short) = arg.get_short() {
-                option["short"] = json!(format!("-{}", short));
-            }
+    #[tokio::test]
+    async fn test_schema_json_omits_empty_env() {
+        let router = router_with_examples();
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "--help".to_string(),
+                "--json".to_string(),
+            ])
+            .await;
+        let schema: serde_json::Value = serde_json::from_str(&text_output(response)).unwrap();
+        assert!(schema["commands"]["db:create"].get("env").is_none());
+    }
 
-            // Add default value if available
-            let defaults = arg.get_default_values();
-            if !defaults.is_empty() {
-                option["default"] = json!(defaults[0].to_string_lossy().to_string());
-            }
+    /// Leading `--key value` pairs; stops at the first other token
+    struct Pairs(Vec<String>);
 
-            // Add env var if available
-            if let Some(env) = arg.get_env() {
-                option["env"] = json!(env.to_string_lossy().to_string());
-            }
+    impl FromArgs for Pairs {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            Ok(Self::from_args_prefix(args)?.0)
+        }
 
-            options.push(option);
+        fn from_args_prefix(args: &[String]) -> Result<(Self, usize), CliError> {
+            let consumed = args
+                .chunks(2)
+                .take_while(|pair| pair.len() == 2 && pair[0].starts_with("--"))
+                .count()
+                * 2;
+            Ok((Pairs(args[..consumed].to_vec()), consumed))
         }
     }
 
-    json!({
-        "arguments": positionals,
-        "options": options,
-    })
-}
+    fn exec_args(args: &[&str]) -> Vec<String> {
+        ["test", "exec"]
+            .iter()
+            .chain(args)
+            .map(|s| s.to_string())
+            .collect()
+    }
 
-#[cfg(feature = "clap")]
-/// Blanket implementation for global options using clap::Parser.
-///
-/// This allows using clap's derive macros for global flags:
-///
-/// ```ignore
-/// use clap::Parser;
-///
-/// #[derive(Parser, Clone)]
-/// struct GlobalOpts {
-///     /// Enable verbose logging
-///     #[arg(long, short, global = true)]
-///     verbose: bool,
-///
-///     /// Configuration file path
-///     #[arg(long, global = true)]
-///     config: Option<String>,
-/// }
-/// ```
-impl<T> FromGlobalArgs for T
-where
-    T: clap::Parser + Clone,
-{
-    fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
-        // Try to parse global options using clap
-        // We need to use clap's API to extract global flags and return remaining args
+    #[tokio::test]
+    async fn test_rest_captures_args_after_separator() {
+        async fn exec(
+            _state: State<()>,
+            Args(args): Args<RawArgs>,
+            Rest(rest): Rest,
+        ) -> CliResult<String> {
+            Ok(format!("{:?} {:?}", args.0, rest))
+        }
+        let router = Router::new().route("exec", exec).with_state(());
 
-        // For now, parse all args and let clap handle it
-        // In a real implementation, we'd need to separate global from command-specific args
-        let args_with_cmd = std::iter::once("cmd".to_string())
-            .chain(args.iter().cloned())
-            .collect::<Vec<_>>();
+        let response = router
+            .execute_with(&exec_args(&["--env", "prod", "--", "ls", "-la", "--"]))
+            .await;
+        assert_eq!(
+            text_output(response),
+            r#"["--env", "prod"] ["ls", "-la", "--"]"#
+        );
 
-        match T::try_parse_from(&args_with_cmd) {
-            Ok(global) => {
-                // For simplicity, return empty remaining args
-                // In practice, clap would need to be configured to allow unknown args
-                Ok((global, vec![]))
-            }
-            Err(e) => Err(CliError::user(e.to_string())),
-        }
+        let response = router.execute_with(&exec_args(&["--env", "prod"])).await;
+        assert_eq!(text_output(response), r#"["--env", "prod"] []"#);
     }
-}
 
-// ============================================================================
-// Manual FromArgs implementations (when clap feature is NOT enabled)
-// ============================================================================
+    #[tokio::test]
+    async fn test_rest_uses_consumed_count_of_parser() {
+        async fn exec(
+            _state: State<()>,
+            Args(pairs): Args<Pairs>,
+            Rest(rest): Rest,
+        ) -> CliResult<String> {
+            Ok(format!("{:?} {:?}", pairs.0, rest))
+        }
+        let router = Router::new().route("exec", exec).with_state(());
 
-#[cfg(not(feature = "clap"))]
-// Implement FromArgs for () (no args needed)
-impl FromArgs for () {
-    fn from_args(_args: &[String]) -> Result<Self, CliError> {
-        Ok(())
+        let response = router
+            .execute_with(&exec_args(&["--env", "prod", "ls", "--color", "auto"]))
+            .await;
+        assert_eq!(
+            text_output(response),
+            r#"["--env", "prod"] ["ls", "--color", "auto"]"#
+        );
     }
-}
 
-#[cfg(not(feature = "clap"))]
-// Implement FromArgs for Vec<String> (raw args)
-impl FromArgs for Vec<String> {
-    fn from_args(args: &[String]) -> Result<Self, CliError> {
-        Ok(args.to_vec())
-    }
-}
+    #[tokio::test]
+    async fn test_complete_uses_route_completer() {
+        async fn delete(_state: State<()>, Args(args): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("Deleted {}", args.0.join(" ")))
+        }
 
-// ============================================================================
-// Handler Implementations for Common Function Signatures
-// ============================================================================
+        let router = Router::new()
+            .route("db:delete", delete)
+            .route("db:create", delete)
+            .route("status", delete)
+            .completer("db:delete", |command: String, current: String| async move {
+                assert_eq!(command, "db delete");
+                ["users", "orders", "uploads"]
+                    .into_iter()
+                    .filter(|name| name.starts_with(&current))
+                    .map(String::from)
+                    .collect()
+            })
+            .with_state(());
+        let complete = |words: &[&str]| {
+            let args: Vec<String> = ["test", "__complete"]
+                .iter()
+                .chain(words)
+                .map(|s| s.to_string())
+                .collect();
+            let router = &router;
+            async move { text_output(router.execute_with(&args).await) }
+        };
 
-// Handler for: async fn(State<S>) -> impl IntoResponse
-impl<F, Fut, S, Res> Handler<(State<S>,), S> for F
-where
-    F: Fn(State<S>) -> Fut + Clone + Send + Sync + 'static,
-    Fut: Future<Output = Res> + Send + 'static,
-    Res: IntoResponse + 'static,
-    S: Send + Sync + Clone + 'static,
-{
-    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+        assert_eq!(complete(&["db", "delete", "u"]).await, "users\nuploads");
+        assert_eq!(complete(&["db", "delete", "--force", "o"]).await, "orders");
+        // Without a completer, subcommand names are completed
+        assert_eq!(complete(&["db", ""]).await, "create\ndelete");
+        assert_eq!(complete(&["s"]).await, "status");
+        assert_eq!(complete(&["db", "create", "x"]).await, "");
 
-    fn call(self, state: State<S>, _args: Vec<String>) -> Self::Future {
-        Box::pin(async move {
-            let result = self(state).await;
-            result.into_response()
-        })
+        // The hidden command stays out of help
+        let response = router
+            .execute_with(&["test".to_string(), "--help".to_string()])
+            .await;
+        assert!(!text_output(response).contains("__complete"));
     }
-}
 
-// Handler for: async fn(State<S>, Args<T>) -> impl IntoResponse
-impl<F, Fut, S, T, Res> Handler<(State<S>, Args<T>), S> for F
-where
-    F: Fn(State<S>, Args<T>) -> Fut + Clone + Send + Sync + 'static,
-    Fut: Future<Output = Res> + Send + 'static,
-    Res: IntoResponse + 'static,
-    T: FromArgs + Send + 'static,
-    S: Send + Sync + Clone + 'static,
-{
-    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+    #[tokio::test]
+    async fn test_catalog_hash_tracks_commands_not_registration_order() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("ok".to_string())
+        }
 
-    fn call(self, state: State<S>, args: Vec<String>) -> Self::Future {
-        Box::pin(async move {
-            // Parse args
-            let parsed_args = match T::from_args(&args) {
-                Ok(args) => args,
-                Err(e) => {
-                    let result: CliResult<()> = Err(e);
-                    return result.into_response();
-                }
-            };
+        async fn deploy(_state: State<()>, _args: Args<HelpArgs>) -> CliResult<String> {
+            Ok("deployed".to_string())
+        }
 
-            let result = self(state, Args(parsed_args)).await;
-            result.into_response()
-        })
+        let forward = Router::new()
+            .route("status", status)
+            .route("deploy", deploy)
+            .with_state(());
+        let reversed = Router::new()
+            .route("deploy", deploy)
+            .route("status", status)
+            .with_state(());
+        let extended = Router::new()
+            .route("status", status)
+            .route("deploy", deploy)
+            .route("db:create", deploy)
+            .with_state(());
+
+        let hash = forward.catalog_hash();
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, reversed.catalog_hash());
+        assert_ne!(hash, extended.catalog_hash());
+
+        let response = forward
+            .execute_with(&[
+                "test".to_string(),
+                "--help".to_string(),
+                "--json".to_string(),
+            ])
+            .await;
+        let schema: serde_json::Value = serde_json::from_str(&text_output(response)).unwrap();
+        assert_eq!(schema["catalog_hash"], hash);
     }
 
-    fn args_schema(&self) -> Option<serde_json::Value> {
-        T::cli_schema()
+    #[tokio::test]
+    async fn test_command_help_renders_arg_constraints() {
+        async fn export(_state: State<()>, _args: Args<HelpArgs>) -> CliResult<String> {
+            Ok("exported".to_string())
+        }
+
+        let schema = serde_json::json!({
+            "arguments": [],
+            "options": [
+                { "name": "--json", "conflicts_with": ["--yaml"] },
+                { "name": "--yaml", "conflicts_with": ["--json"] },
+                { "name": "--output", "requires": ["--format"] },
+                { "name": "--format" }
+            ]
+        });
+        let router = Router::new()
+            .route_with_schema("export", export, schema)
+            .with_state(());
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "export".to_string(),
+                "--help".to_string(),
+            ])
+            .await;
+
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(
+            text_output(response),
+            "Usage: db create <NAME>\n\nConstraints:\n  --json cannot be used with --yaml\n  --output requires --format\n"
+        );
     }
-}
 
-// Handler for: async fn(Args<T>) -> impl IntoResponse (no state)
-impl<F, Fut, T, Res> Handler<(Args<T>,), ()> for F
-where
-    F: Fn(Args<T>) -> Fut + Clone + Send + Sync + 'static,
-    Fut: Future<Output = Res> + Send + 'static,
-    Res: IntoResponse + 'static,
-    T: FromArgs + Send + 'static,
-{
-    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_schema_includes_conflicts_with() {
+        use clap::{ArgGroup, CommandFactory, Parser};
+
+        #[derive(Parser)]
+        #[command(group(ArgGroup::new("level").args(["debug", "trace"])))]
+        struct ExportArgs {
+            /// Output JSON
+            #[arg(long, conflicts_with = "yaml")]
+            json: bool,
+            /// Output YAML
+            #[arg(long)]
+            yaml: bool,
+            #[arg(long)]
+            debug: bool,
+            #[arg(long)]
+            trace: bool,
+        }
 
-    fn call(self, _state: State<()>, args: Vec<String>) -> Self::Future {
-        Box::pin(async move {
-            // Parse args
-            let parsed_args = match T::from_args(&args) {
-                Ok(args) => args,
-                Err(e) => {
-                    let result: CliResult<()> = Err(e);
-                    return result.into_response();
-                }
-            };
+        let schema = clap_command_to_json(&ExportArgs::command());
+        let option = |name: &str| {
+            schema["options"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|o| o["name"] == name)
+                .unwrap()
+                .clone()
+        };
 
-            let result = self(Args(parsed_args)).await;
-            result.into_response()
-        })
+        assert_eq!(
+            option("--json")["conflicts_with"],
+            serde_json::json!(["--yaml"])
+        );
+        assert_eq!(
+            option("--debug")["conflicts_with"],
+            serde_json::json!(["--trace"])
+        );
+        assert_eq!(
+            option("--trace")["conflicts_with"],
+            serde_json::json!(["--debug"])
+        );
+        // Only the declaring side lists an explicit conflict
+        assert!(option("--yaml").get("conflicts_with").is_none());
     }
 
-    fn args_schema(&self) -> Option<serde_json::Value> {
-        T::cli_schema()
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_schema_marks_variadic_positionals() {
+        use clap::{CommandFactory, Parser};
+
+        #[derive(Parser)]
+        struct RunArgs {
+            /// Script to run
+            script: String,
+            /// Arguments passed to the script
+            args: Vec<String>,
+        }
+
+        #[derive(Parser)]
+        struct PairArgs {
+            #[arg(num_args = 1..=2)]
+            pair: String,
+        }
+
+        let schema = clap_command_to_json(&RunArgs::command());
+        let arguments = schema["arguments"].as_array().unwrap();
+        assert_eq!(arguments[0]["name"], "script");
+        assert!(arguments[0].get("variadic").is_none());
+        assert_eq!(arguments[1]["name"], "args");
+        assert_eq!(arguments[1]["variadic"], true);
+        assert!(arguments[1]["max_values"].is_null());
+
+        let schema = clap_command_to_json(&PairArgs::command());
+        assert_eq!(schema["arguments"][0]["variadic"], true);
+        assert_eq!(schema["arguments"][0]["max_values"], 2);
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    #[cfg(feature = "clap")]
+    mod infer_long_args {
+        use super::*;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        #[derive(clap::Parser)]
+        struct BuildArgs {
+            #[arg(long)]
+            release: bool,
+            #[arg(long)]
+            remote: Option<String>,
+        }
 
-    // ========================================
-    // Tier Tests
-    // ========================================
+        async fn build(_state: State<()>, Args(args): Args<BuildArgs>) -> CliResult<String> {
+            Ok(format!("release={} remote={:?}", args.release, args.remote))
+        }
 
-    #[test]
-    fn test_tier_parse() {
-        assert_eq!(Tier::parse("safe"), Some(Tier::Safe));
-        assert_eq!(Tier::parse("SAFE"), Some(Tier::Safe));
-        assert_eq!(Tier::parse("standard"), Some(Tier::Standard));
-        assert_eq!(Tier::parse("critical"), Some(Tier::Critical));
-        assert_eq!(Tier::parse("invalid"), None);
-    }
+        fn run_args(flag: &str) -> Vec<String> {
+            vec!["test".to_string(), "build".to_string(), flag.to_string()]
+        }
 
-    #[test]
-    fn test_tier_as_str() {
-        assert_eq!(Tier::Safe.as_str(), "safe");
-        assert_eq!(Tier::Standard.as_str(), "standard");
-        assert_eq!(Tier::Critical.as_str(), "critical");
-    }
+        #[tokio::test]
+        async fn test_long_flag_abbreviation_is_opt_in() {
+            let router = Router::new().route("build", build).with_state(());
+            let response = router.execute_with(&run_args("--rel")).await;
+            assert_eq!(response.exit_code, 1);
+
+            let router = Router::new()
+                .route("build", build)
+                .infer_long_args(true)
+                .with_state(());
+            let response = router.execute_with(&run_args("--rel")).await;
+            assert_eq!(response.exit_code, 0);
+            assert_eq!(text_output(response), "release=true remote=None");
+        }
 
-    #[test]
-    fn test_tier_requires_approval() {
-        assert!(!Tier::Safe.requires_approval());
-        assert!(!Tier::Standard.requires_approval());
-        assert!(Tier::Critical.requires_approval());
-    }
+        #[tokio::test]
+        async fn test_ambiguous_long_flag_abbreviation_fails() {
+            let router = Router::new()
+                .route("build", build)
+                .infer_long_args(true)
+                .with_state(());
+            let response = router.execute_with(&run_args("--re")).await;
+            assert_eq!(response.exit_code, 1);
+            assert!(text_output(response).contains("--re"));
+        }
 
-    #[test]
-    fn test_tier_display() {
-        assert_eq!(format!("{}", Tier::Safe), "safe");
-        assert_eq!(format!("{}", Tier::Standard), "standard");
-        assert_eq!(format!("{}", Tier::Critical), "critical");
+        #[derive(clap::Parser, Clone)]
+        struct DbOpts {
+            #[arg(long)]
+            namespace: String,
+        }
+
+        async fn create(
+            _state: State<()>,
+            Scoped(db): Scoped<DbOpts>,
+            _args: Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(db.namespace)
+        }
+
+        #[tokio::test]
+        async fn test_long_flag_abbreviation_applies_to_scoped_args() {
+            let router = Router::new()
+                .nest_with_args::<DbOpts>("db", Router::new().route("create", create))
+                .infer_long_args(true)
+                .with_state(());
+            let args: Vec<String> = ["test", "db", "create", "--name", "prod"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            let response = router.execute_with(&args).await;
+            assert_eq!(response.exit_code, 0);
+            assert_eq!(text_output(response), "prod");
+        }
     }
 
-    // ========================================
-    // State Tests
-    // ========================================
+    #[cfg(feature = "clap")]
+    mod command_name_in_errors {
+        use super::*;
 
-    #[tokio::test]
-    async fn test_state_creation_and_access() {
-        struct TestState {
-            value: i32,
+        #[derive(clap::Parser)]
+        struct CreateArgs {
+            name: String,
+        }
+
+        async fn create(_state: State<()>, Args(args): Args<CreateArgs>) -> CliResult<String> {
+            Ok(args.name)
+        }
+
+        #[tokio::test]
+        async fn test_clap_error_shows_real_command_name() {
+            let router = Router::new()
+                .route("db:create", create)
+                .with_metadata(RouterMetadata {
+                    name: "myctl",
+                    version: None,
+                    about: None,
+                })
+                .with_state(());
+            let args: Vec<String> = ["test", "db", "create"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            let response = router.execute_with(&args).await;
+            assert_eq!(response.exit_code, 1);
+            let text = text_output(response);
+            assert!(text.contains("Usage: myctl db create <NAME>"), "{}", text);
+            assert!(!text.contains("cmd"));
         }
 
-        let state = State::new(TestState { value: 42 });
-        assert_eq!(state.read().await.value, 42);
+        #[test]
+        fn test_from_args_without_router_uses_dummy_name() {
+            let err = match CreateArgs::from_args(&[]) {
+                Err(CliError::User(err)) => err.to_string(),
+                _ => panic!("Expected a user error"),
+            };
+            assert!(err.contains("Usage: cmd <NAME>"));
 
-        let cloned = state.clone();
-        assert_eq!(cloned.read().await.value, 42);
+            let err = match CreateArgs::from_args_named("tool make", &[]) {
+                Err(CliError::User(err)) => err.to_string(),
+                _ => panic!("Expected a user error"),
+            };
+            assert!(err.contains("Usage: tool make <NAME>"));
+        }
     }
 
-    #[tokio::test]
-    async fn test_state_write() {
-        struct TestState {
-            value: i32,
+    #[cfg(feature = "clap")]
+    mod clap_subcommand {
+        use super::*;
+
+        #[derive(clap::Subcommand)]
+        enum DbCommand {
+            /// Create a database
+            Create { name: String },
+            /// Drop a database
+            Drop {
+                name: String,
+                #[arg(long)]
+                force: bool,
+            },
         }
 
-        let state = State::new(TestState { value: 42 });
+        async fn dispatch(_state: State<()>, command: DbCommand) -> CliResult<String> {
+            Ok(match command {
+                DbCommand::Create { name } => format!("created {}", name),
+                DbCommand::Drop { name, force } => format!("dropped {} force={}", name, force),
+            })
+        }
 
-        // Mutate through write lock
-        {
-            let mut app = state.write().await;
-            app.value = 100;
+        fn router() -> Router<()> {
+            Router::from_clap_subcommand::<DbCommand, _, _, _>(dispatch).with_state(())
         }
 
-        // Verify mutation
-        assert_eq!(state.read().await.value, 100);
-    }
+        fn run_args(args: &[&str]) -> Vec<String> {
+            std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect()
+        }
 
-    #[test]
-    fn test_user_error_exit_code() {
-        let err = CliError::user("test error");
-        assert_eq!(err.exit_code(), 1);
-    }
+        #[tokio::test]
+        async fn test_each_variant_is_a_route() {
+            let router = router();
 
-    #[test]
-    fn test_system_error_exit_code() {
-        let err = CliError::system("test error");
-        assert_eq!(err.exit_code(), 101);
-    }
+            let response = router.execute_with(&run_args(&["create", "users"])).await;
+            assert_eq!(text_output(response), "created users");
 
-    #[test]
-    fn test_string_into_response() {
-        let response = "hello".to_string().into_response();
-        assert_eq!(response.exit_code, 0);
-        assert!(matches!(response.output, Output::Text(_)));
-    }
+            let response = router
+                .execute_with(&run_args(&["drop", "users", "--force"]))
+                .await;
+            assert_eq!(text_output(response), "dropped users force=true");
 
-    #[test]
-    fn test_unit_into_response() {
-        let response = ().into_response();
-        assert_eq!(response.exit_code, 0);
-        assert!(matches!(response.output, Output::Silent));
-    }
+            let response = router.execute_with(&run_args(&["drop"])).await;
+            assert_eq!(response.exit_code, 1);
+        }
 
-    #[test]
-    fn test_result_ok_into_response() {
-        let result: CliResult<String> = Ok("success".to_string());
-        let response = result.into_response();
-        assert_eq!(response.exit_code, 0);
-    }
+        #[test]
+        fn test_routes_carry_about_and_arg_schema() {
+            let router = router();
+            let mut keys: Vec<_> = router.routes.keys().cloned().collect();
+            keys.sort();
+            assert_eq!(keys, ["create", "drop"]);
+
+            let meta = &router.route_metadata["drop"];
+            assert_eq!(meta.get_description(), Some("Drop a database"));
+            let schema = meta.get_args_schema().unwrap();
+            assert_eq!(schema["arguments"][0]["name"], "name");
+            assert_eq!(schema["options"][0]["name"], "--force");
+        }
 
-    #[test]
-    fn test_result_err_into_response() {
-        let result: CliResult<String> = Err(CliError::user("failure"));
-        let response = result.into_response();
-        assert_eq!(response.exit_code, 1);
+        #[derive(clap::Subcommand)]
+        enum AppCommand {
+            #[command(subcommand)]
+            Db(DbCommand),
+        }
+
+        #[tokio::test]
+        async fn test_nested_subcommands_become_nested_routes() {
+            let router = Router::from_clap_subcommand::<AppCommand, _, _, _>(
+                |state: State<()>, AppCommand::Db(command)| dispatch(state, command),
+            )
+            .with_state(());
+            assert!(router.routes.contains_key("db:create"));
+
+            let response = router
+                .execute_with(&run_args(&["db", "create", "users"]))
+                .await;
+            assert_eq!(text_output(response), "created users");
+        }
     }
 
     // ========================================
-    // Router Tests
+    // Response File (@file) Tests
     // ========================================
 
-    #[tokio::test]
-    async fn test_router_basic() {
-        #[derive(Clone)]
-        struct AppState {
-            value: i32,
-        }
+    fn write_args_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("sen-args-{}-{}.txt", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
 
-        async fn get_value(state: State<AppState>) -> CliResult<String> {
-            let app = state.read().await;
-            Ok(format!("Value: {}", app.value))
-        }
+    #[derive(Debug)]
+    struct RawArgs(Vec<String>);
 
-        let state = AppState { value: 42 };
-        let router = Router::new().route("status", get_value).with_state(state);
+    impl FromArgs for RawArgs {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            Ok(RawArgs(args.to_vec()))
+        }
+    }
 
-        let response = router
-            .execute_with(&["test".to_string(), "status".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        assert!(matches!(response.output, Output::Text(_)));
+    async fn join_args(Args(args): Args<RawArgs>) -> CliResult<String> {
+        Ok(args.0.join(" "))
     }
 
     #[tokio::test]
-    async fn test_router_unknown_command() {
-        let router: Router<()> = Router::new().with_state(());
+    async fn test_response_file_expansion() {
+        let path = write_args_file("expand", "--env\nprod\n\n  --force  \n");
+        let router = Router::new().route("deploy", join_args).with_state(());
+
         let response = router
-            .execute_with(&["test".to_string(), "unknown".to_string()])
+            .execute_argv(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                format!("@{}", path.display()),
+                "--verbose".to_string(),
+            ])
             .await;
-        assert_eq!(response.exit_code, 1);
-    }
+        std::fs::remove_file(&path).unwrap();
 
-    #[tokio::test]
-    async fn test_router_no_command() {
-        let router: Router<()> = Router::new().with_state(());
-        let response = router.execute_with(&["test".to_string()]).await;
-        // Empty args now show help with exit code 0 (changed from error)
         assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "--env prod --force --verbose");
+        } else {
+            panic!("Expected text output");
+        }
     }
 
     #[tokio::test]
-    async fn test_router_multiple_routes() {
-        #[derive(Clone)]
-        struct AppState {
-            #[allow(dead_code)]
-            count: i32,
-        }
+    async fn test_response_file_can_contain_command() {
+        let path = write_args_file("command", "deploy\nstaging\n");
+        let router = Router::new().route("deploy", join_args).with_state(());
 
-        async fn status(_state: State<AppState>) -> CliResult<String> {
-            Ok("OK".to_string())
-        }
+        let response = router
+            .execute_argv(&["test".to_string(), format!("@{}", path.display())])
+            .await;
+        std::fs::remove_file(&path).unwrap();
 
-        async fn version(_state: State<AppState>) -> CliResult<String> {
-            Ok("v1.0.0".to_string())
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "staging");
+        } else {
+            panic!("Expected text output");
         }
+    }
 
-        let state = AppState { count: 0 };
-        let router = Router::new()
-            .route("status", status)
-            .route("version", version)
-            .with_state(state);
+    #[tokio::test]
+    async fn test_response_file_escape() {
+        let router = Router::new().route("mention", join_args).with_state(());
 
-        let response1 = router
-            .execute_with(&["test".to_string(), "status".to_string()])
+        let response = router
+            .execute_argv(&[
+                "test".to_string(),
+                "mention".to_string(),
+                "@@alice".to_string(),
+                "@".to_string(),
+            ])
             .await;
-        assert_eq!(response1.exit_code, 0);
 
-        let response2 = router
-            .execute_with(&["test".to_string(), "version".to_string()])
-            .await;
-        assert_eq!(response2.exit_code, 0);
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "@alice @");
+        } else {
+            panic!("Expected text output");
+        }
     }
 
-    // ========================================
-    // Args Extractor Tests
-    // ========================================
-
     #[tokio::test]
-    async fn test_router_with_args() {
-        #[derive(Clone)]
-        struct AppState {
-            #[allow(dead_code)]
-            base_cmd: String,
-        }
-
-        #[derive(Debug)]
-        struct BuildArgs {
-            release: bool,
-        }
+    async fn test_response_file_missing() {
+        let router = Router::new().route("deploy", join_args).with_state(());
 
-        impl FromArgs for BuildArgs {
-            fn from_args(args: &[String]) -> Result<Self, CliError> {
-                Ok(BuildArgs {
-                    release: args.first().map(|s| s == "--release").unwrap_or(false),
-                })
-            }
-        }
+        let response = router
+            .execute_argv(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                "@/nonexistent/sen-args-missing.txt".to_string(),
+            ])
+            .await;
 
-        async fn build(_state: State<AppState>, Args(args): Args<BuildArgs>) -> CliResult<String> {
-            if args.release {
-                Ok("release".to_string())
-            } else {
-                Ok("debug".to_string())
-            }
+        assert_eq!(response.exit_code, 1);
+        if let Output::Text(output) = response.output {
+            assert!(output.contains("Failed to read argument file"));
+            assert!(output.contains("/nonexistent/sen-args-missing.txt"));
+        } else {
+            panic!("Expected text output");
         }
 
-        let state = AppState {
-            base_cmd: "cargo build".to_string(),
-        };
-        let router = Router::new().route("build", build).with_state(state);
-
-        // Test with --release flag
-        let response1 = router
+        // Programmatic arguments are passed through literally
+        let response = router
             .execute_with(&[
                 "test".to_string(),
-                "build".to_string(),
-                "--release".to_string(),
+                "deploy".to_string(),
+                "@/nonexistent/sen-args-missing.txt".to_string(),
             ])
             .await;
-        assert_eq!(response1.exit_code, 0);
-        if let Output::Text(output) = response1.output {
-            assert_eq!(output, "release");
-        }
-
-        // Test without flag
-        let response2 = router
-            .execute_with(&["test".to_string(), "build".to_string()])
-            .await;
-        assert_eq!(response2.exit_code, 0);
-        if let Output::Text(output) = response2.output {
-            assert_eq!(output, "debug");
-        }
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(
+            response.output.to_string(),
+            "@/nonexistent/sen-args-missing.txt"
+        );
     }
 
-    #[tokio::test]
-    async fn test_router_args_no_state() {
-        #[derive(Debug)]
-        struct EchoArgs {
-            message: String,
-        }
+    // ========================================
+    // Progress Extractor Tests
+    // ========================================
 
-        impl FromArgs for EchoArgs {
-            fn from_args(args: &[String]) -> Result<Self, CliError> {
-                let message = args.first().cloned().unwrap_or_else(|| "".to_string());
-                Ok(EchoArgs { message })
-            }
-        }
+    #[tokio::test]
+    async fn test_progress_events_collected_from_handler() {
+        #[derive(Clone)]
+        struct AppState;
 
-        async fn echo(Args(args): Args<EchoArgs>) -> CliResult<String> {
-            Ok(args.message)
+        async fn deploy(
+            _state: State<AppState>,
+            progress: Progress,
+            Args(args): Args<RawArgs>,
+        ) -> CliResult<String> {
+            progress.set(0.0, "starting");
+            progress.set(0.5, "halfway");
+            progress.set(1.0, "done");
+            Ok(format!("deployed {}", args.0.join(" ")))
         }
 
-        let router = Router::new().route("echo", echo).with_state(());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let router = Router::new()
+            .route("deploy", deploy)
+            .with_progress_sink(tx)
+            .with_state(AppState);
 
         let response = router
-            .execute_with(&["test".to_string(), "echo".to_string(), "Hello!".to_string()])
+            .execute_with(&["test".to_string(), "deploy".to_string(), "prod".to_string()])
             .await;
         assert_eq!(response.exit_code, 0);
         if let Output::Text(output) = response.output {
-            assert_eq!(output, "Hello!");
+            assert_eq!(output, "deployed prod");
+        } else {
+            panic!("Expected text output");
         }
-    }
-
-    #[tokio::test]
-    async fn test_router_args_parse_error() {
-        #[derive(Debug)]
-        struct StrictArgs;
 
-        impl FromArgs for StrictArgs {
-            fn from_args(args: &[String]) -> Result<Self, CliError> {
-                if args.is_empty() {
-                    Err(CliError::user("Arguments required"))
-                } else {
-                    Ok(StrictArgs)
-                }
-            }
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
         }
+        assert_eq!(
+            events,
+            vec![
+                ProgressEvent {
+                    fraction: 0.0,
+                    message: "starting".to_string()
+                },
+                ProgressEvent {
+                    fraction: 0.5,
+                    message: "halfway".to_string()
+                },
+                ProgressEvent {
+                    fraction: 1.0,
+                    message: "done".to_string()
+                },
+            ]
+        );
+    }
 
-        async fn strict(Args(_args): Args<StrictArgs>) -> CliResult<String> {
-            Ok("success".to_string())
+    #[tokio::test]
+    async fn test_progress_held_by_spawned_task_does_not_block() {
+        async fn start(_state: State<()>, progress: Progress) -> CliResult<String> {
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+                progress.set(1.0, "never");
+            });
+            Ok("started".to_string())
         }
 
-        let router = Router::new().route("strict", strict).with_state(());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let router = Router::new()
+            .route("start", start)
+            .with_progress_sink(tx)
+            .with_state(());
 
-        // Should fail with no args
-        let response = router
-            .execute_with(&["test".to_string(), "strict".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 1);
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            router.execute_with(&["test".to_string(), "start".to_string()]),
+        )
+        .await
+        .expect("command should return once the handler finishes");
+        assert_eq!(text_output(response), "started");
     }
 
     // ========================================
-    // Router::nest() Tests
+    // Router::with_writer() Tests
     // ========================================
 
     #[tokio::test]
-    async fn test_router_nest_basic() {
+    async fn test_writer_captures_progress_and_stream() {
         #[derive(Clone)]
         struct AppState;
 
-        async fn db_create(_state: State<AppState>) -> CliResult<String> {
-            Ok("DB created".to_string())
-        }
-
-        async fn db_list(_state: State<AppState>) -> CliResult<String> {
-            Ok("DB list".to_string())
+        async fn export(
+            _state: State<AppState>,
+            progress: Progress,
+            _args: Args<RawArgs>,
+        ) -> CliResult<String> {
+            let emitter = Emitter::current();
+            progress.set(0.5, "halfway");
+            emitter.emit("row 1");
+            emitter.emit("row 2");
+            Ok("exported".to_string())
         }
 
-        let db_router = Router::new()
-            .route("create", db_create)
-            .route("list", db_list);
-
-        let router = Router::new().nest("db", db_router).with_state(AppState);
+        let buffer = Arc::new(std::sync::Mutex::new(Vec::<u8>::new()));
+        let router = Router::new()
+            .route("export", export)
+            .with_writer(buffer.clone())
+            .with_state(AppState);
 
-        // Test nested command: "db create"
         let response = router
-            .execute_with(&["test".to_string(), "db".to_string(), "create".to_string()])
+            .execute_with(&["test".to_string(), "export".to_string()])
             .await;
         assert_eq!(response.exit_code, 0);
         if let Output::Text(output) = response.output {
-            assert_eq!(output, "DB created");
+            assert_eq!(output, "exported");
+        } else {
+            panic!("Expected text output");
         }
 
-        // Test nested command: "db list"
+        let captured = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        let mut lines: Vec<&str> = captured.lines().collect();
+        lines.sort_unstable();
+        assert_eq!(lines, vec!["[ 50%] halfway", "row 1", "row 2"]);
+    }
+
+    // ========================================
+    // Cancel Extractor Tests
+    // ========================================
+
+    #[tokio::test]
+    async fn test_cancellation_reaches_handler() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        #[derive(Clone)]
+        struct AppState {
+            cleaned_up: Arc<AtomicBool>,
+        }
+
+        async fn long_running(
+            state: State<AppState>,
+            cancel: Cancel,
+            _args: Args<RawArgs>,
+        ) -> CliResult<String> {
+            cancel.cancelled().await;
+            state.read().await.cleaned_up.store(true, Ordering::SeqCst);
+            Ok("cleaned up".to_string())
+        }
+
+        let trigger = Arc::new(tokio::sync::Notify::new());
+        let signal = trigger.clone();
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+        let router = Router::new()
+            .route("sync", long_running)
+            .with_cancellation_signal(move || {
+                let signal = signal.clone();
+                async move { signal.notified().await }
+            })
+            .with_state(AppState {
+                cleaned_up: cleaned_up.clone(),
+            });
+
+        trigger.notify_one();
         let response = router
-            .execute_with(&["test".to_string(), "db".to_string(), "list".to_string()])
+            .execute_with(&["test".to_string(), "sync".to_string()])
             .await;
-        assert_eq!(response.exit_code, 0);
+
+        assert_eq!(response.exit_code, 130);
+        assert!(cleaned_up.load(Ordering::SeqCst));
         if let Output::Text(output) = response.output {
-            assert_eq!(output, "DB list");
+            assert!(output.contains("Interrupted"));
+        } else {
+            panic!("Expected text output");
         }
     }
 
     #[tokio::test]
-    async fn test_router_nest_with_args() {
+    async fn test_cancellation_not_triggered_passes_result_through() {
         #[derive(Clone)]
         struct AppState;
 
-        #[derive(Debug)]
-        struct CreateArgs {
-            name: String,
+        async fn quick(
+            _state: State<AppState>,
+            cancel: Cancel,
+            _args: Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("cancelled: {}", cancel.is_cancelled()))
         }
 
-        impl FromArgs for CreateArgs {
-            fn from_args(args: &[String]) -> Result<Self, CliError> {
-                let name = args
-                    .first()
-                    .cloned()
-                    .unwrap_or_else(|| "default".to_string());
-                Ok(CreateArgs { name })
-            }
-        }
+        let router = Router::new()
+            .route("quick", quick)
+            .with_cancellation_signal(std::future::pending::<()>)
+            .with_state(AppState);
 
-        async fn db_create(
-            _state: State<AppState>,
-            Args(args): Args<CreateArgs>,
-        ) -> CliResult<String> {
-            Ok(format!("Created DB: {}", args.name))
+        let response = router
+            .execute_with(&["test".to_string(), "quick".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 0);
+        if let Output::Text(output) = response.output {
+            assert_eq!(output, "cancelled: false");
+        } else {
+            panic!("Expected text output");
         }
+    }
 
-        let db_router = Router::new().route("create", db_create);
+    // ========================================
+    // Redirect Tests
+    // ========================================
 
-        let router = Router::new().nest("db", db_router).with_state(AppState);
+    async fn echo_deploy(
+        _state: State<()>,
+        Args(RawArgs(args)): Args<RawArgs>,
+    ) -> CliResult<String> {
+        Ok(format!("deploy {}", args.join(" ")))
+    }
+
+    #[tokio::test]
+    async fn test_route_redirect_passes_combined_args() {
+        let router = Router::new()
+            .route("deploy", echo_deploy)
+            .route_redirect("deploy:prod", "deploy", ["--env=production"])
+            .with_state(());
 
-        // Test: "db create mydb"
         let response = router
             .execute_with(&[
                 "test".to_string(),
-                "db".to_string(),
-                "create".to_string(),
-                "mydb".to_string(),
+                "deploy".to_string(),
+                "prod".to_string(),
+                "--dry-run".to_string(),
             ])
             .await;
-
         assert_eq!(response.exit_code, 0);
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "Created DB: mydb");
+        assert_eq!(text_output(response), "deploy --env=production --dry-run");
+
+        let help = text_output(router.generate_help_text(None));
+        assert!(help.contains("Alias for 'deploy --env=production'"));
+    }
+
+    #[tokio::test]
+    async fn test_handler_can_return_redirect() {
+        async fn latest(_state: State<()>) -> CliResult<Redirect> {
+            Ok(Redirect::to("deploy").args(["--version", "1.2.0"]))
         }
+
+        let router = Router::new()
+            .route("deploy", echo_deploy)
+            .route("deploy:latest", latest)
+            .with_state(());
+
+        let response = router
+            .execute_with(&[
+                "test".to_string(),
+                "deploy".to_string(),
+                "latest".to_string(),
+            ])
+            .await;
+        assert_eq!(text_output(response), "deploy --version 1.2.0");
     }
 
     #[tokio::test]
-    async fn test_router_nest_multiple_levels() {
-        #[derive(Clone)]
-        struct AppState;
+    async fn test_redirect_cycle_is_an_error() {
+        let router = Router::new()
+            .route_redirect("ping", "pong", Vec::<String>::new())
+            .route_redirect("pong", "ping", Vec::<String>::new())
+            .route_redirect("broken", "missing", Vec::<String>::new())
+            .with_state(());
 
-        async fn status(_state: State<AppState>) -> CliResult<String> {
-            Ok("OK".to_string())
-        }
+        let response = router
+            .execute_with(&["test".to_string(), "ping".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 101);
+        assert!(text_output(response).contains("Too many redirects: ping -> pong -> ping"));
 
-        async fn db_create(_state: State<AppState>) -> CliResult<String> {
-            Ok("DB created".to_string())
-        }
+        let response = router
+            .execute_with(&["test".to_string(), "broken".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 101);
+        assert!(
+            text_output(response).contains("Redirect target 'missing' is not a registered command")
+        );
+    }
 
-        async fn server_start(_state: State<AppState>) -> CliResult<String> {
-            Ok("Server started".to_string())
-        }
+    // ========================================
+    // Handler Timeout Tests
+    // ========================================
 
-        let db_router = Router::new().route("create", db_create);
-        let server_router = Router::new().route("start", server_start);
+    async fn sleepy(_state: State<()>, _args: Args<RawArgs>) -> CliResult<String> {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        Ok("done".to_string())
+    }
+
+    async fn quick(_state: State<()>, _args: Args<RawArgs>) -> CliResult<String> {
+        Ok("done".to_string())
+    }
 
+    #[tokio::test]
+    async fn test_default_timeout_fails_slow_handler() {
         let router = Router::new()
-            .route("status", status) // Top-level command
-            .nest("db", db_router) // Nested commands
-            .nest("server", server_router)
-            .with_state(AppState);
+            .route("db:migrate", sleepy)
+            .route("status", quick)
+            .with_default_timeout(std::time::Duration::from_millis(50))
+            .with_state(());
 
-        // Top-level command
         let response = router
-            .execute_with(&["test".to_string(), "status".to_string()])
+            .execute_with(&["test".to_string(), "db".to_string(), "migrate".to_string()])
             .await;
-        assert_eq!(response.exit_code, 0);
+        assert_eq!(response.exit_code, 124);
+        match response.output {
+            Output::Text(output) => {
+                assert_eq!(output, "Error: Command 'db migrate' timed out after 50ms")
+            }
+            _ => panic!("Expected text output"),
+        }
 
-        // Nested command: db create
         let response = router
-            .execute_with(&["test".to_string(), "db".to_string(), "create".to_string()])
+            .execute_with(&["test".to_string(), "status".to_string()])
             .await;
         assert_eq!(response.exit_code, 0);
+    }
 
-        // Nested command: server start
+    #[tokio::test]
+    async fn test_handler_timeout_overrides_default() {
+        let router = Router::new()
+            .route(
+                "migrate",
+                HandlerWithMeta::new(
+                    sleepy,
+                    HandlerMetadata {
+                        timeout: Some(std::time::Duration::from_millis(20)),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .with_default_timeout(std::time::Duration::from_secs(60))
+            .with_state(());
+
+        let start = std::time::Instant::now();
         let response = router
-            .execute_with(&[
-                "test".to_string(),
-                "server".to_string(),
-                "start".to_string(),
-            ])
+            .execute_with(&["test".to_string(), "migrate".to_string()])
             .await;
-        assert_eq!(response.exit_code, 0);
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(response.exit_code, 124);
+        assert_eq!(response.error.unwrap().kind, "timeout");
     }
 
+    // ========================================
+    // Router::merge() Tests
+    // ========================================
+
     #[tokio::test]
-    async fn test_router_nest_unknown_subcommand() {
+    async fn test_router_merge_basic() {
         #[derive(Clone)]
         struct AppState;
 
+        async fn status(_state: State<AppState>) -> CliResult<String> {
+            Ok("status".to_string())
+        }
+
+        async fn deploy(_state: State<AppState>) -> CliResult<String> {
+            Ok("deploy".to_string())
+        }
+
         async fn db_create(_state: State<AppState>) -> CliResult<String> {
-            Ok("DB created".to_string())
+            Ok("db:create".to_string())
         }
 
-        let db_router = Router::new().route("create", db_create);
-        let router = Router::new().nest("db", db_router).with_state(AppState);
+        let core = Router::new().route(
+            "status",
+            HandlerWithMeta::new(
+                status,
+                HandlerMetadata {
+                    desc: Some("Show status"),
+                    ..Default::default()
+                },
+            ),
+        );
+        let extras = Router::new()
+            .route(
+                "deploy",
+                HandlerWithMeta::new(
+                    deploy,
+                    HandlerMetadata {
+                        desc: Some("Deploy the app"),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .nest("db", Router::new().route("create", db_create));
+
+        let router = core.merge(extras).with_state(AppState);
+
+        for (args, expected) in [
+            (vec!["status"], "status"),
+            (vec!["deploy"], "deploy"),
+            (vec!["db", "create"], "db:create"),
+        ] {
+            let mut full_args = vec!["test".to_string()];
+            full_args.extend(args.iter().map(|s| s.to_string()));
+            let response = router.execute_with(&full_args).await;
+            assert_eq!(response.exit_code, 0);
+            if let Output::Text(output) = response.output {
+                assert_eq!(output, expected);
+            } else {
+                panic!("Expected text output");
+            }
+        }
 
-        // Unknown subcommand
-        let response = router
-            .execute_with(&["test".to_string(), "db".to_string(), "delete".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 1);
+        // Help lists commands and descriptions from both routers
+        let response = router.execute_with(&["test".to_string()]).await;
+        if let Output::Text(help) = response.output {
+            assert!(help.contains("status"));
+            assert!(help.contains("Show status"));
+            assert!(help.contains("deploy"));
+            assert!(help.contains("Deploy the app"));
+            assert!(help.contains("create"));
+        } else {
+            panic!("Expected help text");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate route: status")]
+    fn test_router_merge_duplicate_panics() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("status".to_string())
+        }
+
+        let a: Router<()> = Router::new().route("status", status);
+        let b: Router<()> = Router::new().route("status", status);
+        let _ = a.merge(b);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate scoped args: db")]
+    fn test_router_merge_duplicate_scoped_args_panics() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("status".to_string())
+        }
+
+        let a: Router<()> =
+            Router::new().nest_with_args::<DbOpts>("db", Router::new().route("create", status));
+        let b: Router<()> =
+            Router::new().nest_with_args::<DbOpts>("db", Router::new().route("list", status));
+        let _ = a.merge(b);
     }
 
     // ========================================
-    // Clap Integration Tests
+    // try_execute_with() Tests
     // ========================================
-    // TODO: These tests need Handler trait implementation for module-scoped async functions
-    // Currently blocked - async functions defined in test scope don't satisfy Handler trait bounds
-    // Workaround: Move handlers to top-level module or use #[sen::handler] macro
 
-    #[cfg(feature = "unstable-clap-tests")]
     #[tokio::test]
-    async fn test_clap_integration_basic() {
-        mod test_scope {
-            use super::*;
-            use clap::Parser;
+    async fn test_try_execute_with_preserves_handler_error() {
+        async fn validate(_state: State<()>) -> CliResult<String> {
+            Err(CliError::User(UserError::InvalidArgument {
+                arg: "--port".to_string(),
+                reason: "must be a number".to_string(),
+            }))
+        }
 
-            #[derive(Clone)]
-            pub struct AppState;
+        async fn crash(_state: State<()>) -> CliResult<String> {
+            Err(CliError::System(SystemError::ConfigParse(
+                "bad toml".to_string(),
+            )))
+        }
 
-            #[derive(Parser, Debug)]
-            pub struct BuildArgs {
-                /// Database name
-                pub name: String,
+        let router = Router::new()
+            .route("validate", validate)
+            .route("crash", crash)
+            .with_state(());
 
-                /// Build in release mode
-                #[arg(long)]
-                pub release: bool,
+        let err = router
+            .try_execute_with(&["test".to_string(), "validate".to_string()])
+            .await
+            .err()
+            .expect("Expected typed error");
+        match err {
+            CliError::User(UserError::InvalidArgument { arg, reason }) => {
+                assert_eq!(arg, "--port");
+                assert_eq!(reason, "must be a number");
             }
+            other => panic!("Unexpected error: {:?}", other),
+        }
 
-            pub async fn build(
-                _state: State<AppState>,
-                Args(args): Args<BuildArgs>,
-            ) -> CliResult<String> {
-                if args.release {
-                    Ok(format!("Release build: {}", args.name))
-                } else {
-                    Ok(format!("Debug build: {}", args.name))
-                }
+        let err = router
+            .try_execute_with(&["test".to_string(), "crash".to_string()])
+            .await
+            .err()
+            .expect("Expected typed error");
+        assert!(matches!(
+            err,
+            CliError::System(SystemError::ConfigParse(ref msg)) if msg == "bad toml"
+        ));
+        assert_eq!(err.exit_code(), 101);
+
+        // execute_with still folds the error into a Response
+        let response = router
+            .execute_with(&["test".to_string(), "validate".to_string()])
+            .await;
+        assert_eq!(response.exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_try_execute_with_preserves_args_and_routing_errors() {
+        #[derive(Debug)]
+        struct PortArgs;
+
+        impl FromArgs for PortArgs {
+            fn from_args(_args: &[String]) -> Result<Self, CliError> {
+                Err(CliError::User(UserError::ValidationFailed {
+                    details: vec!["port is required".to_string()],
+                }))
             }
         }
 
+        async fn serve(Args(_args): Args<PortArgs>) -> CliResult<String> {
+            Ok("serving".to_string())
+        }
+
         let router = Router::new()
-            .route("build", test_scope::build)
-            .with_state(test_scope::AppState);
+            .route("deploy", join_args)
+            .route("serve", serve)
+            .with_state(());
+
+        let err = router
+            .try_execute_with(&["test".to_string(), "serve".to_string()])
+            .await
+            .err()
+            .expect("Expected args parse error");
+        assert!(matches!(
+            err,
+            CliError::User(UserError::ValidationFailed { ref details }) if details.len() == 1
+        ));
+
+        let err = router
+            .try_execute_with(&["test".to_string(), "unknown".to_string()])
+            .await
+            .err()
+            .expect("Expected unknown command error");
+        assert!(matches!(
+            err,
+            CliError::User(UserError::UnknownCommand { ref command, .. }) if command == "unknown"
+        ));
 
-        // Test with --release
         let response = router
-            .execute(&[
-                "build".to_string(),
-                "myapp".to_string(),
-                "--release".to_string(),
-            ])
-            .await;
+            .try_execute_with(&["test".to_string(), "deploy".to_string(), "prod".to_string()])
+            .await
+            .expect("Expected success");
         assert_eq!(response.exit_code, 0);
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "Release build: myapp");
+    }
+
+    #[tokio::test]
+    async fn test_help_header_uses_metadata() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("ok".to_string())
         }
 
-        // Test without --release
-        let response = router
-            .execute(&["build".to_string(), "myapp".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "Debug build: myapp");
+        let router = Router::new()
+            .route("status", status)
+            .with_metadata(RouterMetadata {
+                name: "myctl",
+                version: Some("1.2.3"),
+                about: Some("Manage things"),
+            })
+            .with_state(());
+
+        let response = router.execute_with(&["myctl".to_string()]).await;
+        match response.output {
+            Output::Text(help) => {
+                assert!(help.starts_with("myctl 1.2.3\nManage things\n\n"));
+                assert!(help.contains("Usage: myctl [OPTIONS] <COMMAND>"));
+            }
+            _ => panic!("Expected help text"),
         }
     }
 
-    #[cfg(feature = "unstable-clap-tests")]
     #[tokio::test]
-    async fn test_clap_integration_with_env() {
-        mod test_scope {
-            use super::*;
-            use clap::Parser;
-
-            #[derive(Clone)]
-            pub struct AppState;
+    async fn test_nested_group_description_in_help() {
+        async fn create(_state: State<()>) -> CliResult<String> {
+            Ok("created".to_string())
+        }
 
-            #[derive(Parser, Debug)]
-            pub struct DeployArgs {
-                /// App name
-                pub app: String,
+        let db_router = Router::new()
+            .route("create", create)
+            .describe_group("Database operations");
+        let router = Router::new()
+            .nest("db", db_router)
+            .nest("server", Router::new().route("start", create))
+            .with_state(());
 
-                /// Target environment (from env var or flag)
-                #[arg(long, env = "DEPLOY_ENV", default_value = "production")]
-                pub env: String,
+        let response = router.execute_with(&["myctl".to_string()]).await;
+        match response.output {
+            Output::Text(help) => {
+                let heading = help
+                    .lines()
+                    .find(|line| line.contains("Database Commands:"))
+                    .expect("db group heading");
+                assert!(heading.contains("Database operations"));
+
+                // Groups without a description keep the plain heading
+                let heading = help
+                    .lines()
+                    .find(|line| line.contains("Server Commands:"))
+                    .expect("server group heading");
+                assert!(!heading.contains("Database operations"));
             }
+            _ => panic!("Expected help text"),
+        }
+    }
 
-            pub async fn deploy(
-                _state: State<AppState>,
-                Args(args): Args<DeployArgs>,
-            ) -> CliResult<String> {
-                Ok(format!("Deploying {} to {}", args.app, args.env))
+    #[derive(Clone)]
+    struct DbOpts {
+        namespace: String,
+    }
+
+    impl FromGlobalArgs for DbOpts {
+        fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
+            let mut namespace = "default".to_string();
+            let mut remaining = Vec::new();
+            let mut iter = args.iter();
+            while let Some(arg) = iter.next() {
+                if arg == "--namespace" {
+                    namespace = iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| CliError::user("--namespace requires a value"))?;
+                } else {
+                    remaining.push(arg.clone());
+                }
             }
+            Ok((DbOpts { namespace }, remaining))
         }
+    }
 
-        let router = Router::new()
-            .route("deploy", test_scope::deploy)
-            .with_state(test_scope::AppState);
+    fn scoped_router() -> Router<()> {
+        async fn create(
+            _state: State<()>,
+            Scoped(db): Scoped<DbOpts>,
+            Args(RawArgs(args)): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("create {} in {}", args.join(" "), db.namespace))
+        }
 
-        // Test with explicit --env flag
-        let response = router
-            .execute(&[
-                "deploy".to_string(),
-                "myapp".to_string(),
-                "--env".to_string(),
-                "staging".to_string(),
-            ])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "Deploying myapp to staging");
+        async fn list(
+            _state: State<()>,
+            Scoped(db): Scoped<DbOpts>,
+            Args(RawArgs(_)): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("list in {}", db.namespace))
         }
 
-        // Test with default value
-        let response = router
-            .execute(&["deploy".to_string(), "myapp".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "Deploying myapp to production");
+        async fn start(_state: State<()>, Args(RawArgs(args)): Args<RawArgs>) -> CliResult<String> {
+            Ok(format!("start {}", args.join(" ")))
+        }
+
+        async fn stop(
+            _state: State<()>,
+            Scoped(db): Scoped<DbOpts>,
+            Args(RawArgs(_)): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(db.namespace)
         }
+
+        Router::new()
+            .nest_with_args::<DbOpts>(
+                "db",
+                Router::new().route("create", create).route("list", list),
+            )
+            .nest(
+                "server",
+                Router::new().route("start", start).route("stop", stop),
+            )
+            .with_state(())
     }
 
-    #[cfg(feature = "unstable-clap-tests")]
     #[tokio::test]
-    async fn test_clap_integration_validation() {
-        mod test_scope {
-            use super::*;
-            use clap::Parser;
+    async fn test_scoped_args_reach_every_command_in_namespace() {
+        let router = scoped_router();
 
-            #[derive(Clone)]
-            pub struct AppState;
+        let args: Vec<String> = ["test", "db", "create", "users", "--namespace", "prod"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(text_output(response), "create users in prod");
 
-            #[derive(Parser, Debug)]
-            pub struct CreateArgs {
-                /// Name (required)
-                pub name: String,
+        let args: Vec<String> = ["test", "db", "list", "--namespace", "staging"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(text_output(response), "list in staging");
 
-                /// Port number
-                #[arg(long, value_parser = clap::value_parser!(u16).range(1..=65535))]
-                pub port: Option<u16>,
-            }
+        let args: Vec<String> = ["test", "db", "list"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(text_output(response), "list in default");
 
-            pub async fn create(
-                _state: State<AppState>,
-                Args(args): Args<CreateArgs>,
-            ) -> CliResult<String> {
-                Ok(format!("Created {} on port {:?}", args.name, args.port))
-            }
-        }
+        // Parse errors of the scoped options are reported like argument errors
+        let args: Vec<String> = ["test", "db", "list", "--namespace"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(response.exit_code, 1);
+    }
 
-        let router = Router::new()
-            .route("create", test_scope::create)
-            .with_state(test_scope::AppState);
+    #[tokio::test]
+    async fn test_scoped_args_not_recognized_outside_namespace() {
+        let router = scoped_router();
 
-        // Valid port
-        let response = router
-            .execute(&[
-                "create".to_string(),
-                "mydb".to_string(),
-                "--port".to_string(),
-                "3000".to_string(),
-            ])
-            .await;
-        assert_eq!(response.exit_code, 0);
+        // `--namespace` is an ordinary argument under `server`
+        let args: Vec<String> = ["test", "server", "start", "--namespace", "prod"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(text_output(response), "start --namespace prod");
 
-        // Invalid port (out of range) - clap will return error
-        let response = router
-            .execute(&[
-                "create".to_string(),
-                "mydb".to_string(),
-                "--port".to_string(),
-                "99999".to_string(),
-            ])
-            .await;
-        assert_eq!(response.exit_code, 1);
+        // Asking for the options outside their namespace is a wiring bug
+        let args: Vec<String> = ["test", "server", "stop"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(response.exit_code, 101);
     }
 
-    // ========================================
-    // Agent Mode Tests
-    // ========================================
+    #[tokio::test]
+    async fn test_scoped_args_with_custom_separator() {
+        async fn grant(
+            _state: State<()>,
+            Scoped(db): Scoped<DbOpts>,
+            Args(RawArgs(args)): Args<RawArgs>,
+        ) -> CliResult<String> {
+            Ok(format!("grant {} in {}", args.join(" "), db.namespace))
+        }
+
+        let router = Router::new()
+            .with_separator('/')
+            .nest_with_args::<DbOpts>("db/user", Router::new().route("grant", grant))
+            .with_state(());
+        assert!(router.scoped_args.contains_key("db:user"));
+
+        let args: Vec<String> = [
+            "test",
+            "db",
+            "user",
+            "grant",
+            "alice",
+            "--namespace",
+            "prod",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let response = router.execute_with(&args).await;
+        assert_eq!(text_output(response), "grant alice in prod");
+    }
 
     #[tokio::test]
-    async fn test_router_with_agent_mode_enabled() {
+    async fn test_describe_sets_route_description() {
         async fn status(_state: State<()>) -> CliResult<String> {
-            Ok("Status: OK".to_string())
+            Ok("ok".to_string())
         }
 
         let router = Router::new()
             .route("status", status)
-            .with_agent_mode()
+            .route("build", status)
+            .describe("status", "Show service status")
             .with_state(());
 
-        // Without --agent-mode flag
-        let response = router
-            .execute_with(&["test".to_string(), "status".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        assert!(!response.agent_mode);
+        let help = text_output(router.execute_with(&["test".to_string()]).await);
+        let line = help
+            .lines()
+            .find(|line| line.contains("status"))
+            .expect("status listed");
+        assert!(line.contains("Show service status"));
+        assert_eq!(
+            router.route_metadata["status"].get_description(),
+            Some("Show service status")
+        );
+        assert_eq!(router.route_metadata["build"].get_description(), None);
+    }
 
-        // With --agent-mode flag
-        let response = router
-            .execute_with(&[
-                "test".to_string(),
-                "--agent-mode".to_string(),
-                "status".to_string(),
-            ])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        assert!(response.agent_mode);
+    #[test]
+    #[should_panic(expected = "Cannot describe unknown route: deploy")]
+    fn test_describe_unknown_route_panics() {
+        let _ = Router::<()>::new().describe("deploy", "Deploy");
+    }
+
+    async fn ok(_state: State<()>) -> CliResult<String> {
+        Ok("ok".to_string())
+    }
+
+    fn gated_router() -> Router<()> {
+        Router::new()
+            .route("status", ok)
+            .route("deploy", ok)
+            .nest("db", Router::new().route("create", ok).route("list", ok))
     }
 
     #[tokio::test]
-    async fn test_router_without_agent_mode_enabled() {
-        async fn status(_state: State<()>) -> CliResult<String> {
-            Ok("Status: OK".to_string())
-        }
+    async fn test_disabled_command_is_unroutable_and_hidden() {
+        let router = gated_router()
+            .disable("deploy")
+            .disable("db create")
+            .disable("not-built")
+            .with_state(());
 
-        let router = Router::new().route("status", status).with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
 
-        // With --agent-mode flag but agent_mode not enabled
-        // The flag is treated as a command, resulting in "Unknown command" error
-        let response = router
-            .execute_with(&[
-                "test".to_string(),
-                "--agent-mode".to_string(),
-                "status".to_string(),
-            ])
-            .await;
-        assert_eq!(response.exit_code, 1); // Error: unknown command
-        assert!(!response.agent_mode);
+        for args in [&["deploy"][..], &["db", "create"]] {
+            let response = run(args).await;
+            assert_eq!(response.exit_code, 1);
+            assert_eq!(response.error.unwrap().kind, "unknown_command");
+        }
+        assert_eq!(text_output(run(&["status"]).await), "ok");
+        assert_eq!(text_output(run(&["db", "list"]).await), "ok");
+
+        let help = text_output(run(&[]).await);
+        assert!(help.contains("status"));
+        assert!(help.contains("list"));
+        assert!(!help.contains("deploy"));
+        assert!(!help.contains("create"));
+    }
 
-        // Without --agent-mode flag works fine
-        let response = router
-            .execute_with(&["test".to_string(), "status".to_string()])
-            .await;
-        assert_eq!(response.exit_code, 0);
-        assert!(!response.agent_mode);
+    #[test]
+    fn test_enable_only_keeps_listed_commands_and_groups() {
+        let router = gated_router().enable_only(&["status", "db"]);
+
+        let mut routes: Vec<_> = router.routes.keys().cloned().collect();
+        routes.sort();
+        assert_eq!(routes, ["db:create", "db:list", "status"]);
+        assert!(!router.route_metadata.contains_key("deploy"));
+
+        // A group name does not match other commands sharing its prefix
+        let router = gated_router().route("dbx", ok).disable("db");
+        assert!(router.routes.contains_key("dbx"));
+        assert!(!router.routes.contains_key("db:list"));
+    }
+
+    #[test]
+    fn test_disable_prunes_completers_and_scoped_args() {
+        let router = Router::new()
+            .route("deploy", ok)
+            .completer("deploy", |_command: String, _current: String| async move {
+                vec!["prod".to_string()]
+            })
+            .nest_with_args::<DbOpts>("db", Router::new().route("create", ok).route("list", ok))
+            .disable("deploy")
+            .disable("db create");
+        assert!(!router.completers.contains_key("deploy"));
+        // The scope stays while any command under it remains
+        assert!(router.scoped_args.contains_key("db"));
+
+        let router = router.disable("db list");
+        assert!(router.scoped_args.is_empty());
     }
 
     #[tokio::test]
-    async fn test_agent_mode_flag_stripped_from_args() {
-        #[derive(Debug)]
-        struct TestArgs {
-            message: String,
+    async fn test_help_header_falls_back_without_metadata() {
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("ok".to_string())
         }
 
-        impl FromArgs for TestArgs {
-            fn from_args(args: &[String]) -> Result<Self, CliError> {
-                // --agent-mode should NOT appear in args
-                for arg in args {
-                    if arg == "--agent-mode" {
-                        return Err(CliError::user(
-                            "Unexpected --agent-mode flag in handler args",
-                        ));
-                    }
-                }
+        let router = Router::new().route("status", status).with_state(());
+        let (name, version) = router.help_header();
+
+        // The test binary stands in for the CLI executable
+        let expected = std::env::current_exe()
+            .unwrap()
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(name, expected);
+        assert_eq!(version, None);
 
-                let message = args.first().cloned().unwrap_or_else(|| "empty".to_string());
-                Ok(TestArgs { message })
+        let response = router.execute_with(&["test".to_string()]).await;
+        match response.output {
+            Output::Text(help) => {
+                let header = format!("{}\n\n", expected);
+                assert!(help.starts_with(&header));
+                assert!(help.contains(&format!("Usage: {} [OPTIONS] <COMMAND>", expected)));
             }
+            _ => panic!("Expected help text"),
         }
+    }
 
-        async fn echo(Args(args): Args<TestArgs>) -> CliResult<String> {
-            Ok(args.message)
+    #[tokio::test]
+    async fn test_try_execute_with_help_is_ok() {
+        async fn help(_state: State<()>) -> CliResult<String> {
+            Err(CliError::User(UserError::Help("usage: help".to_string())))
         }
 
-        let router = Router::new()
-            .route("echo", echo)
-            .with_agent_mode()
-            .with_state(());
-
-        // --agent-mode should be stripped before passing to handler
+        let router = Router::new().route("help", help).with_state(());
         let response = router
-            .execute_with(&[
-                "test".to_string(),
-                "--agent-mode".to_string(),
-                "echo".to_string(),
-                "hello".to_string(),
-            ])
-            .await;
-
+            .try_execute_with(&["test".to_string(), "help".to_string()])
+            .await
+            .expect("Help should be Ok");
         assert_eq!(response.exit_code, 0);
-        assert!(response.agent_mode);
-        // Should receive "hello", not "--agent-mode" or error
-        if let Output::Text(output) = response.output {
-            assert_eq!(output, "hello");
-        } else {
-            panic!("Expected text output");
-        }
     }
 }
@@ -7,6 +7,8 @@
 //!
 //! - `--mcp-server`: Start in MCP server mode (JSON-RPC over stdio)
 //! - `--mcp-init <client>`: Generate MCP configuration for specified client
+//! - `--mcp-call <tool> '<json-args>'`: Run one tool call and print the response
+//! - A `__describe` tool returning the whole command catalog in one call
 //! - Automatic tool schema generation from RouteMetadata
 //! - Streaming support (stdout → MCP notifications)
 
@@ -16,6 +18,17 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::io::{BufRead, BufReader, Write};
 
+/// JSON-RPC error code returned by `tools/call` for an unknown tool
+/// ("Method not found")
+pub const UNKNOWN_TOOL_ERROR_CODE: i64 = -32601;
+
+/// JSON-RPC error code returned by `tools/call` when the arguments do not
+/// match the tool's input schema ("Invalid params")
+pub const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+
+/// Default name of the catalog tool (see [`McpTool::describe`])
+pub const DESCRIBE_TOOL: &str = "__describe";
+
 /// MCP Tool definition according to Model Context Protocol specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpTool {
@@ -26,6 +39,9 @@ pub struct McpTool {
     /// JSON Schema for tool input parameters
     #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
+    /// Command tags, used to filter `tools/list` (not sent to clients)
+    #[serde(default, skip_serializing)]
+    pub tags: Vec<String>,
 }
 
 impl McpTool {
@@ -41,25 +57,214 @@ impl McpTool {
     /// An MCP tool with proper JSON Schema for input parameters
     pub fn from_route_metadata(name: String, metadata: &RouteMetadata) -> Self {
         // Get description from metadata (prefer route description over handler description)
-        let description = metadata
+        let mut description = metadata
             .get_description()
             .unwrap_or("No description available")
             .to_string();
 
+        let aliases = metadata.get_aliases();
+        if !aliases.is_empty() {
+            description.push_str(&format!("\n\nAliases: {}", aliases.join(", ")));
+        }
+
+        // Examples show agents how the command is called
+        let examples = metadata.get_examples();
+        if !examples.is_empty() {
+            description.push_str("\n\nExamples:");
+            for example in examples {
+                description.push_str("\n  ");
+                description.push_str(example);
+            }
+        }
+
         // Get input schema from metadata, or use default empty object schema
-        let input_schema = metadata.get_args_schema().cloned().unwrap_or_else(|| {
-            json!({
-                "type": "object",
-                "properties": {},
-                "required": []
-            })
-        });
+        let input_schema = metadata
+            .get_args_schema()
+            .map(args_schema_to_input_schema)
+            .unwrap_or_else(|| {
+                json!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                })
+            });
+
+        let tags = metadata
+            .handler_meta
+            .as_ref()
+            .and_then(|h| h.tags.as_ref())
+            .map(|tags| tags.iter().map(|tag| tag.to_string()).collect())
+            .unwrap_or_default();
 
         McpTool {
             name,
             description,
             input_schema,
+            tags,
+        }
+    }
+
+    /// Synthetic tool that returns the command catalog
+    ///
+    /// Calling it yields the same JSON as `--help --json` (commands with
+    /// tiers, tags and argument schemas, plus the version and
+    /// `catalog_hash`), so an agent can bootstrap from one request. The
+    /// optional `--tag` argument limits the catalog to commands carrying
+    /// that tag.
+    pub fn describe(name: impl Into<String>) -> Self {
+        McpTool {
+            name: name.into(),
+            description: "Describe every command of this CLI: descriptions, safety tiers, \
+                          tags, and argument schemas, plus the version and catalog_hash"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "--tag": {
+                        "type": "string",
+                        "description": "Only include commands carrying this tag"
+                    }
+                },
+                "required": []
+            }),
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// Convert a CLI args schema (`arguments`/`options`) to a JSON Schema.
+///
+/// Property names match the keys `tools/call` expects: positional names as-is,
+/// options with their `--` prefix. `possible_values` become `enum` and
+/// `default` is converted to the property's type. Variadic positionals become arrays,
+/// `requires` becomes `dependencies` and each
+/// `conflicts_with` pair becomes a `not: { required: [a, b] }` clause.
+/// Schemas that already have a `type` are passed through unchanged.
+fn args_schema_to_input_schema(schema: &Value) -> Value {
+    if schema.get("type").is_some() {
+        return schema.clone();
+    }
+
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    let mut dependencies = serde_json::Map::new();
+    let mut exclusions = std::collections::BTreeSet::new();
+
+    let entries = ["arguments", "options"]
+        .iter()
+        .filter_map(|key| schema.get(*key).and_then(Value::as_array))
+        .flatten();
+    for entry in entries {
+        let Some(name) = entry.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+
+        let mut property = json!({
+            "description": entry.get("description").cloned().unwrap_or(json!("")),
+        });
+        let item_type = entry
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(json_schema_type);
+        if entry.get("variadic").and_then(Value::as_bool) == Some(true) {
+            property["type"] = json!("array");
+            if let Some(ty) = item_type {
+                property["items"] = json!({ "type": ty });
+            }
+            if let Some(max) = entry.get("max_values").and_then(Value::as_u64) {
+                property["maxItems"] = json!(max);
+            }
+        } else if let Some(ty) = item_type {
+            property["type"] = json!(ty);
+        }
+
+        if let Some(values) = entry.get("possible_values").and_then(Value::as_array) {
+            if !values.is_empty() {
+                if property.get("type").is_none() {
+                    property["type"] = json!("string");
+                }
+                let target = if property["type"] == "array" {
+                    &mut property["items"]
+                } else {
+                    &mut property
+                };
+                target["enum"] = Value::Array(values.clone());
+            }
+        }
+        if let Some(default) = entry.get("default").filter(|d| !d.is_null()) {
+            let ty = property["type"].as_str().unwrap_or_default();
+            property["default"] = typed_default(ty, default);
+        }
+        properties.insert(name.to_string(), property);
+
+        if entry.get("required").and_then(Value::as_bool) == Some(true) {
+            required.push(json!(name));
+        }
+        if let Some(requires) = entry.get("requires").and_then(Value::as_array) {
+            if !requires.is_empty() {
+                dependencies.insert(name.to_string(), Value::Array(requires.clone()));
+            }
+        }
+        for other in entry
+            .get("conflicts_with")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            let pair = if name < other {
+                (name, other)
+            } else {
+                (other, name)
+            };
+            exclusions.insert(pair);
+        }
+    }
+
+    let mut input_schema = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    if !dependencies.is_empty() {
+        input_schema["dependencies"] = Value::Object(dependencies);
+    }
+    if !exclusions.is_empty() {
+        input_schema["allOf"] = exclusions
+            .into_iter()
+            .map(|(a, b)| json!({ "not": { "required": [a, b] } }))
+            .collect();
+    }
+    input_schema
+}
+
+/// Convert a string default from an args schema to the property's JSON type.
+///
+/// Defaults that do not parse as that type are kept as given.
+fn typed_default(ty: &str, default: &Value) -> Value {
+    let Some(text) = default.as_str() else {
+        return default.clone();
+    };
+    let parsed = match ty {
+        "boolean" => text.parse::<bool>().ok().map(Value::from),
+        "integer" => text.parse::<i64>().ok().map(Value::from),
+        "number" => text.parse::<f64>().ok().map(Value::from),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| default.clone())
+}
+
+/// Map a Rust type name from an args schema to a JSON Schema type.
+fn json_schema_type(rust_type: &str) -> Option<&'static str> {
+    // clap reports full paths in debug builds (e.g. `alloc::string::String`)
+    match rust_type.rsplit("::").next().unwrap_or(rust_type) {
+        "bool" => Some("boolean"),
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            Some("integer")
         }
+        "f32" | "f64" => Some("number"),
+        "String" | "PathBuf" => Some("string"),
+        _ => None,
     }
 }
 
@@ -103,25 +308,223 @@ impl Default for McpServer {
     }
 }
 
-/// Send an MCP notification to stdout
+/// Write a JSON-RPC message as one line and flush it
+fn write_message<W: Write>(out: &mut W, message: &Value) {
+    if let Ok(message_str) = serde_json::to_string(message) {
+        let _ = writeln!(out, "{}", message_str);
+        let _ = out.flush();
+    }
+}
+
+/// Send an MCP notification
 ///
 /// # Arguments
 ///
+/// * `out` - Transport the notification is written to
 /// * `method` - Notification method (e.g., "notifications/message")
 /// * `params` - Notification parameters as JSON value
-fn send_notification(method: &str, params: Value) {
+fn send_notification<W: Write>(out: &mut W, method: &str, params: Value) {
     let notification = json!({
         "jsonrpc": "2.0",
         "method": method,
         "params": params
     });
+    write_message(out, &notification);
+}
+
+/// Check tool arguments against a tool's input schema
+///
+/// Supports the subset of JSON Schema that [`McpTool::from_route_metadata`]
+/// generates: `required`, property `type` and `enum` (including array `items`
+/// and `maxItems`), `dependencies` and `allOf` of `not: { required }` clauses.
+/// Unknown arguments are rejected when the schema lists any properties.
+/// An argument of `null` or `false` counts as absent, matching how it is
+/// turned into CLI args.
+///
+/// Returns one message per problem found.
+fn validate_arguments(schema: &Value, arguments: &Value) -> Result<(), Vec<String>> {
+    let Some(arguments) = arguments.as_object() else {
+        return Err(vec!["arguments must be an object".to_string()]);
+    };
+    let present = |name: &str| {
+        arguments
+            .get(name)
+            .is_some_and(|v| !v.is_null() && *v != Value::Bool(false))
+    };
+    let names = |value: Option<&Value>| -> Vec<String> {
+        value
+            .and_then(Value::as_array)
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut errors = Vec::new();
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    for name in names(schema.get("required")) {
+        if !present(&name) {
+            errors.push(format!("missing required argument '{}'", name));
+        }
+    }
+
+    for (name, value) in arguments {
+        let Some(property) = properties.and_then(|p| p.get(name)) else {
+            if properties.is_some_and(|p| !p.is_empty()) {
+                errors.push(format!("unknown argument '{}'", name));
+            }
+            continue;
+        };
+        if value.is_null() {
+            continue;
+        }
+        if let Some(ty) = property.get("type").and_then(Value::as_str) {
+            if !matches_type(ty, value) {
+                errors.push(format!("argument '{}' must be of type {}", name, ty));
+                continue;
+            }
+        }
+        if let Some(allowed) = property.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                errors.push(format!(
+                    "argument '{}' must be one of {}",
+                    name,
+                    format_enum(allowed)
+                ));
+                continue;
+            }
+        }
+        if let Some(items) = value.as_array() {
+            if let Some(allowed) = property.pointer("/items/enum").and_then(Value::as_array) {
+                if !items.iter().all(|item| allowed.contains(item)) {
+                    errors.push(format!(
+                        "items of argument '{}' must be one of {}",
+                        name,
+                        format_enum(allowed)
+                    ));
+                }
+            }
+            if let Some(ty) = property.pointer("/items/type").and_then(Value::as_str) {
+                if !items.iter().all(|item| matches_type(ty, item)) {
+                    errors.push(format!(
+                        "items of argument '{}' must be of type {}",
+                        name, ty
+                    ));
+                }
+            }
+            if let Some(max) = property.get("maxItems").and_then(Value::as_u64) {
+                if items.len() as u64 > max {
+                    errors.push(format!(
+                        "argument '{}' accepts at most {} values",
+                        name, max
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(dependencies) = schema.get("dependencies").and_then(Value::as_object) {
+        for (name, required) in dependencies {
+            if !present(name) {
+                continue;
+            }
+            for other in names(Some(required)) {
+                if !present(&other) {
+                    errors.push(format!("argument '{}' requires '{}'", name, other));
+                }
+            }
+        }
+    }
+
+    for clause in schema
+        .get("allOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let excluded = names(clause.pointer("/not/required"));
+        if !excluded.is_empty() && excluded.iter().all(|name| present(name)) {
+            errors.push(format!(
+                "arguments {} cannot be used together",
+                excluded
+                    .iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Render allowed values as `a, b, c`
+fn format_enum(allowed: &[Value]) -> String {
+    allowed
+        .iter()
+        .map(|v| {
+            v.as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| v.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    if let Ok(notification_str) = serde_json::to_string(&notification) {
-        println!("{}", notification_str);
-        std::io::stdout().flush().ok();
+/// Whether `value` is an instance of the JSON Schema type `ty`
+fn matches_type(ty: &str, value: &Value) -> bool {
+    match ty {
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
     }
 }
 
+/// Tool names close to `name`, best match first
+fn suggest_tools(name: &str, tools: &[McpTool]) -> Vec<String> {
+    const MAX_SUGGESTIONS: usize = 3;
+
+    let threshold = (name.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &str)> = tools
+        .iter()
+        .map(|tool| (crate::edit_distance(name, &tool.name), tool.name.as_str()))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// JSON-RPC error response for an unknown tool
+fn unknown_tool_error(id: Option<&Value>, tool_name: &str, did_you_mean: Vec<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": UNKNOWN_TOOL_ERROR_CODE,
+            "message": format!("Unknown tool: {}", tool_name),
+            "data": {
+                "kind": "unknown_command",
+                "did_you_mean": did_you_mean
+            }
+        }
+    })
+}
+
 /// Convert MCP arguments (JSON object) to CLI arguments (Vec<String>)
 ///
 /// # Arguments
@@ -158,16 +561,22 @@ fn convert_mcp_arguments_to_cli_args(arguments: &Value) -> Vec<String> {
                     }
                 }
             } else {
-                // Positional argument
-                match value {
-                    Value::String(s) => {
-                        args.push(s.clone());
-                    }
-                    Value::Number(n) => {
-                        args.push(n.to_string());
-                    }
-                    _ => {
-                        args.push(value.to_string());
+                // Positional argument (an array fills a variadic positional)
+                let values = match value {
+                    Value::Array(items) => items.iter().collect(),
+                    _ => vec![value],
+                };
+                for value in values {
+                    match value {
+                        Value::String(s) => {
+                            args.push(s.clone());
+                        }
+                        Value::Number(n) => {
+                            args.push(n.to_string());
+                        }
+                        _ => {
+                            args.push(value.to_string());
+                        }
                     }
                 }
             }
@@ -201,145 +610,38 @@ where
         server.add_tool(&tool.name, &tool.description);
     }
 
-    // Store tools for tools/list response
-    let tools_json = serde_json::to_value(&tools).unwrap_or_else(|_| json!([]));
-
     // Print server ready message to stderr (stdout is for JSON-RPC)
     eprintln!("MCP server started. Listening on stdin...");
 
-    // Read from stdin, process JSON-RPC requests
     let stdin = std::io::stdin();
     let reader = BufReader::new(stdin.lock());
+    serve(reader, std::io::stdout(), &tools, &execute_tool);
+
+    Response::silent()
+}
 
+/// Process newline-delimited JSON-RPC requests from `reader` until EOF
+///
+/// Responses and notifications are written to `out`, one message per line.
+fn serve<R, W, F>(reader: R, mut out: W, tools: &[McpTool], execute_tool: &F)
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(&str, Vec<String>) -> Response,
+{
     for line in reader.lines() {
         match line {
             Ok(request_str) => {
+                if request_str.trim().is_empty() {
+                    continue;
+                }
                 // Parse and handle JSON-RPC request
-                if let Ok(request) = serde_json::from_str::<Value>(&request_str) {
-                    let method = request.get("method").and_then(|m| m.as_str());
-
-                    // Handle MCP protocol methods
-                    let result = match method {
-                        Some("initialize") => {
-                            json!({
-                                "protocolVersion": "2024-11-05",
-                                "capabilities": {
-                                    "tools": {},
-                                    "logging": {}
-                                },
-                                "serverInfo": {
-                                    "name": "sen-rs MCP server",
-                                    "version": "0.5.0"
-                                }
-                            })
-                        }
-                        Some("tools/list") => {
-                            json!({
-                                "tools": tools_json
-                            })
-                        }
-                        Some("tools/call") => {
-                            // Extract tool name and arguments from params
-                            let params = request.get("params");
-                            let tool_name = params
-                                .and_then(|p| p.get("name"))
-                                .and_then(|n| n.as_str())
-                                .unwrap_or("");
-
-                            let arguments = params
-                                .and_then(|p| p.get("arguments"))
-                                .cloned()
-                                .unwrap_or_else(|| json!({}));
-
-                            // Send notification: execution started
-                            send_notification(
-                                "notifications/message",
-                                json!({
-                                    "level": "info",
-                                    "logger": "sen-rs.mcp",
-                                    "data": format!("Executing tool: {}", tool_name)
-                                }),
-                            );
-
-                            // Convert MCP arguments to CLI args
-                            let cli_args = convert_mcp_arguments_to_cli_args(&arguments);
-
-                            // Execute the tool
-                            let tool_response = execute_tool(tool_name, cli_args);
-
-                            // Send notification: execution completed
-                            let notification_level = if tool_response.exit_code == 0 {
-                                "info"
-                            } else {
-                                "error"
-                            };
-                            send_notification(
-                                "notifications/message",
-                                json!({
-                                    "level": notification_level,
-                                    "logger": "sen-rs.mcp",
-                                    "data": format!(
-                                        "Tool {} completed with exit code {}",
-                                        tool_name,
-                                        tool_response.exit_code
-                                    )
-                                }),
-                            );
-
-                            // Convert Response to MCP result
-                            let mcp_result = match tool_response.output {
-                                Output::Text(text) => {
-                                    json!({
-                                        "content": [{
-                                            "type": "text",
-                                            "text": text
-                                        }],
-                                        "isError": tool_response.exit_code != 0
-                                    })
-                                }
-                                Output::Json(json_str) => {
-                                    json!({
-                                        "content": [{
-                                            "type": "text",
-                                            "text": json_str
-                                        }],
-                                        "isError": tool_response.exit_code != 0
-                                    })
-                                }
-                                Output::Silent => {
-                                    json!({
-                                        "content": [{
-                                            "type": "text",
-                                            "text": ""
-                                        }],
-                                        "isError": false
-                                    })
-                                }
-                            };
-
-                            mcp_result
-                        }
-                        _ => {
-                            json!({
-                                "status": "received",
-                                "method": method
-                            })
-                        }
-                    };
-
-                    let response = json!({
-                        "jsonrpc": "2.0",
-                        "id": request.get("id"),
-                        "result": result
-                    });
-
-                    // Write response to stdout
-                    if let Ok(response_str) = serde_json::to_string(&response) {
-                        println!("{}", response_str);
-                        std::io::stdout().flush().ok();
+                match serde_json::from_str::<Value>(&request_str) {
+                    Ok(request) => {
+                        let response = handle_request(&request, tools, execute_tool, &mut out);
+                        write_message(&mut out, &response);
                     }
-                } else {
-                    eprintln!("Failed to parse JSON-RPC request: {}", request_str);
+                    Err(e) => write_message(&mut out, &parse_error(None, &e)),
                 }
             }
             Err(e) => {
@@ -348,16 +650,256 @@ where
             }
         }
     }
+}
 
-    Response {
-        exit_code: 0,
-        output: Output::Silent,
-        agent_mode: false,
-        #[cfg(feature = "sensors")]
-        metadata: None,
+/// Run a single `tools/call` request and return its JSON-RPC response
+///
+/// Backs `--mcp-call <tool> '<json-args>'`: the call goes through the same
+/// validation and execution path as [`run_mcp_server`], so tool schemas can be
+/// tested without an MCP client. Notifications are written to stderr. The
+/// pretty-printed response is the output, with exit code 1 if it is a
+/// JSON-RPC error or the tool failed.
+pub fn call_mcp_tool<F>(
+    tools: &[McpTool],
+    tool_name: &str,
+    arguments: &str,
+    execute_tool: F,
+) -> Response
+where
+    F: Fn(&str, Vec<String>) -> Response,
+{
+    let id = json!(1);
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    let response = match serde_json::from_str::<Value>(arguments) {
+        Ok(arguments) => {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": tool_name, "arguments": arguments }
+            });
+            handle_request(&request, tools, &execute_tool, &mut std::io::stderr())
+        }
+        Err(e) => parse_error(Some(&id), &e),
+    };
+
+    let failed =
+        response.get("error").is_some() || response["result"]["isError"].as_bool().unwrap_or(false);
+    let output = serde_json::to_string_pretty(&response).unwrap_or_else(|_| "{}".to_string());
+    if failed {
+        Response::error(1, output)
+    } else {
+        Response::text(output)
     }
 }
 
+/// JSON-RPC "Parse error" response for input that is not valid JSON
+fn parse_error(id: Option<&Value>, error: &serde_json::Error) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": -32700,
+            "message": format!("Parse error: {}", error)
+        }
+    })
+}
+
+/// Build the JSON-RPC response for a single MCP request
+///
+/// `tools/call` for a tool that is not in `tools` returns a JSON-RPC error
+/// with [`UNKNOWN_TOOL_ERROR_CODE`] and `data` carrying the error `kind` and
+/// `did_you_mean` tool names, so agents can retry with a valid tool.
+/// Arguments that do not match the tool's input schema return
+/// [`INVALID_PARAMS_ERROR_CODE`] without running the tool. Progress
+/// notifications are written to `out`.
+fn handle_request<F, W>(request: &Value, tools: &[McpTool], execute_tool: &F, out: &mut W) -> Value
+where
+    F: Fn(&str, Vec<String>) -> Response,
+    W: Write,
+{
+    let method = request.get("method").and_then(|m| m.as_str());
+
+    // Handle MCP protocol methods
+    let result = match method {
+        Some("initialize") => {
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {
+                    "tools": {},
+                    "logging": {}
+                },
+                "serverInfo": {
+                    "name": "sen-rs MCP server",
+                    "version": "0.5.0"
+                }
+            })
+        }
+        Some("tools/list") => {
+            // Optional `params.tag` lists only the tools carrying that tag
+            let tag = request
+                .get("params")
+                .and_then(|p| p.get("tag"))
+                .and_then(|t| t.as_str());
+            let tools: Vec<&McpTool> = tools
+                .iter()
+                .filter(|tool| tag.is_none_or(|tag| tool.tags.iter().any(|t| t == tag)))
+                .collect();
+            json!({
+                "tools": tools
+            })
+        }
+        Some("tools/call") => {
+            // Extract tool name and arguments from params
+            let params = request.get("params");
+            let tool_name = params
+                .and_then(|p| p.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or("");
+
+            let arguments = params
+                .and_then(|p| p.get("arguments"))
+                .filter(|a| !a.is_null())
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+
+            let Some(tool) = tools.iter().find(|t| t.name == tool_name) else {
+                return unknown_tool_error(
+                    request.get("id"),
+                    tool_name,
+                    suggest_tools(tool_name, tools),
+                );
+            };
+
+            if let Err(errors) = validate_arguments(&tool.input_schema, &arguments) {
+                return json!({
+                    "jsonrpc": "2.0",
+                    "id": request.get("id"),
+                    "error": {
+                        "code": INVALID_PARAMS_ERROR_CODE,
+                        "message": format!("Invalid params: {}", errors.join("; ")),
+                        "data": {
+                            "kind": "invalid_params",
+                            "errors": errors
+                        }
+                    }
+                });
+            }
+
+            // Send notification: execution started
+            send_notification(
+                out,
+                "notifications/message",
+                json!({
+                    "level": "info",
+                    "logger": "sen-rs.mcp",
+                    "data": format!("Executing tool: {}", tool_name)
+                }),
+            );
+
+            // Convert MCP arguments to CLI args
+            let cli_args = convert_mcp_arguments_to_cli_args(&arguments);
+
+            // Execute the tool
+            let tool_response = execute_tool(tool_name, cli_args);
+
+            // Send notification: execution completed
+            let notification_level = if tool_response.exit_code == 0 {
+                "info"
+            } else {
+                "error"
+            };
+            send_notification(
+                out,
+                "notifications/message",
+                json!({
+                    "level": notification_level,
+                    "logger": "sen-rs.mcp",
+                    "data": format!(
+                        "Tool {} completed with exit code {}",
+                        tool_name,
+                        tool_response.exit_code
+                    )
+                }),
+            );
+
+            // A listed tool the router cannot resolve is still a protocol error
+            if let Some(error) = tool_response
+                .error
+                .as_ref()
+                .filter(|e| e.kind == "unknown_command")
+            {
+                let did_you_mean = error
+                    .did_you_mean
+                    .iter()
+                    .map(|name| name.replace(' ', ":"))
+                    .collect();
+                return unknown_tool_error(request.get("id"), tool_name, did_you_mean);
+            }
+
+            // Convert Response to MCP result
+            match tool_response.output {
+                Output::Text(text) => {
+                    json!({
+                        "content": [{
+                            "type": "text",
+                            "text": text
+                        }],
+                        "isError": tool_response.exit_code != 0
+                    })
+                }
+                Output::Json(json_str) => {
+                    json!({
+                        "content": [{
+                            "type": "text",
+                            "text": json_str
+                        }],
+                        "isError": tool_response.exit_code != 0
+                    })
+                }
+                Output::Binary(bytes) => {
+                    json!({
+                        "content": [{
+                            "type": "resource",
+                            "resource": {
+                                "uri": format!("sen://{}", tool_name),
+                                "mimeType": "application/octet-stream",
+                                "blob": crate::encode_base64(&bytes)
+                            }
+                        }],
+                        "isError": tool_response.exit_code != 0
+                    })
+                }
+                Output::Silent | Output::Redirect(_) => {
+                    json!({
+                        "content": [{
+                            "type": "text",
+                            "text": ""
+                        }],
+                        "isError": false
+                    })
+                }
+            }
+        }
+        _ => {
+            json!({
+                "status": "received",
+                "method": method
+            })
+        }
+    };
+
+    json!({
+        "jsonrpc": "2.0",
+        "id": request.get("id"),
+        "result": result
+    })
+}
+
 /// Generate MCP configuration JSON for a given client
 ///
 /// This function outputs the MCP server configuration JSON to stdout,
@@ -429,13 +971,7 @@ pub fn generate_mcp_config(client: &str, command_path: String, tools: Vec<McpToo
     // Output the JSON to stdout (so users can pipe it to a file if needed)
     let config_str = serde_json::to_string_pretty(&config).unwrap_or_else(|_| "{}".to_string());
 
-    Response {
-        exit_code: 0,
-        output: Output::Text(config_str),
-        agent_mode: false,
-        #[cfg(feature = "sensors")]
-        metadata: None,
-    }
+    Response::text(config_str)
 }
 
 #[cfg(test)]
@@ -489,6 +1025,139 @@ mod tests {
         assert_eq!(tool.input_schema["properties"]["name"]["type"], "string");
     }
 
+    #[test]
+    fn test_mcp_tool_description_includes_examples() {
+        let metadata = RouteMetadata {
+            handler_meta: Some(crate::HandlerMetadata {
+                desc: Some("Create a database"),
+                examples: vec!["myctl db create mydb", "myctl db create mydb --size 20GB"],
+                ..Default::default()
+            }),
+            description: None,
+            args_schema: None,
+        };
+
+        let tool = McpTool::from_route_metadata("db:create".to_string(), &metadata);
+
+        assert_eq!(
+            tool.description,
+            "Create a database\n\nExamples:\n  myctl db create mydb\n  myctl db create mydb --size 20GB"
+        );
+    }
+
+    #[test]
+    fn test_mcp_tool_converts_args_schema_constraints() {
+        let metadata = RouteMetadata {
+            handler_meta: None,
+            description: Some("Export data".to_string()),
+            args_schema: Some(json!({
+                "arguments": [
+                    { "name": "table", "type": "alloc::string::String", "required": true, "description": "Table" }
+                ],
+                "options": [
+                    { "name": "--json", "type": "bool", "required": false, "description": "JSON", "conflicts_with": ["--yaml"] },
+                    { "name": "--yaml", "type": "bool", "required": false, "description": "YAML", "conflicts_with": ["--json"] },
+                    { "name": "--output", "type": "PathBuf", "required": false, "description": "File", "requires": ["--format"] },
+                    { "name": "--format", "type": "String", "required": false, "description": "Format" }
+                ]
+            })),
+        };
+
+        let schema = McpTool::from_route_metadata("export".to_string(), &metadata).input_schema;
+
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["table"]));
+        assert_eq!(schema["properties"]["table"]["type"], "string");
+        assert_eq!(schema["properties"]["--json"]["type"], "boolean");
+        assert_eq!(schema["dependencies"], json!({ "--output": ["--format"] }));
+        assert_eq!(
+            schema["allOf"],
+            json!([{ "not": { "required": ["--json", "--yaml"] } }])
+        );
+    }
+
+    #[test]
+    fn test_variadic_positional_is_an_array() {
+        let metadata = RouteMetadata {
+            handler_meta: None,
+            description: None,
+            args_schema: Some(json!({
+                "arguments": [
+                    { "name": "script", "type": "String", "required": true, "description": "Script" },
+                    { "name": "args", "type": "String", "required": false, "description": "Script args", "variadic": true, "max_values": null }
+                ],
+                "options": []
+            })),
+        };
+
+        let schema = McpTool::from_route_metadata("run".to_string(), &metadata).input_schema;
+        assert_eq!(
+            schema["properties"]["args"],
+            json!({ "description": "Script args", "type": "array", "items": { "type": "string" } })
+        );
+
+        let args = super::convert_mcp_arguments_to_cli_args(&json!({
+            "args": ["--verbose", "in.txt"]
+        }));
+        assert_eq!(args, vec!["--verbose", "in.txt"]);
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_mcp_tool_from_clap_schema_has_enum_and_defaults() {
+        use crate::FromArgs;
+        use clap::{Parser, ValueEnum};
+
+        #[derive(Clone, Debug, ValueEnum)]
+        enum Format {
+            Json,
+            Yaml,
+            Table,
+        }
+
+        #[derive(Parser, Debug)]
+        struct ExportArgs {
+            /// Table to export
+            table: String,
+            /// Output format
+            #[arg(long, value_enum, default_value = "table")]
+            format: Format,
+            /// Maximum rows
+            #[arg(long, default_value_t = 100)]
+            limit: u32,
+        }
+
+        let metadata = RouteMetadata {
+            handler_meta: None,
+            description: Some("Export a table".to_string()),
+            args_schema: ExportArgs::cli_schema(),
+        };
+        let schema = McpTool::from_route_metadata("export".to_string(), &metadata).input_schema;
+
+        assert_eq!(schema["required"], json!(["table"]));
+        assert_eq!(schema["properties"]["table"]["type"], "string");
+        assert_eq!(
+            schema["properties"]["--format"],
+            json!({
+                "description": "Output format",
+                "type": "string",
+                "enum": ["json", "yaml", "table"],
+                "default": "table"
+            })
+        );
+        assert_eq!(schema["properties"]["--limit"]["type"], "integer");
+        assert_eq!(schema["properties"]["--limit"]["default"], 100);
+
+        assert!(
+            validate_arguments(&schema, &json!({ "table": "users", "--format": "yaml" })).is_ok()
+        );
+        assert_eq!(
+            validate_arguments(&schema, &json!({ "table": "users", "--format": "xml" }))
+                .unwrap_err(),
+            vec!["argument '--format' must be one of json, yaml, table"]
+        );
+    }
+
     #[test]
     fn test_mcp_tool_with_no_description() {
         let metadata = RouteMetadata {
@@ -510,6 +1179,7 @@ mod tests {
             name: "test-tool".to_string(),
             description: "Test tool description".to_string(),
             input_schema: json!({"type": "object"}),
+            tags: Vec::new(),
         }];
 
         let response = generate_mcp_config("claude", "/usr/bin/myctl".to_string(), tools);
@@ -593,4 +1263,385 @@ mod tests {
         assert!(args.contains(&"--port".to_string()));
         assert!(args.contains(&"8080".to_string()));
     }
+
+    fn call_router(tool_name: &str, tool_args: Vec<String>) -> Response {
+        async fn create(_state: crate::State<()>) -> crate::CliResult<String> {
+            Ok("created".to_string())
+        }
+
+        let router = crate::Router::new()
+            .route("db:create", create)
+            .with_state(());
+        let mut args = vec!["test".to_string()];
+        args.extend(tool_name.split(':').map(String::from));
+        args.extend(tool_args);
+
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(router.execute_with(&args))
+    }
+
+    /// Tools for `call_router`: `db:create` takes no arguments, `db:init`
+    /// has a required name and conflicting `--json`/`--yaml` flags
+    fn db_tools() -> Vec<McpTool> {
+        let create = RouteMetadata {
+            handler_meta: None,
+            description: Some("Create a database".to_string()),
+            args_schema: None,
+        };
+        let init = RouteMetadata {
+            handler_meta: None,
+            description: Some("Initialize a database".to_string()),
+            args_schema: Some(json!({
+                "arguments": [
+                    { "name": "name", "type": "String", "required": true, "description": "Name" }
+                ],
+                "options": [
+                    { "name": "--json", "type": "bool", "required": false, "description": "JSON", "conflicts_with": ["--yaml"] },
+                    { "name": "--yaml", "type": "bool", "required": false, "description": "YAML" }
+                ]
+            })),
+        };
+        vec![
+            McpTool::from_route_metadata("db:create".to_string(), &create),
+            McpTool::from_route_metadata("db:init".to_string(), &init),
+        ]
+    }
+
+    /// Run the server over an in-memory transport, returning the responses
+    /// (notifications are dropped)
+    fn serve_lines(requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let mut output = Vec::new();
+        serve(
+            std::io::Cursor::new(input),
+            &mut output,
+            &db_tools(),
+            &call_router,
+        );
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .filter(|message| message.get("id").is_some())
+            .collect()
+    }
+
+    #[test]
+    fn test_serve_unknown_tool_returns_method_not_found() {
+        let responses = serve_lines(&[json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "db:craete", "arguments": {} }
+        })]);
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["error"]["code"], -32601);
+        assert_eq!(
+            responses[0]["error"]["data"]["did_you_mean"],
+            json!(["db:create"])
+        );
+    }
+
+    #[test]
+    fn test_serve_invalid_params_are_rejected_before_running() {
+        let responses = serve_lines(&[
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": "db:init", "arguments": { "--json": true, "--yaml": true } }
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "db:init", "arguments": { "name": 3 } }
+            }),
+        ]);
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["error"]["code"], -32602);
+        assert_eq!(
+            responses[0]["error"]["data"]["errors"],
+            json!([
+                "missing required argument 'name'",
+                "arguments '--json', '--yaml' cannot be used together"
+            ])
+        );
+        assert_eq!(responses[1]["error"]["code"], -32602);
+        assert_eq!(
+            responses[1]["error"]["data"]["errors"],
+            json!(["argument 'name' must be of type string"])
+        );
+    }
+
+    #[test]
+    fn test_tools_list_filters_by_tag() {
+        let tool = |name: &str, tags: &[&str]| McpTool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        };
+        let tools = vec![
+            tool("db:create", &["database", "write"]),
+            tool("server:start", &["server"]),
+            tool("db:list", &["database"]),
+        ];
+        let list = |params: Value| {
+            let request =
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list", "params": params });
+            let response = handle_request(&request, &tools, &call_router, &mut std::io::sink());
+            response["result"]["tools"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            list(json!({ "tag": "database" })),
+            vec!["db:create", "db:list"]
+        );
+        assert_eq!(list(json!({})).len(), 3);
+
+        // Tags are only used for filtering; the wire format is unchanged
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" });
+        let response = handle_request(&request, &tools, &call_router, &mut std::io::sink());
+        assert!(response["result"]["tools"][0].get("tags").is_none());
+    }
+
+    #[test]
+    fn test_serve_reports_parse_errors_and_keeps_running() {
+        let input = "not json\n{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"tools/list\"}\n";
+        let mut output = Vec::new();
+        serve(
+            std::io::Cursor::new(input),
+            &mut output,
+            &db_tools(),
+            &call_router,
+        );
+
+        let messages: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(messages[0]["error"]["code"], -32700);
+        assert_eq!(messages[1]["id"], 3);
+        assert_eq!(messages[1]["result"]["tools"][0]["name"], "db:create");
+    }
+
+    #[test]
+    fn test_validate_arguments_checks_dependencies_and_unknown_keys() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "--output": { "type": "string" },
+                "--format": { "type": "string" },
+                "files": { "type": "array", "items": { "type": "string" }, "maxItems": 2 }
+            },
+            "required": [],
+            "dependencies": { "--output": ["--format"] }
+        });
+
+        assert!(validate_arguments(&schema, &json!({})).is_ok());
+        assert_eq!(
+            validate_arguments(
+                &schema,
+                &json!({ "--output": "out.txt", "--color": true, "files": ["a", "b", "c"] })
+            )
+            .unwrap_err(),
+            vec![
+                "unknown argument '--color'",
+                "argument 'files' accepts at most 2 values",
+                "argument '--output' requires '--format'",
+            ]
+        );
+        // A schema without properties accepts anything
+        assert!(validate_arguments(&json!({ "type": "object" }), &json!({ "x": 1 })).is_ok());
+    }
+
+    #[test]
+    fn test_tools_call_unknown_tool_returns_error_code() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": "db:craete", "arguments": {} }
+        });
+
+        let response = handle_request(&request, &db_tools(), &call_router, &mut std::io::sink());
+
+        assert_eq!(response["id"], 7);
+        assert!(response.get("result").is_none());
+        assert_eq!(response["error"]["code"], UNKNOWN_TOOL_ERROR_CODE);
+        assert_eq!(response["error"]["message"], "Unknown tool: db:craete");
+        assert_eq!(response["error"]["data"]["kind"], "unknown_command");
+        assert_eq!(
+            response["error"]["data"]["did_you_mean"],
+            json!(["db:create"])
+        );
+    }
+
+    #[test]
+    fn test_tools_call_known_tool_returns_result() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 8,
+            "method": "tools/call",
+            "params": { "name": "db:create", "arguments": {} }
+        });
+
+        let response = handle_request(&request, &db_tools(), &call_router, &mut std::io::sink());
+
+        assert!(response.get("error").is_none());
+        assert_eq!(response["result"]["content"][0]["text"], "created");
+        assert_eq!(response["result"]["isError"], false);
+    }
+
+    #[test]
+    fn test_tools_call_binary_output_is_a_blob_resource() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 9,
+            "method": "tools/call",
+            "params": { "name": "db:create", "arguments": {} }
+        });
+        let export = |_: &str, _: Vec<String>| -> Response {
+            let mut response = Response::silent();
+            response.output = Output::Binary(vec![0xff, 0x00, 0x80]);
+            response
+        };
+
+        let response = handle_request(&request, &db_tools(), &export, &mut std::io::sink());
+
+        let content = &response["result"]["content"][0];
+        assert_eq!(content["type"], "resource");
+        assert_eq!(content["resource"]["uri"], "sen://db:create");
+        assert_eq!(content["resource"]["blob"], "/wCA");
+        assert_eq!(response["result"]["isError"], false);
+    }
+
+    struct InitArgs(Vec<String>);
+
+    impl crate::FromArgs for InitArgs {
+        fn from_args(args: &[String]) -> Result<Self, crate::CliError> {
+            Ok(InitArgs(args.to_vec()))
+        }
+    }
+
+    fn mcp_call_router() -> crate::Router<()> {
+        async fn init(
+            crate::Args(InitArgs(args)): crate::Args<InitArgs>,
+        ) -> crate::CliResult<String> {
+            Ok(format!("init {}", args.join(" ")))
+        }
+
+        crate::Router::new()
+            .route_with_schema(
+                "db:init",
+                init,
+                json!({
+                    "arguments": [
+                        { "name": "name", "type": "String", "required": true, "description": "Name" }
+                    ],
+                    "options": [
+                        { "name": "--json", "type": "bool", "required": false, "description": "JSON" }
+                    ]
+                }),
+            )
+            .with_mcp()
+            .with_state(())
+    }
+
+    fn mcp_call(args: &[&str]) -> Response {
+        let args: Vec<String> = ["myctl", "--mcp-call"]
+            .iter()
+            .chain(args)
+            .map(|s| s.to_string())
+            .collect();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(mcp_call_router().execute_argv(&args))
+    }
+
+    #[test]
+    fn test_mcp_call_runs_one_tool_call() {
+        let response = mcp_call(&["db:init", r#"{"name": "main", "--json": true}"#]);
+
+        assert_eq!(response.exit_code, 0);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "content": [{ "type": "text", "text": "init --json main" }],
+                    "isError": false
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_mcp_call_does_not_expand_response_files() {
+        let response = mcp_call(&["db:init", r#"{"name": "@/etc/passwd"}"#]);
+
+        assert_eq!(response.exit_code, 0);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        assert_eq!(json["result"]["content"][0]["text"], "init @/etc/passwd");
+    }
+
+    #[test]
+    fn test_mcp_call_reports_errors_with_failing_exit_code() {
+        let response = mcp_call(&["db:init", "{}"]);
+        assert_eq!(response.exit_code, 1);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        assert_eq!(json["error"]["code"], INVALID_PARAMS_ERROR_CODE);
+
+        let response = mcp_call(&["db:init", "{not json"]);
+        assert_eq!(response.exit_code, 1);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        assert_eq!(json["error"]["code"], -32700);
+        assert_eq!(json["id"], 1);
+
+        let response = mcp_call(&["db:nope"]);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        assert_eq!(json["error"]["code"], UNKNOWN_TOOL_ERROR_CODE);
+    }
+
+    #[test]
+    fn test_describe_tool_returns_catalog() {
+        let response = mcp_call(&["__describe"]);
+        assert_eq!(response.exit_code, 0);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        let catalog: Value =
+            serde_json::from_str(json["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+
+        let help =
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(mcp_call_router().execute_with(&[
+                    "myctl".to_string(),
+                    "--help".to_string(),
+                    "--json".to_string(),
+                ]));
+        let expected: Value = serde_json::from_str(&help.output.to_string()).unwrap();
+        assert_eq!(catalog, expected);
+        assert!(catalog["commands"]["db:init"].is_object());
+        assert!(catalog["catalog_hash"].is_string());
+
+        // The tag filter applies as in `--help --json --tag`
+        let response = mcp_call(&["__describe", r#"{"--tag": "admin"}"#]);
+        let json: Value = serde_json::from_str(&response.output.to_string()).unwrap();
+        let catalog: Value =
+            serde_json::from_str(json["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(catalog["commands"], json!({}));
+    }
 }
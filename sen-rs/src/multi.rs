@@ -0,0 +1,229 @@
+//! Per-target results for commands that act on several targets.
+//!
+//! Commands like `server stop web01 web02 web03` should report which targets
+//! failed instead of aborting on the first error. Handlers record one outcome
+//! per target in a [`MultiResult`] and return it:
+//!
+//! - Text mode shows one line per target and a summary
+//! - Agent mode adds the outcomes as a `data` array of
+//!   `{ target, ok, message }` objects
+//! - The exit code follows the [`FailurePolicy`]
+//!
+//! # Example
+//!
+//! ```ignore
+//! use sen::{Args, CliResult, MultiResult, State};
+//!
+//! async fn stop(state: State<AppState>, Args(args): Args<StopArgs>) -> CliResult<MultiResult> {
+//!     let app = state.read().await;
+//!     let mut results = MultiResult::new();
+//!     for host in &args.hosts {
+//!         results.push(host, app.servers.stop(host).await.map(|_| "stopped"));
+//!     }
+//!     Ok(results)
+//! }
+//! ```
+
+use crate::{IntoResponse, Response};
+use std::fmt::Display;
+
+/// When a [`MultiResult`] counts as failed (exit code 1).
+///
+/// All targets succeeding (or there being none) is always exit code 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Fail if any target failed (default).
+    #[default]
+    AnyFailed,
+
+    /// Fail only if every target failed.
+    AllFailed,
+}
+
+/// Outcome of one target.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TargetOutcome {
+    /// Target name (host, file, ...)
+    pub target: String,
+
+    /// Whether the operation succeeded for this target
+    pub ok: bool,
+
+    /// Result or error message
+    pub message: String,
+}
+
+/// Outcomes of a command run against several targets.
+#[derive(Debug, Clone, Default)]
+pub struct MultiResult {
+    outcomes: Vec<TargetOutcome>,
+    policy: FailurePolicy,
+}
+
+impl MultiResult {
+    /// Create an empty result with the default [`FailurePolicy::AnyFailed`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy that decides the exit code.
+    pub fn policy(mut self, policy: FailurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Record a successful target.
+    pub fn ok(&mut self, target: impl Into<String>, message: impl Into<String>) -> &mut Self {
+        self.record(target.into(), true, message.into())
+    }
+
+    /// Record a failed target.
+    pub fn err(&mut self, target: impl Into<String>, message: impl Into<String>) -> &mut Self {
+        self.record(target.into(), false, message.into())
+    }
+
+    /// Record a target from the result of its operation.
+    pub fn push<T, E>(&mut self, target: impl Into<String>, result: Result<T, E>) -> &mut Self
+    where
+        T: Into<String>,
+        E: Display,
+    {
+        match result {
+            Ok(message) => self.ok(target, message),
+            Err(e) => self.err(target, e.to_string()),
+        }
+    }
+
+    /// Outcomes in the order they were recorded.
+    pub fn outcomes(&self) -> &[TargetOutcome] {
+        &self.outcomes
+    }
+
+    /// Number of failed targets.
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| !o.ok).count()
+    }
+
+    /// Exit code under the configured policy.
+    pub fn exit_code(&self) -> i32 {
+        let failed = self.failed();
+        let is_failure = match self.policy {
+            FailurePolicy::AnyFailed => failed > 0,
+            FailurePolicy::AllFailed => failed > 0 && failed == self.outcomes.len(),
+        };
+        i32::from(is_failure)
+    }
+
+    fn record(&mut self, target: String, ok: bool, message: String) -> &mut Self {
+        self.outcomes.push(TargetOutcome {
+            target,
+            ok,
+            message,
+        });
+        self
+    }
+
+    /// One line per target, then a summary line.
+    fn summary(&self) -> String {
+        let mut text = String::new();
+        for outcome in &self.outcomes {
+            let mark = if outcome.ok { "✓" } else { "✗" };
+            if outcome.message.is_empty() {
+                text.push_str(&format!("{} {}\n", mark, outcome.target));
+            } else {
+                text.push_str(&format!(
+                    "{} {}: {}\n",
+                    mark, outcome.target, outcome.message
+                ));
+            }
+        }
+
+        let total = self.outcomes.len();
+        match self.failed() {
+            0 => text.push_str(&format!("{} of {} succeeded", total, total)),
+            failed => text.push_str(&format!("{} of {} failed", failed, total)),
+        }
+        text
+    }
+}
+
+impl IntoResponse for MultiResult {
+    fn into_response(self) -> Response {
+        let exit_code = self.exit_code();
+        let data = serde_json::to_value(&self.outcomes).unwrap_or_default();
+        let mut response = if exit_code == 0 {
+            Response::text(self.summary())
+        } else {
+            Response::error(exit_code, self.summary())
+        };
+        response.data = Some(data);
+        response
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Output;
+
+    fn mixed() -> MultiResult {
+        let mut results = MultiResult::new();
+        results
+            .ok("web01", "stopped")
+            .push("web02", Err::<&str, _>("connection refused"))
+            .push("web03", Ok::<_, String>("stopped"));
+        results
+    }
+
+    #[test]
+    fn test_mixed_outcomes_summary_and_exit_code() {
+        let response = mixed().into_response();
+        assert_eq!(response.exit_code, 1);
+        match &response.output {
+            Output::Text(text) => assert_eq!(
+                text,
+                "✓ web01: stopped\n✗ web02: connection refused\n✓ web03: stopped\n1 of 3 failed"
+            ),
+            other => panic!("Expected text output, got {:?}", other),
+        }
+
+        let data = response.data.expect("outcomes in data");
+        assert_eq!(data.as_array().unwrap().len(), 3);
+        assert_eq!(
+            data[1],
+            serde_json::json!({ "target": "web02", "ok": false, "message": "connection refused" })
+        );
+    }
+
+    #[test]
+    fn test_failure_policy() {
+        assert_eq!(mixed().exit_code(), 1);
+        assert_eq!(mixed().policy(FailurePolicy::AllFailed).exit_code(), 0);
+
+        let mut all_failed = MultiResult::new().policy(FailurePolicy::AllFailed);
+        all_failed.err("web01", "down").err("web02", "down");
+        assert_eq!(all_failed.exit_code(), 1);
+
+        let mut all_ok = MultiResult::new();
+        all_ok.ok("web01", "");
+        let response = all_ok.into_response();
+        assert_eq!(response.exit_code, 0);
+        assert!(matches!(response.output, Output::Text(ref t) if t == "✓ web01\n1 of 1 succeeded"));
+
+        assert_eq!(MultiResult::new().exit_code(), 0);
+    }
+
+    #[cfg(feature = "sensors")]
+    #[test]
+    fn test_agent_json_includes_outcomes() {
+        let json: serde_json::Value =
+            serde_json::from_str(&mixed().into_response().to_agent_json()).unwrap();
+        assert_eq!(json["result"], "error");
+        assert_eq!(json["data"][0]["target"], "web01");
+        assert_eq!(json["data"][1]["ok"], false);
+    }
+}
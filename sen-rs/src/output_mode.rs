@@ -0,0 +1,90 @@
+//! Letting handlers see how their output will be rendered.
+//!
+//! The router renders a handler's output as text, or as JSON when the
+//! command runs in agent mode (`--agent-mode` on a router built with
+//! `Router::with_agent_mode()`). A handler that can produce either a table
+//! or structured data takes an `OutputMode` extractor and picks one itself.
+
+// ============================================================================
+// OutputMode Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static AGENT_MODE: bool;
+}
+
+/// Output mode extractor for handler functions.
+///
+/// `OutputMode::Agent` when the command was invoked with `--agent-mode`,
+/// `OutputMode::Text` otherwise.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{CliResult, OutputMode, State};
+///
+/// async fn list(state: State<AppState>, mode: OutputMode) -> CliResult<String> {
+///     let rows = state.read().await.db.list().await?;
+///     if mode.is_agent() {
+///         return Ok(serde_json::to_string(&rows)?);
+///     }
+///     Ok(render_table(&rows))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Human-readable text output
+    #[default]
+    Text,
+    /// Machine-readable JSON output (`--agent-mode`)
+    Agent,
+}
+
+impl OutputMode {
+    /// Get the output mode of the currently executing command.
+    pub fn current() -> Self {
+        if AGENT_MODE.try_with(|agent| *agent).unwrap_or(false) {
+            OutputMode::Agent
+        } else {
+            OutputMode::Text
+        }
+    }
+
+    /// Whether the output will be rendered as JSON for an agent.
+    pub fn is_agent(self) -> bool {
+        self == OutputMode::Agent
+    }
+}
+
+impl crate::FromContext for OutputMode {
+    fn from_context() -> Result<Self, crate::CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with `agent_mode` available to `OutputMode` extractors.
+pub(crate) async fn scope<F: std::future::Future>(agent_mode: bool, fut: F) -> F::Output {
+    AGENT_MODE.scope(agent_mode, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_mode_outside_router_is_text() {
+        assert_eq!(OutputMode::current(), OutputMode::Text);
+    }
+
+    #[tokio::test]
+    async fn test_output_mode_observes_scope() {
+        scope(true, async {
+            assert!(OutputMode::current().is_agent());
+        })
+        .await;
+    }
+}
@@ -0,0 +1,122 @@
+//! Platform-correct locations for config, cache, and data files.
+//!
+//! Use these instead of hardcoding `~/.myctl/...` so a CLI follows each
+//! platform's conventions:
+//!
+//! - [`config_dir`]: `~/.config/<app>` (Linux, or `$XDG_CONFIG_HOME`),
+//!   `~/Library/Application Support/<app>` (macOS), `%APPDATA%\<app>` (Windows)
+//! - [`cache_dir`]: `~/.cache/<app>` (Linux, or `$XDG_CACHE_HOME`),
+//!   `~/Library/Caches/<app>` (macOS), `%LOCALAPPDATA%\<app>` (Windows)
+//! - [`data_dir`]: `~/.local/share/<app>` (Linux, or `$XDG_DATA_HOME`),
+//!   `~/Library/Application Support/<app>` (macOS), `%APPDATA%\<app>` (Windows)
+//!
+//! When the platform directory cannot be determined (e.g. no home directory),
+//! a relative fallback (`.config/<app>`, `.cache/<app>`, `.local/share/<app>`)
+//! is returned, so callers always get a usable path.
+//!
+//! User-supplied paths (flags, config values) go through [`tilde_expand`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let config_path = sen::paths::config_dir("myctl").join("config.yaml");
+//! let custom = sen::paths::tilde_expand("~/work/myctl.yaml");
+//! ```
+
+use std::path::{Path, PathBuf};
+
+/// Config directory for `app` (e.g. `~/.config/myctl` on Linux).
+pub fn config_dir(app: &str) -> PathBuf {
+    app_dir(dirs::config_dir(), ".config", app)
+}
+
+/// Cache directory for `app` (e.g. `~/.cache/myctl` on Linux).
+pub fn cache_dir(app: &str) -> PathBuf {
+    app_dir(dirs::cache_dir(), ".cache", app)
+}
+
+/// Data directory for `app` (e.g. `~/.local/share/myctl` on Linux).
+pub fn data_dir(app: &str) -> PathBuf {
+    app_dir(dirs::data_dir(), ".local/share", app)
+}
+
+fn app_dir(base: Option<PathBuf>, fallback: &str, app: &str) -> PathBuf {
+    base.unwrap_or_else(|| PathBuf::from(fallback)).join(app)
+}
+
+/// Expand a leading `~` to the user's home directory.
+///
+/// Handles `~` alone and `~/...` (also `~\...` on Windows). Other paths,
+/// including `~user/...`, are returned unchanged, as is everything when the
+/// home directory is unknown.
+pub fn tilde_expand(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let rest = match path.to_str().and_then(|p| p.strip_prefix('~')) {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => &rest[1..],
+        _ => return path.to_path_buf(),
+    };
+
+    match dirs::home_dir() {
+        Some(home) if rest.is_empty() => home,
+        Some(home) => home.join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_dirs_end_with_app_name() {
+        for dir in [config_dir("myctl"), cache_dir("myctl"), data_dir("myctl")] {
+            assert!(dir.ends_with("myctl"), "{}", dir.display());
+        }
+    }
+
+    #[test]
+    fn test_app_dirs_use_platform_base() {
+        if let Some(base) = dirs::config_dir() {
+            assert_eq!(config_dir("myctl"), base.join("myctl"));
+        }
+        if let Some(base) = dirs::cache_dir() {
+            assert_eq!(cache_dir("myctl"), base.join("myctl"));
+        }
+        if let Some(base) = dirs::data_dir() {
+            assert_eq!(data_dir("myctl"), base.join("myctl"));
+        }
+    }
+
+    #[test]
+    fn test_app_dir_falls_back_to_relative_path() {
+        assert_eq!(
+            app_dir(None, ".config", "myctl"),
+            PathBuf::from(".config/myctl")
+        );
+    }
+
+    #[test]
+    fn test_tilde_expand_home() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        assert_eq!(tilde_expand("~"), home);
+        assert_eq!(
+            tilde_expand("~/.myctl/config.yaml"),
+            home.join(".myctl/config.yaml")
+        );
+    }
+
+    #[test]
+    fn test_tilde_expand_leaves_other_paths_alone() {
+        assert_eq!(tilde_expand("config.yaml"), PathBuf::from("config.yaml"));
+        assert_eq!(tilde_expand("/etc/myctl"), PathBuf::from("/etc/myctl"));
+        assert_eq!(tilde_expand("~alice/x"), PathBuf::from("~alice/x"));
+        assert_eq!(tilde_expand("a/~/b"), PathBuf::from("a/~/b"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Running external commands from handlers.
+//!
+//! Handlers that shell out (git, docker, `$EDITOR`, ...) should use this
+//! module instead of spawning processes by hand, so failures map to the same
+//! errors everywhere:
+//!
+//! - Binary not found → `UserError::MissingDependency`
+//! - Non-zero exit → `UserError::Generic` with the captured stderr
+//! - Timeout → `UserError::Generic`
+//! - Cancelled (see `Router::with_cancellation()`) → `UserError::Cancelled`
+//!
+//! The child is killed whenever it is abandoned (timeout, cancellation, or the
+//! handler future being dropped). Environment variables are set on the child
+//! only, so handlers never need to mutate the process-wide environment.
+
+use crate::{Cancel, CliError, CliResult, UserError};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Captured output of a successful command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Captured standard output (lossy UTF-8)
+    pub stdout: String,
+    /// Captured standard error (lossy UTF-8)
+    pub stderr: String,
+}
+
+/// Builder for an external command.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::process::Command;
+/// use std::time::Duration;
+///
+/// let output = Command::new("git")
+///     .args(["status", "--short"])
+///     .env("GIT_PAGER", "cat")
+///     .timeout(Duration::from_secs(10))
+///     .install_hint("Install git from https://git-scm.com")
+///     .run()
+///     .await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Command {
+    program: String,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, Option<OsString>)>,
+    current_dir: Option<PathBuf>,
+    timeout: Option<Duration>,
+    install_hint: Option<String>,
+}
+
+impl Command {
+    /// Create a command for `program`, looked up on `PATH`.
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            current_dir: None,
+            timeout: None,
+            install_hint: None,
+        }
+    }
+
+    /// Add an argument.
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add several arguments.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set an environment variable for the child only.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.envs.push((key.into(), Some(value.into())));
+        self
+    }
+
+    /// Remove an inherited environment variable from the child.
+    pub fn env_remove(mut self, key: impl Into<OsString>) -> Self {
+        self.envs.push((key.into(), None));
+        self
+    }
+
+    /// Set the child's working directory.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Kill the child and fail if it runs longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Hint shown in the `MissingDependency` error when the binary is not found.
+    pub fn install_hint(mut self, hint: impl Into<String>) -> Self {
+        self.install_hint = Some(hint.into());
+        self
+    }
+
+    /// Run the command to completion, capturing stdout and stderr.
+    pub async fn run(self) -> CliResult<CommandOutput> {
+        let mut cmd = tokio::process::Command::new(&self.program);
+        cmd.args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+
+        let child = cmd.spawn().map_err(|e| self.spawn_error(e))?;
+
+        let cancel = Cancel::current();
+        let deadline = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        // Dropping the wait future drops the child, which kills it
+        let output = tokio::select! {
+            result = child.wait_with_output() => result.map_err(|e| {
+                CliError::system(format!("Failed to wait for '{}': {}", self.program, e))
+            })?,
+            _ = cancel.cancelled() => return Err(CliError::User(UserError::Cancelled)),
+            _ = deadline => {
+                return Err(CliError::user(format!(
+                    "'{}' timed out after {:?}",
+                    self.program,
+                    self.timeout.unwrap_or_default()
+                )));
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+        if !output.status.success() {
+            let status = match output.status.code() {
+                Some(code) => format!("exit code {}", code),
+                None => "a signal".to_string(),
+            };
+            let mut message = format!("'{}' failed with {}", self.program, status);
+            let stderr = stderr.trim();
+            if !stderr.is_empty() {
+                message.push_str("\n\n");
+                message.push_str(stderr);
+            }
+            return Err(CliError::user(message));
+        }
+
+        Ok(CommandOutput { stdout, stderr })
+    }
+
+    fn spawn_error(&self, error: std::io::Error) -> CliError {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            CliError::User(UserError::MissingDependency {
+                tool: self.program.clone(),
+                install_hint: self.install_hint.clone().unwrap_or_else(|| {
+                    format!(
+                        "Install '{}' and make sure it is on your PATH",
+                        self.program
+                    )
+                }),
+            })
+        } else {
+            CliError::system(format!("Failed to run '{}': {}", self.program, error))
+        }
+    }
+}
+
+/// Run `program` with `args`, capturing its output.
+///
+/// Shorthand for `Command::new(program).args(args).run()`; use [`Command`]
+/// to set environment variables, a working directory, or a timeout.
+pub async fn run_command<I, S>(program: &str, args: I) -> CliResult<CommandOutput>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<OsString>,
+{
+    Command::new(program).args(args).run().await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::sync::watch;
+
+    #[tokio::test]
+    async fn test_run_command_captures_stdout() {
+        let output = run_command("echo", ["hello", "world"]).await.unwrap();
+        assert_eq!(output.stdout, "hello world\n");
+        assert_eq!(output.stderr, "");
+    }
+
+    #[tokio::test]
+    async fn test_env_is_set_on_child_only() {
+        let output = Command::new("sh")
+            .args(["-c", "echo \"$SEN_PROCESS_TEST\""])
+            .env("SEN_PROCESS_TEST", "from-child")
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(output.stdout, "from-child\n");
+        assert!(std::env::var_os("SEN_PROCESS_TEST").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_non_zero_exit_maps_to_user_error() {
+        let err = run_command("sh", ["-c", "echo boom >&2; exit 3"])
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        assert_eq!(
+            err.to_string(),
+            "Error: 'sh' failed with exit code 3\n\nboom"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_binary_maps_to_missing_dependency() {
+        let err = Command::new("sen-definitely-not-installed")
+            .install_hint("cargo install sen-definitely-not-installed")
+            .run()
+            .await
+            .unwrap_err();
+        match err {
+            CliError::User(UserError::MissingDependency { tool, install_hint }) => {
+                assert_eq!(tool, "sen-definitely-not-installed");
+                assert_eq!(install_hint, "cargo install sen-definitely-not-installed");
+            }
+            other => panic!("Expected MissingDependency, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_child() {
+        let err = Command::new("sleep")
+            .arg("5")
+            .timeout(Duration::from_millis(50))
+            .run()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'sleep' timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_kills_child() {
+        let (tx, rx) = watch::channel(false);
+        let result = crate::cancel::scope(rx, async {
+            let run = Command::new("sleep").arg("5").run();
+            tokio::pin!(run);
+            tokio::select! {
+                _ = &mut run => panic!("sleep should not finish first"),
+                _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+            }
+            tx.send(true).unwrap();
+            run.await
+        })
+        .await;
+
+        assert!(matches!(result, Err(CliError::User(UserError::Cancelled))));
+    }
+}
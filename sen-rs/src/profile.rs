@@ -0,0 +1,185 @@
+//! Global `--profile <name>` flag for tools that switch whole config sets
+//! (like `aws --profile prod`).
+//!
+//! When a router is built with `Router::with_profile_flag(env_var)`:
+//!
+//! - The router strips `--profile <name>` / `--profile=<name>` from the
+//!   arguments (like `--agent-mode`), so handlers parse their args as usual
+//! - The name is resolved from the flag, then `env_var`, then
+//!   [`DEFAULT_PROFILE`]
+//! - Handlers read the resolved name with the [`Profile`] extractor
+//! - `--profile` is listed under the options in `--help`
+//!
+//! Tools without a router can parse the flag themselves with
+//! [`ProfileFlag`]. With the `config` feature, `sen::config::Profiles` loads
+//! the named section of a config file.
+
+use crate::{CliError, FromGlobalArgs, UserError};
+
+/// The global flag recognized by `Router::with_profile_flag`.
+pub const PROFILE_FLAG: &str = "--profile";
+
+/// Profile used when neither `--profile` nor the environment names one.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The global `--profile <name>` flag.
+///
+/// Parse it with [`FromGlobalArgs`] before routing; `--profile prod` and
+/// `--profile=prod` are both accepted and stripped from the arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileFlag(pub Option<String>);
+
+impl ProfileFlag {
+    /// Resolve the profile name: the flag, then the `env_var` environment
+    /// variable, then [`DEFAULT_PROFILE`].
+    pub fn resolve(&self, env_var: &str) -> String {
+        self.resolve_with(env_var, |name| std::env::var(name).ok())
+    }
+
+    /// Like [`ProfileFlag::resolve`], but looks `env_var` up with `lookup`
+    /// instead of the process environment.
+    pub fn resolve_with(&self, env_var: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+        self.0
+            .clone()
+            .or_else(|| lookup(env_var).filter(|name| !name.is_empty()))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+}
+
+impl FromGlobalArgs for ProfileFlag {
+    fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
+        let mut profile = None;
+        let mut remaining = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if arg == PROFILE_FLAG {
+                let name = iter.next().ok_or_else(|| {
+                    CliError::User(UserError::InvalidArgument {
+                        arg: PROFILE_FLAG.to_string(),
+                        reason: "Expected a profile name after --profile".to_string(),
+                    })
+                })?;
+                profile = Some(name.clone());
+            } else if let Some(name) = arg.strip_prefix("--profile=") {
+                profile = Some(name.to_string());
+            } else {
+                remaining.push(arg.clone());
+            }
+        }
+
+        Ok((Self(profile), remaining))
+    }
+}
+
+// ============================================================================
+// Profile Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static PROFILE: String;
+}
+
+/// Profile extractor for handler functions.
+///
+/// Holds the profile resolved by a router built with
+/// `Router::with_profile_flag()`, or [`DEFAULT_PROFILE`] otherwise.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{CliResult, Profile};
+/// use sen::config::Profiles;
+///
+/// async fn deploy(Profile(profile): Profile) -> CliResult<String> {
+///     let config: ApiConfig = Profiles::load("myctl.toml", &profile)?;
+///     Ok(format!("Deploying to {}", config.endpoint))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile(pub String);
+
+impl Profile {
+    /// Get the profile of the currently executing command.
+    pub fn current() -> Self {
+        Profile(
+            PROFILE
+                .try_with(Clone::clone)
+                .unwrap_or_else(|_| DEFAULT_PROFILE.to_string()),
+        )
+    }
+}
+
+impl crate::FromContext for Profile {
+    fn from_context() -> Result<Self, CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with `profile` available to `Profile` extractors.
+pub(crate) async fn scope<F: std::future::Future>(profile: String, fut: F) -> F::Output {
+    PROFILE.scope(profile, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_profile_flag_parsing_and_resolution() {
+        let (flag, rest) =
+            ProfileFlag::from_global_args(&args(&["--profile", "prod", "deploy"])).unwrap();
+        assert_eq!(flag, ProfileFlag(Some("prod".to_string())));
+        assert_eq!(rest, args(&["deploy"]));
+
+        let (flag, rest) =
+            ProfileFlag::from_global_args(&args(&["deploy", "--profile=staging"])).unwrap();
+        assert_eq!(flag.resolve_with("MYCTL_PROFILE", |_| None), "staging");
+        assert_eq!(rest, args(&["deploy"]));
+
+        let (flag, _) = ProfileFlag::from_global_args(&args(&["deploy"])).unwrap();
+        assert_eq!(
+            flag.resolve_with("MYCTL_PROFILE", |_| None),
+            DEFAULT_PROFILE
+        );
+
+        assert!(ProfileFlag::from_global_args(&args(&["--profile"])).is_err());
+    }
+
+    #[test]
+    fn test_environment_is_consulted_only_without_flag() {
+        let env = |name: &str| (name == "MYCTL_PROFILE").then(|| "prod".to_string());
+
+        assert_eq!(ProfileFlag(None).resolve_with("MYCTL_PROFILE", env), "prod");
+        assert_eq!(
+            ProfileFlag(None).resolve_with("OTHER_PROFILE", env),
+            DEFAULT_PROFILE
+        );
+        let explicit = ProfileFlag(Some("staging".to_string()));
+        assert_eq!(explicit.resolve_with("MYCTL_PROFILE", env), "staging");
+
+        // An empty variable counts as unset
+        let empty = |_: &str| Some(String::new());
+        assert_eq!(
+            ProfileFlag(None).resolve_with("MYCTL_PROFILE", empty),
+            DEFAULT_PROFILE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_profile_observes_scope() {
+        assert_eq!(Profile::current(), Profile(DEFAULT_PROFILE.to_string()));
+        scope("prod".to_string(), async {
+            assert_eq!(Profile::current(), Profile("prod".to_string()));
+        })
+        .await;
+    }
+}
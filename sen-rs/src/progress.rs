@@ -0,0 +1,165 @@
+//! Progress reporting for long-running handlers.
+//!
+//! Handlers that take a `Progress` extractor can report how far along they are
+//! without producing streaming output. The router drains the events while the
+//! handler runs and renders them:
+//!
+//! - `Router::with_writer(w)`: one line per event to `w` (NDJSON in agent mode)
+//! - Interactive terminal: a single updating status line on stderr
+//! - Agent mode: one JSON object per line (NDJSON) on stderr
+//! - Otherwise: events are discarded
+//!
+//! stdout stays reserved for the handler's final `Response`.
+
+use tokio::sync::mpsc;
+
+// ============================================================================
+// Progress Event
+// ============================================================================
+
+/// A single progress update emitted by a handler.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ProgressEvent {
+    /// Completion fraction in the range `0.0..=1.0`
+    pub fraction: f64,
+
+    /// Human-readable status message
+    pub message: String,
+}
+
+impl ProgressEvent {
+    /// Render this event as a single NDJSON line for agent mode.
+    pub fn to_ndjson(&self) -> String {
+        serde_json::json!({
+            "type": "progress",
+            "fraction": self.fraction,
+            "message": self.message,
+        })
+        .to_string()
+    }
+
+    /// Render this event as a terminal status line.
+    pub fn to_status_line(&self) -> String {
+        format!(
+            "[{:>3}%] {}",
+            (self.fraction * 100.0).round() as u32,
+            self.message
+        )
+    }
+}
+
+// ============================================================================
+// Progress Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static PROGRESS_SINK: mpsc::UnboundedSender<ProgressEvent>;
+}
+
+/// Progress extractor for handler functions.
+///
+/// Similar to `Args<T>` and `State<S>`, this can be used in handler signatures
+/// to report progress back to the router while the handler runs.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, CliResult, Progress, State};
+///
+/// async fn deploy(
+///     state: State<AppState>,
+///     progress: Progress,
+///     Args(args): Args<DeployArgs>,
+/// ) -> CliResult<String> {
+///     progress.set(0.0, "uploading");
+///     upload(&args).await?;
+///     progress.set(0.5, "halfway");
+///     restart(&args).await?;
+///     progress.set(1.0, "done");
+///     Ok("Deployed".to_string())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Progress {
+    tx: Option<mpsc::UnboundedSender<ProgressEvent>>,
+}
+
+impl Progress {
+    /// Get the progress reporter for the currently executing command.
+    ///
+    /// Outside of router execution this returns a reporter that discards events.
+    pub fn current() -> Self {
+        Self {
+            tx: PROGRESS_SINK.try_with(|tx| tx.clone()).ok(),
+        }
+    }
+
+    /// Report progress as a fraction (clamped to `0.0..=1.0`) with a message.
+    pub fn set(&self, fraction: f64, message: impl Into<String>) {
+        if let Some(ref tx) = self.tx {
+            let _ = tx.send(ProgressEvent {
+                fraction: fraction.clamp(0.0, 1.0),
+                message: message.into(),
+            });
+        }
+    }
+}
+
+impl crate::FromContext for Progress {
+    fn from_context() -> Result<Self, crate::CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with progress events routed to `tx`.
+pub(crate) async fn scope<F: std::future::Future>(
+    tx: mpsc::UnboundedSender<ProgressEvent>,
+    fut: F,
+) -> F::Output {
+    PROGRESS_SINK.scope(tx, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_outside_router_is_noop() {
+        let progress = Progress::current();
+        assert!(progress.tx.is_none());
+        progress.set(0.5, "ignored");
+    }
+
+    #[tokio::test]
+    async fn test_progress_clamps_fraction() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        scope(tx, async {
+            let progress = Progress::current();
+            progress.set(1.5, "over");
+            progress.set(-1.0, "under");
+        })
+        .await;
+
+        assert_eq!(rx.recv().await.unwrap().fraction, 1.0);
+        assert_eq!(rx.recv().await.unwrap().fraction, 0.0);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[test]
+    fn test_progress_event_rendering() {
+        let event = ProgressEvent {
+            fraction: 0.5,
+            message: "halfway".to_string(),
+        };
+        assert_eq!(event.to_status_line(), "[ 50%] halfway");
+
+        let json: serde_json::Value = serde_json::from_str(&event.to_ndjson()).unwrap();
+        assert_eq!(json["type"], "progress");
+        assert_eq!(json["fraction"], 0.5);
+        assert_eq!(json["message"], "halfway");
+    }
+}
@@ -0,0 +1,180 @@
+//! Swapping native routes at runtime.
+//!
+//! Plugins hot-reload through the plugin registry; `ReloadableRouter` gives
+//! native commands the same workflow (e.g. handlers from a rebuilt dylib, or
+//! a REPL `:reload` command). Every call runs against a snapshot of the
+//! current router:
+//!
+//! - `swap_routes` never waits for running commands
+//! - A running command finishes on the routes it started with
+//! - Calls that start after the swap see the new routes
+
+use crate::{Response, Router};
+use std::sync::{Arc, RwLock};
+
+/// Router whose routes can be replaced atomically while it is in use.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{ReloadableRouter, Router};
+///
+/// let router = ReloadableRouter::new(
+///     Router::new()
+///         .route("status", handlers::status)
+///         .with_agent_mode()
+///         .with_state(state.clone()),
+/// );
+///
+/// // Later, e.g. after rebuilding a dylib
+/// router.swap_routes(
+///     Router::new()
+///         .route("status", reloaded::status)
+///         .route("deploy", reloaded::deploy)
+///         .with_state(state),
+/// );
+///
+/// let response = router.execute_with(&args).await;
+/// ```
+pub struct ReloadableRouter {
+    current: RwLock<Arc<Router<()>>>,
+}
+
+impl ReloadableRouter {
+    /// Wrap `router`; its configuration is kept across swaps.
+    pub fn new(router: Router<()>) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(router)),
+        }
+    }
+
+    /// Snapshot of the current router.
+    pub fn router(&self) -> Arc<Router<()>> {
+        // A poisoned lock only means a swap panicked; the old router is intact
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the routes with those registered on `routes`.
+    ///
+    /// Only the routes and their metadata are taken from `routes`; settings
+    /// such as agent mode, MCP, timeouts, and the writer stay as configured
+    /// on the wrapped router. Returns the previous router, which running
+    /// commands keep using until they finish.
+    pub fn swap_routes(&self, routes: Router<()>) -> Arc<Router<()>> {
+        let next = Arc::new(with_routes(&self.router(), routes));
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, next)
+    }
+
+    /// Execute a command from `std::env::args()` on the current routes.
+    pub async fn execute(&self) -> Response {
+        self.router().execute().await
+    }
+
+    /// Execute a command with custom arguments on the current routes.
+    pub async fn execute_with(&self, args: &[String]) -> Response {
+        self.router().execute_with(args).await
+    }
+}
+
+/// Copy of `config` with the routes of `routes`.
+fn with_routes(config: &Router<()>, routes: Router<()>) -> Router<()> {
+    Router {
+        routes: routes.routes,
+        route_metadata: routes.route_metadata,
+        group_descriptions: routes.group_descriptions,
+        scoped_args: routes.scoped_args,
+        completers: routes.completers,
+        aliases: routes.aliases,
+        config: config.config.clone(),
+        stdin_state: config.stdin_state.clone(),
+        _marker: std::marker::PhantomData,
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CliResult, Output, State};
+    use std::time::Duration;
+    use tokio::sync::{Notify, RwLock as AsyncRwLock};
+
+    fn args(command: &str) -> Vec<String> {
+        vec!["test".to_string(), command.to_string()]
+    }
+
+    fn text(response: Response) -> String {
+        match response.output {
+            Output::Text(s) => s,
+            other => panic!("Expected text output, got {:?}", other),
+        }
+    }
+
+    #[derive(Clone)]
+    struct AppState {
+        started: Arc<Notify>,
+        gate: Arc<AsyncRwLock<()>>,
+    }
+
+    /// Holds a read lock on `gate` until the test releases its write lock
+    async fn slow(state: State<AppState>) -> CliResult<String> {
+        let app = state.read().await.clone();
+        app.started.notify_one();
+        let _guard = app.gate.read().await;
+        Ok("slow v1".to_string())
+    }
+
+    async fn fast(_state: State<AppState>) -> CliResult<String> {
+        Ok("fast v2".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_swap_routes_while_command_is_running() {
+        let state = AppState {
+            started: Arc::new(Notify::new()),
+            gate: Arc::new(AsyncRwLock::new(())),
+        };
+        let release = state.gate.clone().write_owned().await;
+
+        let router = ReloadableRouter::new(
+            Router::new()
+                .route("slow", slow)
+                .with_agent_mode()
+                .with_state(state.clone()),
+        );
+
+        let slow_args = args("slow");
+        let running = router.execute_with(&slow_args);
+        let reload = async {
+            state.started.notified().await;
+
+            let previous = router.swap_routes(Router::new().route("fast", fast).with_state(state));
+            assert!(previous.route_metadata.contains_key("slow"));
+
+            let response = router.execute_with(&args("fast")).await;
+            assert_eq!(text(response), "fast v2");
+
+            // The old route is gone for new calls
+            let response = router.execute_with(&args("slow")).await;
+            assert_eq!(response.exit_code, 1);
+            // Settings of the wrapped router are kept
+            assert!(router.router().config.agent_mode_enabled);
+
+            drop(release);
+        };
+
+        let (response, ()) = tokio::time::timeout(Duration::from_secs(5), async {
+            tokio::join!(running, reload)
+        })
+        .await
+        .expect("swap_routes deadlocked with a running command");
+        assert_eq!(text(response), "slow v1");
+    }
+}
@@ -0,0 +1,146 @@
+//! Options shared by every command under one namespace.
+//!
+//! Global flags (`FromGlobalArgs`) apply to the whole CLI and per-command
+//! flags (`FromArgs`) to a single handler. Scoped options sit in between:
+//! register them with `Router::nest_with_args` and the router parses them
+//! for every command under that prefix, before the handler sees its args.
+//!
+//! - `myctl db create users --namespace prod` gives `db create` the parsed
+//!   options through the `Scoped<T>` extractor, and `users` as its args
+//! - `myctl server start --namespace prod` leaves `--namespace` untouched,
+//!   so it reaches the `server start` handler like any other argument
+//!
+//! When namespaces are nested, each level parses its own options, outermost
+//! first.
+
+use crate::{CliError, FromGlobalArgs};
+use std::any::Any;
+use std::sync::Arc;
+
+/// Parsed options of one namespace.
+pub(crate) type ScopedValue = Arc<dyn Any + Send + Sync>;
+
+/// Parser registered for a namespace; returns the options and remaining args.
+pub(crate) type ScopedParser =
+    Arc<dyn Fn(&[String]) -> Result<(ScopedValue, Vec<String>), CliError> + Send + Sync>;
+
+/// Build the parser for options of type `T`.
+pub(crate) fn parser<T>() -> ScopedParser
+where
+    T: FromGlobalArgs + Send + Sync + 'static,
+{
+    Arc::new(|args| {
+        let (options, remaining) = T::from_global_args(args)?;
+        Ok((Arc::new(options) as ScopedValue, remaining))
+    })
+}
+
+// ============================================================================
+// Scoped Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static SCOPED_OPTIONS: Vec<ScopedValue>;
+}
+
+/// Namespace options extractor for handler functions.
+///
+/// Receives the options registered with `Router::nest_with_args` for the
+/// namespace the command lives in.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, CliError, CliResult, FromGlobalArgs, Router, Scoped, State};
+///
+/// #[derive(Clone)]
+/// struct DbOpts {
+///     namespace: String,
+/// }
+///
+/// impl FromGlobalArgs for DbOpts {
+///     fn from_global_args(args: &[String]) -> Result<(Self, Vec<String>), CliError> {
+///         // Strip `--namespace <name>` and return the rest
+///         // ...
+///     }
+/// }
+///
+/// async fn create(
+///     state: State<AppState>,
+///     Scoped(db): Scoped<DbOpts>,
+///     Args(args): Args<CreateArgs>,
+/// ) -> CliResult<String> {
+///     Ok(format!("Created {} in {}", args.name, db.namespace))
+/// }
+///
+/// let app = Router::new()
+///     .nest_with_args::<DbOpts>("db", db_router)
+///     .nest("server", server_router)
+///     .with_state(state);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scoped<T>(pub T);
+
+impl<T: Clone + 'static> Scoped<T> {
+    /// Get the options of type `T` for the currently executing command.
+    ///
+    /// Returns `None` when the command is not under a namespace registered
+    /// with options of this type. The innermost matching namespace wins.
+    pub fn current() -> Option<Self> {
+        SCOPED_OPTIONS
+            .try_with(|options| {
+                options
+                    .iter()
+                    .rev()
+                    .find_map(|value| value.downcast_ref::<T>().cloned())
+            })
+            .ok()
+            .flatten()
+            .map(Scoped)
+    }
+}
+
+impl<T: Clone + Send + 'static> crate::FromContext for Scoped<T> {
+    fn from_context() -> Result<Self, crate::CliError> {
+        Self::current().ok_or_else(|| {
+            crate::CliError::system(format!(
+                "No scoped options of type {} for this command (register them with nest_with_args)",
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+}
+
+/// Run `fut` with `options` available to `Scoped` extractors.
+pub(crate) async fn scope<F: std::future::Future>(options: Vec<ScopedValue>, fut: F) -> F::Output {
+    SCOPED_OPTIONS.scope(options, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoped_outside_router_is_none() {
+        assert!(Scoped::<String>::current().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scoped_innermost_wins() {
+        let options: Vec<ScopedValue> = vec![
+            Arc::new("outer".to_string()),
+            Arc::new(7u32),
+            Arc::new("inner".to_string()),
+        ];
+        scope(options, async {
+            assert_eq!(Scoped::<String>::current().unwrap().0, "inner");
+            assert_eq!(Scoped::<u32>::current().unwrap().0, 7);
+            assert!(Scoped::<bool>::current().is_none());
+        })
+        .await;
+    }
+}
@@ -0,0 +1,110 @@
+//! Streamed output for handlers.
+//!
+//! Most handlers return their whole output in the final `Response`. Handlers
+//! that produce output incrementally (e.g. plugin `Emit` effects) write lines
+//! through an `Emitter` instead, which goes to the router's writer:
+//!
+//! - Default: stdout, line by line
+//! - `Router::with_writer(w)`: the given writer (e.g. a buffer in tests)
+//!
+//! The same writer also receives rendered progress events when set.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Shared writer the router emits streamed output and progress to.
+pub type SharedWriter = Arc<Mutex<dyn Write + Send>>;
+
+tokio::task_local! {
+    static STREAM_WRITER: SharedWriter;
+}
+
+/// Write a line to `writer`, flushing so output appears immediately.
+pub(crate) fn write_line(writer: &SharedWriter, line: &str) {
+    // A poisoned lock only means another writer panicked mid-line
+    let mut w = writer.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = writeln!(w, "{}", line);
+    let _ = w.flush();
+}
+
+/// Streamed output handle for the currently executing command.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::Emitter;
+///
+/// let emitter = Emitter::current();
+/// for chunk in chunks {
+///     emitter.emit(&chunk);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Emitter {
+    writer: Option<SharedWriter>,
+}
+
+impl std::fmt::Debug for Emitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Emitter")
+            .field("custom_writer", &self.writer.is_some())
+            .finish()
+    }
+}
+
+impl Emitter {
+    /// Get the emitter for the currently executing command.
+    ///
+    /// Outside of router execution, or when the router has no writer, lines
+    /// go to stdout.
+    pub fn current() -> Self {
+        Self {
+            writer: STREAM_WRITER.try_with(|w| w.clone()).ok(),
+        }
+    }
+
+    /// Emit one line of output immediately.
+    pub fn emit(&self, line: &str) {
+        match self.writer {
+            Some(ref writer) => write_line(writer, line),
+            None => {
+                let mut stdout = std::io::stdout().lock();
+                let _ = writeln!(stdout, "{}", line);
+                let _ = stdout.flush();
+            }
+        }
+    }
+}
+
+/// Run `fut` with streamed output routed to `writer`.
+pub(crate) async fn scope<F: std::future::Future>(writer: SharedWriter, fut: F) -> F::Output {
+    STREAM_WRITER.scope(writer, fut).await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_emitter_writes_to_scoped_writer() {
+        let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+        scope(buffer.clone(), async {
+            let emitter = Emitter::current();
+            emitter.emit("first");
+            emitter.emit("second");
+        })
+        .await;
+
+        let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        assert_eq!(output, "first\nsecond\n");
+    }
+
+    #[test]
+    fn test_emitter_outside_router_uses_stdout() {
+        assert!(Emitter::current().writer.is_none());
+    }
+}
@@ -0,0 +1,298 @@
+//! Rendering structured output through a user-supplied template (opt-in).
+//!
+//! With `Router::with_template_flag()`, `--template '<template>'` renders a
+//! command's structured result (`Response::with_data` or `Output::Json`)
+//! instead of its normal output, in the spirit of `kubectl -o jsonpath`:
+//!
+//! ```text
+//! $ myctl db list --template '{{name}} ({{engine}})'
+//! users (postgres)
+//! orders (mysql)
+//! ```
+//!
+//! - `{{field}}` inserts a field; `{{a.b}}` and `{{items.0}}` walk into
+//!   objects and arrays; `{{this}}` is the whole value
+//! - An array result renders the template once per element, one per line
+//! - Strings are inserted as-is, `null` as nothing, objects and arrays as JSON
+//! - Text-only output, failures, and agent mode are left unchanged
+//! - An unclosed `{{` or a missing field is a `UserError::InvalidArgument`;
+//!   the template is parsed before the command runs
+//! - Arguments after `--` are left to the handler
+
+use crate::{CliError, Output, Response, UserError};
+use serde_json::Value;
+
+/// Name of the global flag taking the template.
+pub(crate) const TEMPLATE_FLAG: &str = "--template";
+
+/// A template parsed from `--template`.
+#[derive(Debug)]
+pub(crate) struct Template {
+    parts: Vec<Part>,
+}
+
+/// One piece of a parsed template.
+#[derive(Debug)]
+enum Part {
+    Literal(String),
+    /// Path segments, empty for `{{this}}`
+    Field(Vec<String>),
+}
+
+/// Remove `--template <t>` / `--template=<t>` from `args`, returning the
+/// parsed template if it was given.
+///
+/// Arguments after `--` are left alone, so handlers can still take a
+/// literal `--template`.
+pub(crate) fn take_flag(args: Vec<String>) -> Result<(Option<Template>, Vec<String>), CliError> {
+    let mut template = None;
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            remaining.push(arg);
+            remaining.extend(iter);
+            break;
+        } else if arg == TEMPLATE_FLAG {
+            let value = iter.next().ok_or_else(|| {
+                invalid("a template is required, e.g. --template '{{name}}'".to_string())
+            })?;
+            template = Some(Template::parse(&value)?);
+        } else if let Some(value) = arg.strip_prefix("--template=") {
+            template = Some(Template::parse(value)?);
+        } else {
+            remaining.push(arg);
+        }
+    }
+    Ok((template, remaining))
+}
+
+impl Template {
+    /// Parse `template`; an unclosed or empty `{{` is an error.
+    pub(crate) fn parse(template: &str) -> Result<Self, CliError> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| invalid(format!("unclosed '{{{{' in template '{}'", template)))?;
+            let path = after[..end].trim();
+            if path.is_empty() {
+                return Err(invalid(format!(
+                    "empty '{{{{}}}}' in template '{}'",
+                    template
+                )));
+            }
+            let segments = if path == "this" || path == "." {
+                Vec::new()
+            } else {
+                path.split('.').map(String::from).collect()
+            };
+            parts.push(Part::Field(segments));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Render a successful response's structured result.
+    pub(crate) fn apply(&self, mut response: Response) -> Result<Response, CliError> {
+        if response.exit_code != 0 {
+            return Ok(response);
+        }
+        let value = match (&response.data, &response.output) {
+            (Some(data), _) => data.clone(),
+            (None, Output::Json(json)) => match serde_json::from_str(json) {
+                Ok(value) => value,
+                Err(_) => return Ok(response),
+            },
+            _ => return Ok(response),
+        };
+
+        response.output = Output::Text(self.render(&value)?);
+        Ok(response)
+    }
+
+    /// Render against `value`, once per element if it is an array.
+    pub(crate) fn render(&self, value: &Value) -> Result<String, CliError> {
+        match value {
+            Value::Array(items) => {
+                let lines = items
+                    .iter()
+                    .map(|item| render_parts(&self.parts, item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(lines.join("\n"))
+            }
+            _ => render_parts(&self.parts, value),
+        }
+    }
+}
+
+fn render_parts(parts: &[Part], value: &Value) -> Result<String, CliError> {
+    let mut out = String::new();
+    for part in parts {
+        match part {
+            Part::Literal(text) => out.push_str(text),
+            Part::Field(path) => {
+                let field = lookup(value, path).ok_or_else(|| {
+                    invalid(format!("field '{}' not found in output", path.join(".")))
+                })?;
+                match field {
+                    Value::Null => {}
+                    Value::String(s) => out.push_str(s),
+                    other => out.push_str(&other.to_string()),
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn lookup<'v>(value: &'v Value, path: &[String]) -> Option<&'v Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+fn invalid(reason: String) -> CliError {
+    CliError::User(UserError::InvalidArgument {
+        arg: TEMPLATE_FLAG.to_string(),
+        reason,
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, value: &Value) -> Result<String, CliError> {
+        Template::parse(template)?.render(value)
+    }
+
+    fn is_invalid_argument(result: Result<String, CliError>) -> bool {
+        matches!(
+            result,
+            Err(CliError::User(UserError::InvalidArgument { .. }))
+        )
+    }
+
+    #[test]
+    fn test_render_array_of_objects() {
+        let value = json!([
+            { "name": "users", "engine": "postgres", "size": { "gb": 10 } },
+            { "name": "orders", "engine": "mysql", "size": { "gb": 2.5 } },
+        ]);
+        let rendered = render("{{name}} ({{ engine }}) {{size.gb}}GB", &value).unwrap();
+        assert_eq!(rendered, "users (postgres) 10GB\norders (mysql) 2.5GB");
+    }
+
+    #[test]
+    fn test_render_scalars_and_indexes() {
+        let value = json!({ "tags": ["a", "b"], "owner": null, "ok": true });
+        assert_eq!(
+            render("{{tags.1}}|{{owner}}|{{ok}}|{{tags}}", &value).unwrap(),
+            "b||true|[\"a\",\"b\"]"
+        );
+        assert_eq!(
+            render("-> {{this}}", &json!(["x", "y"])).unwrap(),
+            "-> x\n-> y"
+        );
+    }
+
+    #[test]
+    fn test_invalid_template_or_missing_field() {
+        let value = json!({ "name": "users" });
+        assert!(is_invalid_argument(render("{{name", &value)));
+        assert!(is_invalid_argument(render("{{ }}", &value)));
+        assert!(is_invalid_argument(render("{{engine}}", &value)));
+    }
+
+    #[test]
+    fn test_take_flag_forms() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let value = json!({ "name": "users", "id": 7 });
+        let rendered = |template: Option<Template>| template.unwrap().render(&value).unwrap();
+
+        let (template, rest) = take_flag(args(&["list", "--template", "{{name}}"])).unwrap();
+        assert_eq!(rendered(template), "users");
+        assert_eq!(rest, ["list"]);
+
+        let (template, rest) = take_flag(args(&["--template={{id}}", "list"])).unwrap();
+        assert_eq!(rendered(template), "7");
+        assert_eq!(rest, ["list"]);
+
+        assert!(take_flag(args(&["list", "--template"])).is_err());
+        assert!(take_flag(args(&["list", "--template", "{{name"])).is_err());
+
+        // Arguments after `--` belong to the handler
+        let (template, rest) = take_flag(args(&["echo", "--", "--template", "{{x"])).unwrap();
+        assert!(template.is_none());
+        assert_eq!(rest, ["echo", "--", "--template", "{{x"]);
+    }
+
+    #[tokio::test]
+    async fn test_router_renders_structured_output() {
+        use crate::{CliResult, Router, State};
+
+        async fn list(_state: State<()>) -> CliResult<Response> {
+            let rows = json!([
+                { "name": "users", "engine": "postgres" },
+                { "name": "orders", "engine": "mysql" },
+            ]);
+            Ok(Response::text("2 databases").with_data(rows))
+        }
+        async fn status(_state: State<()>) -> CliResult<String> {
+            Ok("up".to_string())
+        }
+
+        let router = Router::new()
+            .route("db:list", list)
+            .route("status", status)
+            .with_template_flag()
+            .with_state(());
+        let run = |args: &[&str]| {
+            let args: Vec<String> = std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect();
+            let router = &router;
+            async move { router.execute_with(&args).await }
+        };
+        let text = |response: Response| match response.output {
+            Output::Text(text) => text,
+            other => panic!("Expected text output, got {:?}", other),
+        };
+
+        let response = run(&["db", "list", "--template", "{{name}} ({{engine}})"]).await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(text(response), "users (postgres)\norders (mysql)");
+
+        // Without the flag the handler's text is kept; text-only output ignores it
+        assert_eq!(text(run(&["db", "list"]).await), "2 databases");
+        assert_eq!(text(run(&["status", "--template={{name}}"]).await), "up");
+
+        let response = run(&["db", "list", "--template", "{{size}}"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert!(text(response).contains("field 'size' not found"));
+
+        // A malformed template is rejected before any handler runs
+        let response = run(&["status", "--template={{name"]).await;
+        assert_eq!(response.exit_code, 1);
+        assert!(text(response).contains("unclosed"));
+    }
+}
@@ -0,0 +1,196 @@
+//! Helpers for testing a CLI end to end (requires the `testing` feature).
+//!
+//! `TestCli` runs commands against a router the way the binary would, minus
+//! the program name and process exit, and `TestResult` gives typed access to
+//! the response:
+//!
+//! ```ignore
+//! use sen::testing::TestCli;
+//!
+//! #[tokio::test]
+//! async fn creates_database() {
+//!     let cli = TestCli::new(build_router());
+//!
+//!     let result = cli.run(&["db", "create", "users"]).await;
+//!     assert_eq!(result.exit_code(), 0);
+//!     assert_eq!(result.text(), "Created users");
+//!
+//!     let result = cli.run(&["db", "list", "--agent-mode"]).await;
+//!     assert_eq!(result.agent_json()["result"], "success");
+//! }
+//! ```
+
+use crate::{Output, Response, Router};
+
+/// Program name passed as `args[0]`.
+const PROGRAM: &str = "test";
+
+/// A router under test.
+pub struct TestCli {
+    router: Router<()>,
+}
+
+impl TestCli {
+    /// Wrap a router (after `with_state`).
+    pub fn new(router: Router<()>) -> Self {
+        Self { router }
+    }
+
+    /// Run a command, e.g. `run(&["db", "create", "users"])`.
+    pub async fn run(&self, args: &[&str]) -> TestResult {
+        let args: Vec<String> = std::iter::once(PROGRAM)
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        TestResult {
+            response: self.router.execute_with(&args).await,
+            #[cfg(feature = "sensors")]
+            agent_output_limit: self.router.config.agent_output_limit,
+        }
+    }
+
+    /// The wrapped router.
+    pub fn router(&self) -> &Router<()> {
+        &self.router
+    }
+}
+
+/// The response of one `TestCli::run`.
+///
+/// Accessors panic with the output in the message when it isn't what they
+/// expect, so a failing test shows what the command returned.
+pub struct TestResult {
+    response: Response,
+    /// The router's `agent_output_limit`, applied by `agent_json`
+    #[cfg(feature = "sensors")]
+    agent_output_limit: Option<usize>,
+}
+
+impl TestResult {
+    /// Exit code (0 = success, 1 = user error, 101 = system error).
+    pub fn exit_code(&self) -> i32 {
+        self.response.exit_code
+    }
+
+    /// Text output; empty for silent responses.
+    ///
+    /// Error responses carry their message here too.
+    pub fn text(&self) -> &str {
+        match &self.response.output {
+            Output::Text(text) | Output::Json(text) => text,
+            Output::Silent => "",
+            other => panic!("Expected text output, got {:?}", other),
+        }
+    }
+
+    /// Output parsed as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(self.text())
+            .unwrap_or_else(|e| panic!("Output is not valid JSON ({}): {}", e, self.text()))
+    }
+
+    /// The agent-mode JSON envelope (`result`, `exit_code`, `output`, ...).
+    ///
+    /// Available whether or not the command ran with `--agent-mode`;
+    /// `output` is cut to the router's `agent_output_limit`.
+    #[cfg(feature = "sensors")]
+    pub fn agent_json(&self) -> serde_json::Value {
+        serde_json::from_str(
+            &self
+                .response
+                .to_agent_json_with_limit(self.agent_output_limit),
+        )
+        .expect("to_agent_json always produces valid JSON")
+    }
+
+    /// The underlying response.
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Take the underlying response.
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Args, CliError, CliResult, FromArgs, State};
+
+    struct Name(String);
+
+    impl FromArgs for Name {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            args.first()
+                .cloned()
+                .map(Name)
+                .ok_or_else(|| CliError::user("Missing database name"))
+        }
+    }
+
+    async fn create(_state: State<()>, Args(name): Args<Name>) -> CliResult<String> {
+        Ok(format!("Created {}", name.0))
+    }
+
+    async fn list(_state: State<()>) -> CliResult<String> {
+        Ok(r#"["users","orders"]"#.to_string())
+    }
+
+    fn cli() -> TestCli {
+        TestCli::new(
+            Router::new()
+                .route("db:create", create)
+                .route("db:list", list)
+                .with_agent_mode()
+                .with_state(()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_exit_code_and_text() {
+        let cli = cli();
+
+        let result = cli.run(&["db", "create", "users"]).await;
+        assert_eq!(result.exit_code(), 0);
+        assert_eq!(result.text(), "Created users");
+
+        let result = cli.run(&["db", "create"]).await;
+        assert_eq!(result.exit_code(), 1);
+        assert!(result.text().contains("Missing database name"));
+    }
+
+    #[tokio::test]
+    async fn test_json_parses_output() {
+        let result = cli().run(&["db", "list"]).await;
+        assert_eq!(result.json(), serde_json::json!(["users", "orders"]));
+    }
+
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_agent_json_wraps_response() {
+        let result = cli().run(&["db", "create", "users", "--agent-mode"]).await;
+        let agent = result.agent_json();
+        assert_eq!(agent["result"], "success");
+        assert_eq!(agent["exit_code"], 0);
+        assert_eq!(agent["output"], "Created users");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command_fails() {
+        let result = cli().run(&["db", "drop"]).await;
+        assert_eq!(result.exit_code(), 1);
+        assert!(result.text().contains("db drop"));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "not valid JSON")]
+    async fn test_json_panics_on_text_output() {
+        cli().run(&["db", "create", "users"]).await.json();
+    }
+}
@@ -0,0 +1,172 @@
+//! Parsing sizes and durations given as arguments.
+//!
+//! Flags like `--size 10GB` or `--ttl 30m` take a number with a unit. Parse
+//! them with these helpers instead of by hand:
+//!
+//! - [`parse_size`]: bytes, with decimal (`KB` = 1000) or binary
+//!   (`KiB` = 1024) units
+//! - [`parse_duration`]: `s`, `m`, `h`, `d`, combinable as in `1h30m`
+//!
+//! Both return `UserError::InvalidArgument` for input they can't parse, so
+//! `?` in a handler reports it like any other argument error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use sen::{parse_duration, parse_size, CliResult};
+//!
+//! async fn create(state: State<AppState>, Args(args): Args<CreateArgs>) -> CliResult<String> {
+//!     let size = parse_size(&args.size)?;
+//!     let ttl = parse_duration(&args.ttl)?;
+//!     // ...
+//! }
+//! ```
+
+use crate::{CliError, CliResult, UserError};
+use std::time::Duration;
+
+const SIZE_HINT: &str = "Expected a size like 512, 10KB, 1.5GB or 4GiB";
+const DURATION_HINT: &str = "Expected a duration like 30s, 15m, 2h, 1d or 1h30m";
+
+/// Parse a size in bytes, e.g. `"10GB"` or `"512KiB"`.
+///
+/// Units are case-insensitive. `K`/`KB`, `M`/`MB`, `G`/`GB` and `T`/`TB` are
+/// decimal (powers of 1000); `KiB`, `MiB`, `GiB` and `TiB` are binary (powers
+/// of 1024). A bare number or `B` means bytes. Fractions are rounded to the
+/// nearest byte (`1.5KB` = 1500).
+pub fn parse_size(input: &str) -> CliResult<u64> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "t" | "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(invalid(input, SIZE_HINT)),
+    };
+
+    if !number.contains('.') {
+        return number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .ok_or_else(|| invalid(input, SIZE_HINT));
+    }
+
+    let bytes = number
+        .parse::<f64>()
+        .map(|n| (n * multiplier as f64).round())
+        .map_err(|_| invalid(input, SIZE_HINT))?;
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        return Err(invalid(input, SIZE_HINT));
+    }
+    Ok(bytes as u64)
+}
+
+/// Parse a duration, e.g. `"30m"` or `"1h30m"`.
+///
+/// Each part is a whole number followed by `s`, `m`, `h` or `d`. A number
+/// without a unit is rejected, since `30` could mean seconds or minutes.
+pub fn parse_duration(input: &str) -> CliResult<Duration> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(invalid(input, DURATION_HINT));
+    }
+
+    let mut total: u64 = 0;
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| invalid(input, DURATION_HINT))?;
+        let (number, tail) = rest.split_at(digits);
+        let mut chars = tail.chars();
+        let seconds_per_unit = match chars.next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => return Err(invalid(input, DURATION_HINT)),
+        };
+
+        total = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(seconds_per_unit))
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(|| invalid(input, DURATION_HINT))?;
+        rest = chars.as_str();
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+fn invalid(input: &str, hint: &str) -> CliError {
+    CliError::User(UserError::InvalidArgument {
+        arg: input.to_string(),
+        reason: hint.to_string(),
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid_argument(result: CliResult<impl std::fmt::Debug>) -> bool {
+        matches!(
+            result,
+            Err(CliError::User(UserError::InvalidArgument { .. }))
+        )
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("512B").unwrap(), 512);
+        assert_eq!(parse_size("10KB").unwrap(), 10_000);
+        assert_eq!(parse_size("10kb").unwrap(), 10_000);
+        assert_eq!(parse_size("10 MB").unwrap(), 10_000_000);
+        assert_eq!(parse_size("10GB").unwrap(), 10_000_000_000);
+        assert_eq!(parse_size("2G").unwrap(), 2_000_000_000);
+        assert_eq!(parse_size("1KiB").unwrap(), 1024);
+        assert_eq!(parse_size("4GiB").unwrap(), 4 << 30);
+        assert_eq!(parse_size("1.5KB").unwrap(), 1500);
+        assert_eq!(parse_size("0.5MiB").unwrap(), 512 * 1024);
+    }
+
+    #[test]
+    fn test_parse_size_rejects_bad_input() {
+        for input in ["", "GB", "10XB", "-1KB", "1.2.3MB", "1e3", "99999999999TB"] {
+            assert!(is_invalid_argument(parse_size(input)), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86_400));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_bad_input() {
+        for input in ["", "30", "m", "1.5h", "10w", "1h30", "-5s"] {
+            assert!(is_invalid_argument(parse_duration(input)), "{:?}", input);
+        }
+    }
+}
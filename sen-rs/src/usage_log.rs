@@ -0,0 +1,197 @@
+//! Local record of which commands run (opt-in).
+//!
+//! `Router::with_usage_log(path)` appends one JSON line per routed command:
+//!
+//! ```text
+//! {"timestamp_ms":1718000000000,"command":"db create","exit_code":0,"duration_ms":12,"agent_mode":false}
+//! ```
+//!
+//! - Arguments are left out unless `Router::with_usage_log_args()` is also
+//!   set, since they may contain secrets
+//! - Help, version, and unknown commands are not recorded
+//! - Writing never affects the command: errors (missing directory, read-only
+//!   file) are ignored
+
+use crate::{CliError, Response, Router};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// One routed command, as recorded in the usage log.
+pub(crate) struct Invocation<'a> {
+    pub(crate) route_key: &'a str,
+    /// `None` unless argument logging is enabled
+    pub(crate) args: Option<&'a [String]>,
+    pub(crate) exit_code: i32,
+    pub(crate) duration: Duration,
+    pub(crate) agent_mode: bool,
+}
+
+/// One line of the usage log.
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp_ms: u128,
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<&'a [String]>,
+    exit_code: i32,
+    duration_ms: u128,
+    agent_mode: bool,
+}
+
+impl Router<()> {
+    /// Record a routed command in the usage log, if one is kept.
+    ///
+    /// `args` are only written with `Router::with_usage_log_args()`.
+    pub(crate) fn log_usage(
+        &self,
+        route_key: &str,
+        args: &[String],
+        started: Instant,
+        agent_mode: bool,
+        result: &Result<Response, CliError>,
+    ) {
+        let Some(path) = &self.config.usage_log else {
+            return;
+        };
+        let invocation = Invocation {
+            route_key,
+            args: self.config.usage_log_args.then_some(args),
+            exit_code: match result {
+                Ok(response) => response.exit_code,
+                Err(e) => e.exit_code(),
+            },
+            duration: started.elapsed(),
+            agent_mode,
+        };
+        append(path, &invocation);
+    }
+}
+
+/// Append a line for `invocation` to the log at `path`, ignoring failures.
+pub(crate) fn append(path: &Path, invocation: &Invocation) {
+    let record = Record {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+        command: invocation.route_key.replace(':', " "),
+        args: invocation.args,
+        exit_code: invocation.exit_code,
+        duration_ms: invocation.duration.as_millis(),
+        agent_mode: invocation.agent_mode,
+    };
+    let _ = write_line(path, &record);
+}
+
+fn write_line(path: &Path, record: &Record) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{Args, CliError, CliResult, FromArgs, Router, State};
+
+    struct Token(String);
+
+    impl FromArgs for Token {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            args.first()
+                .cloned()
+                .map(Token)
+                .ok_or_else(|| CliError::user("Missing token"))
+        }
+    }
+
+    async fn login(_state: State<()>, Args(token): Args<Token>) -> CliResult<String> {
+        Ok(format!("Logged in with {} chars", token.0.len()))
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("test")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn lines(path: &std::path::Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_appends_one_record_without_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.jsonl");
+        let router = Router::new()
+            .route("auth:login", login)
+            .with_usage_log(&path)
+            .with_state(());
+
+        router
+            .execute_with(&args(&["auth", "login", "s3cret"]))
+            .await;
+        let records = lines(&path);
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record["command"], "auth login");
+        assert_eq!(record["exit_code"], 0);
+        assert_eq!(record["agent_mode"], false);
+        assert!(record["timestamp_ms"].as_u64().unwrap() > 0);
+        assert!(record["duration_ms"].is_u64());
+        assert!(record.get("args").is_none());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("s3cret"));
+
+        // Failures are recorded too; help is not
+        router.execute_with(&args(&["auth", "login"])).await;
+        router.execute_with(&args(&["--help"])).await;
+        let records = lines(&path);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["exit_code"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_args_recorded_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.jsonl");
+        let router = Router::new()
+            .route("auth:login", login)
+            .with_agent_mode()
+            .with_usage_log(&path)
+            .with_usage_log_args()
+            .with_state(());
+
+        router
+            .execute_with(&args(&["auth", "login", "tok", "--agent-mode"]))
+            .await;
+        let records = lines(&path);
+        assert_eq!(records[0]["args"], serde_json::json!(["tok"]));
+        assert_eq!(records[0]["agent_mode"], true);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_log_does_not_fail_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = Router::new()
+            .route("auth:login", login)
+            .with_usage_log(dir.path().join("missing/usage.jsonl"))
+            .with_state(());
+
+        let response = router.execute_with(&args(&["auth", "login", "tok"])).await;
+        assert_eq!(response.exit_code, 0);
+    }
+}
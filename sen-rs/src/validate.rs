@@ -0,0 +1,167 @@
+//! Argument validation that runs before the handler body.
+//!
+//! Implement `Validate` for an args type and take it as
+//! `Args<Validated<T>>`. The `Args` extractor then parses the arguments,
+//! calls `T::validate`, and reports a failure like any other argument error:
+//! the handler body never runs.
+//!
+//! The wrapper is how a handler opts in; stable Rust can't check whether
+//! `T: Validate` inside the existing `Args<T>` handler impls.
+
+use crate::{CliError, FromArgs};
+use std::ops::{Deref, DerefMut};
+
+/// Checks that parsed arguments make sense together.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, CliError, CliResult, State, Validate, Validated};
+///
+/// impl Validate for DeployArgs {
+///     fn validate(&self) -> Result<(), CliError> {
+///         if self.replicas == 0 {
+///             return Err(CliError::user("--replicas must be at least 1"));
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// async fn deploy(
+///     state: State<AppState>,
+///     Args(Validated(args)): Args<Validated<DeployArgs>>,
+/// ) -> CliResult<String> {
+///     Ok(format!("Deploying {} replicas", args.replicas))
+/// }
+/// ```
+pub trait Validate {
+    /// Return an error (usually `CliError::user`) if the arguments are invalid.
+    fn validate(&self) -> Result<(), CliError>;
+}
+
+/// Arguments of type `T` that passed `T::validate`.
+///
+/// Parses like `T` and has the same CLI schema, so help, `--help-json`, and
+/// MCP tool schemas are unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    /// Unwrap the validated arguments.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Validated<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FromArgs for Validated<T>
+where
+    T: FromArgs + Validate,
+{
+    fn from_args(args: &[String]) -> Result<Self, CliError> {
+        let parsed = T::from_args(args)?;
+        parsed.validate()?;
+        Ok(Validated(parsed))
+    }
+
+    fn from_args_named(name: &str, args: &[String]) -> Result<Self, CliError> {
+        let parsed = T::from_args_named(name, args)?;
+        parsed.validate()?;
+        Ok(Validated(parsed))
+    }
+
+    fn from_args_prefix(args: &[String]) -> Result<(Self, usize), CliError> {
+        let (parsed, consumed) = T::from_args_prefix(args)?;
+        parsed.validate()?;
+        Ok((Validated(parsed), consumed))
+    }
+
+    fn cli_schema() -> Option<serde_json::Value> {
+        T::cli_schema()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Args, CliResult, Output, Router, State};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Replicas(u32);
+
+    impl FromArgs for Replicas {
+        fn from_args(args: &[String]) -> Result<Self, CliError> {
+            let count = args.first().map(String::as_str).unwrap_or("1");
+            count
+                .parse()
+                .map(Replicas)
+                .map_err(|_| CliError::user(format!("Not a number: {}", count)))
+        }
+    }
+
+    impl Validate for Replicas {
+        fn validate(&self) -> Result<(), CliError> {
+            if self.0 == 0 {
+                return Err(CliError::user("replicas must be at least 1"));
+            }
+            Ok(())
+        }
+    }
+
+    async fn scale(
+        state: State<Arc<AtomicUsize>>,
+        Args(Validated(replicas)): Args<Validated<Replicas>>,
+    ) -> CliResult<String> {
+        state.read().await.fetch_add(1, Ordering::SeqCst);
+        Ok(format!("Scaled to {}", replicas.0))
+    }
+
+    fn args(count: &str) -> Vec<String> {
+        vec!["test".to_string(), "scale".to_string(), count.to_string()]
+    }
+
+    #[tokio::test]
+    async fn test_validation_failure_skips_handler_body() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("scale", scale)
+            .with_state(calls.clone());
+
+        let response = router.execute_with(&args("0")).await;
+        assert_eq!(response.exit_code, 1);
+        match response.output {
+            Output::Text(text) => assert!(text.contains("replicas must be at least 1")),
+            other => panic!("Expected text output, got {:?}", other),
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let response = router.execute_with(&args("3")).await;
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_errors_come_before_validation() {
+        let err = Validated::<Replicas>::from_args(&["many".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Not a number"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Warnings from commands that still succeed.
+//!
+//! A command may finish but want to flag something ("using default region").
+//! Warnings are kept apart from the output and the exit code:
+//!
+//! - Interactive: printed to stderr as `Warning: ...` by `Response::exit()`
+//! - Agent mode: a `"warnings"` array in the JSON
+//!
+//! Attach them with `Response::warn`, or take a `Warnings` extractor and
+//! call `warn` anywhere in the handler; the router adds them to the response.
+
+use std::sync::{Arc, Mutex};
+
+// ============================================================================
+// Warnings Extractor (for use in handlers)
+// ============================================================================
+
+tokio::task_local! {
+    static WARNINGS: Warnings;
+}
+
+/// Warnings extractor for handler functions.
+///
+/// # Example
+///
+/// ```ignore
+/// use sen::{Args, CliResult, State, Warnings};
+///
+/// async fn deploy(
+///     state: State<AppState>,
+///     warnings: Warnings,
+///     Args(args): Args<DeployArgs>,
+/// ) -> CliResult<String> {
+///     let region = args.region.unwrap_or_else(|| {
+///         warnings.warn("No --region given, using us-east-1");
+///         "us-east-1".to_string()
+///     });
+///     Ok(format!("Deployed to {}", region))
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Warnings {
+    collected: Arc<Mutex<Vec<String>>>,
+}
+
+impl Warnings {
+    /// Get the warnings collector of the currently executing command.
+    ///
+    /// Outside of router execution this returns a collector nobody reads.
+    pub fn current() -> Self {
+        WARNINGS.try_with(Warnings::clone).unwrap_or_default()
+    }
+
+    /// Add a warning to the command's response.
+    pub fn warn(&self, message: impl Into<String>) {
+        self.collected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message.into());
+    }
+
+    /// Remove and return the warnings collected so far.
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.collected.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl crate::FromContext for Warnings {
+    fn from_context() -> Result<Self, crate::CliError> {
+        Ok(Self::current())
+    }
+}
+
+/// Run `fut` with a fresh collector, appending what it collected to the
+/// warnings of a successful response.
+pub(crate) async fn scope<F>(fut: F) -> Result<crate::Response, crate::CliError>
+where
+    F: std::future::Future<Output = Result<crate::Response, crate::CliError>>,
+{
+    let warnings = Warnings::default();
+    let result = WARNINGS.scope(warnings.clone(), fut).await;
+    result.map(|mut response| {
+        response.warnings.extend(warnings.take());
+        response
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_outside_router_are_discarded() {
+        Warnings::current().warn("nobody listens");
+        assert!(Warnings::current().take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scope_appends_to_response() {
+        let response = scope(async {
+            Warnings::current().warn("second");
+            Ok(crate::Response::text("done").warn("first"))
+        })
+        .await;
+
+        let Ok(response) = response else {
+            panic!("Expected a response");
+        };
+        assert_eq!(response.warnings(), ["first", "second"]);
+    }
+}
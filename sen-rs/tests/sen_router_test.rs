@@ -0,0 +1,210 @@
+//! Tests for the router generated by `#[derive(SenRouter)]`
+
+use sen::{Args, CliError, CliResult, FromArgs, Output, Response, SenRouter, State};
+
+#[derive(Clone)]
+struct AppState {
+    greeting: String,
+}
+
+mod handlers {
+    use super::*;
+
+    pub async fn status(state: State<AppState>) -> CliResult<String> {
+        Ok(format!("{} from status", state.read().await.greeting))
+    }
+
+    pub async fn db_create(_state: State<AppState>, Names(names): Names) -> CliResult<String> {
+        Ok(format!("created {}", names.join(",")))
+    }
+
+    pub async fn db_list(_state: State<AppState>) -> CliResult<String> {
+        Ok("users,orders".to_string())
+    }
+
+    pub async fn deploy(state: State<AppState>, Args(args): Args<DeployArgs>) -> CliResult<String> {
+        Ok(format!(
+            "{}, deploying to {}",
+            state.read().await.greeting,
+            args.target
+        ))
+    }
+}
+
+/// Positional names, parsed the same way with and without the `clap` feature
+struct Names(Vec<String>);
+
+impl FromArgs for Names {
+    fn from_args(args: &[String]) -> Result<Self, CliError> {
+        Ok(Names(args.to_vec()))
+    }
+}
+
+struct DeployArgs {
+    target: String,
+}
+
+impl FromArgs for DeployArgs {
+    fn from_args(args: &[String]) -> Result<Self, CliError> {
+        let target = args
+            .first()
+            .cloned()
+            .ok_or_else(|| CliError::user("deploy needs a target"))?;
+        Ok(DeployArgs { target })
+    }
+}
+
+#[derive(SenRouter)]
+#[sen(state = AppState, name = "myctl", version = "1.2.3", about = "Test CLI", agent_mode)]
+enum Commands {
+    #[sen(handler = handlers::status, desc = "Show status")]
+    Status,
+
+    #[sen(handler = handlers::db_create)]
+    DbCreate(Names),
+
+    #[sen(handler = handlers::db_list, rename = "db:list", desc = "List tables")]
+    DbList,
+
+    #[sen(handler = handlers::deploy, args = DeployArgs)]
+    Deploy,
+}
+
+fn state() -> State<AppState> {
+    State::new(AppState {
+        greeting: "hello".to_string(),
+    })
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    std::iter::once("myctl")
+        .chain(args.iter().copied())
+        .map(String::from)
+        .collect()
+}
+
+fn text(response: Response) -> String {
+    match response.output {
+        Output::Text(s) => s,
+        other => panic!("Expected text output, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_router_dispatches_variants_by_name() {
+    let router = Commands::router(state());
+
+    let response = router.execute_with(&args(&["status"])).await;
+    assert_eq!(text(response), "hello from status");
+
+    let response = router
+        .execute_with(&args(&["db-create", "users", "orders"]))
+        .await;
+    assert_eq!(text(response), "created users,orders");
+}
+
+#[test]
+fn test_command_names_default_to_kebab_case() {
+    let names: Vec<_> = Commands::commands_metadata()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, vec!["status", "db-create", "db:list", "deploy"]);
+}
+
+#[tokio::test]
+async fn test_renamed_variant_routes_under_explicit_name() {
+    let router = Commands::router(state());
+
+    let response = router.execute_with(&args(&["db", "list"])).await;
+    assert_eq!(text(response), "users,orders");
+
+    // The kebab-case default is not registered for a renamed variant
+    let response = router.execute_with(&args(&["db-list"])).await;
+    assert_ne!(response.exit_code, 0);
+}
+
+#[tokio::test]
+async fn test_router_has_metadata_and_agent_mode() {
+    let router = Commands::router(state());
+
+    let help = text(router.execute_with(&args(&["--help"])).await);
+    assert!(help.contains("myctl"), "{}", help);
+    assert!(help.contains("Test CLI"), "{}", help);
+    assert!(help.contains("Show status"), "{}", help);
+
+    let response = router
+        .execute_with(&args(&["--agent-mode", "status"]))
+        .await;
+    assert!(response.agent_mode);
+}
+
+#[tokio::test]
+async fn test_execute_still_dispatches_enum() {
+    let response = Commands::Status.execute(state()).await;
+    assert_eq!(text(response), "hello from status");
+
+    let response = Commands::DbCreate(Names(vec!["users".to_string()]))
+        .execute(state())
+        .await;
+    assert_eq!(text(response), "created users");
+
+    let response = Commands::DbList.execute(state()).await;
+    assert_eq!(text(response), "users,orders");
+}
+
+#[tokio::test]
+async fn test_unit_variant_parses_declared_args() {
+    let response = Commands::Deploy
+        .execute_with_args(state(), vec!["prod".to_string()])
+        .await;
+    assert_eq!(text(response), "hello, deploying to prod");
+
+    let response = Commands::Deploy.execute(state()).await;
+    assert_eq!(response.exit_code, 1);
+
+    let response = Commands::router(state())
+        .execute_with(&args(&["deploy", "staging"]))
+        .await;
+    assert_eq!(text(response), "hello, deploying to staging");
+}
+
+async fn help_json(router: &sen::Router<()>) -> serde_json::Value {
+    let schema = text(router.execute_with(&args(&["--help", "--json"])).await);
+    serde_json::from_str(&schema).unwrap()
+}
+
+#[tokio::test]
+async fn test_enum_routes_match_hand_built_router_schema() {
+    async fn db_create(state: State<AppState>, Args(names): Args<Names>) -> CliResult<String> {
+        handlers::db_create(state, names).await
+    }
+
+    let metadata = || sen::RouterMetadata {
+        name: "myctl",
+        version: Some("1.2.3"),
+        about: Some("Test CLI"),
+    };
+    let hand_built = sen::Router::new()
+        .route("status", handlers::status)
+        .describe("status", "Show status")
+        .route("db-create", db_create)
+        .route("db:list", handlers::db_list)
+        .describe("db:list", "List tables")
+        .route("deploy", handlers::deploy)
+        .with_metadata(metadata())
+        .with_shared_state(state());
+    let from_enum = sen::Router::from_enum_router::<Commands>()
+        .with_metadata(metadata())
+        .with_shared_state(state());
+
+    let expected = help_json(&hand_built).await;
+    assert_eq!(expected["commands"].as_object().unwrap().len(), 4);
+    assert_eq!(help_json(&from_enum).await, expected);
+    assert_eq!(help_json(&Commands::router(state())).await, expected);
+
+    assert_eq!(
+        <Commands as sen::SenRouterTrait>::commands_metadata(),
+        Commands::commands_metadata()
+    );
+}